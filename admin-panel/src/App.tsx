@@ -39,6 +39,8 @@ export function App() {
     addresses: [],
   });
   const [addressesInput, setAddressesInput] = useState("");
+  const [heightRangeInput, setHeightRangeInput] = useState({ from: "", to: "" });
+  const [descriptorInput, setDescriptorInput] = useState({ descriptor: "", gapLimit: "" });
 
   const [nodes, setNodes] = useState<NodeSummary[]>([]);
   const [nodesError, setNodesError] = useState<string | null>(null);
@@ -184,6 +186,15 @@ export function App() {
                 .map((value) => value.trim())
                 .filter(Boolean)
             : [],
+        ...(jobDraft.mode === "height_range"
+          ? { from_height: Number(heightRangeInput.from), to_height: Number(heightRangeInput.to) }
+          : {}),
+        ...(jobDraft.mode === "descriptor"
+          ? {
+              descriptor: descriptorInput.descriptor.trim(),
+              ...(descriptorInput.gapLimit ? { gap_limit: Number(descriptorInput.gapLimit) } : {}),
+            }
+          : {}),
       };
 
       const details = await createJob(payload);
@@ -195,6 +206,8 @@ export function App() {
         addresses: [],
       });
       setAddressesInput("");
+      setHeightRangeInput({ from: "", to: "" });
+      setDescriptorInput({ descriptor: "", gapLimit: "" });
       await refreshJobs();
     } catch (error) {
       setJobsError(asErrorMessage(error));
@@ -274,9 +287,62 @@ export function App() {
                   >
                     <option value="address_list">address_list</option>
                     <option value="all_addresses">all_addresses</option>
+                    <option value="height_range">height_range</option>
+                    <option value="descriptor">descriptor</option>
                   </select>
                 </label>
               </div>
+              {jobDraft.mode === "height_range" ? (
+                <div className="form-grid">
+                  <label className="field">
+                    <span>From height</span>
+                    <input
+                      min={0}
+                      onChange={(event) => setHeightRangeInput((current) => ({ ...current, from: event.target.value }))}
+                      placeholder="700000"
+                      required
+                      type="number"
+                      value={heightRangeInput.from}
+                    />
+                  </label>
+                  <label className="field">
+                    <span>To height</span>
+                    <input
+                      min={0}
+                      onChange={(event) => setHeightRangeInput((current) => ({ ...current, to: event.target.value }))}
+                      placeholder="750000"
+                      required
+                      type="number"
+                      value={heightRangeInput.to}
+                    />
+                  </label>
+                </div>
+              ) : null}
+              {jobDraft.mode === "descriptor" ? (
+                <div className="form-grid">
+                  <label className="field">
+                    <span>Descriptor or xpub</span>
+                    <input
+                      onChange={(event) => setDescriptorInput((current) => ({ ...current, descriptor: event.target.value }))}
+                      placeholder="wpkh([fingerprint/84h/0h/0h]xpub.../<0;1>/*)"
+                      required
+                      type="text"
+                      value={descriptorInput.descriptor}
+                    />
+                  </label>
+                  <label className="field">
+                    <span>Gap limit</span>
+                    <input
+                      max={1000}
+                      min={1}
+                      onChange={(event) => setDescriptorInput((current) => ({ ...current, gapLimit: event.target.value }))}
+                      placeholder="20"
+                      type="number"
+                      value={descriptorInput.gapLimit}
+                    />
+                  </label>
+                </div>
+              ) : null}
               <label className="field checkbox-field">
                 <input
                   checked={jobDraft.enabled}
@@ -288,7 +354,7 @@ export function App() {
               <label className="field">
                 <span>Addresses</span>
                 <textarea
-                  disabled={jobDraft.mode === "all_addresses"}
+                  disabled={jobDraft.mode !== "address_list"}
                   onChange={(event) => setAddressesInput(event.target.value)}
                   placeholder="addr1&#10;addr2"
                   rows={4}
@@ -296,7 +362,7 @@ export function App() {
                 />
               </label>
               <div className="form-hint">
-                Для `address_list` укажи адреса через новую строку или запятую. Для `all_addresses` список должен быть пустым.
+                Для `address_list` укажи адреса через новую строку или запятую. Для `all_addresses`, `height_range` и `descriptor` список должен быть пустым.
               </div>
               <button className="action-button" disabled={createJobPending} type="submit">
                 {createJobPending ? "Creating..." : "Create Job"}
@@ -345,6 +411,9 @@ export function App() {
                   <MetricCard label="Mode" value={selectedJob.mode} />
                   <MetricCard label="Status" value={selectedJob.status} />
                   <MetricCard label="Progress" value={String(selectedJob.progress_height)} />
+                  <MetricCard label="Remaining" value={selectedJob.remaining_blocks === null ? "n/a" : String(selectedJob.remaining_blocks)} />
+                  <MetricCard label="Rate" value={selectedJob.blocks_per_sec === null ? "n/a" : `${selectedJob.blocks_per_sec.toFixed(2)} blk/s`} />
+                  <MetricCard label="ETA" value={selectedJob.eta_seconds === null ? "n/a" : `${selectedJob.eta_seconds}s`} />
                   <MetricCard label="Updated" value={formatDate(selectedJob.updated_at)} />
                 </div>
                 <div className="action-row">
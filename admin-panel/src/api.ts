@@ -6,6 +6,11 @@ export interface JobSummary {
   status: JobStatus;
   progress_height: number;
   tip_height: number | null;
+  blocks_per_sec: number | null;
+  remaining_blocks: number | null;
+  eta_seconds: number | null;
+  from_height: number | null;
+  to_height: number | null;
   updated_at: string | null;
   last_error: string | null;
 }
@@ -16,9 +21,18 @@ export interface JobDetails extends JobSummary {
 
 export interface CreateJobPayload {
   job_id: string;
-  mode: "all_addresses" | "address_list";
+  mode: "all_addresses" | "address_list" | "height_range" | "descriptor";
   enabled: boolean;
   addresses: string[];
+  from_height?: number;
+  to_height?: number;
+  descriptor?: string;
+  gap_limit?: number;
+  retry?: {
+    max_attempts: number;
+    backoff_ms: number;
+    max_backoff_ms?: number;
+  };
 }
 
 export interface NodeSummary {
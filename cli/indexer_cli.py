@@ -1,9 +1,12 @@
 #!/usr/bin/env python3
 import argparse
 import base64
+import hashlib
+import hmac
 import json
 import os
 import sys
+import time
 import urllib.error
 import urllib.parse
 import urllib.request
@@ -23,6 +26,8 @@ class CliConfig:
     base_url: str
     username: str
     password: str
+    hmac_key_id: Optional[str] = None
+    hmac_secret: Optional[str] = None
 
 
 class ApiClient:
@@ -32,14 +37,23 @@ class ApiClient:
     def get(self, path: str, query: Optional[Dict[str, Any]] = None) -> Any:
         return self._request("GET", path, query=query)
 
-    def post(self, path: str) -> Any:
-        return self._request("POST", path)
+    def get_bytes(self, path: str, query: Optional[Dict[str, Any]] = None, timeout: int = 30) -> bytes:
+        return self._request("GET", path, query=query, raw=True, timeout=timeout)
+
+    def post(self, path: str, body: Optional[Dict[str, Any]] = None, query: Optional[Dict[str, Any]] = None) -> Any:
+        return self._request("POST", path, body=body, query=query)
+
+    def delete(self, path: str) -> Any:
+        return self._request("DELETE", path)
 
     def _request(
         self,
         method: str,
         path: str,
         query: Optional[Dict[str, Any]] = None,
+        body: Optional[Dict[str, Any]] = None,
+        raw: bool = False,
+        timeout: int = 30,
     ) -> Any:
         base_url = self._config.base_url.rstrip("/")
         url = f"{base_url}{path}"
@@ -48,27 +62,43 @@ class ApiClient:
             if pairs:
                 url = f"{url}?{urllib.parse.urlencode(pairs)}"
 
-        credentials = f"{self._config.username}:{self._config.password}".encode("utf-8")
-        auth_header = base64.b64encode(credentials).decode("ascii")
+        data = json.dumps(body).encode("utf-8") if body is not None else None
+        headers = {"Content-Type": "application/json"}
+
+        if self._config.hmac_key_id and self._config.hmac_secret:
+            request_path = url[len(base_url):]
+            timestamp = str(int(time.time()))
+            payload = f"{timestamp}\n{method}\n{request_path}\n".encode("utf-8") + (data or b"")
+            headers["X-Signature-Key-Id"] = self._config.hmac_key_id
+            headers["X-Signature-Timestamp"] = timestamp
+            headers["X-Signature"] = hmac.new(
+                self._config.hmac_secret.encode("utf-8"),
+                payload,
+                hashlib.sha256,
+            ).hexdigest()
+        else:
+            credentials = f"{self._config.username}:{self._config.password}".encode("utf-8")
+            headers["Authorization"] = f"Basic {base64.b64encode(credentials).decode('ascii')}"
 
         request = urllib.request.Request(
             url,
+            data=data,
             method=method,
-            headers={
-                "Authorization": f"Basic {auth_header}",
-                "Content-Type": "application/json",
-            },
+            headers=headers,
         )
 
         try:
-            with urllib.request.urlopen(request, timeout=30) as response:
-                payload = response.read().decode("utf-8")
+            with urllib.request.urlopen(request, timeout=timeout) as response:
+                content = response.read()
         except urllib.error.HTTPError as exc:
             body = exc.read().decode("utf-8", errors="replace")
             raise CliError(format_http_error(exc.code, body)) from exc
         except urllib.error.URLError as exc:
             raise CliError(f"request failed: {exc.reason}") from exc
 
+        if raw:
+            return content
+        payload = content.decode("utf-8")
         if not payload:
             return None
 
@@ -112,9 +142,21 @@ def build_parser() -> argparse.ArgumentParser:
         default=os.getenv("INDEXER_API_PASSWORD", ""),
         help="Basic Auth password. Default: env INDEXER_API_PASSWORD",
     )
+    parser.add_argument(
+        "--hmac-key-id",
+        default=os.getenv("INDEXER_API_HMAC_KEY_ID", ""),
+        help="HMAC signing key id; signs requests instead of Basic Auth. Default: env INDEXER_API_HMAC_KEY_ID",
+    )
+    parser.add_argument(
+        "--hmac-secret",
+        default=os.getenv("INDEXER_API_HMAC_SECRET", ""),
+        help="HMAC signing secret. Default: env INDEXER_API_HMAC_SECRET",
+    )
 
     subparsers = parser.add_subparsers(dest="resource", required=True)
 
+    subparsers.add_parser("status", help="Show node tip, indexed height and sync lag")
+
     jobs_parser = subparsers.add_parser("jobs", help="Manage indexing jobs")
     jobs_subparsers = jobs_parser.add_subparsers(dest="action", required=True)
     jobs_subparsers.add_parser("list", help="List jobs")
@@ -122,9 +164,54 @@ def build_parser() -> argparse.ArgumentParser:
     jobs_get = jobs_subparsers.add_parser("get", help="Get job details")
     jobs_get.add_argument("job_id", help="Job identifier")
 
+    jobs_eta = jobs_subparsers.add_parser("eta", help="Estimate remaining sync time")
+    jobs_eta.add_argument("job_id", help="Job identifier")
+
+    jobs_estimate = jobs_subparsers.add_parser("estimate", help="Dry-run estimate of blocks, storage and duration")
+    jobs_estimate.add_argument("job_id", help="Job identifier")
+
+    jobs_dry_run = jobs_subparsers.add_parser("dry-run", help="Fetch and parse the next blocks without writing")
+    jobs_dry_run.add_argument("job_id", help="Job identifier")
+    jobs_dry_run.add_argument("--blocks", type=int, default=None)
+
+    jobs_descriptors = jobs_subparsers.add_parser("descriptors", help="Output descriptors of the job's watched addresses")
+    jobs_descriptors.add_argument("job_id", help="Job identifier")
+
+    jobs_errors = jobs_subparsers.add_parser("errors", help="Show job error history")
+    jobs_errors.add_argument("job_id", help="Job identifier")
+    jobs_errors.add_argument("--limit", type=int, default=None)
+
+    jobs_discrepancies = jobs_subparsers.add_parser("discrepancies", help="Show discrepancies found by a verify job")
+    jobs_discrepancies.add_argument("job_id", help="Job identifier")
+    jobs_discrepancies.add_argument("--kind", default=None)
+    jobs_discrepancies.add_argument("--limit", type=int, default=None)
+
+    jobs_delete = jobs_subparsers.add_parser("delete", help="Delete a stopped job")
+    jobs_delete.add_argument("job_id", help="Job identifier")
+
+    jobs_add_addresses = jobs_subparsers.add_parser(
+        "add-addresses",
+        help="Add addresses to an address_list job",
+    )
+    jobs_add_addresses.add_argument("job_id", help="Job identifier")
+    jobs_add_addresses.add_argument("addresses", nargs="+", help="Addresses to watch")
+    jobs_add_addresses.add_argument(
+        "--backfill",
+        action="store_true",
+        help="Rebuild balances and UTXOs for new addresses from indexed history",
+    )
+
+    jobs_remove_address = jobs_subparsers.add_parser(
+        "remove-address",
+        help="Remove an address from an address_list job",
+    )
+    jobs_remove_address.add_argument("job_id", help="Job identifier")
+    jobs_remove_address.add_argument("address", help="Address to remove")
+
     for action in ("start", "stop", "pause", "resume", "retry"):
         job_action = jobs_subparsers.add_parser(action, help=f"{action.title()} a job")
         job_action.add_argument("job_id", help="Job identifier")
+        job_action.add_argument("--reason", help="Reason recorded in the job history")
 
     nodes_parser = subparsers.add_parser("nodes", help="Inspect node health")
     nodes_subparsers = nodes_parser.add_subparsers(dest="action", required=True)
@@ -132,6 +219,7 @@ def build_parser() -> argparse.ArgumentParser:
 
     nodes_get = nodes_subparsers.add_parser("health", help="Get detailed node health")
     nodes_get.add_argument("node_id", help="Node identifier")
+    nodes_subparsers.add_parser("info", help="Show chain, network and mempool state of the RPC node")
 
     data_parser = subparsers.add_parser("data", help="Query indexed blockchain data")
     data_subparsers = data_parser.add_subparsers(dest="action", required=True)
@@ -184,10 +272,69 @@ def build_parser() -> argparse.ArgumentParser:
     blocks_parser.add_argument("--offset", type=int, default=None)
     blocks_parser.add_argument("--limit", type=int, default=None)
 
+    scripts_parser = data_subparsers.add_parser("scripts", help="Search outputs by scriptPubKey pattern")
+    scripts_parser.add_argument("pattern", help="Hex prefix; ?? matches any byte, trailing $ requires full match")
+    scripts_parser.add_argument("--type", dest="script_type", default=None)
+    scripts_parser.add_argument("--offset", type=int, default=None)
+    scripts_parser.add_argument("--limit", type=int, default=None)
+
+    fees_parser = data_subparsers.add_parser("fees", help="Estimate fee rate from recent blocks and mempool")
+    fees_parser.add_argument("--target-blocks", type=int, default=None)
+
+    broadcast_parser = data_subparsers.add_parser("broadcast", help="Relay a raw transaction through the node")
+    broadcast_parser.add_argument("hex", help="Serialized transaction in hex")
+
+    scan_parser = data_subparsers.add_parser("scan", help="Find UTXOs of descriptors with the node's scantxoutset")
+    scan_parser.add_argument("descriptors", nargs="+", help="Output descriptors, e.g. addr(bc1...) or wpkh(xpub.../0/*)")
+    scan_parser.add_argument("--range", type=int, default=None, help="Keys derived per ranged descriptor")
+
+    events_parser = subparsers.add_parser("events", help="Read transaction confirmation events")
+    events_subparsers = events_parser.add_subparsers(dest="action", required=True)
+    events_list = events_subparsers.add_parser("list", help="List events in ascending id order")
+    events_list.add_argument("--levels", default=None, help="Comma-separated: seen,confirmed,finalized")
+    events_list.add_argument("--address", default=None)
+    events_list.add_argument("--txid", default=None)
+    events_list.add_argument("--after-id", type=int, default=None)
+    events_list.add_argument("--limit", type=int, default=None)
+
+    admin_parser = subparsers.add_parser("admin", help="Inspect indexer instances")
+    admin_subparsers = admin_parser.add_subparsers(dest="action", required=True)
+    uptime_parser = admin_subparsers.add_parser("uptime", help="Show uptime and restart history")
+    uptime_parser.add_argument("--limit", type=int, default=None)
+    admin_subparsers.add_parser("reload", help="Re-read config YAML and apply runtime changes")
+    admin_subparsers.add_parser(
+        "credentials-reload", help="Re-read rotated passwords and certificates and rebuild auth and RPC clients"
+    )
+    admin_subparsers.add_parser("replication", help="Show CDC publication and replication slot health")
+    provenance_parser = admin_subparsers.add_parser(
+        "provenance", help="Show height ranges by the pipeline version and stages that indexed them"
+    )
+    provenance_parser.add_argument(
+        "--missing-stage", default=None, help="Only ranges indexed without: chain, address_index, fees"
+    )
+    admin_subparsers.add_parser("runtime", help="Show tokio runtime task counts, queues and worker busy time")
+    profile_parser = admin_subparsers.add_parser(
+        "profile", help="Capture a CPU profile in pprof format (backend built with --features profiling)"
+    )
+    profile_parser.add_argument("--seconds", type=int, default=None)
+    profile_parser.add_argument("--frequency", type=int, default=None)
+    profile_parser.add_argument("--output", default="cpu.pb")
+
     return parser
 
 
 def build_config(args: argparse.Namespace) -> CliConfig:
+    if args.hmac_key_id or args.hmac_secret:
+        if not (args.hmac_key_id and args.hmac_secret):
+            raise CliError("HMAC signing requires both --hmac-key-id and --hmac-secret")
+        return CliConfig(
+            base_url=args.base_url,
+            username=args.username,
+            password=args.password,
+            hmac_key_id=args.hmac_key_id,
+            hmac_secret=args.hmac_secret,
+        )
+
     if not args.username:
         raise CliError("missing Basic Auth username: pass --username or set INDEXER_API_USERNAME")
     if not args.password:
@@ -205,8 +352,34 @@ def handle_jobs(client: ApiClient, args: argparse.Namespace) -> Any:
         return client.get("/v1/jobs")
     if args.action == "get":
         return client.get(f"/v1/jobs/{args.job_id}")
+    if args.action == "eta":
+        return client.get(f"/v1/jobs/{args.job_id}/eta")
+    if args.action == "estimate":
+        return client.post(f"/v1/jobs/{args.job_id}/estimate")
+    if args.action == "dry-run":
+        return client.post(f"/v1/jobs/{args.job_id}/dry-run", query={"blocks": args.blocks})
+    if args.action == "descriptors":
+        return client.get(f"/v1/jobs/{args.job_id}/descriptors")
+    if args.action == "errors":
+        return client.get(f"/v1/jobs/{args.job_id}/errors", query={"limit": args.limit})
+    if args.action == "discrepancies":
+        return client.get(
+            f"/v1/jobs/{args.job_id}/discrepancies",
+            query={"kind": args.kind, "limit": args.limit},
+        )
+    if args.action == "delete":
+        return client.delete(f"/v1/jobs/{args.job_id}")
+    if args.action == "add-addresses":
+        return client.post(
+            f"/v1/jobs/{args.job_id}/addresses",
+            body={"addresses": args.addresses, "backfill": args.backfill},
+        )
+    if args.action == "remove-address":
+        address = urllib.parse.quote(args.address, safe="")
+        return client.delete(f"/v1/jobs/{args.job_id}/addresses/{address}")
     if args.action in {"start", "stop", "pause", "resume", "retry"}:
-        return client.post(f"/v1/jobs/{args.job_id}/{args.action}")
+        body = {"reason": args.reason} if args.reason else None
+        return client.post(f"/v1/jobs/{args.job_id}/{args.action}", body)
     raise CliError(f"unsupported jobs action: {args.action}")
 
 
@@ -215,6 +388,8 @@ def handle_nodes(client: ApiClient, args: argparse.Namespace) -> Any:
         return client.get("/v1/nodes")
     if args.action == "health":
         return client.get(f"/v1/nodes/{args.node_id}/health")
+    if args.action == "info":
+        return client.get("/v1/node/info")
     raise CliError(f"unsupported nodes action: {args.action}")
 
 
@@ -281,9 +456,69 @@ def handle_data(client: ApiClient, args: argparse.Namespace) -> Any:
                 "limit": args.limit,
             },
         )
+    if args.action == "scripts":
+        return client.get(
+            "/v1/scripts/search",
+            query={
+                "pattern": args.pattern,
+                "type": args.script_type,
+                "offset": args.offset,
+                "limit": args.limit,
+            },
+        )
+    if args.action == "fees":
+        return client.get("/v1/fees/estimate", query={"target_blocks": args.target_blocks})
+    if args.action == "broadcast":
+        return client.post("/v1/txs", body={"hex": args.hex})
+    if args.action == "scan":
+        body: Dict[str, Any] = {"descriptors": args.descriptors}
+        if args.range is not None:
+            body["range"] = args.range
+        return client.post("/v1/scan", body=body)
     raise CliError(f"unsupported data action: {args.action}")
 
 
+def handle_events(client: ApiClient, args: argparse.Namespace) -> Any:
+    if args.action == "list":
+        return client.get(
+            "/v1/events",
+            query={
+                "levels": args.levels,
+                "address": args.address,
+                "txid": args.txid,
+                "after_id": args.after_id,
+                "limit": args.limit,
+            },
+        )
+    raise CliError(f"unsupported events action: {args.action}")
+
+
+def handle_admin(client: ApiClient, args: argparse.Namespace) -> Any:
+    if args.action == "uptime":
+        return client.get("/v1/admin/uptime", query={"limit": args.limit})
+    if args.action == "reload":
+        return client.post("/v1/admin/reload")
+    if args.action == "credentials-reload":
+        return client.post("/v1/admin/credentials/reload")
+    if args.action == "replication":
+        return client.get("/v1/admin/replication")
+    if args.action == "provenance":
+        return client.get("/v1/admin/provenance", query={"missing_stage": args.missing_stage})
+    if args.action == "runtime":
+        return client.get("/v1/admin/runtime")
+    if args.action == "profile":
+        seconds = args.seconds if args.seconds is not None else 10
+        profile = client.get_bytes(
+            "/v1/admin/profile/cpu",
+            query={"seconds": args.seconds, "frequency": args.frequency},
+            timeout=seconds + 30,
+        )
+        with open(args.output, "wb") as output:
+            output.write(profile)
+        return {"output": args.output, "bytes": len(profile)}
+    raise CliError(f"unsupported admin action: {args.action}")
+
+
 def main(argv: list[str]) -> int:
     parser = build_parser()
     args = parser.parse_args(argv)
@@ -292,12 +527,18 @@ def main(argv: list[str]) -> int:
         config = build_config(args)
         client = ApiClient(config)
 
-        if args.resource == "jobs":
+        if args.resource == "status":
+            payload = client.get("/v1/status")
+        elif args.resource == "jobs":
             payload = handle_jobs(client, args)
         elif args.resource == "nodes":
             payload = handle_nodes(client, args)
         elif args.resource == "data":
             payload = handle_data(client, args)
+        elif args.resource == "events":
+            payload = handle_events(client, args)
+        elif args.resource == "admin":
+            payload = handle_admin(client, args)
         else:
             raise CliError(f"unsupported resource: {args.resource}")
     except CliError as exc:
@@ -0,0 +1,11 @@
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS from_height INT NULL;
+
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS to_height INT NULL;
+
+ALTER TABLE jobs
+    DROP CONSTRAINT IF EXISTS jobs_mode_check;
+
+ALTER TABLE jobs
+    ADD CONSTRAINT jobs_mode_check CHECK (mode IN ('all_addresses', 'address_list', 'height_range'));
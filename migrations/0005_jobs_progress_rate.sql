@@ -0,0 +1,5 @@
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS tip_height INT NULL;
+
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS blocks_per_sec DOUBLE PRECISION NULL;
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS process_events (
+    id BIGSERIAL PRIMARY KEY,
+    instance_id TEXT NOT NULL,
+    event TEXT NOT NULL CHECK (event IN ('started', 'stopped', 'crashed')),
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_seen_at TIMESTAMPTZ NULL,
+    details JSONB NOT NULL DEFAULT '{}'::jsonb
+);
+
+CREATE INDEX IF NOT EXISTS idx_process_events_occurred_at ON process_events(occurred_at DESC);
+
+CREATE UNIQUE INDEX IF NOT EXISTS uq_process_events_terminal
+    ON process_events(instance_id)
+    WHERE event IN ('stopped', 'crashed');
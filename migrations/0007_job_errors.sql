@@ -0,0 +1,15 @@
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS retry_count INT NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS job_errors (
+    id BIGSERIAL PRIMARY KEY,
+    job_id TEXT NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    category TEXT NOT NULL CHECK (category IN ('rpc', 'storage', 'parse')),
+    block_height INT NULL,
+    retry_count INT NOT NULL DEFAULT 0,
+    message TEXT NOT NULL,
+    CONSTRAINT fk_job_errors_job_id FOREIGN KEY (job_id) REFERENCES jobs(job_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_errors_job_id_occurred_at ON job_errors(job_id, occurred_at DESC);
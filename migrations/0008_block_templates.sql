@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS block_templates (
+    id BIGSERIAL PRIMARY KEY,
+    captured_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    height INT NOT NULL,
+    previous_block_hash TEXT NOT NULL,
+    bits TEXT NOT NULL,
+    curtime BIGINT NOT NULL,
+    coinbase_value_sats BIGINT NOT NULL,
+    total_fees_sats BIGINT NOT NULL,
+    tx_count INT NOT NULL,
+    total_weight BIGINT NOT NULL,
+    transactions JSONB NOT NULL DEFAULT '[]'::jsonb
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_templates_height_captured_at ON block_templates(height, captured_at DESC);
@@ -0,0 +1 @@
+CREATE INDEX IF NOT EXISTS idx_tx_outputs_script_hex_pattern ON tx_outputs(script_hex text_pattern_ops);
@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS tx_events (
+    id BIGSERIAL PRIMARY KEY,
+    txid TEXT NOT NULL,
+    address TEXT NOT NULL,
+    level TEXT NOT NULL CHECK (level IN ('seen', 'confirmed', 'finalized')),
+    block_height INT NULL,
+    block_hash TEXT NULL,
+    confirmations INT NOT NULL DEFAULT 0,
+    occurred_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One event per level and block: a tx confirmed again in another block after a reorg emits a new event.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_tx_events_unique
+    ON tx_events(txid, address, level, COALESCE(block_hash, ''));
+CREATE INDEX IF NOT EXISTS idx_tx_events_level_id ON tx_events(level, id);
+CREATE INDEX IF NOT EXISTS idx_tx_events_address_id ON tx_events(address, id);
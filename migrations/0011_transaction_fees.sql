@@ -0,0 +1,7 @@
+-- NULL for coinbase transactions and for spends whose prevouts are unknown to the indexer.
+ALTER TABLE transactions
+    ADD COLUMN IF NOT EXISTS fee_sats BIGINT NULL;
+
+-- Sum of fee_sats over the block; NULL while any non-coinbase transaction fee is unknown.
+ALTER TABLE blocks
+    ADD COLUMN IF NOT EXISTS total_fees_sats BIGINT NULL;
@@ -0,0 +1,16 @@
+ALTER TABLE jobs
+    DROP CONSTRAINT IF EXISTS jobs_mode_check;
+
+ALTER TABLE jobs
+    ADD CONSTRAINT jobs_mode_check CHECK (mode IN ('all_addresses', 'address_list', 'height_range', 'descriptor'));
+
+-- Derivation window of descriptor jobs; every address is also watched through job_addresses.
+CREATE TABLE IF NOT EXISTS job_derived_addresses (
+    job_id TEXT NOT NULL,
+    branch INT NOT NULL,
+    derivation_index INT NOT NULL,
+    address TEXT NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (job_id, branch, derivation_index),
+    CONSTRAINT fk_job_derived_addresses_job_id FOREIGN KEY (job_id) REFERENCES jobs(job_id) ON DELETE CASCADE
+);
@@ -0,0 +1,14 @@
+-- Events of persisted chain data awaiting delivery to the configured sink (Kafka/NATS).
+-- Rows are written in the same transaction as the data they describe and marked
+-- published once the broker acknowledged them, which gives at-least-once delivery.
+CREATE TABLE IF NOT EXISTS event_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    stream TEXT NOT NULL CHECK (stream IN ('blocks', 'transactions', 'address_activity')),
+    event_key TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    published_at TIMESTAMPTZ NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_event_outbox_pending ON event_outbox(id) WHERE published_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_event_outbox_published_at ON event_outbox(published_at) WHERE published_at IS NOT NULL;
@@ -0,0 +1,21 @@
+ALTER TABLE jobs
+    DROP CONSTRAINT IF EXISTS jobs_mode_check;
+
+ALTER TABLE jobs
+    ADD CONSTRAINT jobs_mode_check
+    CHECK (mode IN ('all_addresses', 'address_list', 'height_range', 'descriptor', 'export'));
+
+-- Files written by export jobs, one row per table and height chunk; the highest exported
+-- height is where an interrupted job resumes.
+CREATE TABLE IF NOT EXISTS job_exports (
+    job_id TEXT NOT NULL,
+    table_name TEXT NOT NULL,
+    from_height INT NOT NULL,
+    to_height INT NOT NULL,
+    location TEXT NOT NULL,
+    row_count BIGINT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    exported_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, table_name, from_height),
+    CONSTRAINT fk_job_exports_job_id FOREIGN KEY (job_id) REFERENCES jobs(job_id) ON DELETE CASCADE
+);
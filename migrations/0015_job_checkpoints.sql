@@ -0,0 +1,5 @@
+-- Last height a job committed, written in the same transaction as the block; a job
+-- resumes after it once the node still has `checkpoint_hash` at that height.
+ALTER TABLE jobs
+    ADD COLUMN IF NOT EXISTS checkpoint_height INT NULL,
+    ADD COLUMN IF NOT EXISTS checkpoint_hash TEXT NULL;
@@ -0,0 +1,9 @@
+-- Headers of the node's active chain, synced ahead of block bodies. Rows above a fork are
+-- replaced when the node reorgs, so the table holds a single chain.
+CREATE TABLE IF NOT EXISTS block_headers (
+    height INT PRIMARY KEY,
+    hash TEXT NOT NULL UNIQUE,
+    prev_hash TEXT NULL,
+    time BIGINT NOT NULL,
+    synced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
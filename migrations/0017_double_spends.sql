@@ -0,0 +1,25 @@
+-- Conflicting spends of one outpoint by two known (mempool or confirmed) transactions,
+-- recorded when the later of the two is stored; one row per outpoint and pair.
+CREATE TABLE IF NOT EXISTS double_spends (
+    id BIGSERIAL PRIMARY KEY,
+    prev_txid TEXT NOT NULL,
+    prev_vout INT NOT NULL,
+    txid TEXT NOT NULL,
+    txid_status TEXT NOT NULL CHECK (txid_status IN ('mempool', 'confirmed')),
+    conflicting_txid TEXT NOT NULL,
+    conflicting_status TEXT NOT NULL CHECK (conflicting_status IN ('mempool', 'confirmed')),
+    block_height INT NULL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_double_spends_pair
+    ON double_spends(prev_txid, prev_vout, LEAST(txid, conflicting_txid), GREATEST(txid, conflicting_txid));
+CREATE INDEX IF NOT EXISTS idx_double_spends_txid ON double_spends(txid);
+CREATE INDEX IF NOT EXISTS idx_double_spends_conflicting_txid ON double_spends(conflicting_txid);
+
+ALTER TABLE event_outbox
+    DROP CONSTRAINT IF EXISTS event_outbox_stream_check;
+
+ALTER TABLE event_outbox
+    ADD CONSTRAINT event_outbox_stream_check
+    CHECK (stream IN ('blocks', 'transactions', 'address_activity', 'double_spends'));
@@ -0,0 +1,12 @@
+-- BIP-125 replacements seen in the mempool: a mempool transaction evicted by a
+-- conflicting one that pays a higher fee. Each transaction is replaced at most once,
+-- so following `replacement_txid` from a txid walks the fee bumps of one payment.
+CREATE TABLE IF NOT EXISTS tx_replacements (
+    replaced_txid TEXT PRIMARY KEY,
+    replacement_txid TEXT NOT NULL,
+    replaced_fee_sats BIGINT NULL,
+    replacement_fee_sats BIGINT NULL,
+    replaced_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_tx_replacements_replacement_txid ON tx_replacements(replacement_txid);
@@ -0,0 +1,5 @@
+-- Serves GET /v1/addresses/top from `address_balance_current`, which the indexer keeps
+-- up to date per block, without sorting every address on each request.
+CREATE INDEX IF NOT EXISTS idx_address_balance_current_top
+    ON address_balance_current(balance_sats DESC, address)
+    WHERE balance_sats > 0;
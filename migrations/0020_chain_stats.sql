@@ -0,0 +1,34 @@
+-- Rollups maintained by the stats aggregator, so `GET /v1/stats` does not group raw
+-- transactions per request. `block_stats` has one row per canonical block; days whose
+-- blocks changed (`rolled_up = FALSE`) are recomputed into `daily_stats`.
+CREATE TABLE IF NOT EXISTS block_stats (
+    height INT PRIMARY KEY,
+    hash TEXT NOT NULL,
+    day DATE NOT NULL,
+    time BIGINT NOT NULL,
+    tx_count INT NOT NULL,
+    output_sats BIGINT NOT NULL,
+    fees_sats BIGINT NULL,
+    -- Fees and vsize of the transactions whose fee and size are both known, for fee rates.
+    rated_fees_sats BIGINT NOT NULL,
+    rated_vsize BIGINT NOT NULL,
+    active_addresses INT NOT NULL,
+    rolled_up BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_stats_day ON block_stats(day);
+CREATE INDEX IF NOT EXISTS idx_block_stats_pending ON block_stats(day) WHERE NOT rolled_up;
+
+CREATE TABLE IF NOT EXISTS daily_stats (
+    day DATE PRIMARY KEY,
+    blocks INT NOT NULL,
+    first_height INT NOT NULL,
+    last_height INT NOT NULL,
+    tx_count BIGINT NOT NULL,
+    output_sats BIGINT NOT NULL,
+    fees_sats BIGINT NULL,
+    rated_fees_sats BIGINT NOT NULL,
+    rated_vsize BIGINT NOT NULL,
+    active_addresses BIGINT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
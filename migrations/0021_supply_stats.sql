@@ -0,0 +1,20 @@
+-- Written with each canonical block: value created and spent by it, coin-days destroyed
+-- and the unspent value after it.
+CREATE TABLE IF NOT EXISTS block_supply (
+    height INT PRIMARY KEY,
+    hash TEXT NOT NULL,
+    time BIGINT NOT NULL,
+    created_sats BIGINT NOT NULL,
+    spent_sats BIGINT NOT NULL,
+    -- Inputs whose spent output or its transaction is not stored; left out of the sums.
+    unknown_inputs INT NOT NULL,
+    coin_days_destroyed DOUBLE PRECISION NOT NULL,
+    supply_sats BIGINT NOT NULL
+);
+
+-- Unspent outputs by the UTC day of the block that created them.
+CREATE TABLE IF NOT EXISTS utxo_age_days (
+    day DATE PRIMARY KEY,
+    value_sats BIGINT NOT NULL,
+    outputs BIGINT NOT NULL
+);
@@ -0,0 +1,128 @@
+-- Chain tables range-partitioned by block height. Only an empty database is converted:
+-- rewriting populated tables does not fit in a migration, they keep the plain layout.
+-- A row in `chain_partitioning` marks the partitioned layout.
+CREATE TABLE IF NOT EXISTS chain_partitioning (
+    singleton BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+    blocks_per_partition INT NOT NULL CHECK (blocks_per_partition > 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Creates the partitions of `blocks`, `transactions` and `tx_outputs` for the range holding
+-- `at_height` and the one after it, so the indexer never writes past the last partition.
+-- Returns the number of partitions created; a no-op for the plain layout.
+CREATE OR REPLACE FUNCTION ensure_chain_partitions(at_height INT) RETURNS INT
+LANGUAGE plpgsql AS $$
+DECLARE
+    partition_size INT;
+    from_height INT;
+    chain_table TEXT;
+    partition_name TEXT;
+    created INT := 0;
+BEGIN
+    SELECT blocks_per_partition INTO partition_size FROM chain_partitioning;
+    IF partition_size IS NULL OR at_height < 0 THEN
+        RETURN 0;
+    END IF;
+
+    from_height := at_height - at_height % partition_size;
+    FOR step IN 0..1 LOOP
+        FOREACH chain_table IN ARRAY ARRAY['blocks', 'transactions', 'tx_outputs'] LOOP
+            partition_name := format('%s_p%s', chain_table, from_height);
+            IF to_regclass(partition_name) IS NULL THEN
+                EXECUTE format(
+                    'CREATE TABLE %I PARTITION OF %I FOR VALUES FROM (%s) TO (%s)',
+                    partition_name, chain_table, from_height, from_height + partition_size
+                );
+                -- Without a unique key of NOT NULL columns updates can only be replicated this way.
+                IF chain_table <> 'blocks' THEN
+                    EXECUTE format('ALTER TABLE %I REPLICA IDENTITY FULL', partition_name);
+                END IF;
+                created := created + 1;
+            END IF;
+        END LOOP;
+        from_height := from_height + partition_size;
+    END LOOP;
+
+    RETURN created;
+END
+$$;
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM chain_partitioning)
+        OR EXISTS (SELECT 1 FROM blocks)
+        OR EXISTS (SELECT 1 FROM transactions)
+        OR EXISTS (SELECT 1 FROM tx_outputs)
+        OR EXISTS (SELECT 1 FROM tx_inputs) THEN
+        RETURN;
+    END IF;
+
+    -- `txid` is only unique within a partition, so nothing can reference it.
+    ALTER TABLE tx_inputs DROP CONSTRAINT IF EXISTS fk_tx_inputs_txid;
+    DROP TABLE tx_outputs;
+    DROP TABLE transactions;
+    DROP TABLE blocks;
+
+    CREATE TABLE blocks (
+        id BIGSERIAL NOT NULL,
+        height INT NOT NULL,
+        hash TEXT NOT NULL,
+        prev_hash TEXT NOT NULL,
+        time BIGINT NOT NULL,
+        status TEXT NOT NULL CHECK (status IN ('canonical', 'orphaned')),
+        meta JSONB NOT NULL DEFAULT '{}'::jsonb,
+        total_fees_sats BIGINT NULL,
+        PRIMARY KEY (height, id),
+        UNIQUE (height, hash)
+    ) PARTITION BY RANGE (height);
+
+    CREATE INDEX idx_blocks_hash ON blocks(hash);
+    CREATE INDEX idx_blocks_height ON blocks(height);
+    CREATE INDEX idx_blocks_status_height ON blocks(status, height);
+
+    -- Unconfirmed (mempool, dropped) transactions have no height and live in the default partition.
+    CREATE TABLE transactions (
+        id BIGSERIAL NOT NULL,
+        txid TEXT NOT NULL,
+        block_height INT NULL,
+        block_hash TEXT NULL,
+        time BIGINT NOT NULL,
+        status TEXT NOT NULL CHECK (status IN ('confirmed', 'mempool', 'dropped', 'orphaned')),
+        decoded JSONB NOT NULL,
+        position_in_block INT NOT NULL DEFAULT 0,
+        fee_sats BIGINT NULL
+    ) PARTITION BY RANGE (block_height);
+
+    CREATE TABLE transactions_unconfirmed PARTITION OF transactions
+        (CONSTRAINT transactions_unconfirmed_height CHECK (block_height IS NULL)) DEFAULT;
+    ALTER TABLE transactions_unconfirmed REPLICA IDENTITY FULL;
+
+    CREATE INDEX idx_transactions_txid ON transactions(txid);
+    CREATE INDEX idx_transactions_status ON transactions(status);
+    CREATE INDEX idx_transactions_block_height ON transactions(block_height);
+    CREATE INDEX idx_transactions_time ON transactions(time);
+    CREATE INDEX idx_transactions_block_height_position ON transactions(block_height, position_in_block);
+
+    -- `block_height` follows the transaction: NULL while it is unconfirmed.
+    CREATE TABLE tx_outputs (
+        txid TEXT NOT NULL,
+        vout INT NOT NULL,
+        value_sats BIGINT NOT NULL,
+        script_type TEXT NOT NULL,
+        address TEXT NULL,
+        script_hex TEXT NOT NULL,
+        block_height INT NULL
+    ) PARTITION BY RANGE (block_height);
+
+    CREATE TABLE tx_outputs_unconfirmed PARTITION OF tx_outputs
+        (CONSTRAINT tx_outputs_unconfirmed_height CHECK (block_height IS NULL)) DEFAULT;
+    ALTER TABLE tx_outputs_unconfirmed REPLICA IDENTITY FULL;
+
+    CREATE INDEX idx_tx_outputs_txid_vout ON tx_outputs(txid, vout);
+    CREATE INDEX idx_tx_outputs_address ON tx_outputs(address);
+    CREATE INDEX idx_tx_outputs_script_hex_pattern ON tx_outputs(script_hex text_pattern_ops);
+
+    INSERT INTO chain_partitioning (blocks_per_partition) VALUES (100000);
+    PERFORM ensure_chain_partitions(0);
+END
+$$;
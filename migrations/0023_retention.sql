@@ -0,0 +1,7 @@
+-- Progress of the retention pruner: transactions of canonical blocks up to `pruned_height`
+-- have lost the `vin`/`vout` arrays of `decoded` and the `script_hex` of their outputs.
+CREATE TABLE IF NOT EXISTS pruning_state (
+    singleton BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+    pruned_height INT NOT NULL,
+    pruned_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
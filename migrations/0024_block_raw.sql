@@ -0,0 +1,14 @@
+-- Serialized blocks kept with `indexer.store_raw_blocks`: `raw` is Snappy-compressed
+-- (raw format), `size` the length of the uncompressed block. Orphaned blocks keep theirs.
+CREATE TABLE IF NOT EXISTS block_raw (
+    hash TEXT PRIMARY KEY,
+    height INT NOT NULL,
+    size INT NOT NULL,
+    raw BYTEA NOT NULL,
+    stored_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_raw_height ON block_raw(height);
+
+-- Already compressed: store out of line without another pglz pass.
+ALTER TABLE block_raw ALTER COLUMN raw SET STORAGE EXTERNAL;
@@ -0,0 +1,14 @@
+-- BIP158 basic filters kept with `indexer.block_filters`. `header` is the BIP157 filter
+-- header, NULL when the parent block has no stored filter header; `complete` is false when
+-- a spent script was unknown (below the start height of a job, pruned by retention) and
+-- the filter misses it. Orphaned blocks keep theirs.
+CREATE TABLE IF NOT EXISTS block_filters (
+    hash TEXT PRIMARY KEY,
+    height INT NOT NULL,
+    filter BYTEA NOT NULL,
+    header TEXT NULL,
+    complete BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_filters_height ON block_filters(height);
@@ -0,0 +1,11 @@
+-- Job status changes, indexed blocks and reorgs for the SSE stream `GET /v1/events/stream`.
+-- Rows are written in the transaction of the change they describe and kept for a day
+-- (at most the latest 100k), long enough for a client to resume with `Last-Event-ID`.
+CREATE TABLE IF NOT EXISTS stream_events (
+    id BIGSERIAL PRIMARY KEY,
+    kind TEXT NOT NULL CHECK (kind IN ('job_status', 'block_connected', 'reorg')),
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_stream_events_created_at ON stream_events(created_at);
@@ -0,0 +1,13 @@
+-- Raw transactions submitted through `POST /v1/txs`, one row per relay attempt.
+CREATE TABLE IF NOT EXISTS broadcasts (
+    id BIGSERIAL PRIMARY KEY,
+    txid TEXT NOT NULL,
+    size INT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('accepted', 'rejected', 'failed')),
+    -- Reject reason of the node, or the transport error for `failed`.
+    error TEXT NULL,
+    principal TEXT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_broadcasts_txid ON broadcasts(txid, created_at DESC);
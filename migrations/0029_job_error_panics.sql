@@ -0,0 +1,4 @@
+-- Panics of a job batch are recorded as their own category and retried like transient errors.
+ALTER TABLE job_errors DROP CONSTRAINT IF EXISTS job_errors_category_check;
+ALTER TABLE job_errors
+    ADD CONSTRAINT job_errors_category_check CHECK (category IN ('rpc', 'storage', 'parse', 'panic'));
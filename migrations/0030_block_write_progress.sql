@@ -0,0 +1,17 @@
+-- Blocks with more than `indexer.batching.txs_per_batch` transactions are committed in
+-- chunks. A row marks a block whose first chunks are committed; the chunk with its last
+-- transactions writes the canonical `blocks` row and deletes the marker in the same
+-- transaction, so a block is complete exactly when it is canonical.
+CREATE TABLE IF NOT EXISTS block_write_progress (
+    hash TEXT PRIMARY KEY,
+    height INT NOT NULL,
+    tx_count INT NOT NULL,
+    -- Position of the first transaction not committed yet.
+    next_tx INT NOT NULL,
+    -- Fees of the committed transactions; NULL once the fee of one of them is unknown.
+    total_fees_sats BIGINT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_block_write_progress_height ON block_write_progress(height);
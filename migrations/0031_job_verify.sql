@@ -0,0 +1,23 @@
+ALTER TABLE jobs
+    DROP CONSTRAINT IF EXISTS jobs_mode_check;
+
+ALTER TABLE jobs
+    ADD CONSTRAINT jobs_mode_check
+    CHECK (mode IN ('all_addresses', 'address_list', 'height_range', 'descriptor', 'export', 'verify'));
+
+-- Discrepancies found by verify jobs. Verifying a height chunk again replaces its rows;
+-- the balance check has no height.
+CREATE TABLE IF NOT EXISTS job_discrepancies (
+    id BIGSERIAL PRIMARY KEY,
+    job_id TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (
+        kind IN ('missing_block', 'hash_mismatch', 'tx_count_mismatch', 'missing_prevout', 'balance_mismatch')
+    ),
+    height INT NULL,
+    block_hash TEXT NULL,
+    detail TEXT NOT NULL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_job_discrepancies_job_id FOREIGN KEY (job_id) REFERENCES jobs(job_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_job_discrepancies_job_id_height ON job_discrepancies(job_id, height);
@@ -0,0 +1,8 @@
+-- Heights found missing below the progress of a job, queued until the job indexes them.
+CREATE TABLE IF NOT EXISTS job_gaps (
+    job_id TEXT NOT NULL,
+    height INT NOT NULL,
+    detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (job_id, height),
+    CONSTRAINT fk_job_gaps_job_id FOREIGN KEY (job_id) REFERENCES jobs(job_id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- Spent output of an input, denormalized by the indexer; NULL when it could not be resolved.
+ALTER TABLE tx_inputs ADD COLUMN IF NOT EXISTS prev_value_sats BIGINT;
+ALTER TABLE tx_inputs ADD COLUMN IF NOT EXISTS prev_script_type TEXT;
+ALTER TABLE tx_inputs ADD COLUMN IF NOT EXISTS prev_address TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_tx_inputs_prev_address ON tx_inputs(prev_address) WHERE prev_address IS NOT NULL;
@@ -0,0 +1,7 @@
+-- Size and header fields of transactions, and the witness stacks of inputs with storage.store_witness.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS vsize INT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS weight INT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS version BIGINT;
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS locktime BIGINT;
+
+ALTER TABLE tx_inputs ADD COLUMN IF NOT EXISTS witness TEXT[];
@@ -0,0 +1,55 @@
+-- Runes etched on the indexed chain, keyed by "block:tx" of the etching.
+CREATE TABLE IF NOT EXISTS runes (
+    rune_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    spaced_name TEXT NOT NULL,
+    block_height INT NOT NULL,
+    etching_txid TEXT NOT NULL,
+    divisibility SMALLINT NOT NULL DEFAULT 0,
+    symbol TEXT,
+    premine NUMERIC(39, 0) NOT NULL DEFAULT 0,
+    terms BOOLEAN NOT NULL DEFAULT FALSE,
+    mint_amount NUMERIC(39, 0),
+    mint_cap NUMERIC(39, 0),
+    mint_height_start BIGINT,
+    mint_height_end BIGINT,
+    mint_offset_start BIGINT,
+    mint_offset_end BIGINT,
+    turbo BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS idx_runes_block_height ON runes(block_height);
+
+-- Accepted mints; their count is checked against the cap of the rune.
+CREATE TABLE IF NOT EXISTS rune_mints (
+    rune_id TEXT NOT NULL,
+    txid TEXT NOT NULL,
+    block_height INT NOT NULL,
+    amount NUMERIC(39, 0) NOT NULL,
+    PRIMARY KEY (rune_id, txid)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rune_mints_block_height ON rune_mints(block_height);
+
+-- Runes carried by an output, until the output is spent.
+CREATE TABLE IF NOT EXISTS rune_outputs (
+    txid TEXT NOT NULL,
+    vout INT NOT NULL,
+    rune_id TEXT NOT NULL,
+    amount NUMERIC(39, 0) NOT NULL,
+    address TEXT,
+    block_height INT NOT NULL,
+    spent_txid TEXT,
+    spent_height INT,
+    PRIMARY KEY (txid, vout, rune_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_rune_outputs_unspent_address ON rune_outputs(address, rune_id) WHERE spent_txid IS NULL;
+CREATE INDEX IF NOT EXISTS idx_rune_outputs_block_height ON rune_outputs(block_height);
+CREATE INDEX IF NOT EXISTS idx_rune_outputs_spent_height ON rune_outputs(spent_height) WHERE spent_height IS NOT NULL;
+
+CREATE OR REPLACE VIEW rune_balances AS
+SELECT address, rune_id, SUM(amount) AS amount
+FROM rune_outputs
+WHERE spent_txid IS NULL AND address IS NOT NULL
+GROUP BY address, rune_id;
@@ -0,0 +1,9 @@
+-- Where a watched address of a job comes from: the YAML config or the jobs API. Config sync
+-- only prunes addresses of its own; rows from before the migration count as config.
+ALTER TABLE job_addresses ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'config';
+
+ALTER TABLE job_addresses
+    DROP CONSTRAINT IF EXISTS job_addresses_source_check;
+
+ALTER TABLE job_addresses
+    ADD CONSTRAINT job_addresses_source_check CHECK (source IN ('config', 'api'));
@@ -0,0 +1,8 @@
+-- Transactions of a block written in chunks are `incomplete` until the chunk with the last of
+-- them writes the block row and confirms them in the same transaction, so reads of
+-- `confirmed` transactions never see a partly written block.
+ALTER TABLE transactions DROP CONSTRAINT IF EXISTS transactions_status_check;
+
+ALTER TABLE transactions
+    ADD CONSTRAINT transactions_status_check
+    CHECK (status IN ('confirmed', 'mempool', 'dropped', 'orphaned', 'incomplete'));
@@ -0,0 +1,84 @@
+-- Chain tables of `SqliteStore` (feature `sqlite`): those of 0001_init.sql and
+-- 0002_transactions_position.sql with the columns of 0011_transaction_fees.sql.
+-- JSON is stored as TEXT.
+CREATE TABLE IF NOT EXISTS blocks (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    height INTEGER NOT NULL,
+    hash TEXT NOT NULL UNIQUE,
+    prev_hash TEXT NOT NULL,
+    time INTEGER NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('canonical', 'orphaned')),
+    meta TEXT NOT NULL DEFAULT '{}',
+    total_fees_sats INTEGER NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_height ON blocks(height);
+CREATE INDEX IF NOT EXISTS idx_blocks_status_height ON blocks(status, height);
+
+CREATE TABLE IF NOT EXISTS transactions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    txid TEXT NOT NULL UNIQUE,
+    block_height INTEGER NULL,
+    block_hash TEXT NULL,
+    position_in_block INTEGER NOT NULL DEFAULT 0,
+    time INTEGER NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('confirmed', 'mempool', 'dropped', 'orphaned')),
+    decoded TEXT NOT NULL,
+    fee_sats INTEGER NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_transactions_status ON transactions(status);
+CREATE INDEX IF NOT EXISTS idx_transactions_block_height_position
+    ON transactions(block_height, position_in_block);
+
+CREATE TABLE IF NOT EXISTS tx_outputs (
+    txid TEXT NOT NULL,
+    vout INTEGER NOT NULL,
+    value_sats INTEGER NOT NULL,
+    script_type TEXT NOT NULL,
+    address TEXT NULL,
+    script_hex TEXT NOT NULL,
+    PRIMARY KEY (txid, vout),
+    CONSTRAINT fk_tx_outputs_txid FOREIGN KEY (txid) REFERENCES transactions(txid) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_tx_outputs_address ON tx_outputs(address);
+
+CREATE TABLE IF NOT EXISTS tx_inputs (
+    txid TEXT NOT NULL,
+    vin INTEGER NOT NULL,
+    prev_txid TEXT NOT NULL,
+    prev_vout INTEGER NOT NULL,
+    sequence INTEGER NOT NULL,
+    PRIMARY KEY (txid, vin),
+    CONSTRAINT fk_tx_inputs_txid FOREIGN KEY (txid) REFERENCES transactions(txid) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_tx_inputs_prev ON tx_inputs(prev_txid, prev_vout);
+
+CREATE TABLE IF NOT EXISTS utxos_current (
+    out_txid TEXT NOT NULL,
+    out_vout INTEGER NOT NULL,
+    address TEXT NOT NULL,
+    value_sats INTEGER NOT NULL,
+    created_in_txid TEXT NOT NULL,
+    spent_in_txid TEXT NULL,
+    status TEXT NOT NULL CHECK (status IN ('unspent', 'spent')),
+    PRIMARY KEY (out_txid, out_vout)
+);
+
+CREATE INDEX IF NOT EXISTS idx_utxos_current_address_status ON utxos_current(address, status);
+
+CREATE TABLE IF NOT EXISTS address_balance_current (
+    address TEXT PRIMARY KEY,
+    balance_sats INTEGER NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS address_balance_history (
+    address TEXT NOT NULL,
+    block_height INTEGER NOT NULL,
+    time INTEGER NOT NULL,
+    balance_sats INTEGER NOT NULL,
+    PRIMARY KEY (address, block_height)
+);
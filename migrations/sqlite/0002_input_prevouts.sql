@@ -0,0 +1,4 @@
+-- Spent outputs on inputs of `SqliteStore`, as in 0033_input_prevouts.sql.
+ALTER TABLE tx_inputs ADD COLUMN prev_value_sats INTEGER NULL;
+ALTER TABLE tx_inputs ADD COLUMN prev_script_type TEXT NULL;
+ALTER TABLE tx_inputs ADD COLUMN prev_address TEXT NULL;
@@ -1,107 +1,1163 @@
+use std::future::IntoFuture;
+use std::net::SocketAddr;
+use std::time::Duration;
+
 use anyhow::Result;
-use tracing::info;
+use axum::serve::ListenerExt;
+use axum::Router;
+use chrono::Utc;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
-use crate::modules::api::{self, ApiAuth, AppState};
-use crate::modules::config::AppConfig;
+use crate::modules::alerting;
+use crate::modules::alerting::rules::{AlertRulesRunner, AlertStates, RuleTarget};
+use crate::modules::api::{self, AppState, NetworkState, RouterOptions, StartupGate, TrustedProxies, UnixPeer};
+use crate::modules::auth::AuthChain;
+use crate::modules::broadcast::BroadcastService;
+use crate::modules::chain::{ChainError, ChainParams};
+use crate::modules::config::{
+    AppConfig, DatabaseConfig, IndexerConfig, JobConfig, RpcConfig, StorageConfig, UnixSocketConfig,
+};
 use crate::modules::data::DataService;
+use crate::modules::events::{EventsRunner, EventsRunnerConfig, EventsService};
+use crate::modules::fees::FeesService;
+use crate::modules::headers::{HeaderSyncRunner, HeaderSyncRunnerConfig};
+use crate::modules::indexer::writer::WriterPool;
 use crate::modules::indexer::IndexerService;
 use crate::modules::jobs::{JobsRunner, JobsRunnerConfig, JobsService};
+use crate::modules::logging;
 use crate::modules::mempool::{MempoolRunner, MempoolRunnerConfig};
 use crate::modules::metrics::MetricsService;
+use crate::modules::node_info::NodeInfoService;
 use crate::modules::nodes::{NodesRunner, NodesRunnerConfig, NodesService};
-use crate::modules::rpc::RpcClient;
-use crate::modules::storage::Storage;
+use crate::modules::profiling::ProfilingService;
+use crate::modules::rate_limit::RateLimiter;
+use crate::modules::reload::ConfigReloader;
+use crate::modules::replication::{ReplicationRunner, ReplicationRunnerConfig, ReplicationService};
+use crate::modules::retention::{RetentionRunner, RetentionRunnerConfig, RetentionService};
+use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::scan::ScanService;
+use crate::modules::sink::SinkRunner;
+use crate::modules::stats::{StatsRunner, StatsRunnerConfig, StatsService};
+use crate::modules::status::{DrainState, StatusService};
+use crate::modules::storage::{SchemaFeatures, Storage, StorageError};
+use crate::modules::templates::{BlockTemplateRunner, BlockTemplateRunnerConfig};
+use crate::modules::tls::{self, ServerCertResolver, TlsListener};
+use crate::modules::uptime::{UptimeRunner, UptimeRunnerConfig, UptimeService, DEFAULT_HEARTBEAT_INTERVAL};
+
+/// Poll interval of `getblockchaininfo` while the node is warming up at startup.
+const NODE_WARMUP_POLL_INTERVAL: Duration = Duration::from_secs(5);
 
 pub struct App {
     bind_addr: String,
-    auth: ApiAuth,
-    jobs_runner: JobsRunner,
-    mempool_runner: MempoolRunner,
+    unix_socket: Option<UnixSocketConfig>,
+    shutdown_grace_period: Duration,
+    auth: AuthChain,
+    proxies: TrustedProxies,
+    limiter: RateLimiter,
+    router_options: RouterOptions,
+    network_runners: Vec<NetworkRunners>,
     nodes_runner: NodesRunner,
+    uptime_runner: UptimeRunner,
+    replication_runner: Option<ReplicationRunner>,
+    sink_runner: Option<SinkRunner>,
+    alert_rules_runner: Option<AlertRulesRunner>,
+    /// `server.tls.watch_interval_ms`; `None` when the watcher is disabled.
+    credentials_watch_interval: Option<Duration>,
+    /// Certificate of the HTTPS listeners with `server.tls.enabled`.
+    tls: Option<ServerCertResolver>,
+    admin: Option<AdminServer>,
     state: AppState,
 }
 
+/// Listener of `server.admin` and the auth chain of its routes.
+struct AdminServer {
+    bind_addr: String,
+    auth: AuthChain,
+}
+
+/// Runners of one indexed network: the primary one or an entry of `instances`.
+struct NetworkRunners {
+    jobs: JobsRunner,
+    mempool: MempoolRunner,
+    block_template: Option<BlockTemplateRunner>,
+    events: Option<EventsRunner>,
+    headers: Option<HeaderSyncRunner>,
+    stats: Option<StatsRunner>,
+    retention: Option<RetentionRunner>,
+}
+
+impl NetworkRunners {
+    fn start(&self) {
+        self.jobs.start();
+        self.mempool.start();
+        if let Some(runner) = &self.block_template {
+            runner.start();
+        }
+        if let Some(runner) = &self.events {
+            runner.start();
+        }
+        if let Some(runner) = &self.headers {
+            runner.start();
+        }
+        if let Some(runner) = &self.stats {
+            runner.start();
+        }
+        if let Some(runner) = &self.retention {
+            runner.start();
+        }
+    }
+}
+
+type ServerTask = JoinHandle<std::io::Result<()>>;
+
+/// Server task and the receiver of the signal that stops it, see [`spawn_server`].
+type SpawnedServer = (ServerTask, oneshot::Receiver<&'static str>);
+
+/// Socket the API is served on: `bind_host:bind_port`, over HTTPS with `server.tls.enabled`,
+/// or the unix socket of `server.bind`.
+enum ApiListener {
+    Tcp(tokio::net::TcpListener),
+    Tls(TlsListener),
+    #[cfg(unix)]
+    Unix(tokio::net::UnixListener),
+}
+
+impl ApiListener {
+    async fn bind(
+        bind_addr: &str,
+        unix_socket: Option<&UnixSocketConfig>,
+        tls: Option<&ServerCertResolver>,
+    ) -> Result<Self> {
+        match unix_socket {
+            Some(socket) => bind_unix_socket(socket),
+            None => Self::bind_tcp(bind_addr, tls).await,
+        }
+    }
+
+    async fn bind_tcp(bind_addr: &str, tls: Option<&ServerCertResolver>) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind(bind_addr).await?;
+        Ok(match tls {
+            Some(certificates) => Self::Tls(TlsListener::new(listener, certificates)?),
+            None => Self::Tcp(listener),
+        })
+    }
+}
+
+/// Address the API listens on, for logs.
+fn listen_address(bind_addr: &str, unix_socket: Option<&UnixSocketConfig>, tls: bool) -> String {
+    match unix_socket {
+        Some(socket) => format!("unix:{}", socket.path.display()),
+        None if tls => format!("https://{bind_addr}"),
+        None => bind_addr.to_string(),
+    }
+}
+
+/// Binds the socket file with `server.socket_mode` permissions. A socket file left by a
+/// process that did not shut down cleanly is replaced; a live socket or any other file is not.
+#[cfg(unix)]
+fn bind_unix_socket(socket: &UnixSocketConfig) -> Result<ApiListener> {
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+
+    if let Ok(metadata) = std::fs::symlink_metadata(&socket.path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a unix socket", socket.path.display());
+        }
+        if std::os::unix::net::UnixStream::connect(&socket.path).is_ok() {
+            anyhow::bail!("unix socket {} is in use by another process", socket.path.display());
+        }
+        std::fs::remove_file(&socket.path)?;
+    }
+
+    // Bound in a directory only this process can enter and moved into place once its mode
+    // is set, so the socket is never reachable with the permissions of the umask.
+    let name = socket.path.file_name().unwrap_or_default().to_string_lossy();
+    let parent = socket.path.parent().filter(|parent| !parent.as_os_str().is_empty());
+    let staging = parent
+        .unwrap_or(std::path::Path::new("."))
+        .join(format!(".{name}.{}", std::process::id()));
+    if std::fs::symlink_metadata(&staging).is_ok_and(|metadata| metadata.is_dir()) {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let staged = staging.join("api.sock");
+    let bound = tokio::net::UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(socket.mode))?;
+        std::fs::rename(&staged, &socket.path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    Ok(ApiListener::Unix(bound?))
+}
+
+#[cfg(not(unix))]
+fn bind_unix_socket(_socket: &UnixSocketConfig) -> Result<ApiListener> {
+    anyhow::bail!("server.bind unix sockets are only supported on unix")
+}
+
+/// Binds the `server.admin` listener; it is always TCP, also when the API uses a unix socket,
+/// and serves HTTPS with the API certificate.
+async fn bind_admin(bind_addr: &str, tls: Option<&ServerCertResolver>) -> Result<ApiListener> {
+    let listener = ApiListener::bind_tcp(bind_addr, tls).await?;
+    info!(
+        component = "api",
+        bind_addr = %listen_address(bind_addr, None, tls.is_some()),
+        message = "admin server listening"
+    );
+    Ok(listener)
+}
+
+/// Removes the socket file of `server.bind` once the server has stopped.
+fn remove_unix_socket(unix_socket: Option<&UnixSocketConfig>) {
+    let Some(socket) = unix_socket else {
+        return;
+    };
+    if let Err(err) = std::fs::remove_file(&socket.path) {
+        warn!(
+            component = "api",
+            path = %socket.path.display(),
+            error = %err,
+            message = "failed to remove unix socket"
+        );
+    }
+}
+
 impl App {
-    pub async fn bootstrap() -> Result<Self> {
+    /// Runs the server. By default storage is prepared before the port is bound and a failure
+    /// exits the process; with `server.degraded_start` the port is bound first, see [`Self::serve_degraded`].
+    pub async fn serve() -> Result<()> {
         info!(component = "app", message = "bootstrap started");
 
-        let config = AppConfig::load()?;
+        let config = load_config()?;
+        if let Some(otlp) = &config.logging.otlp {
+            logging::otlp::start(otlp)?;
+            info!(
+                component = "otlp",
+                endpoint = %otlp.endpoint,
+                service_name = %otlp.service_name,
+                message = "span export enabled"
+            );
+        }
+        if let Some(alerting) = &config.alerting {
+            alerting::start(alerting)?;
+            info!(
+                component = "alerting",
+                webhook = alerting.webhook.is_some(),
+                sentry = alerting.sentry.is_some(),
+                message = "error reporting enabled"
+            );
+        }
+
+        let result = if config.server.degraded_start {
+            Self::serve_degraded(config).await
+        } else {
+            Self::serve_with_storage(config).await
+        };
+        alerting::flush().await;
+        logging::otlp::flush().await;
+        result
+    }
+
+    /// Prepares storage before the port is bound; a failure exits the process.
+    async fn serve_with_storage(config: AppConfig) -> Result<()> {
+        let tls = ServerCertResolver::from_config(&config.server.tls)?;
+        let (storage, schema) = prepare_storage(&config.database).await?;
+        Self::bootstrap(config, storage, schema, DrainState::default(), tls)
+            .await?
+            .run()
+            .await
+    }
+
+    async fn bootstrap(
+        config: AppConfig,
+        storage: Storage,
+        schema: SchemaFeatures,
+        drain: DrainState,
+        tls: Option<ServerCertResolver>,
+    ) -> Result<Self> {
         let bind_addr = format!("{}:{}", config.server.bind_host, config.server.bind_port);
+        let unix_socket = config.server.unix_socket.clone();
+        let shutdown_grace_period = Duration::from_millis(config.server.shutdown_grace_period_ms);
+        let credentials_watch_interval =
+            Some(config.server.tls.watch_interval_ms).filter(|ms| *ms > 0).map(Duration::from_millis);
 
-        let storage = Storage::connect().await?;
-        storage.apply_migrations().await?;
-        let jobs_service = JobsService::new(storage.pool().clone());
-        jobs_service.sync_from_config(&config.jobs).await?;
-        jobs_service.activate_enabled_jobs(&config.jobs).await?;
+        let uptime = UptimeService::new(storage.pool().clone()).with_schema_features(schema);
+        uptime.record_start(DEFAULT_HEARTBEAT_INTERVAL).await?;
         let metrics = MetricsService::new();
         let nodes_service = NodesService::new(storage.pool().clone());
         nodes_service.ensure_primary_node(&config.rpc).await?;
-        let rpc = RpcClient::from_config(&config.rpc)?.with_metrics(metrics.clone());
-        let indexer = IndexerService::new(rpc.clone(), storage.pool().clone(), metrics.clone());
-        let mempool_runner = MempoolRunner::new(
-            rpc.clone(),
-            storage.pool().clone(),
-            MempoolRunnerConfig {
-                poll_interval: std::time::Duration::from_millis(config.indexer.poll.mempool_interval_ms),
-            },
-        );
+        let (primary, primary_runners, rpc) = bootstrap_network(
+            &config.indexer.network,
+            &storage,
+            schema,
+            &config.rpc,
+            &config.indexer,
+            &config.storage,
+            &config.jobs,
+            &metrics,
+            config.sink.is_some(),
+        )
+        .await?;
+        let alert_states = AlertStates::default();
+        let status = StatusService::new(storage.pool().clone())
+            .with_drain(drain)
+            .with_rpc(rpc.clone())
+            .with_migrations_check(!storage.compat_mode())
+            .with_alerts(alert_states.clone());
+        let mut rule_targets = vec![RuleTarget {
+            network: config.indexer.network.clone(),
+            status: status.clone(),
+            jobs: primary.jobs.clone(),
+            mempool: primary_runners.mempool.clone(),
+        }];
         let nodes_runner = NodesRunner::new(
             storage.pool().clone(),
             metrics.clone(),
-            NodesRunnerConfig {
-                poll_interval: std::time::Duration::from_millis(config.indexer.poll.tip_interval_ms),
-            },
+            NodesRunnerConfig::from_config(&config.indexer),
         );
-        let jobs_runner = JobsRunner::new(
-            jobs_service.clone(),
-            rpc,
-            indexer,
-            metrics.clone(),
-            JobsRunnerConfig {
-                max_jobs: config.indexer.concurrency.max_jobs as usize,
-                poll_interval: std::time::Duration::from_millis(config.indexer.poll.tip_interval_ms),
-                blocks_per_batch: config.indexer.batching.blocks_per_batch,
-                reorg_depth: config.indexer.reorg_depth,
+
+        let mut networks = vec![primary.clone()];
+        let mut network_runners = vec![primary_runners];
+        let mut rpc_clients = vec![rpc];
+        for instance in &config.instances {
+            let instance_storage = storage.with_schema(&instance.schema).await?;
+            let instance_schema = instance_storage.prepare_schema().await?;
+            let (network, runners, instance_rpc) = bootstrap_network(
+                &instance.name,
+                &instance_storage,
+                instance_schema,
+                &instance.rpc,
+                &instance.indexer,
+                &config.storage,
+                &instance.jobs,
+                &metrics,
+                false,
+            )
+            .await?;
+            info!(
+                component = "app",
+                instance = %instance.name,
+                schema = %instance.schema,
+                chain = %instance.indexer.chain,
+                network = %instance.indexer.network,
+                jobs_count = instance.jobs.len(),
+                message = "indexer instance configured"
+            );
+            rule_targets.push(RuleTarget {
+                network: instance.name.clone(),
+                status: StatusService::new(instance_storage.pool().clone()).with_rpc(instance_rpc.clone()),
+                jobs: network.jobs.clone(),
+                mempool: runners.mempool.clone(),
+            });
+            networks.push(network);
+            network_runners.push(runners);
+            rpc_clients.push(instance_rpc);
+        }
+        let alert_rules_runner = config
+            .alerting
+            .as_ref()
+            .and_then(|alerting| alerting.rules.clone())
+            .map(|rules| AlertRulesRunner::new(rules, rule_targets, alert_states));
+
+        let uptime_runner = UptimeRunner::new(
+            uptime.clone(),
+            UptimeRunnerConfig {
+                heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
             },
         );
 
+        let (replication, replication_runner) = match config.replication.as_ref() {
+            Some(replication_config) => {
+                let service = ReplicationService::new(storage.pool().clone(), replication_config.clone());
+                if let Err(err) = service.ensure_publication().await {
+                    warn!(component = "replication", error = %err, message = "failed to configure replication publication");
+                }
+                let runner = ReplicationRunner::new(
+                    service.clone(),
+                    metrics.clone(),
+                    ReplicationRunnerConfig::from_config(replication_config),
+                );
+                (Some(service), Some(runner))
+            }
+            None => (None, None),
+        };
+        let sink_runner = config
+            .sink
+            .as_ref()
+            .map(|sink| {
+                SinkRunner::new(storage.pool().clone(), metrics.clone(), sink.clone())
+                    .map(|runner| runner.with_schema_features(schema))
+            })
+            .transpose()?;
+
+        let auth = AuthChain::from_config(&config.server.auth);
+        let admin = config.server.admin.as_ref().map(|admin| AdminServer {
+            bind_addr: format!("{}:{}", admin.bind_host, admin.bind_port),
+            auth: AuthChain::from_config(&admin.auth),
+        });
+        let proxies = TrustedProxies::new(config.server.trusted_proxies.clone())
+            .with_unix_peers(config.server.unix_socket.as_ref().is_some_and(|socket| socket.trust_forwarded));
+        let limiter = RateLimiter::new(&config.server.rate_limit);
+        let router_options = RouterOptions {
+            swagger_ui: config.server.swagger_ui,
+            compression: config.server.compression,
+            admin: admin.is_none(),
+        };
+        let jobs_count = config.jobs.len();
+        let network = config.indexer.network.clone();
+        // Reload applies to the primary network only; `instances` are restart-only.
+        let primary_runners = &network_runners[0];
+        let retention = primary_runners.retention.as_ref().map(|runner| runner.service().clone());
+        let reload = ConfigReloader::new(AppConfig::path(), config, primary.jobs.clone())
+            .with_jobs_runner(primary_runners.jobs.clone())
+            .with_mempool_runner(primary_runners.mempool.clone())
+            .with_nodes_runner(nodes_runner.clone())
+            .with_block_template_runner(primary_runners.block_template.clone())
+            .with_events_runner(primary_runners.events.clone())
+            .with_header_sync_runner(primary_runners.headers.clone())
+            .with_stats_runner(primary_runners.stats.clone())
+            .with_retention(retention.clone())
+            .with_auth(auth.clone())
+            .with_admin_auth(admin.as_ref().map(|admin| admin.auth.clone()))
+            .with_rpc_clients(rpc_clients)
+            .with_nodes_service(nodes_service.clone())
+            .with_server_certificates(tls.clone());
+
         info!(
             component = "config",
-            network = %config.indexer.network,
-            jobs_count = config.jobs.len(),
+            network = %network,
+            jobs_count,
+            auth_providers = ?auth.provider_names(),
             message = "configuration loaded"
         );
 
         Ok(Self {
             bind_addr,
-            auth: ApiAuth {
-                username: config.server.auth.username,
-                password: config.server.auth.password,
-            },
-            jobs_runner,
-            mempool_runner,
+            unix_socket,
+            shutdown_grace_period,
+            auth,
+            proxies,
+            limiter,
+            router_options,
+            network_runners,
             nodes_runner,
+            uptime_runner,
+            replication_runner,
+            sink_runner,
+            alert_rules_runner,
+            credentials_watch_interval,
+            tls,
+            admin,
             state: AppState {
-                jobs: jobs_service,
-                data: DataService::new(storage.pool().clone()),
+                jobs: primary.jobs,
+                data: primary.data,
+                events: primary.events,
+                fees: primary.fees,
+                stats: primary.stats,
+                broadcast: primary.broadcast,
+                scan: primary.scan,
+                node_info: primary.node_info,
                 metrics,
                 nodes: nodes_service,
+                status,
+                uptime,
+                profiling: ProfilingService::new(),
+                reload: Some(reload),
+                replication,
+                retention,
+                networks,
             },
         })
     }
 
-    pub async fn run(self) -> Result<()> {
-        self.jobs_runner.start();
-        self.mempool_runner.start();
-        self.nodes_runner.start();
-        let listener = tokio::net::TcpListener::bind(&self.bind_addr).await?;
+    async fn run(self) -> Result<()> {
+        self.start_runners();
+        let listener = ApiListener::bind(&self.bind_addr, self.unix_socket.as_ref(), self.tls.as_ref()).await?;
         info!(
             component = "api",
-            bind_addr = %self.bind_addr,
+            bind_addr = %listen_address(&self.bind_addr, self.unix_socket.as_ref(), self.tls.is_some()),
             message = "http server listening"
         );
+        let admin_listener = match &self.admin {
+            Some(admin) => Some(bind_admin(&admin.bind_addr, self.tls.as_ref()).await?),
+            None => None,
+        };
+
+        let uptime = self.state.uptime.clone();
+        let unix_socket = self.unix_socket.clone();
+        let shutdown_grace_period = self.shutdown_grace_period;
+        let drain = self.state.status.drain().clone();
+        let (router, admin_router) = self.into_routers();
+        let admin = admin_listener
+            .zip(admin_router)
+            .map(|(listener, router)| spawn_server(listener, router, drain.clone()));
+        let (server, signal_rx) = spawn_server(listener, router, drain);
 
-        axum::serve(listener, api::router(self.auth, self.state)).await?;
+        let (reason, ()) = tokio::try_join!(
+            wait_for_shutdown(server, signal_rx, shutdown_grace_period),
+            wait_for_admin_shutdown(admin, shutdown_grace_period),
+        )?;
+        remove_unix_socket(unix_socket.as_ref());
+        uptime.record_stop(reason).await?;
         Ok(())
     }
+
+    /// Binds the port before storage is ready and serves [`api::startup_router`], which reports
+    /// `starting`, while storage connection and migrations are retried. The application router
+    /// takes over once bootstrap completes; later bootstrap failures still exit the process.
+    async fn serve_degraded(config: AppConfig) -> Result<()> {
+        let bind_addr = format!("{}:{}", config.server.bind_host, config.server.bind_port);
+        let unix_socket = config.server.unix_socket.clone();
+        let shutdown_grace_period = Duration::from_millis(config.server.shutdown_grace_period_ms);
+        let retry_interval = Duration::from_millis(config.server.storage_retry_interval_ms);
+        let drain = DrainState::default();
+        let gate = StartupGate::new(drain.clone());
+        let admin_gate = StartupGate::new(drain.clone());
+        let tls = ServerCertResolver::from_config(&config.server.tls)?;
+
+        let listener = ApiListener::bind(&bind_addr, unix_socket.as_ref(), tls.as_ref()).await?;
+        info!(
+            component = "api",
+            bind_addr = %listen_address(&bind_addr, unix_socket.as_ref(), tls.is_some()),
+            message = "http server listening, waiting for storage"
+        );
+        let proxies = TrustedProxies::new(config.server.trusted_proxies.clone())
+            .with_unix_peers(config.server.unix_socket.as_ref().is_some_and(|socket| socket.trust_forwarded));
+        let admin = match &config.server.admin {
+            Some(admin_config) => {
+                let admin_addr = format!("{}:{}", admin_config.bind_host, admin_config.bind_port);
+                let listener = bind_admin(&admin_addr, tls.as_ref()).await?;
+                let startup = api::startup_router(
+                    AuthChain::from_config(&admin_config.auth),
+                    proxies.clone(),
+                    admin_gate.clone(),
+                    true,
+                );
+                Some(spawn_server(listener, startup, drain.clone()))
+            }
+            None => None,
+        };
+        let startup = api::startup_router(
+            AuthChain::from_config(&config.server.auth),
+            proxies,
+            gate.clone(),
+            config.server.admin.is_none(),
+        );
+        let (server, mut signal_rx) = spawn_server(listener, startup, drain.clone());
+
+        let storage_ready = async {
+            loop {
+                match prepare_storage(&config.database).await {
+                    Ok(ready) => return ready,
+                    Err(err) => {
+                        warn!(
+                            component = "storage",
+                            error = %err,
+                            retry_in_ms = retry_interval.as_millis() as u64,
+                            message = "storage is not ready, retrying"
+                        );
+                        gate.record_storage_error(err.to_string());
+                        admin_gate.record_storage_error(err.to_string());
+                        tokio::time::sleep(retry_interval).await;
+                    }
+                }
+            }
+        };
+        let (storage, schema) = tokio::select! {
+            ready = storage_ready => ready,
+            signal = &mut signal_rx => {
+                // Nothing was recorded in storage yet, so there is no stop event to write.
+                let stopped = async {
+                    match signal {
+                        Ok(reason) => drain_server(server, reason, shutdown_grace_period).await,
+                        Err(_) => Ok(server.await??),
+                    }
+                };
+                tokio::try_join!(stopped, wait_for_admin_shutdown(admin, shutdown_grace_period))?;
+                remove_unix_socket(unix_socket.as_ref());
+                return Ok(());
+            }
+        };
+
+        let app = match Self::bootstrap(config, storage, schema, drain, tls).await {
+            Ok(app) => app,
+            Err(err) => {
+                server.abort();
+                if let Some((admin_server, _)) = &admin {
+                    admin_server.abort();
+                }
+                return Err(err);
+            }
+        };
+        app.start_runners();
+        let uptime = app.state.uptime.clone();
+        let (router, admin_router) = app.into_routers();
+        gate.open(router);
+        if let Some(router) = admin_router {
+            admin_gate.open(router);
+        }
+        info!(component = "app", message = "storage ready, serving application routes");
+
+        let (reason, ()) = tokio::try_join!(
+            wait_for_shutdown(server, signal_rx, shutdown_grace_period),
+            wait_for_admin_shutdown(admin, shutdown_grace_period),
+        )?;
+        remove_unix_socket(unix_socket.as_ref());
+        uptime.record_stop(reason).await?;
+        Ok(())
+    }
+
+    /// Application router and, with `server.admin`, the router of the admin listener, which
+    /// then takes the admin routes off the application router.
+    fn into_routers(self) -> (Router, Option<Router>) {
+        let admin = self
+            .admin
+            .map(|admin| api::admin_router(admin.auth, self.proxies.clone(), self.state.clone()));
+        let router = api::router(self.auth, self.proxies, self.limiter, self.state, self.router_options);
+        (router, admin)
+    }
+
+    fn start_runners(&self) {
+        for runners in &self.network_runners {
+            runners.start();
+        }
+        self.nodes_runner.start();
+        self.uptime_runner.start();
+        if let Some(runner) = &self.replication_runner {
+            runner.start();
+        }
+        if let Some(runner) = &self.sink_runner {
+            runner.start();
+        }
+        if let Some(runner) = &self.alert_rules_runner {
+            runner.start();
+        }
+        if let Some(reload) = self.state.reload.clone() {
+            spawn_reload_on_sighup(reload.clone());
+            if let Some(interval) = self.credentials_watch_interval {
+                spawn_credentials_watch(reload, interval);
+            }
+        }
+    }
+}
+
+/// Syncs the configured jobs of one network into `storage`, checks its node and builds its
+/// services and runners. Returns the RPC client of the network as well. With `outbox` the
+/// indexer queues events of persisted blocks for the sink publisher.
+#[allow(clippy::too_many_arguments)]
+async fn bootstrap_network(
+    name: &str,
+    storage: &Storage,
+    schema: SchemaFeatures,
+    rpc_config: &RpcConfig,
+    indexer_config: &IndexerConfig,
+    storage_config: &StorageConfig,
+    jobs: &[JobConfig],
+    metrics: &MetricsService,
+    outbox: bool,
+) -> Result<(NetworkState, NetworkRunners, RpcClient)> {
+    let chain = ChainParams::from_config(indexer_config);
+    let jobs_service = JobsService::new(storage.pool().clone())
+        .with_read_pool(storage.read_pool().clone())
+        .with_schema_features(schema)
+        .with_chain_params(chain.clone());
+    jobs_service.sync_from_config(jobs).await?;
+    jobs_service.activate_enabled_jobs(jobs).await?;
+    let rpc = RpcClient::from_config(rpc_config)?
+        .with_metrics(metrics.clone())
+        .with_parallelism(indexer_config.concurrency.rpc_parallelism);
+    wait_for_node(name, &rpc, Duration::from_millis(rpc_config.timeouts.startup_ms)).await;
+    match chain.verify_node(&rpc).await {
+        Ok(()) => {}
+        Err(ChainError::Rpc(err)) => {
+            warn!(
+                component = "chain",
+                instance = name,
+                error = %err,
+                message = "node network check skipped, rpc unavailable"
+            );
+        }
+        Err(err) => return Err(err.into()),
+    }
+
+    let indexer = IndexerService::new(rpc.clone(), storage.pool().clone(), metrics.clone())
+        .with_schema_features(schema)
+        .with_network(chain.address_encoding())
+        .with_chain(chain.chain)
+        .with_outbox(outbox)
+        .with_raw_blocks(indexer_config.store_raw_blocks)
+        .with_decoded_tx(storage_config.store_decoded_tx)
+        .with_witness(storage_config.store_witness)
+        .with_block_filters(indexer_config.block_filters)
+        .with_prevout_rpc_fallback(indexer_config.prevout_rpc_fallback)
+        .with_txs_per_batch(indexer_config.batching.txs_per_batch)
+        .with_writer_pool(WriterPool::new(indexer_config.concurrency.db_writer_parallelism));
+    let jobs_service = jobs_service.with_indexer(indexer.clone());
+    let mempool = MempoolRunner::new(
+        rpc.clone(),
+        storage.pool().clone(),
+        MempoolRunnerConfig::from_config(indexer_config),
+    )
+    .with_network(chain.address_encoding())
+    .with_schema_features(schema)
+    .with_outbox(outbox)
+    .with_decoded_tx(storage_config.store_decoded_tx)
+    .with_witness(storage_config.store_witness);
+    let block_template = BlockTemplateRunnerConfig::from_config(indexer_config).map(|runner_config| {
+        BlockTemplateRunner::new(rpc.clone(), storage.pool().clone(), runner_config).with_schema_features(schema)
+    });
+    let events = EventsRunnerConfig::from_config(indexer_config).map(|runner_config| {
+        EventsRunner::new(storage.pool().clone(), runner_config).with_schema_features(schema)
+    });
+    let headers = HeaderSyncRunnerConfig::from_config(indexer_config).map(|runner_config| {
+        HeaderSyncRunner::new(rpc.clone(), storage.pool().clone(), metrics.clone(), runner_config)
+            .with_schema_features(schema)
+    });
+    let stats = StatsRunnerConfig::from_config(indexer_config)
+        .map(|runner_config| StatsRunner::new(storage.pool().clone(), runner_config).with_schema_features(schema));
+    let retention = RetentionRunnerConfig::from_config(indexer_config).map(|runner_config| {
+        RetentionRunner::new(RetentionService::new(storage.pool().clone(), runner_config).with_schema_features(schema))
+    });
+    let jobs_runner = JobsRunner::new(
+        jobs_service.clone(),
+        rpc.clone(),
+        indexer,
+        metrics.clone(),
+        JobsRunnerConfig::from_config(indexer_config),
+    );
+
+    let state = NetworkState {
+        name: name.to_string(),
+        jobs: jobs_service,
+        data: DataService::new(storage.read_pool().clone()).with_schema_features(schema),
+        events: EventsService::new(storage.read_pool().clone()).with_schema_features(schema),
+        fees: FeesService::new(storage.read_pool().clone()).with_schema_features(schema),
+        stats: StatsService::new(storage.read_pool().clone()).with_schema_features(schema),
+        broadcast: BroadcastService::new(storage.pool().clone())
+            .with_schema_features(schema)
+            .with_rpc(rpc.clone())
+            .with_mempool(mempool.clone()),
+        scan: ScanService::new().with_rpc(rpc.clone()),
+        node_info: NodeInfoService::new().with_rpc(rpc.clone()),
+    };
+    let runners = NetworkRunners {
+        jobs: jobs_runner,
+        mempool,
+        block_template,
+        events,
+        headers,
+        stats,
+        retention,
+    };
+    Ok((state, runners, rpc))
+}
+
+fn load_config() -> Result<AppConfig> {
+    let config = AppConfig::load()?;
+    tls::warn_expiring(&tls::preflight(&config, Utc::now())?);
+
+    Ok(config)
+}
+
+async fn prepare_storage(database: &DatabaseConfig) -> Result<(Storage, SchemaFeatures), StorageError> {
+    let storage = Storage::connect(database).await?;
+    let schema = storage.prepare_schema().await?;
+    Ok((storage, schema))
+}
+
+/// Serves `router` until a shutdown signal, whose name is sent through the returned receiver.
+fn spawn_server(
+    listener: ApiListener,
+    router: Router,
+    drain: DrainState,
+) -> SpawnedServer {
+    let (signal_tx, signal_rx) = oneshot::channel();
+    // On a signal the listener stops accepting, idle keep-alive connections
+    // are closed and in-flight requests get the grace period to finish.
+    let shutdown = async move {
+        let reason = shutdown_signal().await;
+        drain.start();
+        let _ = signal_tx.send(reason);
+    };
+    let server = match listener {
+        ApiListener::Tcp(listener) => tokio::spawn(
+            axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(shutdown)
+                .into_future(),
+        ),
+        // `tap_io` makes the peer address available as `ConnectInfo<SocketAddr>`.
+        ApiListener::Tls(listener) => tokio::spawn(
+            axum::serve(
+                listener.tap_io(|_| {}),
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown)
+            .into_future(),
+        ),
+        #[cfg(unix)]
+        ApiListener::Unix(listener) => tokio::spawn(
+            axum::serve(listener, router.into_make_service_with_connect_info::<UnixPeer>())
+                .with_graceful_shutdown(shutdown)
+                .into_future(),
+        ),
+    };
+
+    (server, signal_rx)
+}
+
+/// Returns the signal that stopped the server, or `shutdown` when it stopped on its own.
+async fn wait_for_shutdown(
+    server: ServerTask,
+    signal_rx: oneshot::Receiver<&'static str>,
+    grace_period: Duration,
+) -> Result<&'static str> {
+    let Ok(reason) = signal_rx.await else {
+        // The server stopped without a signal, so there is nothing to drain.
+        server.await??;
+        return Ok("shutdown");
+    };
+    drain_server(server, reason, grace_period).await?;
+    Ok(reason)
+}
+
+/// Drains the `server.admin` listener, if any, on the signal that stops the API.
+async fn wait_for_admin_shutdown(admin: Option<SpawnedServer>, grace_period: Duration) -> Result<()> {
+    match admin {
+        Some((server, signal_rx)) => wait_for_shutdown(server, signal_rx, grace_period).await.map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+async fn drain_server(mut server: ServerTask, reason: &'static str, grace_period: Duration) -> Result<()> {
+    info!(
+        component = "app",
+        reason,
+        grace_period_ms = grace_period.as_millis() as u64,
+        message = "shutdown requested, draining in-flight requests"
+    );
+    match tokio::time::timeout(grace_period, &mut server).await {
+        Ok(result) => result??,
+        Err(_) => {
+            warn!(component = "app", message = "shutdown grace period elapsed, aborting in-flight requests");
+            server.abort();
+        }
+    }
+
+    Ok(())
+}
+
+/// Lets a node still loading its block index finish before the network check and the runners
+/// start; gives up after `timeout` and starts anyway, the runners retry warm-up errors.
+async fn wait_for_node(name: &str, rpc: &RpcClient, timeout: Duration) {
+    match rpc.wait_until_ready(timeout, NODE_WARMUP_POLL_INTERVAL).await {
+        Ok(info) => info!(
+            component = "rpc",
+            instance = name,
+            blocks = info.blocks,
+            headers = info.headers,
+            verification_progress_pct = (info.verification_progress * 10_000.0).round() / 100.0,
+            initial_block_download = info.initial_block_download,
+            message = "node is ready"
+        ),
+        Err(RpcError::WarmingUp(status)) => warn!(
+            component = "rpc",
+            instance = name,
+            status = %status,
+            timeout_ms = timeout.as_millis() as u64,
+            message = "node still warming up after rpc.timeouts.startup_ms, starting anyway"
+        ),
+        // Reported by the network check that follows.
+        Err(_) => {}
+    }
+}
+
+#[cfg(unix)]
+fn spawn_reload_on_sighup(reload: ConfigReloader) {
+    tokio::spawn(async move {
+        let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+            Ok(signal) => signal,
+            Err(err) => {
+                warn!(component = "config", error = %err, message = "failed to install SIGHUP handler");
+                return;
+            }
+        };
+
+        while hangup.recv().await.is_some() {
+            info!(component = "config", message = "SIGHUP received, reloading configuration");
+            if let Err(err) = reload.reload().await {
+                warn!(component = "config", error = %err, message = "configuration reload rejected");
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn spawn_reload_on_sighup(_reload: ConfigReloader) {}
+
+/// Reloads credentials when a `password_file`, `server.tls` or `rpc.mtls` file changes, so
+/// certificates renewed by cert-manager or secrets rotated by a secret manager are picked up
+/// without an API call or a restart.
+fn spawn_credentials_watch(reload: ConfigReloader, interval: Duration) {
+    tokio::spawn(async move {
+        let mut seen = reload.credential_files_state().await;
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker.tick().await;
+
+        loop {
+            ticker.tick().await;
+            let state = reload.credential_files_state().await;
+            if state == seen {
+                continue;
+            }
+            let files: Vec<String> = state
+                .iter()
+                .filter(|entry| !seen.contains(entry))
+                .map(|(path, _)| path.display().to_string())
+                .collect();
+            // A certificate renewed before its key fails the preflight; the key write changes
+            // the state again and the next tick retries.
+            seen = state;
+            info!(
+                component = "config",
+                files = ?files,
+                message = "credential files changed, reloading credentials"
+            );
+            match reload.reload_credentials().await {
+                // `password_file` paths may have changed with the reload.
+                Ok(_) => seen = reload.credential_files_state().await,
+                Err(err) => warn!(component = "config", error = %err, message = "credentials reload rejected"),
+            }
+        }
+    });
+}
+
+async fn shutdown_signal() -> &'static str {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => "SIGINT",
+        _ = terminate => "SIGTERM",
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use rcgen::{CertificateParams, KeyPair};
+    use rustls::pki_types::{CertificateDer, ServerName};
+    use tokio_rustls::TlsConnector;
+
+    use super::{bind_unix_socket, remove_unix_socket, spawn_credentials_watch, spawn_server, ApiListener};
+    use crate::modules::api::{self, StartupGate, TrustedProxies};
+    use crate::modules::auth::{AuthChain, BasicAuthProvider};
+    use crate::modules::config::{AppConfig, UnixSocketConfig};
+    use crate::modules::jobs::JobsService;
+    use crate::modules::reload::ConfigReloader;
+    use crate::modules::status::DrainState;
+    use crate::modules::tls::{self, ServerCertResolver};
+
+    const HEALTH_REQUEST: &[u8] =
+        b"GET /health/live HTTP/1.1\r\nHost: localhost\r\nAuthorization: Basic YWRtaW46cGFzcw==\r\nConnection: close\r\n\r\n";
+
+    fn startup_router(drain: &DrainState) -> axum::Router {
+        let auth = AuthChain::new().with_provider(Arc::new(BasicAuthProvider::new("admin", "pass")));
+        api::startup_router(auth, TrustedProxies::default(), StartupGate::new(drain.clone()), true)
+    }
+
+    /// Writes a self-signed `localhost` pair to `dir` and returns its certificate.
+    fn write_certificate(dir: &std::path::Path) -> CertificateDer<'static> {
+        let key = KeyPair::generate().expect("key");
+        let certificate = CertificateParams::new(vec!["localhost".to_string()])
+            .expect("params")
+            .self_signed(&key)
+            .expect("self-signed certificate");
+        std::fs::write(dir.join("server.crt"), certificate.pem()).expect("write certificate");
+        std::fs::write(dir.join("server.key"), key.serialize_pem()).expect("write key");
+        certificate.der().clone()
+    }
+
+    /// Sends a health check over HTTPS, trusting only `certificate`.
+    async fn https_health(addr: std::net::SocketAddr, certificate: CertificateDer<'static>) -> std::io::Result<String> {
+        let mut roots = rustls::RootCertStore::empty();
+        roots.add(certificate).expect("trust anchor");
+        let config = rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("protocol versions")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let stream = tokio::net::TcpStream::connect(addr).await?;
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").expect("server name"), stream)
+            .await?;
+        stream.write_all(HEALTH_REQUEST).await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    #[tokio::test]
+    async fn serves_on_a_unix_socket_replacing_a_stale_one() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let socket = UnixSocketConfig {
+            path: dir.path().join("api.sock"),
+            mode: 0o660,
+            trust_forwarded: false,
+        };
+        // Left behind by a process that did not shut down cleanly: nothing accepts on it.
+        drop(std::os::unix::net::UnixListener::bind(&socket.path).expect("stale socket"));
+        assert!(socket.path.exists());
+
+        let listener = bind_unix_socket(&socket).expect("stale socket is replaced");
+        assert!(matches!(listener, ApiListener::Unix(_)));
+        let mode = std::fs::metadata(&socket.path).expect("socket file").permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+        let entries: Vec<_> = std::fs::read_dir(dir.path()).expect("read dir").collect();
+        assert_eq!(entries.len(), 1, "staging directory is removed");
+
+        let drain = DrainState::default();
+        let (_server, _signal) = spawn_server(listener, startup_router(&drain), drain);
+
+        let mut stream = tokio::net::UnixStream::connect(&socket.path).await.expect("connect");
+        stream.write_all(HEALTH_REQUEST).await.expect("send request");
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.expect("read response");
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let err = bind_unix_socket(&socket).err().expect("live socket is kept");
+        assert!(err.to_string().contains("is in use by another process"));
+
+        remove_unix_socket(Some(&socket));
+        assert!(!socket.path.exists());
+        std::fs::write(&socket.path, b"not a socket").expect("regular file");
+        let err = bind_unix_socket(&socket).err().expect("regular file is kept");
+        assert!(err.to_string().contains("is not a unix socket"));
+    }
+
+    #[tokio::test]
+    async fn serves_https_with_the_replaced_certificate() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let (cert_path, key_path) = (dir.path().join("server.crt"), dir.path().join("server.key"));
+        let first = write_certificate(dir.path());
+        let certificates = ServerCertResolver::new(tls::load_key_pair(&cert_path, &key_path).expect("first pair"));
+
+        let listener = ApiListener::bind_tcp("127.0.0.1:0", Some(&certificates)).await.expect("bind");
+        let ApiListener::Tls(tls_listener) = &listener else {
+            panic!("expected a TLS listener");
+        };
+        let addr = axum::serve::Listener::local_addr(tls_listener).expect("local address");
+        let drain = DrainState::default();
+        let (_server, _signal) = spawn_server(listener, startup_router(&drain), drain);
+
+        let response = https_health(addr, first.clone()).await.expect("https request");
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let second = write_certificate(dir.path());
+        certificates.replace(tls::load_key_pair(&cert_path, &key_path).expect("second pair"));
+        let response = https_health(addr, second).await.expect("https request after rotation");
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(https_health(addr, first).await.is_err(), "the replaced certificate is still served");
+    }
+
+    #[tokio::test]
+    async fn credentials_watch_swaps_the_served_certificate() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let first = write_certificate(dir.path());
+        std::fs::write(dir.path().join("api-password"), "pass\n").expect("write api password");
+        std::fs::write(dir.path().join("rpc-password"), "rpc\n").expect("write rpc password");
+        let yaml = format!(
+            r#"server:
+  bind_host: "127.0.0.1"
+  bind_port: 8443
+  tls:
+    enabled: true
+    cert_path: "{dir}/server.crt"
+    key_path: "{dir}/server.key"
+    watch_interval_ms: 20
+  auth:
+    basic:
+      username: "admin"
+      password_file: "{dir}/api-password"
+rpc:
+  node_id: "unused-node"
+  url: "http://127.0.0.1:1"
+  auth:
+    basic:
+      username: "rpcuser"
+      password_file: "{dir}/rpc-password"
+  timeouts:
+    connect_ms: 5000
+    request_ms: 5000
+indexer:
+  chain: "bitcoin"
+  network: "regtest"
+  reorg_depth: 6
+  poll:
+    tip_interval_ms: 5000
+    mempool_interval_ms: 3000
+  concurrency:
+    max_jobs: 2
+    rpc_parallelism: 4
+    db_writer_parallelism: 2
+  batching:
+    blocks_per_batch: 50
+    txs_per_batch: 5000
+jobs: []
+"#,
+            dir = dir.path().display(),
+        );
+        let path = dir.path().join("indexer.yaml");
+        std::fs::write(&path, yaml).expect("write config");
+
+        let config = AppConfig::load_from_path(&path).expect("load config");
+        let certificates = ServerCertResolver::from_config(&config.server.tls)
+            .expect("server pair")
+            .expect("https enabled");
+        // Credentials reload does not touch jobs, so the pool is never connected.
+        let pool = sqlx::PgPool::connect_lazy("postgres://unused@127.0.0.1:1/unused").expect("lazy pool");
+        let reload = ConfigReloader::new(path, config, JobsService::new(pool))
+            .with_server_certificates(Some(certificates.clone()));
+
+        let listener = ApiListener::bind_tcp("127.0.0.1:0", Some(&certificates)).await.expect("bind");
+        let ApiListener::Tls(tls_listener) = &listener else {
+            panic!("expected a TLS listener");
+        };
+        let addr = axum::serve::Listener::local_addr(tls_listener).expect("local address");
+        let drain = DrainState::default();
+        let (_server, _signal) = spawn_server(listener, startup_router(&drain), drain);
+        spawn_credentials_watch(reload, Duration::from_millis(20));
+        assert!(https_health(addr, first).await.expect("https request").starts_with("HTTP/1.1 200"));
+
+        // Let the watcher record the initial state before the pair is renewed.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let renewed = write_certificate(dir.path());
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+        while https_health(addr, renewed.clone()).await.is_err() {
+            assert!(tokio::time::Instant::now() < deadline, "renewed certificate is not served");
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
 }
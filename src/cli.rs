@@ -0,0 +1,201 @@
+use anyhow::{bail, Result};
+use chrono::Utc;
+use clap::{Parser, Subcommand};
+use tracing::info;
+
+use crate::app::App;
+use crate::modules::chain::ChainParams;
+use crate::modules::config::AppConfig;
+use crate::modules::indexer::{IndexerService, PersistBlockOutcome};
+use crate::modules::jobs::JobsService;
+use crate::modules::metrics::MetricsService;
+use crate::modules::rpc::RpcClient;
+#[cfg(feature = "sqlite")]
+use crate::modules::storage::sqlite::SqliteStore;
+use crate::modules::storage::store::ChainStore;
+use crate::modules::storage::{database_url, is_sqlite_url, SchemaFeatures, Storage};
+use crate::modules::tls;
+
+#[derive(Debug, Parser)]
+#[command(name = "bitcoin-blockchain-indexer", version, about = "Bitcoin blockchain indexer")]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the HTTP API and background runners (default).
+    Serve,
+    /// Load and validate the config, including env overrides and secrets.
+    ValidateConfig,
+    /// Apply database migrations and exit.
+    Migrate,
+    /// Index a height range once without starting the server.
+    Backfill {
+        #[arg(long)]
+        from: u32,
+        #[arg(long)]
+        to: u32,
+    },
+    /// Orphan an indexed block and everything above it, then index it again.
+    ReindexBlock { hash: String },
+}
+
+impl Cli {
+    pub async fn run(self) -> Result<()> {
+        match self.command.unwrap_or(Command::Serve) {
+            Command::Serve => App::serve().await,
+            Command::ValidateConfig => validate_config(),
+            Command::Migrate => migrate().await,
+            Command::Backfill { from, to } => backfill(from, to).await,
+            Command::ReindexBlock { hash } => reindex_block(&hash).await,
+        }
+    }
+}
+
+fn validate_config() -> Result<()> {
+    let path = AppConfig::path();
+    let config = AppConfig::load()?;
+    let certificates = tls::preflight(&config, Utc::now())?;
+    println!(
+        "config '{}' is valid: chain={}, network={}, jobs={}, instances={}",
+        path.display(),
+        config.indexer.chain,
+        config.indexer.network,
+        config.jobs.len(),
+        config.instances.len()
+    );
+    for certificate in certificates {
+        println!(
+            "certificate '{}' ({}) expires at {}, {} days left{}",
+            certificate.path.display(),
+            certificate.subject,
+            certificate.not_after,
+            certificate.days_left,
+            if certificate.expiring { " [expiring]" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+async fn migrate() -> Result<()> {
+    let config = AppConfig::load()?;
+    let storage = Storage::connect(&config.database).await?;
+    storage.apply_migrations().await?;
+    println!("migrations applied");
+    Ok(())
+}
+
+async fn backfill(from: u32, to: u32) -> Result<()> {
+    if from > to {
+        bail!("--from MUST be <= --to");
+    }
+    let config = AppConfig::load()?;
+    let database_url = database_url(&config.database)?;
+    if is_sqlite_url(&database_url) {
+        return backfill_sqlite(&config, &database_url, from, to).await;
+    }
+
+    let (indexer, _) = connect_indexer(&config).await?;
+    run_backfill(&indexer, from, to).await
+}
+
+/// Backfill into the chain tables of a SQLite file, for regtest without PostgreSQL.
+#[cfg(feature = "sqlite")]
+async fn backfill_sqlite(config: &AppConfig, database_url: &str, from: u32, to: u32) -> Result<()> {
+    let store = SqliteStore::connect(database_url).await?;
+    let indexer = build_indexer(config, store, SqliteStore::schema_features())?;
+    run_backfill(&indexer, from, to).await
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn backfill_sqlite(_config: &AppConfig, _database_url: &str, _from: u32, _to: u32) -> Result<()> {
+    bail!("SQLite database URLs need a build with the `sqlite` feature")
+}
+
+async fn run_backfill<S: ChainStore>(indexer: &IndexerService<RpcClient, S>, from: u32, to: u32) -> Result<()> {
+    let start_height = i32::try_from(from)?;
+    let mut indexed = 0u64;
+    let mut txs = 0u64;
+    for height in from..=to {
+        let result = indexer.index_height_from(height, start_height).await?;
+        if result.outcome == PersistBlockOutcome::Indexed {
+            indexed += 1;
+            txs += result.tx_count;
+        }
+        if height % 1000 == 0 {
+            info!(component = "cli", height, to, message = "backfill progress");
+        }
+    }
+
+    println!(
+        "backfill {from}..={to} done: {indexed} blocks indexed, {} already present, {txs} transactions",
+        u64::from(to - from) + 1 - indexed
+    );
+    Ok(())
+}
+
+async fn reindex_block(hash: &str) -> Result<()> {
+    let config = AppConfig::load()?;
+    let (indexer, jobs) = connect_indexer(&config).await?;
+    let height = indexer.reindex_block(hash).await?;
+    jobs.rewind_all_progress(height).await?;
+
+    println!("block {hash} at height {height} reindexed; jobs rewound to {height}");
+    Ok(())
+}
+
+async fn connect_indexer(config: &AppConfig) -> Result<(IndexerService, JobsService)> {
+    let storage = Storage::connect(&config.database).await?;
+    let schema = storage.prepare_schema().await?;
+    let indexer = build_indexer(config, storage.pool().clone(), schema)?;
+    let jobs = JobsService::new(storage.pool().clone()).with_schema_features(schema);
+
+    Ok((indexer, jobs))
+}
+
+fn build_indexer<S: ChainStore>(
+    config: &AppConfig,
+    store: S,
+    schema: SchemaFeatures,
+) -> Result<IndexerService<RpcClient, S>> {
+    let metrics = MetricsService::new();
+    let rpc = RpcClient::from_config(&config.rpc)?
+        .with_metrics(metrics.clone())
+        .with_parallelism(config.indexer.concurrency.rpc_parallelism);
+    let chain = ChainParams::from_config(&config.indexer);
+    Ok(IndexerService::new(rpc, store, metrics)
+        .with_schema_features(schema)
+        .with_network(chain.address_encoding())
+        .with_chain(chain.chain)
+        // Events are queued for the publisher of the running server.
+        .with_outbox(config.sink.is_some())
+        .with_raw_blocks(config.indexer.store_raw_blocks)
+        .with_decoded_tx(config.storage.store_decoded_tx)
+        .with_witness(config.storage.store_witness)
+        .with_block_filters(config.indexer.block_filters)
+        .with_prevout_rpc_fallback(config.indexer.prevout_rpc_fallback)
+        .with_txs_per_batch(config.indexer.batching.txs_per_batch))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::{Cli, Command};
+
+    #[test]
+    fn parses_subcommands() {
+        let cli = Cli::try_parse_from(["indexer"]).expect("no subcommand");
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["indexer", "backfill", "--from", "10", "--to", "20"]).expect("backfill");
+        assert!(matches!(cli.command, Some(Command::Backfill { from: 10, to: 20 })));
+
+        let cli = Cli::try_parse_from(["indexer", "reindex-block", "00ab"]).expect("reindex-block");
+        assert!(matches!(cli.command, Some(Command::ReindexBlock { hash }) if hash == "00ab"));
+
+        assert!(Cli::try_parse_from(["indexer", "backfill", "--from", "10"]).is_err());
+    }
+}
@@ -1,15 +1,19 @@
 mod app;
-mod core;
-mod modules;
+mod cli;
 
 use anyhow::Result;
-use app::App;
+use bitcoin_blockchain_indexer::modules;
+use clap::Parser;
+use cli::Cli;
+use modules::config::AppConfig;
 use modules::logging;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    logging::init();
+    let cli = Cli::parse();
+    // A config that fails to load is reported by the command; logs then use the defaults.
+    let logging = AppConfig::load().map(|config| config.logging).unwrap_or_default();
+    logging::init(&logging)?;
 
-    let app = App::bootstrap().await?;
-    app.run().await
+    cli.run().await
 }
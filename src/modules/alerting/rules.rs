@@ -0,0 +1,335 @@
+//! Conditions evaluated by [`AlertRulesRunner`]: sync lag, failed jobs and a stalled mempool
+//! poller. Transitions to `firing` and back are reported like any other alert.
+
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::config::AlertRulesConfig;
+use crate::modules::jobs::JobsService;
+use crate::modules::mempool::MempoolRunner;
+use crate::modules::status::StatusService;
+
+use super::report_rule;
+
+pub const RULE_TIP_LAG: &str = "tip_lag";
+pub const RULE_JOB_FAILED: &str = "job_failed";
+pub const RULE_MEMPOOL_STALLED: &str = "mempool_stalled";
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct AlertStatus {
+    /// `tip_lag`, `job_failed` or `mempool_stalled`.
+    pub rule: String,
+    pub network: String,
+    /// Failed job of `job_failed`.
+    pub job_id: Option<String>,
+    /// `pending` while `tip_lag` has lasted less than `tip_lag_for_ms`, then `firing`.
+    pub state: String,
+    pub message: String,
+    /// First evaluation that found the condition.
+    pub since: DateTime<Utc>,
+    pub fired_at: Option<DateTime<Utc>>,
+}
+
+/// Pending and firing alerts of the last evaluation, shared with `/v1/status`.
+#[derive(Debug, Clone, Default)]
+pub struct AlertStates {
+    inner: Arc<RwLock<Vec<AlertStatus>>>,
+}
+
+impl AlertStates {
+    pub fn list(&self) -> Vec<AlertStatus> {
+        self.inner.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+}
+
+/// Services of one indexed network the rules are evaluated against.
+#[derive(Clone)]
+pub struct RuleTarget {
+    pub network: String,
+    pub status: StatusService,
+    pub jobs: JobsService,
+    pub mempool: MempoolRunner,
+}
+
+/// Condition found by one evaluation; it fires once it has held for `hold`.
+#[derive(Debug, Clone)]
+struct Condition {
+    rule: &'static str,
+    network: String,
+    job_id: Option<String>,
+    message: String,
+    hold: Duration,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Transition {
+    Firing(AlertStatus),
+    Resolved(AlertStatus),
+}
+
+#[derive(Clone)]
+pub struct AlertRulesRunner {
+    config: AlertRulesConfig,
+    targets: Arc<Vec<RuleTarget>>,
+    states: AlertStates,
+    started_at: DateTime<Utc>,
+}
+
+impl AlertRulesRunner {
+    pub fn new(config: AlertRulesConfig, targets: Vec<RuleTarget>, states: AlertStates) -> Self {
+        Self {
+            config,
+            targets: Arc::new(targets),
+            states,
+            started_at: Utc::now(),
+        }
+    }
+
+    pub fn start(&self) {
+        let runner = self.clone();
+        info!(
+            component = "alerting",
+            interval_ms = runner.config.interval_ms,
+            networks = runner.targets.len(),
+            message = "alert rules runner started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                runner.evaluate_once().await;
+                tokio::time::sleep(Duration::from_millis(runner.config.interval_ms)).await;
+            }
+        });
+    }
+
+    async fn evaluate_once(&self) {
+        let mut conditions = Vec::new();
+        // Rules whose input could not be read keep their previous state.
+        let mut unknown = Vec::new();
+        let now = Utc::now();
+        for target in self.targets.iter() {
+            self.evaluate_target(target, now, &mut conditions, &mut unknown).await;
+        }
+
+        let transitions = {
+            let mut states = self.states.inner.write().unwrap_or_else(|err| err.into_inner());
+            let (next, transitions) = advance(&states, conditions, &unknown, now);
+            *states = next;
+            transitions
+        };
+        for transition in transitions {
+            match transition {
+                Transition::Firing(status) => {
+                    warn!(
+                        component = "alerting",
+                        rule = %status.rule,
+                        network = %status.network,
+                        job_id = ?status.job_id,
+                        alert = %status.message,
+                        message = "alert firing"
+                    );
+                    report_rule(&status, false);
+                }
+                Transition::Resolved(status) => {
+                    info!(
+                        component = "alerting",
+                        rule = %status.rule,
+                        network = %status.network,
+                        job_id = ?status.job_id,
+                        message = "alert resolved"
+                    );
+                    report_rule(&status, true);
+                }
+            }
+        }
+    }
+
+    async fn evaluate_target(
+        &self,
+        target: &RuleTarget,
+        now: DateTime<Utc>,
+        conditions: &mut Vec<Condition>,
+        unknown: &mut Vec<(&'static str, String)>,
+    ) {
+        if self.config.tip_lag_blocks > 0 {
+            let status = target.status.report().await;
+            match status.sync_lag_blocks {
+                Some(lag) if lag > i64::try_from(self.config.tip_lag_blocks).unwrap_or(i64::MAX) => {
+                    conditions.push(Condition {
+                        rule: RULE_TIP_LAG,
+                        network: target.network.clone(),
+                        job_id: None,
+                        message: format!(
+                            "{}: indexed height {} is {lag} blocks behind the node tip",
+                            target.network,
+                            status.indexed_height.unwrap_or_default()
+                        ),
+                        hold: Duration::from_millis(self.config.tip_lag_for_ms),
+                    });
+                }
+                Some(_) => {}
+                None => unknown.push((RULE_TIP_LAG, target.network.clone())),
+            }
+        }
+
+        if self.config.job_failed {
+            match target.jobs.list().await {
+                Ok(jobs) => conditions.extend(jobs.into_iter().filter(|job| job.status == "failed").map(|job| {
+                    Condition {
+                        rule: RULE_JOB_FAILED,
+                        network: target.network.clone(),
+                        message: format!(
+                            "{}: job {} failed: {}",
+                            target.network,
+                            job.job_id,
+                            job.last_error.as_deref().unwrap_or("unknown error")
+                        ),
+                        job_id: Some(job.job_id),
+                        hold: Duration::ZERO,
+                    }
+                })),
+                Err(err) => {
+                    warn!(
+                        component = "alerting",
+                        network = %target.network,
+                        error = %err,
+                        message = "failed to list jobs for alert rules"
+                    );
+                    unknown.push((RULE_JOB_FAILED, target.network.clone()));
+                }
+            }
+        }
+
+        if self.config.mempool_stalled_ms > 0 {
+            let last_synced_at = target.mempool.last_synced_at();
+            let stalled_for = now - last_synced_at.unwrap_or(self.started_at);
+            if stalled_for.num_milliseconds() > i64::try_from(self.config.mempool_stalled_ms).unwrap_or(i64::MAX) {
+                let message = match last_synced_at {
+                    Some(at) => format!("{}: no successful mempool poll since {}", target.network, at.to_rfc3339()),
+                    None => format!("{}: no successful mempool poll since startup", target.network),
+                };
+                conditions.push(Condition {
+                    rule: RULE_MEMPOOL_STALLED,
+                    network: target.network.clone(),
+                    job_id: None,
+                    message,
+                    hold: Duration::ZERO,
+                });
+            }
+        }
+    }
+}
+
+/// Next alert states from the conditions found at `now`; alerts keep `since` across
+/// evaluations and move to `firing` once their condition held for its `hold`.
+fn advance(
+    previous: &[AlertStatus],
+    conditions: Vec<Condition>,
+    unknown: &[(&'static str, String)],
+    now: DateTime<Utc>,
+) -> (Vec<AlertStatus>, Vec<Transition>) {
+    let mut next = Vec::new();
+    let mut transitions = Vec::new();
+
+    for condition in conditions {
+        let existing = previous.iter().find(|status| {
+            status.rule == condition.rule && status.network == condition.network && status.job_id == condition.job_id
+        });
+        let since = existing.map_or(now, |status| status.since);
+        let held = (now - since).to_std().unwrap_or_default();
+        let mut status = AlertStatus {
+            rule: condition.rule.to_string(),
+            network: condition.network,
+            job_id: condition.job_id,
+            state: "pending".to_string(),
+            message: condition.message,
+            since,
+            fired_at: existing.and_then(|status| status.fired_at),
+        };
+        if held >= condition.hold {
+            status.state = "firing".to_string();
+            if status.fired_at.is_none() {
+                status.fired_at = Some(now);
+                transitions.push(Transition::Firing(status.clone()));
+            }
+        }
+        next.push(status);
+    }
+
+    for status in previous {
+        let reported = next.iter().any(|current| {
+            current.rule == status.rule && current.network == status.network && current.job_id == status.job_id
+        });
+        if reported {
+            continue;
+        }
+        if unknown
+            .iter()
+            .any(|(rule, network)| *rule == status.rule && *network == status.network)
+        {
+            next.push(status.clone());
+        } else if status.fired_at.is_some() {
+            transitions.push(Transition::Resolved(status.clone()));
+        }
+    }
+
+    (next, transitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tip_lag(hold_secs: u64) -> Condition {
+        Condition {
+            rule: RULE_TIP_LAG,
+            network: "mainnet".to_string(),
+            job_id: None,
+            message: "mainnet: 12 blocks behind".to_string(),
+            hold: Duration::from_secs(hold_secs),
+        }
+    }
+
+    #[test]
+    fn fires_after_hold_and_resolves_when_condition_clears() {
+        let start = Utc::now();
+
+        let (states, transitions) = advance(&[], vec![tip_lag(600)], &[], start);
+        assert_eq!(states[0].state, "pending");
+        assert!(transitions.is_empty());
+
+        let later = start + chrono::Duration::seconds(601);
+        let (states, transitions) = advance(&states, vec![tip_lag(600)], &[], later);
+        assert_eq!((states[0].state.as_str(), states[0].since), ("firing", start));
+        assert_eq!(states[0].fired_at, Some(later));
+        assert!(matches!(&transitions[..], [Transition::Firing(status)] if status.rule == RULE_TIP_LAG));
+
+        // Still firing: reported once.
+        let (states, transitions) = advance(&states, vec![tip_lag(600)], &[], later + chrono::Duration::seconds(30));
+        assert!(transitions.is_empty());
+
+        // Lag unknown while the node is down: state is kept.
+        let unknown = [(RULE_TIP_LAG, "mainnet".to_string())];
+        let (states, transitions) = advance(&states, Vec::new(), &unknown, later + chrono::Duration::seconds(60));
+        assert_eq!(states.len(), 1);
+        assert!(transitions.is_empty());
+
+        let (states, transitions) = advance(&states, Vec::new(), &[], later + chrono::Duration::seconds(90));
+        assert!(states.is_empty());
+        assert!(matches!(&transitions[..], [Transition::Resolved(status)] if status.fired_at == Some(later)));
+    }
+
+    #[test]
+    fn pending_alert_clears_without_resolution() {
+        let start = Utc::now();
+        let (states, _) = advance(&[], vec![tip_lag(600)], &[], start);
+        let (states, transitions) = advance(&states, Vec::new(), &[], start + chrono::Duration::seconds(30));
+        assert!(states.is_empty());
+        assert!(transitions.is_empty());
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::value::RawValue;
+
+pub const SATS_PER_BTC: i64 = 100_000_000;
+const BTC_DECIMALS: i64 = 8;
+
+/// Satoshis of a BTC amount in JSON number notation (`0.29`, `50.00000000`, `1e-8`),
+/// converted with integer math. `None` for malformed text, sub-satoshi precision or overflow.
+pub fn parse_btc(text: &str) -> Option<i64> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.strip_prefix('+').unwrap_or(exponent).parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if whole.is_empty() || !whole.bytes().chain(fraction.bytes()).all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = format!("{whole}{fraction}");
+    let mut digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        return Some(0);
+    }
+    let mut shift = exponent.checked_add(BTC_DECIMALS)?.checked_sub(fraction.len() as i64)?;
+    while shift < 0 {
+        digits = digits.strip_suffix('0')?;
+        shift += 1;
+    }
+
+    let mut sats: i64 = digits.parse().ok()?;
+    for _ in 0..shift {
+        sats = sats.checked_mul(10)?;
+    }
+    Some(if negative { -sats } else { sats })
+}
+
+/// BTC amount with the eight decimals printed by Bitcoin Core.
+pub fn format_btc(sats: i64) -> String {
+    let sign = if sats < 0 { "-" } else { "" };
+    let abs = sats.unsigned_abs();
+    let per_btc = SATS_PER_BTC as u64;
+    format!("{sign}{}.{:08}", abs / per_btc, abs % per_btc)
+}
+
+/// `#[serde(with = "...")]` for RPC amounts in BTC kept as satoshis. Reads the number
+/// text as written by the node, so no amount passes through `f64`.
+pub mod btc {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(sats: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+        RawValue::from_string(format_btc(*sats))
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+        let raw = Box::<RawValue>::deserialize(deserializer)?;
+        parse_btc(raw.get()).ok_or_else(|| serde::de::Error::custom(format!("invalid BTC amount: {}", raw.get())))
+    }
+}
+
+/// [`btc`] for optional amounts; `null` maps to `None`.
+pub mod btc_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(sats: &Option<i64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match sats {
+            Some(sats) => btc::serialize(sats, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+        let Some(raw) = Option::<Box<RawValue>>::deserialize(deserializer)? else {
+            return Ok(None);
+        };
+        parse_btc(raw.get())
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid BTC amount: {}", raw.get())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_btc, parse_btc};
+
+    #[test]
+    fn converts_btc_amounts_exactly() {
+        assert_eq!(parse_btc("0"), Some(0));
+        assert_eq!(parse_btc("0.00000001"), Some(1));
+        assert_eq!(parse_btc("1e-8"), Some(1));
+        assert_eq!(parse_btc("0.29"), Some(29_000_000));
+        assert_eq!(parse_btc("1.15"), Some(115_000_000));
+        assert_eq!(parse_btc("50.00000000"), Some(5_000_000_000));
+        assert_eq!(parse_btc("20999999.99999999"), Some(2_099_999_999_999_999));
+        assert_eq!(parse_btc("0.000000001"), None);
+        assert_eq!(parse_btc("0.000000010"), Some(1));
+        assert_eq!(parse_btc("1.5e1"), Some(1_500_000_000));
+        assert_eq!(parse_btc("abc"), None);
+        assert_eq!(parse_btc(".5"), None);
+        assert_eq!(parse_btc("1e400"), None);
+
+        assert_eq!(format_btc(29_000_000), "0.29000000");
+        assert_eq!(format_btc(-1), "-0.00000001");
+    }
+}
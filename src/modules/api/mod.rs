@@ -1,35 +1,175 @@
-use axum::body::Body;
-use axum::extract::{Path, Query, State};
-use axum::http::header::AUTHORIZATION;
-use axum::http::{HeaderValue, Request, StatusCode};
-use axum::middleware::{from_fn_with_state, Next};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use axum::body::{Body, HttpBody};
+#[cfg(unix)]
+use axum::extract::connect_info::Connected;
+use axum::extract::{ConnectInfo, OriginalUri, Path, Query, State};
+use axum::handler::Handler;
+use axum::http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RETRY_AFTER};
+use axum::http::{HeaderMap, HeaderValue, Method, Request, StatusCode};
+use axum::middleware::{from_fn, from_fn_with_state, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{IntoResponse, Response};
+#[cfg(unix)]
+use axum::serve::IncomingStream;
 use axum::{routing::get, Json, Router};
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine;
+use ipnet::IpNet;
+use futures_util::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tower::ServiceExt;
+use tower_http::compression::CompressionLayer;
+use tracing::{debug, info, info_span, warn, Instrument};
 use utoipa::{IntoParams, OpenApi, ToSchema};
 use utoipa_swagger_ui::SwaggerUi;
 
+use crate::modules::alerting::rules::AlertStatus;
+use crate::modules::auth::{AuthChain, Credentials, Principal, Role};
+use crate::modules::broadcast::{BroadcastError, BroadcastResult, BroadcastService};
 use crate::modules::data::{
-    BalanceFilter, BlocksFilter, DataError, DataService, Pagination, TransactionsFilter,
+    BalanceFilter, BlocksFilter, DataError, DataService, Pagination, ScriptSearchFilter, TransactionsFilter,
+};
+use crate::modules::events::{
+    parse_stream_kinds, EventsError, EventsFilter, EventsService, StreamEvent, TxEvent,
 };
-use crate::modules::jobs::{CreateJobRequest, JobDetails, JobSummary, JobsError, JobsService};
+use crate::modules::fees::{FeeEstimate, FeeRatePercentiles, FeeRateSample, FeesError, FeesService};
+use crate::modules::jobs::{
+    AddJobAddressesRequest, CreateJobRequest, DescriptorImport, IdempotencyKeyState, JobActionRequest, JobDescriptor,
+    BlockReindex, JobDescriptors, JobDetails, JobDryRun, JobDryRunBlock, JobErrorItem, JobEstimate, JobEta, JobSummary, JobsError,
+    JobsListFilter, JobsService,
+};
+use crate::modules::logging::{self, LogFilterError, LogFilterStatus};
 use crate::modules::metrics::MetricsService;
+use crate::modules::node_info::{
+    NodeBlockchainInfo, NodeInfo, NodeInfoError, NodeInfoService, NodeMempoolInfo, NodeNetworkInfo,
+};
 use crate::modules::nodes::{CreateNodeRequest, NodeHealthDetails, NodeSummary, NodesError, NodesService};
+use crate::modules::profiling::{
+    BlockingPoolMetrics, ProfilingError, ProfilingService, RuntimeMetrics, WorkerMetrics,
+};
+use crate::modules::rate_limit::RateLimiter;
+use crate::modules::reload::{ConfigReloader, CredentialsReport, ReloadError, ReloadReport};
+use crate::modules::replication::{ReplicationError, ReplicationService, ReplicationSlotStatus, ReplicationStatus};
+use crate::modules::retention::{PruneReport, RetentionError, RetentionService};
+use crate::modules::scan::{ScanError, ScanResult, ScanService, ScanUtxo};
+use crate::modules::stats::{
+    BlockSupplyItem, StatsError, StatsFilter, StatsItem, StatsPage, StatsService, SupplyResponse, UtxoAgeBucket,
+};
+use crate::modules::status::{DrainState, DrainStatus, Readiness, StatusService, SyncStatus};
+use crate::modules::tls::CertificateStatus;
+use crate::modules::uptime::{ProcessEvent, UptimeError, UptimeReport, UptimeService};
+use crate::modules::verify::DiscrepancyItem;
 
-#[derive(Debug, Clone)]
-pub struct ApiAuth {
-    pub username: String,
-    pub password: String,
+const SIGNED_BODY_LIMIT_BYTES: usize = 1024 * 1024;
+const REQUEST_ID_HEADER: &str = "x-request-id";
+const MAX_REQUEST_ID_LEN: usize = 128;
+const X_FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+const IDEMPOTENT_BODY_LIMIT_BYTES: usize = 1024 * 1024;
+/// Larger responses (raw blocks of big blocks) are sent without `ETag`.
+const ETAG_BODY_LIMIT_BYTES: u64 = 32 * 1024 * 1024;
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+/// How often an open event stream checks `stream_events` for new rows.
+const EVENT_STREAM_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Address of the client that sent the request, put into request extensions
+/// by the tracing middleware when the server is run with connect info.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+/// Connect info of requests accepted on the unix socket of `server.bind`. The peer has no
+/// address, see [`TrustedProxies::unix_client_ip`].
+#[derive(Debug, Clone, Copy)]
+pub struct UnixPeer;
+
+/// Client address of unix socket requests whose forwarded headers are not believed, so
+/// every local process on the socket shares one per-IP rate limit bucket.
+const UNIX_PEER_IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+#[cfg(unix)]
+impl Connected<IncomingStream<'_, tokio::net::UnixListener>> for UnixPeer {
+    fn connect_info(_stream: IncomingStream<'_, tokio::net::UnixListener>) -> Self {
+        Self
+    }
+}
+
+/// Networks of proxies whose `Forwarded`/`X-Forwarded-For` headers are believed.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Arc<Vec<IpNet>>,
+    /// `server.socket_trust_forwarded`: peers of the unix socket are believed as well.
+    unix_peers: bool,
+}
+
+/// Hands requests of a degraded start to the application router once bootstrap completes.
+/// Until then only liveness is served, readiness reports `starting` and every other route
+/// answers `503 STARTING`.
+#[derive(Debug, Clone, Default)]
+pub struct StartupGate {
+    app: Arc<OnceLock<Router>>,
+    drain: DrainState,
+    storage_error: Arc<RwLock<Option<String>>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub jobs: JobsService,
     pub data: DataService,
+    pub events: EventsService,
+    pub fees: FeesService,
+    pub stats: StatsService,
+    pub broadcast: BroadcastService,
+    pub scan: ScanService,
+    pub node_info: NodeInfoService,
     pub metrics: MetricsService,
     pub nodes: NodesService,
+    pub status: StatusService,
+    pub uptime: UptimeService,
+    pub profiling: ProfilingService,
+    pub reload: Option<ConfigReloader>,
+    pub replication: Option<ReplicationService>,
+    pub retention: Option<RetentionService>,
+    /// Indexed networks served under `/v1/{name}`; the services above belong to the primary one.
+    pub networks: Vec<NetworkState>,
+}
+
+/// Services of one indexed network: the primary one or an entry of `instances` in the config.
+#[derive(Debug, Clone)]
+pub struct NetworkState {
+    pub name: String,
+    pub jobs: JobsService,
+    pub data: DataService,
+    pub events: EventsService,
+    pub fees: FeesService,
+    pub stats: StatsService,
+    pub broadcast: BroadcastService,
+    pub scan: ScanService,
+    pub node_info: NodeInfoService,
+}
+
+impl AppState {
+    /// State whose jobs, data, events, fees, stats, broadcast, scan and node info services are the ones of `network`.
+    fn for_network(&self, network: &NetworkState) -> Self {
+        Self {
+            jobs: network.jobs.clone(),
+            data: network.data.clone(),
+            events: network.events.clone(),
+            fees: network.fees.clone(),
+            stats: network.stats.clone(),
+            broadcast: network.broadcast.clone(),
+            scan: network.scan.clone(),
+            node_info: network.node_info.clone(),
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -38,11 +178,131 @@ struct HealthResponse {
     status: &'static str,
 }
 
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct StatusResponse {
+    item: SyncStatus,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ReadinessResponse {
+    item: Readiness,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct DrainResponse {
+    item: DrainStatus,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct LogLevelResponse {
+    item: LogFilterStatus,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(ToSchema)]
+struct LogLevelRequest {
+    /// `RUST_LOG` directives, e.g. `info,bitcoin_blockchain_indexer::modules::jobs=debug`.
+    filter: String,
+}
+
+/// Machine-readable error codes returned in `ApiError.code`.
+///
+/// This enum is the single source of truth for both error responses and the
+/// `GET /v1/errors` catalog, so a new code cannot be returned without being listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum ApiErrorCode {
+    AuthFailed,
+    Forbidden,
+    NotFound,
+    AddressNotIndexed,
+    Conflict,
+    PayloadTooLarge,
+    RateLimited,
+    ValidationError,
+    InternalError,
+    NodeUnavailable,
+    Starting,
+    TxRejected,
+}
+
+impl ApiErrorCode {
+    const ALL: [ApiErrorCode; 12] = [
+        ApiErrorCode::AuthFailed,
+        ApiErrorCode::Forbidden,
+        ApiErrorCode::NotFound,
+        ApiErrorCode::AddressNotIndexed,
+        ApiErrorCode::Conflict,
+        ApiErrorCode::PayloadTooLarge,
+        ApiErrorCode::RateLimited,
+        ApiErrorCode::ValidationError,
+        ApiErrorCode::InternalError,
+        ApiErrorCode::NodeUnavailable,
+        ApiErrorCode::Starting,
+        ApiErrorCode::TxRejected,
+    ];
+
+    fn status(self) -> StatusCode {
+        match self {
+            ApiErrorCode::AuthFailed => StatusCode::UNAUTHORIZED,
+            ApiErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ApiErrorCode::NotFound => StatusCode::NOT_FOUND,
+            ApiErrorCode::AddressNotIndexed => StatusCode::NOT_FOUND,
+            ApiErrorCode::Conflict => StatusCode::CONFLICT,
+            ApiErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiErrorCode::ValidationError => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiErrorCode::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiErrorCode::NodeUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiErrorCode::Starting => StatusCode::SERVICE_UNAVAILABLE,
+            ApiErrorCode::TxRejected => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            ApiErrorCode::AuthFailed => "Credentials are missing, invalid or not accepted by any auth provider",
+            ApiErrorCode::Forbidden => "Credentials are valid but their role does not allow this request",
+            ApiErrorCode::NotFound => "Requested job, node or job address does not exist",
+            ApiErrorCode::AddressNotIndexed => "Address is not covered by any indexing job",
+            ApiErrorCode::Conflict => "Resource already exists, the job state transition is not allowed or the scan queue is busy",
+            ApiErrorCode::PayloadTooLarge => "Signed request body exceeds the 1 MiB limit",
+            ApiErrorCode::RateLimited => "Client or credential exceeded server.rate_limit; retry after Retry-After seconds",
+            ApiErrorCode::ValidationError => "Request parameters or body failed validation; see details.reason",
+            ApiErrorCode::InternalError => "Storage or serialization failure on the server side",
+            ApiErrorCode::NodeUnavailable => "Bitcoin RPC node is unreachable or did not respond to the health probe",
+            ApiErrorCode::Starting => "Instance is still waiting for storage after a degraded start; retry later",
+            ApiErrorCode::TxRejected => "Node refused to relay the transaction; see details.reason",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ErrorCatalogItem {
+    code: ApiErrorCode,
+    http_status: u16,
+    description: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ErrorCatalogResponse {
+    items: Vec<ErrorCatalogItem>,
+}
+
 #[derive(Debug, Serialize)]
 #[derive(ToSchema)]
 struct ApiError {
-    code: &'static str,
-    message: &'static str,
+    code: ApiErrorCode,
+    /// Human-readable, naming the job, node or value the error is about.
+    message: String,
+    /// Machine-readable context of the error, e.g. `job_id`, `current_status` and `requested`
+    /// of a rejected state transition or `reason` of a failed validation; `{}` when there is none.
     details: serde_json::Value,
 }
 
@@ -50,6 +310,10 @@ struct ApiError {
 #[derive(ToSchema)]
 struct JobsListResponse {
     items: Vec<JobSummary>,
+    offset: i64,
+    limit: i64,
+    /// Jobs matching the filter across all pages.
+    total: i64,
 }
 
 #[derive(Debug, Serialize)]
@@ -70,11 +334,162 @@ struct NodeDetailsResponse {
     item: NodeHealthDetails,
 }
 
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct NodeInfoResponse {
+    item: NodeInfo,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct JobEtaResponse {
+    item: JobEta,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct JobEstimateResponse {
+    item: JobEstimate,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct JobDryRunResponse {
+    item: JobDryRun,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct JobDescriptorsResponse {
+    item: JobDescriptors,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct JobErrorsResponse {
+    items: Vec<JobErrorItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct JobDiscrepanciesResponse {
+    items: Vec<DiscrepancyItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct UptimeResponse {
+    item: UptimeReport,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ReloadResponse {
+    item: ReloadReport,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct CredentialsReloadResponse {
+    item: CredentialsReport,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ReplicationResponse {
+    item: ReplicationStatus,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct PruneResponse {
+    item: PruneReport,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct TxReplacementsResponse {
+    txid: String,
+    items: Vec<crate::modules::data::TxReplacementItem>,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct BlockForksResponse {
+    height: i32,
+    items: Vec<crate::modules::data::ForkBlockItem>,
+}
+
 #[derive(Debug, Deserialize)]
-#[derive(IntoParams)]
-struct PaginationQuery {
-    offset: Option<i64>,
-    limit: Option<i64>,
+#[derive(ToSchema)]
+struct BroadcastRequest {
+    /// Serialized transaction as hex, at most 400 000 bytes.
+    hex: String,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct BroadcastResponse {
+    item: BroadcastResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(ToSchema)]
+struct ScanRequest {
+    /// Output descriptors as accepted by `scantxoutset`, at most 100; the checksum is optional.
+    descriptors: Vec<String>,
+    /// Keys derived per ranged descriptor, 1..=100000; the node default of 1000 when absent.
+    range: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ScanResponse {
+    item: ScanResult,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct RawBlockResponse {
+    item: crate::modules::data::RawBlockItem,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct BlockFilterResponse {
+    item: crate::modules::data::BlockFilterItem,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(ToSchema)]
+struct ReindexBlocksRequest {
+    from_height: i32,
+    /// Inclusive; a request covers at most 1000 heights.
+    to_height: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct BlockReindexResponse {
+    item: BlockReindex,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct ProvenanceResponse {
+    items: Vec<crate::modules::data::ProvenanceRange>,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct RuntimeMetricsResponse {
+    item: RuntimeMetrics,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct EventsResponse {
+    items: Vec<TxEvent>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -97,6 +512,81 @@ struct BalanceHistoryQuery {
     limit: Option<i64>,
 }
 
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct ScriptSearchQuery {
+    /// Hex bytes matched as a prefix; `??` matches any byte, a trailing `$` requires a full match.
+    pattern: String,
+    /// Exact `script_type` as reported by the node, e.g. `nulldata` or `witness_v1_taproot`.
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    script_type: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct TopBalancesQuery {
+    /// Number of addresses, 1..=1000; 100 when omitted.
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct FeeEstimateQuery {
+    /// Confirmation target in blocks, 1..=1008; 6 when omitted.
+    target_blocks: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+#[derive(ToSchema)]
+struct FeeEstimateResponse {
+    item: FeeEstimate,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct StatsQuery {
+    /// First UTC day, `YYYY-MM-DD`, inclusive.
+    from: Option<String>,
+    /// Last UTC day, `YYYY-MM-DD`, inclusive.
+    to: Option<String>,
+    /// `day` (default) or `block`.
+    granularity: Option<String>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct SupplyQuery {
+    /// Latest blocks to return, 0..=1000; 10 when omitted.
+    blocks: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct EventsQuery {
+    /// Comma-separated subset of `seen,confirmed,finalized`; all levels when omitted.
+    levels: Option<String>,
+    address: Option<String>,
+    txid: Option<String>,
+    /// Returns events with a greater `id`; pass the last received `id` to poll for new events.
+    after_id: Option<i64>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct EventsStreamQuery {
+    /// Comma-separated subset of `job_status,block_connected,reorg`; all kinds when omitted.
+    kinds: Option<String>,
+    /// Resume after this event id; the `Last-Event-ID` header takes precedence. Without
+    /// either the stream starts with events recorded after the connection.
+    last_event_id: Option<i64>,
+}
+
 #[derive(Debug, Deserialize)]
 #[derive(IntoParams)]
 struct TransactionsQuery {
@@ -118,6 +608,62 @@ struct MempoolQuery {
     limit: Option<i64>,
 }
 
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct JobsListQuery {
+    /// `created`, `running`, `paused`, `failed` or `completed`.
+    status: Option<String>,
+    mode: Option<String>,
+    /// `job_id` (default), `updated_at` or `progress_height`; prefix with `-` for descending order.
+    sort: Option<String>,
+    offset: Option<i64>,
+    /// 1..=1000, default 100.
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct JobErrorsQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct JobDiscrepanciesQuery {
+    /// `missing_block`, `hash_mismatch`, `tx_count_mismatch`, `missing_prevout` or `balance_mismatch`.
+    kind: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct JobDryRunQuery {
+    /// Blocks to fetch, between 1 and 100; 10 by default.
+    blocks: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct UptimeQuery {
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct ProvenanceQuery {
+    /// Only blocks persisted without this stage: `chain`, `address_index` or `fees`.
+    missing_stage: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[derive(IntoParams)]
+struct CpuProfileQuery {
+    /// Sampling window, 1 to 60 seconds; 10 by default.
+    seconds: Option<u64>,
+    /// Samples per second, 1 to 1000; 99 by default.
+    frequency: Option<u32>,
+}
+
 #[derive(Debug, Deserialize)]
 #[derive(IntoParams)]
 struct BlocksQuery {
@@ -136,10 +682,24 @@ struct BlocksQuery {
 #[openapi(
     paths(
         health,
+        health_live,
+        health_ready,
+        get_status,
         metrics,
+        openapi_json,
+        list_errors,
         list_jobs,
         create_job,
         get_job,
+        get_job_eta,
+        estimate_job,
+        dry_run_job,
+        get_job_descriptors,
+        get_job_errors,
+        get_job_discrepancies,
+        delete_job,
+        add_job_addresses,
+        remove_job_address,
         start_job,
         stop_job,
         pause_job,
@@ -148,27 +708,89 @@ struct BlocksQuery {
         list_nodes,
         create_node,
         get_node_health,
+        get_node_info,
         get_balance,
         get_balance_history,
         get_utxos,
+        get_rune_balances,
         list_transactions,
         list_mempool_transactions,
-        list_blocks
+        list_blocks,
+        list_block_forks,
+        get_raw_block,
+        get_block_filter,
+        list_tx_replacements,
+        list_top_balances,
+        search_scripts,
+        estimate_fees,
+        get_stats,
+        get_supply,
+        list_events,
+        stream_events,
+        broadcast_transaction,
+        scan_utxo_set,
+        get_uptime,
+        reload_config,
+        reload_credentials,
+        start_drain,
+        stop_drain,
+        get_log_level,
+        set_log_level,
+        reset_log_level,
+        get_replication,
+        prune_history,
+        get_provenance,
+        reindex_block,
+        reindex_blocks,
+        get_runtime_metrics,
+        capture_cpu_profile
     ),
     components(
         schemas(
             HealthResponse,
+            ReadinessResponse,
+            Readiness,
+            StatusResponse,
+            SyncStatus,
+            AlertStatus,
             ApiError,
+            ApiErrorCode,
+            ErrorCatalogItem,
+            ErrorCatalogResponse,
             JobsListResponse,
             JobDetailsResponse,
             CreateJobRequest,
+            crate::modules::config::JobRetryPolicy,
+            crate::modules::config::JobExportConfig,
+            AddJobAddressesRequest,
+            JobActionRequest,
             NodesListResponse,
             NodeDetailsResponse,
             CreateNodeRequest,
             JobSummary,
             JobDetails,
+            JobEtaResponse,
+            JobEta,
+            JobEstimateResponse,
+            JobEstimate,
+            JobDryRunResponse,
+            JobDryRun,
+            JobDryRunBlock,
+            JobDescriptorsResponse,
+            JobDescriptors,
+            JobDescriptor,
+            DescriptorImport,
+            JobErrorsResponse,
+            JobErrorItem,
+            JobDiscrepanciesResponse,
+            DiscrepancyItem,
             NodeSummary,
             NodeHealthDetails,
+            NodeInfoResponse,
+            NodeInfo,
+            NodeBlockchainInfo,
+            NodeNetworkInfo,
+            NodeMempoolInfo,
             crate::modules::data::Pagination,
             crate::modules::data::BalanceResponse,
             crate::modules::data::BalanceAsOf,
@@ -176,11 +798,71 @@ struct BlocksQuery {
             crate::modules::data::BalanceHistoryPage,
             crate::modules::data::UtxoItem,
             crate::modules::data::UtxosResponse,
+            crate::modules::data::RuneBalanceItem,
+            crate::modules::data::RuneBalancesResponse,
             crate::modules::data::TransactionIo,
             crate::modules::data::TransactionItem,
             crate::modules::data::TransactionsPage,
             crate::modules::data::BlockItem,
-            crate::modules::data::BlocksPage
+            crate::modules::data::BlocksPage,
+            BlockForksResponse,
+            crate::modules::data::ForkBlockItem,
+            RawBlockResponse,
+            crate::modules::data::RawBlockItem,
+            BlockFilterResponse,
+            crate::modules::data::BlockFilterItem,
+            TxReplacementsResponse,
+            crate::modules::data::TxReplacementItem,
+            crate::modules::data::TopBalancesPage,
+            crate::modules::data::TopBalanceItem,
+            crate::modules::data::ScriptMatchItem,
+            crate::modules::data::ScriptSearchPage,
+            FeeEstimateResponse,
+            FeeEstimate,
+            FeeRateSample,
+            FeeRatePercentiles,
+            StatsPage,
+            StatsItem,
+            SupplyResponse,
+            UtxoAgeBucket,
+            BlockSupplyItem,
+            EventsResponse,
+            TxEvent,
+            StreamEvent,
+            BroadcastRequest,
+            BroadcastResponse,
+            BroadcastResult,
+            ScanRequest,
+            ScanResponse,
+            ScanResult,
+            ScanUtxo,
+            UptimeResponse,
+            UptimeReport,
+            ProcessEvent,
+            ReloadResponse,
+            ReloadReport,
+            CredentialsReloadResponse,
+            CredentialsReport,
+            CertificateStatus,
+            DrainResponse,
+            DrainStatus,
+            LogLevelResponse,
+            LogLevelRequest,
+            LogFilterStatus,
+            ReplicationResponse,
+            ReplicationStatus,
+            ReplicationSlotStatus,
+            PruneResponse,
+            PruneReport,
+            ProvenanceResponse,
+            ReindexBlocksRequest,
+            BlockReindexResponse,
+            BlockReindex,
+            crate::modules::data::ProvenanceRange,
+            RuntimeMetricsResponse,
+            RuntimeMetrics,
+            WorkerMetrics,
+            BlockingPoolMetrics
         )
     ),
     modifiers(&ApiSecurityAddon),
@@ -188,7 +870,11 @@ struct BlocksQuery {
         (name = "system", description = "Service health and metrics"),
         (name = "jobs", description = "Indexer jobs management"),
         (name = "nodes", description = "Bitcoin RPC node health"),
-        (name = "data", description = "Indexed blockchain data queries")
+        (name = "data", description = "Indexed blockchain data queries"),
+        (name = "events", description = "Confirmation events of transactions touching job addresses"),
+        (name = "broadcast", description = "Raw transaction relay through the node of the network"),
+        (name = "scan", description = "UTXO set scans of output descriptors on the node of the network"),
+        (name = "admin", description = "Operational history and runtime configuration of indexer instances")
     )
 )]
 struct ApiDoc;
@@ -197,7 +883,7 @@ struct ApiSecurityAddon;
 
 impl utoipa::Modify for ApiSecurityAddon {
     fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
-        use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+        use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
 
         let components = openapi.components.get_or_insert_with(Default::default);
         components.add_security_scheme(
@@ -209,48 +895,452 @@ impl utoipa::Modify for ApiSecurityAddon {
                     .build(),
             ),
         );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-API-Key",
+                "Static API key configured in server.auth.api_keys",
+            ))),
+        );
+        components.add_security_scheme(
+            "hmac_signature",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "X-Signature",
+                "Hex HMAC-SHA256 over \"{timestamp}\\n{METHOD}\\n{path_and_query}\\n{body}\"; \
+                 send together with X-Signature-Key-Id and X-Signature-Timestamp (unix seconds)",
+            ))),
+        );
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .description(Some(
+                        "Static API token from server.auth.tokens or HS256 JWT issued by the configured identity provider",
+                    ))
+                    .build(),
+            ),
+        );
     }
 }
 
-pub fn router(auth: ApiAuth, state: AppState) -> Router {
-    let openapi = ApiDoc::openapi();
+impl TrustedProxies {
+    pub fn new(networks: Vec<IpNet>) -> Self {
+        Self {
+            networks: Arc::new(networks),
+            unix_peers: false,
+        }
+    }
 
-    Router::new()
-        .route("/health", get(health))
-        .route("/metrics", get(metrics))
-        .route("/v1/jobs", get(list_jobs).post(create_job))
-        .route("/v1/jobs/{job_id}", get(get_job))
-        .route("/v1/jobs/{job_id}/start", axum::routing::post(start_job))
-        .route("/v1/jobs/{job_id}/stop", axum::routing::post(stop_job))
-        .route("/v1/jobs/{job_id}/pause", axum::routing::post(pause_job))
-        .route("/v1/jobs/{job_id}/resume", axum::routing::post(resume_job))
-        .route("/v1/jobs/{job_id}/retry", axum::routing::post(retry_job))
-        .route("/v1/nodes", get(list_nodes).post(create_node))
-        .route("/v1/nodes/{node_id}/health", get(get_node_health))
-        .route("/v1/data/addresses/{address}/balance", get(get_balance))
-        .route("/v1/data/addresses/{address}/balance/history", get(get_balance_history))
-        .route("/v1/data/addresses/{address}/utxos", get(get_utxos))
-        .route("/v1/data/transactions", get(list_transactions))
-        .route("/v1/data/transactions/mempool", get(list_mempool_transactions))
-        .route("/v1/data/blocks", get(list_blocks))
-        .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi))
-        .with_state(state)
-        .layer(from_fn_with_state(auth, basic_auth_middleware))
-}
+    pub fn with_unix_peers(mut self, trusted: bool) -> Self {
+        self.unix_peers = trusted;
+        self
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(&ip))
+    }
+
+    /// Walks the forwarded chain from the nearest hop while the current hop is a
+    /// trusted proxy; the first address outside the trusted networks is the client.
+    /// Headers of untrusted peers are ignored, so clients cannot spoof their address.
+    /// A hop that cannot be parsed (`unknown`, obfuscated names) ends the walk at the
+    /// proxy that reported it.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = peer.to_canonical();
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        self.forwarded_client_ip(headers).unwrap_or(peer)
+    }
+
+    /// Client of a request on the unix socket of `server.bind`. Only with
+    /// `server.socket_trust_forwarded` is the client taken from the forwarded headers of the
+    /// reverse proxy on the other end; otherwise, and without a parseable hop, it is
+    /// [`UNIX_PEER_IP`], so local processes cannot pick a fresh rate limit bucket per request.
+    pub fn unix_client_ip(&self, headers: &HeaderMap) -> IpAddr {
+        if !self.unix_peers {
+            return UNIX_PEER_IP;
+        }
+
+        self.forwarded_client_ip(headers).unwrap_or(UNIX_PEER_IP)
+    }
+
+    /// Client reported by a peer that is trusted without an address; the chain is walked as
+    /// in [`Self::client_ip`]. `None` when the peer sent no parseable hop.
+    fn forwarded_client_ip(&self, headers: &HeaderMap) -> Option<IpAddr> {
+        let mut client = None;
+        for hop in forwarded_hops(headers).into_iter().rev() {
+            let Some(ip) = hop else {
+                break;
+            };
+            client = Some(ip);
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+
+        client
+    }
+}
+
+/// Hops listed in `Forwarded` (RFC 7239) or, without it, in `X-Forwarded-For`,
+/// from the original client to the nearest proxy.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    let forwarded: Vec<Option<IpAddr>> = headers
+        .get_all(axum::http::header::FORWARDED)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|element| {
+            element.split(';').find_map(|pair| {
+                let (name, value) = pair.trim().split_once('=')?;
+                name.trim().eq_ignore_ascii_case("for").then(|| parse_forwarded_node(value))
+            })?
+        })
+        .collect();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+
+    headers
+        .get_all(X_FORWARDED_FOR_HEADER)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(parse_forwarded_node)
+        .collect()
+}
+
+/// `192.0.2.60`, `"192.0.2.60:4711"`, `"[2001:db8::17]:4711"` or a bare IPv6 address.
+fn parse_forwarded_node(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        return Some(ip.to_canonical());
+    }
+    if let Some(bracketed) = value.strip_prefix('[') {
+        let (ip, _) = bracketed.split_once(']')?;
+        return ip.parse::<Ipv6Addr>().ok().map(|ip| IpAddr::V6(ip).to_canonical());
+    }
+
+    value.parse::<SocketAddr>().ok().map(|addr| addr.ip().to_canonical())
+}
+
+/// Optional parts of [`router`].
+#[derive(Debug, Clone, Copy)]
+pub struct RouterOptions {
+    /// Swagger UI at `/docs` on top of the OpenAPI document served at `/v1/openapi.json`
+    /// (and at `/openapi.json` for older clients).
+    pub swagger_ui: bool,
+    /// gzip/Brotli response bodies negotiated through `Accept-Encoding`.
+    pub compression: bool,
+    /// The routes of [`admin_router`]; without them they are left to the `server.admin` listener.
+    pub admin: bool,
+}
+
+/// Application routes with the parts enabled in `options`.
+pub fn router(
+    auth: AuthChain,
+    proxies: TrustedProxies,
+    limiter: RateLimiter,
+    state: AppState,
+    options: RouterOptions,
+) -> Router {
+    let openapi = ApiDoc::openapi();
+    let drain = state.status.drain().clone();
+    let networks = state.networks.iter().fold(Router::new(), |router, network| {
+        router.nest(
+            &format!("/v1/{}", network.name),
+            network_routes(network.jobs.clone()).with_state(state.for_network(network)),
+        )
+    });
+
+    let docs = Router::new()
+        .route("/v1/openapi.json", get(openapi_json))
+        .route("/openapi.json", get(openapi_json))
+        .with_state(Arc::new(openapi));
+    let docs = if options.swagger_ui {
+        docs.merge(SwaggerUi::new("/docs").config(utoipa_swagger_ui::Config::from("/v1/openapi.json")))
+    } else {
+        docs
+    };
+
+    let app = Router::new()
+        .route("/v1/status", get(get_status))
+        .route("/v1/errors", get(list_errors))
+        .nest("/v1", network_routes(state.jobs.clone()))
+        .merge(networks)
+        .route("/v1/nodes", get(list_nodes).post(create_node))
+        .route("/v1/nodes/{node_id}/health", get(get_node_health));
+    let (app, probes) = if options.admin {
+        (app.merge(admin_routes()), probe_routes().with_state(state.clone()))
+    } else {
+        (app, Router::new())
+    };
+    let app = app.with_state(state).merge(docs);
+    let app = if options.compression {
+        app.layer(CompressionLayer::new())
+    } else {
+        app
+    };
+
+    with_middleware(app, probes, auth, proxies, limiter, drain)
+}
+
+/// Middleware of [`router`], outermost last: the trace layer resolves the client address
+/// before the per-IP limit, which runs before auth; the per-credential limit runs after it.
+/// `probes` are merged outside auth, so orchestrators can call them without credentials.
+fn with_middleware(
+    app: Router,
+    probes: Router,
+    auth: AuthChain,
+    proxies: TrustedProxies,
+    limiter: RateLimiter,
+    drain: DrainState,
+) -> Router {
+    // Merged into `probes`, so unmatched paths keep the fallback behind auth.
+    probes
+        .merge(
+            app.layer(from_fn_with_state(limiter.clone(), credential_rate_limit_middleware))
+                .layer(from_fn_with_state(auth, auth_middleware)),
+        )
+        .layer(from_fn_with_state(limiter, ip_rate_limit_middleware))
+        .layer(from_fn_with_state(drain, drain_middleware))
+        .layer(from_fn_with_state(proxies, trace_middleware))
+}
+
+/// Liveness and readiness probes, served without authentication next to [`admin_routes`].
+fn probe_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(health))
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
+}
+
+/// Metrics and instance management; served by [`router`] unless `server.admin` moves
+/// them to a listener of their own.
+fn admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/v1/admin/uptime", get(get_uptime))
+        .route("/v1/admin/reload", axum::routing::post(reload_config))
+        .route("/v1/admin/credentials/reload", axum::routing::post(reload_credentials))
+        .route("/v1/admin/drain", axum::routing::post(start_drain).delete(stop_drain))
+        .route("/v1/admin/log-level", get(get_log_level).put(set_log_level).delete(reset_log_level))
+        .route("/v1/admin/replication", get(get_replication))
+        .route("/v1/admin/prune", axum::routing::post(prune_history))
+        .route("/v1/admin/provenance", get(get_provenance))
+        .route("/v1/admin/blocks/reindex", axum::routing::post(reindex_blocks))
+        .route("/v1/admin/blocks/{hash}/reindex", axum::routing::post(reindex_block))
+        .route("/v1/admin/runtime", get(get_runtime_metrics))
+        .route("/v1/admin/profile/cpu", get(capture_cpu_profile))
+}
+
+/// Router of the `server.admin` listener: the probes and the admin routes behind their
+/// own auth chain, with the drain and tracing layers of [`router`] but without rate limits.
+pub fn admin_router(auth: AuthChain, proxies: TrustedProxies, state: AppState) -> Router {
+    let drain = state.status.drain().clone();
+    probe_routes()
+        .with_state(state.clone())
+        .merge(admin_routes().with_state(state).layer(from_fn_with_state(auth, auth_middleware)))
+        .layer(from_fn_with_state(drain, drain_middleware))
+        .layer(from_fn_with_state(proxies, trace_middleware))
+}
+
+/// Routes backed by the services of one network, mounted at `/v1` for the primary
+/// network and at `/v1/{name}` for every entry of [`AppState::networks`]. Idempotency
+/// keys of job actions are kept by `jobs`, the jobs service of the network; data routes
+/// answer conditional requests through `ETag`.
+fn network_routes(jobs: JobsService) -> Router<AppState> {
+    let job_actions = Router::new()
+        .route("/jobs/{job_id}/start", axum::routing::post(start_job))
+        .route("/jobs/{job_id}/stop", axum::routing::post(stop_job))
+        .route("/jobs/{job_id}/pause", axum::routing::post(pause_job))
+        .route("/jobs/{job_id}/resume", axum::routing::post(resume_job))
+        .route("/jobs/{job_id}/retry", axum::routing::post(retry_job))
+        .route_layer(from_fn_with_state(jobs, idempotency_middleware));
+
+    let data = Router::new()
+        .route("/data/addresses/{address}/balance", get(get_balance))
+        .route("/data/addresses/{address}/balance/history", get(get_balance_history))
+        .route("/data/addresses/{address}/utxos", get(get_utxos))
+        .route("/data/addresses/{address}/runes", get(get_rune_balances))
+        .route("/data/transactions", get(list_transactions))
+        .route("/data/transactions/mempool", get(list_mempool_transactions))
+        .route("/data/blocks", get(list_blocks))
+        .route("/blocks/{height}/forks", get(list_block_forks))
+        .route("/blocks/{hash}/raw", get(get_raw_block))
+        .route("/blocks/{hash}/filter", get(get_block_filter))
+        .route("/txs/{txid}/replacements", get(list_tx_replacements))
+        .route("/addresses/top", get(list_top_balances))
+        .route("/scripts/search", get(search_scripts))
+        .route("/fees/estimate", get(estimate_fees))
+        .route("/stats", get(get_stats))
+        .route("/stats/supply", get(get_supply))
+        .route("/events", get(events))
+        .route_layer(from_fn(etag_middleware));
+
+    Router::new()
+        .route("/jobs", get(list_jobs).post(create_job))
+        .route("/jobs/{job_id}", get(get_job).delete(delete_job))
+        .route("/jobs/{job_id}/eta", get(get_job_eta))
+        .route("/jobs/{job_id}/estimate", axum::routing::post(estimate_job))
+        .route("/jobs/{job_id}/dry-run", axum::routing::post(dry_run_job))
+        .route("/jobs/{job_id}/descriptors", get(get_job_descriptors))
+        .route("/jobs/{job_id}/errors", get(get_job_errors))
+        .route("/jobs/{job_id}/discrepancies", get(get_job_discrepancies))
+        .route("/jobs/{job_id}/addresses", axum::routing::post(add_job_addresses))
+        .route("/jobs/{job_id}/addresses/{address}", axum::routing::delete(remove_job_address))
+        // Kept for clients that cannot set `Accept` on `GET /v1/events`.
+        .route("/events/stream", get(stream_events))
+        .route("/txs", axum::routing::post(broadcast_transaction))
+        .route("/scan", axum::routing::post(scan_utxo_set))
+        .route("/node/info", get(get_node_info))
+        .merge(job_actions)
+        .merge(data)
+}
+
+impl StartupGate {
+    pub fn new(drain: DrainState) -> Self {
+        Self {
+            drain,
+            ..Self::default()
+        }
+    }
+
+    /// Last storage failure, reported in readiness until bootstrap succeeds.
+    pub fn record_storage_error(&self, error: String) {
+        *self.storage_error.write().unwrap_or_else(|err| err.into_inner()) = Some(error);
+    }
+
+    pub fn open(&self, app: Router) {
+        let _ = self.app.set(app);
+    }
+
+    fn storage_error(&self) -> Option<String> {
+        self.storage_error.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+}
+
+/// Router of a degraded start: serves the startup routes behind the same auth, drain and
+/// tracing layers as [`router`], with the probes outside auth, then forwards everything
+/// to the router passed to [`StartupGate::open`]. Without `probes` only the fallback is
+/// served, as on the public address when `server.admin` serves the health routes.
+pub fn startup_router(auth: AuthChain, proxies: TrustedProxies, gate: StartupGate, probes: bool) -> Router {
+    let starting = Router::new();
+    let starting = if probes {
+        starting
+            .route("/health", get(health))
+            .route("/health/live", get(health_live))
+            .route("/health/ready", get(startup_ready))
+    } else {
+        starting
+    };
+    let unavailable = Router::new()
+        .fallback(startup_unavailable)
+        .with_state(gate.clone())
+        .layer(from_fn_with_state(auth, auth_middleware));
+    let starting = starting
+        .with_state(gate.clone())
+        .merge(unavailable)
+        .layer(from_fn_with_state(gate.drain.clone(), drain_middleware))
+        .layer(from_fn_with_state(proxies, trace_middleware));
+
+    Router::new().fallback(move |request: Request<Body>| {
+        let target = gate.app.get().cloned().unwrap_or_else(|| starting.clone());
+        async move { target.oneshot(request).await.unwrap_or_else(|never| match never {}) }
+    })
+}
+
+async fn startup_ready(State(gate): State<StartupGate>) -> (StatusCode, Json<ReadinessResponse>) {
+    let item = Readiness::starting(gate.drain.is_draining(), gate.storage_error());
+    (StatusCode::SERVICE_UNAVAILABLE, Json(ReadinessResponse { item }))
+}
+
+async fn startup_unavailable(State(gate): State<StartupGate>) -> ApiResponse {
+    ApiResponse::with_details(
+        ApiErrorCode::Starting,
+        "Instance is starting",
+        serde_json::json!({ "database_error": gate.storage_error() }),
+    )
+}
 
 #[utoipa::path(
     get,
     path = "/health",
     tag = "system",
+    responses(
+        (status = 200, description = "Service health status; alias of /health/live", body = HealthResponse)
+    )
+)]
+async fn health() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "system",
+    responses(
+        (status = 200, description = "Liveness probe: the process is up and serving requests", body = HealthResponse)
+    )
+)]
+async fn health_live() -> Json<HealthResponse> {
+    Json(HealthResponse { status: "ok" })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "system",
+    responses(
+        (status = 200, description = "Readiness probe: database and RPC reachable, migrations applied", body = ReadinessResponse),
+        (status = 503, description = "Instance must not receive traffic, or is `starting` after a degraded start; failed checks are in the body", body = ReadinessResponse)
+    )
+)]
+async fn health_ready(State(state): State<AppState>) -> (StatusCode, Json<ReadinessResponse>) {
+    let item = state.status.readiness().await;
+    let status = if item.is_ready() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(ReadinessResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/status",
+    tag = "system",
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Service health status", body = HealthResponse)
+        (status = 200, description = "Node tip, indexed height, sync lag and dependency reachability", body = StatusResponse)
     )
 )]
-async fn health() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "ok" })
+async fn get_status(State(state): State<AppState>) -> Json<StatusResponse> {
+    Json(StatusResponse {
+        item: state.status.report().await,
+    })
+}
+
+/// OpenAPI 3.1 document of this API, the input of Swagger UI and client SDK generators.
+#[utoipa::path(
+    get,
+    path = "/v1/openapi.json",
+    tag = "system",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "OpenAPI document", content_type = "application/json", body = Object)
+    )
+)]
+async fn openapi_json(State(openapi): State<Arc<utoipa::openapi::OpenApi>>) -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi.as_ref().clone())
 }
 
 #[utoipa::path(
@@ -269,7 +1359,7 @@ async fn metrics(State(state): State<AppState>) -> Result<Response, ApiResponse>
         .metrics
         .render(state.jobs.pool())
         .await
-        .map_err(|_| ApiResponse::new(StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", "Storage failure"))?;
+        .map_err(|_| ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"))?;
 
     Ok((
         StatusCode::OK,
@@ -279,32 +1369,75 @@ async fn metrics(State(state): State<AppState>) -> Result<Response, ApiResponse>
         .into_response())
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/errors",
+    tag = "system",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Catalog of error codes the API can return", body = ErrorCatalogResponse),
+        (status = 401, description = "Unauthorized", body = ApiError)
+    )
+)]
+async fn list_errors() -> Json<ErrorCatalogResponse> {
+    let items = ApiErrorCode::ALL
+        .into_iter()
+        .map(|code| ErrorCatalogItem {
+            code,
+            http_status: code.status().as_u16(),
+            description: code.description(),
+        })
+        .collect();
+
+    Json(ErrorCatalogResponse { items })
+}
+
 #[utoipa::path(
     get,
     path = "/v1/jobs",
     tag = "jobs",
+    params(JobsListQuery),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Configured jobs with current status", body = JobsListResponse),
+        (status = 200, description = "Page of jobs with current status and the total matching count", body = JobsListResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn list_jobs(State(state): State<AppState>) -> Result<Json<JobsListResponse>, ApiResponse> {
-    let tip_height = state.nodes.tip_height().await.map_err(ApiResponse::from)?;
-    let items = state
+async fn list_jobs(
+    Query(query): Query<JobsListQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<JobsListResponse>, ApiResponse> {
+    let page = state
         .jobs
-        .list()
+        .list_filtered(&JobsListFilter {
+            status: query.status,
+            mode: query.mode,
+            sort: query.sort,
+            offset: query.offset,
+            limit: query.limit,
+        })
         .await
-        .map_err(ApiResponse::from)?
+        .map_err(ApiResponse::from)?;
+    let tip_height = state.nodes.tip_height().await.map_err(ApiResponse::from)?;
+    let items = page
+        .items
         .into_iter()
         .map(|mut item| {
             item.tip_height = tip_height;
             item
         })
         .collect();
-    Ok(Json(JobsListResponse { items }))
+    Ok(Json(JobsListResponse {
+        items,
+        offset: page.offset,
+        limit: page.limit,
+        total: page.total,
+    }))
 }
 
 #[utoipa::path(
@@ -356,72 +1489,89 @@ async fn get_job(
 
 #[utoipa::path(
     get,
-    path = "/v1/nodes",
-    tag = "nodes",
+    path = "/v1/jobs/{job_id}/eta",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier")
+    ),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Configured nodes", body = NodesListResponse),
+        (status = 200, description = "Estimated time to reach the target height", body = JobEtaResponse),
+        (status = 404, description = "Job not found", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn list_nodes(State(state): State<AppState>) -> Result<Json<NodesListResponse>, ApiResponse> {
-    let items = state.nodes.list().await.map_err(ApiResponse::from)?;
-    Ok(Json(NodesListResponse { items }))
+async fn get_job_eta(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<JobEtaResponse>, ApiResponse> {
+    let item = state.jobs.eta(&job_id).await.map_err(ApiResponse::from)?;
+    Ok(Json(JobEtaResponse { item }))
 }
 
 #[utoipa::path(
     post,
-    path = "/v1/nodes",
-    tag = "nodes",
-    request_body = CreateNodeRequest,
+    path = "/v1/jobs/{job_id}/estimate",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier")
+    ),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 201, description = "Created node", body = NodeDetailsResponse),
-        (status = 409, description = "Node already exists", body = ApiError),
-        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 200, description = "Dry-run estimate of blocks, transactions, storage and duration; the job is not started", body = JobEstimateResponse),
+        (status = 404, description = "Job not found", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn create_node(
+async fn estimate_job(
+    Path(job_id): Path<String>,
     State(state): State<AppState>,
-    Json(request): Json<CreateNodeRequest>,
-) -> Result<(StatusCode, Json<NodeDetailsResponse>), ApiResponse> {
-    let item = state.nodes.create(request).await.map_err(ApiResponse::from)?;
-    Ok((StatusCode::CREATED, Json(NodeDetailsResponse { item })))
+) -> Result<Json<JobEstimateResponse>, ApiResponse> {
+    let tip_height = state.nodes.tip_height().await.map_err(ApiResponse::from)?;
+    let item = state.jobs.estimate(&job_id, tip_height).await.map_err(ApiResponse::from)?;
+    Ok(Json(JobEstimateResponse { item }))
 }
 
 #[utoipa::path(
-    get,
-    path = "/v1/nodes/{node_id}/health",
-    tag = "nodes",
+    post,
+    path = "/v1/jobs/{job_id}/dry-run",
+    tag = "jobs",
     params(
-        ("node_id" = String, Path, description = "Node identifier")
+        ("job_id" = String, Path, description = "Job identifier"),
+        JobDryRunQuery
     ),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Node health details", body = NodeDetailsResponse),
-        (status = 404, description = "Node not found", body = ApiError),
-        (status = 503, description = "Node is unavailable", body = ApiError),
-        (status = 500, description = "Storage failure", body = ApiError)
+        (status = 200, description = "Next blocks of the job fetched and decoded with per-block stats and errors; nothing is written and the job is not started", body = JobDryRunResponse),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError),
+        (status = 503, description = "No node is configured", body = ApiError)
     )
 )]
-async fn get_node_health(
-    Path(node_id): Path<String>,
+async fn dry_run_job(
+    Path(job_id): Path<String>,
+    Query(query): Query<JobDryRunQuery>,
     State(state): State<AppState>,
-) -> Result<Json<NodeDetailsResponse>, ApiResponse> {
-    let item = state.nodes.get(&node_id).await.map_err(ApiResponse::from)?;
-    Ok(Json(NodeDetailsResponse { item }))
+) -> Result<Json<JobDryRunResponse>, ApiResponse> {
+    let tip_height = state.nodes.tip_height().await.map_err(ApiResponse::from)?;
+    let item = state
+        .jobs
+        .dry_run(&job_id, query.blocks, tip_height)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDryRunResponse { item }))
 }
 
 #[utoipa::path(
-    post,
-    path = "/v1/jobs/{job_id}/start",
+    get,
+    path = "/v1/jobs/{job_id}/descriptors",
     tag = "jobs",
     params(
         ("job_id" = String, Path, description = "Job identifier")
@@ -430,73 +1580,85 @@ async fn get_node_health(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Started job", body = JobDetailsResponse),
+        (status = 200, description = "Bitcoin Core output descriptors for the job's watched addresses", body = JobDescriptorsResponse),
         (status = 404, description = "Job not found", body = ApiError),
-        (status = 409, description = "Invalid state transition", body = ApiError),
+        (status = 422, description = "Job is not an address_list job", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn start_job(
+async fn get_job_descriptors(
     Path(job_id): Path<String>,
     State(state): State<AppState>,
-) -> Result<Json<JobDetailsResponse>, ApiResponse> {
-    let item = state.jobs.start(&job_id).await.map_err(ApiResponse::from)?;
-    Ok(Json(JobDetailsResponse { item }))
+) -> Result<Json<JobDescriptorsResponse>, ApiResponse> {
+    let item = state.jobs.descriptors(&job_id).await.map_err(ApiResponse::from)?;
+    Ok(Json(JobDescriptorsResponse { item }))
 }
 
 #[utoipa::path(
-    post,
-    path = "/v1/jobs/{job_id}/stop",
+    get,
+    path = "/v1/jobs/{job_id}/errors",
     tag = "jobs",
     params(
-        ("job_id" = String, Path, description = "Job identifier")
+        ("job_id" = String, Path, description = "Job identifier"),
+        JobErrorsQuery
     ),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Stopped job", body = JobDetailsResponse),
+        (status = 200, description = "Job error history, newest first", body = JobErrorsResponse),
         (status = 404, description = "Job not found", body = ApiError),
-        (status = 409, description = "Invalid state transition", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn stop_job(
+async fn get_job_errors(
     Path(job_id): Path<String>,
+    Query(query): Query<JobErrorsQuery>,
     State(state): State<AppState>,
-) -> Result<Json<JobDetailsResponse>, ApiResponse> {
-    let item = state.jobs.stop(&job_id).await.map_err(ApiResponse::from)?;
-    Ok(Json(JobDetailsResponse { item }))
+) -> Result<Json<JobErrorsResponse>, ApiResponse> {
+    let items = state
+        .jobs
+        .errors(&job_id, query.limit)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobErrorsResponse { items }))
 }
 
 #[utoipa::path(
-    post,
-    path = "/v1/jobs/{job_id}/pause",
+    get,
+    path = "/v1/jobs/{job_id}/discrepancies",
     tag = "jobs",
     params(
-        ("job_id" = String, Path, description = "Job identifier")
+        ("job_id" = String, Path, description = "Job identifier"),
+        JobDiscrepanciesQuery
     ),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Paused job", body = JobDetailsResponse),
+        (status = 200, description = "Discrepancies found by a verify job, by height; the balance check last", body = JobDiscrepanciesResponse),
         (status = 404, description = "Job not found", body = ApiError),
-        (status = 409, description = "Invalid state transition", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn pause_job(
+async fn get_job_discrepancies(
     Path(job_id): Path<String>,
+    Query(query): Query<JobDiscrepanciesQuery>,
     State(state): State<AppState>,
-) -> Result<Json<JobDetailsResponse>, ApiResponse> {
-    let item = state.jobs.pause(&job_id).await.map_err(ApiResponse::from)?;
-    Ok(Json(JobDetailsResponse { item }))
+) -> Result<Json<JobDiscrepanciesResponse>, ApiResponse> {
+    let items = state
+        .jobs
+        .discrepancies(&job_id, query.kind.as_deref(), query.limit)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDiscrepanciesResponse { items }))
 }
 
 #[utoipa::path(
-    post,
-    path = "/v1/jobs/{job_id}/resume",
+    delete,
+    path = "/v1/jobs/{job_id}",
     tag = "jobs",
     params(
         ("job_id" = String, Path, description = "Job identifier")
@@ -505,65 +1667,353 @@ async fn pause_job(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Resumed job", body = JobDetailsResponse),
+        (status = 204, description = "Job deleted"),
         (status = 404, description = "Job not found", body = ApiError),
-        (status = 409, description = "Invalid state transition", body = ApiError),
+        (status = 409, description = "Running job must be stopped before deletion", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn resume_job(
+async fn delete_job(
     Path(job_id): Path<String>,
     State(state): State<AppState>,
-) -> Result<Json<JobDetailsResponse>, ApiResponse> {
-    let item = state.jobs.resume(&job_id).await.map_err(ApiResponse::from)?;
-    Ok(Json(JobDetailsResponse { item }))
+) -> Result<StatusCode, ApiResponse> {
+    state.jobs.delete(&job_id).await.map_err(ApiResponse::from)?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
 #[utoipa::path(
     post,
-    path = "/v1/jobs/{job_id}/retry",
+    path = "/v1/jobs/{job_id}/addresses",
     tag = "jobs",
     params(
         ("job_id" = String, Path, description = "Job identifier")
     ),
+    request_body = AddJobAddressesRequest,
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Retried job", body = JobDetailsResponse),
+        (status = 200, description = "Updated job", body = JobDetailsResponse),
         (status = 404, description = "Job not found", body = ApiError),
-        (status = 409, description = "Invalid state transition", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn retry_job(
+async fn add_job_addresses(
     Path(job_id): Path<String>,
     State(state): State<AppState>,
+    Json(request): Json<AddJobAddressesRequest>,
 ) -> Result<Json<JobDetailsResponse>, ApiResponse> {
-    let item = state.jobs.retry(&job_id).await.map_err(ApiResponse::from)?;
+    let item = state
+        .jobs
+        .add_addresses(&job_id, request)
+        .await
+        .map_err(ApiResponse::from)?;
     Ok(Json(JobDetailsResponse { item }))
 }
 
 #[utoipa::path(
-    get,
-    path = "/v1/data/addresses/{address}/balance",
-    tag = "data",
+    delete,
+    path = "/v1/jobs/{job_id}/addresses/{address}",
+    tag = "jobs",
     params(
-        ("address" = String, Path, description = "Bitcoin address"),
-        BalanceQuery
+        ("job_id" = String, Path, description = "Job identifier"),
+        ("address" = String, Path, description = "Watched address")
     ),
     security(
         ("basic_auth" = [])
     ),
     responses(
-        (status = 200, description = "Current or historical address balance", body = crate::modules::data::BalanceResponse),
-        (status = 404, description = "Address is not indexed", body = ApiError),
+        (status = 200, description = "Updated job", body = JobDetailsResponse),
+        (status = 404, description = "Job or address not found", body = ApiError),
         (status = 422, description = "Validation failed", body = ApiError),
         (status = 500, description = "Storage failure", body = ApiError)
     )
 )]
-async fn get_balance(
-    Path(address): Path<String>,
+async fn remove_job_address(
+    Path((job_id, address)): Path<(String, String)>,
+    State(state): State<AppState>,
+) -> Result<Json<JobDetailsResponse>, ApiResponse> {
+    let item = state
+        .jobs
+        .remove_address(&job_id, &address)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/nodes",
+    tag = "nodes",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Configured nodes", body = NodesListResponse),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn list_nodes(State(state): State<AppState>) -> Result<Json<NodesListResponse>, ApiResponse> {
+    let items = state.nodes.list().await.map_err(ApiResponse::from)?;
+    Ok(Json(NodesListResponse { items }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/nodes",
+    tag = "nodes",
+    request_body = CreateNodeRequest,
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Created node", body = NodeDetailsResponse),
+        (status = 409, description = "Node already exists", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn create_node(
+    State(state): State<AppState>,
+    Json(request): Json<CreateNodeRequest>,
+) -> Result<(StatusCode, Json<NodeDetailsResponse>), ApiResponse> {
+    let item = state.nodes.create(request).await.map_err(ApiResponse::from)?;
+    Ok((StatusCode::CREATED, Json(NodeDetailsResponse { item })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/nodes/{node_id}/health",
+    tag = "nodes",
+    params(
+        ("node_id" = String, Path, description = "Node identifier")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Node health details", body = NodeDetailsResponse),
+        (status = 404, description = "Node not found", body = ApiError),
+        (status = 503, description = "Node is unavailable", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_node_health(
+    Path(node_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<NodeDetailsResponse>, ApiResponse> {
+    let item = state.nodes.get(&node_id).await.map_err(ApiResponse::from)?;
+    Ok(Json(NodeDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/node/info",
+    tag = "nodes",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Chain, network and mempool state of the network's node, cached for 5 seconds", body = NodeInfoResponse),
+        (status = 503, description = "Node is unavailable", body = ApiError)
+    )
+)]
+async fn get_node_info(State(state): State<AppState>) -> Result<Json<NodeInfoResponse>, ApiResponse> {
+    let item = state.node_info.info().await.map_err(ApiResponse::from)?;
+    Ok(Json(NodeInfoResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/jobs/{job_id}/start",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a request with the same key is retried")
+    ),
+    request_body(content = Option<JobActionRequest>, description = "Optional reason kept in job_events"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Started job", body = JobDetailsResponse),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 409, description = "Invalid state transition, or the request with the same Idempotency-Key is in progress", body = ApiError),
+        (status = 422, description = "Validation failed or Idempotency-Key reused for another request", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn start_job(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+    body: Option<Json<JobActionRequest>>,
+) -> Result<Json<JobDetailsResponse>, ApiResponse> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    let item = state
+        .jobs
+        .start(&job_id, reason.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/jobs/{job_id}/stop",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a request with the same key is retried")
+    ),
+    request_body(content = Option<JobActionRequest>, description = "Optional reason kept in job_events"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Stopped job", body = JobDetailsResponse),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 409, description = "Invalid state transition, or the request with the same Idempotency-Key is in progress", body = ApiError),
+        (status = 422, description = "Validation failed or Idempotency-Key reused for another request", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn stop_job(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+    body: Option<Json<JobActionRequest>>,
+) -> Result<Json<JobDetailsResponse>, ApiResponse> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    let item = state
+        .jobs
+        .stop(&job_id, reason.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/jobs/{job_id}/pause",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a request with the same key is retried")
+    ),
+    request_body(content = Option<JobActionRequest>, description = "Optional reason kept in job_events"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Paused job", body = JobDetailsResponse),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 409, description = "Invalid state transition, or the request with the same Idempotency-Key is in progress", body = ApiError),
+        (status = 422, description = "Validation failed or Idempotency-Key reused for another request", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn pause_job(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+    body: Option<Json<JobActionRequest>>,
+) -> Result<Json<JobDetailsResponse>, ApiResponse> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    let item = state
+        .jobs
+        .pause(&job_id, reason.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/jobs/{job_id}/resume",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a request with the same key is retried")
+    ),
+    request_body(content = Option<JobActionRequest>, description = "Optional reason kept in job_events"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Resumed job", body = JobDetailsResponse),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 409, description = "Invalid state transition, or the request with the same Idempotency-Key is in progress", body = ApiError),
+        (status = 422, description = "Validation failed or Idempotency-Key reused for another request", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn resume_job(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+    body: Option<Json<JobActionRequest>>,
+) -> Result<Json<JobDetailsResponse>, ApiResponse> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    let item = state
+        .jobs
+        .resume(&job_id, reason.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/jobs/{job_id}/retry",
+    tag = "jobs",
+    params(
+        ("job_id" = String, Path, description = "Job identifier"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the stored response when a request with the same key is retried")
+    ),
+    request_body(content = Option<JobActionRequest>, description = "Optional reason kept in job_events"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Retried job", body = JobDetailsResponse),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 409, description = "Invalid state transition, or the request with the same Idempotency-Key is in progress", body = ApiError),
+        (status = 422, description = "Validation failed or Idempotency-Key reused for another request", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn retry_job(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+    body: Option<Json<JobActionRequest>>,
+) -> Result<Json<JobDetailsResponse>, ApiResponse> {
+    let reason = body.and_then(|Json(body)| body.reason);
+    let item = state
+        .jobs
+        .retry(&job_id, reason.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(JobDetailsResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/data/addresses/{address}/balance",
+    tag = "data",
+    params(
+        ("address" = String, Path, description = "Bitcoin address"),
+        BalanceQuery
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Current or historical address balance", body = crate::modules::data::BalanceResponse),
+        (status = 404, description = "Address is not indexed", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_balance(
+    Path(address): Path<String>,
     Query(query): Query<BalanceQuery>,
     State(state): State<AppState>,
 ) -> Result<Json<crate::modules::data::BalanceResponse>, ApiResponse> {
@@ -648,6 +2098,30 @@ async fn get_utxos(
     Ok(Json(item))
 }
 
+#[utoipa::path(
+    get,
+    path = "/v1/data/addresses/{address}/runes",
+    tag = "data",
+    params(
+        ("address" = String, Path, description = "Bitcoin address")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Rune balances of the unspent outputs of address", body = crate::modules::data::RuneBalancesResponse),
+        (status = 404, description = "Address is not indexed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_rune_balances(
+    Path(address): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<crate::modules::data::RuneBalancesResponse>, ApiResponse> {
+    let item = state.data.get_rune_balances(&address).await.map_err(ApiResponse::from)?;
+    Ok(Json(item))
+}
+
 #[utoipa::path(
     get,
     path = "/v1/data/transactions",
@@ -753,61 +2227,1090 @@ async fn list_blocks(
     Ok(Json(page))
 }
 
-fn parse_pagination(
-    _data: &DataService,
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{height}/forks",
+    tag = "data",
+    params(
+        ("height" = i32, Path, description = "Block height")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Every known block at the height: the canonical one and blocks orphaned by reorgs", body = BlockForksResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn list_block_forks(
+    Path(height): Path<i32>,
+    State(state): State<AppState>,
+) -> Result<Json<BlockForksResponse>, ApiResponse> {
+    let items = state.data.list_block_forks(height).await.map_err(ApiResponse::from)?;
+    Ok(Json(BlockForksResponse { height, items }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{hash}/raw",
+    tag = "data",
+    params(
+        ("hash" = String, Path, description = "Block hash")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Serialized block as hex, stored with indexer.store_raw_blocks", body = RawBlockResponse),
+        (status = 404, description = "Raw block is not stored", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_raw_block(
+    Path(hash): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<RawBlockResponse>, ApiResponse> {
+    let Some(item) = state.data.raw_block(&hash).await.map_err(ApiResponse::from)? else {
+        return Err(ApiResponse::with_details(
+            ApiErrorCode::NotFound,
+            format!("Raw block '{hash}' is not stored"),
+            serde_json::json!({ "hash": hash }),
+        ));
+    };
+    Ok(Json(RawBlockResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/blocks/{hash}/filter",
+    tag = "data",
+    params(
+        ("hash" = String, Path, description = "Block hash")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "BIP158 basic filter, stored with indexer.block_filters", body = BlockFilterResponse),
+        (status = 404, description = "Block filter is not stored", body = ApiError),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_block_filter(
+    Path(hash): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<BlockFilterResponse>, ApiResponse> {
+    let Some(item) = state.data.block_filter(&hash).await.map_err(ApiResponse::from)? else {
+        return Err(ApiResponse::with_details(
+            ApiErrorCode::NotFound,
+            format!("Block filter of '{hash}' is not stored"),
+            serde_json::json!({ "hash": hash }),
+        ));
+    };
+    Ok(Json(BlockFilterResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/txs",
+    tag = "broadcast",
+    request_body(content = BroadcastRequest, description = "Raw transaction to relay through the node of the network"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Transaction accepted by the node and tracked in the mempool", body = BroadcastResponse),
+        (status = 422, description = "Invalid hex (VALIDATION_ERROR) or transaction rejected by the node (TX_REJECTED)", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError),
+        (status = 503, description = "Node is unavailable", body = ApiError)
+    )
+)]
+async fn broadcast_transaction(
+    State(state): State<AppState>,
+    principal: Option<axum::Extension<Principal>>,
+    Json(body): Json<BroadcastRequest>,
+) -> Result<Json<BroadcastResponse>, ApiResponse> {
+    let principal = principal.map(|axum::Extension(principal)| principal_name(&principal));
+    let item = state
+        .broadcast
+        .broadcast(&body.hex, principal.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(BroadcastResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/scan",
+    tag = "scan",
+    request_body(content = ScanRequest, description = "Descriptors to look up in the UTXO set of the node"),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Unspent outputs of the descriptors at the node tip", body = ScanResponse),
+        (status = 409, description = "Scan queue is full or another scan is running on the node", body = ApiError),
+        (status = 422, description = "Invalid request or descriptor rejected by the node", body = ApiError),
+        (status = 503, description = "Node is unavailable", body = ApiError)
+    )
+)]
+async fn scan_utxo_set(
+    State(state): State<AppState>,
+    Json(body): Json<ScanRequest>,
+) -> Result<Json<ScanResponse>, ApiResponse> {
+    let item = state
+        .scan
+        .scan(&body.descriptors, body.range)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(ScanResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/txs/{txid}/replacements",
+    tag = "data",
+    params(
+        ("txid" = String, Path, description = "Any transaction of the replacement chain")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "BIP-125 replacements of the chain, oldest first; empty when the transaction was not replaced", body = TxReplacementsResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn list_tx_replacements(
+    Path(txid): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<TxReplacementsResponse>, ApiResponse> {
+    let items = state.data.list_tx_replacements(&txid).await.map_err(ApiResponse::from)?;
+    Ok(Json(TxReplacementsResponse { txid, items }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/addresses/top",
+    tag = "data",
+    params(TopBalancesQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Addresses with the largest confirmed balances", body = crate::modules::data::TopBalancesPage),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn list_top_balances(
+    Query(query): Query<TopBalancesQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<crate::modules::data::TopBalancesPage>, ApiResponse> {
+    let page = state.data.top_balances(query.limit).await.map_err(ApiResponse::from)?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/scripts/search",
+    tag = "data",
+    params(ScriptSearchQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Confirmed outputs whose scriptPubKey matches the pattern", body = crate::modules::data::ScriptSearchPage),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn search_scripts(
+    Query(query): Query<ScriptSearchQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<crate::modules::data::ScriptSearchPage>, ApiResponse> {
+    let pagination = parse_pagination(&state.data, query.offset, query.limit)?;
+    let page = state
+        .data
+        .search_scripts(
+            ScriptSearchFilter {
+                pattern: query.pattern,
+                script_type: query.script_type,
+            },
+            pagination,
+        )
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/fees/estimate",
+    tag = "data",
+    params(FeeEstimateQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Fee-rate estimate from recent blocks and the indexed mempool", body = FeeEstimateResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn estimate_fees(
+    Query(query): Query<FeeEstimateQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<FeeEstimateResponse>, ApiResponse> {
+    let item = state
+        .fees
+        .estimate(query.target_blocks)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(FeeEstimateResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats",
+    tag = "data",
+    params(StatsQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Daily or per-block chain rollups in ascending order", body = StatsPage),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_stats(
+    Query(query): Query<StatsQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<StatsPage>, ApiResponse> {
+    let page = state
+        .stats
+        .list(StatsFilter {
+            from: query.from,
+            to: query.to,
+            granularity: query.granularity,
+            offset: query.offset,
+            limit: query.limit,
+        })
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats/supply",
+    tag = "data",
+    params(SupplyQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Circulating supply, UTXO age buckets and coin-days destroyed of the latest blocks", body = SupplyResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_supply(
+    Query(query): Query<SupplyQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<SupplyResponse>, ApiResponse> {
+    let supply = state.stats.supply(query.blocks).await.map_err(ApiResponse::from)?;
+    Ok(Json(supply))
+}
+
+/// `GET /v1/events` serves the event feed as JSON, or the Server-Sent Events of
+/// [`stream_events`] when the client accepts `text/event-stream`, as `EventSource` does.
+async fn events(State(state): State<AppState>, request: Request<Body>) -> Response {
+    if accepts_event_stream(request.headers()) {
+        stream_events.call(request, state).await
+    } else {
+        list_events.call(request, state).await
+    }
+}
+
+fn accepts_event_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(ACCEPT)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|media_range| {
+            media_range
+                .split(';')
+                .next()
+                .is_some_and(|media_type| media_type.trim().eq_ignore_ascii_case("text/event-stream"))
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/events",
+    tag = "events",
+    params(
+        EventsQuery,
+        EventsStreamQuery,
+        ("Accept" = Option<String>, Header, description = "`text/event-stream` opens the SSE stream of `/v1/events/stream`, which takes `kinds` and `last_event_id`"),
+        ("Last-Event-ID" = Option<i64>, Header, description = "Id of the last received event, sent by EventSource on reconnect")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Events in ascending id order, or with `Accept: text/event-stream` Server-Sent Events named after `kind`", content(
+            (EventsResponse = "application/json"),
+            (StreamEvent = "text/event-stream")
+        )),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn list_events(
+    Query(query): Query<EventsQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<EventsResponse>, ApiResponse> {
+    let items = state
+        .events
+        .list(EventsFilter {
+            levels: query.levels,
+            address: query.address,
+            txid: query.txid,
+            after_id: query.after_id,
+            limit: query.limit,
+        })
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(EventsResponse { items }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/events/stream",
+    tag = "events",
+    params(
+        EventsStreamQuery,
+        ("Last-Event-ID" = Option<i64>, Header, description = "Id of the last received event, sent by EventSource on reconnect")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events named after `kind`, with the event as JSON data; same as `GET /v1/events` with `Accept: text/event-stream`", content_type = "text/event-stream", body = StreamEvent),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn stream_events(
+    Query(query): Query<EventsStreamQuery>,
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiResponse> {
+    let kinds = parse_stream_kinds(query.kinds.as_deref()).map_err(ApiResponse::from)?;
+    let last_event_id = match headers.get(LAST_EVENT_ID_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .ok_or_else(|| ApiResponse::validation("Last-Event-ID MUST be an event id".to_string()))?,
+        ),
+        None => query.last_event_id,
+    };
+    let cursor = match last_event_id {
+        Some(id) => id,
+        None => state.events.latest_stream_id().await.map_err(ApiResponse::from)?,
+    };
+
+    let events = state.events.clone();
+    let drain = state.status.drain().clone();
+    let pending: VecDeque<StreamEvent> = VecDeque::new();
+    // Ends when the instance drains, so shutdown does not wait for the client; EventSource
+    // reconnects with `Last-Event-ID`.
+    let stream = stream::unfold((cursor, pending), move |(mut cursor, mut pending)| {
+        let (events, drain, kinds) = (events.clone(), drain.clone(), kinds.clone());
+        async move {
+            loop {
+                if let Some(event) = pending.pop_front() {
+                    let sse = Event::default()
+                        .id(event.id.to_string())
+                        .event(event.kind.clone())
+                        .json_data(&event);
+                    return Some((sse, (cursor, pending)));
+                }
+                if drain.is_draining() {
+                    return None;
+                }
+                match events.stream_after(cursor, &kinds).await {
+                    Ok(batch) if !batch.is_empty() => {
+                        cursor = batch.last().map_or(cursor, |event| event.id);
+                        pending.extend(batch);
+                    }
+                    Ok(_) => tokio::time::sleep(EVENT_STREAM_POLL_INTERVAL).await,
+                    Err(err) => {
+                        warn!(component = "api", error = %err, message = "event stream poll failed");
+                        tokio::time::sleep(EVENT_STREAM_POLL_INTERVAL).await;
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/uptime",
+    tag = "admin",
+    params(UptimeQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Current instance uptime and recent start/stop/crash events", body = UptimeResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_uptime(
+    Query(query): Query<UptimeQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<UptimeResponse>, ApiResponse> {
+    let item = state.uptime.report(query.limit).await.map_err(ApiResponse::from)?;
+    Ok(Json(UptimeResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/reload",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Config re-read from disk and runtime changes applied", body = ReloadResponse),
+        (status = 422, description = "Config is invalid or changes settings that require a restart", body = ApiError),
+        (status = 500, description = "Reload is not configured or storage failure", body = ApiError)
+    )
+)]
+async fn reload_config(State(state): State<AppState>) -> Result<Json<ReloadResponse>, ApiResponse> {
+    let Some(reload) = state.reload.as_ref() else {
+        return Err(ApiResponse::new(ApiErrorCode::InternalError, "Config reload is not configured"));
+    };
+
+    let item = reload.reload().await.map_err(ApiResponse::from)?;
+    Ok(Json(ReloadResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/credentials/reload",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Secrets and certificates re-read and the API auth and RPC clients rebuilt", body = CredentialsReloadResponse),
+        (status = 422, description = "Config is invalid, a certificate failed the preflight or an RPC client could not be built", body = ApiError),
+        (status = 500, description = "Reload is not configured or storage failure", body = ApiError)
+    )
+)]
+async fn reload_credentials(State(state): State<AppState>) -> Result<Json<CredentialsReloadResponse>, ApiResponse> {
+    let Some(reload) = state.reload.as_ref() else {
+        return Err(ApiResponse::new(ApiErrorCode::InternalError, "Config reload is not configured"));
+    };
+
+    let item = reload.reload_credentials().await.map_err(ApiResponse::from)?;
+    Ok(Json(CredentialsReloadResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/drain",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Instance drains: readiness fails and responses carry Connection: close", body = DrainResponse)
+    )
+)]
+async fn start_drain(State(state): State<AppState>) -> Json<DrainResponse> {
+    Json(DrainResponse {
+        item: state.status.drain().start(),
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/drain",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Instance accepts traffic again", body = DrainResponse)
+    )
+)]
+async fn stop_drain(State(state): State<AppState>) -> Json<DrainResponse> {
+    Json(DrainResponse {
+        item: state.status.drain().stop(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/log-level",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Log filter in effect and the one selected at startup", body = LogLevelResponse),
+        (status = 500, description = "Logging is not initialized", body = ApiError)
+    )
+)]
+async fn get_log_level() -> Result<Json<LogLevelResponse>, ApiResponse> {
+    let item = logging::filter_status().map_err(ApiResponse::from)?;
+    Ok(Json(LogLevelResponse { item }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/v1/admin/log-level",
+    tag = "admin",
+    request_body = LogLevelRequest,
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Log filter replaced until reset or restart", body = LogLevelResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Logging is not initialized", body = ApiError)
+    )
+)]
+async fn set_log_level(Json(request): Json<LogLevelRequest>) -> Result<Json<LogLevelResponse>, ApiResponse> {
+    let item = logging::set_filter(&request.filter).map_err(ApiResponse::from)?;
+    Ok(Json(LogLevelResponse { item }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/log-level",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Startup log filter restored", body = LogLevelResponse),
+        (status = 500, description = "Logging is not initialized", body = ApiError)
+    )
+)]
+async fn reset_log_level() -> Result<Json<LogLevelResponse>, ApiResponse> {
+    let item = logging::reset_filter().map_err(ApiResponse::from)?;
+    Ok(Json(LogLevelResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/replication",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "CDC publication and logical replication slot health", body = ReplicationResponse),
+        (status = 404, description = "Replication is not configured", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_replication(State(state): State<AppState>) -> Result<Json<ReplicationResponse>, ApiResponse> {
+    let Some(replication) = state.replication.as_ref() else {
+        return Err(ApiResponse::new(ApiErrorCode::NotFound, "Replication is not configured"));
+    };
+
+    let item = replication.status().await.map_err(ApiResponse::from)?;
+    Ok(Json(ReplicationResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/prune",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Transaction detail below the retained blocks pruned", body = PruneResponse),
+        (status = 404, description = "Retention is not configured", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn prune_history(State(state): State<AppState>) -> Result<Json<PruneResponse>, ApiResponse> {
+    let Some(retention) = state.retention.as_ref() else {
+        return Err(ApiResponse::new(ApiErrorCode::NotFound, "Retention is not configured"));
+    };
+
+    let item = retention.prune().await.map_err(ApiResponse::from)?;
+    Ok(Json(PruneResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/provenance",
+    tag = "admin",
+    params(ProvenanceQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Canonical height ranges grouped by the pipeline version and stages that indexed them", body = ProvenanceResponse),
+        (status = 422, description = "Validation failed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError)
+    )
+)]
+async fn get_provenance(
+    Query(query): Query<ProvenanceQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<ProvenanceResponse>, ApiResponse> {
+    let items = state
+        .data
+        .list_provenance_ranges(query.missing_stage.as_deref())
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(ProvenanceResponse { items }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/blocks/{hash}/reindex",
+    tag = "admin",
+    params(
+        ("hash" = String, Path, description = "Hash of an indexed canonical block")
+    ),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Block refetched from the node and its rows rewritten in one transaction", body = BlockReindexResponse),
+        (status = 422, description = "The block is not indexed as canonical", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError),
+        (status = 503, description = "No node is configured or the node is unavailable", body = ApiError)
+    )
+)]
+async fn reindex_block(
+    Path(hash): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<BlockReindexResponse>, ApiResponse> {
+    let item = state.jobs.reindex_block(&hash).await.map_err(ApiResponse::from)?;
+    Ok(Json(BlockReindexResponse { item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/blocks/reindex",
+    tag = "admin",
+    request_body = ReindexBlocksRequest,
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Canonical blocks of the height range refetched and rewritten, one transaction per block", body = BlockReindexResponse),
+        (status = 422, description = "Invalid range or heights not all indexed", body = ApiError),
+        (status = 500, description = "Storage failure", body = ApiError),
+        (status = 503, description = "No node is configured or the node is unavailable", body = ApiError)
+    )
+)]
+async fn reindex_blocks(
+    State(state): State<AppState>,
+    Json(request): Json<ReindexBlocksRequest>,
+) -> Result<Json<BlockReindexResponse>, ApiResponse> {
+    let item = state
+        .jobs
+        .reindex_range(request.from_height, request.to_height)
+        .await
+        .map_err(ApiResponse::from)?;
+    Ok(Json(BlockReindexResponse { item }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/runtime",
+    tag = "admin",
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Tokio runtime health: task counts, queue depths and worker busy time", body = RuntimeMetricsResponse)
+    )
+)]
+async fn get_runtime_metrics(State(state): State<AppState>) -> Json<RuntimeMetricsResponse> {
+    Json(RuntimeMetricsResponse {
+        item: state.profiling.runtime_metrics(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/profile/cpu",
+    tag = "admin",
+    params(CpuProfileQuery),
+    security(
+        ("basic_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "CPU profile in pprof protobuf format, for `go tool pprof`", content_type = "application/octet-stream"),
+        (status = 404, description = "The build has no `profiling` feature", body = ApiError),
+        (status = 409, description = "Another capture is running", body = ApiError),
+        (status = 422, description = "Invalid sampling window or frequency", body = ApiError),
+        (status = 500, description = "Profiler failure", body = ApiError)
+    )
+)]
+async fn capture_cpu_profile(
+    Query(query): Query<CpuProfileQuery>,
+    State(state): State<AppState>,
+) -> Result<Response, ApiResponse> {
+    let body = state
+        .profiling
+        .capture_cpu_profile(query.seconds, query.frequency)
+        .await
+        .map_err(ApiResponse::from)?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            (axum::http::header::CONTENT_TYPE, "application/octet-stream"),
+            (axum::http::header::CONTENT_DISPOSITION, "attachment; filename=\"cpu.pb\""),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn parse_pagination(
+    _data: &DataService,
     offset: Option<i64>,
     limit: Option<i64>,
 ) -> Result<Pagination, ApiResponse> {
     DataService::validate_pagination(offset, limit).map_err(ApiResponse::from)
 }
 
-async fn basic_auth_middleware(
-    State(auth): State<ApiAuth>,
-    request: Request<Body>,
+async fn auth_middleware(
+    State(auth): State<AuthChain>,
+    mut request: Request<Body>,
     next: Next,
 ) -> Response {
-    match is_authorized(request.headers().get(AUTHORIZATION), &auth) {
-        true => next.run(request).await,
-        false => unauthorized_response(),
+    let mut credentials = Credentials::from_headers(request.headers());
+    if credentials.signature.is_some() && auth.accepts_signatures() {
+        let (parts, body) = request.into_parts();
+        let Ok(body) = axum::body::to_bytes(body, SIGNED_BODY_LIMIT_BYTES).await else {
+            return ApiResponse::new(ApiErrorCode::PayloadTooLarge, "Request body too large")
+                .into_response();
+        };
+        let path = parts
+            .uri
+            .path_and_query()
+            .map(|value| value.as_str())
+            .unwrap_or_else(|| parts.uri.path());
+        credentials = credentials.with_request(parts.method.as_str(), path, body.clone());
+        request = Request::from_parts(parts, Body::from(body));
+    }
+
+    let Some(principal) = auth.authenticate_blocking(credentials).await else {
+        return unauthorized_response();
+    };
+
+    let required = Role::required_for(request.method(), request.uri().path());
+    let role = principal.role();
+    if role < required {
+        return ApiResponse::with_details(
+            ApiErrorCode::Forbidden,
+            "Role does not allow this request",
+            serde_json::json!({ "role": role.as_str(), "required_role": required.as_str() }),
+        )
+        .into_response();
     }
+
+    request.extensions_mut().insert(principal);
+    next.run(request).await
 }
 
-fn is_authorized(header: Option<&HeaderValue>, auth: &ApiAuth) -> bool {
-    let Some(header) = header else {
-        return false;
+/// Replays the stored response of a job action retried with the same `Idempotency-Key`,
+/// so a client that lost the first response does not apply the action twice. Keys are
+/// scoped to the authenticated principal; server errors are not stored, so such a
+/// request can be retried with the same key.
+async fn idempotency_middleware(State(jobs): State<JobsService>, request: Request<Body>, next: Next) -> Response {
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return next.run(request).await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => key.to_string(),
+        _ => {
+            return ApiResponse::validation(format!(
+                "Idempotency-Key MUST be 1 to {MAX_IDEMPOTENCY_KEY_LEN} visible ASCII characters"
+            ))
+            .into_response();
+        }
+    };
+    let principal = request
+        .extensions()
+        .get::<Principal>()
+        .map_or_else(|| "anonymous".to_string(), principal_name);
+    let method = request.method().to_string();
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| request.uri().path().to_string(), |OriginalUri(uri)| uri.path().to_string());
+
+    match jobs.reserve_idempotency_key(&principal, &key, &method, &path).await {
+        Ok(IdempotencyKeyState::Reserved) => {}
+        Ok(IdempotencyKeyState::Completed { status, body }) => {
+            let mut response = (StatusCode::from_u16(status).unwrap_or(StatusCode::OK), body).into_response();
+            response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            response.headers_mut().insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+            return response;
+        }
+        Ok(IdempotencyKeyState::InProgress) => {
+            return ApiResponse::with_details(
+                ApiErrorCode::Conflict,
+                format!("Request with Idempotency-Key '{key}' is still in progress"),
+                serde_json::json!({ "idempotency_key": key }),
+            )
+            .into_response();
+        }
+        Ok(IdempotencyKeyState::Mismatch) => {
+            return ApiResponse::validation(format!("Idempotency-Key '{key}' was used for another request"))
+                .into_response();
+        }
+        Err(err) => return ApiResponse::from(err).into_response(),
+    }
+
+    let (parts, body) = next.run(request).await.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, IDEMPOTENT_BODY_LIMIT_BYTES).await else {
+        let _ = jobs.release_idempotency_key(&principal, &key).await;
+        return ApiResponse::new(ApiErrorCode::InternalError, "Response body too large").into_response();
     };
+    let stored = if parts.status.is_server_error() {
+        jobs.release_idempotency_key(&principal, &key).await
+    } else {
+        jobs.complete_idempotency_key(&principal, &key, parts.status.as_u16(), &body)
+            .await
+    };
+    // The action is applied either way; a retry then gets the job's state transition error.
+    if let Err(err) = stored {
+        warn!(component = "api", idempotency_key = %key, error = %err, message = "idempotent response not stored");
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
 
-    let Ok(value) = header.to_str() else {
-        return false;
+/// Tags `200` responses with a weak `ETag` over the uncompressed body and answers
+/// `304 Not Modified` when `If-None-Match` already holds it. The handler still runs:
+/// the tag saves bandwidth, not the query.
+async fn etag_middleware(request: Request<Body>, next: Next) -> Response {
+    if request.method() != Method::GET && request.method() != Method::HEAD {
+        return next.run(request).await;
+    }
+    let if_none_match = request.headers().get(IF_NONE_MATCH).cloned();
+    let response = next.run(request).await;
+    let fits = response
+        .body()
+        .size_hint()
+        .exact()
+        .is_some_and(|len| len <= ETAG_BODY_LIMIT_BYTES);
+    if response.status() != StatusCode::OK || !fits {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, usize::MAX).await else {
+        return ApiResponse::new(ApiErrorCode::InternalError, "Failed to read response body").into_response();
     };
+    let etag = body_etag(&body);
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        parts.headers.insert(ETAG, value);
+    }
+    if if_none_match
+        .as_ref()
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &etag))
+    {
+        parts.status = StatusCode::NOT_MODIFIED;
+        parts.headers.remove(CONTENT_LENGTH);
+        return Response::from_parts(parts, Body::empty());
+    }
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+fn body_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("W/\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// Weak comparison of RFC 9110: `W/` prefixes are ignored, `*` matches any tag.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.split(',').any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+/// `provider:subject` of an authenticated caller, the key of its idempotency keys and broadcasts.
+fn principal_name(principal: &Principal) -> String {
+    format!("{}:{}", principal.provider, principal.subject)
+}
+
+/// Rejects requests over `server.rate_limit.per_ip` with `429 RATE_LIMITED`. Runs before
+/// auth, so unauthenticated and failing requests are limited too, and inside the trace
+/// layer that resolves the [`ClientIp`]; probes and metrics scrapes are never limited.
+async fn ip_rate_limit_middleware(State(limiter): State<RateLimiter>, request: Request<Body>, next: Next) -> Response {
+    if !limiter.limits_ip() || is_probe_path(request.uri().path()) {
+        return next.run(request).await;
+    }
 
-    let Some(encoded) = value.strip_prefix("Basic ") else {
-        return false;
+    let Some(&ClientIp(client_ip)) = request.extensions().get::<ClientIp>() else {
+        return next.run(request).await;
     };
+    match limiter.check_ip(client_ip) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => rate_limited(retry_after),
+    }
+}
+
+/// Rejects requests over `server.rate_limit.per_credential`; runs after auth, so the
+/// principal is known.
+async fn credential_rate_limit_middleware(State(limiter): State<RateLimiter>, request: Request<Body>, next: Next) -> Response {
+    if !limiter.limits_credential() || is_probe_path(request.uri().path()) {
+        return next.run(request).await;
+    }
 
-    let Ok(decoded) = STANDARD.decode(encoded) else {
-        return false;
+    let Some(credential) = request.extensions().get::<Principal>().map(principal_name) else {
+        return next.run(request).await;
     };
+    match limiter.check_credential(&credential) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => rate_limited(retry_after),
+    }
+}
+
+fn rate_limited(retry_after: Duration) -> Response {
+    let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = ApiResponse::with_details(
+        ApiErrorCode::RateLimited,
+        "Too many requests",
+        serde_json::json!({ "retry_after_secs": retry_after_secs }),
+    )
+    .into_response();
+    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
 
-    let Ok(credentials) = String::from_utf8(decoded) else {
-        return false;
+/// Runs the request inside an `http_request` span carrying its request ID, so
+/// RPC calls and queries made by the handler are logged with it, and writes an
+/// access log line once the response is ready.
+async fn trace_middleware(State(proxies): State<TrustedProxies>, mut request: Request<Body>, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let client_ip = match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(peer)) => Some(proxies.client_ip(peer.ip(), request.headers())),
+        None if request.extensions().get::<ConnectInfo<UnixPeer>>().is_some() => {
+            Some(proxies.unix_client_ip(request.headers()))
+        }
+        None => None,
     };
+    if let Some(client_ip) = client_ip {
+        request.extensions_mut().insert(ClientIp(client_ip));
+    }
+    let span = info_span!(
+        "http_request",
+        request_id = %request_id,
+        client_ip = client_ip.map(tracing::field::display),
+        method = %method,
+        path = %path
+    );
+    let started = Instant::now();
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+
+    let status = response.status().as_u16();
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    span.in_scope(|| {
+        if response.status().is_server_error() {
+            warn!(component = "http", method = %method, path = %path, status, latency_ms, message = "request completed");
+        } else if is_probe_path(&path) {
+            debug!(component = "http", method = %method, path = %path, status, latency_ms, message = "request completed");
+        } else {
+            info!(component = "http", method = %method, path = %path, status, latency_ms, message = "request completed");
+        }
+    });
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+/// Asks clients to close keep-alive connections while draining, so load
+/// balancers reconnect to another instance instead of reusing this one.
+async fn drain_middleware(State(drain): State<DrainState>, request: Request<Body>, next: Next) -> Response {
+    let mut response = next.run(request).await;
+    if drain.is_draining() {
+        response
+            .headers_mut()
+            .insert(axum::http::header::CONNECTION, HeaderValue::from_static("close"));
+    }
+
+    response
+}
+
+/// IDs set by a proxy are kept as long as they are short, printable tokens.
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Health probes and metric scrapes arrive every few seconds and would drown
+/// the access log at `info`.
+fn is_probe_path(path: &str) -> bool {
+    path == "/metrics" || path == "/health" || path.starts_with("/health/")
+}
 
-    let mut parts = credentials.splitn(2, ':');
-    let username = parts.next().unwrap_or_default();
-    let password = parts.next().unwrap_or_default();
+/// Random per-process prefix plus a sequence number: unique without a UUID dependency.
+fn new_request_id() -> String {
+    static PREFIX: OnceLock<u64> = OnceLock::new();
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
 
-    username == auth.username && password == auth.password
+    let prefix = PREFIX.get_or_init(|| RandomState::new().build_hasher().finish());
+    format!("{prefix:016x}-{:08x}", SEQUENCE.fetch_add(1, Ordering::Relaxed))
 }
 
 fn unauthorized_response() -> Response {
     let body = Json(ApiError {
-        code: "AUTH_FAILED",
-        message: "Authentication failed",
+        code: ApiErrorCode::AuthFailed,
+        message: "Authentication failed".to_string(),
         details: serde_json::json!({}),
     });
 
-    let mut response = (StatusCode::UNAUTHORIZED, body).into_response();
+    let mut response = (ApiErrorCode::AuthFailed.status(), body).into_response();
     response.headers_mut().insert(
         "www-authenticate",
         HeaderValue::from_static("Basic realm=\"indexer\""),
@@ -825,27 +3328,41 @@ struct ApiResponse {
 impl From<JobsError> for ApiResponse {
     fn from(err: JobsError) -> Self {
         match err {
-            JobsError::NotFound => ApiResponse::new(StatusCode::NOT_FOUND, "NOT_FOUND", "Not found"),
-            JobsError::AlreadyExists => ApiResponse::new(StatusCode::CONFLICT, "CONFLICT", "Job already exists"),
-            JobsError::InvalidTransition(_) => ApiResponse::new(
-                StatusCode::CONFLICT,
-                "CONFLICT",
-                "Invalid job state transition",
+            JobsError::NotFound(job_id) => ApiResponse::with_details(
+                ApiErrorCode::NotFound,
+                format!("Job '{job_id}' not found"),
+                serde_json::json!({ "job_id": job_id }),
+            ),
+            JobsError::AddressNotFound { job_id, address } => ApiResponse::with_details(
+                ApiErrorCode::NotFound,
+                format!("Address '{address}' is not watched by job '{job_id}'"),
+                serde_json::json!({ "job_id": job_id, "address": address }),
+            ),
+            JobsError::AlreadyExists(job_id) => ApiResponse::with_details(
+                ApiErrorCode::Conflict,
+                format!("Job '{job_id}' already exists"),
+                serde_json::json!({ "job_id": job_id }),
+            ),
+            JobsError::InvalidTransition {
+                job_id,
+                current_status,
+                requested,
+            } => ApiResponse::with_details(
+                ApiErrorCode::Conflict,
+                format!("Job '{job_id}' cannot {requested} from status '{current_status}'"),
+                serde_json::json!({ "job_id": job_id, "current_status": current_status, "requested": requested }),
             ),
-            JobsError::Validation(message) => ApiResponse::with_details(
-                StatusCode::UNPROCESSABLE_ENTITY,
-                "VALIDATION_ERROR",
-                "Validation failed",
-                serde_json::json!({ "reason": message }),
+            JobsError::Validation(message) => ApiResponse::validation(message),
+            JobsError::NodeUnavailable => ApiResponse::new(
+                ApiErrorCode::NodeUnavailable,
+                "Node is unavailable",
             ),
             JobsError::Serialization(_) => ApiResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "INTERNAL_ERROR",
+                ApiErrorCode::InternalError,
                 "Serialization failure",
             ),
             JobsError::Storage(_) => ApiResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "INTERNAL_ERROR",
+                ApiErrorCode::InternalError,
                 "Storage failure",
             ),
         }
@@ -856,20 +3373,13 @@ impl From<DataError> for ApiResponse {
     fn from(err: DataError) -> Self {
         match err {
             DataError::AddressNotIndexed => ApiResponse::with_details(
-                StatusCode::NOT_FOUND,
-                "ADDRESS_NOT_INDEXED",
+                ApiErrorCode::AddressNotIndexed,
                 "Address is not indexed",
                 serde_json::json!({}),
             ),
-            DataError::Validation(message) => ApiResponse::with_details(
-                StatusCode::UNPROCESSABLE_ENTITY,
-                "VALIDATION_ERROR",
-                "Validation failed",
-                serde_json::json!({ "reason": message }),
-            ),
+            DataError::Validation(message) => ApiResponse::validation(message),
             DataError::Storage(_) => ApiResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "INTERNAL_ERROR",
+                ApiErrorCode::InternalError,
                 "Storage failure",
             ),
         }
@@ -879,55 +3389,185 @@ impl From<DataError> for ApiResponse {
 impl From<NodesError> for ApiResponse {
     fn from(err: NodesError) -> Self {
         match err {
-            NodesError::NotFound => ApiResponse::new(StatusCode::NOT_FOUND, "NOT_FOUND", "Not found"),
-            NodesError::AlreadyExists => ApiResponse::new(StatusCode::CONFLICT, "CONFLICT", "Node already exists"),
-            NodesError::Validation(message) => ApiResponse::with_details(
-                StatusCode::UNPROCESSABLE_ENTITY,
-                "VALIDATION_ERROR",
-                "Validation failed",
-                serde_json::json!({ "reason": message }),
+            NodesError::NotFound(node_id) => ApiResponse::with_details(
+                ApiErrorCode::NotFound,
+                format!("Node '{node_id}' not found"),
+                serde_json::json!({ "node_id": node_id }),
             ),
+            NodesError::AlreadyExists(node_id) => ApiResponse::with_details(
+                ApiErrorCode::Conflict,
+                format!("Node '{node_id}' already exists"),
+                serde_json::json!({ "node_id": node_id }),
+            ),
+            NodesError::Validation(message) => ApiResponse::validation(message),
             NodesError::Rpc(_) => ApiResponse::new(
-                StatusCode::SERVICE_UNAVAILABLE,
-                "NODE_UNAVAILABLE",
+                ApiErrorCode::NodeUnavailable,
                 "Node is unavailable",
             ),
             NodesError::Storage(_) => ApiResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "INTERNAL_ERROR",
+                ApiErrorCode::InternalError,
                 "Storage failure",
             ),
         }
     }
 }
 
-impl ApiResponse {
-    fn new(status: StatusCode, code: &'static str, message: &'static str) -> Self {
-        Self {
-            status,
-            body: Json(ApiError {
-                code,
-                message,
-                details: serde_json::json!({}),
-            }),
+impl From<ProfilingError> for ApiResponse {
+    fn from(err: ProfilingError) -> Self {
+        match err {
+            ProfilingError::Validation(message) => ApiResponse::validation(message),
+            ProfilingError::Busy => ApiResponse::new(ApiErrorCode::Conflict, "CPU profile capture already running"),
+            ProfilingError::Disabled => ApiResponse::new(ApiErrorCode::NotFound, "CPU profiling is not compiled in"),
+            ProfilingError::Capture(reason) => ApiResponse::with_details(
+                ApiErrorCode::InternalError,
+                "CPU profile capture failed",
+                serde_json::json!({ "reason": reason }),
+            ),
+        }
+    }
+}
+
+impl From<UptimeError> for ApiResponse {
+    fn from(err: UptimeError) -> Self {
+        match err {
+            UptimeError::Validation(message) => ApiResponse::validation(message),
+            UptimeError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<EventsError> for ApiResponse {
+    fn from(err: EventsError) -> Self {
+        match err {
+            EventsError::Validation(message) => ApiResponse::validation(message),
+            EventsError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<BroadcastError> for ApiResponse {
+    fn from(err: BroadcastError) -> Self {
+        match err {
+            BroadcastError::Validation(message) => ApiResponse::validation(message),
+            BroadcastError::Rejected { txid, reason } => ApiResponse::with_details(
+                ApiErrorCode::TxRejected,
+                format!("Transaction '{txid}' rejected by node: {reason}"),
+                serde_json::json!({ "txid": txid, "reason": reason }),
+            ),
+            BroadcastError::NodeUnavailable(_) => ApiResponse::new(ApiErrorCode::NodeUnavailable, "Node is unavailable"),
+            BroadcastError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<NodeInfoError> for ApiResponse {
+    fn from(err: NodeInfoError) -> Self {
+        match err {
+            NodeInfoError::NodeUnavailable(_) => ApiResponse::new(ApiErrorCode::NodeUnavailable, "Node is unavailable"),
+        }
+    }
+}
+
+impl From<ScanError> for ApiResponse {
+    fn from(err: ScanError) -> Self {
+        match err {
+            ScanError::Validation(message) => ApiResponse::validation(message),
+            ScanError::Rejected(reason) => ApiResponse::validation(format!("Node rejected the scan: {reason}")),
+            ScanError::QueueFull => ApiResponse::new(ApiErrorCode::Conflict, "Scan queue is full; retry later"),
+            ScanError::NodeBusy => ApiResponse::new(ApiErrorCode::Conflict, "Another scan is running on the node"),
+            ScanError::NodeUnavailable(_) => ApiResponse::new(ApiErrorCode::NodeUnavailable, "Node is unavailable"),
+        }
+    }
+}
+
+impl From<FeesError> for ApiResponse {
+    fn from(err: FeesError) -> Self {
+        match err {
+            FeesError::Validation(message) => ApiResponse::validation(message),
+            FeesError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<StatsError> for ApiResponse {
+    fn from(err: StatsError) -> Self {
+        match err {
+            StatsError::Validation(message) => ApiResponse::validation(message),
+            StatsError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<ReplicationError> for ApiResponse {
+    fn from(err: ReplicationError) -> Self {
+        match err {
+            ReplicationError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<RetentionError> for ApiResponse {
+    fn from(err: RetentionError) -> Self {
+        match err {
+            RetentionError::Storage(_) => ApiResponse::new(ApiErrorCode::InternalError, "Storage failure"),
+        }
+    }
+}
+
+impl From<LogFilterError> for ApiResponse {
+    fn from(err: LogFilterError) -> Self {
+        match err {
+            LogFilterError::Invalid(_) => ApiResponse::validation(err.to_string()),
+            LogFilterError::NotInitialized | LogFilterError::Reload(_) => {
+                ApiResponse::new(ApiErrorCode::InternalError, "Log filter is unavailable")
+            }
+        }
+    }
+}
+
+impl From<ReloadError> for ApiResponse {
+    fn from(err: ReloadError) -> Self {
+        match err {
+            ReloadError::Config(err) => ApiResponse::with_details(
+                ApiErrorCode::ValidationError,
+                "Config reload rejected",
+                serde_json::json!({ "reason": err.to_string() }),
+            ),
+            ReloadError::Tls(_) | ReloadError::Rpc(_) => ApiResponse::with_details(
+                ApiErrorCode::ValidationError,
+                "Credentials reload rejected",
+                serde_json::json!({ "reason": err.to_string() }),
+            ),
+            ReloadError::Jobs(err) => ApiResponse::from(err),
+            ReloadError::Nodes(err) => ApiResponse::from(err),
         }
     }
+}
+
+impl ApiResponse {
+    fn new(code: ApiErrorCode, message: impl Into<String>) -> Self {
+        Self::with_details(code, message, serde_json::json!({}))
+    }
 
-    fn with_details(
-        status: StatusCode,
-        code: &'static str,
-        message: &'static str,
-        details: serde_json::Value,
-    ) -> Self {
+    fn with_details(code: ApiErrorCode, message: impl Into<String>, details: serde_json::Value) -> Self {
         Self {
-            status,
+            status: code.status(),
             body: Json(ApiError {
                 code,
-                message,
+                message: message.into(),
                 details,
             }),
         }
     }
+
+    /// `422 VALIDATION_ERROR` whose message and `details.reason` carry `reason`.
+    fn validation(reason: String) -> Self {
+        Self::with_details(
+            ApiErrorCode::ValidationError,
+            format!("Validation failed: {reason}"),
+            serde_json::json!({ "reason": reason }),
+        )
+    }
 }
 
 impl IntoResponse for ApiResponse {
@@ -935,3 +3575,270 @@ impl IntoResponse for ApiResponse {
         (self.status, self.body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn error_catalog_lists_unique_codes() {
+        let codes: HashSet<String> = ApiErrorCode::ALL
+            .into_iter()
+            .map(|code| serde_json::to_value(code).unwrap().as_str().unwrap().to_string())
+            .collect();
+
+        assert_eq!(codes.len(), ApiErrorCode::ALL.len());
+        assert!(codes.contains("ADDRESS_NOT_INDEXED"));
+        assert_eq!(ApiErrorCode::ValidationError.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[test]
+    fn etags_match_with_weak_comparison() {
+        let etag = body_etag(br#"{"items":[]}"#);
+        assert!(etag.starts_with("W/\"") && etag.len() == 36);
+        assert_ne!(etag, body_etag(br#"{"items":[1]}"#));
+
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(etag.trim_start_matches("W/"), &etag));
+        assert!(etag_matches(&format!("\"other\", {etag}"), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+    }
+
+    #[test]
+    fn errors_carry_their_context_in_message_and_details() {
+        let transition = ApiResponse::from(JobsError::InvalidTransition {
+            job_id: "backfill".to_string(),
+            current_status: "created".to_string(),
+            requested: "pause".to_string(),
+        });
+        assert_eq!(transition.status, StatusCode::CONFLICT);
+        assert_eq!(transition.body.message, "Job 'backfill' cannot pause from status 'created'");
+        assert_eq!(
+            transition.body.details,
+            serde_json::json!({ "job_id": "backfill", "current_status": "created", "requested": "pause" })
+        );
+
+        let validation = ApiResponse::from(JobsError::Validation("limit MUST be between 1 and 500".to_string()));
+        assert_eq!(validation.body.message, "Validation failed: limit MUST be between 1 and 500");
+        assert_eq!(validation.body.details["reason"], "limit MUST be between 1 and 500");
+    }
+
+    #[test]
+    fn negotiates_the_event_stream_through_accept() {
+        let accept = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_str(value).unwrap());
+            headers
+        };
+
+        assert!(accepts_event_stream(&accept("text/event-stream")));
+        assert!(accepts_event_stream(&accept("application/json, Text/Event-Stream;q=0.5")));
+        assert!(!accepts_event_stream(&accept("application/json")));
+        assert!(!accepts_event_stream(&accept("*/*")));
+        assert!(!accepts_event_stream(&HeaderMap::new()));
+    }
+
+    #[test]
+    fn openapi_document_covers_routes_with_unique_operation_ids() {
+        let openapi = ApiDoc::openapi();
+        for path in [
+            "/v1/jobs",
+            "/v1/jobs/{job_id}",
+            "/v1/data/blocks",
+            "/v1/data/transactions",
+            "/v1/data/addresses/{address}/balance",
+            "/v1/data/addresses/{address}/utxos",
+            "/v1/data/addresses/{address}/runes",
+            "/v1/stats/supply",
+            "/v1/openapi.json",
+        ] {
+            assert!(openapi.paths.paths.contains_key(path), "missing {path}");
+        }
+
+        let operation_ids: Vec<String> = openapi
+            .paths
+            .paths
+            .values()
+            .flat_map(|item| {
+                [&item.get, &item.post, &item.put, &item.delete, &item.patch]
+                    .into_iter()
+                    .flatten()
+                    .map(|operation| operation.operation_id.clone().expect("operation id"))
+            })
+            .collect();
+        let unique: HashSet<&String> = operation_ids.iter().collect();
+        assert_eq!(unique.len(), operation_ids.len());
+    }
+
+    #[tokio::test]
+    async fn startup_router_reports_starting_until_opened() {
+        let auth = AuthChain::new().with_provider(Arc::new(crate::modules::auth::BasicAuthProvider::new(
+            "admin", "pass",
+        )));
+        let gate = StartupGate::new(DrainState::default());
+        gate.record_storage_error("connection refused".to_string());
+        let startup = startup_router(auth, TrustedProxies::default(), gate.clone(), true);
+        let request = |uri: &str| {
+            Request::get(uri)
+                .header("authorization", "Basic YWRtaW46cGFzcw==")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let body = |response: Response| async move {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+        };
+
+        let live = startup.clone().oneshot(request("/health/live")).await.unwrap();
+        assert_eq!(live.status(), StatusCode::OK);
+        let anonymous = Request::get("/health/live").body(Body::empty()).unwrap();
+        assert_eq!(startup.clone().oneshot(anonymous).await.unwrap().status(), StatusCode::OK);
+
+        let ready = startup.clone().oneshot(request("/health/ready")).await.unwrap();
+        assert_eq!(ready.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let ready = body(ready).await;
+        assert_eq!(ready["item"]["status"], "starting");
+        assert_eq!(ready["item"]["database_error"], "connection refused");
+
+        let jobs = startup.clone().oneshot(request("/v1/jobs")).await.unwrap();
+        assert_eq!(jobs.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body(jobs).await["code"], "STARTING");
+
+        let anonymous = Request::get("/v1/jobs").body(Body::empty()).unwrap();
+        assert_eq!(startup.clone().oneshot(anonymous).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        gate.open(Router::new().route("/v1/jobs", get(|| async { "jobs" })));
+        let jobs = startup.oneshot(request("/v1/jobs")).await.unwrap();
+        assert_eq!(jobs.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn limits_client_ip_before_auth() {
+        let auth = AuthChain::new().with_provider(Arc::new(crate::modules::auth::BasicAuthProvider::new(
+            "admin", "pass",
+        )));
+        let limiter = RateLimiter::new(&crate::modules::config::RateLimitConfig {
+            per_ip: Some(crate::modules::config::RateLimitRule {
+                requests_per_sec: 1,
+                burst: 2,
+            }),
+            per_credential: None,
+        });
+        let app = with_middleware(
+            Router::new().route("/v1/jobs", get(|| async { "jobs" })),
+            Router::new(),
+            auth,
+            TrustedProxies::default(),
+            limiter,
+            DrainState::default(),
+        );
+        let request = |ip: [u8; 4], authorization: &str| {
+            let mut request = Request::get("/v1/jobs")
+                .header("authorization", authorization)
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from((ip, 40000))));
+            request
+        };
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(request([10, 0, 0, 1], "Basic d3Jvbmc6cGFzcw==")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        }
+        let limited = app.clone().oneshot(request([10, 0, 0, 1], "Basic YWRtaW46cGFzcw==")).await.unwrap();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(limited.headers().contains_key(RETRY_AFTER));
+
+        let other = app.oneshot(request([10, 0, 0, 2], "Basic YWRtaW46cGFzcw==")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn serves_probes_without_credentials() {
+        let auth = AuthChain::new().with_provider(Arc::new(crate::modules::auth::BasicAuthProvider::new(
+            "admin", "pass",
+        )));
+        let app = with_middleware(
+            Router::new()
+                .route("/metrics", get(|| async { "metrics" }))
+                .route("/v1/admin/uptime", get(|| async { "uptime" })),
+            Router::new().route("/health/live", get(health_live)),
+            auth,
+            TrustedProxies::default(),
+            RateLimiter::default(),
+            DrainState::default(),
+        );
+        let anonymous = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+        let live = app.clone().oneshot(anonymous("/health/live")).await.unwrap();
+        assert_eq!(live.status(), StatusCode::OK);
+        for uri in ["/metrics", "/v1/admin/uptime", "/v1/unknown"] {
+            let response = app.clone().oneshot(anonymous(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{uri}");
+        }
+        let authorized = Request::get("/metrics")
+            .header("authorization", "Basic YWRtaW46cGFzcw==")
+            .body(Body::empty())
+            .unwrap();
+        assert_eq!(app.oneshot(authorized).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn accepts_proxy_request_ids_and_generates_unique_ones() {
+        assert!(is_valid_request_id("3f2a9c1e-7b4d-4e8a-9c2f-1a2b3c4d5e6f"));
+        assert!(is_valid_request_id("lb:1234.abc_def"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("id with spaces"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+
+        let first = new_request_id();
+        let second = new_request_id();
+        assert_ne!(first, second);
+        assert!(is_valid_request_id(&first));
+    }
+
+    #[test]
+    fn resolves_client_ip_only_through_trusted_proxies() {
+        let proxies = TrustedProxies::new(vec!["10.0.0.0/8".parse().unwrap(), "fd00::/8".parse().unwrap()]);
+        let ingress: IpAddr = "10.0.0.1".parse().unwrap();
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.append(*name, HeaderValue::from_static(value));
+            }
+            headers
+        };
+
+        let spoofed = headers(&[("x-forwarded-for", "1.1.1.1, 203.0.113.7, 10.0.0.5")]);
+        assert_eq!(proxies.client_ip(ingress, &spoofed), "203.0.113.7".parse::<IpAddr>().unwrap());
+        let direct: IpAddr = "198.51.100.9".parse().unwrap();
+        assert_eq!(proxies.client_ip(direct, &spoofed), direct);
+        assert_eq!(proxies.client_ip(ingress, &HeaderMap::new()), ingress);
+        assert_eq!(
+            proxies.client_ip("::ffff:10.0.0.1".parse().unwrap(), &headers(&[("x-forwarded-for", "203.0.113.7")])),
+            "203.0.113.7".parse::<IpAddr>().unwrap()
+        );
+
+        let forwarded = headers(&[
+            ("forwarded", "for=\"[2001:db8::17]:4711\";proto=https"),
+            ("forwarded", "for=\"[fd00::2]\", for=10.0.0.9:8080"),
+            ("x-forwarded-for", "203.0.113.7"),
+        ]);
+        assert_eq!(proxies.client_ip(ingress, &forwarded), "2001:db8::17".parse::<IpAddr>().unwrap());
+
+        let obfuscated = headers(&[("forwarded", "for=203.0.113.7, for=_hidden, for=10.0.0.9")]);
+        assert_eq!(proxies.client_ip(ingress, &obfuscated), "10.0.0.9".parse::<IpAddr>().unwrap());
+
+        // A unix socket peer has no address; its headers are believed only when opted in.
+        assert_eq!(proxies.unix_client_ip(&spoofed), UNIX_PEER_IP);
+        let unix_proxy = proxies.with_unix_peers(true);
+        assert_eq!(unix_proxy.unix_client_ip(&spoofed), "203.0.113.7".parse::<IpAddr>().unwrap());
+        assert_eq!(unix_proxy.unix_client_ip(&headers(&[("x-forwarded-for", "198.51.100.9")])), direct);
+        assert_eq!(unix_proxy.unix_client_ip(&HeaderMap::new()), UNIX_PEER_IP);
+    }
+}
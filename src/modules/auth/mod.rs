@@ -0,0 +1,926 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+
+use axum::body::Bytes;
+use axum::http::header::AUTHORIZATION;
+use axum::http::{HeaderMap, Method};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use argon2::{Argon2, PasswordVerifier};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use tracing::warn;
+use zeroize::Zeroizing;
+
+use crate::modules::config::{
+    ApiKeyResolved, ApiTokenResolved, HmacAuthResolved, HmacKeyResolved, JwtAuthResolved, ServerAuthConfig,
+};
+
+const API_KEY_HEADER: &str = "x-api-key";
+pub const SIGNATURE_KEY_ID_HEADER: &str = "x-signature-key-id";
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-signature";
+/// Verified `username:password` digests kept by [`BasicAuthProvider`] before the cache
+/// is reset.
+const MAX_VERIFIED_CREDENTIALS: usize = 64;
+
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    pub authorization: Option<String>,
+    pub api_key: Option<String>,
+    pub signature: Option<RequestSignature>,
+    pub method: String,
+    pub path: String,
+    pub body: Bytes,
+}
+
+/// Signature headers sent by machine clients using HMAC request signing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSignature {
+    pub key_id: String,
+    pub timestamp: i64,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub subject: String,
+    pub provider: &'static str,
+    pub roles: Vec<String>,
+}
+
+/// Access level of a principal; each role includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Read-only requests (`GET`, `HEAD`, `OPTIONS`).
+    Read,
+    /// Managing jobs and nodes: creating, deleting, starting, stopping, pausing.
+    Operator,
+    /// Any request to `/v1/admin/*`: changing the instance (reload, drain) and reading its
+    /// runtime state, profiles and provenance.
+    Admin,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "read" => Some(Role::Read),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Read => "read",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Role required to send `method` to `path`.
+    pub fn required_for(method: &Method, path: &str) -> Self {
+        if path.starts_with("/v1/admin/") {
+            Role::Admin
+        } else if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+            Role::Read
+        } else {
+            Role::Operator
+        }
+    }
+}
+
+impl Principal {
+    /// Highest role among `roles`; principals without a known role are read-only.
+    pub fn role(&self) -> Role {
+        self.roles
+            .iter()
+            .filter_map(|role| Role::parse(role))
+            .max()
+            .unwrap_or(Role::Read)
+    }
+}
+
+/// Verifies request credentials and resolves the calling principal.
+///
+/// Custom providers (e.g. LDAP) implement this trait and are appended to the
+/// [`AuthChain`] built in `App::bootstrap`.
+pub trait AuthProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn verify(&self, credentials: &Credentials) -> Option<Principal>;
+
+    /// Whether `verify` does slow work, such as hashing a password or calling a
+    /// directory, so [`AuthChain::authenticate_blocking`] moves it off the async workers.
+    fn is_blocking(&self) -> bool {
+        false
+    }
+}
+
+/// Clones share the providers, so [`AuthChain::reload`] reaches every router holding one.
+#[derive(Clone, Default)]
+pub struct AuthChain {
+    providers: Arc<RwLock<Vec<Arc<dyn AuthProvider>>>>,
+    /// Replay cache of the HMAC provider, kept across [`AuthChain::reload`] so a signed
+    /// request cannot be replayed right after the providers are rebuilt.
+    seen_signatures: SeenSignatures,
+}
+
+/// Signatures accepted within the replay window, keyed by signature with its timestamp.
+type SeenSignatures = Arc<Mutex<HashMap<String, i64>>>;
+
+pub struct BasicAuthProvider {
+    username: String,
+    /// Plain password, or a bcrypt/Argon2 hash of it.
+    password: Zeroizing<String>,
+    /// SHA-256 of `username:password` credentials that matched the hash, so the slow
+    /// hash is not recomputed on every request of the same client.
+    verified: Mutex<HashSet<[u8; 32]>>,
+}
+
+pub struct ApiKeyAuthProvider {
+    keys: Vec<ApiKeyResolved>,
+}
+
+pub struct TokenAuthProvider {
+    tokens: Vec<ApiTokenResolved>,
+}
+
+pub struct JwtAuthProvider {
+    key: DecodingKey,
+    validation: Validation,
+    roles_claim: String,
+}
+
+pub struct HmacAuthProvider {
+    keys: Vec<HmacKeyResolved>,
+    replay_window_secs: u64,
+    seen_signatures: SeenSignatures,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtClaims {
+    sub: String,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Credentials {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            authorization: headers
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            api_key: headers
+                .get(API_KEY_HEADER)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            signature: RequestSignature::from_headers(headers),
+            ..Self::default()
+        }
+    }
+
+    /// Attaches the request line and buffered body, needed to verify signed requests.
+    pub fn with_request(mut self, method: &str, path: &str, body: Bytes) -> Self {
+        self.method = method.to_string();
+        self.path = path.to_string();
+        self.body = body;
+        self
+    }
+
+    fn scheme_value(&self, scheme: &str) -> Option<&str> {
+        let value = self.authorization.as_deref()?;
+        let (name, rest) = value.split_once(' ')?;
+        if name.eq_ignore_ascii_case(scheme) {
+            Some(rest.trim())
+        } else {
+            None
+        }
+    }
+}
+
+impl RequestSignature {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+        Some(Self {
+            key_id: header(SIGNATURE_KEY_ID_HEADER)?.to_string(),
+            timestamp: header(SIGNATURE_TIMESTAMP_HEADER)?.trim().parse().ok()?,
+            signature: header(SIGNATURE_HEADER)?.trim().to_ascii_lowercase(),
+        })
+    }
+}
+
+/// Compares secrets in time independent of where they differ; comparing digests also
+/// keeps the length of the expected secret from leaking.
+fn secrets_match(presented: &str, expected: &str) -> bool {
+    let presented = Sha256::digest(presented.as_bytes());
+    let expected = Sha256::digest(expected.as_bytes());
+    presented.as_slice().ct_eq(expected.as_slice()).into()
+}
+
+/// Whether a `server.auth.basic` password is a complete bcrypt (`$2b$12$...`) or
+/// Argon2 PHC (`$argon2id$v=19$...`) hash rather than the password itself.
+pub fn is_password_hash(password: &str) -> bool {
+    looks_like_password_hash(password) && validate_password_hash(password).is_ok()
+}
+
+fn looks_like_password_hash(password: &str) -> bool {
+    password.starts_with("$2") || password.starts_with("$argon2")
+}
+
+/// Checks that a password starting like a bcrypt or Argon2 hash parses as one, so a
+/// malformed hash fails config validation instead of rejecting every login.
+pub fn validate_password_hash(password: &str) -> Result<(), String> {
+    if !looks_like_password_hash(password) {
+        return Ok(());
+    }
+    if password.starts_with("$argon2") {
+        let parsed = argon2::PasswordHash::new(password)
+            .map_err(|err| format!("MUST be a valid Argon2 PHC hash: {err}"))?;
+        argon2::Algorithm::try_from(parsed.algorithm)
+            .map_err(|err| format!("MUST be a valid Argon2 PHC hash: {err}"))?;
+        if parsed.hash.is_none() || parsed.salt.is_none() {
+            return Err("MUST be a valid Argon2 PHC hash: salt or hash is missing".to_string());
+        }
+        return Ok(());
+    }
+
+    // `$2[abxy]$NN$` followed by the 22-character salt and 31-character hash.
+    let bytes = password.as_bytes();
+    let well_formed = bytes.len() == 60
+        && matches!(bytes[2], b'a' | b'b' | b'x' | b'y')
+        && bytes[3] == b'$'
+        && bytes[4..6].iter().all(u8::is_ascii_digit)
+        && bytes[6] == b'$'
+        && bytes[7..].iter().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'/'));
+    if !well_formed {
+        return Err("MUST be a valid bcrypt hash: expected `$2[abxy]$NN$` and 53 characters".to_string());
+    }
+    password
+        .parse::<bcrypt::HashParts>()
+        .map(|_| ())
+        .map_err(|err| format!("MUST be a valid bcrypt hash: {err}"))
+}
+
+/// Computes the hex-encoded HMAC-SHA256 signature over
+/// `"{timestamp}\n{METHOD}\n{path_and_query}\n{body}"`.
+pub fn sign_request(secret: &str, timestamp: i64, method: &str, path: &str, body: &[u8]) -> String {
+    let mut mac = signing_mac(secret, timestamp, method, path, body);
+    hex::encode(mac.finalize_reset().into_bytes())
+}
+
+fn signing_mac(secret: &str, timestamp: i64, method: &str, path: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(format!("{timestamp}\n{}\n{path}\n", method.to_ascii_uppercase()).as_bytes());
+    mac.update(body);
+    mac
+}
+
+impl AuthChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: &ServerAuthConfig) -> Self {
+        Self::new().with_config(config)
+    }
+
+    fn with_config(self, config: &ServerAuthConfig) -> Self {
+        let mut chain = self.with_provider(Arc::new(BasicAuthProvider::new(
+            &config.basic.username,
+            &config.basic.password,
+        )));
+
+        if !config.api_keys.is_empty() {
+            chain = chain.with_provider(Arc::new(ApiKeyAuthProvider::new(config.api_keys.clone())));
+        }
+
+        // Before JWT, so opaque tokens are not parsed as JWTs first.
+        if !config.tokens.is_empty() {
+            chain = chain.with_provider(Arc::new(TokenAuthProvider::new(config.tokens.clone())));
+        }
+
+        if let Some(jwt) = &config.jwt {
+            chain = chain.with_provider(Arc::new(JwtAuthProvider::new(jwt)));
+        }
+
+        if let Some(hmac) = &config.hmac {
+            let provider = HmacAuthProvider::new(hmac).with_seen_signatures(chain.seen_signatures.clone());
+            chain = chain.with_provider(Arc::new(provider));
+        }
+
+        chain
+    }
+
+    pub fn with_provider(self, provider: Arc<dyn AuthProvider>) -> Self {
+        self.providers.write().unwrap_or_else(|err| err.into_inner()).push(provider);
+        self
+    }
+
+    /// Replaces the providers with ones built from `config`, e.g. after a password, key or
+    /// token was rotated. Requests already being verified finish with the previous ones;
+    /// signatures seen before the reload are still rejected as replays.
+    pub fn reload(&self, config: &ServerAuthConfig) {
+        let next = Self {
+            providers: Arc::default(),
+            seen_signatures: self.seen_signatures.clone(),
+        }
+        .with_config(config)
+        .providers();
+        *self.providers.write().unwrap_or_else(|err| err.into_inner()) = next;
+    }
+
+    pub fn provider_names(&self) -> Vec<&'static str> {
+        self.providers().iter().map(|provider| provider.name()).collect()
+    }
+
+    pub fn accepts_signatures(&self) -> bool {
+        self.providers().iter().any(|provider| provider.name() == "hmac")
+    }
+
+    pub fn authenticate(&self, credentials: &Credentials) -> Option<Principal> {
+        self.providers()
+            .iter()
+            .find_map(|provider| provider.verify(credentials))
+    }
+
+    /// [`Self::authenticate`] for async callers: runs on the blocking pool when a provider
+    /// does slow work, so bcrypt or Argon2 checks do not stall other requests.
+    pub async fn authenticate_blocking(&self, credentials: Credentials) -> Option<Principal> {
+        if !self.providers().iter().any(|provider| provider.is_blocking()) {
+            return self.authenticate(&credentials);
+        }
+
+        let chain = self.clone();
+        tokio::task::spawn_blocking(move || chain.authenticate(&credentials))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    fn providers(&self) -> Vec<Arc<dyn AuthProvider>> {
+        self.providers.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+}
+
+impl std::fmt::Debug for AuthChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthChain")
+            .field("providers", &self.provider_names())
+            .finish()
+    }
+}
+
+impl BasicAuthProvider {
+    pub fn new(username: &str, password: &str) -> Self {
+        Self {
+            username: username.to_string(),
+            password: Zeroizing::new(password.to_string()),
+            verified: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Checks the password of `username`; the lock on the verified credentials is not
+    /// held while hashing, so concurrent requests are not serialized behind it.
+    fn password_matches(&self, username: &str, presented: &str) -> bool {
+        if !is_password_hash(&self.password) {
+            return secrets_match(presented, &self.password);
+        }
+
+        let mut hasher = Sha256::new();
+        hasher.update(username.as_bytes());
+        hasher.update(b":");
+        hasher.update(presented.as_bytes());
+        let digest: [u8; 32] = hasher.finalize().into();
+        if self.verified.lock().unwrap_or_else(|err| err.into_inner()).contains(&digest) {
+            return true;
+        }
+
+        let matches = if self.password.starts_with("$argon2") {
+            argon2::PasswordHash::new(&self.password)
+                .is_ok_and(|hash| Argon2::default().verify_password(presented.as_bytes(), &hash).is_ok())
+        } else {
+            bcrypt::verify(presented, &self.password).unwrap_or(false)
+        };
+        if matches && secrets_match(username, &self.username) {
+            let mut verified = self.verified.lock().unwrap_or_else(|err| err.into_inner());
+            if verified.len() >= MAX_VERIFIED_CREDENTIALS {
+                verified.clear();
+            }
+            verified.insert(digest);
+        }
+        matches
+    }
+}
+
+impl AuthProvider for BasicAuthProvider {
+    fn name(&self) -> &'static str {
+        "basic"
+    }
+
+    fn verify(&self, credentials: &Credentials) -> Option<Principal> {
+        let encoded = credentials.scheme_value("Basic")?;
+        let decoded = STANDARD.decode(encoded).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = decoded.split_once(':').unwrap_or((decoded.as_str(), ""));
+
+        // Both checks run, so a wrong username takes as long as a wrong password.
+        let username_matches = secrets_match(username, &self.username);
+        if self.password_matches(username, password) && username_matches {
+            Some(Principal {
+                subject: username.to_string(),
+                provider: self.name(),
+                roles: vec!["admin".to_string()],
+            })
+        } else {
+            None
+        }
+    }
+
+    fn is_blocking(&self) -> bool {
+        is_password_hash(&self.password)
+    }
+}
+
+impl ApiKeyAuthProvider {
+    pub fn new(keys: Vec<ApiKeyResolved>) -> Self {
+        Self { keys }
+    }
+}
+
+impl AuthProvider for ApiKeyAuthProvider {
+    fn name(&self) -> &'static str {
+        "api_key"
+    }
+
+    fn verify(&self, credentials: &Credentials) -> Option<Principal> {
+        let presented = credentials.api_key.as_deref()?;
+        self.keys
+            .iter()
+            .find(|key| secrets_match(presented, &key.key))
+            .map(|key| Principal {
+                subject: key.name.clone(),
+                provider: self.name(),
+                roles: key.roles.clone(),
+            })
+    }
+}
+
+impl TokenAuthProvider {
+    pub fn new(tokens: Vec<ApiTokenResolved>) -> Self {
+        Self { tokens }
+    }
+}
+
+impl AuthProvider for TokenAuthProvider {
+    fn name(&self) -> &'static str {
+        "token"
+    }
+
+    fn verify(&self, credentials: &Credentials) -> Option<Principal> {
+        let presented = credentials.scheme_value("Bearer")?;
+        let token = self
+            .tokens
+            .iter()
+            .find(|token| token.token.as_deref().is_some_and(|secret| secrets_match(presented, secret)))?;
+
+        if !token.enabled {
+            // Still in use by someone after the rotation that disabled it.
+            warn!(component = "auth", label = %token.label, message = "disabled api token presented");
+            return None;
+        }
+
+        Some(Principal {
+            subject: token.label.clone(),
+            provider: self.name(),
+            roles: token.roles.clone(),
+        })
+    }
+}
+
+impl JwtAuthProvider {
+    pub fn new(config: &JwtAuthResolved) -> Self {
+        let mut validation = Validation::new(Algorithm::HS256);
+        if let Some(issuer) = &config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &config.audience {
+            validation.set_audience(&[audience]);
+        } else {
+            validation.validate_aud = false;
+        }
+
+        Self {
+            key: DecodingKey::from_secret(config.secret.as_bytes()),
+            validation,
+            roles_claim: config.roles_claim.clone(),
+        }
+    }
+}
+
+impl AuthProvider for JwtAuthProvider {
+    fn name(&self) -> &'static str {
+        "jwt"
+    }
+
+    fn verify(&self, credentials: &Credentials) -> Option<Principal> {
+        let token = credentials.scheme_value("Bearer")?;
+        let data = jsonwebtoken::decode::<JwtClaims>(token, &self.key, &self.validation).ok()?;
+        let roles = data
+            .claims
+            .extra
+            .get(&self.roles_claim)
+            .and_then(|value| value.as_array())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Some(Principal {
+            subject: data.claims.sub,
+            provider: self.name(),
+            roles,
+        })
+    }
+}
+
+impl HmacAuthProvider {
+    pub fn new(config: &HmacAuthResolved) -> Self {
+        Self {
+            keys: config.keys.clone(),
+            replay_window_secs: config.replay_window_secs,
+            seen_signatures: SeenSignatures::default(),
+        }
+    }
+
+    /// Shares the replay cache with another provider, e.g. the one this provider replaces.
+    fn with_seen_signatures(mut self, seen_signatures: SeenSignatures) -> Self {
+        self.seen_signatures = seen_signatures;
+        self
+    }
+
+    fn verify_at(&self, credentials: &Credentials, now: i64) -> Option<Principal> {
+        let signature = credentials.signature.as_ref()?;
+        let key = self.keys.iter().find(|key| key.key_id == signature.key_id)?;
+
+        if now.abs_diff(signature.timestamp) > self.replay_window_secs {
+            return None;
+        }
+
+        let expected = hex::decode(&signature.signature).ok()?;
+        signing_mac(
+            &key.secret,
+            signature.timestamp,
+            &credentials.method,
+            &credentials.path,
+            &credentials.body,
+        )
+        .verify_slice(&expected)
+        .ok()?;
+
+        let mut seen = self.seen_signatures.lock().unwrap_or_else(|err| err.into_inner());
+        seen.retain(|_, timestamp| now.abs_diff(*timestamp) <= self.replay_window_secs);
+        if seen.insert(signature.signature.clone(), signature.timestamp).is_some() {
+            return None;
+        }
+
+        Some(Principal {
+            subject: key.key_id.clone(),
+            provider: self.name(),
+            roles: key.roles.clone(),
+        })
+    }
+}
+
+impl AuthProvider for HmacAuthProvider {
+    fn name(&self) -> &'static str {
+        "hmac"
+    }
+
+    fn verify(&self, credentials: &Credentials) -> Option<Principal> {
+        self.verify_at(credentials, chrono::Utc::now().timestamp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use axum::http::Method;
+    use jsonwebtoken::{EncodingKey, Header};
+
+    use super::{
+        is_password_hash, secrets_match, sign_request, validate_password_hash, ApiKeyAuthProvider, AuthChain, AuthProvider, BasicAuthProvider, Credentials,
+        HmacAuthProvider, JwtAuthProvider, Principal, RequestSignature, Role, TokenAuthProvider,
+    };
+    use crate::modules::config::{
+        ApiKeyResolved, ApiTokenResolved, BasicAuthResolved, HmacAuthResolved, HmacKeyResolved, JwtAuthResolved,
+        ServerAuthConfig,
+    };
+
+    fn basic(username: &str, password: &str) -> Credentials {
+        Credentials {
+            authorization: Some(format!(
+                "Basic {}",
+                STANDARD.encode(format!("{username}:{password}"))
+            )),
+            ..Credentials::default()
+        }
+    }
+
+    #[test]
+    fn basic_provider_checks_username_and_password() {
+        let provider = BasicAuthProvider::new("admin", "pass");
+        assert!(provider.verify(&basic("admin", "pass")).is_some());
+        assert!(provider.verify(&basic("admin", "wrong")).is_none());
+        assert!(provider.verify(&Credentials::default()).is_none());
+    }
+
+    #[test]
+    fn basic_provider_verifies_bcrypt_and_argon2_hashes() {
+        use argon2::password_hash::{PasswordHasher, SaltString};
+
+        let bcrypt_hash = bcrypt::hash("pass", 4).expect("bcrypt hash");
+        let params = argon2::Params::new(8, 1, 1, None).expect("argon2 params");
+        let argon2_hash = argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+            .hash_password(b"pass", &SaltString::from_b64("c2FsdHNhbHRzYWx0").expect("salt"))
+            .expect("argon2 hash")
+            .to_string();
+
+        for hash in [bcrypt_hash, argon2_hash] {
+            assert!(is_password_hash(&hash));
+            assert!(validate_password_hash(&hash).is_ok());
+            let provider = BasicAuthProvider::new("admin", &hash);
+            assert!(provider.verify(&basic("admin", "pass")).is_some());
+            assert!(provider.verify(&basic("admin", "pass")).is_some(), "cached verification");
+            assert!(provider.verify(&basic("admin", "wrong")).is_none());
+            assert!(provider.verify(&basic("other", "pass")).is_none());
+            assert!(provider.is_blocking());
+            assert_eq!(provider.verified.lock().unwrap().len(), 1, "only the matching credential is cached");
+        }
+        assert!(!is_password_hash("pass"));
+        assert!(!BasicAuthProvider::new("admin", "pass").is_blocking());
+        assert!(validate_password_hash("pass").is_ok());
+        for malformed in [
+            "$2b$04$truncated",
+            "$2$",
+            "$2q$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW",
+            "$2b$1x$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW",
+            "$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMU!",
+            "$argon2id$v=19$broken",
+            "$argon2id$v=19$m=8,t=1,p=1$c2FsdHNhbHRzYWx0",
+            "$argon2q$v=19$m=8,t=1,p=1$c2FsdHNhbHRzYWx0$aGFzaGhhc2hoYXNoaGFzaA",
+        ] {
+            assert!(!is_password_hash(malformed), "{malformed}");
+            assert!(validate_password_hash(malformed).is_err(), "{malformed}");
+        }
+    }
+
+    #[tokio::test]
+    async fn chain_verifies_password_hashes_on_the_blocking_pool() {
+        let hash = bcrypt::hash("pass", 4).expect("bcrypt hash");
+        let chain = AuthChain::new().with_provider(Arc::new(BasicAuthProvider::new("admin", &hash)));
+
+        let principal = chain.authenticate_blocking(basic("admin", "pass")).await.expect("principal");
+        assert_eq!(principal.subject, "admin");
+        assert!(chain.authenticate_blocking(basic("admin", "wrong")).await.is_none());
+        let plain = AuthChain::new().with_provider(Arc::new(BasicAuthProvider::new("admin", "pass")));
+        assert!(plain.authenticate_blocking(basic("admin", "pass")).await.is_some());
+    }
+
+    #[test]
+    fn api_key_provider_resolves_named_principal() {
+        let provider = ApiKeyAuthProvider::new(vec![ApiKeyResolved {
+            name: "ci".to_string(),
+            key: "secret-key".to_string().into(),
+            roles: vec!["read".to_string()],
+        }]);
+
+        let principal = provider
+            .verify(&Credentials {
+                api_key: Some("secret-key".to_string()),
+                ..Credentials::default()
+            })
+            .expect("known key");
+        assert_eq!(principal.subject, "ci");
+        assert_eq!(principal.roles, vec!["read".to_string()]);
+    }
+
+    #[test]
+    fn roles_gate_writes_and_admin_routes() {
+        let principal = |roles: &[&str]| Principal {
+            subject: "client".to_string(),
+            provider: "api_key",
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        };
+
+        assert_eq!(principal(&["read"]).role(), Role::Read);
+        assert_eq!(principal(&["read", "operator"]).role(), Role::Operator);
+        assert_eq!(principal(&["ingest"]).role(), Role::Read);
+        assert_eq!(principal(&[]).role(), Role::Read);
+        assert_eq!(Role::required_for(&Method::GET, "/v1/jobs"), Role::Read);
+        assert_eq!(Role::required_for(&Method::GET, "/v1/admin/uptime"), Role::Admin);
+        assert_eq!(Role::required_for(&Method::GET, "/v1/admin/profile/cpu"), Role::Admin);
+        assert_eq!(Role::required_for(&Method::HEAD, "/v1/admin/log-level"), Role::Admin);
+        assert_eq!(Role::required_for(&Method::POST, "/v1/jobs/full-sync/start"), Role::Operator);
+        assert_eq!(Role::required_for(&Method::DELETE, "/v1/mainnet/jobs/full-sync"), Role::Operator);
+        assert_eq!(Role::required_for(&Method::POST, "/v1/admin/reload"), Role::Admin);
+    }
+
+    #[test]
+    fn token_provider_matches_bearer_tokens_and_rejects_disabled_ones() {
+        let token = |label: &str, secret: Option<&str>, enabled| ApiTokenResolved {
+            label: label.to_string(),
+            token: secret.map(|secret| secret.to_string().into()),
+            roles: vec!["read".to_string()],
+            enabled,
+        };
+        let provider = TokenAuthProvider::new(vec![
+            token("dashboard", Some("dashboard-token"), true),
+            token("old-etl", Some("old-etl-token"), false),
+            token("retired", None, false),
+        ]);
+        let bearer = |value: &str| Credentials {
+            authorization: Some(format!("Bearer {value}")),
+            ..Credentials::default()
+        };
+
+        let principal = provider.verify(&bearer("dashboard-token")).expect("enabled token");
+        assert_eq!(principal.subject, "dashboard");
+        assert_eq!(principal.provider, "token");
+        assert!(provider.verify(&bearer("old-etl-token")).is_none());
+        assert!(provider.verify(&bearer("dashboard-token-2")).is_none());
+        assert!(provider.verify(&basic("dashboard", "dashboard-token")).is_none());
+        assert!(secrets_match("same", "same"));
+        assert!(!secrets_match("same", "samf"));
+    }
+
+    #[test]
+    fn jwt_provider_reads_roles_claim() {
+        let config = JwtAuthResolved {
+            secret: "jwt-secret".to_string().into(),
+            issuer: Some("sso".to_string()),
+            audience: None,
+            roles_claim: "roles".to_string(),
+        };
+        let token = jsonwebtoken::encode(
+            &Header::default(),
+            &serde_json::json!({
+                "sub": "alice",
+                "iss": "sso",
+                "exp": chrono::Utc::now().timestamp() + 60,
+                "roles": ["operator"]
+            }),
+            &EncodingKey::from_secret(b"jwt-secret"),
+        )
+        .expect("encode token");
+
+        let principal = JwtAuthProvider::new(&config)
+            .verify(&Credentials {
+                authorization: Some(format!("Bearer {token}")),
+                ..Credentials::default()
+            })
+            .expect("valid token");
+        assert_eq!(principal.subject, "alice");
+        assert_eq!(principal.roles, vec!["operator".to_string()]);
+    }
+
+    fn signed(key_id: &str, secret: &str, timestamp: i64, body: &str) -> Credentials {
+        Credentials {
+            signature: Some(RequestSignature {
+                key_id: key_id.to_string(),
+                timestamp,
+                signature: sign_request(secret, timestamp, "POST", "/v1/jobs", body.as_bytes()),
+            }),
+            ..Credentials::default()
+        }
+        .with_request("POST", "/v1/jobs", body.as_bytes().to_vec().into())
+    }
+
+    #[test]
+    fn sign_request_matches_reference_vector() {
+        assert_eq!(
+            sign_request("hmac-secret", 1_700_000_000, "get", "/v1/jobs?limit=10", b""),
+            "d4a05d16949a1dc0f2f2cc07e546bd615165e556344f0f5547021235bf22756d"
+        );
+    }
+
+    #[test]
+    fn hmac_provider_enforces_signature_and_replay_window() {
+        let provider = HmacAuthProvider::new(&HmacAuthResolved {
+            replay_window_secs: 300,
+            keys: vec![HmacKeyResolved {
+                key_id: "bot".to_string(),
+                secret: "hmac-secret".to_string().into(),
+                roles: vec!["read".to_string()],
+            }],
+        });
+        let now = 1_700_000_000;
+
+        let principal = provider
+            .verify_at(&signed("bot", "hmac-secret", now, "{}"), now)
+            .expect("valid signature");
+        assert_eq!(principal.subject, "bot");
+        assert_eq!(principal.roles, vec!["read".to_string()]);
+
+        assert!(provider.verify_at(&signed("bot", "hmac-secret", now, "{}"), now).is_none());
+        assert!(provider.verify_at(&signed("bot", "wrong", now + 1, "{}"), now).is_none());
+        assert!(provider.verify_at(&signed("bot", "hmac-secret", now - 301, "{}"), now).is_none());
+        assert!(provider.verify_at(&signed("other", "hmac-secret", now + 2, "{}"), now).is_none());
+
+        let mut tampered = signed("bot", "hmac-secret", now + 3, "{}");
+        tampered.body = b"{\"job_id\":\"x\"}".to_vec().into();
+        assert!(provider.verify_at(&tampered, now).is_none());
+    }
+
+    struct StaticProvider;
+
+    impl AuthProvider for StaticProvider {
+        fn name(&self) -> &'static str {
+            "static"
+        }
+
+        fn verify(&self, _credentials: &Credentials) -> Option<Principal> {
+            Some(Principal {
+                subject: "custom".to_string(),
+                provider: self.name(),
+                roles: vec![],
+            })
+        }
+    }
+
+    #[test]
+    fn chain_falls_through_to_custom_provider() {
+        let chain = AuthChain::new()
+            .with_provider(Arc::new(BasicAuthProvider::new("admin", "pass")))
+            .with_provider(Arc::new(StaticProvider));
+
+        let principal = chain.authenticate(&Credentials::default()).expect("custom provider");
+        assert_eq!(principal.provider, "static");
+        assert_eq!(chain.provider_names(), vec!["basic", "static"]);
+    }
+
+    #[test]
+    fn reload_rotates_credentials_of_every_clone() {
+        let config = |password: &str| ServerAuthConfig {
+            basic: BasicAuthResolved {
+                username: "admin".to_string(),
+                password: password.to_string().into(),
+                password_file: None,
+            },
+            api_keys: Vec::new(),
+            tokens: Vec::new(),
+            jwt: None,
+            hmac: None,
+        };
+        let chain = AuthChain::from_config(&config("old"));
+        let router_chain = chain.clone();
+        assert!(router_chain.authenticate(&basic("admin", "old")).is_some());
+
+        chain.reload(&config("new"));
+        assert!(router_chain.authenticate(&basic("admin", "old")).is_none());
+        assert!(router_chain.authenticate(&basic("admin", "new")).is_some());
+        assert_eq!(router_chain.provider_names(), vec!["basic"]);
+    }
+
+    #[test]
+    fn reload_keeps_replay_cache_of_hmac_provider() {
+        let config = |secret: &str| ServerAuthConfig {
+            basic: BasicAuthResolved {
+                username: "admin".to_string(),
+                password: "pass".to_string().into(),
+                password_file: None,
+            },
+            api_keys: Vec::new(),
+            tokens: Vec::new(),
+            jwt: None,
+            hmac: Some(HmacAuthResolved {
+                replay_window_secs: 300,
+                keys: vec![HmacKeyResolved {
+                    key_id: "bot".to_string(),
+                    secret: secret.to_string().into(),
+                    roles: vec!["read".to_string()],
+                }],
+            }),
+        };
+        let chain = AuthChain::from_config(&config("hmac-secret"));
+        let now = chrono::Utc::now().timestamp();
+        let request = signed("bot", "hmac-secret", now, "{}");
+        assert!(chain.authenticate(&request).is_some());
+
+        chain.reload(&config("hmac-secret"));
+        assert!(chain.authenticate(&request).is_none());
+        assert!(chain.authenticate(&signed("bot", "hmac-secret", now + 1, "{}")).is_some());
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use bitcoin::consensus::encode;
+use bitcoin::Transaction;
+use serde::Serialize;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::mempool::MempoolRunner;
+use crate::modules::rpc::RpcClient;
+use crate::modules::storage::SchemaFeatures;
+
+/// Largest transaction accepted for relay: `MAX_STANDARD_TX_WEIGHT` of Bitcoin Core, whose
+/// size in bytes is never above its weight.
+pub const MAX_TX_BYTES: usize = 400_000;
+
+#[derive(Debug, Error)]
+pub enum BroadcastError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error("transaction {txid} rejected by node: {reason}")]
+    Rejected { txid: String, reason: String },
+    #[error("node unavailable: {0}")]
+    NodeUnavailable(String),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Transaction relayed to the node.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BroadcastResult {
+    pub txid: String,
+    pub wtxid: String,
+    /// Serialized size in bytes.
+    pub size: usize,
+    pub vsize: usize,
+}
+
+/// Relays raw transactions through `sendrawtransaction` of the network's node and
+/// records every attempt in `broadcasts`.
+#[derive(Clone)]
+pub struct BroadcastService {
+    pool: PgPool,
+    schema: SchemaFeatures,
+    rpc: Option<RpcClient>,
+    mempool: Option<MempoolRunner>,
+}
+
+impl fmt::Debug for BroadcastService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BroadcastService")
+            .field("schema", &self.schema)
+            .field("rpc", &self.rpc.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl BroadcastService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+            rpc: None,
+            mempool: None,
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Node the transactions are relayed through; without it every broadcast fails with
+    /// [`BroadcastError::NodeUnavailable`].
+    pub fn with_rpc(mut self, rpc: RpcClient) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+
+    /// Mempool watcher that picks an accepted transaction up right away instead of on its
+    /// next poll.
+    pub fn with_mempool(mut self, mempool: MempoolRunner) -> Self {
+        self.mempool = Some(mempool);
+        self
+    }
+
+    /// Decodes `raw_hex`, relays it and records the outcome for `principal`. Invalid hex
+    /// is rejected before reaching the node and is not recorded.
+    pub async fn broadcast(&self, raw_hex: &str, principal: Option<&str>) -> Result<BroadcastResult, BroadcastError> {
+        let tx = decode_transaction(raw_hex)?;
+        let result = BroadcastResult {
+            txid: tx.compute_txid().to_string(),
+            wtxid: tx.compute_wtxid().to_string(),
+            size: tx.total_size(),
+            vsize: tx.vsize(),
+        };
+        let Some(rpc) = &self.rpc else {
+            return Err(BroadcastError::NodeUnavailable("rpc client is not configured".to_string()));
+        };
+
+        let outcome = rpc.send_raw_transaction(raw_hex.trim()).await;
+        let (status, error) = match &outcome {
+            Ok(_) => ("accepted", None),
+            Err(err) => match err.node_message() {
+                Some(reason) => ("rejected", Some(reason.to_string())),
+                None => ("failed", Some(err.to_string())),
+            },
+        };
+        self.record(&result, status, error.as_deref(), principal).await?;
+
+        if let Err(err) = outcome {
+            return Err(match err.node_message() {
+                Some(reason) => BroadcastError::Rejected {
+                    txid: result.txid,
+                    reason: reason.to_string(),
+                },
+                None => BroadcastError::NodeUnavailable(err.to_string()),
+            });
+        }
+        info!(component = "broadcast", txid = %result.txid, size = result.size, message = "transaction relayed");
+
+        if let Some(mempool) = &self.mempool {
+            // The watcher finds the transaction on its next poll anyway.
+            if let Err(err) = mempool.track(&result.txid).await {
+                warn!(
+                    component = "broadcast",
+                    txid = %result.txid,
+                    error = %err,
+                    message = "relayed transaction not tracked yet"
+                );
+            }
+        }
+        Ok(result)
+    }
+
+    async fn record(
+        &self,
+        result: &BroadcastResult,
+        status: &str,
+        error: Option<&str>,
+        principal: Option<&str>,
+    ) -> Result<(), BroadcastError> {
+        if !self.schema.broadcasts {
+            return Ok(());
+        }
+
+        sqlx::query("INSERT INTO broadcasts (txid, size, status, error, principal) VALUES ($1, $2, $3, $4, $5)")
+            .bind(&result.txid)
+            .bind(result.size as i32)
+            .bind(status)
+            .bind(error)
+            .bind(principal)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+fn decode_transaction(raw_hex: &str) -> Result<Transaction, BroadcastError> {
+    let raw_hex = raw_hex.trim();
+    if raw_hex.is_empty() {
+        return Err(BroadcastError::Validation("hex MUST NOT be empty".to_string()));
+    }
+    if raw_hex.len() > MAX_TX_BYTES * 2 {
+        return Err(BroadcastError::Validation(format!(
+            "hex MUST encode at most {MAX_TX_BYTES} bytes"
+        )));
+    }
+    let bytes = hex::decode(raw_hex)
+        .map_err(|err| BroadcastError::Validation(format!("hex MUST be a hex string: {err}")))?;
+    encode::deserialize(&bytes)
+        .map_err(|err| BroadcastError::Validation(format!("hex MUST be a serialized transaction: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Spends the genesis coinbase output to an empty script; never valid on-chain, but well formed.
+    const TX_HEX: &str = "0200000001\
+        3ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000ffffffff\
+        01e8030000000000000000000000";
+
+    #[test]
+    fn decodes_raw_transactions() {
+        let tx = decode_transaction(&format!(" {TX_HEX}\n")).expect("decode transaction");
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.output[0].value.to_sat(), 1000);
+
+        for invalid in ["", "zz", "0200", &format!("{TX_HEX}00")] {
+            assert!(matches!(decode_transaction(invalid), Err(BroadcastError::Validation(_))), "{invalid}");
+        }
+        let oversized = "00".repeat(MAX_TX_BYTES + 1);
+        assert!(matches!(decode_transaction(&oversized), Err(BroadcastError::Validation(_))));
+    }
+}
@@ -0,0 +1,397 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::info;
+
+use crate::modules::config::IndexerConfig;
+use crate::modules::indexer::chain::{Chain, NetworkParams};
+use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::scripts::AddressEncoding;
+
+/// Challenge of the default public signet.
+pub const DEFAULT_SIGNET_CHALLENGE: &str = "512103ad5e0edad18cb1f0fc0d28a3d4f1f3e445640337489abb10404f2d1e086be430210359ef5021964fe22d6f8e05b2463c9540ce96883fe3b278760f048f5189f2e6c452ae";
+
+/// Approximate average mainnet block weight (WU) from the given height on.
+///
+/// Early blocks were nearly empty, blocks filled up through 2015-2016 and have
+/// stayed close to the 4M WU limit since the 2023 inscription wave.
+const MAINNET_BLOCK_WEIGHT_ERAS: &[(i32, u64)] = &[
+    (0, 1_000),
+    (100_000, 80_000),
+    (150_000, 240_000),
+    (200_000, 800_000),
+    (250_000, 700_000),
+    (300_000, 1_400_000),
+    (350_000, 1_800_000),
+    (380_000, 3_000_000),
+    (420_000, 3_600_000),
+    (480_000, 3_700_000),
+    (500_000, 3_400_000),
+    (550_000, 3_300_000),
+    (600_000, 3_800_000),
+    (700_000, 3_600_000),
+    (770_000, 3_990_000),
+];
+
+#[derive(Debug, Error)]
+pub enum ChainError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    #[error("node does not match indexer.network '{network}': {reason}")]
+    NetworkMismatch { network: String, reason: String },
+}
+
+/// Subset of the `getblockchaininfo` response used to check the node network and
+/// reported by `GET /v1/node/info`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcBlockchainInfo {
+    pub chain: String,
+    #[serde(default)]
+    pub signet_challenge: Option<String>,
+    #[serde(default)]
+    pub blocks: u64,
+    #[serde(default)]
+    pub headers: u64,
+    #[serde(default, rename = "bestblockhash")]
+    pub best_block_hash: String,
+    #[serde(default)]
+    pub difficulty: f64,
+    #[serde(default, rename = "mediantime")]
+    pub median_time: i64,
+    #[serde(default, rename = "verificationprogress")]
+    pub verification_progress: f64,
+    #[serde(default, rename = "initialblockdownload")]
+    pub initial_block_download: bool,
+    #[serde(default)]
+    pub size_on_disk: u64,
+    #[serde(default)]
+    pub pruned: bool,
+}
+
+/// Consensus and address parameters of the configured network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainParams {
+    pub chain: Chain,
+    pub network: String,
+    /// `chain` value reported by `getblockchaininfo`.
+    pub rpc_chain: &'static str,
+    pub genesis_hash: String,
+    /// P2P message start bytes as hex.
+    pub magic: String,
+    /// `None` on chains without segwit.
+    pub bech32_hrp: Option<&'static str>,
+    base58_prefixes: &'static [char],
+    address_encoding: AddressEncoding,
+    /// Block signing challenge; set for signet only.
+    pub signet_challenge: Option<String>,
+}
+
+impl ChainParams {
+    /// Config validation guarantees a known chain and a network it supports; anything
+    /// else falls back to the regtest of the chain.
+    pub fn from_config(config: &IndexerConfig) -> Self {
+        let chain = Chain::from_name(&config.chain).unwrap_or(Chain::Bitcoin);
+        let (network, params) = match chain.network_params(&config.network) {
+            Some(params) => (config.network.clone(), params),
+            None => (
+                "regtest".to_string(),
+                chain.network_params("regtest").expect("every chain has regtest"),
+            ),
+        };
+        let address_encoding = match chain {
+            Chain::Bitcoin => AddressEncoding::Bitcoin(bitcoin_network(&network)),
+            _ => params.address_encoding(),
+        };
+
+        let mut chain_params = Self {
+            chain,
+            network,
+            rpc_chain: params.rpc_chain,
+            genesis_hash: params.genesis_hash.to_string(),
+            magic: params.magic.to_string(),
+            bech32_hrp: params.bech32_hrp,
+            base58_prefixes: params.base58_leading,
+            address_encoding,
+            signet_challenge: None,
+        };
+
+        if chain == Chain::Bitcoin && chain_params.network == "signet" {
+            let signet = config.signet.as_ref();
+            let challenge = signet
+                .map(|signet| signet.challenge.clone())
+                .unwrap_or_else(|| DEFAULT_SIGNET_CHALLENGE.to_string());
+            chain_params.magic = signet
+                .and_then(|signet| signet.magic.clone())
+                .unwrap_or_else(|| signet_magic(&challenge));
+            if let Some(genesis_hash) = signet.and_then(|signet| signet.genesis_hash.clone()) {
+                chain_params.genesis_hash = genesis_hash;
+            }
+            chain_params.signet_challenge = Some(challenge);
+        }
+
+        chain_params
+    }
+
+    /// Rejects addresses that clearly belong to another network (by bech32 HRP or
+    /// base58 leading character). Unrecognized formats are accepted as is.
+    pub fn check_address(&self, address: &str) -> Result<(), String> {
+        let lower = address.to_ascii_lowercase();
+        if let Some(hrp) = known_networks()
+            .filter_map(|params| params.bech32_hrp)
+            .find(|hrp| lower.starts_with(&format!("{hrp}1")))
+        {
+            if Some(hrp) != self.bech32_hrp {
+                return Err(self.foreign_address(address));
+            }
+            return Ok(());
+        }
+
+        let Some(first) = address.chars().next() else {
+            return Ok(());
+        };
+        let known_base58 = known_networks().any(|params| params.base58_leading.contains(&first));
+        if known_base58 && !self.base58_prefixes.contains(&first) {
+            return Err(self.foreign_address(address));
+        }
+
+        Ok(())
+    }
+
+    /// Bitcoin network with the same key and address versions, used for descriptors.
+    pub fn bitcoin_network(&self) -> bitcoin::Network {
+        bitcoin_network(&self.network)
+    }
+
+    /// Encoding for decoding output scripts into addresses.
+    pub fn address_encoding(&self) -> AddressEncoding {
+        self.address_encoding
+    }
+
+    /// Whether typical block weights of the network are known; test networks and
+    /// other chains vary too much.
+    pub fn has_block_weight_profile(&self) -> bool {
+        self.chain == Chain::Bitcoin && self.network == "mainnet"
+    }
+
+    /// Total of the average block weight over heights `from..=to`, or `None`
+    /// without a block weight profile.
+    pub fn expected_weight(&self, from: i32, to: i32) -> Option<u64> {
+        if !self.has_block_weight_profile() {
+            return None;
+        }
+
+        let mut total = 0u64;
+        for (index, &(start, weight)) in MAINNET_BLOCK_WEIGHT_ERAS.iter().enumerate() {
+            let end = MAINNET_BLOCK_WEIGHT_ERAS
+                .get(index + 1)
+                .map_or(i32::MAX, |&(next_start, _)| next_start - 1);
+            let (low, high) = (from.max(start), to.min(end));
+            if low <= high {
+                total += (i64::from(high) - i64::from(low) + 1) as u64 * weight;
+            }
+        }
+        Some(total)
+    }
+
+    fn foreign_address(&self, address: &str) -> String {
+        format!(
+            "address {address} does not belong to network {} of chain {}",
+            self.network,
+            self.chain.name()
+        )
+    }
+
+    /// Checks that the RPC node runs the configured chain: chain name, genesis
+    /// block and, for signet, the block signing challenge.
+    pub async fn verify_node(&self, rpc: &RpcClient) -> Result<(), ChainError> {
+        let info = rpc.get_blockchain_info().await?;
+        if info.chain != self.rpc_chain {
+            return Err(self.mismatch(format!("node chain is '{}', expected '{}'", info.chain, self.rpc_chain)));
+        }
+
+        let genesis_hash = rpc.get_block_hash(0).await?;
+        if genesis_hash != self.genesis_hash {
+            return Err(self.mismatch(format!(
+                "node genesis is {genesis_hash}, expected {}",
+                self.genesis_hash
+            )));
+        }
+
+        if let (Some(expected), Some(actual)) = (&self.signet_challenge, &info.signet_challenge) {
+            if !expected.eq_ignore_ascii_case(actual) {
+                return Err(self.mismatch(format!("node signet challenge is {actual}, expected {expected}")));
+            }
+        }
+
+        info!(
+            component = "chain",
+            chain = self.chain.name(),
+            network = %self.network,
+            genesis_hash = %self.genesis_hash,
+            magic = %self.magic,
+            message = "node network verified"
+        );
+        Ok(())
+    }
+
+    fn mismatch(&self, reason: String) -> ChainError {
+        ChainError::NetworkMismatch {
+            network: self.network.clone(),
+            reason,
+        }
+    }
+}
+
+fn bitcoin_network(network: &str) -> bitcoin::Network {
+    match network {
+        "mainnet" => bitcoin::Network::Bitcoin,
+        "testnet" => bitcoin::Network::Testnet,
+        "testnet4" => bitcoin::Network::Testnet4,
+        "signet" => bitcoin::Network::Signet,
+        _ => bitcoin::Network::Regtest,
+    }
+}
+
+/// Parameters of every network of every chain, used to tell which one an address belongs to.
+fn known_networks() -> impl Iterator<Item = NetworkParams> {
+    Chain::ALL.into_iter().flat_map(|chain| {
+        chain
+            .networks()
+            .iter()
+            .filter_map(move |network| chain.network_params(network))
+    })
+}
+
+/// Message start of a signet: the first 4 bytes of `sha256d(compact_size(len) || challenge)`.
+///
+/// `challenge` MUST be valid hex; config validation guarantees that.
+pub fn signet_magic(challenge: &str) -> String {
+    let challenge = hex::decode(challenge).unwrap_or_default();
+    let mut serialized = compact_size(challenge.len());
+    serialized.extend_from_slice(&challenge);
+
+    let digest = Sha256::digest(Sha256::digest(&serialized));
+    hex::encode(&digest[..4])
+}
+
+fn compact_size(len: usize) -> Vec<u8> {
+    match len {
+        0..=0xfc => vec![len as u8],
+        0xfd..=0xffff => {
+            let mut bytes = vec![0xfd];
+            bytes.extend_from_slice(&(len as u16).to_le_bytes());
+            bytes
+        }
+        _ => {
+            let mut bytes = vec![0xfe];
+            bytes.extend_from_slice(&(len as u32).to_le_bytes());
+            bytes
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{signet_magic, ChainParams, DEFAULT_SIGNET_CHALLENGE};
+    use crate::modules::config::{BatchingConfig, ConcurrencyConfig, IndexerConfig, PollConfig, SignetConfig};
+
+    fn indexer_config(network: &str, signet: Option<SignetConfig>) -> IndexerConfig {
+        IndexerConfig {
+            chain: "bitcoin".to_string(),
+            network: network.to_string(),
+            signet,
+            reorg_depth: 6,
+            poll: PollConfig {
+                tip_interval_ms: 1000,
+                mempool_interval_ms: 1000,
+                block_template_interval_ms: None,
+                gap_scan_interval_ms: None,
+            },
+            concurrency: ConcurrencyConfig {
+                max_jobs: 1,
+                rpc_parallelism: 1,
+                db_writer_parallelism: 1,
+            },
+            batching: BatchingConfig {
+                blocks_per_batch: 10,
+                txs_per_batch: 100,
+            },
+            store_raw_blocks: false,
+            block_filters: false,
+            prevout_rpc_fallback: false,
+            events: None,
+            headers: None,
+            stats: None,
+            retention: None,
+        }
+    }
+
+    #[test]
+    fn derives_signet_magic_from_challenge() {
+        assert_eq!(signet_magic(DEFAULT_SIGNET_CHALLENGE), "0a03cf40");
+
+        let params = ChainParams::from_config(&indexer_config(
+            "signet",
+            Some(SignetConfig {
+                challenge: "51".to_string(),
+                magic: None,
+                genesis_hash: None,
+            }),
+        ));
+        assert_eq!(params.signet_challenge.as_deref(), Some("51"));
+        assert_eq!(params.magic, signet_magic("51"));
+        assert_ne!(params.magic, "0a03cf40");
+
+        let params = ChainParams::from_config(&indexer_config("testnet4", None));
+        assert_eq!(params.rpc_chain, "testnet4");
+        assert_eq!(params.magic, "1c163f28");
+    }
+
+    #[test]
+    fn sums_expected_block_weight_per_era() {
+        let mainnet = ChainParams::from_config(&indexer_config("mainnet", None));
+
+        assert_eq!(mainnet.expected_weight(0, 0), Some(1_000));
+        assert_eq!(mainnet.expected_weight(99_999, 100_000), Some(1_000 + 80_000));
+        assert_eq!(mainnet.expected_weight(800_000, 800_009), Some(10 * 3_990_000));
+        assert_eq!(mainnet.expected_weight(10, 9), Some(0));
+        assert!(mainnet.expected_weight(0, 250_000) < mainnet.expected_weight(700_000, 750_000));
+        assert_eq!(
+            ChainParams::from_config(&indexer_config("testnet4", None)).expected_weight(0, 10),
+            None
+        );
+    }
+
+    #[test]
+    fn checks_address_network() {
+        let mainnet = ChainParams::from_config(&indexer_config("mainnet", None));
+        let testnet4 = ChainParams::from_config(&indexer_config("testnet4", None));
+        let regtest = ChainParams::from_config(&indexer_config("regtest", None));
+
+        assert!(mainnet.check_address("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq").is_ok());
+        assert!(mainnet.check_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").is_ok());
+        assert!(mainnet.check_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").is_err());
+        assert!(mainnet.check_address("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn").is_err());
+
+        assert!(testnet4.check_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").is_ok());
+        assert!(testnet4.check_address("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc").is_ok());
+        assert!(testnet4.check_address("bcrt1qexample").is_err());
+        assert!(testnet4.check_address("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy").is_err());
+
+        assert!(regtest.check_address("bcrt1qexample").is_ok());
+        assert!(regtest.check_address("addr1").is_ok());
+
+        let litecoin = ChainParams::from_config(&IndexerConfig {
+            chain: "litecoin".to_string(),
+            ..indexer_config("mainnet", None)
+        });
+        assert_eq!(litecoin.rpc_chain, "main");
+        assert_eq!(litecoin.magic, "fbc0b6db");
+        assert!(litecoin.check_address("ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9").is_ok());
+        assert!(litecoin.check_address("LUEweDxDA4WhvWiNXXSxjM9CYzHPJv4QQF").is_ok());
+        assert!(litecoin.check_address("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq").is_err());
+        assert!(litecoin.check_address("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").is_err());
+        assert!(mainnet.check_address("LUEweDxDA4WhvWiNXXSxjM9CYzHPJv4QQF").is_err());
+        assert!(!litecoin.has_block_weight_profile());
+    }
+}
@@ -1,12 +1,103 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
+use std::net::IpAddr;
 use std::path::{Path, PathBuf};
 
+use ipnet::IpNet;
+
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use utoipa::ToSchema;
+use zeroize::Zeroizing;
+
+use crate::modules::alerting;
+use crate::modules::auth;
+use crate::modules::chain::ChainParams;
+use crate::modules::descriptors::{self, WatchDescriptor};
+use crate::modules::indexer::chain::Chain;
+use crate::modules::protocols;
 
 const DEFAULT_CONFIG_PATH: &str = "config/indexer.yaml";
+/// Prefix of env vars overriding YAML keys, e.g. `INDEXER__SERVER__BIND_PORT=8443`.
+const ENV_OVERRIDE_PREFIX: &str = "INDEXER__";
+const ENV_OVERRIDE_SEPARATOR: &str = "__";
+const DEFAULT_RETRY_MAX_BACKOFF_MS: u64 = 600_000;
+const DEFAULT_SHUTDOWN_GRACE_PERIOD_MS: u64 = 30_000;
+const DEFAULT_BIND_HOST: &str = "0.0.0.0";
+const DEFAULT_BIND_PORT: u16 = 8080;
+/// Read and write for the owner and its group, e.g. a reverse proxy sidecar.
+const DEFAULT_UNIX_SOCKET_MODE: u32 = 0o660;
+const UNIX_SOCKET_PREFIX: &str = "unix:";
+/// The admin listener is meant for probes and operators on the same host.
+const DEFAULT_ADMIN_BIND_HOST: &str = "127.0.0.1";
+const DEFAULT_STORAGE_RETRY_INTERVAL_MS: u64 = 2_000;
+/// Loading the block index of a mainnet node after an unclean shutdown takes minutes.
+const DEFAULT_RPC_STARTUP_MS: u64 = 600_000;
+const DEFAULT_CERT_EXPIRY_WARNING_DAYS: u32 = 30;
+const DEFAULT_TLS_WATCH_INTERVAL_MS: u64 = 10_000;
+const DEFAULT_REPLICATION_CHECK_INTERVAL_MS: u64 = 30_000;
+const DEFAULT_REPLICATION_MAX_SLOT_LAG_BYTES: u64 = 1024 * 1024 * 1024;
+const DEFAULT_SINK_TOPIC_PREFIX: &str = "indexer";
+const DEFAULT_SINK_BATCH_SIZE: u32 = 500;
+const MAX_SINK_BATCH_SIZE: u32 = 10_000;
+const DEFAULT_SINK_POLL_INTERVAL_MS: u64 = 1_000;
+const DEFAULT_SINK_RETAIN_PUBLISHED_HOURS: u32 = 24;
+const DEFAULT_LOG_LEVEL: &str = "info";
+const DEFAULT_LOG_MAX_FILES: usize = 7;
+const LOG_FORMATS: &[&str] = &["json", "pretty", "compact"];
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+const LOG_ROTATIONS: &[&str] = &["daily", "hourly", "never"];
+const DEFAULT_OTLP_SERVICE_NAME: &str = "blockchain-indexer";
+const DEFAULT_OTLP_EXPORT_INTERVAL_MS: u64 = 5_000;
+const DEFAULT_OTLP_MAX_QUEUE_SIZE: usize = 2_048;
+/// Same alert, e.g. failures of one job, reported at most once per interval.
+const DEFAULT_ALERT_MIN_INTERVAL_MS: u64 = 300_000;
+const DEFAULT_ALERT_ERROR_THRESHOLD: u32 = 10;
+const DEFAULT_ALERT_ERROR_WINDOW_MS: u64 = 60_000;
+/// `errors_total` types counted towards repeated error alerts: node and Postgres failures.
+const DEFAULT_ALERT_ERROR_TYPES: &[&str] = &["rpc", "db_write", "node_health", "sink_publish"];
+const DEFAULT_ALERT_RULES_INTERVAL_MS: u64 = 30_000;
+/// About an hour of blocks; a single slow block does not page anyone.
+const DEFAULT_ALERT_TIP_LAG_BLOCKS: u64 = 6;
+const DEFAULT_ALERT_TIP_LAG_FOR_MS: u64 = 600_000;
+const DEFAULT_ALERT_MEMPOOL_STALLED_MS: u64 = 600_000;
+/// Headers per message of the P2P `headers` reply, a batch a node serves cheaply.
+const DEFAULT_HEADERS_BATCH_SIZE: u32 = 2_000;
+const DEFAULT_STATS_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_RETENTION_INTERVAL_MS: u64 = 3_600_000;
+const DEFAULT_RETENTION_BATCH_BLOCKS: u32 = 1_000;
+const DEFAULT_DATABASE_URL_ENV: &str = "DATABASE_URL";
+const DEFAULT_DATABASE_MAX_CONNECTIONS: u32 = 10;
+const DEFAULT_DATABASE_ACQUIRE_TIMEOUT_MS: u64 = 30_000;
+const DEFAULT_DATABASE_IDLE_TIMEOUT_MS: u64 = 600_000;
+/// Brokers events of persisted chain data can be published to.
+pub const SINK_KINDS: &[&str] = &["kafka", "nats"];
+/// First path segments of `/v1` routes, which instance names would shadow.
+const RESERVED_INSTANCE_NAMES: &[&str] = &[
+    "status", "errors", "jobs", "nodes", "data", "addresses", "blocks", "txs", "scripts", "fees", "events", "stats",
+    "admin", "scan", "node",
+];
+/// Confirmation levels of watched transaction events, in the order they are reached.
+pub const EVENT_LEVELS: &[&str] = &["seen", "confirmed", "finalized"];
+/// Access roles of API credentials, from least to most privileged.
+pub const API_ROLES: &[&str] = &["read", "operator", "admin"];
+/// Role of API keys, tokens and HMAC keys configured without `roles`.
+const DEFAULT_API_ROLE: &str = "operator";
+/// File formats of `export` jobs.
+pub const EXPORT_FORMATS: &[&str] = &["csv", "parquet"];
+/// Indexed chain data tables an `export` job can dump.
+pub const EXPORT_TABLES: &[&str] = &["blocks", "transactions", "tx_outputs"];
+/// Indexed chain data tables that may be added to the replication publication.
+pub const REPLICATION_TABLES: &[&str] = &[
+    "blocks",
+    "transactions",
+    "tx_outputs",
+    "tx_inputs",
+    "utxos_current",
+    "address_balance_current",
+    "address_balance_history",
+];
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
@@ -27,87 +118,521 @@ pub struct AppConfig {
     pub server: ServerConfig,
     pub rpc: RpcConfig,
     pub indexer: IndexerConfig,
+    /// Postgres connection shared by every indexed network.
+    pub database: DatabaseConfig,
+    /// What is persisted per transaction, for every indexed network.
+    pub storage: StorageConfig,
     pub jobs: Vec<JobConfig>,
+    pub replication: Option<ReplicationConfig>,
+    /// Broker that receives events of the primary network's persisted blocks.
+    pub sink: Option<SinkConfig>,
+    /// Additional networks indexed by the same process.
+    pub instances: Vec<InstanceConfig>,
+    pub logging: LoggingConfig,
+    /// Reports of panics, failed jobs and repeated errors; off when unset.
+    pub alerting: Option<AlertingConfig>,
 }
 
-#[derive(Debug, Clone)]
+/// Output of `logging::init`, selected once at startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoggingConfig {
+    /// `json`, `pretty` (multi-line, for a terminal) or `compact` (one line per event).
+    pub format: String,
+    /// Default level of every target; `RUST_LOG`, when set, replaces it and `modules`.
+    pub level: String,
+    /// Levels of single modules or targets, e.g. `sqlx::query: warn`, applied over `level`.
+    pub modules: Vec<(String, String)>,
+    /// Log file replacing stdout; `None` writes to stdout.
+    pub file: Option<LogFileConfig>,
+    /// Exports spans of HTTP requests, RPC calls and block persistence; off when unset.
+    pub otlp: Option<OtlpConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFileConfig {
+    pub path: PathBuf,
+    /// `daily`, `hourly` or `never`; rotated files get the period as a suffix, e.g. `indexer.log.2026-10-16`.
+    pub rotation: String,
+    /// Rotated files kept next to the current one; older ones are deleted.
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            format: "json".to_string(),
+            level: DEFAULT_LOG_LEVEL.to_string(),
+            modules: Vec::new(),
+            file: None,
+            otlp: None,
+        }
+    }
+}
+
+/// OTLP/HTTP collector receiving spans, e.g. an OpenTelemetry Collector, Jaeger or Tempo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtlpConfig {
+    /// Base URL of the collector; spans are posted to `{endpoint}/v1/traces`.
+    pub endpoint: String,
+    /// `service.name` resource attribute of the exported spans.
+    pub service_name: String,
+    pub export_interval_ms: u64,
+    /// Finished spans kept until the next export; further spans are dropped.
+    pub max_queue_size: usize,
+}
+
+/// Destinations and thresholds of `alerting::start`, selected once at startup.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertingConfig {
+    /// `environment` of the reports, e.g. `production`.
+    pub environment: Option<String>,
+    /// Reports of the same panic location, job or error type within the interval are dropped.
+    pub min_interval_ms: u64,
+    /// `errors_total` increments of one type within `error_window_ms` that raise an alert.
+    pub error_threshold: u32,
+    pub error_window_ms: u64,
+    /// `errors_total` types counted, e.g. `rpc` and `db_write`.
+    pub error_types: Vec<String>,
+    pub webhook: Option<AlertWebhookConfig>,
+    pub sentry: Option<SentryConfig>,
+    /// Conditions evaluated periodically; off when unset.
+    pub rules: Option<AlertRulesConfig>,
+}
+
+/// Rules of `AlertRulesRunner`, evaluated for every indexed network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertRulesConfig {
+    pub interval_ms: u64,
+    /// Sync lag above which `tip_lag` becomes pending; `0` disables the rule.
+    pub tip_lag_blocks: u64,
+    /// How long the lag has to last before `tip_lag` fires.
+    pub tip_lag_for_ms: u64,
+    /// Fires `job_failed` for every job in the `failed` state.
+    pub job_failed: bool,
+    /// Time without a successful mempool poll that fires `mempool_stalled`; `0` disables the rule.
+    pub mempool_stalled_ms: u64,
+}
+
+/// Endpoint receiving every alert as a JSON `POST`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertWebhookConfig {
+    pub url: String,
+    /// Sent with every request, e.g. `Authorization`.
+    pub headers: Vec<(String, Zeroizing<String>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentryConfig {
+    /// Client key of the project, e.g. `https://<key>@o0.ingest.sentry.io/<project_id>`.
+    pub dsn: Zeroizing<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct ServerConfig {
     pub bind_host: String,
     pub bind_port: u16,
+    /// Unix socket of `server.bind`, served instead of `bind_host:bind_port`.
+    pub unix_socket: Option<UnixSocketConfig>,
     pub tls: TlsConfig,
-    pub auth: BasicAuthResolved,
+    pub auth: ServerAuthConfig,
+    /// Time in-flight requests get to finish after a shutdown signal before they are aborted.
+    pub shutdown_grace_period_ms: u64,
+    /// Peers allowed to report the client address in `X-Forwarded-For`/`Forwarded`.
+    pub trusted_proxies: Vec<IpNet>,
+    /// Bind the HTTP server before storage is ready and report `starting` until bootstrap
+    /// completes, instead of exiting when Postgres is unreachable at startup.
+    pub degraded_start: bool,
+    /// Delay between storage connection/migration attempts in degraded start.
+    pub storage_retry_interval_ms: u64,
+    /// Serve Swagger UI at `/docs`; the OpenAPI document is served either way.
+    pub swagger_ui: bool,
+    /// Compress responses with gzip or Brotli when the client sends `Accept-Encoding`.
+    pub compression: bool,
+    pub rate_limit: RateLimitConfig,
+    /// Second listener of `server.admin` serving `/health/*`, `/metrics` and `/v1/admin/*`,
+    /// which are then no longer served on the public address.
+    pub admin: Option<AdminServerConfig>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminServerConfig {
+    pub bind_host: String,
+    pub bind_port: u16,
+    /// `server.admin.auth`, or a copy of `server.auth` when it is not set.
+    pub auth: ServerAuthConfig,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnixSocketConfig {
+    pub path: PathBuf,
+    /// Permission bits set on the socket file before it appears at `path`.
+    pub mode: u32,
+    /// Whether the `Forwarded`/`X-Forwarded-For` headers of socket peers name the client;
+    /// otherwise every request on the socket counts as one client.
+    pub trust_forwarded: bool,
+}
+
+/// Token bucket limits of the HTTP API; a missing rule means no limit of that kind.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitConfig {
+    /// Keyed by client address (see `trusted_proxies`).
+    pub per_ip: Option<RateLimitRule>,
+    /// Keyed by the authenticated principal (provider and subject).
+    pub per_credential: Option<RateLimitRule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitRule {
+    /// Bucket refill rate.
+    pub requests_per_sec: u32,
+    /// Bucket size: requests allowed at once after an idle period.
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TlsConfig {
+    /// Serve the API and `server.admin` over HTTPS with this pair; off when TLS is terminated
+    /// by a reverse proxy in front of the indexer.
+    pub enabled: bool,
     pub cert_path: PathBuf,
     pub key_path: PathBuf,
+    /// Certificates expiring within this many days are reported by the startup preflight.
+    pub expiry_warning_days: u32,
+    /// How often certificate and `password_file` files are checked for changes; `0` disables
+    /// the watcher.
+    pub watch_interval_ms: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerAuthConfig {
+    pub basic: BasicAuthResolved,
+    pub api_keys: Vec<ApiKeyResolved>,
+    pub tokens: Vec<ApiTokenResolved>,
+    pub jwt: Option<JwtAuthResolved>,
+    pub hmac: Option<HmacAuthResolved>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct BasicAuthResolved {
     pub username: String,
-    pub password: String,
+    /// For `server.auth.basic` also a bcrypt or Argon2 hash, see [`auth::validate_password_hash`].
+    pub password: Zeroizing<String>,
+    /// File the password was read from, watched for rotation; `None` when it came from `password_env`.
+    pub password_file: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKeyResolved {
+    pub name: String,
+    pub key: Zeroizing<String>,
+    pub roles: Vec<String>,
+}
+
+/// Opaque bearer token from `server.auth.tokens`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiTokenResolved {
+    pub label: String,
+    /// `None` for a disabled token whose `token_env` is no longer set.
+    pub token: Option<Zeroizing<String>>,
+    pub roles: Vec<String>,
+    /// Disabled tokens are rejected; they are kept so their use can still be reported.
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtAuthResolved {
+    pub secret: Zeroizing<String>,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+    pub roles_claim: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HmacAuthResolved {
+    pub replay_window_secs: u64,
+    pub keys: Vec<HmacKeyResolved>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HmacKeyResolved {
+    pub key_id: String,
+    pub secret: Zeroizing<String>,
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct RpcConfig {
     pub node_id: String,
     pub url: String,
+    /// Base URL of the node's `-rest` interface; blocks are fetched from it instead of JSON-RPC.
+    pub rest_url: Option<String>,
     pub auth: BasicAuthResolved,
     pub mtls: Option<MtlsConfig>,
     pub insecure_skip_verify: bool,
     pub timeouts: RpcTimeouts,
+    /// Node-wide cap of RPC calls per second, shared by all jobs; unlimited when unset.
+    pub max_requests_per_second: Option<u32>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MtlsConfig {
     pub ca_path: PathBuf,
     pub client_cert_path: PathBuf,
     pub client_key_path: PathBuf,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct RpcTimeouts {
     pub connect_ms: u64,
     pub request_ms: u64,
+    /// How long startup waits for a node answering `-28` (still loading); `0` does not wait.
+    pub startup_ms: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IndexerConfig {
     pub chain: String,
     pub network: String,
+    /// Custom signet parameters; only allowed with `network: signet`.
+    pub signet: Option<SignetConfig>,
     pub reorg_depth: u32,
     pub poll: PollConfig,
     pub concurrency: ConcurrencyConfig,
     pub batching: BatchingConfig,
+    /// Also fetches the serialized block and stores it compressed in `block_raw`.
+    pub store_raw_blocks: bool,
+    /// Builds BIP158 basic filters of indexed blocks into `block_filters`.
+    pub block_filters: bool,
+    /// Fetches outputs spent by indexed inputs that are neither stored nor in the block
+    /// with `getrawtransaction`, so `tx_inputs` gets their value and address.
+    pub prevout_rpc_fallback: bool,
+    /// Watched transaction events; disabled when unset.
+    pub events: Option<EventsConfig>,
+    /// Header-first sync of the node's chain; disabled when unset.
+    pub headers: Option<HeadersConfig>,
+    /// Daily and per-block stats aggregator; disabled when unset.
+    pub stats: Option<StatsConfig>,
+    /// Pruning of transaction detail below the retained blocks; disabled when unset.
+    pub retention: Option<RetentionConfig>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadersConfig {
+    /// Headers fetched per JSON-RPC batch.
+    pub batch_size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsConfig {
+    /// Pause between aggregation passes once the aggregator caught up.
+    pub interval_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionConfig {
+    /// Blocks below the canonical tip whose transactions keep `decoded` and `script_hex`.
+    pub keep_blocks: u32,
+    /// Pause between pruning passes once pruning caught up.
+    pub interval_ms: u64,
+    /// Blocks pruned per database transaction.
+    pub batch_blocks: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventsConfig {
+    /// Emitted levels, a subset of [`EVENT_LEVELS`].
+    pub levels: Vec<String>,
+    /// Confirmations after which a transaction is reported as `finalized`.
+    pub finality_depth: u32,
+}
+
+/// Parameters of a custom signet. Unset fields fall back to the default signet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignetConfig {
+    /// Block signing challenge script, hex.
+    pub challenge: String,
+    /// Message start bytes, hex; derived from the challenge when unset.
+    pub magic: Option<String>,
+    pub genesis_hash: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct PollConfig {
     pub tip_interval_ms: u64,
     pub mempool_interval_ms: u64,
+    /// `getblocktemplate` snapshot interval; ingestion is disabled when unset.
+    pub block_template_interval_ms: Option<u64>,
+    /// Interval of the scan for heights missing below the progress of jobs; off when unset.
+    pub gap_scan_interval_ms: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ConcurrencyConfig {
     pub max_jobs: u8,
     pub rpc_parallelism: u16,
     pub db_writer_parallelism: u16,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct BatchingConfig {
     pub blocks_per_batch: u32,
     pub txs_per_batch: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Logical replication publication managed by the indexer for CDC consumers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplicationConfig {
+    pub publication: String,
+    pub tables: Vec<String>,
+    pub check_interval_ms: u64,
+    /// WAL retained by a logical slot above which the slot is reported as stalled.
+    pub max_slot_lag_bytes: u64,
+}
+
+/// Postgres connection URL and pool settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatabaseConfig {
+    /// URL given in the config; otherwise it is read from `url_env` when connecting.
+    pub url: Option<Zeroizing<String>>,
+    pub url_env: String,
+    pub max_connections: u32,
+    /// Connections the pool keeps open even when idle.
+    pub min_connections: u32,
+    /// Wait for a free connection before a query fails.
+    pub acquire_timeout_ms: u64,
+    /// Idle connections above `min_connections` are closed after this long.
+    pub idle_timeout_ms: u64,
+    /// Postgres `statement_timeout` of every connection; the server default when `None`.
+    pub statement_timeout_ms: Option<u64>,
+    /// Read replica URL for API queries, given in the config.
+    pub replica_url: Option<Zeroizing<String>>,
+    /// Env var holding the read replica URL, read when connecting.
+    pub replica_url_env: Option<String>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            url_env: DEFAULT_DATABASE_URL_ENV.to_string(),
+            max_connections: DEFAULT_DATABASE_MAX_CONNECTIONS,
+            min_connections: 0,
+            acquire_timeout_ms: DEFAULT_DATABASE_ACQUIRE_TIMEOUT_MS,
+            idle_timeout_ms: DEFAULT_DATABASE_IDLE_TIMEOUT_MS,
+            statement_timeout_ms: None,
+            replica_url: None,
+            replica_url_env: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StorageConfig {
+    /// Keeps the node JSON of transactions in `transactions.decoded`; otherwise only `vsize`
+    /// is stored there, inputs and outputs are still written to their tables.
+    pub store_decoded_tx: bool,
+    /// Keeps the witness stack of every input in `tx_inputs.witness`.
+    pub store_witness: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            store_decoded_tx: true,
+            store_witness: false,
+        }
+    }
+}
+
+/// Kafka or NATS JetStream target of the event outbox publisher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SinkConfig {
+    /// `kafka` or `nats`.
+    pub kind: String,
+    /// Kafka bootstrap servers (`host:port`) or NATS server URLs.
+    pub servers: Vec<String>,
+    /// Events go to `{topic_prefix}.blocks`, `{topic_prefix}.transactions` and
+    /// `{topic_prefix}.address_activity` (Kafka topics or NATS subjects).
+    pub topic_prefix: String,
+    /// Outbox rows published per poll.
+    pub batch_size: u32,
+    pub poll_interval_ms: u64,
+    /// Published rows are deleted from the outbox after this many hours.
+    pub retain_published_hours: u32,
+}
+
+/// Network indexed next to the primary one, with its own node, jobs and Postgres schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstanceConfig {
+    /// Path segment of the instance API, `/v1/{name}/...`.
+    pub name: String,
+    /// Postgres schema holding the tables of the instance.
+    pub schema: String,
+    pub rpc: RpcConfig,
+    pub indexer: IndexerConfig,
+    pub jobs: Vec<JobConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct JobConfig {
     pub job_id: String,
     pub mode: String,
     pub enabled: bool,
     pub addresses: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_height: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_height: Option<i32>,
+    /// Watched wallet of `descriptor` mode: an output descriptor or a bare xpub/ypub/zpub.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub descriptor: Option<String>,
+    /// Unused addresses kept derived past the last used one on each branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap_limit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<JobRetryPolicy>,
+    /// Output of `export` mode.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export: Option<JobExportConfig>,
+    /// Scheduling priority; higher starts first among jobs of its kind, `0` when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,
+    /// JSON-RPC and REST requests per second the job may send to the node; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rpc_per_second: Option<u32>,
+    /// Blocks per minute the job may index; unlimited when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_blocks_per_minute: Option<u32>,
+    /// Protocol indexers run on the blocks the job writes, see [`protocols::PROTOCOLS`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protocols: Vec<String>,
+}
+
+/// Where and how an `export` job writes its height range.
+///
+/// Every chunk of `indexer.batching.blocks_per_batch` heights becomes one file per table:
+/// `{destination}/{job_id}/{table}/{from_height:010}-{to_height:010}.{format}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct JobExportConfig {
+    /// `csv` or `parquet`.
+    pub format: String,
+    /// Absolute local directory or `s3://bucket/prefix`; S3 credentials and region come
+    /// from the standard `AWS_*` environment variables.
+    pub destination: String,
+    /// Subset of `blocks`, `transactions` and `tx_outputs`; all of them by default.
+    #[serde(default = "default_export_tables")]
+    pub tables: Vec<String>,
+}
+
+/// Automatic `failed -> running` transitions for transient (rpc/storage) errors.
+///
+/// The delay before retry `n` (0-based) is `backoff_ms * 2^n`, capped at `max_backoff_ms`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct JobRetryPolicy {
+    pub max_attempts: u32,
+    pub backoff_ms: u64,
+    #[serde(default = "default_retry_max_backoff_ms")]
+    pub max_backoff_ms: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -115,21 +640,169 @@ struct RawAppConfig {
     server: RawServerConfig,
     rpc: RawRpcConfig,
     indexer: RawIndexerConfig,
+    database: Option<RawDatabaseConfig>,
+    storage: Option<RawStorageConfig>,
+    jobs: Vec<RawJobConfig>,
+    replication: Option<RawReplicationConfig>,
+    sink: Option<RawSinkConfig>,
+    #[serde(default)]
+    instances: Vec<RawInstanceConfig>,
+    logging: Option<RawLoggingConfig>,
+    alerting: Option<RawAlertingConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawAlertingConfig {
+    environment: Option<String>,
+    min_interval_ms: Option<u64>,
+    error_threshold: Option<u32>,
+    error_window_ms: Option<u64>,
+    error_types: Option<Vec<String>>,
+    webhook: Option<RawAlertWebhookConfig>,
+    sentry: Option<RawSentryConfig>,
+    rules: Option<RawAlertRulesConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawAlertRulesConfig {
+    interval_ms: Option<u64>,
+    tip_lag_blocks: Option<u64>,
+    tip_lag_for_ms: Option<u64>,
+    job_failed: Option<bool>,
+    mempool_stalled_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawAlertWebhookConfig {
+    url: String,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSentryConfig {
+    dsn: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawLoggingConfig {
+    format: Option<String>,
+    level: Option<String>,
+    #[serde(default)]
+    modules: BTreeMap<String, String>,
+    file: Option<RawLogFileConfig>,
+    otlp: Option<RawOtlpConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawLogFileConfig {
+    path: String,
+    rotation: Option<String>,
+    max_files: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawOtlpConfig {
+    endpoint: String,
+    service_name: Option<String>,
+    export_interval_ms: Option<u64>,
+    max_queue_size: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawDatabaseConfig {
+    url: Option<String>,
+    url_env: Option<String>,
+    max_connections: Option<u32>,
+    min_connections: Option<u32>,
+    acquire_timeout_ms: Option<u64>,
+    idle_timeout_ms: Option<u64>,
+    statement_timeout_ms: Option<u64>,
+    replica_url: Option<String>,
+    replica_url_env: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStorageConfig {
+    store_decoded_tx: Option<bool>,
+    store_witness: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawInstanceConfig {
+    name: Option<String>,
+    schema: Option<String>,
+    rpc: RawRpcConfig,
+    indexer: RawIndexerConfig,
+    #[serde(default)]
     jobs: Vec<RawJobConfig>,
 }
 
+#[derive(Debug, Deserialize)]
+struct RawSinkConfig {
+    kind: String,
+    servers: Vec<String>,
+    topic_prefix: Option<String>,
+    batch_size: Option<u32>,
+    poll_interval_ms: Option<u64>,
+    retain_published_hours: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawReplicationConfig {
+    publication: String,
+    tables: Option<Vec<String>>,
+    check_interval_ms: Option<u64>,
+    max_slot_lag_bytes: Option<u64>,
+}
+
 #[derive(Debug, Deserialize)]
 struct RawServerConfig {
-    bind_host: String,
-    bind_port: u16,
+    bind_host: Option<String>,
+    bind_port: Option<u16>,
+    bind: Option<String>,
+    socket_mode: Option<String>,
+    socket_trust_forwarded: Option<bool>,
     tls: RawTlsConfig,
-    auth: RawAuthConfig,
+    auth: RawServerAuthConfig,
+    shutdown_grace_period_ms: Option<u64>,
+    #[serde(default)]
+    trusted_proxies: Vec<String>,
+    #[serde(default)]
+    degraded_start: bool,
+    storage_retry_interval_ms: Option<u64>,
+    swagger_ui: Option<bool>,
+    compression: Option<bool>,
+    rate_limit: Option<RawRateLimitConfig>,
+    admin: Option<RawAdminServerConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawAdminServerConfig {
+    bind_host: Option<String>,
+    bind_port: u16,
+    auth: Option<RawServerAuthConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRateLimitConfig {
+    per_ip: Option<RawRateLimitRule>,
+    per_credential: Option<RawRateLimitRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRateLimitRule {
+    requests_per_sec: u32,
+    burst: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RawTlsConfig {
+    enabled: Option<bool>,
     cert_path: String,
     key_path: String,
+    expiry_warning_days: Option<u32>,
+    watch_interval_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -137,20 +810,68 @@ struct RawAuthConfig {
     basic: RawBasicAuth,
 }
 
+#[derive(Debug, Deserialize)]
+struct RawServerAuthConfig {
+    basic: RawBasicAuth,
+    api_keys: Option<Vec<RawApiKey>>,
+    tokens: Option<Vec<RawApiToken>>,
+    jwt: Option<RawJwtAuth>,
+    hmac: Option<RawHmacAuth>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawApiKey {
+    name: String,
+    key_env: String,
+    roles: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawApiToken {
+    label: String,
+    token_env: String,
+    roles: Option<Vec<String>>,
+    enabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawJwtAuth {
+    secret_env: String,
+    issuer: Option<String>,
+    audience: Option<String>,
+    roles_claim: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawHmacAuth {
+    replay_window_secs: Option<u64>,
+    keys: Vec<RawHmacKey>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawHmacKey {
+    key_id: String,
+    secret_env: String,
+    roles: Option<Vec<String>>,
+}
+
 #[derive(Debug, Deserialize)]
 struct RawBasicAuth {
     username: String,
-    password_env: String,
+    password_env: Option<String>,
+    password_file: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RawRpcConfig {
     node_id: String,
     url: String,
+    rest_url: Option<String>,
     auth: RawAuthConfig,
     mtls: Option<RawMtlsConfig>,
     insecure_skip_verify: Option<bool>,
     timeouts: RawRpcTimeouts,
+    max_requests_per_second: Option<u32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -165,22 +886,63 @@ struct RawMtlsConfig {
 struct RawRpcTimeouts {
     connect_ms: u64,
     request_ms: u64,
+    startup_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RawIndexerConfig {
     chain: String,
     network: String,
+    signet: Option<RawSignetConfig>,
     reorg_depth: i64,
     poll: RawPollConfig,
     concurrency: RawConcurrencyConfig,
     batching: RawBatchingConfig,
+    store_raw_blocks: Option<bool>,
+    block_filters: Option<bool>,
+    prevout_rpc_fallback: Option<bool>,
+    events: Option<RawEventsConfig>,
+    headers: Option<RawHeadersConfig>,
+    stats: Option<RawStatsConfig>,
+    retention: Option<RawRetentionConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawHeadersConfig {
+    batch_size: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStatsConfig {
+    interval_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRetentionConfig {
+    keep_blocks: u32,
+    interval_ms: Option<u64>,
+    batch_blocks: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawEventsConfig {
+    levels: Option<Vec<String>>,
+    finality_depth: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawSignetConfig {
+    challenge: String,
+    magic: Option<String>,
+    genesis_hash: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RawPollConfig {
     tip_interval_ms: u64,
     mempool_interval_ms: u64,
+    block_template_interval_ms: Option<u64>,
+    gap_scan_interval_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -202,12 +964,224 @@ struct RawJobConfig {
     mode: String,
     enabled: bool,
     addresses: Option<Vec<String>>,
+    from_height: Option<i32>,
+    to_height: Option<i32>,
+    descriptor: Option<String>,
+    gap_limit: Option<u32>,
+    retry: Option<JobRetryPolicy>,
+    export: Option<JobExportConfig>,
+    priority: Option<i32>,
+    max_rpc_per_second: Option<u32>,
+    max_blocks_per_minute: Option<u32>,
+    protocols: Option<Vec<String>>,
+}
+
+impl JobRetryPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_attempts == 0 {
+            return Err("retry.max_attempts MUST be > 0".to_string());
+        }
+        if self.backoff_ms == 0 {
+            return Err("retry.backoff_ms MUST be > 0".to_string());
+        }
+        if self.max_backoff_ms < self.backoff_ms {
+            return Err("retry.max_backoff_ms MUST be >= retry.backoff_ms".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Delay before the retry that follows `retry_count` earlier retries.
+    pub fn backoff(&self, retry_count: u32) -> std::time::Duration {
+        let factor = 1_u64.checked_shl(retry_count).unwrap_or(u64::MAX);
+        let delay_ms = self.backoff_ms.saturating_mul(factor).min(self.max_backoff_ms);
+        std::time::Duration::from_millis(delay_ms)
+    }
+}
+
+fn default_retry_max_backoff_ms() -> u64 {
+    DEFAULT_RETRY_MAX_BACKOFF_MS
+}
+
+impl JobExportConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !EXPORT_FORMATS.contains(&self.format.as_str()) {
+            return Err(format!("export.format MUST be one of: {}", EXPORT_FORMATS.join("|")));
+        }
+
+        match self.destination.strip_prefix("s3://") {
+            Some(location) if location.split('/').next().unwrap_or_default().is_empty() => {
+                return Err("export.destination MUST name a bucket: s3://bucket/prefix".to_string());
+            }
+            None if !Path::new(&self.destination).is_absolute() => {
+                return Err("export.destination MUST be an absolute path or an s3:// URL".to_string());
+            }
+            _ => {}
+        }
+
+        if self.tables.is_empty() {
+            return Err("export.tables MUST be non-empty".to_string());
+        }
+        let mut seen = HashSet::new();
+        for table in &self.tables {
+            if !EXPORT_TABLES.contains(&table.as_str()) || !seen.insert(table) {
+                return Err(format!(
+                    "export.tables MUST be distinct values of: {}",
+                    EXPORT_TABLES.join("|")
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn default_export_tables() -> Vec<String> {
+    EXPORT_TABLES.iter().map(|table| table.to_string()).collect()
 }
 
 impl AppConfig {
     pub fn load() -> Result<Self, ConfigError> {
-        let path = env::var("INDEXER_CONFIG_PATH").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
-        Self::load_from_path(Path::new(&path))
+        Self::load_from_path(&Self::path())
+    }
+
+    pub fn path() -> PathBuf {
+        PathBuf::from(env::var("INDEXER_CONFIG_PATH").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string()))
+    }
+
+    /// Files credentials are read from: `password_file` secrets, the `server.tls` pair and the
+    /// `rpc.mtls` files of the primary network and of every instance.
+    pub fn credential_files(&self) -> Vec<PathBuf> {
+        let rpcs = std::iter::once(&self.rpc).chain(self.instances.iter().map(|instance| &instance.rpc));
+        let mut files: Vec<PathBuf> = std::iter::once(&self.server.auth.basic)
+            .chain(self.server.admin.iter().map(|admin| &admin.auth.basic))
+            .chain(rpcs.clone().map(|rpc| &rpc.auth))
+            .filter_map(|basic| basic.password_file.clone())
+            .collect();
+        files.push(self.server.tls.cert_path.clone());
+        files.push(self.server.tls.key_path.clone());
+        for mtls in rpcs.filter_map(|rpc| rpc.mtls.as_ref()) {
+            files.extend([
+                mtls.ca_path.clone(),
+                mtls.client_cert_path.clone(),
+                mtls.client_key_path.clone(),
+            ]);
+        }
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    /// Rejects a reloaded config that changes settings only applied at startup.
+    ///
+    /// Jobs, `indexer.poll` intervals and `indexer.batching` can be applied at runtime;
+    /// block template interval, events, headers, stats and retention settings can change but not be switched on or off.
+    pub fn ensure_reloadable(&self, next: &AppConfig) -> Result<(), ConfigError> {
+        let mut changed = Vec::new();
+
+        if self.server.bind_host != next.server.bind_host || self.server.bind_port != next.server.bind_port {
+            changed.push("server.bind_host/bind_port");
+        }
+        if self.server.unix_socket != next.server.unix_socket {
+            changed.push("server.bind/socket_mode/socket_trust_forwarded");
+        }
+        if self.server.admin != next.server.admin {
+            changed.push("server.admin");
+        }
+        if self.server.tls != next.server.tls {
+            changed.push("server.tls");
+        }
+        if self.server.auth != next.server.auth {
+            changed.push("server.auth");
+        }
+        if self.server.shutdown_grace_period_ms != next.server.shutdown_grace_period_ms {
+            changed.push("server.shutdown_grace_period_ms");
+        }
+        if self.server.trusted_proxies != next.server.trusted_proxies {
+            changed.push("server.trusted_proxies");
+        }
+        if self.server.degraded_start != next.server.degraded_start
+            || self.server.storage_retry_interval_ms != next.server.storage_retry_interval_ms
+        {
+            changed.push("server.degraded_start/storage_retry_interval_ms");
+        }
+        if self.server.swagger_ui != next.server.swagger_ui {
+            changed.push("server.swagger_ui");
+        }
+        if self.server.compression != next.server.compression {
+            changed.push("server.compression");
+        }
+        if self.server.rate_limit != next.server.rate_limit {
+            changed.push("server.rate_limit");
+        }
+        if self.rpc != next.rpc {
+            changed.push("rpc");
+        }
+        if self.indexer.chain != next.indexer.chain || self.indexer.network != next.indexer.network {
+            changed.push("indexer.chain/network");
+        }
+        if self.indexer.reorg_depth != next.indexer.reorg_depth {
+            changed.push("indexer.reorg_depth");
+        }
+        if self.indexer.concurrency != next.indexer.concurrency {
+            changed.push("indexer.concurrency");
+        }
+        if self.indexer.store_raw_blocks != next.indexer.store_raw_blocks {
+            changed.push("indexer.store_raw_blocks");
+        }
+        if self.indexer.block_filters != next.indexer.block_filters {
+            changed.push("indexer.block_filters");
+        }
+        if self.indexer.prevout_rpc_fallback != next.indexer.prevout_rpc_fallback {
+            changed.push("indexer.prevout_rpc_fallback");
+        }
+        if self.replication != next.replication {
+            changed.push("replication");
+        }
+        if self.sink != next.sink {
+            changed.push("sink");
+        }
+        if self.logging != next.logging {
+            changed.push("logging");
+        }
+        if self.alerting != next.alerting {
+            changed.push("alerting");
+        }
+        if self.database != next.database {
+            changed.push("database");
+        }
+        if self.storage != next.storage {
+            changed.push("storage");
+        }
+        if self.instances != next.instances {
+            changed.push("instances");
+        }
+        if self.indexer.poll.block_template_interval_ms.is_some()
+            != next.indexer.poll.block_template_interval_ms.is_some()
+        {
+            changed.push("indexer.poll.block_template_interval_ms (enable/disable)");
+        }
+        if self.indexer.events.is_some() != next.indexer.events.is_some() {
+            changed.push("indexer.events (enable/disable)");
+        }
+        if self.indexer.headers.is_some() != next.indexer.headers.is_some() {
+            changed.push("indexer.headers (enable/disable)");
+        }
+        if self.indexer.stats.is_some() != next.indexer.stats.is_some() {
+            changed.push("indexer.stats (enable/disable)");
+        }
+        if self.indexer.retention.is_some() != next.indexer.retention.is_some() {
+            changed.push("indexer.retention (enable/disable)");
+        }
+
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        Err(ConfigError::Validation(format!(
+            "{} cannot be changed without restart",
+            changed.join(", ")
+        )))
     }
 
     pub fn load_from_path(path: &Path) -> Result<Self, ConfigError> {
@@ -216,7 +1190,17 @@ impl AppConfig {
             source,
         })?;
 
-        let raw: RawAppConfig = serde_yaml::from_str(&content)?;
+        Self::from_yaml(&content, env::vars())
+    }
+
+    fn from_yaml(
+        content: &str,
+        env_vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self, ConfigError> {
+        let mut value: serde_yaml::Value = serde_yaml::from_str(content)?;
+        apply_env_overrides(&mut value, env_vars)?;
+
+        let raw: RawAppConfig = serde_yaml::from_value(value)?;
         Self::from_raw(raw)
     }
 
@@ -224,442 +1208,2760 @@ impl AppConfig {
         validate_readable_file(&raw.server.tls.cert_path)?;
         validate_readable_file(&raw.server.tls.key_path)?;
 
-        let mtls = match raw.rpc.mtls {
-            Some(mtls) => {
-                let enabled = mtls.enabled.unwrap_or(true);
-                if enabled {
-                    validate_readable_file(&mtls.ca_path)?;
-                    validate_readable_file(&mtls.client_cert_path)?;
-                    validate_readable_file(&mtls.client_key_path)?;
-                    Some(MtlsConfig {
-                        ca_path: PathBuf::from(mtls.ca_path),
-                        client_cert_path: PathBuf::from(mtls.client_cert_path),
-                        client_key_path: PathBuf::from(mtls.client_key_path),
-                    })
-                } else {
-                    None
-                }
-            }
-            None => None,
-        };
-
-        let server_auth = resolve_basic_auth(&raw.server.auth.basic)?;
-        let rpc_auth = resolve_basic_auth(&raw.rpc.auth.basic)?;
-
-        if raw.indexer.reorg_depth < 0 {
+        let server_auth = resolve_server_auth(&raw.server.auth)?;
+        let rate_limit = resolve_rate_limit(raw.server.rate_limit.as_ref())?;
+        let replication = raw.replication.as_ref().map(resolve_replication).transpose()?;
+        let sink = raw.sink.as_ref().map(resolve_sink).transpose()?;
+        let logging = raw.logging.as_ref().map(resolve_logging).transpose()?.unwrap_or_default();
+        let alerting = raw.alerting.as_ref().map(resolve_alerting).transpose()?;
+        let rpc = resolve_rpc(raw.rpc)?;
+
+        let shutdown_grace_period_ms = raw
+            .server
+            .shutdown_grace_period_ms
+            .unwrap_or(DEFAULT_SHUTDOWN_GRACE_PERIOD_MS);
+        if shutdown_grace_period_ms == 0 {
             return Err(ConfigError::Validation(
-                "indexer.reorg_depth MUST be >= 0".to_string(),
+                "server.shutdown_grace_period_ms MUST be > 0".to_string(),
             ));
         }
 
-        if !matches!(
-            raw.indexer.network.as_str(),
-            "mainnet" | "testnet" | "signet" | "regtest"
-        ) {
+        let trusted_proxies = raw
+            .server
+            .trusted_proxies
+            .iter()
+            .map(|value| {
+                parse_trusted_proxy(value).ok_or_else(|| {
+                    ConfigError::Validation(format!(
+                        "server.trusted_proxies MUST contain IP addresses or CIDRs: {value}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let storage_retry_interval_ms = raw
+            .server
+            .storage_retry_interval_ms
+            .unwrap_or(DEFAULT_STORAGE_RETRY_INTERVAL_MS);
+        if storage_retry_interval_ms == 0 {
             return Err(ConfigError::Validation(
-                "indexer.network MUST be one of: mainnet|testnet|signet|regtest".to_string(),
+                "server.storage_retry_interval_ms MUST be > 0".to_string(),
             ));
         }
 
-        let mut seen_job_ids = HashSet::new();
-        let mut jobs = Vec::with_capacity(raw.jobs.len());
-
-        for job in raw.jobs {
-            if !seen_job_ids.insert(job.job_id.clone()) {
+        let unix_socket = resolve_unix_socket(
+            raw.server.bind.as_deref(),
+            raw.server.socket_mode.as_deref(),
+            raw.server.socket_trust_forwarded,
+        )?;
+        if unix_socket.is_some() && raw.server.tls.enabled == Some(true) {
+            return Err(ConfigError::Validation(
+                "server.tls.enabled MUST be false when server.bind is a unix socket".to_string(),
+            ));
+        }
+        let bind_host = raw.server.bind_host.unwrap_or_else(|| DEFAULT_BIND_HOST.to_string());
+        let bind_port = raw.server.bind_port.unwrap_or(DEFAULT_BIND_PORT);
+        let admin = raw
+            .server
+            .admin
+            .as_ref()
+            .map(|admin| resolve_admin_server(admin, &server_auth))
+            .transpose()?;
+        if let Some(admin) = &admin {
+            if unix_socket.is_none() && admin.bind_port == bind_port && admin.bind_host == bind_host {
+                return Err(ConfigError::Validation(format!(
+                    "server.admin MUST listen on another address than the API: {bind_host}:{bind_port}"
+                )));
+            }
+        }
+
+        let indexer = resolve_indexer(raw.indexer)?;
+        let database = raw.database.map(resolve_database).transpose()?.unwrap_or_default();
+        let storage = StorageConfig {
+            store_decoded_tx: raw
+                .storage
+                .as_ref()
+                .and_then(|storage| storage.store_decoded_tx)
+                .unwrap_or(true),
+            store_witness: raw
+                .storage
+                .as_ref()
+                .and_then(|storage| storage.store_witness)
+                .unwrap_or(false),
+        };
+        let jobs = resolve_jobs(raw.jobs, &indexer)?;
+        let instances = resolve_instances(raw.instances, &indexer)?;
+
+        Ok(AppConfig {
+            server: ServerConfig {
+                bind_host,
+                bind_port,
+                unix_socket,
+                tls: TlsConfig {
+                    enabled: raw.server.tls.enabled.unwrap_or(false),
+                    cert_path: PathBuf::from(raw.server.tls.cert_path),
+                    key_path: PathBuf::from(raw.server.tls.key_path),
+                    expiry_warning_days: raw
+                        .server
+                        .tls
+                        .expiry_warning_days
+                        .unwrap_or(DEFAULT_CERT_EXPIRY_WARNING_DAYS),
+                    watch_interval_ms: raw.server.tls.watch_interval_ms.unwrap_or(DEFAULT_TLS_WATCH_INTERVAL_MS),
+                },
+                auth: server_auth,
+                shutdown_grace_period_ms,
+                trusted_proxies,
+                degraded_start: raw.server.degraded_start,
+                storage_retry_interval_ms,
+                swagger_ui: raw.server.swagger_ui.unwrap_or(true),
+                compression: raw.server.compression.unwrap_or(true),
+                rate_limit,
+                admin,
+            },
+            rpc,
+            indexer,
+            database,
+            storage,
+            jobs,
+            replication,
+            sink,
+            instances,
+            logging,
+            alerting,
+        })
+    }
+}
+
+/// `server.bind: "unix:<path>"` with `server.socket_mode` as octal permission bits.
+fn resolve_unix_socket(
+    bind: Option<&str>,
+    socket_mode: Option<&str>,
+    trust_forwarded: Option<bool>,
+) -> Result<Option<UnixSocketConfig>, ConfigError> {
+    let Some(bind) = bind else {
+        if socket_mode.is_some() {
+            return Err(ConfigError::Validation(
+                "server.socket_mode MUST be set only with server.bind".to_string(),
+            ));
+        }
+        if trust_forwarded.is_some() {
+            return Err(ConfigError::Validation(
+                "server.socket_trust_forwarded MUST be set only with server.bind".to_string(),
+            ));
+        }
+        return Ok(None);
+    };
+
+    let path = bind
+        .strip_prefix(UNIX_SOCKET_PREFIX)
+        .map(str::trim)
+        .filter(|path| !path.is_empty())
+        .ok_or_else(|| {
+            ConfigError::Validation(format!(
+                "server.bind MUST be a unix socket path like unix:/run/indexer/api.sock: {bind}"
+            ))
+        })?;
+    let mode = match socket_mode {
+        Some(mode) => u32::from_str_radix(mode.trim(), 8)
+            .ok()
+            .filter(|mode| *mode <= 0o777)
+            .ok_or_else(|| {
+                ConfigError::Validation(format!(
+                    "server.socket_mode MUST be octal permission bits like \"660\": {mode}"
+                ))
+            })?,
+        None => DEFAULT_UNIX_SOCKET_MODE,
+    };
+
+    Ok(Some(UnixSocketConfig {
+        path: PathBuf::from(path),
+        mode,
+        trust_forwarded: trust_forwarded.unwrap_or(false),
+    }))
+}
+
+/// `server.admin`; without its own `auth` the listener accepts the credentials of `server.auth`.
+fn resolve_admin_server(raw: &RawAdminServerConfig, server_auth: &ServerAuthConfig) -> Result<AdminServerConfig, ConfigError> {
+    let bind_host = raw.bind_host.clone().unwrap_or_else(|| DEFAULT_ADMIN_BIND_HOST.to_string());
+    if bind_host.trim().is_empty() {
+        return Err(ConfigError::Validation(
+            "server.admin.bind_host MUST be non-empty".to_string(),
+        ));
+    }
+    let auth = match &raw.auth {
+        Some(auth) => resolve_server_auth(auth)?,
+        None => server_auth.clone(),
+    };
+
+    Ok(AdminServerConfig {
+        bind_host,
+        bind_port: raw.bind_port,
+        auth,
+    })
+}
+
+fn resolve_rpc(raw: RawRpcConfig) -> Result<RpcConfig, ConfigError> {
+    let mtls = match raw.mtls {
+        Some(mtls) => {
+            let enabled = mtls.enabled.unwrap_or(true);
+            if enabled {
+                validate_readable_file(&mtls.ca_path)?;
+                validate_readable_file(&mtls.client_cert_path)?;
+                validate_readable_file(&mtls.client_key_path)?;
+                Some(MtlsConfig {
+                    ca_path: PathBuf::from(mtls.ca_path),
+                    client_cert_path: PathBuf::from(mtls.client_cert_path),
+                    client_key_path: PathBuf::from(mtls.client_key_path),
+                })
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+
+    let auth = resolve_basic_auth(&raw.auth.basic)?;
+
+    let rest_url = raw
+        .rest_url
+        .map(|url| url.trim_end_matches('/').to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &rest_url {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(ConfigError::Validation(
+                "rpc.rest_url MUST be an http:// or https:// URL".to_string(),
+            ));
+        }
+    }
+    if raw.max_requests_per_second == Some(0) {
+        return Err(ConfigError::Validation(
+            "rpc.max_requests_per_second MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(RpcConfig {
+        node_id: raw.node_id,
+        url: raw.url,
+        rest_url,
+        auth,
+        mtls,
+        insecure_skip_verify: raw.insecure_skip_verify.unwrap_or(false),
+        timeouts: RpcTimeouts {
+            connect_ms: raw.timeouts.connect_ms,
+            request_ms: raw.timeouts.request_ms,
+            startup_ms: raw.timeouts.startup_ms.unwrap_or(DEFAULT_RPC_STARTUP_MS),
+        },
+        max_requests_per_second: raw.max_requests_per_second,
+    })
+}
+
+fn resolve_indexer(raw: RawIndexerConfig) -> Result<IndexerConfig, ConfigError> {
+    if raw.reorg_depth < 0 {
+        return Err(ConfigError::Validation(
+            "indexer.reorg_depth MUST be >= 0".to_string(),
+        ));
+    }
+    if raw.concurrency.rpc_parallelism == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.concurrency.rpc_parallelism MUST be > 0".to_string(),
+        ));
+    }
+    if raw.batching.txs_per_batch == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.batching.txs_per_batch MUST be > 0".to_string(),
+        ));
+    }
+    if raw.concurrency.db_writer_parallelism == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.concurrency.db_writer_parallelism MUST be > 0".to_string(),
+        ));
+    }
+
+    let Some(chain) = Chain::from_name(&raw.chain) else {
+        return Err(ConfigError::Validation(
+            "indexer.chain MUST be one of: bitcoin|litecoin|dogecoin".to_string(),
+        ));
+    };
+    if !chain.networks().contains(&raw.network.as_str()) {
+        return Err(ConfigError::Validation(format!(
+            "indexer.network MUST be one of: {} for chain {}",
+            chain.networks().join("|"),
+            chain.name()
+        )));
+    }
+
+    let signet = raw.signet.as_ref().map(resolve_signet).transpose()?;
+    if signet.is_some() && raw.network != "signet" {
+        return Err(ConfigError::Validation(
+            "indexer.signet is only supported for network signet".to_string(),
+        ));
+    }
+
+    if raw.poll.block_template_interval_ms == Some(0) {
+        return Err(ConfigError::Validation(
+            "indexer.poll.block_template_interval_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    if raw.poll.gap_scan_interval_ms == Some(0) {
+        return Err(ConfigError::Validation(
+            "indexer.poll.gap_scan_interval_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    let events = raw
+        .events
+        .as_ref()
+        .map(|events| resolve_events(events, raw.reorg_depth as u32))
+        .transpose()?;
+    let headers = raw.headers.as_ref().map(resolve_headers).transpose()?;
+    let stats = raw.stats.as_ref().map(resolve_stats).transpose()?;
+    let retention = raw.retention.as_ref().map(resolve_retention).transpose()?;
+
+    Ok(IndexerConfig {
+        chain: raw.chain,
+        network: raw.network,
+        signet,
+        reorg_depth: raw.reorg_depth as u32,
+        poll: PollConfig {
+            tip_interval_ms: raw.poll.tip_interval_ms,
+            mempool_interval_ms: raw.poll.mempool_interval_ms,
+            block_template_interval_ms: raw.poll.block_template_interval_ms,
+            gap_scan_interval_ms: raw.poll.gap_scan_interval_ms,
+        },
+        concurrency: ConcurrencyConfig {
+            max_jobs: raw.concurrency.max_jobs,
+            rpc_parallelism: raw.concurrency.rpc_parallelism,
+            db_writer_parallelism: raw.concurrency.db_writer_parallelism,
+        },
+        batching: BatchingConfig {
+            blocks_per_batch: raw.batching.blocks_per_batch,
+            txs_per_batch: raw.batching.txs_per_batch,
+        },
+        store_raw_blocks: raw.store_raw_blocks.unwrap_or(false),
+        block_filters: raw.block_filters.unwrap_or(false),
+        prevout_rpc_fallback: raw.prevout_rpc_fallback.unwrap_or(false),
+        events,
+        headers,
+        stats,
+        retention,
+    })
+}
+
+/// Jobs of `indexer`; addresses and descriptors are checked against its chain and network.
+fn resolve_jobs(raw: Vec<RawJobConfig>, indexer: &IndexerConfig) -> Result<Vec<JobConfig>, ConfigError> {
+    let mut seen_job_ids = HashSet::new();
+    let mut jobs = Vec::with_capacity(raw.len());
+
+    for job in raw {
+        if !seen_job_ids.insert(job.job_id.clone()) {
+            return Err(ConfigError::Validation(format!(
+                "jobs[*].job_id MUST be unique: {}",
+                job.job_id
+            )));
+        }
+
+        if !matches!(
+            job.mode.as_str(),
+            "all_addresses" | "address_list" | "height_range" | "descriptor" | "export" | "verify"
+        ) {
+            return Err(ConfigError::Validation(format!(
+                "jobs[*].mode has unsupported value: {}",
+                job.mode
+            )));
+        }
+
+        let addresses = job.addresses.unwrap_or_default();
+        if job.mode == "address_list" && addresses.is_empty() {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].addresses MUST be non-empty for address_list mode",
+                job_id = job.job_id
+            )));
+        }
+
+        if matches!(job.mode.as_str(), "height_range" | "export" | "verify") {
+            let (Some(from_height), Some(to_height)) = (job.from_height, job.to_height) else {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].from_height and to_height MUST be set for {mode} mode",
+                    job_id = job.job_id,
+                    mode = job.mode
+                )));
+            };
+
+            if from_height < 0 || from_height > to_height {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}] MUST satisfy 0 <= from_height <= to_height",
+                    job_id = job.job_id
+                )));
+            }
+        } else if job.from_height.is_some() || job.to_height.is_some() {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].from_height/to_height are only supported for height_range, export and verify modes",
+                job_id = job.job_id
+            )));
+        }
+
+        if job.mode == "export" {
+            let Some(export) = &job.export else {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].export MUST be set for export mode",
+                    job_id = job.job_id
+                )));
+            };
+
+            if !addresses.is_empty() {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].addresses MUST be empty for export mode",
+                    job_id = job.job_id
+                )));
+            }
+
+            export.validate().map_err(|reason| {
+                ConfigError::Validation(format!("jobs[{job_id}].{reason}", job_id = job.job_id))
+            })?;
+        } else if job.export.is_some() {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].export is only supported for export mode",
+                job_id = job.job_id
+            )));
+        }
+
+        let descriptor = job.descriptor.map(|descriptor| descriptor.trim().to_string());
+        if job.mode == "descriptor" {
+            if descriptor.as_deref().unwrap_or_default().is_empty() {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].descriptor MUST be set for descriptor mode",
+                    job_id = job.job_id
+                )));
+            }
+
+            if !addresses.is_empty() {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].addresses MUST be empty for descriptor mode",
+                    job_id = job.job_id
+                )));
+            }
+
+            if job.gap_limit.is_some_and(|gap_limit| gap_limit == 0 || gap_limit > descriptors::MAX_GAP_LIMIT) {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].gap_limit MUST be between 1 and {max}",
+                    job_id = job.job_id,
+                    max = descriptors::MAX_GAP_LIMIT
+                )));
+            }
+        } else if descriptor.is_some() || job.gap_limit.is_some() {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].descriptor/gap_limit are only supported for descriptor mode",
+                job_id = job.job_id
+            )));
+        }
+
+        if let Some(retry) = &job.retry {
+            retry.validate().map_err(|reason| {
+                ConfigError::Validation(format!("jobs[{job_id}].{reason}", job_id = job.job_id))
+            })?;
+        }
+
+        if job.max_rpc_per_second == Some(0) {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].max_rpc_per_second MUST be > 0",
+                job_id = job.job_id
+            )));
+        }
+        if job.max_blocks_per_minute == Some(0) {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].max_blocks_per_minute MUST be > 0",
+                job_id = job.job_id
+            )));
+        }
+
+        let protocols = job.protocols.unwrap_or_default();
+        if let Some(protocol) = protocols.iter().find(|protocol| !protocols::PROTOCOLS.contains(&protocol.as_str())) {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].protocols MUST contain only {}: {protocol}",
+                protocols::PROTOCOLS.join("|"),
+                job_id = job.job_id
+            )));
+        }
+        if !protocols.is_empty() && matches!(job.mode.as_str(), "export" | "verify") {
+            return Err(ConfigError::Validation(format!(
+                "jobs[{job_id}].protocols are not supported for {mode} mode",
+                job_id = job.job_id,
+                mode = job.mode
+            )));
+        }
+
+        jobs.push(JobConfig {
+            job_id: job.job_id,
+            mode: job.mode,
+            enabled: job.enabled,
+            addresses,
+            from_height: job.from_height,
+            to_height: job.to_height,
+            descriptor,
+            gap_limit: job.gap_limit,
+            retry: job.retry,
+            export: job.export,
+            priority: job.priority,
+            max_rpc_per_second: job.max_rpc_per_second,
+            max_blocks_per_minute: job.max_blocks_per_minute,
+            protocols,
+        });
+    }
+
+    let chain = ChainParams::from_config(indexer);
+    for job in &jobs {
+        for address in &job.addresses {
+            chain.check_address(address).map_err(|reason| {
+                ConfigError::Validation(format!("jobs[{job_id}].addresses: {reason}", job_id = job.job_id))
+            })?;
+        }
+
+        if let Some(descriptor) = &job.descriptor {
+            if chain.chain != Chain::Bitcoin {
+                return Err(ConfigError::Validation(format!(
+                    "jobs[{job_id}].mode descriptor is only supported for chain bitcoin",
+                    job_id = job.job_id
+                )));
+            }
+            WatchDescriptor::parse(descriptor, chain.bitcoin_network()).map_err(|reason| {
+                ConfigError::Validation(format!("jobs[{job_id}].descriptor: {reason}", job_id = job.job_id))
+            })?;
+        }
+    }
+
+    Ok(jobs)
+}
+
+/// Additional networks; every instance gets the validation of the top-level sections,
+/// with errors prefixed by `instances[{name}].`.
+fn resolve_instances(
+    raw: Vec<RawInstanceConfig>,
+    primary: &IndexerConfig,
+) -> Result<Vec<InstanceConfig>, ConfigError> {
+    let mut names = HashSet::from([primary.network.clone()]);
+    let mut schemas = HashSet::new();
+    let mut instances = Vec::with_capacity(raw.len());
+
+    for instance in raw {
+        let name = instance
+            .name
+            .unwrap_or_else(|| instance.indexer.network.clone())
+            .trim()
+            .to_string();
+        let valid_name = name.len() <= 32
+            && name
+                .chars()
+                .next()
+                .is_some_and(|first| first.is_ascii_lowercase() || first.is_ascii_digit())
+            && name
+                .chars()
+                .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-' || ch == '_');
+        if !valid_name {
+            return Err(ConfigError::Validation(format!(
+                "instances[*].name MUST be a lowercase path segment ([a-z0-9][a-z0-9_-]*, up to 32 chars): {name}"
+            )));
+        }
+        if RESERVED_INSTANCE_NAMES.contains(&name.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "instances[*].name MUST NOT be one of: {}",
+                RESERVED_INSTANCE_NAMES.join("|")
+            )));
+        }
+        if !names.insert(name.clone()) {
+            return Err(ConfigError::Validation(format!(
+                "instances[*].name MUST be unique and differ from indexer.network: {name}"
+            )));
+        }
+
+        let schema = instance
+            .schema
+            .map(|schema| schema.trim().to_string())
+            .unwrap_or_else(|| format!("indexer_{}", name.replace('-', "_")));
+        if !is_lowercase_identifier(&schema) || schema == "public" || schema.starts_with("pg_") {
+            return Err(ConfigError::Validation(format!(
+                "instances[{name}].schema MUST be a lowercase identifier ([a-z_][a-z0-9_]*, up to 63 chars) \
+                 other than public and pg_*"
+            )));
+        }
+        if !schemas.insert(schema.clone()) {
+            return Err(ConfigError::Validation(format!(
+                "instances[*].schema MUST be unique: {schema}"
+            )));
+        }
+
+        let rpc = resolve_rpc(instance.rpc).map_err(|err| instance_error(&name, err))?;
+        let indexer = resolve_indexer(instance.indexer).map_err(|err| instance_error(&name, err))?;
+        let jobs = resolve_jobs(instance.jobs, &indexer).map_err(|err| instance_error(&name, err))?;
+        instances.push(InstanceConfig {
+            name,
+            schema,
+            rpc,
+            indexer,
+            jobs,
+        });
+    }
+
+    Ok(instances)
+}
+
+fn instance_error(name: &str, err: ConfigError) -> ConfigError {
+    match err {
+        ConfigError::Validation(reason) => ConfigError::Validation(format!("instances[{name}].{reason}")),
+        err => err,
+    }
+}
+
+/// Postgres identifier that needs no quoting: `[a-z_][a-z0-9_]*`, up to 63 chars.
+fn is_lowercase_identifier(value: &str) -> bool {
+    value
+        .chars()
+        .next()
+        .is_some_and(|first| first.is_ascii_lowercase() || first == '_')
+        && value.len() <= 63
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+}
+
+/// `10.0.0.0/8` or a single address, taken as a host route.
+fn parse_trusted_proxy(value: &str) -> Option<IpNet> {
+    let value = value.trim();
+    value
+        .parse::<IpNet>()
+        .ok()
+        .or_else(|| value.parse::<IpAddr>().ok().map(IpNet::from))
+        .map(|net| net.trunc())
+}
+
+/// Layers `INDEXER__A__B=value` env vars over the parsed YAML before validation.
+///
+/// Path segments are lowercased keys; numeric segments index into lists
+/// (`INDEXER__JOBS__0__ENABLED`). Missing mapping keys are created. Values are
+/// parsed as YAML (`8443`, `true`, `["blocks"]`) unless the key currently holds a string.
+fn apply_env_overrides(
+    root: &mut serde_yaml::Value,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Result<(), ConfigError> {
+    let mut overrides: Vec<(String, String)> = vars
+        .into_iter()
+        .filter(|(name, _)| name.starts_with(ENV_OVERRIDE_PREFIX))
+        .collect();
+    // Deterministic order so that `INDEXER__JOBS` is applied before `INDEXER__JOBS__0__ENABLED`.
+    overrides.sort();
+
+    for (name, raw_value) in overrides {
+        let path: Vec<String> = name[ENV_OVERRIDE_PREFIX.len()..]
+            .split(ENV_OVERRIDE_SEPARATOR)
+            .map(|segment| segment.to_ascii_lowercase())
+            .collect();
+        if path.iter().any(String::is_empty) {
+            return Err(ConfigError::Validation(format!(
+                "{name} MUST name a config key as INDEXER__SECTION__KEY"
+            )));
+        }
+
+        let target = env_override_target(root, &path)
+            .map_err(|reason| ConfigError::Validation(format!("{name}: {reason}")))?;
+        *target = if target.is_string() {
+            serde_yaml::Value::String(raw_value)
+        } else {
+            serde_yaml::from_str(&raw_value).unwrap_or(serde_yaml::Value::String(raw_value))
+        };
+    }
+
+    Ok(())
+}
+
+fn env_override_target<'a>(
+    root: &'a mut serde_yaml::Value,
+    path: &[String],
+) -> Result<&'a mut serde_yaml::Value, String> {
+    let mut current = root;
+
+    for (depth, segment) in path.iter().enumerate() {
+        if current.is_null() {
+            *current = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        }
+
+        current = match current {
+            serde_yaml::Value::Mapping(mapping) => mapping
+                .entry(serde_yaml::Value::String(segment.clone()))
+                .or_insert(serde_yaml::Value::Null),
+            serde_yaml::Value::Sequence(items) => {
+                let key = path[..=depth].join(".");
+                let index: usize = segment
+                    .parse()
+                    .map_err(|_| format!("{key} MUST be a list index"))?;
+                let len = items.len();
+                items
+                    .get_mut(index)
+                    .ok_or_else(|| format!("{key} is out of range, list has {len} items"))?
+            }
+            _ => return Err(format!("{} is not a mapping or list", path[..depth].join("."))),
+        };
+    }
+
+    Ok(current)
+}
+
+fn validate_readable_file(path: &str) -> Result<(), ConfigError> {
+    File::open(path).map_err(|err| {
+        ConfigError::Validation(format!("file '{path}' MUST exist and be readable: {err}"))
+    })?;
+    Ok(())
+}
+
+fn resolve_basic_auth(raw: &RawBasicAuth) -> Result<BasicAuthResolved, ConfigError> {
+    match (&raw.password_env, &raw.password_file) {
+        (Some(_), Some(_)) | (None, None) => Err(ConfigError::Validation(
+            "exactly one of password_env or password_file MUST be set".to_string(),
+        )),
+        (Some(password_env), None) => {
+            if password_env.trim().is_empty() {
+                return Err(ConfigError::Validation(
+                    "password_env MUST be non-empty".to_string(),
+                ));
+            }
+
+            let password = env::var(password_env).map_err(|_| {
+                ConfigError::Validation(format!(
+                    "env variable '{password_env}' MUST be set"
+                ))
+            })?;
+
+            Ok(BasicAuthResolved {
+                username: raw.username.clone(),
+                password: Zeroizing::new(password),
+                password_file: None,
+            })
+        }
+        (None, Some(password_file)) => Ok(BasicAuthResolved {
+            username: raw.username.clone(),
+            password: read_secret_file(password_file)?,
+            password_file: Some(PathBuf::from(password_file)),
+        }),
+    }
+}
+
+/// Reads a secret mounted as a file, e.g. a Docker or Kubernetes secret, without the
+/// trailing line break editors and `echo` add.
+fn read_secret_file(path: &str) -> Result<Zeroizing<String>, ConfigError> {
+    let content = Zeroizing::new(fs::read_to_string(path).map_err(|err| {
+        ConfigError::Validation(format!("file '{path}' MUST exist and be readable: {err}"))
+    })?);
+    let secret = content.trim_end_matches(['\r', '\n']);
+    if secret.is_empty() {
+        return Err(ConfigError::Validation(format!("file '{path}' MUST be non-empty")));
+    }
+
+    Ok(Zeroizing::new(secret.to_string()))
+}
+
+fn resolve_server_auth(raw: &RawServerAuthConfig) -> Result<ServerAuthConfig, ConfigError> {
+    let basic = resolve_basic_auth(&raw.basic)?;
+    auth::validate_password_hash(&basic.password).map_err(|reason| {
+        let source = match &basic.password_file {
+            Some(path) => format!("file '{}'", path.display()),
+            None => format!("env variable '{}'", raw.basic.password_env.as_deref().unwrap_or_default()),
+        };
+        ConfigError::Validation(format!("{source} {reason}"))
+    })?;
+
+    let mut seen_names = HashSet::new();
+    let mut api_keys = Vec::new();
+    for api_key in raw.api_keys.iter().flatten() {
+        if api_key.name.trim().is_empty() {
+            return Err(ConfigError::Validation(
+                "server.auth.api_keys[*].name MUST be non-empty".to_string(),
+            ));
+        }
+
+        if !seen_names.insert(api_key.name.clone()) {
+            return Err(ConfigError::Validation(format!(
+                "server.auth.api_keys[*].name MUST be unique: {}",
+                api_key.name
+            )));
+        }
+
+        api_keys.push(ApiKeyResolved {
+            name: api_key.name.clone(),
+            key: resolve_secret_env(&api_key.key_env, "key_env")?,
+            roles: resolve_roles(api_key.roles.as_ref(), "server.auth.api_keys[*].roles")?,
+        });
+    }
+
+    let mut seen_labels = HashSet::new();
+    let mut tokens = Vec::new();
+    for token in raw.tokens.iter().flatten() {
+        if token.label.trim().is_empty() {
+            return Err(ConfigError::Validation(
+                "server.auth.tokens[*].label MUST be non-empty".to_string(),
+            ));
+        }
+
+        if !seen_labels.insert(token.label.clone()) {
+            return Err(ConfigError::Validation(format!(
+                "server.auth.tokens[*].label MUST be unique: {}",
+                token.label
+            )));
+        }
+
+        let enabled = token.enabled.unwrap_or(true);
+        let secret = if enabled {
+            Some(resolve_secret_env(&token.token_env, "token_env")?)
+        } else {
+            env::var(&token.token_env).ok().filter(|value| !value.is_empty()).map(Zeroizing::new)
+        };
+        tokens.push(ApiTokenResolved {
+            label: token.label.clone(),
+            token: secret,
+            roles: resolve_roles(token.roles.as_ref(), "server.auth.tokens[*].roles")?,
+            enabled,
+        });
+    }
+
+    let jwt = match &raw.jwt {
+        Some(jwt) => Some(JwtAuthResolved {
+            secret: resolve_secret_env(&jwt.secret_env, "secret_env")?,
+            issuer: jwt.issuer.clone(),
+            audience: jwt.audience.clone(),
+            roles_claim: jwt.roles_claim.clone().unwrap_or_else(|| "roles".to_string()),
+        }),
+        None => None,
+    };
+
+    let hmac = match &raw.hmac {
+        Some(hmac) => Some(resolve_hmac_auth(hmac)?),
+        None => None,
+    };
+
+    Ok(ServerAuthConfig {
+        basic,
+        api_keys,
+        tokens,
+        jwt,
+        hmac,
+    })
+}
+
+fn resolve_hmac_auth(raw: &RawHmacAuth) -> Result<HmacAuthResolved, ConfigError> {
+    let replay_window_secs = raw.replay_window_secs.unwrap_or(300);
+    if replay_window_secs == 0 {
+        return Err(ConfigError::Validation(
+            "server.auth.hmac.replay_window_secs MUST be > 0".to_string(),
+        ));
+    }
+
+    if raw.keys.is_empty() {
+        return Err(ConfigError::Validation(
+            "server.auth.hmac.keys MUST be non-empty".to_string(),
+        ));
+    }
+
+    let mut seen_key_ids = HashSet::new();
+    let mut keys = Vec::with_capacity(raw.keys.len());
+    for key in &raw.keys {
+        if key.key_id.trim().is_empty() {
+            return Err(ConfigError::Validation(
+                "server.auth.hmac.keys[*].key_id MUST be non-empty".to_string(),
+            ));
+        }
+
+        if !seen_key_ids.insert(key.key_id.clone()) {
+            return Err(ConfigError::Validation(format!(
+                "server.auth.hmac.keys[*].key_id MUST be unique: {}",
+                key.key_id
+            )));
+        }
+
+        keys.push(HmacKeyResolved {
+            key_id: key.key_id.clone(),
+            secret: resolve_secret_env(&key.secret_env, "secret_env")?,
+            roles: resolve_roles(key.roles.as_ref(), "server.auth.hmac.keys[*].roles")?,
+        });
+    }
+
+    Ok(HmacAuthResolved {
+        replay_window_secs,
+        keys,
+    })
+}
+
+fn resolve_rate_limit(raw: Option<&RawRateLimitConfig>) -> Result<RateLimitConfig, ConfigError> {
+    let Some(raw) = raw else {
+        return Ok(RateLimitConfig::default());
+    };
+
+    let resolve_rule = |rule: Option<&RawRateLimitRule>, field: &str| {
+        rule.map(|rule| {
+            let burst = rule.burst.unwrap_or(rule.requests_per_sec);
+            if rule.requests_per_sec == 0 || burst == 0 {
                 return Err(ConfigError::Validation(format!(
-                    "jobs[*].job_id MUST be unique: {}",
-                    job.job_id
+                    "server.rate_limit.{field}.requests_per_sec and burst MUST be > 0"
                 )));
             }
+            Ok(RateLimitRule {
+                requests_per_sec: rule.requests_per_sec,
+                burst,
+            })
+        })
+        .transpose()
+    };
+
+    Ok(RateLimitConfig {
+        per_ip: resolve_rule(raw.per_ip.as_ref(), "per_ip")?,
+        per_credential: resolve_rule(raw.per_credential.as_ref(), "per_credential")?,
+    })
+}
+
+/// Roles of statically configured credentials; [`DEFAULT_API_ROLE`] when `roles` is omitted.
+fn resolve_roles(raw: Option<&Vec<String>>, field: &str) -> Result<Vec<String>, ConfigError> {
+    let Some(roles) = raw else {
+        return Ok(vec![DEFAULT_API_ROLE.to_string()]);
+    };
+
+    if let Some(role) = roles.iter().find(|role| !API_ROLES.contains(&role.as_str())) {
+        return Err(ConfigError::Validation(format!(
+            "{field} MUST contain only {}: {role}",
+            API_ROLES.join("|")
+        )));
+    }
+
+    Ok(roles.clone())
+}
+
+fn resolve_events(raw: &RawEventsConfig, reorg_depth: u32) -> Result<EventsConfig, ConfigError> {
+    let levels = raw
+        .levels
+        .clone()
+        .unwrap_or_else(|| EVENT_LEVELS.iter().map(|level| level.to_string()).collect());
+    if levels.is_empty() {
+        return Err(ConfigError::Validation(
+            "indexer.events.levels MUST be non-empty".to_string(),
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    for level in &levels {
+        if !EVENT_LEVELS.contains(&level.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "indexer.events.levels has unsupported value: {level}"
+            )));
+        }
+        if !seen.insert(level.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "indexer.events.levels MUST be unique: {level}"
+            )));
+        }
+    }
+
+    // By default a transaction is final once it is deeper than the reorg window.
+    let finality_depth = raw.finality_depth.unwrap_or(reorg_depth.saturating_add(1));
+    if finality_depth == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.events.finality_depth MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(EventsConfig { levels, finality_depth })
+}
+
+fn resolve_headers(raw: &RawHeadersConfig) -> Result<HeadersConfig, ConfigError> {
+    let batch_size = raw.batch_size.unwrap_or(DEFAULT_HEADERS_BATCH_SIZE);
+    if batch_size == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.headers.batch_size MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(HeadersConfig { batch_size })
+}
+
+fn resolve_stats(raw: &RawStatsConfig) -> Result<StatsConfig, ConfigError> {
+    let interval_ms = raw.interval_ms.unwrap_or(DEFAULT_STATS_INTERVAL_MS);
+    if interval_ms == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.stats.interval_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(StatsConfig { interval_ms })
+}
+
+fn resolve_retention(raw: &RawRetentionConfig) -> Result<RetentionConfig, ConfigError> {
+    if raw.keep_blocks == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.retention.keep_blocks MUST be > 0".to_string(),
+        ));
+    }
+    let interval_ms = raw.interval_ms.unwrap_or(DEFAULT_RETENTION_INTERVAL_MS);
+    if interval_ms == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.retention.interval_ms MUST be > 0".to_string(),
+        ));
+    }
+    let batch_blocks = raw.batch_blocks.unwrap_or(DEFAULT_RETENTION_BATCH_BLOCKS);
+    if batch_blocks == 0 {
+        return Err(ConfigError::Validation(
+            "indexer.retention.batch_blocks MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(RetentionConfig {
+        keep_blocks: raw.keep_blocks,
+        interval_ms,
+        batch_blocks,
+    })
+}
+
+fn resolve_database(raw: RawDatabaseConfig) -> Result<DatabaseConfig, ConfigError> {
+    let url = match (raw.url, raw.url_env.as_ref()) {
+        (Some(_), Some(_)) => {
+            return Err(ConfigError::Validation(
+                "database.url and database.url_env MUST NOT be set together".to_string(),
+            ));
+        }
+        (Some(url), None) if url.trim().is_empty() => {
+            return Err(ConfigError::Validation("database.url MUST be non-empty".to_string()));
+        }
+        (url, _) => url.map(Zeroizing::new),
+    };
+    let url_env = raw.url_env.unwrap_or_else(|| DEFAULT_DATABASE_URL_ENV.to_string());
+    if url_env.trim().is_empty() {
+        return Err(ConfigError::Validation("database.url_env MUST be non-empty".to_string()));
+    }
+
+    let max_connections = raw.max_connections.unwrap_or(DEFAULT_DATABASE_MAX_CONNECTIONS);
+    if max_connections == 0 {
+        return Err(ConfigError::Validation(
+            "database.max_connections MUST be > 0".to_string(),
+        ));
+    }
+    let min_connections = raw.min_connections.unwrap_or(0);
+    if min_connections > max_connections {
+        return Err(ConfigError::Validation(
+            "database.min_connections MUST be <= database.max_connections".to_string(),
+        ));
+    }
+    let acquire_timeout_ms = raw.acquire_timeout_ms.unwrap_or(DEFAULT_DATABASE_ACQUIRE_TIMEOUT_MS);
+    if acquire_timeout_ms == 0 {
+        return Err(ConfigError::Validation(
+            "database.acquire_timeout_ms MUST be > 0".to_string(),
+        ));
+    }
+    let idle_timeout_ms = raw.idle_timeout_ms.unwrap_or(DEFAULT_DATABASE_IDLE_TIMEOUT_MS);
+    if idle_timeout_ms == 0 {
+        return Err(ConfigError::Validation(
+            "database.idle_timeout_ms MUST be > 0".to_string(),
+        ));
+    }
+    if raw.statement_timeout_ms == Some(0) {
+        return Err(ConfigError::Validation(
+            "database.statement_timeout_ms MUST be > 0".to_string(),
+        ));
+    }
+    if raw.replica_url.is_some() && raw.replica_url_env.is_some() {
+        return Err(ConfigError::Validation(
+            "database.replica_url and database.replica_url_env MUST NOT be set together".to_string(),
+        ));
+    }
+    if raw.replica_url.as_ref().is_some_and(|url| url.trim().is_empty()) {
+        return Err(ConfigError::Validation("database.replica_url MUST be non-empty".to_string()));
+    }
+    if raw.replica_url_env.as_ref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(ConfigError::Validation(
+            "database.replica_url_env MUST be non-empty".to_string(),
+        ));
+    }
+
+    Ok(DatabaseConfig {
+        url,
+        url_env,
+        max_connections,
+        min_connections,
+        acquire_timeout_ms,
+        idle_timeout_ms,
+        statement_timeout_ms: raw.statement_timeout_ms,
+        replica_url: raw.replica_url.map(Zeroizing::new),
+        replica_url_env: raw.replica_url_env,
+    })
+}
+
+fn resolve_signet(raw: &RawSignetConfig) -> Result<SignetConfig, ConfigError> {
+    let is_hex = |value: &str| hex::decode(value).is_ok();
+
+    if raw.challenge.is_empty() || !is_hex(&raw.challenge) {
+        return Err(ConfigError::Validation(
+            "indexer.signet.challenge MUST be a non-empty hex script".to_string(),
+        ));
+    }
+    if let Some(magic) = &raw.magic {
+        if magic.len() != 8 || !is_hex(magic) {
+            return Err(ConfigError::Validation(
+                "indexer.signet.magic MUST be 4 bytes of hex".to_string(),
+            ));
+        }
+    }
+    if let Some(genesis_hash) = &raw.genesis_hash {
+        if genesis_hash.len() != 64 || !is_hex(genesis_hash) {
+            return Err(ConfigError::Validation(
+                "indexer.signet.genesis_hash MUST be a 32-byte hex block hash".to_string(),
+            ));
+        }
+    }
+
+    Ok(SignetConfig {
+        challenge: raw.challenge.to_ascii_lowercase(),
+        magic: raw.magic.as_ref().map(|magic| magic.to_ascii_lowercase()),
+        genesis_hash: raw.genesis_hash.as_ref().map(|hash| hash.to_ascii_lowercase()),
+    })
+}
+
+fn resolve_replication(raw: &RawReplicationConfig) -> Result<ReplicationConfig, ConfigError> {
+    let publication = raw.publication.trim();
+    if !is_lowercase_identifier(publication) {
+        return Err(ConfigError::Validation(
+            "replication.publication MUST be a lowercase identifier ([a-z_][a-z0-9_]*, up to 63 chars)".to_string(),
+        ));
+    }
+
+    let tables = raw
+        .tables
+        .clone()
+        .unwrap_or_else(|| REPLICATION_TABLES.iter().map(|table| table.to_string()).collect());
+    if tables.is_empty() {
+        return Err(ConfigError::Validation(
+            "replication.tables MUST be non-empty".to_string(),
+        ));
+    }
+
+    let mut seen_tables = HashSet::new();
+    for table in &tables {
+        if !REPLICATION_TABLES.contains(&table.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "replication.tables has unsupported value: {table} (allowed: {})",
+                REPLICATION_TABLES.join("|")
+            )));
+        }
+
+        if !seen_tables.insert(table.as_str()) {
+            return Err(ConfigError::Validation(format!(
+                "replication.tables MUST be unique: {table}"
+            )));
+        }
+    }
+
+    let check_interval_ms = raw.check_interval_ms.unwrap_or(DEFAULT_REPLICATION_CHECK_INTERVAL_MS);
+    if check_interval_ms == 0 {
+        return Err(ConfigError::Validation(
+            "replication.check_interval_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    let max_slot_lag_bytes = raw.max_slot_lag_bytes.unwrap_or(DEFAULT_REPLICATION_MAX_SLOT_LAG_BYTES);
+    if max_slot_lag_bytes == 0 {
+        return Err(ConfigError::Validation(
+            "replication.max_slot_lag_bytes MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(ReplicationConfig {
+        publication: publication.to_string(),
+        tables,
+        check_interval_ms,
+        max_slot_lag_bytes,
+    })
+}
+
+fn resolve_logging(raw: &RawLoggingConfig) -> Result<LoggingConfig, ConfigError> {
+    let format = raw
+        .format
+        .as_deref()
+        .map(|format| format.trim().to_ascii_lowercase())
+        .unwrap_or_else(|| "json".to_string());
+    if !LOG_FORMATS.contains(&format.as_str()) {
+        return Err(ConfigError::Validation(format!(
+            "logging.format MUST be one of: {}",
+            LOG_FORMATS.join("|")
+        )));
+    }
+
+    let level = resolve_log_level(raw.level.as_deref().unwrap_or(DEFAULT_LOG_LEVEL), "logging.level")?;
+    let mut modules = Vec::new();
+    for (module, module_level) in &raw.modules {
+        let valid_module = !module.is_empty()
+            && module
+                .split("::")
+                .all(|segment| !segment.is_empty() && segment.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_'));
+        if !valid_module {
+            return Err(ConfigError::Validation(format!(
+                "logging.modules keys MUST be module paths like sqlx::query: {module}"
+            )));
+        }
+        modules.push((module.clone(), resolve_log_level(module_level, "logging.modules")?));
+    }
+
+    let file = raw.file.as_ref().map(resolve_log_file).transpose()?;
+    let otlp = raw.otlp.as_ref().map(resolve_otlp).transpose()?;
+
+    Ok(LoggingConfig {
+        format,
+        level,
+        modules,
+        file,
+        otlp,
+    })
+}
+
+fn resolve_log_level(value: &str, field: &str) -> Result<String, ConfigError> {
+    let level = value.trim().to_ascii_lowercase();
+    if !LOG_LEVELS.contains(&level.as_str()) {
+        return Err(ConfigError::Validation(format!(
+            "{field} MUST be one of: {}: {value}",
+            LOG_LEVELS.join("|")
+        )));
+    }
+    Ok(level)
+}
+
+fn resolve_log_file(raw: &RawLogFileConfig) -> Result<LogFileConfig, ConfigError> {
+    let path = PathBuf::from(raw.path.trim());
+    if path.file_name().is_none() {
+        return Err(ConfigError::Validation(format!(
+            "logging.file.path MUST name a file: {}",
+            raw.path
+        )));
+    }
+
+    let rotation = raw
+        .rotation
+        .as_deref()
+        .map(|rotation| rotation.trim().to_ascii_lowercase())
+        .unwrap_or_else(|| "daily".to_string());
+    if !LOG_ROTATIONS.contains(&rotation.as_str()) {
+        return Err(ConfigError::Validation(format!(
+            "logging.file.rotation MUST be one of: {}",
+            LOG_ROTATIONS.join("|")
+        )));
+    }
+
+    let max_files = raw.max_files.unwrap_or(DEFAULT_LOG_MAX_FILES);
+    if max_files == 0 {
+        return Err(ConfigError::Validation(
+            "logging.file.max_files MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(LogFileConfig {
+        path,
+        rotation,
+        max_files,
+    })
+}
+
+fn resolve_otlp(raw: &RawOtlpConfig) -> Result<OtlpConfig, ConfigError> {
+    let endpoint = raw.endpoint.trim().trim_end_matches('/').to_string();
+    if !(endpoint.starts_with("http://") || endpoint.starts_with("https://")) || endpoint.contains(char::is_whitespace)
+    {
+        return Err(ConfigError::Validation(format!(
+            "logging.otlp.endpoint MUST be an http(s) URL like http://otel-collector:4318: {}",
+            raw.endpoint
+        )));
+    }
+
+    let service_name = raw
+        .service_name
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or(DEFAULT_OTLP_SERVICE_NAME)
+        .to_string();
+    if service_name.is_empty() {
+        return Err(ConfigError::Validation(
+            "logging.otlp.service_name MUST be non-empty".to_string(),
+        ));
+    }
+
+    let export_interval_ms = raw.export_interval_ms.unwrap_or(DEFAULT_OTLP_EXPORT_INTERVAL_MS);
+    if export_interval_ms == 0 {
+        return Err(ConfigError::Validation(
+            "logging.otlp.export_interval_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    let max_queue_size = raw.max_queue_size.unwrap_or(DEFAULT_OTLP_MAX_QUEUE_SIZE);
+    if max_queue_size == 0 {
+        return Err(ConfigError::Validation(
+            "logging.otlp.max_queue_size MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(OtlpConfig {
+        endpoint,
+        service_name,
+        export_interval_ms,
+        max_queue_size,
+    })
+}
+
+fn resolve_alerting(raw: &RawAlertingConfig) -> Result<AlertingConfig, ConfigError> {
+    if raw.webhook.is_none() && raw.sentry.is_none() {
+        return Err(ConfigError::Validation(
+            "alerting MUST configure webhook or sentry".to_string(),
+        ));
+    }
+
+    let environment = raw.environment.as_deref().map(str::trim).filter(|environment| !environment.is_empty());
+    let min_interval_ms = raw.min_interval_ms.unwrap_or(DEFAULT_ALERT_MIN_INTERVAL_MS);
+    let error_threshold = raw.error_threshold.unwrap_or(DEFAULT_ALERT_ERROR_THRESHOLD);
+    if error_threshold == 0 {
+        return Err(ConfigError::Validation(
+            "alerting.error_threshold MUST be > 0".to_string(),
+        ));
+    }
+    let error_window_ms = raw.error_window_ms.unwrap_or(DEFAULT_ALERT_ERROR_WINDOW_MS);
+    if error_window_ms == 0 {
+        return Err(ConfigError::Validation(
+            "alerting.error_window_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    let error_types = match &raw.error_types {
+        Some(types) => types.iter().map(|error_type| error_type.trim().to_string()).collect::<Vec<_>>(),
+        None => DEFAULT_ALERT_ERROR_TYPES.iter().map(|error_type| error_type.to_string()).collect(),
+    };
+    if error_types.iter().any(String::is_empty) {
+        return Err(ConfigError::Validation(
+            "alerting.error_types MUST NOT contain empty values".to_string(),
+        ));
+    }
+
+    let webhook = match &raw.webhook {
+        Some(webhook) => {
+            let url = webhook.url.trim().to_string();
+            if !(url.starts_with("http://") || url.starts_with("https://")) || url.contains(char::is_whitespace) {
+                return Err(ConfigError::Validation(format!(
+                    "alerting.webhook.url MUST be an http(s) URL: {}",
+                    webhook.url
+                )));
+            }
+            let headers = webhook
+                .headers
+                .iter()
+                .map(|(name, value)| (name.clone(), Zeroizing::new(value.clone())))
+                .collect();
+            Some(AlertWebhookConfig { url, headers })
+        }
+        None => None,
+    };
+
+    let sentry = match &raw.sentry {
+        Some(sentry) => {
+            let dsn = Zeroizing::new(sentry.dsn.trim().to_string());
+            if let Err(reason) = alerting::SentryDsn::parse(&dsn) {
+                return Err(ConfigError::Validation(format!(
+                    "alerting.sentry.dsn MUST look like https://<key>@<host>/<project_id>: {reason}"
+                )));
+            }
+            Some(SentryConfig { dsn })
+        }
+        None => None,
+    };
+
+    let rules = match &raw.rules {
+        Some(rules) => {
+            let interval_ms = rules.interval_ms.unwrap_or(DEFAULT_ALERT_RULES_INTERVAL_MS);
+            if interval_ms == 0 {
+                return Err(ConfigError::Validation(
+                    "alerting.rules.interval_ms MUST be > 0".to_string(),
+                ));
+            }
+            Some(AlertRulesConfig {
+                interval_ms,
+                tip_lag_blocks: rules.tip_lag_blocks.unwrap_or(DEFAULT_ALERT_TIP_LAG_BLOCKS),
+                tip_lag_for_ms: rules.tip_lag_for_ms.unwrap_or(DEFAULT_ALERT_TIP_LAG_FOR_MS),
+                job_failed: rules.job_failed.unwrap_or(true),
+                mempool_stalled_ms: rules.mempool_stalled_ms.unwrap_or(DEFAULT_ALERT_MEMPOOL_STALLED_MS),
+            })
+        }
+        None => None,
+    };
+
+    Ok(AlertingConfig {
+        environment: environment.map(str::to_string),
+        min_interval_ms,
+        error_threshold,
+        error_window_ms,
+        error_types,
+        webhook,
+        sentry,
+        rules,
+    })
+}
+
+fn resolve_sink(raw: &RawSinkConfig) -> Result<SinkConfig, ConfigError> {
+    let kind = raw.kind.trim().to_ascii_lowercase();
+    if !SINK_KINDS.contains(&kind.as_str()) {
+        return Err(ConfigError::Validation(format!(
+            "sink.kind MUST be one of: {}",
+            SINK_KINDS.join("|")
+        )));
+    }
+
+    let servers: Vec<String> = raw.servers.iter().map(|server| server.trim().to_string()).collect();
+    if servers.is_empty() || servers.iter().any(String::is_empty) {
+        return Err(ConfigError::Validation(
+            "sink.servers MUST contain at least one non-empty address".to_string(),
+        ));
+    }
+
+    let topic_prefix = raw
+        .topic_prefix
+        .as_deref()
+        .map(str::trim)
+        .unwrap_or(DEFAULT_SINK_TOPIC_PREFIX)
+        .to_string();
+    // Valid both as a Kafka topic name and as NATS subject tokens.
+    let valid_prefix = !topic_prefix.is_empty()
+        && topic_prefix.len() <= 200
+        && topic_prefix.split('.').all(|token| !token.is_empty())
+        && topic_prefix
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+    if !valid_prefix {
+        return Err(ConfigError::Validation(
+            "sink.topic_prefix MUST consist of [A-Za-z0-9_-] tokens separated by single dots".to_string(),
+        ));
+    }
+
+    let batch_size = raw.batch_size.unwrap_or(DEFAULT_SINK_BATCH_SIZE);
+    if !(1..=MAX_SINK_BATCH_SIZE).contains(&batch_size) {
+        return Err(ConfigError::Validation(format!(
+            "sink.batch_size MUST be between 1 and {MAX_SINK_BATCH_SIZE}"
+        )));
+    }
+
+    let poll_interval_ms = raw.poll_interval_ms.unwrap_or(DEFAULT_SINK_POLL_INTERVAL_MS);
+    if poll_interval_ms == 0 {
+        return Err(ConfigError::Validation(
+            "sink.poll_interval_ms MUST be > 0".to_string(),
+        ));
+    }
+
+    Ok(SinkConfig {
+        kind,
+        servers,
+        topic_prefix,
+        batch_size,
+        poll_interval_ms,
+        retain_published_hours: raw
+            .retain_published_hours
+            .unwrap_or(DEFAULT_SINK_RETAIN_PUBLISHED_HOURS),
+    })
+}
+
+fn resolve_secret_env(env_name: &str, field: &str) -> Result<Zeroizing<String>, ConfigError> {
+    if env_name.trim().is_empty() {
+        return Err(ConfigError::Validation(format!("{field} MUST be non-empty")));
+    }
+
+    let value = env::var(env_name).map_err(|_| {
+        ConfigError::Validation(format!("env variable '{env_name}' MUST be set"))
+    })?;
+
+    if value.is_empty() {
+        return Err(ConfigError::Validation(format!(
+            "env variable '{env_name}' MUST be non-empty"
+        )));
+    }
+
+    Ok(Zeroizing::new(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::tempdir;
+
+    use super::{AppConfig, DatabaseConfig, LoggingConfig, RateLimitConfig, RateLimitRule};
+
+    fn write_file(path: &std::path::Path) {
+        fs::write(path, b"x").expect("write file");
+    }
+
+    fn make_yaml(paths: &[(&str, String)], jobs: &str, reorg_depth: i64) -> String {
+        let mut p = std::collections::HashMap::new();
+        for (k, v) in paths {
+            p.insert(*k, v.clone());
+        }
+
+        format!(
+            r#"
+server:
+  bind_host: "0.0.0.0"
+  bind_port: 8443
+  tls:
+    cert_path: "{server_cert}"
+    key_path: "{server_key}"
+  auth:
+    basic:
+      username: "admin"
+      password_env: "INDEXER_API_PASSWORD"
+rpc:
+  node_id: "btc-mainnet-1"
+  url: "https://nginx-rpc:443"
+  auth:
+    basic:
+      username: "rpcuser"
+      password_env: "BITCOIN_RPC_PASSWORD"
+  insecure_skip_verify: false
+  mtls:
+    ca_path: "{ca}"
+    client_cert_path: "{client_cert}"
+    client_key_path: "{client_key}"
+  timeouts:
+    connect_ms: 5000
+    request_ms: 30000
+indexer:
+  chain: "bitcoin"
+  network: "mainnet"
+  reorg_depth: {reorg_depth}
+  poll:
+    tip_interval_ms: 5000
+    mempool_interval_ms: 3000
+  concurrency:
+    max_jobs: 5
+    rpc_parallelism: 8
+    db_writer_parallelism: 4
+  batching:
+    blocks_per_batch: 50
+    txs_per_batch: 5000
+jobs:
+{jobs}
+"#,
+            server_cert = p["server_cert"],
+            server_key = p["server_key"],
+            ca = p["ca"],
+            client_cert = p["client_cert"],
+            client_key = p["client_key"],
+            reorg_depth = reorg_depth,
+            jobs = jobs
+        )
+    }
+
+    /// [`make_yaml`] with the TLS and mTLS files written to `dir` and the password env
+    /// variables it references set.
+    fn valid_config_yaml(dir: &std::path::Path, jobs: &str, reorg_depth: i64) -> String {
+        let paths = [
+            ("server_cert", "server.crt"),
+            ("server_key", "server.key"),
+            ("ca", "ca.crt"),
+            ("client_cert", "client.crt"),
+            ("client_key", "client.key"),
+        ]
+        .map(|(name, file)| {
+            let path = dir.join(file);
+            write_file(&path);
+            (name, path.display().to_string())
+        });
+
+        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
+        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
+
+        make_yaml(&paths, jobs, reorg_depth)
+    }
+
+    #[test]
+    fn loads_valid_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, yaml).expect("write yaml");
+
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        assert_eq!(cfg.server.auth.basic.username, "admin");
+        assert!(cfg.server.auth.api_keys.is_empty());
+        assert!(cfg.server.auth.tokens.is_empty());
+        assert!(cfg.server.auth.jwt.is_none());
+        assert!(cfg.server.auth.hmac.is_none());
+        assert_eq!(cfg.rpc.auth.username, "rpcuser");
+        assert_eq!(cfg.jobs.len(), 1);
+        assert!(cfg.indexer.poll.block_template_interval_ms.is_none());
+    }
+
+    #[test]
+    fn rejects_negative_reorg_depth() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            -1,
+        );
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, yaml).expect("write yaml");
+
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("reorg_depth"));
+    }
+
+    #[test]
+    fn rejects_invalid_network() {
+        let dir = tempdir().expect("tempdir");
+
+        let mut yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+
+        yaml = yaml.replace("network: \"mainnet\"", "network: \"unknown\"");
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, yaml).expect("write yaml");
+
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("indexer.network"));
+    }
+
+    #[test]
+    fn rejects_duplicate_job_ids() {
+        let dir = tempdir().expect("tempdir");
+
+        let jobs = "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n";
+
+        let yaml = valid_config_yaml(dir.path(), jobs, 12);
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, yaml).expect("write yaml");
+
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("job_id MUST be unique"));
+    }
+
+    #[test]
+    fn resolves_server_auth_tokens_roles_and_password_hashes() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let with_tokens = |tokens: &str| {
+            yaml.replace(
+                "      password_env: \"INDEXER_API_PASSWORD\"\n",
+                &format!("      password_env: \"INDEXER_API_PASSWORD\"\n    tokens:\n{tokens}"),
+            )
+        };
+
+        std::env::set_var("INDEXER_TOKEN_DASHBOARD", "dashboard-token");
+
+        let cfg = AppConfig::from_yaml(
+            &with_tokens(concat!(
+                "      - label: \"dashboard\"\n        token_env: \"INDEXER_TOKEN_DASHBOARD\"\n        roles: [\"read\"]\n",
+                "      - label: \"old-etl\"\n        token_env: \"INDEXER_TOKEN_OLD_ETL_UNSET\"\n        enabled: false\n",
+            )),
+            Vec::new(),
+        )
+        .expect("tokens should load");
+        let tokens = &cfg.server.auth.tokens;
+        assert_eq!(tokens.len(), 2);
+        assert_eq!(tokens[0].token.as_deref().map(String::as_str), Some("dashboard-token"));
+        assert_eq!(tokens[0].roles, vec!["read".to_string()]);
+        assert!(tokens[0].enabled);
+        assert_eq!(tokens[1].token, None);
+        assert_eq!(tokens[1].roles, vec!["operator".to_string()]);
+        assert!(!tokens[1].enabled);
+
+        let err = AppConfig::from_yaml(
+            &with_tokens("      - label: \"etl\"\n        token_env: \"INDEXER_TOKEN_ETL_UNSET\"\n"),
+            Vec::new(),
+        )
+        .expect_err("should fail");
+        assert!(err.to_string().contains("env variable 'INDEXER_TOKEN_ETL_UNSET' MUST be set"));
+
+        let err = AppConfig::from_yaml(
+            &with_tokens(concat!(
+                "      - label: \"dashboard\"\n        token_env: \"INDEXER_TOKEN_DASHBOARD\"\n",
+                "      - label: \"dashboard\"\n        token_env: \"INDEXER_TOKEN_DASHBOARD\"\n",
+            )),
+            Vec::new(),
+        )
+        .expect_err("should fail");
+        assert!(err.to_string().contains("server.auth.tokens[*].label MUST be unique: dashboard"));
+
+        let err = AppConfig::from_yaml(
+            &with_tokens(concat!(
+                "      - label: \"dashboard\"\n        token_env: \"INDEXER_TOKEN_DASHBOARD\"\n",
+                "        roles: [\"root\"]\n",
+            )),
+            Vec::new(),
+        )
+        .expect_err("should fail");
+        assert!(err.to_string().contains("server.auth.tokens[*].roles MUST contain only read|operator|admin: root"));
+
+        let hashed = yaml.replace("INDEXER_API_PASSWORD", "INDEXER_API_PASSWORD_HASH");
+        std::env::set_var("INDEXER_API_PASSWORD_HASH", "$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW");
+        AppConfig::from_yaml(&hashed, Vec::new()).expect("bcrypt hash should load");
+        std::env::set_var("INDEXER_API_PASSWORD_HASH", "$2b$12$truncated");
+        let err = AppConfig::from_yaml(&hashed, Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("env variable 'INDEXER_API_PASSWORD_HASH' MUST be a valid bcrypt hash"));
+        std::env::set_var("INDEXER_API_PASSWORD_HASH", "$argon2id$v=19$m=8,t=1,p=1$c2FsdHNhbHRzYWx0");
+        let err = AppConfig::from_yaml(&hashed, Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("MUST be a valid Argon2 PHC hash: salt or hash is missing"));
+    }
+
+    #[test]
+    fn rejects_empty_address_list() {
+        let dir = tempdir().expect("tempdir");
+
+        let jobs = "  - job_id: \"watchlist\"\n    mode: \"address_list\"\n    enabled: true\n";
+
+        let yaml = valid_config_yaml(dir.path(), jobs, 12);
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, yaml).expect("write yaml");
+
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("addresses MUST be non-empty"));
+    }
+
+    #[test]
+    fn rejects_inverted_height_range() {
+        let dir = tempdir().expect("tempdir");
+
+        let jobs = "  - job_id: \"window\"\n    mode: \"height_range\"\n    enabled: true\n    from_height: 750000\n    to_height: 700000\n";
+
+        let yaml = valid_config_yaml(dir.path(), jobs, 12);
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, yaml).expect("write yaml");
+
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("from_height <= to_height"));
+    }
+
+    #[test]
+    fn validates_descriptor_jobs() {
+        let dir = tempdir().expect("tempdir");
+
+        let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+        let job = |extra: &str| {
+            format!("  - job_id: \"wallet\"\n    mode: \"descriptor\"\n    enabled: true\n{extra}")
+        };
+
+        let yaml = valid_config_yaml(dir.path(), &job(&format!("    descriptor: \"{zpub}\"\n    gap_limit: 5\n")), 12);
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("descriptor job should load");
+        assert_eq!(cfg.jobs[0].descriptor.as_deref(), Some(zpub));
+        assert_eq!(cfg.jobs[0].gap_limit, Some(5));
+
+        let err = AppConfig::from_yaml(&valid_config_yaml(dir.path(), &job(""), 12), Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("jobs[wallet].descriptor MUST be set for descriptor mode"));
+
+        let yaml = valid_config_yaml(dir.path(), &job(&format!("    descriptor: \"{zpub}\"\n    gap_limit: 0\n")), 12);
+        let err = AppConfig::from_yaml(&yaml, Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("jobs[wallet].gap_limit MUST be between 1 and 1000"));
+
+        let yaml = valid_config_yaml(dir.path(), &job(&format!("    descriptor: \"{zpub}\"\n")), 12);
+        let err = AppConfig::from_yaml(&yaml, vec![("INDEXER__INDEXER__NETWORK".to_string(), "testnet".to_string())])
+            .expect_err("should fail");
+        assert!(err.to_string().contains("jobs[wallet].descriptor: extended key"));
+    }
+
+    #[test]
+    fn validates_export_jobs() {
+        let dir = tempdir().expect("tempdir");
+
+        let job = |extra: &str| {
+            format!(
+                "  - job_id: \"dump\"\n    mode: \"export\"\n    enabled: true\n    from_height: 0\n    to_height: 1000\n{extra}"
+            )
+        };
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            &job("    export:\n      format: \"parquet\"\n      destination: \"s3://analytics/btc\"\n"),
+            12,
+        );
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("export job should load");
+        let export = cfg.jobs[0].export.as_ref().expect("export section");
+        assert_eq!(export.destination, "s3://analytics/btc");
+        assert_eq!(export.tables, vec!["blocks", "transactions", "tx_outputs"]);
+
+        let err = AppConfig::from_yaml(&valid_config_yaml(dir.path(), &job(""), 12), Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("jobs[dump].export MUST be set for export mode"));
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            &job("    export:\n      format: \"csv\"\n      destination: \"/data\"\n      tables: [\"utxos_current\"]\n"),
+            12,
+        );
+        let err = AppConfig::from_yaml(&yaml, Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("jobs[dump].export.tables MUST be distinct values of"));
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            &job("    export:\n      format: \"csv\"\n      destination: \"exports\"\n"),
+            12,
+        );
+        let err = AppConfig::from_yaml(&yaml, Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("jobs[dump].export.destination MUST be an absolute path"));
+    }
+
+    #[test]
+    fn parses_and_validates_job_retry_policy() {
+        let dir = tempdir().expect("tempdir");
+
+        let valid = "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n    retry:\n      max_attempts: 3\n      backoff_ms: 1000\n";
+        let invalid = "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n    retry:\n      max_attempts: 0\n      backoff_ms: 1000\n";
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), valid, 12)).expect("write yaml");
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        let retry = cfg.jobs[0].retry.expect("retry policy");
+        assert_eq!(retry.max_backoff_ms, 600_000);
+        assert_eq!(retry.backoff(0).as_millis(), 1000);
+        assert_eq!(retry.backoff(3).as_millis(), 8000);
+        assert_eq!(retry.backoff(40).as_millis(), 600_000);
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), invalid, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("jobs[full].retry.max_attempts MUST be > 0"));
+    }
+
+    #[test]
+    fn parses_and_validates_replication_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let job = "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n";
+        let valid = format!("{job}replication:\n  publication: \"indexer_cdc\"\n  tables: [\"blocks\", \"tx_outputs\"]\n");
+        let defaults = format!("{job}replication:\n  publication: \"indexer_cdc\"\n");
+        let bad_table = format!("{job}replication:\n  publication: \"indexer_cdc\"\n  tables: [\"jobs\"]\n");
+        let bad_name = format!("{job}replication:\n  publication: \"cdc; DROP TABLE blocks\"\n");
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &valid, 12)).expect("write yaml");
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        let replication = cfg.replication.expect("replication config");
+        assert_eq!(replication.publication, "indexer_cdc");
+        assert_eq!(replication.tables, vec!["blocks".to_string(), "tx_outputs".to_string()]);
+        assert_eq!(replication.check_interval_ms, 30_000);
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &defaults, 12)).expect("write yaml");
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        assert_eq!(cfg.replication.expect("replication config").tables.len(), 7);
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &bad_table, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("replication.tables has unsupported value: jobs"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &bad_name, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("replication.publication MUST be a lowercase identifier"));
+    }
+
+    #[test]
+    fn parses_and_validates_sink_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let job = "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n";
+        let valid = format!("{job}sink:\n  kind: \"Kafka\"\n  servers: [\"kafka-1:9092\", \"kafka-2:9092\"]\n");
+        let bad_kind = format!("{job}sink:\n  kind: \"redis\"\n  servers: [\"redis:6379\"]\n");
+        let no_servers = format!("{job}sink:\n  kind: \"nats\"\n  servers: []\n");
+        let bad_prefix = format!("{job}sink:\n  kind: \"nats\"\n  servers: [\"nats://nats:4222\"]\n  topic_prefix: \"btc.*\"\n");
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &valid, 12)).expect("write yaml");
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        let sink = cfg.sink.expect("sink config");
+        assert_eq!(sink.kind, "kafka");
+        assert_eq!(sink.servers.len(), 2);
+        assert_eq!(sink.topic_prefix, "indexer");
+        assert_eq!(sink.batch_size, 500);
+        assert_eq!(sink.retain_published_hours, 24);
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &bad_kind, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("sink.kind MUST be one of: kafka|nats"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &no_servers, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("sink.servers MUST contain at least one non-empty address"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &bad_prefix, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("sink.topic_prefix MUST consist of"));
+    }
+
+    #[test]
+    fn parses_and_validates_logging_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let job = "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n";
+        let valid = format!("{job}logging:\n  otlp:\n    endpoint: \"http://otel-collector:4318/\"\n");
+        let bad_endpoint = format!("{job}logging:\n  otlp:\n    endpoint: \"otel-collector:4317\"\n");
+        let bad_interval =
+            format!("{job}logging:\n  otlp:\n    endpoint: \"http://tempo:4318\"\n    export_interval_ms: 0\n");
+        let console = format!(
+            "{job}logging:\n  format: \"Pretty\"\n  level: \"warn\"\n  modules:\n    sqlx::query: \"error\"\n    bitcoin_blockchain_indexer::modules::rpc: \"DEBUG\"\n  file:\n    path: \"/var/log/indexer/indexer.log\"\n    rotation: \"hourly\"\n"
+        );
+        let bad_format = format!("{job}logging:\n  format: \"xml\"\n");
+        let bad_level = format!("{job}logging:\n  modules:\n    sqlx::query: \"verbose\"\n");
+        let bad_module = format!("{job}logging:\n  modules:\n    \"sqlx=debug\": \"info\"\n");
+        let bad_rotation = format!("{job}logging:\n  file:\n    path: \"indexer.log\"\n    rotation: \"weekly\"\n");
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), job, 12)).expect("write yaml");
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        assert_eq!(cfg.logging, LoggingConfig::default());
+        assert_eq!((cfg.logging.format.as_str(), cfg.logging.level.as_str()), ("json", "info"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &console, 12)).expect("write yaml");
+        let logging = AppConfig::load_from_path(&yaml_path).expect("config should load").logging;
+        assert_eq!((logging.format.as_str(), logging.level.as_str()), ("pretty", "warn"));
+        assert_eq!(
+            logging.modules,
+            vec![
+                ("bitcoin_blockchain_indexer::modules::rpc".to_string(), "debug".to_string()),
+                ("sqlx::query".to_string(), "error".to_string()),
+            ]
+        );
+        let file = logging.file.expect("log file");
+        assert_eq!(file.path, std::path::PathBuf::from("/var/log/indexer/indexer.log"));
+        assert_eq!((file.rotation.as_str(), file.max_files), ("hourly", 7));
+
+        for (yaml, message) in [
+            (&bad_format, "logging.format MUST be one of: json|pretty|compact"),
+            (&bad_level, "logging.modules MUST be one of"),
+            (&bad_module, "logging.modules keys MUST be module paths"),
+            (&bad_rotation, "logging.file.rotation MUST be one of: daily|hourly|never"),
+        ] {
+            fs::write(&yaml_path, valid_config_yaml(dir.path(), yaml, 12)).expect("write yaml");
+            let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+            assert!(err.to_string().contains(message), "{err}");
+        }
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &valid, 12)).expect("write yaml");
+        let next = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        let otlp = next.logging.otlp.clone().expect("otlp config");
+        assert_eq!(otlp.endpoint, "http://otel-collector:4318");
+        assert_eq!(otlp.service_name, "blockchain-indexer");
+        assert_eq!(otlp.export_interval_ms, 5_000);
+        assert_eq!(otlp.max_queue_size, 2_048);
+        let err = cfg.ensure_reloadable(&next).expect_err("logging is restart-only");
+        assert!(err.to_string().contains("logging"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &bad_endpoint, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("logging.otlp.endpoint MUST be an http(s) URL"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &bad_interval, 12)).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+        assert!(err.to_string().contains("logging.otlp.export_interval_ms MUST be > 0"));
+    }
+
+    #[test]
+    fn parses_and_validates_alerting_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let job = "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n";
+        let valid = format!(
+            "{job}alerting:\n  environment: \"production\"\n  error_types: [\"rpc\"]\n  webhook:\n    url: \"https://hooks.example.com/indexer\"\n    headers:\n      Authorization: \"Bearer secret\"\n  sentry:\n    dsn: \"https://abc@o1.ingest.sentry.io/42\"\n"
+        );
+        let no_destination = format!("{job}alerting:\n  environment: \"production\"\n");
+        let bad_url = format!("{job}alerting:\n  webhook:\n    url: \"hooks.example.com\"\n");
+        let bad_dsn = format!("{job}alerting:\n  sentry:\n    dsn: \"https://o1.ingest.sentry.io/42\"\n");
+        let bad_threshold =
+            format!("{job}alerting:\n  error_threshold: 0\n  webhook:\n    url: \"http://alertmanager:9093\"\n");
+        let rules = format!(
+            "{job}alerting:\n  webhook:\n    url: \"http://alertmanager:9093\"\n  rules:\n    tip_lag_blocks: 3\n    mempool_stalled_ms: 0\n"
+        );
+        let bad_rules =
+            format!("{job}alerting:\n  webhook:\n    url: \"http://alertmanager:9093\"\n  rules:\n    interval_ms: 0\n");
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), job, 12)).expect("write yaml");
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        assert!(cfg.alerting.is_none());
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &valid, 12)).expect("write yaml");
+        let next = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        let alerting = next.alerting.clone().expect("alerting config");
+        assert_eq!(alerting.environment.as_deref(), Some("production"));
+        assert_eq!((alerting.min_interval_ms, alerting.error_threshold), (300_000, 10));
+        assert_eq!(alerting.error_window_ms, 60_000);
+        assert_eq!(alerting.error_types, vec!["rpc".to_string()]);
+        let webhook = alerting.webhook.expect("webhook");
+        assert_eq!(webhook.url, "https://hooks.example.com/indexer");
+        assert_eq!(webhook.headers[0].0, "Authorization");
+        assert_eq!(webhook.headers[0].1.as_str(), "Bearer secret");
+        assert_eq!(alerting.sentry.expect("sentry").dsn.as_str(), "https://abc@o1.ingest.sentry.io/42");
+        assert!(next.alerting.as_ref().is_some_and(|alerting| alerting.rules.is_none()));
+        let err = cfg.ensure_reloadable(&next).expect_err("alerting is restart-only");
+        assert!(err.to_string().contains("alerting"));
+
+        fs::write(&yaml_path, valid_config_yaml(dir.path(), &rules, 12)).expect("write yaml");
+        let rules = AppConfig::load_from_path(&yaml_path)
+            .expect("config should load")
+            .alerting
+            .and_then(|alerting| alerting.rules)
+            .expect("alert rules");
+        assert_eq!((rules.interval_ms, rules.tip_lag_blocks, rules.tip_lag_for_ms), (30_000, 3, 600_000));
+        assert!(rules.job_failed);
+        assert_eq!(rules.mempool_stalled_ms, 0);
+
+        for (yaml, message) in [
+            (&no_destination, "alerting MUST configure webhook or sentry"),
+            (&bad_url, "alerting.webhook.url MUST be an http(s) URL"),
+            (&bad_dsn, "alerting.sentry.dsn MUST look like"),
+            (&bad_threshold, "alerting.error_threshold MUST be > 0"),
+            (&bad_rules, "alerting.rules.interval_ms MUST be > 0"),
+        ] {
+            fs::write(&yaml_path, valid_config_yaml(dir.path(), yaml, 12)).expect("write yaml");
+            let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
+            assert!(err.to_string().contains(message), "{err}");
+        }
+    }
 
-            if !matches!(job.mode.as_str(), "all_addresses" | "address_list") {
-                return Err(ConfigError::Validation(format!(
-                    "jobs[*].mode has unsupported value: {}",
-                    job.mode
-                )));
-            }
+    #[test]
+    fn applies_env_overrides_before_validation() {
+        let dir = tempdir().expect("tempdir");
 
-            let addresses = job.addresses.unwrap_or_default();
-            if job.mode == "address_list" && addresses.is_empty() {
-                return Err(ConfigError::Validation(format!(
-                    "jobs[{job_id}].addresses MUST be non-empty for address_list mode",
-                    job_id = job.job_id
-                )));
-            }
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
 
-            jobs.push(JobConfig {
-                job_id: job.job_id,
-                mode: job.mode,
-                enabled: job.enabled,
-                addresses,
-            });
-        }
+        let cfg = AppConfig::from_yaml(
+            &yaml,
+            vars(&[
+                ("INDEXER__SERVER__BIND_PORT", "9443"),
+                ("INDEXER__RPC__NODE_ID", "1234"),
+                ("INDEXER__INDEXER__POLL__BLOCK_TEMPLATE_INTERVAL_MS", "30000"),
+                ("INDEXER__JOBS__0__ENABLED", "false"),
+                ("INDEXER__REPLICATION__PUBLICATION", "indexer_cdc"),
+                ("INDEXER__REPLICATION__TABLES", "[\"blocks\"]"),
+                ("INDEXER_CONFIG_PATH", "ignored.yaml"),
+            ]),
+        )
+        .expect("config should load");
+        assert_eq!(cfg.server.bind_port, 9443);
+        assert_eq!(cfg.rpc.node_id, "1234");
+        assert_eq!(cfg.indexer.poll.block_template_interval_ms, Some(30_000));
+        assert!(!cfg.jobs[0].enabled);
+        assert_eq!(cfg.replication.expect("replication config").tables, vec!["blocks".to_string()]);
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__REORG_DEPTH", "-1")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.reorg_depth MUST be >= 0"));
+
+        let cfg = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__RPC__MAX_REQUESTS_PER_SECOND", "25")]))
+            .expect("config with rpc rate limit");
+        assert_eq!(cfg.rpc.max_requests_per_second, Some(25));
+        assert_eq!(cfg.rpc.timeouts.startup_ms, 600_000);
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__RPC__MAX_REQUESTS_PER_SECOND", "0")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("rpc.max_requests_per_second MUST be > 0"));
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__CONCURRENCY__RPC_PARALLELISM", "0")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.concurrency.rpc_parallelism MUST be > 0"));
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__CONCURRENCY__DB_WRITER_PARALLELISM", "0")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.concurrency.db_writer_parallelism MUST be > 0"));
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__BATCHING__TXS_PER_BATCH", "0")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.batching.txs_per_batch MUST be > 0"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__JOBS__3__ENABLED", "false")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("jobs.3 is out of range"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__SERVER__BIND_PORT__X", "1")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("server.bind_port is not a mapping or list"));
+    }
 
-        Ok(AppConfig {
-            server: ServerConfig {
-                bind_host: raw.server.bind_host,
-                bind_port: raw.server.bind_port,
-                tls: TlsConfig {
-                    cert_path: PathBuf::from(raw.server.tls.cert_path),
-                    key_path: PathBuf::from(raw.server.tls.key_path),
-                },
-                auth: server_auth,
-            },
-            rpc: RpcConfig {
-                node_id: raw.rpc.node_id,
-                url: raw.rpc.url,
-                auth: rpc_auth,
-                mtls,
-                insecure_skip_verify: raw.rpc.insecure_skip_verify.unwrap_or(false),
-                timeouts: RpcTimeouts {
-                    connect_ms: raw.rpc.timeouts.connect_ms,
-                    request_ms: raw.rpc.timeouts.request_ms,
-                },
-            },
-            indexer: IndexerConfig {
-                chain: raw.indexer.chain,
-                network: raw.indexer.network,
-                reorg_depth: raw.indexer.reorg_depth as u32,
-                poll: PollConfig {
-                    tip_interval_ms: raw.indexer.poll.tip_interval_ms,
-                    mempool_interval_ms: raw.indexer.poll.mempool_interval_ms,
-                },
-                concurrency: ConcurrencyConfig {
-                    max_jobs: raw.indexer.concurrency.max_jobs,
-                    rpc_parallelism: raw.indexer.concurrency.rpc_parallelism,
-                    db_writer_parallelism: raw.indexer.concurrency.db_writer_parallelism,
-                },
-                batching: BatchingConfig {
-                    blocks_per_batch: raw.indexer.batching.blocks_per_batch,
-                    txs_per_batch: raw.indexer.batching.txs_per_batch,
-                },
-            },
-            jobs,
-        })
+    #[test]
+    fn validates_network_signet_and_job_addresses() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"watch\"\n    mode: \"address_list\"\n    enabled: true\n    addresses: [\"tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx\"]\n",
+            12,
+        );
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let cfg = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__NETWORK", "testnet4")]))
+            .expect("testnet4 should load");
+        assert_eq!(cfg.indexer.network, "testnet4");
+        assert!(cfg.indexer.signet.is_none());
+
+        let cfg = AppConfig::from_yaml(
+            &yaml,
+            vars(&[
+                ("INDEXER__INDEXER__NETWORK", "signet"),
+                ("INDEXER__INDEXER__SIGNET__CHALLENGE", "5121AB"),
+            ]),
+        )
+        .expect("custom signet should load");
+        let signet = cfg.indexer.signet.expect("signet config");
+        assert_eq!(signet.challenge, "5121ab");
+        assert!(signet.magic.is_none());
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__NETWORK", "mainnet")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("jobs[watch].addresses: address tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx does not belong to network mainnet"));
+
+        let err = AppConfig::from_yaml(
+            &yaml,
+            vars(&[
+                ("INDEXER__INDEXER__NETWORK", "testnet"),
+                ("INDEXER__INDEXER__SIGNET__CHALLENGE", "51ab"),
+            ]),
+        )
+        .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.signet is only supported for network signet"));
+
+        let err = AppConfig::from_yaml(
+            &yaml,
+            vars(&[
+                ("INDEXER__INDEXER__NETWORK", "signet"),
+                ("INDEXER__INDEXER__SIGNET__CHALLENGE", "\"51\""),
+                ("INDEXER__INDEXER__SIGNET__MAGIC", "0a03"),
+            ]),
+        )
+        .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.signet.magic MUST be 4 bytes of hex"));
     }
-}
 
-fn validate_readable_file(path: &str) -> Result<(), ConfigError> {
-    File::open(path).map_err(|err| {
-        ConfigError::Validation(format!("file '{path}' MUST exist and be readable: {err}"))
-    })?;
-    Ok(())
-}
+    #[test]
+    fn validates_instances() {
+        let dir = tempdir().expect("tempdir");
 
-fn resolve_basic_auth(raw: &RawBasicAuth) -> Result<BasicAuthResolved, ConfigError> {
-    if raw.password_env.trim().is_empty() {
-        return Err(ConfigError::Validation(
-            "password_env MUST be non-empty".to_string(),
-        ));
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        ) + r#"
+instances:
+  - rpc:
+      node_id: "btc-testnet-1"
+      url: "https://testnet-rpc:443"
+      auth:
+        basic:
+          username: "rpcuser"
+          password_env: "BITCOIN_RPC_PASSWORD"
+      timeouts:
+        connect_ms: 5000
+        request_ms: 30000
+    indexer:
+      chain: "bitcoin"
+      network: "testnet4"
+      reorg_depth: 6
+      poll: { tip_interval_ms: 5000, mempool_interval_ms: 3000 }
+      concurrency: { max_jobs: 2, rpc_parallelism: 4, db_writer_parallelism: 2 }
+      batching: { blocks_per_batch: 50, txs_per_batch: 5000 }
+    jobs:
+      - job_id: "watch"
+        mode: "address_list"
+        enabled: true
+        addresses: ["tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"]
+"#;
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("instance should load");
+        assert_eq!(cfg.instances.len(), 1);
+        let instance = &cfg.instances[0];
+        assert_eq!(instance.name, "testnet4");
+        assert_eq!(instance.schema, "indexer_testnet4");
+        assert_eq!(instance.rpc.node_id, "btc-testnet-1");
+        assert!(instance.rpc.mtls.is_none());
+        assert_eq!(instance.jobs[0].job_id, "watch");
+
+        let cfg = AppConfig::from_yaml(
+            &yaml,
+            vars(&[("INDEXER__INSTANCES__0__NAME", "test-4"), ("INDEXER__INSTANCES__0__SCHEMA", "btc_test")]),
+        )
+        .expect("named instance should load");
+        assert_eq!(cfg.instances[0].name, "test-4");
+        assert_eq!(cfg.instances[0].schema, "btc_test");
+
+        let next = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__INDEXER__REORG_DEPTH", "8")]))
+            .expect("changed instance should load");
+        let err = cfg.ensure_reloadable(&next).expect_err("instances are restart-only");
+        assert!(err.to_string().contains("instances cannot be changed without restart"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__INDEXER__NETWORK", "mainnet")]))
+            .expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("instances[*].name MUST be unique and differ from indexer.network: mainnet"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__NAME", "jobs")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("instances[*].name MUST NOT be one of"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__NAME", "scan")]))
+            .expect_err("/v1/scan must not be shadowed");
+        assert!(err.to_string().contains("instances[*].name MUST NOT be one of"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__NAME", "node")]))
+            .expect_err("/v1/node/info must not be shadowed");
+        assert!(err.to_string().contains("instances[*].name MUST NOT be one of"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__SCHEMA", "public")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("instances[testnet4].schema MUST be a lowercase identifier"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__INDEXER__NETWORK", "regtest")]))
+            .expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("instances[regtest].jobs[watch].addresses: address tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INSTANCES__0__INDEXER__CHAIN", "litecoin")]))
+            .expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("instances[testnet4].indexer.network MUST be one of: mainnet|testnet|regtest for chain litecoin"));
     }
 
-    let password = env::var(&raw.password_env).map_err(|_| {
-        ConfigError::Validation(format!(
-            "env variable '{}' MUST be set",
-            raw.password_env
-        ))
-    })?;
+    #[test]
+    fn validates_chain_and_its_networks() {
+        let dir = tempdir().expect("tempdir");
 
-    Ok(BasicAuthResolved {
-        username: raw.username.clone(),
-        password,
-    })
-}
+        let litecoin_yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"watch\"\n    mode: \"address_list\"\n    enabled: true\n    addresses: [\"ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9\"]\n",
+            12,
+        )
+        .replace("chain: \"bitcoin\"", "chain: \"litecoin\"");
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
 
-#[cfg(test)]
-mod tests {
-    use std::fs;
+        let cfg = AppConfig::from_yaml(&litecoin_yaml, Vec::new()).expect("litecoin mainnet should load");
+        assert_eq!(cfg.indexer.chain, "litecoin");
 
-    use tempfile::tempdir;
+        let err = AppConfig::from_yaml(&litecoin_yaml, vars(&[("INDEXER__INDEXER__NETWORK", "signet")]))
+            .expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("indexer.network MUST be one of: mainnet|testnet|regtest for chain litecoin"));
 
-    use super::AppConfig;
+        let err = AppConfig::from_yaml(&litecoin_yaml, vars(&[("INDEXER__INDEXER__NETWORK", "testnet")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("does not belong to network testnet of chain litecoin"));
 
-    fn write_file(path: &std::path::Path) {
-        fs::write(path, b"x").expect("write file");
-    }
+        let err = AppConfig::from_yaml(&litecoin_yaml, vars(&[("INDEXER__INDEXER__CHAIN", "dogecoin")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("does not belong to network mainnet of chain dogecoin"));
 
-    fn make_yaml(paths: &[(&str, String)], jobs: &str, reorg_depth: i64) -> String {
-        let mut p = std::collections::HashMap::new();
-        for (k, v) in paths {
-            p.insert(*k, v.clone());
-        }
+        let err = AppConfig::from_yaml(&litecoin_yaml, vars(&[("INDEXER__INDEXER__CHAIN", "ethereum")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.chain MUST be one of: bitcoin|litecoin|dogecoin"));
 
-        format!(
-            r#"
-server:
-  bind_host: "0.0.0.0"
-  bind_port: 8443
-  tls:
-    cert_path: "{server_cert}"
-    key_path: "{server_key}"
-  auth:
-    basic:
-      username: "admin"
-      password_env: "INDEXER_API_PASSWORD"
-rpc:
-  node_id: "btc-mainnet-1"
-  url: "https://nginx-rpc:443"
-  auth:
-    basic:
-      username: "rpcuser"
-      password_env: "BITCOIN_RPC_PASSWORD"
-  insecure_skip_verify: false
-  mtls:
-    ca_path: "{ca}"
-    client_cert_path: "{client_cert}"
-    client_key_path: "{client_key}"
-  timeouts:
-    connect_ms: 5000
-    request_ms: 30000
-indexer:
-  chain: "bitcoin"
-  network: "mainnet"
-  reorg_depth: {reorg_depth}
-  poll:
-    tip_interval_ms: 5000
-    mempool_interval_ms: 3000
-  concurrency:
-    max_jobs: 5
-    rpc_parallelism: 8
-    db_writer_parallelism: 4
-  batching:
-    blocks_per_batch: 50
-    txs_per_batch: 5000
-jobs:
-{jobs}
-"#,
-            server_cert = p["server_cert"],
-            server_key = p["server_key"],
-            ca = p["ca"],
-            client_cert = p["client_cert"],
-            client_key = p["client_key"],
-            reorg_depth = reorg_depth,
-            jobs = jobs
+        let descriptor_yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"wallet\"\n    mode: \"descriptor\"\n    enabled: true\n    descriptor: \"wpkh(xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V/0/*)\"\n",
+            12,
         )
+        .replace("chain: \"bitcoin\"", "chain: \"dogecoin\"");
+        let err = AppConfig::from_yaml(&descriptor_yaml, Vec::new()).expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("jobs[wallet].mode descriptor is only supported for chain bitcoin"));
     }
 
     #[test]
-    fn loads_valid_config() {
+    fn validates_events_config() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
-
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
-
-        let yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
-            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
+        let vars = |pairs: &[(&str, &str)]| -> Vec<(String, String)> {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect()
+        };
 
-        let yaml_path = dir.path().join("indexer.yaml");
-        fs::write(&yaml_path, yaml).expect("write yaml");
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert!(cfg.indexer.events.is_none());
 
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
+        let cfg = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__EVENTS__FINALITY_DEPTH", "100")]))
+            .expect("events should load");
+        let events = cfg.indexer.events.expect("events config");
+        assert_eq!(events.levels, vec!["seen", "confirmed", "finalized"]);
+        assert_eq!(events.finality_depth, 100);
 
-        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
-        assert_eq!(cfg.server.auth.username, "admin");
-        assert_eq!(cfg.rpc.auth.username, "rpcuser");
-        assert_eq!(cfg.jobs.len(), 1);
+        let cfg = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__EVENTS__LEVELS", "[confirmed]")]))
+            .expect("events should load");
+        let events = cfg.indexer.events.expect("events config");
+        assert_eq!(events.levels, vec!["confirmed"]);
+        assert_eq!(events.finality_depth, 13);
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__EVENTS__LEVELS", "[seen, mined]")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.events.levels has unsupported value: mined"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__EVENTS__LEVELS", "[seen, seen]")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.events.levels MUST be unique: seen"));
+
+        let err = AppConfig::from_yaml(&yaml, vars(&[("INDEXER__INDEXER__EVENTS__FINALITY_DEPTH", "0")]))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.events.finality_depth MUST be > 0"));
     }
 
     #[test]
-    fn rejects_negative_reorg_depth() {
+    fn validates_headers_config() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
-
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
-
-        let yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
-            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
-            -1,
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
         );
+        let var = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert!(cfg.indexer.headers.is_none());
+
+        let cfg = AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__HEADERS", "{}")).expect("headers should load");
+        assert_eq!(cfg.indexer.headers.as_ref().expect("headers config").batch_size, 2_000);
+
+        let next = AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__HEADERS__BATCH_SIZE", "500"))
+            .expect("headers should load");
+        assert_eq!(next.indexer.headers.as_ref().expect("headers config").batch_size, 500);
+        assert!(cfg.ensure_reloadable(&next).is_ok());
+        let err = AppConfig::from_yaml(&yaml, Vec::new())
+            .expect("config should load")
+            .ensure_reloadable(&next)
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.headers (enable/disable)"));
+
+        let err = AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__HEADERS__BATCH_SIZE", "0"))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.headers.batch_size MUST be > 0"));
+    }
 
-        let yaml_path = dir.path().join("indexer.yaml");
-        fs::write(&yaml_path, yaml).expect("write yaml");
+    #[test]
+    fn validates_stats_config() {
+        let dir = tempdir().expect("tempdir");
 
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let var = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert!(cfg.indexer.stats.is_none());
+
+        let cfg = AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__STATS", "{}")).expect("stats should load");
+        assert_eq!(cfg.indexer.stats.as_ref().expect("stats config").interval_ms, 60_000);
+
+        let next = AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__STATS__INTERVAL_MS", "5000"))
+            .expect("stats should load");
+        assert_eq!(next.indexer.stats.as_ref().expect("stats config").interval_ms, 5_000);
+        assert!(cfg.ensure_reloadable(&next).is_ok());
+        let err = AppConfig::from_yaml(&yaml, Vec::new())
+            .expect("config should load")
+            .ensure_reloadable(&next)
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.stats (enable/disable)"));
+
+        let err = AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__STATS__INTERVAL_MS", "0"))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.stats.interval_ms MUST be > 0"));
+    }
 
-        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
-        assert!(err.to_string().contains("reorg_depth"));
+    #[test]
+    fn validates_retention_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let var = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert!(cfg.indexer.retention.is_none());
+
+        let keep = |value: &str| {
+            vec![("INDEXER__INDEXER__RETENTION__KEEP_BLOCKS".to_string(), value.to_string())]
+        };
+        let cfg = AppConfig::from_yaml(&yaml, keep("1000")).expect("retention should load");
+        let retention = cfg.indexer.retention.as_ref().expect("retention config");
+        assert_eq!(retention.keep_blocks, 1_000);
+        assert_eq!(retention.interval_ms, 3_600_000);
+        assert_eq!(retention.batch_blocks, 1_000);
+
+        let mut overrides = keep("50");
+        overrides.push(("INDEXER__INDEXER__RETENTION__BATCH_BLOCKS".to_string(), "10".to_string()));
+        let next = AppConfig::from_yaml(&yaml, overrides).expect("retention should load");
+        assert_eq!(next.indexer.retention.as_ref().expect("retention config").batch_blocks, 10);
+        assert!(cfg.ensure_reloadable(&next).is_ok());
+        let err = AppConfig::from_yaml(&yaml, Vec::new())
+            .expect("config should load")
+            .ensure_reloadable(&next)
+            .expect_err("should fail");
+        assert!(err.to_string().contains("indexer.retention (enable/disable)"));
+
+        assert!(AppConfig::from_yaml(&yaml, var("INDEXER__INDEXER__RETENTION__INTERVAL_MS", "5000")).is_err());
+        let err = AppConfig::from_yaml(&yaml, keep("0")).expect_err("should fail");
+        assert!(err.to_string().contains("indexer.retention.keep_blocks MUST be > 0"));
+        let mut overrides = keep("10");
+        overrides.push(("INDEXER__INDEXER__RETENTION__INTERVAL_MS".to_string(), "0".to_string()));
+        let err = AppConfig::from_yaml(&yaml, overrides).expect_err("should fail");
+        assert!(err.to_string().contains("indexer.retention.interval_ms MUST be > 0"));
     }
 
     #[test]
-    fn rejects_invalid_network() {
+    fn validates_database_config() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
-
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
-
-        let mut yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
-            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
+        let var = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
 
-        yaml = yaml.replace("network: \"mainnet\"", "network: \"unknown\"");
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert_eq!(cfg.database, DatabaseConfig::default());
+        assert_eq!(cfg.database.url_env, "DATABASE_URL");
 
-        let yaml_path = dir.path().join("indexer.yaml");
-        fs::write(&yaml_path, yaml).expect("write yaml");
+        let tuned = format!(
+            "{yaml}database:\n  url_env: \"INDEXER_DATABASE_URL\"\n  max_connections: 20\n  min_connections: 2\n  \
+             acquire_timeout_ms: 5000\n  statement_timeout_ms: 60000\n"
+        );
+        let next = AppConfig::from_yaml(&tuned, Vec::new()).expect("database should load");
+        assert_eq!(next.database.url, None);
+        assert_eq!(next.database.url_env, "INDEXER_DATABASE_URL");
+        assert_eq!(next.database.max_connections, 20);
+        assert_eq!(next.database.min_connections, 2);
+        assert_eq!(next.database.acquire_timeout_ms, 5000);
+        assert_eq!(next.database.idle_timeout_ms, 600_000);
+        assert_eq!(next.database.statement_timeout_ms, Some(60_000));
+        let err = cfg.ensure_reloadable(&next).expect_err("should fail");
+        assert!(err.to_string().contains("database"));
+
+        let inline = AppConfig::from_yaml(&yaml, var("INDEXER__DATABASE__URL", "postgres://db/indexer"))
+            .expect("url should load");
+        assert_eq!(inline.database.url.as_deref().map(String::as_str), Some("postgres://db/indexer"));
+
+        for (key, value, message) in [
+            ("MAX_CONNECTIONS", "0", "database.max_connections MUST be > 0"),
+            ("MIN_CONNECTIONS", "11", "database.min_connections MUST be <= database.max_connections"),
+            ("ACQUIRE_TIMEOUT_MS", "0", "database.acquire_timeout_ms MUST be > 0"),
+            ("IDLE_TIMEOUT_MS", "0", "database.idle_timeout_ms MUST be > 0"),
+            ("STATEMENT_TIMEOUT_MS", "0", "database.statement_timeout_ms MUST be > 0"),
+            ("URL_ENV", "\" \"", "database.url_env MUST be non-empty"),
+        ] {
+            let err = AppConfig::from_yaml(&yaml, var(&format!("INDEXER__DATABASE__{key}"), value))
+                .expect_err("should fail");
+            assert!(err.to_string().contains(message), "{key}: {err}");
+        }
 
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
+        let both = format!("{yaml}database:\n  url: \"postgres://db/indexer\"\n  url_env: \"INDEXER_DATABASE_URL\"\n");
+        let err = AppConfig::from_yaml(&both, Vec::new()).expect_err("should fail");
+        assert!(err.to_string().contains("database.url and database.url_env MUST NOT be set together"));
 
-        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
-        assert!(err.to_string().contains("indexer.network"));
+        let replica = AppConfig::from_yaml(&yaml, var("INDEXER__DATABASE__REPLICA_URL_ENV", "INDEXER_REPLICA_URL"))
+            .expect("replica should load");
+        assert_eq!(replica.database.replica_url_env.as_deref(), Some("INDEXER_REPLICA_URL"));
+        assert!(cfg.ensure_reloadable(&replica).is_err());
+        let both = format!(
+            "{yaml}database:\n  replica_url: \"postgres://replica/indexer\"\n  replica_url_env: \"INDEXER_REPLICA_URL\"\n"
+        );
+        let err = AppConfig::from_yaml(&both, Vec::new()).expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("database.replica_url and database.replica_url_env MUST NOT be set together"));
     }
 
     #[test]
-    fn rejects_duplicate_job_ids() {
+    fn validates_rpc_rest_url() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let var = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
 
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert_eq!(cfg.rpc.rest_url, None);
 
-        let jobs = "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n";
+        let next = AppConfig::from_yaml(&yaml, var("INDEXER__RPC__REST_URL", "http://bitcoind:8332/"))
+            .expect("rest_url should load");
+        assert_eq!(next.rpc.rest_url.as_deref(), Some("http://bitcoind:8332"));
+        let err = cfg.ensure_reloadable(&next).expect_err("should fail");
+        assert!(err.to_string().contains("rpc"));
 
-        let yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
-            jobs,
+        let err = AppConfig::from_yaml(&yaml, var("INDEXER__RPC__REST_URL", "bitcoind:8332"))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("rpc.rest_url MUST be an http:// or https:// URL"));
+    }
+
+    #[test]
+    fn validates_storage_config() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
+        let var = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
 
-        let yaml_path = dir.path().join("indexer.yaml");
-        fs::write(&yaml_path, yaml).expect("write yaml");
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert!(cfg.storage.store_decoded_tx);
+        assert!(!cfg.storage.store_witness);
 
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
+        let next = AppConfig::from_yaml(&yaml, var("INDEXER__STORAGE__STORE_DECODED_TX", "false"))
+            .expect("storage should load");
+        assert!(!next.storage.store_decoded_tx);
+        let err = cfg.ensure_reloadable(&next).expect_err("should fail");
+        assert!(err.to_string().contains("storage"));
+    }
 
-        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
-        assert!(err.to_string().contains("job_id MUST be unique"));
+    #[test]
+    fn validates_shutdown_grace_period() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let vars = |name: &str, value: &str| vec![(name.to_string(), value.to_string())];
+
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert_eq!(cfg.server.shutdown_grace_period_ms, 30_000);
+        assert_eq!(cfg.server.tls.expiry_warning_days, 30);
+        assert_eq!(cfg.server.tls.watch_interval_ms, 10_000);
+        let watch_off = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__TLS__WATCH_INTERVAL_MS", "0"))
+            .expect("disabled watcher should load");
+        assert_eq!(watch_off.server.tls.watch_interval_ms, 0);
+        assert!(!cfg.server.tls.enabled);
+        let https = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__TLS__ENABLED", "true")).expect("https should load");
+        assert!(https.server.tls.enabled);
+
+        assert!(cfg.server.unix_socket.is_none());
+        let unix = AppConfig::from_yaml(
+            &yaml,
+            vec![
+                ("INDEXER__SERVER__BIND".to_string(), "unix:/run/indexer/api.sock".to_string()),
+                ("INDEXER__SERVER__SOCKET_MODE".to_string(), "'600'".to_string()),
+            ],
+        )
+        .expect("unix socket should load");
+        let socket = unix.server.unix_socket.expect("unix socket");
+        assert_eq!(socket.path, std::path::PathBuf::from("/run/indexer/api.sock"));
+        assert_eq!(socket.mode, 0o600);
+        assert!(!socket.trust_forwarded);
+        let default_mode = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__BIND", "unix:/tmp/api.sock"))
+            .expect("unix socket should load");
+        assert_eq!(default_mode.server.unix_socket.expect("unix socket").mode, 0o660);
+        let trusted = AppConfig::from_yaml(
+            &yaml,
+            vec![
+                ("INDEXER__SERVER__BIND".to_string(), "unix:/tmp/api.sock".to_string()),
+                ("INDEXER__SERVER__SOCKET_TRUST_FORWARDED".to_string(), "true".to_string()),
+            ],
+        )
+        .expect("trusted unix socket should load");
+        assert!(trusted.server.unix_socket.expect("unix socket").trust_forwarded);
+        for (name, value) in [
+            ("INDEXER__SERVER__BIND", "0.0.0.0:8080"),
+            ("INDEXER__SERVER__BIND", "unix:"),
+            ("INDEXER__SERVER__SOCKET_MODE", "'660'"),
+            ("INDEXER__SERVER__SOCKET_TRUST_FORWARDED", "true"),
+        ] {
+            assert!(AppConfig::from_yaml(&yaml, vars(name, value)).is_err(), "{name}={value}");
+        }
+        let bad_mode = vec![
+            ("INDEXER__SERVER__BIND".to_string(), "unix:/tmp/api.sock".to_string()),
+            ("INDEXER__SERVER__SOCKET_MODE".to_string(), "'1777'".to_string()),
+        ];
+        assert!(AppConfig::from_yaml(&yaml, bad_mode).is_err());
+        let unix_https = vec![
+            ("INDEXER__SERVER__BIND".to_string(), "unix:/tmp/api.sock".to_string()),
+            ("INDEXER__SERVER__TLS__ENABLED".to_string(), "true".to_string()),
+        ];
+        assert!(AppConfig::from_yaml(&yaml, unix_https).is_err());
+
+        assert!(cfg.server.admin.is_none());
+        let admin = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__ADMIN__BIND_PORT", "9090"))
+            .expect("admin listener should load");
+        let admin = admin.server.admin.expect("admin listener");
+        assert_eq!((admin.bind_host.as_str(), admin.bind_port), ("127.0.0.1", 9090));
+        assert_eq!(admin.auth, cfg.server.auth);
+        std::env::set_var("INDEXER_ADMIN_PASSWORD", "admin-pass");
+        let own_auth = AppConfig::from_yaml(
+            &yaml,
+            vars(
+                "INDEXER__SERVER__ADMIN",
+                "{bind_host: 0.0.0.0, bind_port: 9090, auth: {basic: {username: ops, password_env: INDEXER_ADMIN_PASSWORD}}}",
+            ),
+        )
+        .expect("admin auth should load");
+        let admin = own_auth.server.admin.expect("admin listener");
+        assert_eq!(admin.bind_host, "0.0.0.0");
+        assert_eq!(admin.auth.basic.username, "ops");
+        assert_eq!(admin.auth.basic.password.as_str(), "admin-pass");
+        let same_port = vec![
+            ("INDEXER__SERVER__ADMIN__BIND_HOST".to_string(), cfg.server.bind_host.clone()),
+            ("INDEXER__SERVER__ADMIN__BIND_PORT".to_string(), cfg.server.bind_port.to_string()),
+        ];
+        assert!(AppConfig::from_yaml(&yaml, same_port).is_err());
+
+        let cfg = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__SHUTDOWN_GRACE_PERIOD_MS", "5000"))
+            .expect("grace period should load");
+        assert_eq!(cfg.server.shutdown_grace_period_ms, 5000);
+
+        let err = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__SHUTDOWN_GRACE_PERIOD_MS", "0"))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("server.shutdown_grace_period_ms MUST be > 0"));
+
+        assert!(!cfg.server.degraded_start);
+        assert_eq!(cfg.server.storage_retry_interval_ms, 2000);
+        let cfg = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__DEGRADED_START", "true"))
+            .expect("degraded start should load");
+        assert!(cfg.server.degraded_start);
+
+        assert!(cfg.server.swagger_ui);
+        let cfg = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__SWAGGER_UI", "false"))
+            .expect("swagger ui switch should load");
+        assert!(!cfg.server.swagger_ui);
+
+        assert!(cfg.server.compression);
+        let cfg = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__COMPRESSION", "false"))
+            .expect("compression switch should load");
+        assert!(!cfg.server.compression);
+
+        assert_eq!(cfg.server.rate_limit, RateLimitConfig::default());
+        let cfg = AppConfig::from_yaml(
+            &yaml,
+            vars(
+                "INDEXER__SERVER__RATE_LIMIT",
+                "{per_ip: {requests_per_sec: 10}, per_credential: {requests_per_sec: 5, burst: 20}}",
+            ),
+        )
+        .expect("rate limit should load");
+        assert_eq!(
+            cfg.server.rate_limit.per_ip,
+            Some(RateLimitRule { requests_per_sec: 10, burst: 10 })
+        );
+        assert_eq!(cfg.server.rate_limit.per_credential.map(|rule| rule.burst), Some(20));
+        let err = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__RATE_LIMIT", "{per_ip: {requests_per_sec: 0}}"))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("server.rate_limit.per_ip.requests_per_sec and burst MUST be > 0"));
+
+        let err = AppConfig::from_yaml(&yaml, vars("INDEXER__SERVER__STORAGE_RETRY_INTERVAL_MS", "0"))
+            .expect_err("should fail");
+        assert!(err.to_string().contains("server.storage_retry_interval_ms MUST be > 0"));
     }
 
     #[test]
-    fn rejects_empty_address_list() {
+    fn parses_trusted_proxies() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let vars = |value: &str| vec![("INDEXER__SERVER__TRUSTED_PROXIES".to_string(), value.to_string())];
 
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
+        let cfg = AppConfig::from_yaml(&yaml, Vec::new()).expect("config should load");
+        assert!(cfg.server.trusted_proxies.is_empty());
 
-        let jobs = "  - job_id: \"watchlist\"\n    mode: \"address_list\"\n    enabled: true\n";
+        let cfg = AppConfig::from_yaml(&yaml, vars("[\"10.1.2.3/8\", \"192.168.0.7\", \"fd00::/8\"]"))
+            .expect("trusted proxies should load");
+        let proxies: Vec<String> = cfg.server.trusted_proxies.iter().map(ToString::to_string).collect();
+        assert_eq!(proxies, vec!["10.0.0.0/8", "192.168.0.7/32", "fd00::/8"]);
 
-        let yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
-            jobs,
+        let err = AppConfig::from_yaml(&yaml, vars("[\"10.0.0.0/33\"]")).expect_err("should fail");
+        assert!(err
+            .to_string()
+            .contains("server.trusted_proxies MUST contain IP addresses or CIDRs: 10.0.0.0/33"));
+    }
+
+    #[test]
+    fn reload_allows_runtime_settings_and_rejects_restart_only_changes() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
 
         let yaml_path = dir.path().join("indexer.yaml");
         fs::write(&yaml_path, yaml).expect("write yaml");
-
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
-
-        let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
-        assert!(err.to_string().contains("addresses MUST be non-empty"));
+        let current = AppConfig::load_from_path(&yaml_path).expect("config should load");
+
+        let mut next = current.clone();
+        next.indexer.poll.tip_interval_ms = 1000;
+        next.indexer.batching.blocks_per_batch = 10;
+        next.jobs[0].enabled = false;
+        current.ensure_reloadable(&next).expect("runtime settings are reloadable");
+
+        next.server.bind_port += 1;
+        next.server.tls.cert_path = dir.path().join("other.crt");
+        let err = current.ensure_reloadable(&next).expect_err("should require restart");
+        assert!(err
+            .to_string()
+            .contains("server.bind_host/bind_port, server.tls cannot be changed without restart"));
     }
 
     #[test]
     fn rejects_missing_password_env() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
-
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
-
-        let mut yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
+        let mut yaml = valid_config_yaml(
+            dir.path(),
             "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
@@ -670,46 +3972,66 @@ jobs:
         fs::write(&yaml_path, yaml).expect("write yaml");
 
         std::env::remove_var("MISSING_ENV");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
 
         let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
         assert!(err.to_string().contains("MISSING_ENV"));
     }
 
+    #[test]
+    fn reads_passwords_from_files() {
+        let dir = tempdir().expect("tempdir");
+
+        let yaml = valid_config_yaml(
+            dir.path(),
+            "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
+            12,
+        );
+        let secret = dir.path().join("rpc-password");
+        fs::write(&secret, "rotated-pass\r\n").expect("write secret");
+        let yaml = yaml.replace(
+            "password_env: \"BITCOIN_RPC_PASSWORD\"",
+            &format!("password_file: \"{}\"", secret.display()),
+        );
+
+        let yaml_path = dir.path().join("indexer.yaml");
+        fs::write(&yaml_path, &yaml).expect("write yaml");
+
+        let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
+        assert_eq!(cfg.rpc.auth.password.as_str(), "rotated-pass");
+        assert_eq!(cfg.rpc.auth.password_file.as_deref(), Some(secret.as_path()));
+        assert!(cfg.server.auth.basic.password_file.is_none());
+        let files = cfg.credential_files();
+        assert!(files.contains(&secret));
+        assert!(files.contains(&dir.path().join("server.crt")));
+        assert!(files.contains(&dir.path().join("client.key")));
+
+        fs::write(&secret, "\n").expect("write empty secret");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("empty secret file");
+        assert!(err.to_string().contains("MUST be non-empty"));
+
+        let both = yaml.replace(
+            "password_file:",
+            "password_env: \"BITCOIN_RPC_PASSWORD\"\n      password_file:",
+        );
+        fs::write(&yaml_path, both).expect("write yaml");
+        let err = AppConfig::load_from_path(&yaml_path).expect_err("both sources");
+        assert!(err.to_string().contains("exactly one of password_env or password_file"));
+    }
+
     #[test]
     fn rejects_missing_files() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
-
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        // client_key intentionally missing
-
-        let yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
+        let yaml = valid_config_yaml(
+            dir.path(),
             "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
+        fs::remove_file(dir.path().join("client.key")).expect("remove client key");
 
         let yaml_path = dir.path().join("indexer.yaml");
         fs::write(&yaml_path, yaml).expect("write yaml");
 
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
-
         let err = AppConfig::load_from_path(&yaml_path).expect_err("should fail");
         assert!(err.to_string().contains("client.key"));
     }
@@ -718,26 +4040,8 @@ jobs:
     fn allows_mtls_disabled_without_files() {
         let dir = tempdir().expect("tempdir");
 
-        let server_cert = dir.path().join("server.crt");
-        let server_key = dir.path().join("server.key");
-        let ca = dir.path().join("ca.crt");
-        let client_cert = dir.path().join("client.crt");
-        let client_key = dir.path().join("client.key");
-
-        write_file(&server_cert);
-        write_file(&server_key);
-        write_file(&ca);
-        write_file(&client_cert);
-        write_file(&client_key);
-
-        let mut yaml = make_yaml(
-            &[
-                ("server_cert", server_cert.display().to_string()),
-                ("server_key", server_key.display().to_string()),
-                ("ca", ca.display().to_string()),
-                ("client_cert", client_cert.display().to_string()),
-                ("client_key", client_key.display().to_string()),
-            ],
+        let mut yaml = valid_config_yaml(
+            dir.path(),
             "  - job_id: \"full-sync\"\n    mode: \"all_addresses\"\n    enabled: true\n",
             12,
         );
@@ -750,9 +4054,6 @@ jobs:
         let yaml_path = dir.path().join("indexer.yaml");
         fs::write(&yaml_path, yaml).expect("write yaml");
 
-        std::env::set_var("INDEXER_API_PASSWORD", "api-pass");
-        std::env::set_var("BITCOIN_RPC_PASSWORD", "rpc-pass");
-
         let cfg = AppConfig::load_from_path(&yaml_path).expect("config should load");
         assert!(cfg.rpc.mtls.is_none());
     }
@@ -1,10 +1,17 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use sqlx::{PgPool, Postgres, QueryBuilder, Row};
 use thiserror::Error;
 use utoipa::ToSchema;
 
+use crate::modules::filters::BASIC_FILTER_TYPE;
+use crate::modules::indexer::{BlockProvenance, PIPELINE_STAGES};
+use crate::modules::protocols::PROTOCOLS;
+use crate::modules::storage::repo::decompress_raw_block;
+use crate::modules::storage::SchemaFeatures;
+
 #[derive(Debug, Error)]
 pub enum DataError {
     #[error("address is not indexed")]
@@ -15,9 +22,30 @@ pub enum DataError {
     Storage(#[from] sqlx::Error),
 }
 
+/// Consensus limit for `scriptPubKey` size (10 000 bytes) in hex characters.
+const MAX_SCRIPT_PATTERN_HEX_LEN: usize = 20_000;
+
+/// Balance changes, as `(address, delta_sats)`, made by the committed chunks of a block that
+/// is still being written; reads of current balances take them back out.
+const INCOMPLETE_BALANCE_DELTAS: &str = "SELECT address, SUM(delta_sats)::BIGINT AS delta_sats FROM ( \
+       SELECT u.address, u.value_sats AS delta_sats \
+       FROM transactions t \
+       JOIN utxos_current u ON u.out_txid = t.txid \
+       WHERE t.status = 'incomplete' \
+       UNION ALL \
+       SELECT u.address, -u.value_sats \
+       FROM transactions t \
+       JOIN tx_inputs i ON i.txid = t.txid \
+       JOIN utxos_current u \
+         ON u.out_txid = i.prev_txid AND u.out_vout = i.prev_vout AND u.spent_in_txid = t.txid \
+       WHERE t.status = 'incomplete' \
+     ) changes \
+     GROUP BY address";
+
 #[derive(Debug, Clone)]
 pub struct DataService {
     pool: PgPool,
+    schema: SchemaFeatures,
 }
 
 #[derive(Debug, Clone, Copy, ToSchema)]
@@ -55,6 +83,12 @@ pub struct BlocksFilter {
     pub address: Option<String>,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ScriptSearchFilter {
+    pub pattern: String,
+    pub script_type: Option<String>,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct BalanceResponse {
     pub address: String,
@@ -97,6 +131,24 @@ pub struct UtxosResponse {
     pub items: Vec<UtxoItem>,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RuneBalanceItem {
+    /// Block and transaction index of the etching, `block:tx`.
+    pub rune_id: String,
+    pub name: String,
+    pub spaced_name: String,
+    pub symbol: Option<String>,
+    pub divisibility: i16,
+    /// Base units, as a decimal string since they may exceed 64 bits.
+    pub amount: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RuneBalancesResponse {
+    pub address: String,
+    pub items: Vec<RuneBalanceItem>,
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct TransactionIo {
     pub txid: Option<String>,
@@ -112,6 +164,8 @@ pub struct TransactionItem {
     pub block_height: Option<i32>,
     pub block_hash: Option<String>,
     pub time: i64,
+    /// `null` for coinbase and mempool transactions and when a spent output is unknown.
+    pub fee_sats: Option<i64>,
     pub inputs: Vec<TransactionIo>,
     pub outputs: Vec<TransactionIo>,
 }
@@ -131,6 +185,8 @@ pub struct BlockItem {
     pub prev_hash: String,
     pub time: i64,
     pub status: String,
+    /// `null` when the fee of any transaction in the block is unknown.
+    pub total_fees_sats: Option<i64>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
@@ -141,9 +197,128 @@ pub struct BlocksPage {
     pub total: i64,
 }
 
+/// Serialized block stored with `indexer.store_raw_blocks`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RawBlockItem {
+    pub hash: String,
+    pub height: i32,
+    /// Serialized size in bytes.
+    pub size: i32,
+    /// The block as returned by `getblock <hash> 0`.
+    pub hex: String,
+}
+
+/// BIP158 filter stored with `indexer.block_filters`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BlockFilterItem {
+    pub hash: String,
+    pub height: i32,
+    /// Always `basic`.
+    pub filter_type: String,
+    /// Serialized filter as hex, as served by BIP157 `cfilter` messages.
+    pub filter: String,
+    /// BIP157 filter header; `null` when the parent block has no stored filter header.
+    pub header: Option<String>,
+    /// `false` when scripts of spent outputs that are not indexed or were pruned are missing from the filter.
+    pub complete: bool,
+}
+
+/// Block known at a height, canonical or orphaned by a reorg.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ForkBlockItem {
+    pub height: i32,
+    pub hash: String,
+    pub prev_hash: String,
+    pub time: i64,
+    /// `canonical` or `orphaned`.
+    pub status: String,
+    /// `null` for the canonical block and for blocks orphaned before the time was recorded.
+    pub orphaned_at: Option<DateTime<Utc>>,
+    /// Transactions the block confirmed, in block order; for an orphaned block as of the reorg.
+    pub txids: Vec<String>,
+    pub total_fees_sats: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TopBalanceItem {
+    /// 1 for the largest balance; ties are ordered by address.
+    pub rank: i64,
+    pub address: String,
+    pub balance_sats: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TopBalancesPage {
+    pub items: Vec<TopBalanceItem>,
+    pub limit: i64,
+    /// Highest canonical block the balances include.
+    pub block_height: Option<i32>,
+}
+
+/// Mempool transaction replaced by a conflicting one that pays a higher fee (BIP-125).
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TxReplacementItem {
+    pub replaced_txid: String,
+    pub replacement_txid: String,
+    /// `null` when a spent output of the transaction is not indexed.
+    pub replaced_fee_sats: Option<i64>,
+    pub replacement_fee_sats: Option<i64>,
+    /// Current status of the replacement: `mempool`, `confirmed`, `dropped` (replaced in turn) or `orphaned`.
+    pub replacement_status: Option<String>,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// Contiguous canonical heights persisted with the same provenance.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProvenanceRange {
+    pub from_height: i32,
+    pub to_height: i32,
+    pub blocks: i64,
+    /// `null` for blocks indexed before provenance was recorded.
+    pub pipeline_version: Option<i32>,
+    pub indexer_version: Option<String>,
+    pub stages: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScriptMatchItem {
+    pub txid: String,
+    pub vout: i32,
+    pub value_sats: i64,
+    pub script_type: String,
+    pub address: Option<String>,
+    pub script_hex: String,
+    pub block_height: Option<i32>,
+    pub time: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScriptSearchPage {
+    pub items: Vec<ScriptMatchItem>,
+    pub offset: i64,
+    pub limit: i64,
+    pub total: i64,
+}
+
 impl DataService {
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Leaves out snapshots at the height of a block that is still being written in chunks.
+    fn skip_incomplete_heights(&self, builder: &mut QueryBuilder<'_, Postgres>) {
+        if self.schema.block_write_progress {
+            builder.push(" AND abh.block_height NOT IN (SELECT height FROM block_write_progress)");
+        }
     }
 
     pub async fn ensure_address_indexed(&self, address: &str) -> Result<(), DataError> {
@@ -151,7 +326,7 @@ impl DataService {
             "SELECT EXISTS(
                 SELECT 1
                 FROM jobs
-                WHERE mode = 'all_addresses'
+                WHERE mode IN ('all_addresses', 'height_range')
                   AND COALESCE((config_snapshot ->> 'enabled')::boolean, false) = true
             ) OR EXISTS(
                 SELECT 1
@@ -196,12 +371,15 @@ impl DataService {
             && filter.to_height.is_none();
 
         if current_query {
-            let balance_sats = sqlx::query_scalar::<_, i64>(
+            let balance_sats = sqlx::query_scalar::<_, i64>(&format!(
                 "SELECT COALESCE(
                     (SELECT balance_sats FROM address_balance_current WHERE address = $1),
                     0
-                )",
-            )
+                ) - COALESCE(
+                    (SELECT delta_sats FROM ({INCOMPLETE_BALANCE_DELTAS}) d WHERE d.address = $1),
+                    0
+                )"
+            ))
             .bind(address)
             .fetch_one(&self.pool)
             .await?;
@@ -286,7 +464,10 @@ impl DataService {
         let rows = sqlx::query(
             "SELECT out_txid, out_vout, value_sats
              FROM utxos_current
-             WHERE address = $1 AND status = 'unspent'
+             WHERE address = $1
+               AND (status = 'unspent'
+                    OR spent_in_txid IN (SELECT txid FROM transactions WHERE status = 'incomplete'))
+               AND out_txid NOT IN (SELECT txid FROM transactions WHERE status = 'incomplete')
              ORDER BY out_txid, out_vout",
         )
         .bind(address)
@@ -306,6 +487,44 @@ impl DataService {
         })
     }
 
+    /// Runes held by the unspent outputs of `address`, as indexed by jobs with the `runes`
+    /// protocol; empty without `0035_runes.sql`.
+    pub async fn get_rune_balances(&self, address: &str) -> Result<RuneBalancesResponse, DataError> {
+        self.ensure_address_indexed(address).await?;
+        if !self.schema.runes {
+            return Ok(RuneBalancesResponse {
+                address: address.to_string(),
+                items: Vec::new(),
+            });
+        }
+
+        let rows = sqlx::query(
+            "SELECT b.rune_id, r.name, r.spaced_name, r.symbol, r.divisibility, b.amount::TEXT AS amount
+             FROM rune_balances b
+             JOIN runes r ON r.rune_id = b.rune_id
+             WHERE b.address = $1
+             ORDER BY r.block_height, b.rune_id",
+        )
+        .bind(address)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(RuneBalancesResponse {
+            address: address.to_string(),
+            items: rows
+                .into_iter()
+                .map(|row| RuneBalanceItem {
+                    rune_id: row.get::<String, _>("rune_id"),
+                    name: row.get::<String, _>("name"),
+                    spaced_name: row.get::<String, _>("spaced_name"),
+                    symbol: row.get::<Option<String>, _>("symbol"),
+                    divisibility: row.get::<i16, _>("divisibility"),
+                    amount: row.get::<String, _>("amount"),
+                })
+                .collect(),
+        })
+    }
+
     pub async fn get_balance_history(
         &self,
         address: &str,
@@ -327,6 +546,7 @@ impl DataService {
             filter.from_time,
             filter.to_time,
         );
+        self.skip_incomplete_heights(&mut count_builder);
         let total = count_builder
             .build()
             .fetch_one(&self.pool)
@@ -346,6 +566,7 @@ impl DataService {
             filter.from_time,
             filter.to_time,
         );
+        self.skip_incomplete_heights(&mut builder);
         builder.push(" ORDER BY abh.block_height DESC, abh.time DESC");
         builder.push(" OFFSET ");
         builder.push_bind(pagination.offset);
@@ -413,10 +634,11 @@ impl DataService {
             .await?
             .get::<i64, _>("total");
 
-        let mut builder = QueryBuilder::<Postgres>::new(
-            "SELECT DISTINCT t.txid, t.status, t.block_height, t.block_hash, t.time
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT DISTINCT t.txid, t.status, t.block_height, t.block_hash, t.time, {}
              FROM transactions t",
-        );
+            self.fee_column()
+        ));
         append_transaction_joins(&mut builder, filter.address.as_deref());
         builder.push(" WHERE t.status = 'confirmed'");
         append_transaction_filters(
@@ -476,10 +698,11 @@ impl DataService {
             .await?
             .get::<i64, _>("total");
 
-        let mut builder = QueryBuilder::<Postgres>::new(
-            "SELECT DISTINCT b.height, b.hash, b.prev_hash, b.time, b.status
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT DISTINCT b.height, b.hash, b.prev_hash, b.time, b.status, {}
              FROM blocks b",
-        );
+            self.total_fees_column()
+        ));
         append_block_joins(&mut builder, filter.has_txid.as_deref(), filter.address.as_deref());
         builder.push(" WHERE b.status = 'canonical'");
         append_block_filters(
@@ -507,6 +730,7 @@ impl DataService {
                 prev_hash: row.get::<String, _>("prev_hash"),
                 time: row.get::<i64, _>("time"),
                 status: row.get::<String, _>("status"),
+                total_fees_sats: row.try_get::<i64, _>("total_fees_sats").ok(),
             })
             .collect();
 
@@ -518,6 +742,64 @@ impl DataService {
         })
     }
 
+    /// Confirmed outputs whose `scriptPubKey` matches a hex pattern, see [`script_pattern_to_like`].
+    pub async fn search_scripts(
+        &self,
+        filter: ScriptSearchFilter,
+        pagination: Pagination,
+    ) -> Result<ScriptSearchPage, DataError> {
+        let like = script_pattern_to_like(&filter.pattern)?;
+        if matches!(filter.script_type.as_deref(), Some(value) if value.trim().is_empty()) {
+            return Err(DataError::Validation("type MUST be non-empty".to_string()));
+        }
+
+        let mut count_builder = QueryBuilder::<Postgres>::new(
+            "SELECT COUNT(*) AS total
+             FROM tx_outputs o
+             JOIN transactions t ON t.txid = o.txid",
+        );
+        append_script_filters(&mut count_builder, &like, filter.script_type.as_deref());
+        let total = count_builder
+            .build()
+            .fetch_one(&self.pool)
+            .await?
+            .get::<i64, _>("total");
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT o.txid, o.vout, o.value_sats, o.script_type, o.address, o.script_hex, t.block_height, t.time
+             FROM tx_outputs o
+             JOIN transactions t ON t.txid = o.txid",
+        );
+        append_script_filters(&mut builder, &like, filter.script_type.as_deref());
+        builder.push(" ORDER BY t.block_height DESC NULLS LAST, o.txid, o.vout");
+        builder.push(" OFFSET ");
+        builder.push_bind(pagination.offset);
+        builder.push(" LIMIT ");
+        builder.push_bind(pagination.limit);
+
+        let rows = builder.build().fetch_all(&self.pool).await?;
+        let items = rows
+            .into_iter()
+            .map(|row| ScriptMatchItem {
+                txid: row.get::<String, _>("txid"),
+                vout: row.get::<i32, _>("vout"),
+                value_sats: row.get::<i64, _>("value_sats"),
+                script_type: row.get::<String, _>("script_type"),
+                address: row.get::<Option<String>, _>("address"),
+                script_hex: row.get::<String, _>("script_hex"),
+                block_height: row.get::<Option<i32>, _>("block_height"),
+                time: row.get::<i64, _>("time"),
+            })
+            .collect();
+
+        Ok(ScriptSearchPage {
+            items,
+            offset: pagination.offset,
+            limit: pagination.limit,
+            total,
+        })
+    }
+
     async fn list_transactions_by_status(
         &self,
         status: &str,
@@ -539,10 +821,11 @@ impl DataService {
             .await?
             .get::<i64, _>("total");
 
-        let mut builder = QueryBuilder::<Postgres>::new(
-            "SELECT DISTINCT t.txid, t.status, t.block_height, t.block_hash, t.time
+        let mut builder = QueryBuilder::<Postgres>::new(format!(
+            "SELECT DISTINCT t.txid, t.status, t.block_height, t.block_hash, t.time, {}
              FROM transactions t",
-        );
+            self.fee_column()
+        ));
         append_transaction_joins(&mut builder, address);
         builder.push(" WHERE t.status = ");
         builder.push_bind(status);
@@ -570,13 +853,19 @@ impl DataService {
             return Ok(Vec::new());
         }
 
-        let inputs_rows = sqlx::query(
-            "SELECT i.txid, i.prev_txid, i.prev_vout, prev_o.address, prev_o.value_sats
+        // Spent outputs below the indexed heights are only known from `tx_inputs`.
+        let prevout_columns = if self.schema.input_prevouts {
+            "COALESCE(prev_o.address, i.prev_address) AS address, COALESCE(prev_o.value_sats, i.prev_value_sats) AS value_sats"
+        } else {
+            "prev_o.address, prev_o.value_sats"
+        };
+        let inputs_rows = sqlx::query(&format!(
+            "SELECT i.txid, i.prev_txid, i.prev_vout, {prevout_columns}
              FROM tx_inputs i
              LEFT JOIN tx_outputs prev_o ON prev_o.txid = i.prev_txid AND prev_o.vout = i.prev_vout
              WHERE i.txid = ANY($1)
-             ORDER BY i.txid, i.vin",
-        )
+             ORDER BY i.txid, i.vin"
+        ))
         .bind(&txids)
         .fetch_all(&self.pool)
         .await?;
@@ -628,11 +917,328 @@ impl DataService {
                     block_height: row.try_get::<i32, _>("block_height").ok(),
                     block_hash: row.try_get::<String, _>("block_hash").ok(),
                     time: row.get::<i64, _>("time"),
+                    fee_sats: row.try_get::<i64, _>("fee_sats").ok(),
                     txid,
                 }
             })
             .collect())
     }
+
+    /// Largest confirmed balances, read from `address_balance_current`, which the indexer
+    /// updates with every block; addresses with a zero balance are left out.
+    pub async fn top_balances(&self, limit: Option<i64>) -> Result<TopBalancesPage, DataError> {
+        let Pagination { limit, .. } = Self::validate_pagination(None, limit)?;
+
+        let writing_block: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM transactions WHERE status = 'incomplete')")
+                .fetch_one(&self.pool)
+                .await?;
+        // The ranking index only serves the stored balances; they are corrected while a block
+        // is being written in chunks.
+        let query = if writing_block {
+            format!(
+                "SELECT b.address, b.balance_sats - COALESCE(d.delta_sats, 0) AS balance_sats, b.updated_at
+                 FROM address_balance_current b
+                 LEFT JOIN ({INCOMPLETE_BALANCE_DELTAS}) d ON d.address = b.address
+                 WHERE b.balance_sats - COALESCE(d.delta_sats, 0) > 0
+                 ORDER BY 2 DESC, b.address
+                 LIMIT $1"
+            )
+        } else {
+            "SELECT address, balance_sats, updated_at
+             FROM address_balance_current
+             WHERE balance_sats > 0
+             ORDER BY balance_sats DESC, address
+             LIMIT $1"
+                .to_string()
+        };
+        let rows = sqlx::query(&query).bind(limit).fetch_all(&self.pool).await?;
+        let block_height: Option<i32> =
+            sqlx::query_scalar("SELECT MAX(height) FROM blocks WHERE status = 'canonical'")
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(TopBalancesPage {
+            items: rows
+                .into_iter()
+                .enumerate()
+                .map(|(idx, row)| TopBalanceItem {
+                    rank: idx as i64 + 1,
+                    address: row.get::<String, _>("address"),
+                    balance_sats: row.get::<i64, _>("balance_sats"),
+                    updated_at: row.get::<DateTime<Utc>, _>("updated_at"),
+                })
+                .collect(),
+            limit,
+            block_height,
+        })
+    }
+
+    /// Replacement chain `txid` belongs to, from the first replaced transaction to the
+    /// latest replacement; empty when `txid` was never replaced nor replaced another.
+    pub async fn list_tx_replacements(&self, txid: &str) -> Result<Vec<TxReplacementItem>, DataError> {
+        if txid.trim().is_empty() {
+            return Err(DataError::Validation("txid MUST be non-empty".to_string()));
+        }
+        if !self.schema.tx_replacements {
+            return Ok(Vec::new());
+        }
+
+        let rows = sqlx::query(
+            "WITH RECURSIVE earlier AS (
+               SELECT r.* FROM tx_replacements r WHERE r.replacement_txid = $1
+               UNION
+               SELECT r.* FROM tx_replacements r JOIN earlier e ON r.replacement_txid = e.replaced_txid
+             ),
+             later AS (
+               SELECT r.* FROM tx_replacements r WHERE r.replaced_txid = $1
+               UNION
+               SELECT r.* FROM tx_replacements r JOIN later l ON r.replaced_txid = l.replacement_txid
+             ),
+             chain AS (
+               SELECT * FROM earlier
+               UNION
+               SELECT * FROM later
+             )
+             SELECT c.replaced_txid, c.replacement_txid, c.replaced_fee_sats, c.replacement_fee_sats,
+                    t.status AS replacement_status, c.replaced_at
+             FROM chain c
+             LEFT JOIN transactions t ON t.txid = c.replacement_txid
+             ORDER BY c.replaced_at, c.replaced_txid",
+        )
+        .bind(txid)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TxReplacementItem {
+                replaced_txid: row.get::<String, _>("replaced_txid"),
+                replacement_txid: row.get::<String, _>("replacement_txid"),
+                replaced_fee_sats: row.get::<Option<i64>, _>("replaced_fee_sats"),
+                replacement_fee_sats: row.get::<Option<i64>, _>("replacement_fee_sats"),
+                replacement_status: row.get::<Option<String>, _>("replacement_status"),
+                replaced_at: row.get::<DateTime<Utc>, _>("replaced_at"),
+            })
+            .collect())
+    }
+
+    /// Every stored block at `height`, the canonical one first, then orphaned blocks newest first.
+    pub async fn list_block_forks(&self, height: i32) -> Result<Vec<ForkBlockItem>, DataError> {
+        if height < 0 {
+            return Err(DataError::Validation("height MUST be >= 0".to_string()));
+        }
+
+        let rows = sqlx::query(&format!(
+            "SELECT b.height, b.hash, b.prev_hash, b.time, b.status, {},
+                    (b.meta->>'orphaned_at')::TIMESTAMPTZ AS orphaned_at,
+                    COALESCE(
+                      b.meta->'txids',
+                      (SELECT jsonb_agg(t.txid ORDER BY t.position_in_block) FROM transactions t WHERE t.block_hash = b.hash),
+                      '[]'::JSONB
+                    ) AS txids
+             FROM blocks b
+             WHERE b.height = $1
+             ORDER BY b.status = 'canonical' DESC, orphaned_at DESC NULLS LAST, b.hash",
+            self.total_fees_column()
+        ))
+        .bind(height)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| ForkBlockItem {
+                height: row.get::<i32, _>("height"),
+                hash: row.get::<String, _>("hash"),
+                prev_hash: row.get::<String, _>("prev_hash"),
+                time: row.get::<i64, _>("time"),
+                status: row.get::<String, _>("status"),
+                orphaned_at: row.get::<Option<DateTime<Utc>>, _>("orphaned_at"),
+                txids: serde_json::from_value(row.get::<serde_json::Value, _>("txids")).unwrap_or_default(),
+                total_fees_sats: row.try_get::<i64, _>("total_fees_sats").ok(),
+            })
+            .collect())
+    }
+
+    /// Stored serialized block with `hash`; `None` when it was not stored, including
+    /// blocks indexed before `indexer.store_raw_blocks` was enabled.
+    pub async fn raw_block(&self, hash: &str) -> Result<Option<RawBlockItem>, DataError> {
+        if hash.len() != 64 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(DataError::Validation("hash MUST be 64 hex characters".to_string()));
+        }
+        if !self.schema.block_raw {
+            return Ok(None);
+        }
+
+        let row = sqlx::query("SELECT hash, height, size, raw FROM block_raw WHERE hash = $1")
+            .bind(hash.to_ascii_lowercase())
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let raw = decompress_raw_block(&row.get::<Vec<u8>, _>("raw"))
+            .map_err(|err| sqlx::Error::Decode(Box::new(err)))?;
+        Ok(Some(RawBlockItem {
+            hash: row.get::<String, _>("hash"),
+            height: row.get::<i32, _>("height"),
+            size: row.get::<i32, _>("size"),
+            hex: hex::encode(raw),
+        }))
+    }
+
+    /// Stored BIP158 filter of the block with `hash`; `None` when it was not stored,
+    /// including blocks indexed before `indexer.block_filters` was enabled.
+    pub async fn block_filter(&self, hash: &str) -> Result<Option<BlockFilterItem>, DataError> {
+        if hash.len() != 64 || !hash.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(DataError::Validation("hash MUST be 64 hex characters".to_string()));
+        }
+        if !self.schema.block_filters {
+            return Ok(None);
+        }
+
+        let row = sqlx::query("SELECT hash, height, filter, header, complete FROM block_filters WHERE hash = $1")
+            .bind(hash.to_ascii_lowercase())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| BlockFilterItem {
+            hash: row.get::<String, _>("hash"),
+            height: row.get::<i32, _>("height"),
+            filter_type: BASIC_FILTER_TYPE.to_string(),
+            filter: hex::encode(row.get::<Vec<u8>, _>("filter")),
+            header: row.get::<Option<String>, _>("header"),
+            complete: row.get::<bool, _>("complete"),
+        }))
+    }
+
+    /// Groups canonical blocks into height ranges by `blocks.meta.provenance`. With
+    /// `missing_stage` only blocks persisted without that stage are considered, i.e. the
+    /// ranges a new stage still has to be backfilled for.
+    pub async fn list_provenance_ranges(&self, missing_stage: Option<&str>) -> Result<Vec<ProvenanceRange>, DataError> {
+        if let Some(stage) = missing_stage {
+            if !PIPELINE_STAGES.contains(&stage) && !PROTOCOLS.contains(&stage) {
+                return Err(DataError::Validation(format!(
+                    "missing_stage MUST be one of: {}, {}",
+                    PIPELINE_STAGES.join(", "),
+                    PROTOCOLS.join(", ")
+                )));
+            }
+        }
+
+        let rows = sqlx::query(
+            "SELECT MIN(height) AS from_height, MAX(height) AS to_height, COUNT(*) AS blocks, provenance
+             FROM (
+                 SELECT height, meta->'provenance' AS provenance,
+                        height - ROW_NUMBER() OVER (PARTITION BY meta->'provenance' ORDER BY height) AS island
+                 FROM blocks
+                 WHERE status = 'canonical'
+                   AND ($1::TEXT IS NULL OR NOT COALESCE(meta->'provenance'->'stages', '[]'::JSONB) ? $1)
+             ) b
+             GROUP BY provenance, island
+             ORDER BY from_height",
+        )
+        .bind(missing_stage)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let provenance = row
+                    .get::<Option<serde_json::Value>, _>("provenance")
+                    .and_then(|value| serde_json::from_value::<BlockProvenance>(value).ok());
+                ProvenanceRange {
+                    from_height: row.get::<i32, _>("from_height"),
+                    to_height: row.get::<i32, _>("to_height"),
+                    blocks: row.get::<i64, _>("blocks"),
+                    pipeline_version: provenance.as_ref().map(|provenance| provenance.pipeline_version),
+                    indexer_version: provenance.as_ref().map(|provenance| provenance.indexer_version.clone()),
+                    stages: provenance.map(|provenance| provenance.stages).unwrap_or_default(),
+                }
+            })
+            .collect())
+    }
+
+    /// Fee columns come from `0011_transaction_fees.sql`; without it fees read as `null`.
+    fn fee_column(&self) -> &'static str {
+        if self.schema.transaction_fees {
+            "t.fee_sats"
+        } else {
+            "NULL::BIGINT AS fee_sats"
+        }
+    }
+
+    fn total_fees_column(&self) -> &'static str {
+        if self.schema.transaction_fees {
+            "b.total_fees_sats"
+        } else {
+            "NULL::BIGINT AS total_fees_sats"
+        }
+    }
+}
+
+fn append_script_filters<'a>(
+    builder: &mut QueryBuilder<'a, Postgres>,
+    like: &'a str,
+    script_type: Option<&'a str>,
+) {
+    builder.push(" WHERE t.status = 'confirmed' AND o.script_hex LIKE ");
+    builder.push_bind(like);
+
+    if let Some(script_type) = script_type {
+        builder.push(" AND o.script_type = ");
+        builder.push_bind(script_type);
+    }
+}
+
+/// Translates a script pattern into a `LIKE` prefix pattern.
+///
+/// The pattern is hex bytes where `??` matches any byte; it matches as a prefix
+/// unless it ends with `$`. The first byte must be literal so the
+/// `text_pattern_ops` index on `tx_outputs.script_hex` can be used.
+fn script_pattern_to_like(pattern: &str) -> Result<String, DataError> {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    let (body, exact) = match pattern.strip_suffix('$') {
+        Some(body) => (body, true),
+        None => (pattern.as_str(), false),
+    };
+
+    if body.is_empty() || body.len() % 2 != 0 || body.len() > MAX_SCRIPT_PATTERN_HEX_LEN {
+        return Err(DataError::Validation(format!(
+            "pattern MUST contain 1..{} whole bytes",
+            MAX_SCRIPT_PATTERN_HEX_LEN / 2
+        )));
+    }
+
+    let mut like = String::with_capacity(body.len() + 1);
+    for (idx, byte) in body.as_bytes().chunks(2).enumerate() {
+        match byte {
+            b"??" if idx == 0 => {
+                return Err(DataError::Validation(
+                    "pattern MUST start with a literal byte".to_string(),
+                ));
+            }
+            b"??" => like.push_str("__"),
+            [high, low] if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => {
+                like.push(*high as char);
+                like.push(*low as char);
+            }
+            _ => {
+                return Err(DataError::Validation(
+                    "pattern MUST consist of hex bytes, `??` wildcards and an optional trailing `$`".to_string(),
+                ));
+            }
+        }
+    }
+
+    if !exact {
+        like.push('%');
+    }
+
+    Ok(like)
 }
 
 fn append_transaction_joins(builder: &mut QueryBuilder<'_, Postgres>, address: Option<&str>) {
@@ -706,6 +1312,7 @@ fn append_block_joins(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn append_block_filters<'a>(
     builder: &mut QueryBuilder<'a, Postgres>,
     from_height: Option<i32>,
@@ -801,3 +1408,21 @@ fn apply_block_bounds(
         builder.push_bind(to_time);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::script_pattern_to_like;
+
+    #[test]
+    fn translates_script_patterns() {
+        assert_eq!(script_pattern_to_like("6A24AA21A9ED").unwrap(), "6a24aa21a9ed%");
+        assert_eq!(script_pattern_to_like("0014????$").unwrap(), "0014____");
+        assert_eq!(script_pattern_to_like(" 51 ").unwrap(), "51%");
+        assert!(script_pattern_to_like("").is_err());
+        assert!(script_pattern_to_like("$").is_err());
+        assert!(script_pattern_to_like("6a2").is_err());
+        assert!(script_pattern_to_like("??14").is_err());
+        assert!(script_pattern_to_like("6a%_").is_err());
+        assert!(script_pattern_to_like("zz").is_err());
+    }
+}
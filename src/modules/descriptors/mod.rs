@@ -0,0 +1,313 @@
+use std::str::FromStr;
+
+use bitcoin::bip32::{ChildNumber, DerivationPath, Xpub};
+use bitcoin::key::CompressedPublicKey;
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, Network, NetworkKind};
+
+const INPUT_CHARSET: &str =
+    "0123456789()[],'/*abcdefgh@:$%{}IJKLMNOPQRSTUVWXYZ&+-.;<=>?!^_|~ijklmnopqrstuvwxyzABCDEFGH`#\"\\ ";
+const CHECKSUM_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+pub const MAX_GAP_LIMIT: u32 = 1000;
+
+/// SLIP-132 version bytes of extended public keys and the script they imply when given bare.
+const EXTENDED_KEY_VERSIONS: &[([u8; 4], NetworkKind, ScriptKind)] = &[
+    ([0x04, 0x88, 0xb2, 0x1e], NetworkKind::Main, ScriptKind::Pkh),
+    ([0x04, 0x9d, 0x7c, 0xb2], NetworkKind::Main, ScriptKind::ShWpkh),
+    ([0x04, 0xb2, 0x47, 0x46], NetworkKind::Main, ScriptKind::Wpkh),
+    ([0x04, 0x35, 0x87, 0xcf], NetworkKind::Test, ScriptKind::Pkh),
+    ([0x04, 0x4a, 0x52, 0x62], NetworkKind::Test, ScriptKind::ShWpkh),
+    ([0x04, 0x5f, 0x1c, 0xf6], NetworkKind::Test, ScriptKind::Wpkh),
+];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScriptKind {
+    Pkh,
+    ShWpkh,
+    Wpkh,
+    Tr,
+}
+
+/// Ranged single-key descriptor of a watched wallet: `pkh`, `wpkh`, `sh(wpkh)` or key-path
+/// `tr` over an extended public key, or a bare xpub/ypub/zpub (tpub/upub/vpub), which
+/// watches its receive (`0/*`) and change (`1/*`) branches with the script of its prefix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchDescriptor {
+    script: ScriptKind,
+    key: Xpub,
+    /// Path from the key to the wildcard, one per branch; `<0;1>` gives two.
+    branches: Vec<DerivationPath>,
+    network: Network,
+}
+
+impl WatchDescriptor {
+    /// Accepts an optional `#checksum`, which must then match; key origins (`[fingerprint/path]`)
+    /// are ignored. Hardened steps after the key and unranged descriptors are rejected.
+    pub fn parse(text: &str, network: Network) -> Result<Self, String> {
+        let text = text.trim();
+        let body = match text.split_once('#') {
+            Some((body, checksum)) => {
+                let expected = descriptor_checksum(body).unwrap_or_default();
+                if expected != checksum {
+                    return Err(format!("descriptor checksum MUST match: expected {expected}"));
+                }
+                body
+            }
+            None => text,
+        };
+
+        if !body.contains('(') {
+            let (key, script) = parse_extended_key(body, network)?;
+            let branches = vec![unhardened_path(&[0]), unhardened_path(&[1])];
+            return Ok(Self {
+                script,
+                key,
+                branches,
+                network,
+            });
+        }
+
+        let (script, inner) = [
+            ("sh(wpkh(", "))", ScriptKind::ShWpkh),
+            ("wpkh(", ")", ScriptKind::Wpkh),
+            ("pkh(", ")", ScriptKind::Pkh),
+            ("tr(", ")", ScriptKind::Tr),
+        ]
+        .into_iter()
+        .find_map(|(prefix, suffix, script)| {
+            body.strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .map(|inner| (script, inner))
+        })
+        .ok_or_else(|| {
+            "descriptor MUST be one of: pkh(KEY), wpkh(KEY), sh(wpkh(KEY)), tr(KEY)".to_string()
+        })?;
+
+        let inner = match inner.strip_prefix('[') {
+            Some(rest) => rest
+                .split_once(']')
+                .map(|(_, key)| key)
+                .ok_or("key origin MUST be closed with ']'")?,
+            None => inner,
+        };
+        let mut steps = inner.split('/');
+        let (key, _) = parse_extended_key(steps.next().unwrap_or_default(), network)?;
+        let steps: Vec<&str> = steps.collect();
+        let Some((&"*", path)) = steps.split_last() else {
+            return Err("descriptor MUST be ranged: the key path MUST end with /*".to_string());
+        };
+
+        let mut branches = vec![Vec::new()];
+        for step in path {
+            let indexes = match step.strip_prefix('<').and_then(|rest| rest.strip_suffix('>')) {
+                Some(_) if branches.len() > 1 => {
+                    return Err("descriptor MUST contain at most one <a;b> multipath step".to_string());
+                }
+                Some(multipath) => multipath.split(';').map(parse_unhardened).collect::<Result<Vec<_>, _>>()?,
+                None => vec![parse_unhardened(step)?],
+            };
+            branches = branches
+                .iter()
+                .flat_map(|branch| indexes.iter().map(move |index| [branch.as_slice(), &[*index]].concat()))
+                .collect();
+        }
+
+        Ok(Self {
+            script,
+            key,
+            branches: branches.iter().map(|branch| unhardened_path(branch)).collect(),
+            network,
+        })
+    }
+
+    pub fn branch_count(&self) -> usize {
+        self.branches.len()
+    }
+
+    /// Address at `index` of `branch` (`0` receive, `1` change for bare keys and `<0;1>`).
+    pub fn derive_address(&self, branch: usize, index: u32) -> Result<String, String> {
+        let secp = Secp256k1::verification_only();
+        let child = ChildNumber::from_normal_idx(index).map_err(|err| err.to_string())?;
+        let path = self.branches.get(branch).ok_or_else(|| format!("descriptor has no branch {branch}"))?;
+        let key = self
+            .key
+            .derive_pub(&secp, &path.child(child))
+            .map_err(|err| err.to_string())?;
+        let public_key = CompressedPublicKey(key.public_key);
+
+        let address = match self.script {
+            ScriptKind::Pkh => Address::p2pkh(public_key, self.network),
+            ScriptKind::ShWpkh => Address::p2shwpkh(&public_key, self.network),
+            ScriptKind::Wpkh => Address::p2wpkh(&public_key, self.network),
+            ScriptKind::Tr => Address::p2tr(&secp, key.to_x_only_pub(), None, self.network),
+        };
+        Ok(address.to_string())
+    }
+}
+
+/// Indexes `0..end` to derive on a branch so `gap_limit` unused addresses follow the last used one.
+pub fn derivation_window_end(last_used: Option<u32>, gap_limit: u32) -> u32 {
+    last_used.map_or(0, |index| index.saturating_add(1)).saturating_add(gap_limit)
+}
+
+/// Decodes any SLIP-132 public key prefix into an `Xpub` of `network`.
+fn parse_extended_key(text: &str, network: Network) -> Result<(Xpub, ScriptKind), String> {
+    let mut data =
+        bitcoin::base58::decode_check(text).map_err(|_| format!("'{text}' is not an extended public key"))?;
+    let (kind, script) = EXTENDED_KEY_VERSIONS
+        .iter()
+        .find(|(version, _, _)| data.starts_with(version))
+        .map(|(_, kind, script)| (*kind, *script))
+        .ok_or_else(|| format!("'{text}' is not an extended public key"))?;
+    if kind != NetworkKind::from(network) {
+        return Err(format!("extended key '{text}' belongs to another network"));
+    }
+    if data.len() >= 4 {
+        let version = if kind == NetworkKind::Main { XPUB_VERSION } else { TPUB_VERSION };
+        data[..4].copy_from_slice(&version);
+    }
+
+    let key = Xpub::decode(&data).map_err(|err| format!("'{text}' is not an extended public key: {err}"))?;
+    Ok((key, script))
+}
+
+fn parse_unhardened(step: &str) -> Result<u32, String> {
+    if step.ends_with(['\'', 'h', 'H']) {
+        return Err(format!("hardened step '{step}' cannot be derived from a public key"));
+    }
+    step.parse::<u32>()
+        .ok()
+        .filter(|index| ChildNumber::from_normal_idx(*index).is_ok())
+        .ok_or_else(|| format!("derivation step '{step}' MUST be an index below 2^31"))
+}
+
+fn unhardened_path(indexes: &[u32]) -> DerivationPath {
+    let path = indexes.iter().map(|index| index.to_string()).collect::<Vec<_>>().join("/");
+    DerivationPath::from_str(&format!("m/{path}")).unwrap_or_default()
+}
+
+/// `addr(<address>)#<checksum>`, accepted by `importdescriptors` for any address type.
+pub fn addr_descriptor(address: &str) -> Option<String> {
+    with_checksum(&format!("addr({address})"))
+}
+
+/// `wpkh(<pubkey>)#<checksum>` for a compressed public key in hex.
+pub fn wpkh_descriptor(pubkey: &str) -> Option<String> {
+    if !is_compressed_pubkey(pubkey) {
+        return None;
+    }
+
+    with_checksum(&format!("wpkh({})", pubkey.to_ascii_lowercase()))
+}
+
+/// Appends the descriptor checksum; `None` for characters outside the descriptor charset.
+pub fn with_checksum(descriptor: &str) -> Option<String> {
+    let checksum = descriptor_checksum(descriptor)?;
+    Some(format!("{descriptor}#{checksum}"))
+}
+
+pub fn is_compressed_pubkey(pubkey: &str) -> bool {
+    pubkey.len() == 66
+        && (pubkey.starts_with("02") || pubkey.starts_with("03"))
+        && pubkey.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn descriptor_checksum(descriptor: &str) -> Option<String> {
+    let mut checksum = 1u64;
+    let mut class = 0u64;
+    let mut class_count = 0;
+    for ch in descriptor.chars() {
+        let position = INPUT_CHARSET.find(ch)? as u64;
+        checksum = polymod(checksum, position & 31);
+        class = class * 3 + (position >> 5);
+        class_count += 1;
+        if class_count == 3 {
+            checksum = polymod(checksum, class);
+            class = 0;
+            class_count = 0;
+        }
+    }
+    if class_count > 0 {
+        checksum = polymod(checksum, class);
+    }
+    for _ in 0..8 {
+        checksum = polymod(checksum, 0);
+    }
+    checksum ^= 1;
+
+    Some(
+        (0..8)
+            .map(|index| CHECKSUM_CHARSET[((checksum >> (5 * (7 - index))) & 31) as usize] as char)
+            .collect(),
+    )
+}
+
+fn polymod(checksum: u64, value: u64) -> u64 {
+    const GENERATORS: [u64; 5] = [0xf5dee51989, 0xa9fdca3312, 0x1bab10e32d, 0x3706b1677a, 0x644d626ffd];
+
+    let top = checksum >> 35;
+    let mut checksum = ((checksum & 0x7ffffffff) << 5) ^ value;
+    for (bit, generator) in GENERATORS.iter().enumerate() {
+        if (top >> bit) & 1 == 1 {
+            checksum ^= generator;
+        }
+    }
+
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Network;
+
+    use super::{addr_descriptor, derivation_window_end, with_checksum, wpkh_descriptor, WatchDescriptor};
+
+    #[test]
+    fn appends_bip380_checksums() {
+        assert_eq!(with_checksum("raw(deadbeef)").as_deref(), Some("raw(deadbeef)#89f8spxm"));
+        assert_eq!(
+            addr_descriptor("mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j").as_deref(),
+            Some("addr(mkmZxiEcEd8ZqjQWVZuC6so5dFMKEFpN2j)#02wpgw69")
+        );
+        assert!(with_checksum("addr(é)").is_none());
+        assert!(wpkh_descriptor("04deadbeef").is_none());
+    }
+
+    #[test]
+    fn derives_addresses_from_bip84_and_bip86_keys() {
+        let zpub = "zpub6rFR7y4Q2AijBEqTUquhVz398htDFrtymD9xYYfG1m4wAcvPhXNfE3EfH1r1ADqtfSdVCToUG868RvUUkgDKf31mGDtKsAYz2oz2AGutZYs";
+        let bare = WatchDescriptor::parse(zpub, Network::Bitcoin).expect("bare zpub");
+        assert_eq!(bare.branch_count(), 2);
+        assert_eq!(bare.derive_address(0, 0).expect("receive address"), "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu");
+        assert_eq!(bare.derive_address(1, 0).expect("change address"), "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el");
+
+        let xpub = "xpub6CatWdiZiodmUeTDp8LT5or8nmbKNcuyvz7WyksVFkKB4RHwCD3XyuvPEbvqAQY3rAPshWcMLoP2fMFMKHPJ4ZeZXYVUhLv1VMrjPC7PW6V";
+        let ranged = WatchDescriptor::parse(&format!("wpkh([73c5da0a/84h/0h/0h]{xpub}/<0;1>/*)"), Network::Bitcoin)
+            .expect("multipath descriptor");
+        assert_eq!(ranged.derive_address(0, 0), bare.derive_address(0, 0));
+        assert_eq!(ranged.derive_address(1, 0), bare.derive_address(1, 0));
+
+        let taproot = with_checksum(
+            "tr(xpub6BgBgsespWvERF3LHQu6CnqdvfEvtMcQjYrcRzx53QJjSxarj2afYWcLteoGVky7D3UKDP9QyrLprQ3VCECoY49yfdDEHGCtMMj92pReUsQ/0/*)",
+        )
+        .expect("checksum");
+        let taproot = WatchDescriptor::parse(&taproot, Network::Bitcoin).expect("taproot descriptor");
+        assert_eq!(taproot.branch_count(), 1);
+        assert_eq!(
+            taproot.derive_address(0, 0).expect("taproot address"),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+
+        assert!(WatchDescriptor::parse(zpub, Network::Testnet).is_err());
+        assert!(WatchDescriptor::parse(&format!("wpkh({xpub}/0)"), Network::Bitcoin).is_err());
+        assert!(WatchDescriptor::parse(&format!("wpkh({xpub}/0h/*)"), Network::Bitcoin).is_err());
+        assert!(WatchDescriptor::parse(&format!("wpkh({xpub}/0/*)#00000000"), Network::Bitcoin).is_err());
+
+        assert_eq!(derivation_window_end(None, 20), 20);
+        assert_eq!(derivation_window_end(Some(4), 20), 25);
+    }
+}
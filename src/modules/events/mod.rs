@@ -0,0 +1,390 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::config::{IndexerConfig, EVENT_LEVELS};
+use crate::modules::reload::ReloadableConfig;
+use crate::modules::storage::SchemaFeatures;
+
+const DEFAULT_EVENTS_LIMIT: i64 = 100;
+const MAX_EVENTS_LIMIT: i64 = 1000;
+/// Kinds of `stream_events`, see `0027_stream_events.sql`.
+pub const STREAM_EVENT_KINDS: [&str; 3] = ["job_status", "block_connected", "reorg"];
+const STREAM_BATCH_LIMIT: i64 = 500;
+
+#[derive(Debug, Error)]
+pub enum EventsError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Confirmation milestone of a transaction touching a watched address.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TxEvent {
+    /// Cursor for `after_id`; grows monotonically.
+    pub id: i64,
+    pub txid: String,
+    pub address: String,
+    /// `seen` (entered mempool), `confirmed` (first included in a block) or
+    /// `finalized` (reached the configured finality depth).
+    pub level: String,
+    pub block_height: Option<i32>,
+    pub block_hash: Option<String>,
+    /// Confirmations when the event was recorded.
+    pub confirmations: i32,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Event of the SSE stream `GET /v1/events/stream`.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+pub struct StreamEvent {
+    /// SSE event id; a reconnecting client resumes after it with `Last-Event-ID`.
+    pub id: i64,
+    /// `job_status`, `block_connected` or `reorg`; also the SSE event name.
+    pub kind: String,
+    /// `job_status`: `job_id`, `action`, `from_status`, `to_status`, `reason`.
+    /// `block_connected`: `height`, `hash`, `prev_hash`, `time`, `tx_count`.
+    /// `reorg`: `divergence_height` and the `orphaned` blocks (`height`, `hash`), tip first.
+    #[schema(value_type = Object)]
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EventsFilter {
+    /// Comma-separated levels; all levels when unset.
+    pub levels: Option<String>,
+    pub address: Option<String>,
+    pub txid: Option<String>,
+    pub after_id: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventsService {
+    pool: PgPool,
+    schema: SchemaFeatures,
+}
+
+#[derive(Debug, Clone)]
+pub struct EventsRunnerConfig {
+    pub poll_interval: Duration,
+    pub levels: Vec<String>,
+    pub finality_depth: u32,
+}
+
+#[derive(Clone)]
+pub struct EventsRunner {
+    pool: PgPool,
+    schema: SchemaFeatures,
+    config: ReloadableConfig<EventsRunnerConfig>,
+}
+
+impl EventsService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Returns events with `id > after_id` in ascending order, so clients can
+    /// subscribe to a set of levels by polling with the last seen id.
+    pub async fn list(&self, filter: EventsFilter) -> Result<Vec<TxEvent>, EventsError> {
+        let levels = parse_levels(filter.levels.as_deref())?;
+        let limit = filter.limit.unwrap_or(DEFAULT_EVENTS_LIMIT);
+        if !(1..=MAX_EVENTS_LIMIT).contains(&limit) {
+            return Err(EventsError::Validation(format!(
+                "limit MUST be between 1 and {MAX_EVENTS_LIMIT}"
+            )));
+        }
+        if !self.schema.tx_events {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<TxEventRow> = sqlx::query_as(
+            "SELECT id, txid, address, level, block_height, block_hash, confirmations, occurred_at
+             FROM tx_events
+             WHERE level = ANY($1)
+               AND id > $2
+               AND ($3::TEXT IS NULL OR address = $3)
+               AND ($4::TEXT IS NULL OR txid = $4)
+             ORDER BY id
+             LIMIT $5",
+        )
+        .bind(&levels)
+        .bind(filter.after_id.unwrap_or(0))
+        .bind(filter.address.as_deref())
+        .bind(filter.txid.as_deref())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(TxEvent::from).collect())
+    }
+
+    /// Id of the newest stream event, where a client without `Last-Event-ID` starts.
+    pub async fn latest_stream_id(&self) -> Result<i64, EventsError> {
+        if !self.schema.stream_events {
+            return Ok(0);
+        }
+
+        let id = sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM stream_events")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(id)
+    }
+
+    /// Next stream events of `kinds` with `id > after_id`, in ascending order.
+    pub async fn stream_after(&self, after_id: i64, kinds: &[String]) -> Result<Vec<StreamEvent>, EventsError> {
+        if !self.schema.stream_events {
+            return Ok(Vec::new());
+        }
+
+        let events = sqlx::query_as(
+            "SELECT id, kind, payload, created_at
+             FROM stream_events
+             WHERE id > $1 AND kind = ANY($2)
+             ORDER BY id
+             LIMIT $3",
+        )
+        .bind(after_id)
+        .bind(kinds)
+        .bind(STREAM_BATCH_LIMIT)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(events)
+    }
+}
+
+/// Parses the comma-separated `kinds` of the event stream; all kinds when unset.
+pub fn parse_stream_kinds(kinds: Option<&str>) -> Result<Vec<String>, EventsError> {
+    let Some(kinds) = kinds else {
+        return Ok(STREAM_EVENT_KINDS.iter().map(|kind| kind.to_string()).collect());
+    };
+
+    kinds
+        .split(',')
+        .map(str::trim)
+        .filter(|kind| !kind.is_empty())
+        .map(|kind| {
+            if STREAM_EVENT_KINDS.contains(&kind) {
+                Ok(kind.to_string())
+            } else {
+                Err(EventsError::Validation(format!(
+                    "kinds MUST be a comma-separated subset of: {}",
+                    STREAM_EVENT_KINDS.join("|")
+                )))
+            }
+        })
+        .collect()
+}
+
+impl EventsRunnerConfig {
+    /// Returns `None` when events are disabled.
+    pub fn from_config(config: &IndexerConfig) -> Option<Self> {
+        config.events.as_ref().map(|events| Self {
+            poll_interval: Duration::from_millis(config.poll.tip_interval_ms),
+            levels: events.levels.clone(),
+            finality_depth: events.finality_depth,
+        })
+    }
+}
+
+impl EventsRunner {
+    pub fn new(pool: PgPool, config: EventsRunnerConfig) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+            config: ReloadableConfig::new(config),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn update_config(&self, config: EventsRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn current_config(&self) -> EventsRunnerConfig {
+        self.config.get()
+    }
+
+    pub fn start(&self) {
+        if !self.schema.tx_events {
+            warn!(component = "events", message = "tx_events table is missing, events disabled");
+            return;
+        }
+
+        let runner = self.clone();
+        let config = runner.current_config();
+        info!(
+            component = "events",
+            levels = ?config.levels,
+            finality_depth = config.finality_depth,
+            message = "tx events started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = runner.detect_once().await {
+                    warn!(component = "events", error = %err, message = "tx events detection failed");
+                }
+
+                tokio::time::sleep(runner.current_config().poll_interval).await;
+            }
+        });
+    }
+
+    /// Records new events of the configured levels for addresses of all jobs,
+    /// returning how many were added.
+    pub async fn detect_once(&self) -> Result<u64, EventsError> {
+        if !self.schema.tx_events {
+            return Ok(0);
+        }
+
+        let config = self.current_config();
+        let tip_height: Option<i32> =
+            sqlx::query_scalar("SELECT MAX(height) FROM blocks WHERE status = 'canonical'")
+                .fetch_one(&self.pool)
+                .await?;
+        let mut recorded = 0;
+
+        for level in &config.levels {
+            let (status, min_confirmations) = match level.as_str() {
+                "seen" => ("mempool", 0),
+                "confirmed" => ("confirmed", 1),
+                _ => ("confirmed", i64::from(config.finality_depth)),
+            };
+            if status == "confirmed" && tip_height.is_none() {
+                continue;
+            }
+
+            recorded += sqlx::query(
+                "WITH watched AS (
+                   SELECT DISTINCT address FROM job_addresses
+                 ),
+                 touched AS (
+                   SELECT o.txid, o.address
+                   FROM tx_outputs o
+                   JOIN watched w ON w.address = o.address
+                   UNION
+                   SELECT i.txid, prev.address
+                   FROM tx_inputs i
+                   JOIN tx_outputs prev ON prev.txid = i.prev_txid AND prev.vout = i.prev_vout
+                   JOIN watched w ON w.address = prev.address
+                 )
+                 INSERT INTO tx_events (txid, address, level, block_height, block_hash, confirmations)
+                 SELECT t.txid,
+                        touched.address,
+                        $1,
+                        t.block_height,
+                        t.block_hash,
+                        COALESCE($3 - t.block_height + 1, 0)
+                 FROM touched
+                 JOIN transactions t ON t.txid = touched.txid
+                 WHERE t.status = $2
+                   AND COALESCE($3 - t.block_height + 1, 0) >= $4
+                 ORDER BY t.block_height NULLS LAST, t.txid, touched.address
+                 ON CONFLICT DO NOTHING",
+            )
+            .bind(level)
+            .bind(status)
+            .bind(tip_height)
+            .bind(min_confirmations)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        }
+
+        if recorded > 0 {
+            info!(component = "events", recorded, message = "tx events recorded");
+        }
+
+        Ok(recorded)
+    }
+}
+
+fn parse_levels(levels: Option<&str>) -> Result<Vec<String>, EventsError> {
+    let Some(levels) = levels else {
+        return Ok(EVENT_LEVELS.iter().map(|level| level.to_string()).collect());
+    };
+
+    levels
+        .split(',')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .map(|level| {
+            if EVENT_LEVELS.contains(&level) {
+                Ok(level.to_string())
+            } else {
+                Err(EventsError::Validation(format!(
+                    "levels MUST be a comma-separated subset of: {}",
+                    EVENT_LEVELS.join("|")
+                )))
+            }
+        })
+        .collect()
+}
+
+impl From<TxEventRow> for TxEvent {
+    fn from(row: TxEventRow) -> Self {
+        Self {
+            id: row.id,
+            txid: row.txid,
+            address: row.address,
+            level: row.level,
+            block_height: row.block_height,
+            block_hash: row.block_hash,
+            confirmations: row.confirmations,
+            occurred_at: row.occurred_at,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct TxEventRow {
+    id: i64,
+    txid: String,
+    address: String,
+    level: String,
+    block_height: Option<i32>,
+    block_hash: Option<String>,
+    confirmations: i32,
+    occurred_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_levels, parse_stream_kinds};
+
+    #[test]
+    fn parses_event_levels() {
+        assert_eq!(parse_levels(None).unwrap(), vec!["seen", "confirmed", "finalized"]);
+        assert_eq!(parse_levels(Some("finalized, seen")).unwrap(), vec!["finalized", "seen"]);
+        assert!(parse_levels(Some("seen,mined")).is_err());
+    }
+
+    #[test]
+    fn parses_stream_event_kinds() {
+        assert_eq!(parse_stream_kinds(None).unwrap(), vec!["job_status", "block_connected", "reorg"]);
+        assert_eq!(parse_stream_kinds(Some("reorg, job_status")).unwrap(), vec!["reorg", "job_status"]);
+        assert!(parse_stream_kinds(Some("reorg,mempool")).is_err());
+    }
+}
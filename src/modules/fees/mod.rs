@@ -0,0 +1,266 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::modules::storage::SchemaFeatures;
+
+pub const DEFAULT_TARGET_BLOCKS: u32 = 6;
+/// Same upper bound as `estimatesmartfee`.
+pub const MAX_TARGET_BLOCKS: u32 = 1008;
+/// Recently confirmed blocks sampled for percentiles, about an hour of chain.
+const FEE_SAMPLE_BLOCKS: i32 = 6;
+/// 4M weight units per block.
+const BLOCK_VSIZE_CAPACITY: i64 = 1_000_000;
+/// Default `minrelaytxfee` of Bitcoin Core.
+const MIN_RELAY_FEE_RATE: f64 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum FeesError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Fee rate in sat/vB for confirmation within `target_blocks`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FeeEstimate {
+    pub target_blocks: u32,
+    /// Recommended rate; `null` when neither sample has transactions with a known fee.
+    pub fee_rate_sat_vb: Option<f64>,
+    /// `mempool` when the backlog paying at least the rate fills `target_blocks` blocks,
+    /// `blocks` when it does not and the median of recent blocks is used, `none` without data.
+    pub source: String,
+    pub blocks: FeeRateSample,
+    pub mempool: FeeRateSample,
+    pub estimated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FeeRateSample {
+    /// Height range of the blocks sample; `null` for the mempool.
+    pub from_height: Option<i32>,
+    pub to_height: Option<i32>,
+    /// Transactions with a known fee and size.
+    pub tx_count: i64,
+    pub vsize: i64,
+    /// Per-transaction fee-rate percentiles in sat/vB; `null` for an empty sample.
+    pub percentiles: Option<FeeRatePercentiles>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FeeRatePercentiles {
+    pub p10: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p90: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeesService {
+    pool: PgPool,
+    schema: SchemaFeatures,
+}
+
+impl FeesService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Combines fee rates of the last [`FEE_SAMPLE_BLOCKS`] canonical blocks with the
+    /// indexed mempool. Mempool fees are derived from indexed prevouts, so transactions
+    /// spending outputs the indexer has not seen are left out.
+    pub async fn estimate(&self, target_blocks: Option<u32>) -> Result<FeeEstimate, FeesError> {
+        let target_blocks = target_blocks.unwrap_or(DEFAULT_TARGET_BLOCKS);
+        if !(1..=MAX_TARGET_BLOCKS).contains(&target_blocks) {
+            return Err(FeesError::Validation(format!(
+                "target_blocks MUST be between 1 and {MAX_TARGET_BLOCKS}"
+            )));
+        }
+
+        let blocks = self.blocks_sample().await?;
+        let mempool_rows: Vec<FeeSampleRow> = sqlx::query_as(
+            "WITH mempool_inputs AS (
+                 SELECT i.txid, COUNT(*) AS inputs, COUNT(prev_o.value_sats) AS known,
+                        COALESCE(SUM(prev_o.value_sats), 0)::BIGINT AS value_sats
+                 FROM transactions t
+                 JOIN tx_inputs i ON i.txid = t.txid
+                 LEFT JOIN tx_outputs prev_o ON prev_o.txid = i.prev_txid AND prev_o.vout = i.prev_vout
+                 WHERE t.status = 'mempool'
+                 GROUP BY i.txid
+             ),
+             mempool_outputs AS (
+                 SELECT o.txid, SUM(o.value_sats)::BIGINT AS value_sats
+                 FROM transactions t
+                 JOIN tx_outputs o ON o.txid = t.txid
+                 WHERE t.status = 'mempool'
+                 GROUP BY o.txid
+             )
+             SELECT mi.value_sats - mo.value_sats AS fee_sats, (t.decoded->>'vsize')::BIGINT AS vsize
+             FROM transactions t
+             JOIN mempool_inputs mi ON mi.txid = t.txid
+             JOIN mempool_outputs mo ON mo.txid = t.txid
+             WHERE t.status = 'mempool'
+               AND mi.known = mi.inputs
+               AND mi.value_sats >= mo.value_sats
+               AND (t.decoded->>'vsize')::BIGINT > 0",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let mempool_rates = sorted_rates(&mempool_rows);
+
+        let backlog_rate = backlog_fee_rate(&mempool_rates, i64::from(target_blocks) * BLOCK_VSIZE_CAPACITY);
+        let (fee_rate_sat_vb, source) = match (backlog_rate, blocks.percentiles.as_ref()) {
+            (Some(rate), _) => (Some(rate.max(MIN_RELAY_FEE_RATE)), "mempool"),
+            (None, Some(percentiles)) => (Some(percentiles.p50.max(MIN_RELAY_FEE_RATE)), "blocks"),
+            (None, None) => (None, "none"),
+        };
+
+        Ok(FeeEstimate {
+            target_blocks,
+            fee_rate_sat_vb,
+            source: source.to_string(),
+            blocks,
+            mempool: FeeRateSample {
+                from_height: None,
+                to_height: None,
+                tx_count: mempool_rates.len() as i64,
+                vsize: mempool_rates.iter().map(|(_, vsize)| vsize).sum(),
+                percentiles: percentiles(&mempool_rates),
+            },
+            estimated_at: Utc::now(),
+        })
+    }
+
+    async fn blocks_sample(&self) -> Result<FeeRateSample, FeesError> {
+        let tip: Option<i32> = sqlx::query_scalar("SELECT MAX(height) FROM blocks WHERE status = 'canonical'")
+            .fetch_one(&self.pool)
+            .await?;
+        let Some(to_height) = tip else {
+            return Ok(FeeRateSample {
+                from_height: None,
+                to_height: None,
+                tx_count: 0,
+                vsize: 0,
+                percentiles: None,
+            });
+        };
+        let from_height = (to_height - FEE_SAMPLE_BLOCKS + 1).max(0);
+
+        let rows: Vec<FeeSampleRow> = if self.schema.transaction_fees {
+            sqlx::query_as(
+                "SELECT fee_sats, (decoded->>'vsize')::BIGINT AS vsize
+                 FROM transactions
+                 WHERE status = 'confirmed'
+                   AND block_height BETWEEN $1 AND $2
+                   AND fee_sats IS NOT NULL
+                   AND (decoded->>'vsize')::BIGINT > 0",
+            )
+            .bind(from_height)
+            .bind(to_height)
+            .fetch_all(&self.pool)
+            .await?
+        } else {
+            Vec::new()
+        };
+        let rates = sorted_rates(&rows);
+
+        Ok(FeeRateSample {
+            from_height: Some(from_height),
+            to_height: Some(to_height),
+            tx_count: rates.len() as i64,
+            vsize: rates.iter().map(|(_, vsize)| vsize).sum(),
+            percentiles: percentiles(&rates),
+        })
+    }
+}
+
+/// `(fee rate, vsize)` pairs ordered from the highest rate.
+fn sorted_rates(rows: &[FeeSampleRow]) -> Vec<(f64, i64)> {
+    let mut rates: Vec<(f64, i64)> = rows
+        .iter()
+        .map(|row| (row.fee_sats as f64 / row.vsize as f64, row.vsize))
+        .collect();
+    rates.sort_by(|left, right| right.0.total_cmp(&left.0));
+    rates
+}
+
+/// Nearest-rank percentiles over rates ordered from the highest.
+fn percentiles(rates: &[(f64, i64)]) -> Option<FeeRatePercentiles> {
+    if rates.is_empty() {
+        return None;
+    }
+
+    let at = |percentile: f64| {
+        let rank = ((percentile * rates.len() as f64).ceil() as usize).clamp(1, rates.len());
+        round_rate(rates[rates.len() - rank].0)
+    };
+    Some(FeeRatePercentiles {
+        p10: at(0.10),
+        p25: at(0.25),
+        p50: at(0.50),
+        p75: at(0.75),
+        p90: at(0.90),
+    })
+}
+
+/// Rate of the transaction that no longer fits into `capacity` vbytes when the
+/// mempool is mined from the highest rate; `None` while the whole backlog fits.
+fn backlog_fee_rate(rates: &[(f64, i64)], capacity: i64) -> Option<f64> {
+    let mut filled = 0i64;
+    for (rate, vsize) in rates {
+        filled += vsize;
+        if filled > capacity {
+            return Some(round_rate(*rate));
+        }
+    }
+
+    None
+}
+
+fn round_rate(rate: f64) -> f64 {
+    (rate * 100.0).round() / 100.0
+}
+
+#[derive(Debug, FromRow)]
+struct FeeSampleRow {
+    fee_sats: i64,
+    vsize: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{backlog_fee_rate, percentiles, sorted_rates, FeeSampleRow};
+
+    #[test]
+    fn computes_percentiles_and_backlog_cutoff() {
+        let rows: Vec<FeeSampleRow> = (1..=10)
+            .map(|rate| FeeSampleRow {
+                fee_sats: rate * 200,
+                vsize: 200,
+            })
+            .collect();
+        let rates = sorted_rates(&rows);
+        assert_eq!(rates[0], (10.0, 200));
+
+        let percentiles = percentiles(&rates).expect("percentiles");
+        assert_eq!(percentiles.p10, 1.0);
+        assert_eq!(percentiles.p50, 5.0);
+        assert_eq!(percentiles.p90, 9.0);
+        assert!(super::percentiles(&[]).is_none());
+
+        assert_eq!(backlog_fee_rate(&rates, 1_000), Some(5.0));
+        assert_eq!(backlog_fee_rate(&rates, 2_000), None);
+    }
+}
@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use bitcoin::bip158::{BlockFilter, FilterHeader, GcsFilterWriter};
+use bitcoin::hashes::Hash;
+use bitcoin::{BlockHash, Script};
+
+/// Golomb-Rice parameters of the BIP158 basic filter.
+const BASIC_FILTER_P: u8 = 19;
+const BASIC_FILTER_M: u64 = 784_931;
+/// Filter type served by `GET /v1/blocks/{hash}/filter`.
+pub const BASIC_FILTER_TYPE: &str = "basic";
+
+/// BIP158 basic filter of the block with `block_hash` (hex as shown by the node) over
+/// `scripts`: the output scripts of the block and the scripts its inputs spend.
+/// `OP_RETURN` and empty scripts are left out, duplicates count once. `None` when the
+/// hash does not parse.
+pub fn basic_filter<'s>(block_hash: &str, scripts: impl IntoIterator<Item = &'s [u8]>) -> Option<Vec<u8>> {
+    let (k0, k1) = siphash_keys(block_hash)?;
+    let mut content = Vec::new();
+    let mut writer = GcsFilterWriter::new(&mut content, k0, k1, BASIC_FILTER_M, BASIC_FILTER_P);
+    for script in scripts {
+        if !Script::from_bytes(script).is_op_return() {
+            writer.add_element(script);
+        }
+    }
+    writer.finish().ok()?;
+
+    Some(content)
+}
+
+/// BIP157 header of `filter` chained to the header of the parent block's filter;
+/// the genesis block chains to the all-zero header.
+pub fn filter_header(filter: &[u8], previous_header: Option<&str>) -> Option<String> {
+    let previous = match previous_header {
+        Some(header) => FilterHeader::from_str(header).ok()?,
+        None => FilterHeader::all_zeros(),
+    };
+
+    Some(BlockFilter::new(filter).filter_header(&previous).to_string())
+}
+
+/// Whether any of `scripts` may be in the block, e.g. to skip blocks in a rescan for
+/// newly watched scripts. False positives happen at a rate of about 1/784931.
+pub fn matches_any<'s>(block_hash: &str, filter: &[u8], scripts: impl IntoIterator<Item = &'s [u8]>) -> bool {
+    let Ok(hash) = BlockHash::from_str(block_hash) else {
+        return false;
+    };
+
+    BlockFilter::new(filter)
+        .match_any(&hash, scripts.into_iter())
+        .unwrap_or(false)
+}
+
+/// The filter is keyed by the first 16 bytes of the block hash in internal byte order.
+fn siphash_keys(block_hash: &str) -> Option<(u64, u64)> {
+    let bytes = BlockHash::from_str(block_hash).ok()?.to_byte_array();
+    let k0 = u64::from_le_bytes(bytes[0..8].try_into().ok()?);
+    let k1 = u64::from_le_bytes(bytes[8..16].try_into().ok()?);
+
+    Some((k0, k1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{basic_filter, filter_header, matches_any};
+
+    const TESTNET_GENESIS_HASH: &str = "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943";
+    const TESTNET_GENESIS_SCRIPT: &str = "4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef\
+38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac";
+
+    #[test]
+    fn builds_bip158_test_vector_of_testnet_genesis() {
+        let script = hex::decode(TESTNET_GENESIS_SCRIPT).expect("script hex");
+        let filter = basic_filter(TESTNET_GENESIS_HASH, [script.as_slice()]).expect("filter");
+        assert_eq!(hex::encode(&filter), "019dfca8");
+        assert_eq!(
+            filter_header(&filter, None).as_deref(),
+            Some("21584579b7eb08997773e5aeff3a7f932700042d0ed2a6129012b7d7ae81b750")
+        );
+
+        assert!(matches_any(TESTNET_GENESIS_HASH, &filter, [script.as_slice()]));
+        assert!(!matches_any(TESTNET_GENESIS_HASH, &filter, [&[0x51u8][..]]));
+    }
+
+    #[test]
+    fn skips_op_return_and_duplicate_scripts() {
+        let script = hex::decode(TESTNET_GENESIS_SCRIPT).expect("script hex");
+        let op_return = [0x6a, 0x01, 0x00];
+        let filter = basic_filter(
+            TESTNET_GENESIS_HASH,
+            [script.as_slice(), &op_return[..], script.as_slice(), &[][..]],
+        )
+        .expect("filter");
+        assert_eq!(hex::encode(&filter), "019dfca8");
+
+        assert_eq!(basic_filter("blockhash0", [script.as_slice()]), None);
+        assert_eq!(hex::encode(basic_filter(TESTNET_GENESIS_HASH, []).expect("empty filter")), "00");
+    }
+}
@@ -0,0 +1,281 @@
+use std::time::Duration;
+
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::modules::config::IndexerConfig;
+use crate::modules::indexer::RpcBlockHeader;
+use crate::modules::metrics::MetricsService;
+use crate::modules::reload::ReloadableConfig;
+use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::storage::SchemaFeatures;
+
+#[derive(Debug, Error)]
+pub enum HeadersError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+    #[error("node tip exceeds i32 range")]
+    TipOverflow,
+}
+
+#[derive(Debug, Clone)]
+pub struct HeaderSyncRunnerConfig {
+    pub poll_interval: Duration,
+    pub batch_size: u32,
+}
+
+/// Result of one [`HeaderSyncRunner::sync_once`] pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderSyncReport {
+    /// Highest stored header after the pass.
+    pub header_height: Option<i32>,
+    /// Headers stored by the pass.
+    pub synced: u32,
+    /// Lowest height whose stored header the node replaced, when it reorged.
+    pub reorg_height: Option<i32>,
+}
+
+/// Header-first phase: keeps `block_headers` at the node's active chain with batched
+/// `getblockhash`/`getblockheader` calls, independently of how far bodies are indexed.
+#[derive(Clone)]
+pub struct HeaderSyncRunner {
+    rpc: RpcClient,
+    pool: PgPool,
+    metrics: MetricsService,
+    schema: SchemaFeatures,
+    config: ReloadableConfig<HeaderSyncRunnerConfig>,
+}
+
+impl HeaderSyncRunnerConfig {
+    /// Returns `None` when header sync is disabled.
+    pub fn from_config(config: &IndexerConfig) -> Option<Self> {
+        config.headers.as_ref().map(|headers| Self {
+            poll_interval: Duration::from_millis(config.poll.tip_interval_ms),
+            batch_size: headers.batch_size,
+        })
+    }
+}
+
+impl HeaderSyncRunner {
+    pub fn new(rpc: RpcClient, pool: PgPool, metrics: MetricsService, config: HeaderSyncRunnerConfig) -> Self {
+        Self {
+            rpc,
+            pool,
+            metrics,
+            schema: SchemaFeatures::latest(),
+            config: ReloadableConfig::new(config),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn update_config(&self, config: HeaderSyncRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn current_config(&self) -> HeaderSyncRunnerConfig {
+        self.config.get()
+    }
+
+    pub fn start(&self) {
+        if !self.schema.block_headers {
+            warn!(component = "headers", message = "block_headers table is missing, header sync disabled");
+            return;
+        }
+
+        let runner = self.clone();
+        let config = runner.current_config();
+        info!(
+            component = "headers",
+            poll_interval_ms = config.poll_interval.as_millis() as u64,
+            batch_size = config.batch_size,
+            message = "header sync started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                // Full batches mean the node is further ahead: keep going without waiting.
+                loop {
+                    match runner.sync_once().await {
+                        Ok(report) if report.synced >= runner.current_config().batch_size => {}
+                        Ok(_) => break,
+                        Err(err) => {
+                            warn!(component = "headers", error = %err, message = "header sync failed");
+                            break;
+                        }
+                    }
+                }
+
+                tokio::time::sleep(runner.current_config().poll_interval).await;
+            }
+        });
+    }
+
+    /// Drops stored headers the node no longer has in its active chain, then stores
+    /// the next batch of headers above the stored tip.
+    pub async fn sync_once(&self) -> Result<HeaderSyncReport, HeadersError> {
+        if !self.schema.block_headers {
+            return Ok(HeaderSyncReport::default());
+        }
+
+        let node_tip = i32::try_from(self.rpc.get_block_count().await?).map_err(|_| HeadersError::TipOverflow)?;
+        let mut report = HeaderSyncReport::default();
+        let mut prev_hash = None;
+        let mut next_height = 0;
+
+        if let Some((tip_height, _)) = header_tip(&self.pool).await? {
+            let fork_height = self.find_fork(tip_height, node_tip).await?;
+            if fork_height <= tip_height {
+                sqlx::query("DELETE FROM block_headers WHERE height >= $1")
+                    .bind(fork_height)
+                    .execute(&self.pool)
+                    .await?;
+                self.metrics.increment_error("header_reorg");
+                warn!(
+                    component = "headers",
+                    fork_height,
+                    stored_tip_height = tip_height,
+                    node_tip_height = node_tip,
+                    message = "node reorged, stored headers above the fork dropped"
+                );
+                report.reorg_height = Some(fork_height);
+            }
+
+            next_height = fork_height;
+            if fork_height > 0 {
+                prev_hash = header_hash(&self.pool, fork_height - 1).await?;
+            }
+        }
+
+        let batch_size = i32::try_from(self.current_config().batch_size.max(1)).unwrap_or(i32::MAX);
+        let to_height = std::cmp::min(next_height.saturating_add(batch_size - 1), node_tip);
+        if next_height <= to_height {
+            let heights: Vec<u32> = (next_height..=to_height).map(|height| height as u32).collect();
+            let hashes = self.rpc.get_block_hashes(&heights).await?;
+            let headers = self.rpc.get_block_headers(&hashes).await?;
+            // The node can reorg between the two batches; the rest is fetched on the next pass.
+            let linked = linked_prefix(prev_hash.as_deref(), next_height, &headers);
+            self.store(&headers[..linked]).await?;
+            report.synced = linked as u32;
+        }
+
+        report.header_height = header_tip(&self.pool).await?.map(|(height, _)| height);
+        Ok(report)
+    }
+
+    /// Lowest height from which stored headers differ from the node, or `tip_height + 1`
+    /// when the stored chain is still part of the node's active chain.
+    async fn find_fork(&self, tip_height: i32, node_tip: i32) -> Result<i32, HeadersError> {
+        let mut fork_height = tip_height.saturating_add(1);
+        for height in (0..=tip_height).rev() {
+            let Some(stored_hash) = header_hash(&self.pool, height).await? else {
+                break;
+            };
+            if height <= node_tip && self.rpc.get_block_hash(height as u32).await? == stored_hash {
+                break;
+            }
+            fork_height = height;
+        }
+
+        Ok(fork_height)
+    }
+
+    async fn store(&self, headers: &[RpcBlockHeader]) -> Result<(), sqlx::Error> {
+        if headers.is_empty() {
+            return Ok(());
+        }
+
+        let heights: Vec<i32> = headers.iter().map(|header| header.height).collect();
+        let hashes: Vec<&str> = headers.iter().map(|header| header.hash.as_str()).collect();
+        let prev_hashes: Vec<Option<&str>> = headers.iter().map(|header| header.prev_hash.as_deref()).collect();
+        let times: Vec<i64> = headers.iter().map(|header| header.time).collect();
+        sqlx::query(
+            "INSERT INTO block_headers (height, hash, prev_hash, time) \
+             SELECT * FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[], $4::BIGINT[]) \
+             ON CONFLICT (height) DO UPDATE \
+             SET hash = EXCLUDED.hash, prev_hash = EXCLUDED.prev_hash, time = EXCLUDED.time, synced_at = NOW()",
+        )
+        .bind(&heights)
+        .bind(&hashes)
+        .bind(&prev_hashes)
+        .bind(&times)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Highest synced header as `(height, hash)`; `None` when nothing is synced yet or the
+/// `block_headers` table does not exist, so callers need no schema check.
+pub async fn header_tip(pool: &PgPool) -> Result<Option<(i32, String)>, sqlx::Error> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass('block_headers') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    if !exists {
+        return Ok(None);
+    }
+
+    sqlx::query_as("SELECT height, hash FROM block_headers ORDER BY height DESC LIMIT 1")
+        .fetch_optional(pool)
+        .await
+}
+
+async fn header_hash(pool: &PgPool, height: i32) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT hash FROM block_headers WHERE height = $1")
+        .bind(height)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Number of leading `headers` that continue the chain ending in `prev_hash` at
+/// `from_height - 1` and link to each other.
+fn linked_prefix(prev_hash: Option<&str>, from_height: i32, headers: &[RpcBlockHeader]) -> usize {
+    let mut expected_prev = prev_hash;
+    for (offset, header) in headers.iter().enumerate() {
+        let height_matches = i64::from(header.height) == i64::from(from_height) + offset as i64;
+        let links = expected_prev.is_none_or(|prev_hash| header.prev_hash.as_deref() == Some(prev_hash));
+        if !height_matches || !links {
+            return offset;
+        }
+        expected_prev = Some(&header.hash);
+    }
+
+    headers.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::linked_prefix;
+    use crate::modules::indexer::RpcBlockHeader;
+
+    fn header(height: i32, hash: &str, prev_hash: Option<&str>) -> RpcBlockHeader {
+        RpcBlockHeader {
+            hash: hash.to_string(),
+            height,
+            prev_hash: prev_hash.map(str::to_string),
+            time: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn stores_only_headers_linked_to_the_stored_chain() {
+        let headers = vec![
+            header(5, "h5", Some("h4")),
+            header(6, "h6", Some("h5")),
+            header(7, "h7b", Some("h6b")),
+        ];
+
+        assert_eq!(linked_prefix(Some("h4"), 5, &headers), 2);
+        assert_eq!(linked_prefix(Some("h4b"), 5, &headers), 0);
+        assert_eq!(linked_prefix(None, 5, &headers[..2]), 2);
+        assert_eq!(linked_prefix(Some("h4"), 4, &headers), 0);
+        assert_eq!(linked_prefix(None, 0, &[header(0, "genesis", None)]), 1);
+    }
+}
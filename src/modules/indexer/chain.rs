@@ -0,0 +1,360 @@
+use bitcoin::block::Header;
+use bitcoin::consensus::encode::{self, Decodable};
+use bitcoin::{BlockHash, Transaction, TxMerkleNode};
+
+use crate::modules::indexer::{RpcScriptPubKey, RpcTransaction, RpcVin, RpcVout};
+use crate::modules::scripts::{classify_script, AddressEncoding};
+
+/// Version bit of merged-mined headers, which are followed by an AuxPoW proof.
+const AUXPOW_VERSION_FLAG: i32 = 1 << 8;
+
+/// Chains whose nodes speak the Bitcoin Core JSON-RPC dialect, selected by `indexer.chain`.
+///
+/// Amounts of all of them have eight decimals, so RPC values go through the same
+/// satoshi conversion as Bitcoin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chain {
+    Bitcoin,
+    Litecoin,
+    Dogecoin,
+}
+
+/// How full blocks are read from the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockSource {
+    /// `getblock <hash> 3`; nodes before Bitcoin Core 23 answer as with verbosity 2.
+    Verbose3,
+    /// `getblock <hash> 2`.
+    Verbose2,
+    /// `getblock <hash> false`, decoded by the indexer, for nodes whose `getblock`
+    /// only returns transaction ids.
+    RawHex,
+}
+
+/// Consensus and address parameters of one network of a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkParams {
+    /// `chain` value reported by `getblockchaininfo`.
+    pub rpc_chain: &'static str,
+    pub genesis_hash: &'static str,
+    /// P2P message start bytes as hex.
+    pub magic: &'static str,
+    pub pubkey_hash_prefix: u8,
+    pub script_hash_prefix: u8,
+    /// `None` on chains without segwit.
+    pub bech32_hrp: Option<&'static str>,
+    /// Leading characters of base58 addresses of the network, including legacy prefixes.
+    pub base58_leading: &'static [char],
+}
+
+impl Chain {
+    pub const ALL: [Chain; 3] = [Chain::Bitcoin, Chain::Litecoin, Chain::Dogecoin];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|chain| chain.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Chain::Bitcoin => "bitcoin",
+            Chain::Litecoin => "litecoin",
+            Chain::Dogecoin => "dogecoin",
+        }
+    }
+
+    /// Values of `indexer.network` the chain supports.
+    pub fn networks(self) -> &'static [&'static str] {
+        match self {
+            Chain::Bitcoin => &["mainnet", "testnet", "testnet4", "signet", "regtest"],
+            Chain::Litecoin | Chain::Dogecoin => &["mainnet", "testnet", "regtest"],
+        }
+    }
+
+    pub fn block_source(self) -> BlockSource {
+        match self {
+            Chain::Bitcoin => BlockSource::Verbose3,
+            // Litecoin Core is based on Bitcoin Core 0.21 and has no verbosity 3.
+            Chain::Litecoin => BlockSource::Verbose2,
+            // Dogecoin Core 1.14 takes a bool verbose flag and has merged-mined headers.
+            Chain::Dogecoin => BlockSource::RawHex,
+        }
+    }
+
+    /// Parameters of `network`; `None` for networks the chain does not have.
+    pub fn network_params(self, network: &str) -> Option<NetworkParams> {
+        let params = match (self, network) {
+            (Chain::Bitcoin, "mainnet") => NetworkParams {
+                rpc_chain: "main",
+                genesis_hash: "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+                magic: "f9beb4d9",
+                pubkey_hash_prefix: 0x00,
+                script_hash_prefix: 0x05,
+                bech32_hrp: Some("bc"),
+                base58_leading: &['1', '3'],
+            },
+            (Chain::Bitcoin, "testnet") => bitcoin_testnet_params(),
+            (Chain::Bitcoin, "testnet4") => NetworkParams {
+                rpc_chain: "testnet4",
+                genesis_hash: "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043",
+                magic: "1c163f28",
+                ..bitcoin_testnet_params()
+            },
+            // Default public signet; a custom one overrides these in `ChainParams`.
+            (Chain::Bitcoin, "signet") => NetworkParams {
+                rpc_chain: "signet",
+                genesis_hash: "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6",
+                magic: "0a03cf40",
+                ..bitcoin_testnet_params()
+            },
+            (Chain::Bitcoin, "regtest") => NetworkParams {
+                rpc_chain: "regtest",
+                genesis_hash: "0f9188f13cb7b2c71f2a335e0a4fc328bf5beb436012afca590b1a11466e2206",
+                magic: "fabfb5da",
+                bech32_hrp: Some("bcrt"),
+                ..bitcoin_testnet_params()
+            },
+            (Chain::Litecoin, "mainnet") => NetworkParams {
+                rpc_chain: "main",
+                genesis_hash: "12a765e31ffd4059bada1e25190f6e98c99d9714d334efa41a195a7e7e04bfe2",
+                magic: "fbc0b6db",
+                pubkey_hash_prefix: 0x30,
+                script_hash_prefix: 0x32,
+                bech32_hrp: Some("ltc"),
+                base58_leading: &['L', 'M', '3'],
+            },
+            (Chain::Litecoin, "testnet") => NetworkParams {
+                rpc_chain: "test",
+                genesis_hash: "4966625a4b2851d9fdee139e56211a0d88575f59ed816ff5e6a63deb4e3e29a0",
+                magic: "fdd2c8f1",
+                pubkey_hash_prefix: 0x6f,
+                script_hash_prefix: 0x3a,
+                bech32_hrp: Some("tltc"),
+                base58_leading: &['m', 'n', 'Q', '2'],
+            },
+            (Chain::Litecoin, "regtest") => NetworkParams {
+                rpc_chain: "regtest",
+                genesis_hash: "530827f38f93b43ed12af0b3ad25a288dc02ed74d6d7857862df51fc56c416f9",
+                magic: "fabfb5da",
+                pubkey_hash_prefix: 0x6f,
+                script_hash_prefix: 0x3a,
+                bech32_hrp: Some("rltc"),
+                base58_leading: &['m', 'n', 'Q', '2'],
+            },
+            (Chain::Dogecoin, "mainnet") => NetworkParams {
+                rpc_chain: "main",
+                genesis_hash: "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691",
+                magic: "c0c0c0c0",
+                pubkey_hash_prefix: 0x1e,
+                script_hash_prefix: 0x16,
+                bech32_hrp: None,
+                base58_leading: &['D', '9', 'A'],
+            },
+            (Chain::Dogecoin, "testnet") => NetworkParams {
+                rpc_chain: "test",
+                genesis_hash: "bb0a78264637406b6360aad926284d544d7049f45189db5664f3c4d07350559e",
+                magic: "fcc1b7dc",
+                pubkey_hash_prefix: 0x71,
+                script_hash_prefix: 0xc4,
+                bech32_hrp: None,
+                base58_leading: &['n', '2'],
+            },
+            (Chain::Dogecoin, "regtest") => NetworkParams {
+                rpc_chain: "regtest",
+                genesis_hash: "3d2160a3b5dc4a9d62e7e66a295f70313ac808440ef7400d6c0772171ce973a5",
+                magic: "fabfb5da",
+                pubkey_hash_prefix: 0x6f,
+                script_hash_prefix: 0xc4,
+                bech32_hrp: None,
+                base58_leading: &['m', 'n', '2'],
+            },
+            _ => return None,
+        };
+        Some(params)
+    }
+}
+
+impl NetworkParams {
+    pub fn address_encoding(&self) -> AddressEncoding {
+        AddressEncoding::Base58 {
+            pubkey_hash: self.pubkey_hash_prefix,
+            script_hash: self.script_hash_prefix,
+            bech32_hrp: self.bech32_hrp,
+        }
+    }
+}
+
+/// Bitcoin testnet3; the other Bitcoin test networks share its address prefixes.
+fn bitcoin_testnet_params() -> NetworkParams {
+    NetworkParams {
+        rpc_chain: "test",
+        genesis_hash: "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943",
+        magic: "0b110907",
+        pubkey_hash_prefix: 0x6f,
+        script_hash_prefix: 0xc4,
+        bech32_hrp: Some("tb"),
+        base58_leading: &['m', 'n', '2'],
+    }
+}
+
+/// Transactions of a serialized block in the shape of `getblock` verbosity 2. The AuxPoW
+/// proof of merged-mined headers is skipped; it only commits the parent chain's work.
+pub fn decode_block_transactions(block_hex: &str, encoding: AddressEncoding) -> Result<Vec<RpcTransaction>, String> {
+    let bytes = hex::decode(block_hex).map_err(|err| format!("block hex does not decode: {err}"))?;
+    let mut reader = bytes.as_slice();
+
+    let header = Header::consensus_decode(&mut reader).map_err(describe)?;
+    if header.version.to_consensus() & AUXPOW_VERSION_FLAG != 0 {
+        skip_auxpow(&mut reader).map_err(describe)?;
+    }
+    let transactions = Vec::<Transaction>::consensus_decode(&mut reader).map_err(describe)?;
+    if !reader.is_empty() {
+        return Err(format!("{} trailing bytes after block transactions", reader.len()));
+    }
+
+    Ok(transactions
+        .iter()
+        .map(|transaction| rpc_transaction(transaction, encoding))
+        .collect())
+}
+
+/// Parent coinbase with its merkle branch, the chain merkle branch and the parent header.
+fn skip_auxpow(reader: &mut &[u8]) -> Result<(), encode::Error> {
+    Transaction::consensus_decode(reader)?;
+    BlockHash::consensus_decode(reader)?;
+    Vec::<TxMerkleNode>::consensus_decode(reader)?;
+    i32::consensus_decode(reader)?;
+    Vec::<TxMerkleNode>::consensus_decode(reader)?;
+    i32::consensus_decode(reader)?;
+    Header::consensus_decode(reader)?;
+    Ok(())
+}
+
+fn rpc_transaction(transaction: &Transaction, encoding: AddressEncoding) -> RpcTransaction {
+    let coinbase = transaction.is_coinbase();
+    RpcTransaction {
+        txid: transaction.compute_txid().to_string(),
+        vin: transaction
+            .input
+            .iter()
+            .map(|input| RpcVin {
+                txid: (!coinbase).then(|| input.previous_output.txid.to_string()),
+                vout: (!coinbase).then_some(input.previous_output.vout as i32),
+                sequence: i64::from(input.sequence.0),
+                txinwitness: (!input.witness.is_empty())
+                    .then(|| input.witness.iter().map(hex::encode).collect()),
+                prevout: None,
+            })
+            .collect(),
+        vout: transaction
+            .output
+            .iter()
+            .enumerate()
+            .map(|(n, output)| {
+                let hex = hex::encode(output.script_pubkey.as_bytes());
+                let (script_type, address) = classify_script(&hex, encoding)
+                    .map(|info| (info.script_type.to_string(), info.address))
+                    .unwrap_or_else(|| ("nonstandard".to_string(), None));
+                RpcVout {
+                    n: n as i32,
+                    value_sats: output.value.to_sat() as i64,
+                    script_pub_key: RpcScriptPubKey {
+                        script_type,
+                        hex,
+                        address,
+                        addresses: None,
+                    },
+                }
+            })
+            .collect(),
+        fee_sats: None,
+        vsize: Some(transaction.vsize() as i64),
+        weight: Some(transaction.weight().to_wu() as i64),
+        version: Some(i64::from(transaction.version.0)),
+        locktime: Some(i64::from(transaction.lock_time.to_consensus_u32())),
+    }
+}
+
+fn describe(err: encode::Error) -> String {
+    format!("block does not decode: {err}")
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::block::{Header, Version};
+    use bitcoin::consensus::serialize;
+    use bitcoin::hashes::Hash;
+    use bitcoin::transaction::Version as TxVersion;
+    use bitcoin::{Amount, BlockHash, CompactTarget, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxMerkleNode, TxOut, Witness};
+
+    use super::{decode_block_transactions, Chain, AUXPOW_VERSION_FLAG};
+
+    fn transaction(previous_output: OutPoint, script_hex: &str, value: u64) -> Transaction {
+        Transaction {
+            version: TxVersion::ONE,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output,
+                script_sig: ScriptBuf::from_bytes(vec![0x51]),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(value),
+                script_pubkey: ScriptBuf::from_bytes(hex::decode(script_hex).expect("script hex")),
+            }],
+        }
+    }
+
+    #[test]
+    fn decodes_merged_mined_blocks() {
+        let header = |version: i32| Header {
+            version: Version::from_consensus(version),
+            prev_blockhash: BlockHash::all_zeros(),
+            merkle_root: TxMerkleNode::all_zeros(),
+            time: 1_700_000_000,
+            bits: CompactTarget::from_consensus(0x1e0ffff0),
+            nonce: 0,
+        };
+        let coinbase = transaction(OutPoint::null(), "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac", 10_000_000_000_000);
+        let spend = transaction(
+            OutPoint::new(coinbase.compute_txid(), 0),
+            "a914748284390f9e263a4b766a75d0633c50426eb87587",
+            4_200_000_000,
+        );
+
+        let mut block = serialize(&header(0x0062_0004 | AUXPOW_VERSION_FLAG));
+        block.extend(serialize(&transaction(OutPoint::null(), "6a00", 0)));
+        block.extend(serialize(&BlockHash::all_zeros()));
+        block.extend(serialize(&vec![TxMerkleNode::all_zeros()]));
+        block.extend(serialize(&0_i32));
+        block.extend(serialize(&Vec::<TxMerkleNode>::new()));
+        block.extend(serialize(&0_i32));
+        block.extend(serialize(&header(0x2000_0000)));
+        block.extend(serialize(&vec![coinbase.clone(), spend.clone()]));
+
+        let encoding = Chain::Dogecoin.network_params("mainnet").expect("dogecoin mainnet").address_encoding();
+        let transactions = decode_block_transactions(&hex::encode(&block), encoding).expect("merged-mined block");
+        assert_eq!(transactions.len(), 2);
+        assert!(transactions[0].is_coinbase());
+        assert_eq!(transactions[0].vout[0].value_sats, 10_000_000_000_000);
+        assert_eq!(
+            transactions[0].vout[0].script_pub_key.address.as_deref(),
+            Some("DEA5vGb2NpAwCiCp5yTE16F3DueQUVivQp")
+        );
+        assert_eq!(transactions[1].txid, spend.compute_txid().to_string());
+        assert_eq!(transactions[1].vin[0].txid.as_deref(), Some(coinbase.compute_txid().to_string().as_str()));
+        assert_eq!(transactions[1].vout[0].script_pub_key.script_type, "scripthash");
+        assert_eq!(transactions[1].weight, Some(spend.weight().to_wu() as i64));
+        assert_eq!((transactions[1].version, transactions[1].locktime), (Some(1), Some(0)));
+
+        let mut plain = serialize(&header(0x0062_0004));
+        plain.extend(serialize(&vec![coinbase]));
+        assert_eq!(decode_block_transactions(&hex::encode(&plain), encoding).expect("plain block").len(), 1);
+        assert!(decode_block_transactions(&hex::encode(&block[..block.len() - 1]), encoding).is_err());
+
+        assert_eq!(Chain::from_name("litecoin"), Some(Chain::Litecoin));
+        assert!(Chain::Litecoin.network_params("signet").is_none());
+        assert!(Chain::Dogecoin.network_params("testnet4").is_none());
+    }
+}
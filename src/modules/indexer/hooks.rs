@@ -0,0 +1,213 @@
+//! Hooks for users of the crate as a library: async callbacks [`IndexerPipeline`] runs on
+//! every block and transaction before it is written, which can add keys to `blocks.meta`,
+//! enrich the `transactions.decoded` JSON or veto persistence.
+//!
+//! [`IndexerPipeline`]: super::IndexerPipeline
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use serde_json::{Map, Value};
+
+use super::{IndexerError, RpcBlock, RpcTransaction};
+
+pub type HookError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookVerdict {
+    Persist,
+    /// A vetoed block is not written and the write fails with [`IndexerError::Vetoed`];
+    /// a vetoed transaction is left out of its block with its inputs and outputs.
+    Veto(String),
+}
+
+pub struct BlockHookContext<'a> {
+    pub block: &'a RpcBlock,
+    /// Keys merged into `blocks.meta`; `provenance` is always the pipeline's own.
+    pub meta: &'a mut Map<String, Value>,
+}
+
+pub struct TransactionHookContext<'a> {
+    pub block: &'a RpcBlock,
+    pub position_in_block: usize,
+    pub tx: &'a RpcTransaction,
+    /// Stored as `transactions.decoded`; only `vsize` is kept without `storage.store_decoded_tx`.
+    pub decoded: &'a mut Value,
+}
+
+/// Callbacks of one hook. Blocks reach them in the order they are written, which is not
+/// strictly height order when jobs fill gaps, and again when a block is retried or rewritten.
+pub trait PipelineHook: Send + Sync {
+    /// Reported in the errors of a failed or vetoed write.
+    fn name(&self) -> &str;
+
+    /// Runs before the transactions of the block.
+    fn on_block<'a>(&'a self, _ctx: BlockHookContext<'a>) -> BoxFuture<'a, Result<HookVerdict, HookError>> {
+        Box::pin(std::future::ready(Ok(HookVerdict::Persist)))
+    }
+
+    fn on_transaction<'a>(&'a self, _ctx: TransactionHookContext<'a>) -> BoxFuture<'a, Result<HookVerdict, HookError>> {
+        Box::pin(std::future::ready(Ok(HookVerdict::Persist)))
+    }
+}
+
+/// Hooks of a pipeline, run in registration order; the first veto wins.
+#[derive(Clone, Default)]
+pub struct HookRegistry {
+    hooks: Vec<Arc<dyn PipelineHook>>,
+}
+
+/// What the hooks made of a block, applied when it is written.
+#[derive(Debug, Default)]
+pub(crate) struct HookedBlock {
+    pub meta: Map<String, Value>,
+    /// `transactions.decoded` by position in the block.
+    pub decoded: HashMap<usize, Value>,
+    /// Positions of vetoed transactions.
+    pub vetoed: HashSet<usize>,
+}
+
+impl HookRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, hook: impl PipelineHook + 'static) -> Self {
+        self.hooks.push(Arc::new(hook));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Runs every hook on `block` and its transactions.
+    pub(crate) async fn apply(&self, block: &RpcBlock) -> Result<HookedBlock, IndexerError> {
+        let mut hooked = HookedBlock::default();
+        if self.hooks.is_empty() {
+            return Ok(hooked);
+        }
+
+        for hook in &self.hooks {
+            let ctx = BlockHookContext {
+                block,
+                meta: &mut hooked.meta,
+            };
+            if let HookVerdict::Veto(reason) = hook.on_block(ctx).await.map_err(|source| hook_failed(hook, block, source))? {
+                return Err(IndexerError::Vetoed {
+                    hook: hook.name().to_string(),
+                    height: block.height,
+                    reason,
+                });
+            }
+        }
+
+        for (position_in_block, tx) in block.tx.iter().enumerate() {
+            let mut decoded = serde_json::to_value(tx).unwrap_or(Value::Null);
+            for hook in &self.hooks {
+                let ctx = TransactionHookContext {
+                    block,
+                    position_in_block,
+                    tx,
+                    decoded: &mut decoded,
+                };
+                if let HookVerdict::Veto(_) = hook.on_transaction(ctx).await.map_err(|source| hook_failed(hook, block, source))? {
+                    hooked.vetoed.insert(position_in_block);
+                    break;
+                }
+            }
+            hooked.decoded.insert(position_in_block, decoded);
+        }
+
+        Ok(hooked)
+    }
+}
+
+fn hook_failed(hook: &Arc<dyn PipelineHook>, block: &RpcBlock, source: HookError) -> IndexerError {
+    IndexerError::Hook {
+        hook: hook.name().to_string(),
+        height: block.height,
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Tagger;
+
+    impl PipelineHook for Tagger {
+        fn name(&self) -> &str {
+            "tagger"
+        }
+
+        fn on_block<'a>(&'a self, ctx: BlockHookContext<'a>) -> BoxFuture<'a, Result<HookVerdict, HookError>> {
+            ctx.meta.insert("tagged".to_string(), Value::Bool(true));
+            Box::pin(std::future::ready(Ok(HookVerdict::Persist)))
+        }
+
+        fn on_transaction<'a>(&'a self, ctx: TransactionHookContext<'a>) -> BoxFuture<'a, Result<HookVerdict, HookError>> {
+            let verdict = if ctx.position_in_block == 0 {
+                HookVerdict::Veto("coinbase".to_string())
+            } else {
+                ctx.decoded["label"] = Value::String(format!("tx {}", ctx.position_in_block));
+                HookVerdict::Persist
+            };
+            Box::pin(std::future::ready(Ok(verdict)))
+        }
+    }
+
+    struct VetoBlocks;
+
+    impl PipelineHook for VetoBlocks {
+        fn name(&self) -> &str {
+            "veto"
+        }
+
+        fn on_block<'a>(&'a self, _ctx: BlockHookContext<'a>) -> BoxFuture<'a, Result<HookVerdict, HookError>> {
+            Box::pin(std::future::ready(Ok(HookVerdict::Veto("not today".to_string()))))
+        }
+    }
+
+    fn block() -> RpcBlock {
+        let tx = |txid: &str| RpcTransaction {
+            txid: txid.to_string(),
+            vin: Vec::new(),
+            vout: Vec::new(),
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
+        };
+        RpcBlock {
+            hash: "00ab".to_string(),
+            height: 7,
+            prev_hash: None,
+            time: 0,
+            tx: vec![tx("a"), tx("b")],
+        }
+    }
+
+    #[tokio::test]
+    async fn hooks_enrich_and_veto() {
+        let block = block();
+        assert!(HookRegistry::new().apply(&block).await.expect("no hooks").decoded.is_empty());
+
+        let hooked = HookRegistry::new().register(Tagger).apply(&block).await.expect("hooks run");
+        assert_eq!(hooked.meta.get("tagged"), Some(&Value::Bool(true)));
+        assert_eq!(hooked.vetoed, HashSet::from([0]));
+        assert_eq!(hooked.decoded[&1]["label"], "tx 1");
+        assert_eq!(hooked.decoded[&1]["txid"], "b");
+
+        let err = HookRegistry::new()
+            .register(Tagger)
+            .register(VetoBlocks)
+            .apply(&block)
+            .await
+            .expect_err("block vetoed");
+        assert_eq!(err.to_string(), "hook veto vetoed block at height 7: not today");
+    }
+}
@@ -1,20 +1,39 @@
 use std::future::Future;
 use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
 use std::time::Instant;
 
 use serde::Deserialize;
 use serde_json::Value;
-use sqlx::{Executor, FromRow, PgConnection, PgPool, Postgres, Row};
+use sqlx::{FromRow, PgConnection, PgPool, Row};
 use thiserror::Error;
+use tracing::{info_span, warn, Instrument};
 
+pub mod chain;
+pub mod hooks;
+pub mod writer;
+
+use crate::modules::filters::{basic_filter, filter_header};
+use crate::modules::rpc::{BitcoinRpc, RpcClient};
 use crate::modules::metrics::MetricsService;
+use crate::modules::protocols::{self, ProtocolIndexer, ProtocolTx};
+use crate::modules::indexer::chain::{decode_block_transactions, BlockSource, Chain};
+use crate::modules::indexer::hooks::{HookError, HookRegistry, HookedBlock};
+use crate::modules::indexer::writer::WriterPool;
+use crate::modules::scripts::{classify_script, AddressEncoding};
+use crate::modules::stats::supply;
+use crate::modules::storage::SchemaFeatures;
 use crate::modules::storage::repo::{
-    AddressBalancesRepo, AddressLookupRepo, BlockRecord, BlocksRepo, TransactionRecord,
-    TransactionsRepo, TxInputRecord, TxInputsRepo, TxOutputRecord, TxOutputsRepo, UtxoCreateRecord,
-    UtxosRepo,
+    BlockFilterRecord, BlockFiltersRepo, BlockRawRepo, BlockRecord, BlockWriteProgressRecord, BlockWriteProgressRepo,
+    DoubleSpendsRepo, OutboxEventRecord, OutboxRepo, PrevoutRecord, StreamEventsRepo, TransactionRecord,
+    TxInputRecord, TxOutputRecord, UtxoCreateRecord,
+};
+use crate::modules::storage::store::{
+    acquire_chain_state_lock, canonical_block_hash_at_height, ChainStore, ChainStoreTx,
 };
 
-#[derive(Debug, Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
 pub struct RpcBlock {
     pub hash: String,
     pub height: i32,
@@ -24,29 +43,90 @@ pub struct RpcBlock {
     pub tx: Vec<RpcTransaction>,
 }
 
-#[derive(Debug, Deserialize, serde::Serialize)]
+/// `getblockheader` response; completed to a block by transactions decoded from the raw block.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcBlockHeader {
+    pub hash: String,
+    pub height: i32,
+    #[serde(rename = "previousblockhash")]
+    pub prev_hash: Option<String>,
+    pub time: i64,
+}
+
+impl RpcBlockHeader {
+    pub fn into_block(self, tx: Vec<RpcTransaction>) -> RpcBlock {
+        RpcBlock {
+            hash: self.hash,
+            height: self.height,
+            prev_hash: self.prev_hash,
+            time: self.time,
+            tx,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
 pub struct RpcTransaction {
     pub txid: String,
     pub vin: Vec<RpcVin>,
     pub vout: Vec<RpcVout>,
+    /// Reported by `getblock` verbosity 2/3 for non-coinbase transactions when the node has undo data.
+    #[serde(
+        rename = "fee",
+        default,
+        with = "crate::modules::amount::btc_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub fee_sats: Option<i64>,
+    /// Virtual size in vbytes; kept in `transactions.decoded` for fee-rate estimates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vsize: Option<i64>,
+    /// BIP141 weight in weight units.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locktime: Option<i64>,
+}
+
+impl RpcTransaction {
+    pub fn is_coinbase(&self) -> bool {
+        self.vin.iter().any(|vin| vin.txid.is_none())
+    }
 }
 
-#[derive(Debug, Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
 pub struct RpcVin {
     pub txid: Option<String>,
     pub vout: Option<i32>,
     pub sequence: i64,
+    /// Kept in `transactions.decoded`; reveals the public key of P2WPKH spends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txinwitness: Option<Vec<String>>,
+    /// Spent output, returned by `getblock` with verbosity 3.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prevout: Option<RpcPrevout>,
 }
 
-#[derive(Debug, Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
+pub struct RpcPrevout {
+    #[serde(rename = "value", with = "crate::modules::amount::btc")]
+    pub value_sats: i64,
+    #[serde(rename = "scriptPubKey", default, skip_serializing_if = "Option::is_none")]
+    pub script_pub_key: Option<RpcScriptPubKey>,
+}
+
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
 pub struct RpcVout {
     pub n: i32,
-    pub value: f64,
+    #[serde(rename = "value", with = "crate::modules::amount::btc")]
+    pub value_sats: i64,
     #[serde(rename = "scriptPubKey")]
     pub script_pub_key: RpcScriptPubKey,
 }
 
-#[derive(Debug, Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Deserialize, serde::Serialize)]
 pub struct RpcScriptPubKey {
     #[serde(rename = "type")]
     pub script_type: String,
@@ -55,12 +135,89 @@ pub struct RpcScriptPubKey {
     pub addresses: Option<Vec<String>>,
 }
 
-pub struct IndexerPipeline<'a> {
-    pool: &'a PgPool,
+impl RpcScriptPubKey {
+    /// Script type and address decoded from `hex` with `encoding`. The node fields are
+    /// used only when `hex` does not decode; `addresses` of pre-22 nodes lists the keys
+    /// of bare multisig outputs, so it is never preferred over the script itself.
+    pub fn resolve(&self, encoding: AddressEncoding) -> (String, Option<String>) {
+        match classify_script(&self.hex, encoding) {
+            Some(info) => (info.script_type.to_string(), info.address),
+            None => (
+                self.script_type.clone(),
+                self.address
+                    .clone()
+                    .or_else(|| self.addresses.as_ref().and_then(|list| list.first().cloned())),
+            ),
+        }
+    }
+}
+
+/// Writes blocks to `S`; the PostgreSQL pool in production, see [`ChainStore`].
+pub struct IndexerPipeline<'a, S = PgPool> {
+    pool: &'a S,
     metrics: MetricsService,
+    schema: SchemaFeatures,
+    encoding: AddressEncoding,
+    outbox: bool,
+    checkpoint_job: Option<&'a str>,
+    raw_block: Option<&'a [u8]>,
+    store_decoded: bool,
+    store_witness: bool,
+    block_filter: bool,
+    txs_per_chunk: Option<usize>,
+    prevouts: Option<&'a HashMap<(String, i32), PrevoutRecord>>,
+    protocols: Vec<Arc<dyn ProtocolIndexer>>,
+    hooks: HookRegistry,
+    /// Off for [`Self::rewrite_block`], which rewrites a block consumers already saw.
+    announce: bool,
 }
 
-const CHAIN_STATE_LOCK_KEY: i64 = -1;
+/// Bumped whenever a stage changes the rows it writes for a block, so heights indexed
+/// by older code can be found through `blocks.meta->'provenance'` and re-enriched.
+///
+/// 2: output script types and addresses are decoded from `scriptPubKey.hex`.
+pub const PIPELINE_VERSION: i32 = 2;
+/// Blocks, transactions, inputs and outputs.
+pub const STAGE_CHAIN: &str = "chain";
+/// `utxos_current` and address balances with their history.
+pub const STAGE_ADDRESS_INDEX: &str = "address_index";
+/// `transactions.fee_sats` and `blocks.total_fees_sats`.
+pub const STAGE_FEES: &str = "fees";
+/// Value and address of the spent output on `tx_inputs` rows.
+pub const STAGE_PREVOUTS: &str = "prevouts";
+/// `block_supply` rows and `utxo_age_days`.
+pub const STAGE_SUPPLY: &str = "supply";
+pub const PIPELINE_STAGES: &[&str] = &[STAGE_CHAIN, STAGE_ADDRESS_INDEX, STAGE_FEES, STAGE_PREVOUTS, STAGE_SUPPLY];
+
+/// What produced the rows of a block; kept in `blocks.meta.provenance`.
+#[derive(Debug, Clone, PartialEq, Deserialize, serde::Serialize)]
+pub struct BlockProvenance {
+    pub pipeline_version: i32,
+    pub indexer_version: String,
+    pub stages: Vec<String>,
+}
+
+impl BlockProvenance {
+    /// Provenance of blocks persisted by this build against a schema with `schema` features.
+    pub fn current(schema: SchemaFeatures) -> Self {
+        let mut stages = vec![STAGE_CHAIN.to_string(), STAGE_ADDRESS_INDEX.to_string()];
+        if schema.transaction_fees {
+            stages.push(STAGE_FEES.to_string());
+        }
+        if schema.input_prevouts {
+            stages.push(STAGE_PREVOUTS.to_string());
+        }
+        if schema.supply_stats {
+            stages.push(STAGE_SUPPLY.to_string());
+        }
+
+        Self {
+            pipeline_version: PIPELINE_VERSION,
+            indexer_version: env!("CARGO_PKG_VERSION").to_string(),
+            stages,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PersistBlockOutcome {
@@ -69,20 +226,296 @@ pub enum PersistBlockOutcome {
     WaitingForPreviousHeight,
 }
 
-impl<'a> IndexerPipeline<'a> {
-    pub fn new(pool: &'a PgPool, metrics: MetricsService) -> Self {
-        Self { pool, metrics }
+impl<'a, S: ChainStore> IndexerPipeline<'a, S> {
+    pub fn new(pool: &'a S, metrics: MetricsService) -> Self {
+        Self {
+            pool,
+            metrics,
+            schema: SchemaFeatures::latest(),
+            encoding: AddressEncoding::Bitcoin(bitcoin::Network::Bitcoin),
+            outbox: false,
+            checkpoint_job: None,
+            raw_block: None,
+            store_decoded: true,
+            store_witness: false,
+            block_filter: false,
+            txs_per_chunk: None,
+            prevouts: None,
+            protocols: Vec::new(),
+            hooks: HookRegistry::default(),
+            announce: true,
+        }
     }
 
-    pub async fn persist_block(&self, block: &RpcBlock) -> Result<PersistBlockOutcome, sqlx::Error> {
-        let mut db_tx = self.pool.begin().await?;
-        acquire_chain_state_lock(&mut *db_tx).await?;
-        acquire_height_lock(&mut *db_tx, block.height).await?;
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Network output addresses are encoded for; Bitcoin mainnet by default.
+    pub fn with_network(mut self, encoding: impl Into<AddressEncoding>) -> Self {
+        self.encoding = encoding.into();
+        self
+    }
+
+    /// Queues block, transaction and address activity events in `event_outbox` in the
+    /// same transaction as the block, for the sink publisher. Off by default.
+    pub fn with_outbox(mut self, enabled: bool) -> Self {
+        self.outbox = enabled;
+        self
+    }
+
+    /// Moves the checkpoint and progress of `job_id` to the block in the same transaction
+    /// that persists it, so a crash never leaves a committed block ahead of the job.
+    pub fn with_checkpoint(mut self, job_id: Option<&'a str>) -> Self {
+        self.checkpoint_job = job_id;
+        self
+    }
+
+    /// Stores the serialized block in `block_raw` along with its rows.
+    pub fn with_raw_block(mut self, raw: Option<&'a [u8]>) -> Self {
+        self.raw_block = raw;
+        self
+    }
+
+    /// Stores the full node JSON of transactions in `transactions.decoded`; on by default,
+    /// see [`TransactionsRepo::with_decoded`].
+    pub fn with_decoded_tx(mut self, enabled: bool) -> Self {
+        self.store_decoded = enabled;
+        self
+    }
+
+    /// Stores the witness stacks of inputs in `tx_inputs.witness`. Off by default.
+    pub fn with_witness(mut self, enabled: bool) -> Self {
+        self.store_witness = enabled;
+        self
+    }
+
+    /// Stores the BIP158 basic filter of the block in `block_filters` along with its rows.
+    pub fn with_block_filter(mut self, enabled: bool) -> Self {
+        self.block_filter = enabled;
+        self
+    }
+
+    /// Commits a block with more than `size` transactions in chunks of `size`, see
+    /// `0030_block_write_progress.sql`. The whole block is one transaction by default.
+    pub fn with_tx_chunks(mut self, size: Option<usize>) -> Self {
+        self.txs_per_chunk = size.filter(|size| *size > 0);
+        self
+    }
+
+    /// Spent outputs that are neither stored nor part of the block JSON, e.g. fetched
+    /// from the node; see [`IndexerService::with_prevout_rpc_fallback`].
+    pub fn with_prevouts(mut self, prevouts: Option<&'a HashMap<(String, i32), PrevoutRecord>>) -> Self {
+        self.prevouts = prevouts;
+        self
+    }
+
+    /// Protocol indexers each transaction is handed to after its outputs are written; their
+    /// names are added to the provenance stages of the block. None by default.
+    pub fn with_protocols(mut self, protocols: Vec<Arc<dyn ProtocolIndexer>>) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// Hooks run on the block and each of its transactions before anything is written,
+    /// see [`hooks`]. None by default.
+    pub fn with_hooks(mut self, hooks: HookRegistry) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    pub async fn persist_block(&self, block: &RpcBlock) -> Result<PersistBlockOutcome, IndexerError> {
+        self.persist_block_from(block, 0).await
+    }
+
+    /// Replaces the rows of `block`, which must be the canonical block at its height, in
+    /// one transaction: its transactions with their inputs and outputs are deleted and
+    /// written again, and the block row gets the current provenance. No events are queued.
+    /// UTXOs and address balances are left as they are; rebuild them afterwards, and so are
+    /// the tables of protocol indexers, which would see the inputs of the block spent twice.
+    pub async fn rewrite_block(mut self, block: &RpcBlock) -> Result<(), IndexerError> {
+        self.outbox = false;
+        self.protocols.clear();
+        self.announce = false;
+        let span = info_span!("rewrite_block", block_height = block.height, block_hash = %block.hash);
+        async {
+            let hooked = self.hooks.apply(block).await?;
+            let mut db_tx = self.pool.begin_write(self.schema).await?;
+            db_tx.lock_chain_state().await?;
+            db_tx.lock_height(block.height).await?;
+            let stored = db_tx.canonical_block_hash_at_height(block.height).await?;
+            if stored.as_deref() != Some(block.hash.as_str()) {
+                return Err(IndexerError::NotCanonical {
+                    height: block.height,
+                    hash: block.hash.clone(),
+                });
+            }
+
+            for statement in [
+                "DELETE FROM tx_inputs WHERE txid IN (SELECT txid FROM transactions WHERE block_hash = $1)",
+                "DELETE FROM tx_outputs WHERE txid IN (SELECT txid FROM transactions WHERE block_hash = $1)",
+                "DELETE FROM transactions WHERE block_hash = $1",
+            ] {
+                sqlx::query(statement)
+                    .bind(&block.hash)
+                    .execute(postgres(&mut db_tx, "rewriting blocks")?)
+                    .await?;
+            }
+
+            let mut total_fees_sats = Some(0i64);
+            let txs = 0..block.tx.len();
+            self.write_transactions(&mut db_tx, block, txs, "confirmed", &mut total_fees_sats, &hooked)
+                .await?;
+            self.finish_block(&mut db_tx, block, total_fees_sats, &hooked).await?;
+            db_tx.commit().await?;
+            Ok(())
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Same as [`Self::persist_block`], but a block at `start_height` is accepted without
+    /// its predecessor being indexed. Used by `height_range` jobs that start mid-chain.
+    pub async fn persist_block_from(
+        &self,
+        block: &RpcBlock,
+        start_height: i32,
+    ) -> Result<PersistBlockOutcome, IndexerError> {
+        let span = info_span!(
+            "persist_block",
+            block_height = block.height,
+            block_hash = %block.hash,
+            tx_count = block.tx.len()
+        );
+        self.write_block(block, start_height).instrument(span).await
+    }
 
-        if let Some(existing_hash) = canonical_block_hash_at_height(&mut *db_tx, block.height).await? {
+    async fn write_block(&self, block: &RpcBlock, start_height: i32) -> Result<PersistBlockOutcome, IndexerError> {
+        // Run before the locks are taken, so slow hooks do not hold up other writers.
+        let hooked = self.hooks.apply(block).await?;
+        let chunk_size = self
+            .txs_per_chunk
+            .filter(|size| self.chunked_writes() && block.tx.len() > *size);
+        if let Some(chunk_size) = chunk_size {
+            return self.write_block_chunked(block, start_height, chunk_size, &hooked).await;
+        }
+
+        let mut db_tx = self.pool.begin_write(self.schema).await?;
+        if let Some(outcome) = self.prepare_height(&mut db_tx, block, start_height).await? {
             db_tx.commit().await?;
+            return Ok(outcome);
+        }
+
+        let mut total_fees_sats = Some(0i64);
+        let txs = 0..block.tx.len();
+        self.write_transactions(&mut db_tx, block, txs, "confirmed", &mut total_fees_sats, &hooked)
+            .await?;
+        self.finish_block(&mut db_tx, block, total_fees_sats, &hooked).await?;
+        db_tx.commit().await?;
+        Ok(PersistBlockOutcome::Indexed)
+    }
+
+    /// Whether blocks above `txs_per_chunk` transactions can be committed in chunks: that
+    /// needs the write progress marker and the `incomplete` status of transactions.
+    fn chunked_writes(&self) -> bool {
+        self.schema.block_write_progress && self.schema.incomplete_transactions
+    }
+
+    /// Commits the transactions of `block` in chunks of `chunk_size`, each in a database
+    /// transaction of its own. `block_write_progress` records the committed chunks, so a
+    /// write interrupted by a crash resumes after them. The transactions stay `incomplete`
+    /// until the last chunk confirms them and writes the block row.
+    async fn write_block_chunked(
+        &self,
+        block: &RpcBlock,
+        start_height: i32,
+        chunk_size: usize,
+        hooked: &HookedBlock,
+    ) -> Result<PersistBlockOutcome, IndexerError> {
+        let progress = BlockWriteProgressRepo;
+        let tx_count = i32::try_from(block.tx.len())
+            .map_err(|_| sqlx::Error::Protocol(format!("block {} has too many transactions", block.hash)))?;
+
+        loop {
+            let mut db_tx = self.pool.begin_write(self.schema).await?;
+            if let Some(outcome) = self.prepare_height(&mut db_tx, block, start_height).await? {
+                db_tx.commit().await?;
+                return Ok(outcome);
+            }
+
+            // Read under the chain state lock, so concurrent writers of the block continue
+            // each other's chunks instead of writing one twice.
+            let stored = progress.at_height(postgres(&mut db_tx, "chunked block writes")?, block.height).await?;
+            let (next_tx, mut total_fees_sats) = match stored {
+                Some(current) if current.hash == block.hash => (
+                    usize::try_from(current.next_tx).unwrap_or_default().min(block.tx.len()),
+                    current.total_fees_sats,
+                ),
+                Some(stale) => {
+                    self.discard_partial_block(&mut db_tx, &stale).await?;
+                    (0, Some(0))
+                }
+                None => (0, Some(0)),
+            };
+
+            let chunk_end = std::cmp::min(next_tx.saturating_add(chunk_size), block.tx.len());
+            self.write_transactions(&mut db_tx, block, next_tx..chunk_end, "incomplete", &mut total_fees_sats, hooked)
+                .await?;
+            if chunk_end == block.tx.len() {
+                let conn = postgres(&mut db_tx, "chunked block writes")?;
+                progress.delete(&mut *conn, &block.hash).await?;
+                progress.confirm_transactions(conn, block.height, &block.hash).await?;
+                self.finish_block(&mut db_tx, block, total_fees_sats, hooked).await?;
+                db_tx.commit().await?;
+                return Ok(PersistBlockOutcome::Indexed);
+            }
+
+            let record = BlockWriteProgressRecord {
+                hash: block.hash.clone(),
+                height: block.height,
+                tx_count,
+                next_tx: chunk_end as i32,
+                total_fees_sats,
+            };
+            let conn = postgres(&mut db_tx, "chunked block writes")?;
+            observe_db_write(&self.metrics, "block_write_progress", progress.save(conn, &record)).await?;
+            db_tx.commit().await?;
+        }
+    }
+
+    /// Spent output of `vin` from the `prevout` of `getblock` verbosity 3, or else from
+    /// [`Self::with_prevouts`].
+    fn unstored_prevout(&self, vin: &RpcVin, prev_txid: &str, prev_vout: i32) -> Option<PrevoutRecord> {
+        if let Some(prevout) = vin.prevout.as_ref() {
+            if let Some(script_pub_key) = prevout.script_pub_key.as_ref() {
+                let (script_type, address) = script_pub_key.resolve(self.encoding);
+                return Some(PrevoutRecord {
+                    value_sats: prevout.value_sats,
+                    script_type,
+                    address,
+                });
+            }
+        }
+
+        self.prevouts?.get(&(prev_txid.to_string(), prev_vout)).cloned()
+    }
+
+    /// Takes the chain state and height locks and checks whether `block` can be written.
+    /// Returns the outcome when it must not be.
+    async fn prepare_height(
+        &self,
+        db_tx: &mut S::Tx,
+        block: &RpcBlock,
+        start_height: i32,
+    ) -> Result<Option<PersistBlockOutcome>, sqlx::Error> {
+        db_tx.lock_chain_state().await?;
+        db_tx.lock_height(block.height).await?;
+
+        if let Some(existing_hash) = db_tx.canonical_block_hash_at_height(block.height).await? {
             if existing_hash == block.hash {
-                return Ok(PersistBlockOutcome::AlreadyIndexed);
+                self.save_checkpoint(db_tx, block).await?;
+                return Ok(Some(PersistBlockOutcome::AlreadyIndexed));
             }
 
             return Err(sqlx::Error::Protocol(format!(
@@ -91,63 +524,91 @@ impl<'a> IndexerPipeline<'a> {
             )));
         }
 
-        if block.height > 0 && canonical_block_hash_at_height(&mut *db_tx, block.height - 1).await?.is_none() {
-            db_tx.commit().await?;
-            return Ok(PersistBlockOutcome::WaitingForPreviousHeight);
+        if block.height > start_height && db_tx.canonical_block_hash_at_height(block.height - 1).await?.is_none() {
+            return Ok(Some(PersistBlockOutcome::WaitingForPreviousHeight));
+        }
+
+        if self.schema.chain_partitions {
+            sqlx::query("SELECT ensure_chain_partitions($1)")
+                .bind(block.height)
+                .execute(postgres(db_tx, "chain partitions")?)
+                .await?;
         }
 
-        let blocks = BlocksRepo::new(self.pool);
-        let txs = TransactionsRepo::new(self.pool);
-        let inputs = TxInputsRepo::new(self.pool);
-        let outputs = TxOutputsRepo::new(self.pool);
-        let utxos = UtxosRepo::new(self.pool);
-        let address_balances = AddressBalancesRepo::new(self.pool);
-        let address_lookup = AddressLookupRepo::new(self.pool);
+        Ok(None)
+    }
+
+    /// Writes the transactions of `block` at the positions in `txs` with `status`, their
+    /// inputs, outputs and effect on UTXOs and address balances, adding their fees to
+    /// `total_fees_sats`.
+    async fn write_transactions(
+        &self,
+        db_tx: &mut S::Tx,
+        block: &RpcBlock,
+        txs: Range<usize>,
+        status: &str,
+        total_fees_sats: &mut Option<i64>,
+        hooked: &HookedBlock,
+    ) -> Result<(), sqlx::Error> {
+        let outbox = OutboxRepo;
+        let double_spends = DoubleSpendsRepo;
+        let outbox_enabled = self.outbox && self.schema.event_outbox;
         let mut address_deltas: HashMap<String, i64> = HashMap::new();
         let mut touched_addresses: HashSet<String> = HashSet::new();
 
-        let block_record = BlockRecord {
-            height: block.height,
-            hash: block.hash.clone(),
-            prev_hash: block.prev_hash.clone().unwrap_or_default(),
-            time: block.time,
-            status: "canonical".to_string(),
-            meta: serde_json::json!({}),
-        };
-        observe_db_write(&self.metrics, "blocks", blocks.upsert(&mut *db_tx, &block_record)).await?;
+        for (tx_position, tx) in block.tx.iter().enumerate().skip(txs.start).take(txs.len()) {
+            if hooked.vetoed.contains(&tx_position) {
+                continue;
+            }
 
-        for (tx_position, tx) in block.tx.iter().enumerate() {
             let tx_record = TransactionRecord {
                 txid: tx.txid.clone(),
                 block_height: Some(block.height),
                 block_hash: Some(block.hash.clone()),
                 position_in_block: tx_position as i32,
                 time: block.time,
-                status: "confirmed".to_string(),
-                decoded: serde_json::to_value(tx).unwrap_or(Value::Null),
+                status: status.to_string(),
+                decoded: match hooked.decoded.get(&tx_position) {
+                    Some(decoded) => decoded.clone(),
+                    None => serde_json::to_value(tx).unwrap_or(Value::Null),
+                },
+                vsize: tx.vsize,
+                weight: tx.weight,
+                version: tx.version,
+                locktime: tx.locktime,
             };
-            observe_db_write(&self.metrics, "transactions", txs.upsert(&mut *db_tx, &tx_record)).await?;
+            observe_db_write(
+                &self.metrics,
+                "transactions",
+                db_tx.upsert_transaction(&tx_record, self.store_decoded),
+            )
+            .await?;
+            let mut tx_outputs = Vec::new();
 
             for (idx, vin) in tx.vin.iter().enumerate() {
                 if let (Some(prev_txid), Some(prev_vout)) = (vin.txid.as_ref(), vin.vout) {
+                    let stored = db_tx.stored_output(prev_txid, prev_vout).await?;
                     let input = TxInputRecord {
                         txid: tx.txid.clone(),
                         vin: idx as i32,
                         prev_txid: prev_txid.clone(),
                         prev_vout,
                         sequence: vin.sequence,
+                        prevout: stored.clone().or_else(|| self.unstored_prevout(vin, prev_txid, prev_vout)),
+                        witness: vin.txinwitness.clone().filter(|_| self.store_witness),
                     };
-                    observe_db_write(&self.metrics, "tx_inputs", inputs.insert(&mut *db_tx, &input)).await?;
+                    observe_db_write(&self.metrics, "tx_inputs", db_tx.insert_input(&input)).await?;
 
-                    if let Some((address, value_sats)) =
-                        address_lookup
-                            .output_address_value(&mut *db_tx, prev_txid, prev_vout)
-                            .await?
+                    if let Some(PrevoutRecord {
+                        address: Some(address),
+                        value_sats,
+                        ..
+                    }) = stored
                     {
                         let spent = observe_db_write(
                             &self.metrics,
                             "utxos_current",
-                            utxos.mark_spent_if_unspent(&mut *db_tx, prev_txid, prev_vout, &tx.txid),
+                            db_tx.mark_spent(prev_txid, prev_vout, &tx.txid),
                         )
                         .await?;
                         if spent {
@@ -158,28 +619,49 @@ impl<'a> IndexerPipeline<'a> {
                 }
             }
 
+            if self.schema.double_spends && !tx.is_coinbase() {
+                let conn = postgres(db_tx, "double spends")?;
+                let conflicts = observe_db_write(
+                    &self.metrics,
+                    "double_spends",
+                    double_spends.record_conflicts(&mut *conn, &tx.txid, "confirmed", Some(block.height)),
+                )
+                .await?;
+                if outbox_enabled {
+                    for conflict in &conflicts {
+                        let event = conflict.outbox_event();
+                        observe_db_write(&self.metrics, "event_outbox", outbox.insert(&mut *conn, &event)).await?;
+                    }
+                }
+            }
+
             for vout in &tx.vout {
-                let address = vout
-                    .script_pub_key
-                    .address
-                    .clone()
-                    .or_else(|| vout.script_pub_key.addresses.as_ref().and_then(|list| list.first().cloned()));
+                let (script_type, address) = vout.script_pub_key.resolve(self.encoding);
 
                 let output = TxOutputRecord {
                     txid: tx.txid.clone(),
                     vout: vout.n,
-                    value_sats: btc_to_sats(vout.value),
-                    script_type: vout.script_pub_key.script_type.clone(),
+                    value_sats: vout.value_sats,
+                    script_type,
                     address,
                     script_hex: vout.script_pub_key.hex.clone(),
+                    block_height: Some(block.height),
                 };
-                observe_db_write(&self.metrics, "tx_outputs", outputs.insert(&mut *db_tx, &output)).await?;
+                observe_db_write(&self.metrics, "tx_outputs", db_tx.insert_output(&output)).await?;
+                if outbox_enabled {
+                    tx_outputs.push(serde_json::json!({
+                        "vout": output.vout,
+                        "value_sats": output.value_sats,
+                        "script_type": output.script_type,
+                        "address": output.address,
+                    }));
+                }
 
                 if let Some(output_address) = output.address.as_ref() {
                     let created = observe_db_write(
                         &self.metrics,
                         "utxos_current",
-                        utxos.insert_unspent_if_absent(&mut *db_tx, &UtxoCreateRecord {
+                        db_tx.insert_unspent(&UtxoCreateRecord {
                             out_txid: output.txid.clone(),
                             out_vout: output.vout,
                             address: output_address.clone(),
@@ -194,41 +676,270 @@ impl<'a> IndexerPipeline<'a> {
                     }
                 }
             }
+
+            let mut tx_fee_sats = None;
+            if self.schema.transaction_fees && !tx.is_coinbase() {
+                let fee_sats = transaction_fee_sats(db_tx, tx).await?;
+                observe_db_write(&self.metrics, "transactions", db_tx.set_transaction_fee(&tx.txid, fee_sats)).await?;
+                *total_fees_sats = total_fees_sats.zip(fee_sats).map(|(total, fee)| total + fee);
+                tx_fee_sats = fee_sats;
+            }
+
+            for protocol in &self.protocols {
+                let protocol_tx = ProtocolTx {
+                    block_height: block.height,
+                    position_in_block: tx_position,
+                    tx,
+                    encoding: self.encoding,
+                };
+                let conn = postgres(db_tx, protocol.name())?;
+                observe_db_write(&self.metrics, protocol.name(), protocol.index_transaction(conn, protocol_tx)).await?;
+            }
+
+            if outbox_enabled {
+                let inputs: Vec<Value> = tx
+                    .vin
+                    .iter()
+                    .filter_map(|vin| Some(serde_json::json!({ "prev_txid": vin.txid.as_ref()?, "prev_vout": vin.vout? })))
+                    .collect();
+                let event = OutboxEventRecord {
+                    stream: "transactions",
+                    event_key: tx.txid.clone(),
+                    payload: serde_json::json!({
+                        "type": "transaction_confirmed",
+                        "txid": tx.txid,
+                        "block_height": block.height,
+                        "block_hash": block.hash,
+                        "position_in_block": tx_position,
+                        "time": block.time,
+                        "coinbase": tx.is_coinbase(),
+                        "fee_sats": tx_fee_sats,
+                        "inputs": inputs,
+                        "outputs": tx_outputs,
+                    }),
+                };
+                let conn = postgres(db_tx, "event outbox")?;
+                observe_db_write(&self.metrics, "event_outbox", outbox.insert(conn, &event)).await?;
+            }
         }
 
-        for (address, delta) in address_deltas {
+        for (address, &delta) in &address_deltas {
             if delta != 0 {
                 observe_db_write(
                     &self.metrics,
                     "address_balance_current",
-                    address_balances.add_delta(&mut *db_tx, &address, delta),
+                    db_tx.add_balance_delta(address, delta),
                 )
                 .await?;
             }
         }
 
+        let mut touched_addresses: Vec<String> = touched_addresses.into_iter().collect();
+        touched_addresses.sort();
         for address in touched_addresses {
-            if let Some(balance_sats) = address_balances
-                .current_balance(&mut *db_tx, &address)
-                .await?
-            {
+            if let Some(balance_sats) = db_tx.current_balance(&address).await? {
                 observe_db_write(
                     &self.metrics,
                     "address_balance_history",
-                    address_balances.upsert_history_snapshot(
-                        &mut *db_tx,
-                        &address,
-                        block.height,
-                        block.time,
-                        balance_sats,
-                    )
+                    db_tx.upsert_balance_snapshot(&address, block.height, block.time, balance_sats),
                 )
                 .await?;
+
+                if outbox_enabled {
+                    let event = OutboxEventRecord {
+                        stream: "address_activity",
+                        event_key: address.clone(),
+                        payload: serde_json::json!({
+                            "type": "address_activity",
+                            "address": address,
+                            "block_height": block.height,
+                            "block_hash": block.hash,
+                            "time": block.time,
+                            "delta_sats": address_deltas.get(&address).copied().unwrap_or(0),
+                            "balance_sats": balance_sats,
+                        }),
+                    };
+                    let conn = postgres(db_tx, "event outbox")?;
+                    observe_db_write(&self.metrics, "event_outbox", outbox.insert(conn, &event)).await?;
+                }
             }
         }
 
-        db_tx.commit().await?;
-        Ok(PersistBlockOutcome::Indexed)
+        Ok(())
+    }
+
+    /// Writes the block row and everything derived from the whole block, then moves the
+    /// checkpoint. Runs in the transaction with the last transactions of the block.
+    async fn finish_block(
+        &self,
+        db_tx: &mut S::Tx,
+        block: &RpcBlock,
+        total_fees_sats: Option<i64>,
+        hooked: &HookedBlock,
+    ) -> Result<(), sqlx::Error> {
+        let outbox_enabled = self.outbox && self.schema.event_outbox;
+
+        let mut provenance = BlockProvenance::current(self.schema);
+        provenance
+            .stages
+            .extend(self.protocols.iter().map(|protocol| protocol.name().to_string()));
+        let mut meta = hooked.meta.clone();
+        meta.insert("provenance".to_string(), serde_json::json!(provenance));
+        let block_record = BlockRecord {
+            height: block.height,
+            hash: block.hash.clone(),
+            prev_hash: block.prev_hash.clone().unwrap_or_default(),
+            time: block.time,
+            status: "canonical".to_string(),
+            meta: Value::Object(meta),
+        };
+        observe_db_write(&self.metrics, "blocks", db_tx.upsert_block(&block_record)).await?;
+        if let Some(raw) = self.raw_block.filter(|_| self.schema.block_raw) {
+            observe_db_write(
+                &self.metrics,
+                "block_raw",
+                BlockRawRepo.insert(postgres(db_tx, "raw blocks")?, &block.hash, block.height, raw),
+            )
+            .await?;
+        }
+
+        if self.schema.transaction_fees {
+            observe_db_write(
+                &self.metrics,
+                "blocks",
+                db_tx.set_block_total_fees(&block.hash, total_fees_sats),
+            )
+            .await?;
+        }
+
+        if let Some(conn) = db_tx.postgres() {
+            observe_db_write(
+                &self.metrics,
+                "address_balance_history",
+                settle_block_below_tip(conn, block.height, &block.hash, block.time),
+            )
+            .await?;
+        }
+
+        if self.schema.supply_stats {
+            observe_db_write(
+                &self.metrics,
+                "block_supply",
+                supply::record_block(postgres(db_tx, "supply stats")?, &block.hash, block.height, block.time),
+            )
+            .await?;
+        }
+
+        if self.block_filter && self.schema.block_filters {
+            let conn = postgres(db_tx, "block filters")?;
+            observe_db_write(&self.metrics, "block_filters", self.save_block_filter(conn, block)).await?;
+        }
+
+        if outbox_enabled {
+            // Queued after the transactions of the block, so a consumer that sees the block has seen them too.
+            let event = OutboxEventRecord {
+                stream: "blocks",
+                event_key: block.hash.clone(),
+                payload: serde_json::json!({
+                    "type": "block_connected",
+                    "height": block.height,
+                    "hash": block.hash,
+                    "prev_hash": block.prev_hash,
+                    "time": block.time,
+                    "tx_count": block.tx.len(),
+                    "total_fees_sats": total_fees_sats.filter(|_| self.schema.transaction_fees),
+                }),
+            };
+            let conn = postgres(db_tx, "event outbox")?;
+            observe_db_write(&self.metrics, "event_outbox", OutboxRepo.insert(conn, &event)).await?;
+        }
+
+        if self.schema.stream_events && self.announce {
+            let payload = serde_json::json!({
+                "height": block.height,
+                "hash": block.hash,
+                "prev_hash": block.prev_hash,
+                "time": block.time,
+                "tx_count": block.tx.len(),
+            });
+            observe_db_write(
+                &self.metrics,
+                "stream_events",
+                StreamEventsRepo.insert(postgres(db_tx, "stream events")?, "block_connected", &payload),
+            )
+            .await?;
+        }
+
+        self.save_checkpoint(db_tx, block).await
+    }
+
+    /// Orphans the transactions of a partly written block that the node replaced at its
+    /// height and reverts their effect on UTXOs and address balances.
+    async fn discard_partial_block(
+        &self,
+        db_tx: &mut S::Tx,
+        stale: &BlockWriteProgressRecord,
+    ) -> Result<(), sqlx::Error> {
+        let conn = postgres(db_tx, "chunked block writes")?;
+        warn!(
+            component = "indexer",
+            block_height = stale.height,
+            block_hash = %stale.hash,
+            committed_txs = stale.next_tx,
+            message = "discarding partly written block replaced by the node"
+        );
+        BlockWriteProgressRepo.discard(conn, stale).await
+    }
+
+    async fn save_checkpoint(&self, db_tx: &mut S::Tx, block: &RpcBlock) -> Result<(), sqlx::Error> {
+        let Some(job_id) = self.checkpoint_job.filter(|_| self.schema.job_checkpoints) else {
+            return Ok(());
+        };
+
+        observe_db_write(&self.metrics, "jobs", db_tx.save_checkpoint(job_id, block.height, &block.hash)).await
+    }
+
+    /// Builds the filter from the output scripts of the block and the scripts it spends.
+    /// Every spent output is looked up in `tx_outputs`, which by now also holds the ones
+    /// created earlier in the block; an unknown one leaves the filter incomplete.
+    async fn save_block_filter(&self, conn: &mut PgConnection, block: &RpcBlock) -> Result<(), sqlx::Error> {
+        let filters = BlockFiltersRepo;
+        let spent: Vec<(String, i32)> = block
+            .tx
+            .iter()
+            .flat_map(|tx| &tx.vin)
+            .filter_map(|vin| Some((vin.txid.clone()?, vin.vout?)))
+            .collect();
+        let spent_scripts = filters.spent_scripts(&mut *conn, &spent).await?;
+        let complete = spent.iter().all(|outpoint| spent_scripts.contains_key(outpoint));
+
+        let scripts: Vec<Vec<u8>> = block
+            .tx
+            .iter()
+            .flat_map(|tx| &tx.vout)
+            .map(|vout| vout.script_pub_key.hex.as_str())
+            .chain(spent_scripts.values().map(String::as_str))
+            .filter_map(|script_hex| hex::decode(script_hex).ok())
+            .collect();
+        let filter = basic_filter(&block.hash, scripts.iter().map(Vec::as_slice))
+            .ok_or_else(|| sqlx::Error::Protocol(format!("block hash {} is not valid hex", block.hash)))?;
+        let header = match block.prev_hash.as_deref() {
+            None => filter_header(&filter, None),
+            Some(prev_hash) => filters
+                .header(&mut *conn, prev_hash)
+                .await?
+                .and_then(|previous| filter_header(&filter, Some(&previous))),
+        };
+
+        filters
+            .upsert(&mut *conn, &BlockFilterRecord {
+                hash: block.hash.clone(),
+                height: block.height,
+                filter,
+                header,
+                complete,
+            })
+            .await
     }
 }
 
@@ -238,34 +949,395 @@ pub enum IndexerError {
     Rpc(#[from] crate::modules::rpc::RpcError),
     #[error("storage error: {0}")]
     Storage(#[from] sqlx::Error),
+    #[error("block {hash} is not the indexed canonical block at height {height}")]
+    NotCanonical { height: i32, hash: String },
+    #[error("hook {hook} failed on block at height {height}: {source}")]
+    Hook {
+        hook: String,
+        height: i32,
+        #[source]
+        source: HookError,
+    },
+    #[error("hook {hook} vetoed block at height {height}: {reason}")]
+    Vetoed { hook: String, height: i32, reason: String },
 }
 
+/// Fetches blocks through `R` and persists them to `S`; [`RpcClient`] and the PostgreSQL
+/// pool in production, a fixture implementation of [`BitcoinRpc`] in tests.
 #[derive(Clone)]
-pub struct IndexerService {
-    rpc: crate::modules::rpc::RpcClient,
-    pool: PgPool,
+pub struct IndexerService<R = RpcClient, S = PgPool> {
+    rpc: R,
+    pool: S,
     metrics: MetricsService,
+    schema: SchemaFeatures,
+    encoding: AddressEncoding,
+    chain: Chain,
+    outbox: bool,
+    store_raw_blocks: bool,
+    store_decoded: bool,
+    store_witness: bool,
+    block_filters: bool,
+    writers: WriterPool,
+    txs_per_batch: Option<usize>,
+    prevout_rpc_fallback: bool,
+    protocols: Vec<Arc<dyn ProtocolIndexer>>,
+    hooks: HookRegistry,
 }
 
-impl IndexerService {
-    pub fn new(rpc: crate::modules::rpc::RpcClient, pool: PgPool, metrics: MetricsService) -> Self {
-        Self { rpc, pool, metrics }
+impl<R: BitcoinRpc, S: ChainStore> IndexerService<R, S> {
+    pub fn new(rpc: R, pool: S, metrics: MetricsService) -> Self {
+        Self {
+            rpc,
+            pool,
+            metrics,
+            schema: SchemaFeatures::latest(),
+            encoding: AddressEncoding::Bitcoin(bitcoin::Network::Bitcoin),
+            chain: Chain::Bitcoin,
+            outbox: false,
+            store_raw_blocks: false,
+            store_decoded: true,
+            store_witness: false,
+            block_filters: false,
+            writers: WriterPool::default(),
+            txs_per_batch: None,
+            prevout_rpc_fallback: false,
+            protocols: Vec::new(),
+            hooks: HookRegistry::default(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn with_network(mut self, encoding: impl Into<AddressEncoding>) -> Self {
+        self.encoding = encoding.into();
+        self
+    }
+
+    /// See [`IndexerPipeline::with_tx_chunks`]; `indexer.batching.txs_per_batch` in production.
+    pub fn with_txs_per_batch(mut self, txs_per_batch: u32) -> Self {
+        self.txs_per_batch = usize::try_from(txs_per_batch).ok();
+        self
+    }
+
+    /// Writer slots block writes wait for; one slot by default.
+    pub fn with_writer_pool(mut self, writers: WriterPool) -> Self {
+        self.writers = writers;
+        self
+    }
+
+    pub fn writer_pool(&self) -> &WriterPool {
+        &self.writers
+    }
+
+    /// Same service fetching through `rpc`, e.g. a client throttled for one job.
+    pub fn with_rpc(mut self, rpc: R) -> Self {
+        self.rpc = rpc;
+        self
+    }
+
+    /// Chain of the node, which decides how blocks are fetched; Bitcoin by default.
+    pub fn with_chain(mut self, chain: Chain) -> Self {
+        self.chain = chain;
+        self
+    }
+
+    /// Queues sink events for persisted and orphaned blocks, see [`IndexerPipeline::with_outbox`].
+    pub fn with_outbox(mut self, enabled: bool) -> Self {
+        self.outbox = enabled;
+        self
+    }
+
+    /// Also fetches `getblock <hash> 0` and stores the serialized block, see
+    /// [`IndexerPipeline::with_raw_block`]. Off by default.
+    pub fn with_raw_blocks(mut self, enabled: bool) -> Self {
+        self.store_raw_blocks = enabled;
+        self
+    }
+
+    /// See [`IndexerPipeline::with_decoded_tx`].
+    pub fn with_decoded_tx(mut self, enabled: bool) -> Self {
+        self.store_decoded = enabled;
+        self
+    }
+
+    /// See [`IndexerPipeline::with_witness`].
+    pub fn with_witness(mut self, enabled: bool) -> Self {
+        self.store_witness = enabled;
+        self
+    }
+
+    /// See [`IndexerPipeline::with_block_filter`]. Off by default.
+    pub fn with_block_filters(mut self, enabled: bool) -> Self {
+        self.block_filters = enabled;
+        self
+    }
+
+    /// Fetches the transactions of spent outputs that are neither stored nor in the block
+    /// JSON with `getrawtransaction` before a block is written, see [`Self::fetch_prevouts`].
+    /// Off by default.
+    pub fn with_prevout_rpc_fallback(mut self, enabled: bool) -> Self {
+        self.prevout_rpc_fallback = enabled;
+        self
+    }
+
+    /// See [`IndexerPipeline::with_protocols`]; jobs enable them by name, see
+    /// [`protocols::enabled`].
+    pub fn with_protocols(mut self, protocols: Vec<Arc<dyn ProtocolIndexer>>) -> Self {
+        self.protocols = protocols;
+        self
+    }
+
+    /// See [`IndexerPipeline::with_hooks`]; applied to every block the service writes.
+    pub fn with_hooks(mut self, hooks: HookRegistry) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Encoding that output addresses are decoded with, see [`RpcScriptPubKey::resolve`].
+    pub fn address_encoding(&self) -> AddressEncoding {
+        self.encoding
+    }
+
+    /// Fetches and decodes the block at `height` the way indexing does, without storing it.
+    pub async fn fetch_height(&self, height: u32) -> Result<RpcBlock, IndexerError> {
+        let hash = self.rpc.get_block_hash(height).await?;
+        let (block, _) = self.fetch_block(&hash).await?;
+        Ok(block)
+    }
+
+    /// The block with its serialized bytes when raw blocks are stored.
+    async fn fetch_block(&self, hash: &str) -> Result<(RpcBlock, Option<Vec<u8>>), IndexerError> {
+        let mut block_hex = None;
+        let block = match self.chain.block_source() {
+            BlockSource::Verbose3 => self.rpc.get_block_verbose3(hash).await?,
+            BlockSource::Verbose2 => self.rpc.get_block_verbose2(hash).await?,
+            BlockSource::RawHex => {
+                let header = self.rpc.get_block_header(hash).await?;
+                let hex = self.rpc.get_block_hex(hash).await?;
+                let tx = decode_block_transactions(&hex, self.encoding)
+                    .map_err(crate::modules::rpc::RpcError::Decode)?;
+                block_hex = Some(hex);
+                header.into_block(tx)
+            }
+        };
+        if !self.store_raw_blocks || !self.schema.block_raw {
+            return Ok((block, None));
+        }
+
+        let block_hex = match block_hex {
+            Some(hex) => hex,
+            None => self.rpc.get_block_hex(hash).await?,
+        };
+        let raw = hex::decode(&block_hex).map_err(|err| {
+            crate::modules::rpc::RpcError::Decode(format!("getblock returned invalid block hex: {err}"))
+        })?;
+        Ok((block, Some(raw)))
+    }
+
+    /// Outputs spent by `block` that are not in its JSON, not created by the block and not
+    /// stored, from `getrawtransaction`. Confirmed transactions need `-txindex` on the node;
+    /// outputs the node does not know stay unresolved.
+    async fn fetch_prevouts(&self, block: &RpcBlock) -> Result<HashMap<(String, i32), PrevoutRecord>, IndexerError> {
+        let mut prevouts = HashMap::new();
+        if !self.prevout_rpc_fallback || !self.schema.input_prevouts {
+            return Ok(prevouts);
+        }
+
+        let block_txids: HashSet<&str> = block.tx.iter().map(|tx| tx.txid.as_str()).collect();
+        let mut wanted: HashSet<(String, i32)> = block
+            .tx
+            .iter()
+            .flat_map(|tx| &tx.vin)
+            .filter(|vin| vin.prevout.as_ref().is_none_or(|prevout| prevout.script_pub_key.is_none()))
+            .filter_map(|vin| Some((vin.txid.clone()?, vin.vout?)))
+            .filter(|(txid, _)| !block_txids.contains(txid.as_str()))
+            .collect();
+        if wanted.is_empty() {
+            return Ok(prevouts);
+        }
+
+        let wanted_outpoints: Vec<(String, i32)> = wanted.iter().cloned().collect();
+        let stored = self.pool.stored_outpoints(&wanted_outpoints).await?;
+        for outpoint in stored {
+            wanted.remove(&outpoint);
+        }
+
+        let prev_txids: HashSet<&str> = wanted.iter().map(|(txid, _)| txid.as_str()).collect();
+        for prev_txid in prev_txids {
+            let prev_tx = match self.rpc.get_raw_transaction_verbose(prev_txid).await {
+                Ok(tx) => tx,
+                Err(crate::modules::rpc::RpcError::NotFound(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            for vout in prev_tx.vout {
+                let outpoint = (prev_txid.to_string(), vout.n);
+                if wanted.contains(&outpoint) {
+                    let (script_type, address) = vout.script_pub_key.resolve(self.encoding);
+                    prevouts.insert(outpoint, PrevoutRecord {
+                        value_sats: vout.value_sats,
+                        script_type,
+                        address,
+                    });
+                }
+            }
+        }
+
+        Ok(prevouts)
     }
 
     pub async fn has_canonical_block(&self, height: i32) -> Result<bool, IndexerError> {
-        Ok(canonical_block_hash_at_height(&self.pool, height).await?.is_some())
+        Ok(self.pool.canonical_block_hash_at_height(height).await?.is_some())
     }
 
     pub async fn index_height(&self, height: u32) -> Result<IndexHeightResult, IndexerError> {
-        let hash = self.rpc.get_block_hash(height).await?;
-        let block = self.rpc.get_block_verbose2(&hash).await?;
+        self.index_height_from(height, 0).await
+    }
+
+    pub async fn index_height_from(&self, height: u32, start_height: i32) -> Result<IndexHeightResult, IndexerError> {
+        self.index_height_for_job(height, start_height, None).await
+    }
+
+    /// Same as [`Self::index_height_from`], moving the checkpoint of `job_id` along with
+    /// the block, see [`IndexerPipeline::with_checkpoint`].
+    pub async fn index_height_for_job(
+        &self,
+        height: u32,
+        start_height: i32,
+        job_id: Option<&str>,
+    ) -> Result<IndexHeightResult, IndexerError> {
+        let span = info_span!("index_block", block_height = height, job_id);
+        async {
+            let fetched = self.fetch_for_index(height).await?;
+            self.persist_fetched(fetched, start_height, job_id).await
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Fetches the block at `height` for [`Self::persist_fetched`], so a job can fetch
+    /// the next blocks while earlier ones are written.
+    pub async fn fetch_for_index(&self, height: u32) -> Result<FetchedBlock, IndexerError> {
+        async {
+            let hash = self.rpc.get_block_hash(height).await?;
+            let (block, raw) = self.fetch_block(&hash).await?;
+            Ok(FetchedBlock { block, raw })
+        }
+        .instrument(info_span!("fetch_block", block_height = height))
+        .await
+    }
+
+    /// Persists a block of [`Self::fetch_for_index`] once a writer slot is free.
+    pub async fn persist_fetched(
+        &self,
+        fetched: FetchedBlock,
+        start_height: i32,
+        job_id: Option<&str>,
+    ) -> Result<IndexHeightResult, IndexerError> {
+        let FetchedBlock { block, raw } = fetched;
         let tx_count = block.tx.len() as u64;
 
-        let pipeline = IndexerPipeline::new(&self.pool, self.metrics.clone());
-        let outcome = pipeline.persist_block(&block).await?;
+        let _slot = self.writers.acquire().await;
+        // Looked up once the block below is written, since it may create the spent outputs.
+        let prevouts = self.fetch_prevouts(&block).await?;
+        let pipeline = IndexerPipeline::new(&self.pool, self.metrics.clone())
+            .with_schema_features(self.schema)
+            .with_network(self.encoding)
+            .with_outbox(self.outbox)
+            .with_checkpoint(job_id)
+            .with_raw_block(raw.as_deref())
+            .with_decoded_tx(self.store_decoded)
+            .with_witness(self.store_witness)
+            .with_block_filter(self.block_filters)
+            .with_tx_chunks(self.txs_per_batch)
+            .with_prevouts(Some(&prevouts))
+            .with_protocols(self.protocols.clone())
+            .with_hooks(self.hooks.clone());
+        let outcome = pipeline.persist_block_from(&block, start_height).await?;
         Ok(IndexHeightResult { outcome, tx_count })
     }
 
+}
+
+/// Reindexing and reorg handling, which rewrite stored chain state with queries of their own.
+impl<R: BitcoinRpc> IndexerService<R, PgPool> {
+    /// Re-indexes the block with `hash` as if a reorg happened at its height: it and
+    /// every block above are orphaned, derived state is rebuilt, and the block is
+    /// fetched again. Jobs have to re-index the heights above it afterwards.
+    /// Returns the height of the block.
+    pub async fn reindex_block(&self, hash: &str) -> Result<i32, IndexerError> {
+        let (block, raw) = self.fetch_block(hash).await?;
+        let height = u32::try_from(block.height)
+            .map_err(|_| sqlx::Error::Protocol(format!("block {hash} has invalid height {}", block.height)))?;
+        if self.rpc.get_block_hash(height).await? != block.hash {
+            return Err(sqlx::Error::Protocol(format!("block {hash} is not in the node's active chain")).into());
+        }
+        if !self.has_canonical_block(block.height).await? {
+            return Err(sqlx::Error::Protocol(format!("height {height} is not indexed yet")).into());
+        }
+
+        self.apply_reorg(block.height).await?;
+
+        let prevouts = self.fetch_prevouts(&block).await?;
+        let pipeline = IndexerPipeline::new(&self.pool, self.metrics.clone())
+            .with_schema_features(self.schema)
+            .with_network(self.encoding)
+            .with_outbox(self.outbox)
+            .with_raw_block(raw.as_deref())
+            .with_decoded_tx(self.store_decoded)
+            .with_witness(self.store_witness)
+            .with_block_filter(self.block_filters)
+            .with_tx_chunks(self.txs_per_batch)
+            .with_prevouts(Some(&prevouts))
+            .with_protocols(self.protocols.clone())
+            .with_hooks(self.hooks.clone());
+        pipeline.persist_block_from(&block, block.height).await?;
+        Ok(block.height)
+    }
+
+    /// Refetches the blocks at `from_height..=to_height` and rewrites their rows, see
+    /// [`IndexerPipeline::rewrite_block`], then rebuilds UTXOs and address balances. Each
+    /// height must hold the block the node has there. Returns the transactions written.
+    pub async fn rewrite_range(&self, from_height: i32, to_height: i32) -> Result<u64, IndexerError> {
+        let mut tx_count = 0;
+        for height in from_height..=to_height {
+            let hash = self.rpc.get_block_hash(height as u32).await?;
+            if canonical_block_hash_at_height(&self.pool, height).await?.as_deref() != Some(hash.as_str()) {
+                return Err(IndexerError::NotCanonical { height, hash });
+            }
+
+            let (block, raw) = self.fetch_block(&hash).await?;
+            let prevouts = self.fetch_prevouts(&block).await?;
+            IndexerPipeline::new(&self.pool, self.metrics.clone())
+                .with_schema_features(self.schema)
+                .with_network(self.encoding)
+                .with_raw_block(raw.as_deref())
+                .with_decoded_tx(self.store_decoded)
+                .with_witness(self.store_witness)
+                .with_block_filter(self.block_filters)
+                .with_prevouts(Some(&prevouts))
+                .with_hooks(self.hooks.clone())
+                .rewrite_block(&block)
+                .await?;
+            tx_count += block.tx.len() as u64;
+        }
+
+        self.rebuild_address_index().await?;
+        Ok(tx_count)
+    }
+
+    /// Rebuilds UTXOs and address balances from the canonical blocks, after their rows
+    /// were rewritten.
+    pub async fn rebuild_address_index(&self) -> Result<(), IndexerError> {
+        let mut db_tx = self.pool.begin().await?;
+        acquire_chain_state_lock(&mut *db_tx).await?;
+        rebuild_address_index(&mut db_tx, self.schema).await?;
+        db_tx.commit().await?;
+        Ok(())
+    }
+
     pub async fn reconcile_chain(&self, reorg_depth: u32) -> Result<Option<i32>, IndexerError> {
         let Some(db_tip) = canonical_tip_height(&self.pool).await? else {
             return Ok(None);
@@ -293,64 +1365,151 @@ impl IndexerService {
         Ok(None)
     }
 
+    /// Like [`Self::reconcile_chain`], but walks down from `height` for as long as the
+    /// indexed blocks differ from the node, so a reorg deeper than `reorg_depth` that
+    /// happened while the indexer was down is found too. Returns the divergence height.
+    pub async fn reconcile_below(&self, height: i32) -> Result<Option<i32>, IndexerError> {
+        let node_tip = i32::try_from(self.rpc.get_block_count().await?)
+            .map_err(|_| sqlx::Error::Protocol("node tip exceeds i32 range".into()))?;
+
+        let mut divergence_height = None;
+        for height in (0..=height).rev() {
+            let Some(db_hash) = canonical_block_hash_at_height(&self.pool, height).await? else {
+                break;
+            };
+            if height <= node_tip && self.rpc.get_block_hash(height as u32).await? == db_hash {
+                break;
+            }
+            divergence_height = Some(height);
+        }
+
+        if let Some(height) = divergence_height {
+            self.metrics.increment_error("reorg");
+            self.apply_reorg(height).await?;
+        }
+        Ok(divergence_height)
+    }
+
     async fn apply_reorg(&self, divergence_height: i32) -> Result<(), IndexerError> {
         let mut db_tx = self.pool.begin().await?;
         acquire_chain_state_lock(&mut *db_tx).await?;
 
-        sqlx::query(
-            "UPDATE blocks \
-             SET status = 'orphaned' \
-             WHERE height >= $1 AND status = 'canonical'",
+        // Orphaned blocks are kept with the transactions they confirmed, which a re-confirmation
+        // elsewhere would otherwise overwrite, for `GET /v1/blocks/{height}/forks`.
+        let mut orphaned: Vec<(i32, String)> = sqlx::query_as(
+            "UPDATE blocks b \
+             SET status = 'orphaned', \
+                 meta = b.meta || jsonb_build_object( \
+                   'orphaned_at', NOW(), \
+                   'txids', COALESCE( \
+                     (SELECT jsonb_agg(t.txid ORDER BY t.position_in_block) FROM transactions t WHERE t.block_hash = b.hash), \
+                     '[]'::JSONB \
+                   ) \
+                 ) \
+             WHERE height >= $1 AND status = 'canonical' \
+             RETURNING height, hash",
         )
         .bind(divergence_height)
-        .execute(&mut *db_tx)
+        .fetch_all(&mut *db_tx)
         .await?;
 
+        // Tip first, the order a consumer unwinds them in.
+        orphaned.sort_by_key(|(height, _)| std::cmp::Reverse(*height));
+        if self.schema.stream_events && !orphaned.is_empty() {
+            let orphaned: Vec<Value> = orphaned
+                .iter()
+                .map(|(height, hash)| serde_json::json!({ "height": height, "hash": hash }))
+                .collect();
+            let payload = serde_json::json!({ "divergence_height": divergence_height, "orphaned": orphaned });
+            StreamEventsRepo::new(&self.pool)
+                .insert(&mut *db_tx, "reorg", &payload)
+                .await?;
+        }
+
+        if self.outbox && self.schema.event_outbox {
+            let outbox = OutboxRepo::new(&self.pool);
+            for (height, hash) in orphaned {
+                let event = OutboxEventRecord {
+                    stream: "blocks",
+                    event_key: hash.clone(),
+                    payload: serde_json::json!({ "type": "block_disconnected", "height": height, "hash": hash }),
+                };
+                outbox.insert(&mut *db_tx, &event).await?;
+            }
+        }
+
         sqlx::query(
             "UPDATE transactions \
              SET status = 'orphaned' \
-             WHERE block_height >= $1 AND status = 'confirmed'",
+             WHERE block_height >= $1 AND status IN ('confirmed', 'incomplete')",
         )
         .bind(divergence_height)
         .execute(&mut *db_tx)
         .await?;
 
-        sqlx::query("DELETE FROM utxos_current")
-            .execute(&mut *db_tx)
-            .await?;
-        sqlx::query("DELETE FROM address_balance_current")
-            .execute(&mut *db_tx)
-            .await?;
-        sqlx::query("DELETE FROM address_balance_history")
-            .execute(&mut *db_tx)
-            .await?;
+        if self.schema.block_write_progress {
+            BlockWriteProgressRepo::new(&self.pool)
+                .delete_from(&mut *db_tx, divergence_height)
+                .await?;
+        }
 
-        let canonical_blocks: Vec<CanonicalBlockRow> = sqlx::query_as(
-            "SELECT height, hash, time \
-             FROM blocks \
-             WHERE status = 'canonical' \
-             ORDER BY height ASC",
-        )
-        .fetch_all(&mut *db_tx)
+        if self.schema.supply_stats {
+            supply::forget_from(&mut db_tx, divergence_height).await?;
+        }
+        protocols::rollback_all(&mut db_tx, self.schema, divergence_height).await?;
+        rebuild_address_index(&mut db_tx, self.schema).await?;
+        db_tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Rebuilds UTXOs, address balances and UTXO ages from the transactions of canonical blocks,
+/// and records the supply of blocks written out of height order.
+async fn rebuild_address_index(conn: &mut PgConnection, schema: SchemaFeatures) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM utxos_current")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM address_balance_current")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("DELETE FROM address_balance_history")
+        .execute(&mut *conn)
         .await?;
 
-        for block in canonical_blocks {
-            let txs: Vec<CanonicalTxRow> = sqlx::query_as(
-                "SELECT txid, position_in_block \
-                 FROM transactions \
-                 WHERE block_height = $1 AND status = 'confirmed' \
-                 ORDER BY position_in_block ASC, txid ASC",
-            )
-            .bind(block.height)
-            .fetch_all(&mut *db_tx)
-            .await?;
+    let canonical_blocks: Vec<CanonicalBlockRow> = sqlx::query_as(
+        "SELECT height, time \
+         FROM blocks \
+         WHERE status = 'canonical' \
+         ORDER BY height ASC",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
 
-            replay_canonical_block(&mut *db_tx, &block, &txs).await?;
-        }
+    for block in canonical_blocks {
+        let txs: Vec<CanonicalTxRow> = sqlx::query_as(
+            "SELECT txid \
+             FROM transactions \
+             WHERE block_height = $1 AND status = 'confirmed' \
+             ORDER BY position_in_block ASC, txid ASC",
+        )
+        .bind(block.height)
+        .fetch_all(&mut *conn)
+        .await?;
 
-        db_tx.commit().await?;
-        Ok(())
+        replay_canonical_block(conn, &block, &txs).await?;
     }
+
+    if schema.supply_stats {
+        supply::reconcile(conn).await?;
+    }
+
+    Ok(())
+}
+
+/// Block fetched ahead of its write, with its serialized bytes when raw blocks are stored.
+pub struct FetchedBlock {
+    pub block: RpcBlock,
+    raw: Option<Vec<u8>>,
 }
 
 pub struct IndexHeightResult {
@@ -361,14 +1520,12 @@ pub struct IndexHeightResult {
 #[derive(Debug, FromRow)]
 struct CanonicalBlockRow {
     height: i32,
-    hash: String,
     time: i64,
 }
 
 #[derive(Debug, FromRow)]
 struct CanonicalTxRow {
     txid: String,
-    position_in_block: i32,
 }
 
 #[derive(Debug, FromRow)]
@@ -518,62 +1675,292 @@ async fn replay_canonical_block(
     Ok(())
 }
 
-async fn canonical_tip_height(pool: &PgPool) -> Result<Option<i32>, sqlx::Error> {
-    sqlx::query_scalar(
-        "SELECT MAX(height) \
-         FROM blocks \
-         WHERE status = 'canonical'",
+/// Settles UTXOs and balances after the block at `height` was written below canonical
+/// blocks stored before it, as by a `height_range` job or when gaps are healed. Those
+/// blocks were written without it, so the outputs of the block they spend are still
+/// unspent and the balance snapshots above it lack its deltas. Only the addresses the
+/// block moves and the snapshots from its height up are touched.
+async fn settle_block_below_tip(conn: &mut PgConnection, height: i32, hash: &str, time: i64) -> Result<(), sqlx::Error> {
+    let above: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM blocks WHERE status = 'canonical' AND height > $1)")
+            .bind(height)
+            .fetch_one(&mut *conn)
+            .await?;
+    if !above {
+        return Ok(());
+    }
+
+    // The snapshot of the block was taken from the current balance, which already counts
+    // the blocks above; it is the snapshot below plus the deltas of the block instead.
+    sqlx::query(
+        "WITH moved AS (
+           SELECT o.address, o.value_sats AS delta
+           FROM transactions t
+           JOIN tx_outputs o ON o.txid = t.txid
+           WHERE t.block_height = $1 AND t.block_hash = $2 AND t.status = 'confirmed' AND o.address IS NOT NULL
+           UNION ALL
+           SELECT o.address, -o.value_sats
+           FROM transactions t
+           JOIN tx_inputs i ON i.txid = t.txid
+           JOIN tx_outputs o ON o.txid = i.prev_txid AND o.vout = i.prev_vout
+           JOIN transactions pt ON pt.txid = o.txid AND pt.status = 'confirmed'
+           WHERE t.block_height = $1 AND t.block_hash = $2 AND t.status = 'confirmed' AND o.address IS NOT NULL
+         ),
+         deltas AS (
+           SELECT address, SUM(delta)::BIGINT AS delta FROM moved GROUP BY address
+         ),
+         shifted AS (
+           UPDATE address_balance_history h
+           SET balance_sats = h.balance_sats + d.delta
+           FROM deltas d
+           WHERE h.address = d.address AND h.block_height > $1 AND d.delta <> 0
+         )
+         INSERT INTO address_balance_history (address, block_height, time, balance_sats)
+         SELECT d.address, $1, $3,
+                COALESCE((
+                  SELECT p.balance_sats
+                  FROM address_balance_history p
+                  WHERE p.address = d.address AND p.block_height < $1
+                  ORDER BY p.block_height DESC
+                  LIMIT 1
+                ), 0) + d.delta
+         FROM deltas d
+         ON CONFLICT (address, block_height) DO UPDATE SET
+           time = EXCLUDED.time,
+           balance_sats = EXCLUDED.balance_sats",
     )
-    .fetch_one(pool)
-    .await
-}
+    .bind(height)
+    .bind(hash)
+    .bind(time)
+    .execute(&mut *conn)
+    .await?;
 
-async fn acquire_chain_state_lock<'e, E>(executor: E) -> Result<(), sqlx::Error>
-where
-    E: Executor<'e, Database = Postgres>,
-{
-    sqlx::query("SELECT pg_advisory_xact_lock($1)")
-        .bind(CHAIN_STATE_LOCK_KEY)
-        .execute(executor)
-        .await?;
+    // Outputs of the block spent by blocks above it, by the first spend.
+    let spends: Vec<(String, i32, i64)> = sqlx::query_as(
+        "WITH spends AS (
+           SELECT DISTINCT ON (u.out_txid, u.out_vout)
+                  u.out_txid, u.out_vout, u.address, u.value_sats, i.txid AS spent_in_txid, st.block_height
+           FROM transactions t
+           JOIN utxos_current u ON u.out_txid = t.txid AND u.status = 'unspent'
+           JOIN tx_inputs i ON i.prev_txid = u.out_txid AND i.prev_vout = u.out_vout
+           JOIN transactions st ON st.txid = i.txid AND st.status = 'confirmed' AND st.block_height > $1
+           WHERE t.block_height = $1 AND t.block_hash = $2 AND t.status = 'confirmed'
+           ORDER BY u.out_txid, u.out_vout, st.block_height
+         ),
+         spent AS (
+           UPDATE utxos_current u
+           SET spent_in_txid = s.spent_in_txid, status = 'spent'
+           FROM spends s
+           WHERE u.out_txid = s.out_txid AND u.out_vout = s.out_vout
+         )
+         SELECT address, block_height, SUM(value_sats)::BIGINT
+         FROM spends
+         GROUP BY address, block_height",
+    )
+    .bind(height)
+    .bind(hash)
+    .fetch_all(&mut *conn)
+    .await?;
+    if spends.is_empty() {
+        return Ok(());
+    }
+
+    let mut addresses = Vec::with_capacity(spends.len());
+    let mut heights = Vec::with_capacity(spends.len());
+    let mut values = Vec::with_capacity(spends.len());
+    for (address, spent_height, value_sats) in spends {
+        addresses.push(address);
+        heights.push(spent_height);
+        values.push(value_sats);
+    }
+
+    // Spending blocks without a snapshot of the address get one with the balance before
+    // them, and every snapshot from a spend up loses its value.
+    sqlx::query(
+        "INSERT INTO address_balance_history (address, block_height, time, balance_sats)
+         SELECT s.address, s.height, b.time,
+                COALESCE((
+                  SELECT p.balance_sats
+                  FROM address_balance_history p
+                  WHERE p.address = s.address AND p.block_height < s.height
+                  ORDER BY p.block_height DESC
+                  LIMIT 1
+                ), 0)
+         FROM UNNEST($1::TEXT[], $2::INT[]) AS s(address, height)
+         JOIN blocks b ON b.height = s.height AND b.status = 'canonical'
+         ON CONFLICT (address, block_height) DO NOTHING",
+    )
+    .bind(&addresses)
+    .bind(&heights)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE address_balance_history h
+         SET balance_sats = h.balance_sats - s.spent_sats
+         FROM (
+           SELECT p.address, p.block_height, SUM(s.value_sats)::BIGINT AS spent_sats
+           FROM UNNEST($1::TEXT[], $2::INT[], $3::BIGINT[]) AS s(address, height, value_sats)
+           JOIN address_balance_history p ON p.address = s.address AND p.block_height >= s.height
+           GROUP BY p.address, p.block_height
+         ) s
+         WHERE h.address = s.address AND h.block_height = s.block_height",
+    )
+    .bind(&addresses)
+    .bind(&heights)
+    .bind(&values)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        "UPDATE address_balance_current c
+         SET balance_sats = c.balance_sats - s.spent_sats, updated_at = NOW()
+         FROM (
+           SELECT address, SUM(value_sats)::BIGINT AS spent_sats
+           FROM UNNEST($1::TEXT[], $2::BIGINT[]) AS s(address, value_sats)
+           GROUP BY address
+         ) s
+         WHERE c.address = s.address",
+    )
+    .bind(&addresses)
+    .bind(&values)
+    .execute(&mut *conn)
+    .await?;
 
     Ok(())
 }
 
-async fn acquire_height_lock<'e, E>(executor: E, height: i32) -> Result<(), sqlx::Error>
-where
-    E: Executor<'e, Database = Postgres>,
-{
-    sqlx::query("SELECT pg_advisory_xact_lock($1)")
-        .bind(i64::from(height))
-        .execute(executor)
+/// Rebuilds `utxos_current`, `address_balance_current` and `address_balance_history`
+/// for a single address from already stored canonical transactions, without RPC calls.
+/// Returns the number of balance history snapshots written.
+pub async fn backfill_address(pool: &PgPool, address: &str) -> Result<u64, sqlx::Error> {
+    let mut db_tx = pool.begin().await?;
+    acquire_chain_state_lock(&mut *db_tx).await?;
+
+    sqlx::query("DELETE FROM utxos_current WHERE address = $1")
+        .bind(address)
+        .execute(&mut *db_tx)
+        .await?;
+    sqlx::query("DELETE FROM address_balance_current WHERE address = $1")
+        .bind(address)
+        .execute(&mut *db_tx)
+        .await?;
+    sqlx::query("DELETE FROM address_balance_history WHERE address = $1")
+        .bind(address)
+        .execute(&mut *db_tx)
         .await?;
 
-    Ok(())
-}
+    sqlx::query(
+        "INSERT INTO utxos_current \
+         (out_txid, out_vout, address, value_sats, created_in_txid, spent_in_txid, status) \
+         SELECT o.txid, o.vout, o.address, o.value_sats, o.txid, spend.txid, \
+                CASE WHEN spend.txid IS NULL THEN 'unspent' ELSE 'spent' END \
+         FROM tx_outputs o \
+         JOIN transactions t ON t.txid = o.txid AND t.status = 'confirmed' \
+         LEFT JOIN LATERAL ( \
+             SELECT i.txid \
+             FROM tx_inputs i \
+             JOIN transactions st ON st.txid = i.txid AND st.status = 'confirmed' \
+             WHERE i.prev_txid = o.txid AND i.prev_vout = o.vout \
+             ORDER BY st.block_height ASC \
+             LIMIT 1 \
+         ) spend ON TRUE \
+         WHERE o.address = $1",
+    )
+    .bind(address)
+    .execute(&mut *db_tx)
+    .await?;
 
-async fn canonical_block_hash_at_height<'e, E>(
-    executor: E,
-    height: i32,
-) -> Result<Option<String>, sqlx::Error>
-where
-    E: Executor<'e, Database = Postgres>,
-{
-    let row = sqlx::query(
-        "SELECT hash \
-         FROM blocks \
-         WHERE height = $1 AND status = 'canonical' \
+    let snapshots = sqlx::query(
+        "WITH deltas AS ( \
+             SELECT t.block_height AS height, o.value_sats AS delta \
+             FROM tx_outputs o \
+             JOIN transactions t ON t.txid = o.txid AND t.status = 'confirmed' \
+             WHERE o.address = $1 \
+             UNION ALL \
+             SELECT st.block_height AS height, -o.value_sats AS delta \
+             FROM tx_inputs i \
+             JOIN transactions st ON st.txid = i.txid AND st.status = 'confirmed' \
+             JOIN tx_outputs o ON o.txid = i.prev_txid AND o.vout = i.prev_vout \
+             JOIN transactions t ON t.txid = o.txid AND t.status = 'confirmed' \
+             WHERE o.address = $1 \
+         ), per_height AS ( \
+             SELECT height, SUM(delta) AS delta \
+             FROM deltas \
+             GROUP BY height \
+         ) \
+         INSERT INTO address_balance_history (address, block_height, time, balance_sats) \
+         SELECT $1, p.height, b.time, (SUM(p.delta) OVER (ORDER BY p.height))::BIGINT \
+         FROM per_height p \
+         JOIN blocks b ON b.height = p.height AND b.status = 'canonical'",
+    )
+    .bind(address)
+    .execute(&mut *db_tx)
+    .await?
+    .rows_affected();
+
+    sqlx::query(
+        "INSERT INTO address_balance_current (address, balance_sats, updated_at) \
+         SELECT address, balance_sats, NOW() \
+         FROM address_balance_history \
+         WHERE address = $1 \
+         ORDER BY block_height DESC \
          LIMIT 1",
     )
-    .bind(height)
-    .fetch_optional(executor)
+    .bind(address)
+    .execute(&mut *db_tx)
     .await?;
 
-    Ok(row.map(|row| row.get::<String, _>("hash")))
+    db_tx.commit().await?;
+    Ok(snapshots)
 }
 
-fn btc_to_sats(value: f64) -> i64 {
-    (value * 100_000_000.0).round() as i64
+async fn canonical_tip_height(pool: &PgPool) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT MAX(height) \
+         FROM blocks \
+         WHERE status = 'canonical'",
+    )
+    .fetch_one(pool)
+    .await
+}
+
+/// Connection of `db_tx` for a pipeline stage only PostgreSQL has. Other stores report
+/// schema features without these stages, so this fails only when they are forced on.
+fn postgres<'c>(db_tx: &'c mut impl ChainStoreTx, stage: &str) -> Result<&'c mut PgConnection, sqlx::Error> {
+    db_tx
+        .postgres()
+        .ok_or_else(|| sqlx::Error::Protocol(format!("PostgreSQL storage is needed for {stage}")))
+}
+
+/// Fee of a non-coinbase transaction. Prefers the `fee` reported by the node, then
+/// verbosity-3 `prevout` values, then indexed outputs, which also covers spends of
+/// outputs created earlier in the same block. `None` when any spent output is unknown,
+/// e.g. below the start height of a `height_range` job on a node without undo data.
+async fn transaction_fee_sats(
+    db_tx: &mut impl ChainStoreTx,
+    tx: &RpcTransaction,
+) -> Result<Option<i64>, sqlx::Error> {
+    if let Some(fee_sats) = tx.fee_sats {
+        return Ok(Some(fee_sats));
+    }
+
+    let mut inputs_sats = 0i64;
+    for vin in &tx.vin {
+        let value_sats = match (&vin.prevout, vin.txid.as_deref(), vin.vout) {
+            (Some(prevout), _, _) => Some(prevout.value_sats),
+            (None, Some(prev_txid), Some(prev_vout)) => db_tx
+                .stored_output(prev_txid, prev_vout)
+                .await?
+                .map(|prevout| prevout.value_sats),
+            _ => None,
+        };
+        let Some(value_sats) = value_sats else {
+            return Ok(None);
+        };
+        inputs_sats += value_sats;
+    }
+    let outputs_sats: i64 = tx.vout.iter().map(|vout| vout.value_sats).sum();
+
+    Ok(Some(inputs_sats - outputs_sats))
 }
 
 async fn observe_db_write<F, T>(
@@ -595,13 +1982,162 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{btc_to_sats, PersistBlockOutcome, RpcBlock};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use sqlx::PgPool;
+
+    use super::{
+        IndexerError, IndexerPipeline, IndexerService, PersistBlockOutcome, RpcBlock, RpcBlockHeader, RpcPrevout,
+        RpcScriptPubKey, RpcTransaction, RpcVin,
+    };
+    use crate::modules::indexer::chain::Chain;
+    use crate::modules::jobs::{CreateJobRequest, JobErrorCategory, JobsError, JobsService};
+    use crate::modules::metrics::MetricsService;
+    use crate::modules::rpc::{BitcoinRpc, RpcError};
+    use crate::modules::storage::memory::MemoryStorage;
+    use crate::modules::storage::repo::PrevoutRecord;
+    use crate::modules::storage::SchemaFeatures;
+
+    /// Node with canned blocks: `active` is the chain by height, `blocks` and `hex` are keyed by hash.
+    #[derive(Clone, Default)]
+    struct FixtureRpc {
+        active: Arc<Vec<String>>,
+        blocks: Arc<HashMap<String, RpcBlock>>,
+        hex: Arc<HashMap<String, String>>,
+    }
 
-    #[test]
-    fn converts_btc_to_sats() {
-        assert_eq!(btc_to_sats(0.0), 0);
-        assert_eq!(btc_to_sats(1.0), 100_000_000);
-        assert_eq!(btc_to_sats(0.00000001), 1);
+    impl FixtureRpc {
+        fn block(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+            self.blocks
+                .get(hash)
+                .cloned()
+                .ok_or_else(|| RpcError::NotFound(format!("block {hash} not found")))
+        }
+    }
+
+    impl BitcoinRpc for FixtureRpc {
+        async fn get_block_count(&self) -> Result<u64, RpcError> {
+            Ok(self.active.len().saturating_sub(1) as u64)
+        }
+
+        async fn get_block_hash(&self, height: u32) -> Result<String, RpcError> {
+            self.active
+                .get(height as usize)
+                .cloned()
+                .ok_or_else(|| RpcError::Node {
+                    code: -8,
+                    message: "Block height out of range".to_string(),
+                })
+        }
+
+        async fn get_block_verbose2(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+            self.block(hash)
+        }
+
+        async fn get_block_verbose3(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+            self.block(hash)
+        }
+
+        async fn get_block_hex(&self, hash: &str) -> Result<String, RpcError> {
+            self.hex
+                .get(hash)
+                .cloned()
+                .ok_or_else(|| RpcError::NotFound(format!("block {hash} not found")))
+        }
+
+        async fn get_block_header(&self, hash: &str) -> Result<RpcBlockHeader, RpcError> {
+            Err(RpcError::Rpc(format!("no header fixture for {hash}")))
+        }
+
+        async fn get_raw_transaction_verbose(&self, txid: &str) -> Result<RpcTransaction, RpcError> {
+            Err(RpcError::Rpc(format!("no transaction fixture for {txid}")))
+        }
+
+        async fn get_raw_mempool(&self) -> Result<Vec<String>, RpcError> {
+            Ok(Vec::new())
+        }
+    }
+
+    fn fixture_block(hash: &str, height: i32) -> RpcBlock {
+        RpcBlock {
+            hash: hash.to_string(),
+            height,
+            prev_hash: None,
+            time: 1_700_000_000,
+            tx: Vec::new(),
+        }
+    }
+
+    const P2PKH_1: &str = "76a914111111111111111111111111111111111111111188ac";
+    const P2PKH_2: &str = "76a914222222222222222222222222222222222222222288ac";
+
+    /// Block 0 pays 50 BTC to `P2PKH_1`; block 1 (`hash`) spends it to `P2PKH_2` with a 0.1 BTC fee.
+    fn spend_chain(hash: &str) -> (RpcBlock, RpcBlock) {
+        let genesis = serde_json::json!({
+            "hash": "hash0",
+            "height": 0,
+            "time": 1_700_000_000,
+            "tx": [{
+                "txid": "coinbase0",
+                "vin": [{"coinbase": "00", "sequence": 0}],
+                "vout": [{"n": 0, "value": 50.0, "scriptPubKey": {"type": "pubkeyhash", "hex": P2PKH_1}}]
+            }]
+        });
+        let next = serde_json::json!({
+            "hash": hash,
+            "height": 1,
+            "previousblockhash": "hash0",
+            "time": 1_700_000_600,
+            "tx": [{
+                "txid": format!("spend-{hash}"),
+                "vin": [{"txid": "coinbase0", "vout": 0, "sequence": 0}],
+                "vout": [{"n": 0, "value": 49.9, "scriptPubKey": {"type": "pubkeyhash", "hex": P2PKH_2}}]
+            }]
+        });
+        (
+            serde_json::from_value(genesis).expect("block 0"),
+            serde_json::from_value(next).expect("block 1"),
+        )
+    }
+
+    fn fixture_indexer(rpc: FixtureRpc) -> IndexerService<FixtureRpc> {
+        // Never connects: the calls under test fail or return before touching storage.
+        let pool = PgPool::connect_lazy("postgres://fixture@127.0.0.1:1/fixture").expect("lazy pool");
+        IndexerService::new(rpc, pool, MetricsService::new())
+    }
+
+    #[tokio::test]
+    async fn fetches_blocks_and_raw_bytes_from_the_rpc_source() {
+        let rpc = FixtureRpc {
+            active: Arc::new(vec!["hash0".to_string()]),
+            blocks: Arc::new(HashMap::from([("hash0".to_string(), fixture_block("hash0", 0))])),
+            hex: Arc::new(HashMap::from([("hash0".to_string(), "00ff".to_string())])),
+        };
+
+        let (block, raw) = fixture_indexer(rpc.clone()).fetch_block("hash0").await.expect("fetch block");
+        assert_eq!(block.hash, "hash0");
+        assert_eq!(raw, None);
+
+        let indexer = fixture_indexer(rpc).with_chain(Chain::Litecoin).with_raw_blocks(true);
+        let (block, raw) = indexer.fetch_block("hash0").await.expect("fetch block");
+        assert_eq!(block.height, 0);
+        assert_eq!(raw, Some(vec![0x00, 0xff]));
+
+        let err = indexer.fetch_block("missing").await.expect_err("unknown block");
+        assert!(matches!(err, IndexerError::Rpc(RpcError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn reindex_rejects_blocks_outside_the_active_chain() {
+        let rpc = FixtureRpc {
+            active: Arc::new(vec!["hash0".to_string(), "hash1".to_string()]),
+            blocks: Arc::new(HashMap::from([("stale1".to_string(), fixture_block("stale1", 1))])),
+            hex: Arc::default(),
+        };
+
+        let err = fixture_indexer(rpc).reindex_block("stale1").await.expect_err("stale block");
+        assert!(err.to_string().contains("block stale1 is not in the node's active chain"));
     }
 
     #[test]
@@ -629,6 +2165,291 @@ mod tests {
         assert_eq!(block.tx.len(), 1);
     }
 
+    #[test]
+    fn parses_fees_and_prevouts_from_verbosity_3() {
+        let json = r#"
+        {
+          "hash": "blockhash",
+          "height": 1,
+          "previousblockhash": "prevhash",
+          "time": 1700000000,
+          "tx": [
+            {
+              "txid": "coinbase",
+              "vin": [{"coinbase": "51", "sequence": 4294967295}],
+              "vout": [{"n": 0, "value": 50.0001, "scriptPubKey": {"type": "witness_v0_keyhash", "hex": "00"}}]
+            },
+            {
+              "txid": "tx1",
+              "fee": 0.0001,
+              "vin": [{"txid": "prevtx", "vout": 0, "sequence": 1, "prevout": {"generated": false, "height": 0, "value": 1.0, "scriptPubKey": {"type": "witness_v0_keyhash", "hex": "00"}}}],
+              "vout": [{"n": 0, "value": 0.9999, "scriptPubKey": {"type": "witness_v0_keyhash", "hex": "00"}}]
+            }
+          ]
+        }
+        "#;
+
+        let block: RpcBlock = serde_json::from_str(json).expect("parse block");
+        assert!(block.tx[0].is_coinbase());
+        assert_eq!(block.tx[0].fee_sats, None);
+        assert_eq!(block.tx[0].vout[0].value_sats, 5_000_010_000);
+        assert!(!block.tx[1].is_coinbase());
+        assert_eq!(block.tx[1].fee_sats, Some(10_000));
+        assert_eq!(block.tx[1].vin[0].prevout.as_ref().map(|prevout| prevout.value_sats), Some(100_000_000));
+    }
+
+    #[tokio::test]
+    async fn persists_blocks_in_height_order_to_memory_storage() {
+        let store = MemoryStorage::new();
+        let pipeline =
+            IndexerPipeline::new(&store, MetricsService::new()).with_schema_features(MemoryStorage::schema_features());
+        let (genesis, next) = spend_chain("hash1");
+
+        let outcome = pipeline.persist_block_from(&next, 0).await.expect("block 1 first");
+        assert_eq!(outcome, PersistBlockOutcome::WaitingForPreviousHeight);
+        assert!(store.tables().await.blocks.is_empty());
+
+        assert_eq!(pipeline.persist_block(&genesis).await.expect("block 0"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&next).await.expect("block 1"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&next).await.expect("again"), PersistBlockOutcome::AlreadyIndexed);
+
+        let (_, competing) = spend_chain("competing1");
+        let err = pipeline.persist_block(&competing).await.expect_err("height 1 is taken");
+        assert!(err.to_string().contains("height 1 is already occupied by canonical block hash1"));
+
+        let tables = store.tables().await;
+        let spent = &tables.utxos[&("coinbase0".to_string(), 0)];
+        assert_eq!(spent.spent_in_txid.as_deref(), Some("spend-hash1"));
+        let address = |script: &str| {
+            tables
+                .tx_outputs
+                .values()
+                .find(|output| output.script_hex == script)
+                .and_then(|output| output.address.clone())
+                .expect("output address")
+        };
+        assert_eq!(tables.balances[&address(P2PKH_1)], 0);
+        assert_eq!(tables.balances[&address(P2PKH_2)], 4_990_000_000);
+        assert_eq!(tables.balance_history[&(address(P2PKH_1), 0)], (1_700_000_000, 5_000_000_000));
+        assert_eq!(tables.transactions["spend-hash1"].fee_sats, Some(10_000_000));
+        assert_eq!(tables.blocks["hash1"].total_fees_sats, Some(10_000_000));
+        let input = &tables.tx_inputs[&("spend-hash1".to_string(), 0)];
+        assert_eq!(input.prevout.as_ref().map(|prevout| prevout.value_sats), Some(5_000_000_000));
+    }
+
+    #[tokio::test]
+    async fn rolls_back_the_block_when_a_stage_fails() {
+        let store = MemoryStorage::new();
+        let (genesis, next) = spend_chain("hash1");
+        IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(MemoryStorage::schema_features())
+            .persist_block(&genesis)
+            .await
+            .expect("block 0");
+
+        // Double spend detection runs on PostgreSQL only, so it fails after the inputs are written.
+        let schema = SchemaFeatures {
+            double_spends: true,
+            ..MemoryStorage::schema_features()
+        };
+        let err = IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(schema)
+            .persist_block(&next)
+            .await
+            .expect_err("double spends need PostgreSQL");
+        assert!(err.to_string().contains("PostgreSQL storage is needed for double spends"));
+
+        let tables = store.tables().await;
+        assert!(!tables.blocks.contains_key("hash1"));
+        assert!(!tables.transactions.contains_key("spend-hash1"));
+        assert!(tables.tx_inputs.is_empty());
+        assert_eq!(tables.utxos[&("coinbase0".to_string(), 0)].spent_in_txid, None);
+    }
+
+    #[tokio::test]
+    async fn moves_the_job_checkpoint_with_the_block() {
+        let store = MemoryStorage::new();
+        let pipeline = IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(MemoryStorage::schema_features())
+            .with_checkpoint(Some("job-1"));
+        let (genesis, next) = spend_chain("hash1");
+
+        assert_eq!(pipeline.persist_block(&genesis).await.expect("block 0"), PersistBlockOutcome::Indexed);
+        assert_eq!(store.tables().await.checkpoints["job-1"], (0, "hash0".to_string()));
+        assert_eq!(pipeline.persist_block(&next).await.expect("block 1"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&genesis).await.expect("again"), PersistBlockOutcome::AlreadyIndexed);
+
+        // A checkpoint is never moved back by re-indexing a lower height.
+        assert_eq!(store.tables().await.checkpoints["job-1"], (1, "hash1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn indexes_heights_from_the_node_and_moves_job_checkpoints() {
+        let (genesis, next) = spend_chain("hash1");
+        let rpc = FixtureRpc {
+            active: Arc::new(vec!["hash0".to_string(), "hash1".to_string()]),
+            blocks: Arc::new(HashMap::from([("hash0".to_string(), genesis), ("hash1".to_string(), next)])),
+            hex: Arc::default(),
+        };
+        let store = MemoryStorage::new();
+        let indexer = IndexerService::new(rpc, store.clone(), MetricsService::new())
+            .with_schema_features(MemoryStorage::schema_features());
+        let jobs = memory_jobs(&store);
+        jobs.create(job_request("job-1", true)).await.expect("create job");
+
+        for height in [0, 1] {
+            let result = indexer.index_height_for_job(height, 0, Some("job-1")).await.expect("index height");
+            assert_eq!(result.outcome, PersistBlockOutcome::Indexed);
+            assert_eq!(result.tx_count, 1);
+        }
+        let again = indexer.index_height_for_job(0, 0, Some("job-1")).await.expect("index again");
+        assert_eq!(again.outcome, PersistBlockOutcome::AlreadyIndexed);
+
+        assert!(indexer.has_canonical_block(1).await.expect("block 1"));
+        assert!(!indexer.has_canonical_block(2).await.expect("block 2"));
+        assert_eq!(jobs.checkpoint("job-1").await.expect("checkpoint"), Some((1, "hash1".to_string())));
+
+        jobs.rewind_progress(Some("job-1"), 0).await.expect("rewind");
+        assert_eq!(jobs.checkpoint("job-1").await.expect("checkpoint"), Some((0, "hash0".to_string())));
+        jobs.rewind_all_progress(-1).await.expect("rewind all");
+        assert_eq!(jobs.checkpoint("job-1").await.expect("checkpoint"), None);
+    }
+
+    fn memory_jobs(store: &MemoryStorage) -> JobsService<MemoryStorage> {
+        JobsService::new(store.clone()).with_schema_features(MemoryStorage::schema_features())
+    }
+
+    fn job_request(job_id: &str, enabled: bool) -> CreateJobRequest {
+        CreateJobRequest {
+            job_id: job_id.to_string(),
+            mode: "all_addresses".to_string(),
+            enabled,
+            addresses: vec![],
+            from_height: None,
+            to_height: None,
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn moves_jobs_between_statuses_and_records_the_transitions() {
+        let store = MemoryStorage::new();
+        let jobs = memory_jobs(&store);
+
+        let job = jobs.create(job_request("job-1", false)).await.expect("create");
+        assert_eq!(job.status, "created");
+        let err = jobs.create(job_request("job-1", false)).await.expect_err("duplicate");
+        assert!(matches!(err, JobsError::AlreadyExists(_)));
+
+        assert_eq!(jobs.start("job-1", None).await.expect("start").status, "running");
+        assert!(jobs.is_running("job-1").await.expect("is running"));
+        assert_eq!(jobs.pause("job-1", Some("maintenance")).await.expect("pause").status, "paused");
+        assert_eq!(jobs.resume("job-1", None).await.expect("resume").status, "running");
+        assert_eq!(jobs.stop("job-1", None).await.expect("stop").status, "created");
+
+        let err = jobs.resume("job-1", None).await.expect_err("resume a created job");
+        assert!(matches!(
+            err,
+            JobsError::InvalidTransition { ref current_status, ref requested, .. }
+                if current_status == "created" && requested == "resume"
+        ));
+        let err = jobs.start("missing", None).await.expect_err("unknown job");
+        assert!(matches!(err, JobsError::NotFound(_)));
+
+        let events = store.tables().await.job_events;
+        let transitions: Vec<_> = events
+            .iter()
+            .map(|event| (event.action.as_str(), event.from_status.as_str(), event.to_status.as_str()))
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                ("start", "created", "running"),
+                ("pause", "running", "paused"),
+                ("resume", "paused", "running"),
+                ("stop", "running", "created"),
+            ]
+        );
+        assert_eq!(events[1].reason.as_deref(), Some("maintenance"));
+    }
+
+    #[tokio::test]
+    async fn fails_retries_and_deletes_jobs() {
+        let store = MemoryStorage::new();
+        let jobs = memory_jobs(&store);
+        jobs.create(job_request("job-1", true)).await.expect("create");
+
+        jobs.update_progress("job-1", 5).await.expect("progress");
+        jobs.mark_failed("job-1", JobErrorCategory::Rpc, Some(6), "node unreachable")
+            .await
+            .expect("mark failed");
+        let job = jobs.get("job-1").await.expect("get");
+        assert_eq!((job.status.as_str(), job.progress_height), ("failed", 5));
+        assert_eq!(job.last_error.as_deref(), Some("node unreachable"));
+        assert_eq!(store.tables().await.job_errors[0].block_height, Some(6));
+
+        let job = jobs.retry("job-1", None).await.expect("retry");
+        assert_eq!((job.status.as_str(), job.last_error), ("running", None));
+        assert_eq!(store.tables().await.jobs["job-1"].retry_count, 1);
+
+        let err = jobs.delete("job-1").await.expect_err("delete a running job");
+        assert!(matches!(err, JobsError::InvalidTransition { .. }));
+        jobs.stop("job-1", None).await.expect("stop");
+        jobs.delete("job-1").await.expect("delete");
+
+        let tables = store.tables().await;
+        assert!(tables.jobs.is_empty() && tables.job_events.is_empty() && tables.job_errors.is_empty());
+        assert!(matches!(jobs.get("job-1").await, Err(JobsError::NotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn resolves_unstored_prevouts_from_block_json_or_fetched_outputs() {
+        let pool = PgPool::connect_lazy("postgres://fixture@127.0.0.1:1/fixture").expect("lazy pool");
+        let fetched = HashMap::from([(
+            ("fetched".to_string(), 1),
+            PrevoutRecord {
+                value_sats: 2_000,
+                script_type: "pubkeyhash".to_string(),
+                address: Some("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string()),
+            },
+        )]);
+        let pipeline = IndexerPipeline::new(&pool, MetricsService::new()).with_prevouts(Some(&fetched));
+        let vin = |prevout: Option<RpcPrevout>| RpcVin {
+            txid: Some("prev".to_string()),
+            vout: Some(0),
+            sequence: 1,
+            txinwitness: None,
+            prevout,
+        };
+
+        let verbose3 = vin(Some(RpcPrevout {
+            value_sats: 1_000,
+            script_pub_key: Some(RpcScriptPubKey {
+                script_type: "witness_v0_keyhash".to_string(),
+                hex: "0014751e76e8199196d454941c45d1b3a323f1433bd6".to_string(),
+                address: None,
+                addresses: None,
+            }),
+        }));
+        assert_eq!(
+            pipeline.unstored_prevout(&verbose3, "prev", 0),
+            Some(PrevoutRecord {
+                value_sats: 1_000,
+                script_type: "witness_v0_keyhash".to_string(),
+                address: Some("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string()),
+            })
+        );
+        assert_eq!(pipeline.unstored_prevout(&vin(None), "fetched", 1), fetched.get(&("fetched".to_string(), 1)).cloned());
+        assert_eq!(pipeline.unstored_prevout(&vin(None), "prev", 0), None);
+    }
+
     #[test]
     fn persist_block_outcome_is_comparable() {
         assert_eq!(PersistBlockOutcome::Indexed, PersistBlockOutcome::Indexed);
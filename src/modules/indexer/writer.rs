@@ -0,0 +1,136 @@
+//! Pipeline between block fetching and block writes: a job fetches blocks ahead of the
+//! writer into a bounded queue, and the writes of all jobs of a network share
+//! `indexer.concurrency.db_writer_parallelism` slots.
+
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+
+use crate::modules::metrics::MetricsService;
+
+/// Slots for block write transactions, shared by the jobs of one database.
+#[derive(Debug, Clone)]
+pub struct WriterPool {
+    slots: Arc<Semaphore>,
+    parallelism: usize,
+}
+
+impl WriterPool {
+    pub fn new(parallelism: u16) -> Self {
+        let parallelism = usize::from(parallelism.max(1));
+        Self {
+            slots: Arc::new(Semaphore::new(parallelism)),
+            parallelism,
+        }
+    }
+
+    pub fn parallelism(&self) -> usize {
+        self.parallelism
+    }
+
+    /// Waits for a free writer slot; the slot is released when the permit is dropped.
+    pub async fn acquire(&self) -> OwnedSemaphorePermit {
+        self.slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("writer pool semaphore is never closed")
+    }
+
+    /// Queue of fetched blocks of one job batch, holding as many blocks as there are
+    /// writer slots. The fetcher waits while it is full.
+    pub fn queue<T>(&self, job_id: &str, metrics: MetricsService) -> (WriteQueueSender<T>, WriteQueueReceiver<T>) {
+        let (tx, rx) = mpsc::channel(self.parallelism);
+        let job_id: Arc<str> = Arc::from(job_id);
+        (
+            WriteQueueSender {
+                tx,
+                job_id: job_id.clone(),
+                metrics: metrics.clone(),
+            },
+            WriteQueueReceiver { rx, job_id, metrics },
+        )
+    }
+}
+
+impl Default for WriterPool {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+pub struct WriteQueueSender<T> {
+    tx: mpsc::Sender<T>,
+    job_id: Arc<str>,
+    metrics: MetricsService,
+}
+
+pub struct WriteQueueReceiver<T> {
+    rx: mpsc::Receiver<T>,
+    job_id: Arc<str>,
+    metrics: MetricsService,
+}
+
+impl<T> WriteQueueSender<T> {
+    /// Whether the writer stopped, so fetching further blocks is pointless.
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+
+    /// Queues `item`, waiting while the queue is full. Returns `false` once the writer stopped.
+    pub async fn send(&self, item: T) -> bool {
+        let sent = match self.tx.try_send(item) {
+            Ok(()) => true,
+            Err(mpsc::error::TrySendError::Full(item)) => {
+                self.metrics.increment_write_backpressure(&self.job_id);
+                self.tx.send(item).await.is_ok()
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => false,
+        };
+        self.metrics.set_write_queue_depth(&self.job_id, self.depth());
+        sent
+    }
+
+    fn depth(&self) -> u64 {
+        (self.tx.max_capacity() - self.tx.capacity()) as u64
+    }
+}
+
+impl<T> WriteQueueReceiver<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        let item = self.rx.recv().await;
+        self.metrics.set_write_queue_depth(&self.job_id, self.rx.len() as u64);
+        item
+    }
+}
+
+impl<T> Drop for WriteQueueReceiver<T> {
+    fn drop(&mut self) {
+        self.metrics.set_write_queue_depth(&self.job_id, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn full_queue_holds_the_fetcher_back() {
+        let metrics = MetricsService::new();
+        let pool = WriterPool::new(2);
+        let (tx, mut rx) = pool.queue::<u32>("job-a", metrics.clone());
+
+        assert!(tx.send(1).await);
+        assert!(tx.send(2).await);
+        assert_eq!(tx.depth(), 2);
+        let blocked = tokio::time::timeout(std::time::Duration::from_millis(20), tx.send(3)).await;
+        assert!(blocked.is_err(), "third block waits for the writer");
+
+        assert_eq!(rx.recv().await, Some(1));
+        assert!(tx.send(3).await);
+        assert_eq!(rx.recv().await, Some(2));
+        drop(rx);
+        assert!(tx.is_closed());
+        assert!(!tx.send(4).await);
+    }
+}
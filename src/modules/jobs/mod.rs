@@ -1,19 +1,43 @@
-use std::collections::HashSet;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::panic::AssertUnwindSafe;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use bitcoin::Network;
 use chrono::{DateTime, Utc};
+use futures_util::FutureExt;
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, PgPool};
+use sqlx::{FromRow, PgConnection, PgPool};
 use thiserror::Error;
 use tokio::sync::{Mutex, Semaphore};
-use tracing::{error, warn};
+use tracing::{error, info, info_span, warn, Instrument};
 use utoipa::ToSchema;
 
-use crate::modules::config::JobConfig;
-use crate::modules::indexer::{IndexerError, IndexHeightResult, IndexerService, PersistBlockOutcome};
+pub mod store;
+
+use crate::modules::alerting;
+use crate::modules::chain::ChainParams;
+use crate::modules::config::{IndexerConfig, JobConfig, JobExportConfig, JobRetryPolicy};
+use crate::modules::descriptors::{self, WatchDescriptor};
+use crate::modules::export::{self, ExportError, ExportTarget};
+use crate::modules::headers;
+use crate::modules::indexer::chain::Chain;
+use crate::modules::indexer::{
+    backfill_address, IndexerError, IndexHeightResult, IndexerService, PersistBlockOutcome,
+};
+use crate::modules::jobs::store::{
+    JobErrorRecord, JobEventRecord, JobStatusUpdate, JobStore, JobStoreTx, LastError, NewJobRecord,
+};
 use crate::modules::metrics::MetricsService;
+use crate::modules::protocols;
+use crate::modules::rate_limit::Throttle;
+use crate::modules::reload::ReloadableConfig;
 use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::storage::repo::StreamEventsRepo;
+use crate::modules::storage::SchemaFeatures;
+use crate::modules::verify::{self, DiscrepancyItem, VerifyError};
 
 #[derive(Debug, Clone, Deserialize, ToSchema)]
 pub struct CreateJobRequest {
@@ -21,6 +45,38 @@ pub struct CreateJobRequest {
     pub mode: String,
     pub enabled: bool,
     pub addresses: Vec<String>,
+    pub from_height: Option<i32>,
+    pub to_height: Option<i32>,
+    /// Output descriptor or bare xpub/ypub/zpub, required for `descriptor` mode.
+    #[serde(default)]
+    pub descriptor: Option<String>,
+    /// Defaults to 20 for `descriptor` mode.
+    #[serde(default)]
+    pub gap_limit: Option<u32>,
+    #[serde(default)]
+    pub retry: Option<JobRetryPolicy>,
+    /// Required for `export` mode.
+    #[serde(default)]
+    pub export: Option<JobExportConfig>,
+    /// Higher starts first among jobs of its kind; `0` by default.
+    #[serde(default)]
+    pub priority: Option<i32>,
+    /// Requests per second the job may send to the node; unlimited by default.
+    #[serde(default)]
+    pub max_rpc_per_second: Option<u32>,
+    /// Blocks per minute the job may index; unlimited by default.
+    #[serde(default)]
+    pub max_blocks_per_minute: Option<u32>,
+    /// Protocol indexers run on the blocks the job writes, e.g. `runes`.
+    #[serde(default)]
+    pub protocols: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct AddJobAddressesRequest {
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub backfill: bool,
 }
 
 #[derive(Debug, Clone, Serialize, ToSchema)]
@@ -30,36 +86,223 @@ pub struct JobSummary {
     pub status: String,
     pub progress_height: i32,
     pub tip_height: Option<i32>,
+    pub blocks_per_sec: Option<f64>,
+    pub remaining_blocks: Option<i64>,
+    pub eta_seconds: Option<i64>,
+    pub from_height: Option<i32>,
+    pub to_height: Option<i32>,
     pub updated_at: Option<DateTime<Utc>>,
     pub last_error: Option<String>,
 }
 
+/// Filter, order and page of the jobs list.
+#[derive(Debug, Clone, Default)]
+pub struct JobsListFilter {
+    pub status: Option<String>,
+    pub mode: Option<String>,
+    /// `job_id` (default), `updated_at` or `progress_height`; a leading `-` sorts descending.
+    pub sort: Option<String>,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobsPage {
+    pub items: Vec<JobSummary>,
+    pub offset: i64,
+    pub limit: i64,
+    /// Jobs matching the filter across all pages.
+    pub total: i64,
+}
+
 #[derive(Debug, Clone, Serialize, ToSchema)]
 pub struct JobDetails {
     pub job_id: String,
     pub mode: String,
     pub status: String,
     pub progress_height: i32,
+    pub tip_height: Option<i32>,
+    pub blocks_per_sec: Option<f64>,
+    pub remaining_blocks: Option<i64>,
+    pub eta_seconds: Option<i64>,
+    pub from_height: Option<i32>,
+    pub to_height: Option<i32>,
     pub updated_at: Option<DateTime<Utc>>,
     pub last_error: Option<String>,
     pub config_snapshot: serde_json::Value,
+    /// Heights missing below `progress_height` that wait to be indexed again; `null`
+    /// without migration `0032_job_gaps`.
+    pub gap_heights: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobEta {
+    pub job_id: String,
+    pub status: String,
+    pub progress_height: i32,
+    /// `min(to_height, tip_height)` for `height_range`, otherwise the tip.
+    pub target_height: Option<i32>,
+    pub remaining_blocks: Option<i64>,
+    pub blocks_per_sec: Option<f64>,
+    /// `block_weight` when remaining blocks are weighted by the era's average
+    /// block weight (mainnet), `linear` otherwise.
+    pub model: String,
+    pub eta_seconds: Option<i64>,
+    /// Naive `remaining_blocks / blocks_per_sec`, for comparison.
+    pub linear_eta_seconds: Option<i64>,
+    pub estimated_completion_at: Option<DateTime<Utc>>,
+}
+
+/// Dry-run projection of what indexing the job's range would take.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobEstimate {
+    pub job_id: String,
+    pub mode: String,
+    pub status: String,
+    /// `from_height` of the job, or `0` when it starts from genesis.
+    pub from_height: i32,
+    /// `min(to_height, tip_height)`; `null` while the tip is unknown and the job has no `to_height`.
+    pub to_height: Option<i32>,
+    pub blocks: Option<i64>,
+    /// Canonical blocks of the range that are already stored and will be skipped.
+    pub indexed_blocks: Option<i64>,
+    pub blocks_to_index: Option<i64>,
+    /// Based on the average transaction count of recently indexed blocks.
+    pub estimated_txs: Option<i64>,
+    /// Based on the current on-disk size of indexed data per transaction.
+    pub estimated_storage_bytes: Option<i64>,
+    pub blocks_per_sec: Option<f64>,
+    /// `job` for the job's own recent rate, `running_jobs` when the rate of
+    /// another running job is used.
+    pub rate_source: Option<String>,
+    /// `block_weight` (mainnet) or `linear`, as for the ETA.
+    pub model: String,
+    pub estimated_duration_seconds: Option<i64>,
+}
+
+/// Blocks the job would index next, fetched and decoded without writing anything.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobDryRun {
+    pub job_id: String,
+    pub mode: String,
+    pub status: String,
+    /// Watched addresses of `address_list` and `descriptor` jobs; `null` for other modes.
+    pub watched_addresses: Option<i64>,
+    /// Blocks that could not be fetched or decoded.
+    pub errors: u64,
+    /// Ascending by height; empty when the job has nothing left to index.
+    pub blocks: Vec<JobDryRunBlock>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct JobDryRunBlock {
+    pub height: i32,
+    /// `null` when the block could not be fetched.
+    pub hash: Option<String>,
+    pub txs: u64,
+    pub inputs: u64,
+    pub outputs: u64,
+    /// Outputs whose script decodes to no address, e.g. `nulldata` and bare multisig.
+    pub outputs_without_address: u64,
+    /// Outputs paying watched addresses; `null` for modes without an address list.
+    pub matched_outputs: Option<u64>,
+    pub fetch_ms: u64,
+    pub error: Option<String>,
+}
+
+/// Canonical heights whose rows were refetched and rewritten.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BlockReindex {
+    pub from_height: i32,
+    pub to_height: i32,
+    pub blocks: i32,
+    pub txs: u64,
+}
+
+/// Output descriptors of an `address_list` job for import into a Bitcoin Core wallet.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobDescriptors {
+    pub job_id: String,
+    pub items: Vec<JobDescriptor>,
+    /// Ready-made argument for `bitcoin-cli importdescriptors`.
+    pub import_request: Vec<DescriptorImport>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobDescriptor {
+    pub address: String,
+    /// `wpkh` once a spend revealed the key of a P2WPKH address, otherwise `addr`.
+    pub kind: String,
+    /// Descriptor with its BIP-380 checksum.
+    pub descriptor: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DescriptorImport {
+    pub desc: String,
+    /// Rescan start: time of the earliest indexed output to the address, `0` (genesis) if none is indexed.
+    pub timestamp: i64,
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobErrorItem {
+    pub id: i64,
+    pub job_id: String,
+    pub occurred_at: DateTime<Utc>,
+    pub category: String,
+    pub block_height: Option<i32>,
+    pub retry_count: i32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobErrorCategory {
+    Rpc,
+    Storage,
+    Parse,
+    /// The batch task panicked; caught by the runner instead of leaving the job `running`.
+    Panic,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// Optional body of `POST /v1/jobs/{job_id}/{start,stop,pause,resume,retry}`.
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
 pub struct JobActionRequest {
-    pub _empty: Option<String>,
+    /// Kept with the transition in `job_events`, at most 500 characters.
+    pub reason: Option<String>,
+}
+
+/// State of an `Idempotency-Key` when a job action arrives with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyKeyState {
+    /// First request with the key, reserved until its response is stored.
+    Reserved,
+    /// The same request was already handled; its response is replayed.
+    Completed { status: u16, body: Vec<u8> },
+    /// The first request with the key is still being handled.
+    InProgress,
+    /// The key was used for another method or path.
+    Mismatch,
 }
 
 #[derive(Debug, Error)]
 pub enum JobsError {
-    #[error("job not found")]
-    NotFound,
-    #[error("job already exists")]
-    AlreadyExists,
-    #[error("invalid transition from '{0}'")]
-    InvalidTransition(String),
+    #[error("job '{0}' not found")]
+    NotFound(String),
+    #[error("address '{address}' is not watched by job '{job_id}'")]
+    AddressNotFound { job_id: String, address: String },
+    #[error("job '{0}' already exists")]
+    AlreadyExists(String),
+    #[error("job '{job_id}' cannot {requested} from '{current_status}'")]
+    InvalidTransition {
+        job_id: String,
+        current_status: String,
+        requested: String,
+    },
     #[error("validation error: {0}")]
     Validation(String),
+    #[error("no node is configured for the jobs service")]
+    NodeUnavailable,
     #[error("storage error: {0}")]
     Storage(#[from] sqlx::Error),
     #[error("serialization error: {0}")]
@@ -74,8 +317,18 @@ enum JobExecutionError {
     Rpc(#[from] RpcError),
     #[error(transparent)]
     Indexer(#[from] IndexerError),
+    #[error(transparent)]
+    Export(#[from] ExportError),
+    #[error(transparent)]
+    Verify(#[from] VerifyError),
     #[error("tip height exceeds i32 range")]
     TipOverflow,
+    #[error("{source}")]
+    AtHeight {
+        height: i32,
+        #[source]
+        source: Box<JobExecutionError>,
+    },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -87,9 +340,58 @@ enum JobAction {
     Retry,
 }
 
-#[derive(Debug, Clone)]
-pub struct JobsService {
-    pool: Arc<PgPool>,
+const PROGRESS_RATE_WINDOW: Duration = Duration::from_secs(300);
+const DEFAULT_ERRORS_LIMIT: i64 = 50;
+const MAX_ERRORS_LIMIT: i64 = 500;
+/// How long a stored job action response is replayed for its `Idempotency-Key`.
+const IDEMPOTENCY_KEY_TTL_HOURS: i32 = 24;
+/// Longest `reason` of a job action kept in `job_events`.
+const MAX_REASON_CHARS: usize = 500;
+/// `stream_events` are kept this long, and at most the latest `MAX_STREAM_EVENTS` of them.
+const STREAM_EVENTS_RETENTION_HOURS: i32 = 24;
+const MAX_STREAM_EVENTS: i64 = 100_000;
+const STREAM_EVENTS_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+const DEFAULT_DRY_RUN_BLOCKS: u32 = 10;
+const MAX_DRY_RUN_BLOCKS: u32 = 100;
+/// Heights one reindex request rewrites at most; the request waits for all of them.
+const MAX_REINDEX_HEIGHTS: i32 = 1000;
+/// Missing heights queued per job and gap scan; the rest are found by the next scans.
+const MAX_GAP_HEIGHTS_PER_SCAN: i64 = 10_000;
+const DEFAULT_JOBS_LIMIT: i64 = 100;
+const MAX_JOBS_LIMIT: i64 = 1000;
+const JOB_STATUSES: [&str; 5] = ["created", "running", "paused", "failed", "completed"];
+const JOB_MODES: [&str; 6] = ["all_addresses", "address_list", "height_range", "descriptor", "export", "verify"];
+/// Columns the jobs list can be sorted by.
+const JOB_SORT_COLUMNS: [&str; 3] = ["job_id", "updated_at", "progress_height"];
+/// Fixed per-block cost in weight units (RPC round trip, block row), so nearly
+/// empty early blocks are not estimated as free.
+const BLOCK_OVERHEAD_WEIGHT: u64 = 50_000;
+/// Recently indexed blocks used to sample transactions per block.
+const ESTIMATE_SAMPLE_BLOCKS: i64 = 1000;
+/// Tables that grow with every indexed transaction.
+const INDEXED_DATA_TABLES: [&str; 7] = [
+    "blocks",
+    "transactions",
+    "tx_outputs",
+    "tx_inputs",
+    "utxos_current",
+    "address_balance_current",
+    "address_balance_history",
+];
+/// Advisory lock for config sync; the indexer uses `-1` and block heights.
+const JOBS_SYNC_LOCK_KEY: i64 = -2;
+
+/// Jobs API and runner bookkeeping. Job rows are created, deleted, moved between statuses
+/// and checkpointed through [`JobStore`]; the other queries run on [`PgPool`].
+#[derive(Clone)]
+pub struct JobsService<S = PgPool> {
+    pool: S,
+    /// Pool of the job list; it may lag behind `pool` on a read replica.
+    read_pool: S,
+    schema: SchemaFeatures,
+    chain: Option<ChainParams>,
+    /// Fetches blocks for [`Self::dry_run`].
+    indexer: Option<IndexerService>,
 }
 
 #[derive(Debug, Clone)]
@@ -97,7 +399,10 @@ pub struct JobsRunnerConfig {
     pub max_jobs: usize,
     pub poll_interval: Duration,
     pub blocks_per_batch: u32,
+    pub txs_per_batch: u32,
     pub reorg_depth: u32,
+    /// How often heights missing below the progress of jobs are looked for; `None` disables the scan.
+    pub gap_scan_interval: Option<Duration>,
 }
 
 #[derive(Clone)]
@@ -106,483 +411,2834 @@ pub struct JobsRunner {
     rpc: RpcClient,
     indexer: IndexerService,
     metrics: MetricsService,
-    config: JobsRunnerConfig,
+    config: ReloadableConfig<JobsRunnerConfig>,
     active_jobs: Arc<Mutex<HashSet<String>>>,
+    progress_windows: Arc<Mutex<HashMap<String, ProgressWindow>>>,
+    throttles: Arc<Mutex<HashMap<String, JobThrottle>>>,
+    /// Running jobs whose checkpoint was checked against the node since they started.
+    verified_checkpoints: Arc<Mutex<HashSet<String>>>,
 }
 
-impl JobsService {
-    pub fn new(pool: PgPool) -> Self {
+/// Order in which running jobs take the `max_jobs` slots. Jobs are split into backfill
+/// (a height range, or more than one batch behind the tip they last saw) and tip-following
+/// ones. Within each kind a higher `priority` starts first and jobs of equal priority take
+/// turns; the kinds alternate, so a large backfill cannot starve real-time indexing.
+#[derive(Debug, Default)]
+struct JobScheduler {
+    /// Sequence number of the last start of each running job.
+    last_started: HashMap<String, u64>,
+    starts: u64,
+    /// Whether a backfill job gets the next slot when both kinds are waiting.
+    backfill_next: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ScheduledJob {
+    job_id: String,
+    backfill: bool,
+}
+
+/// `max_rpc_per_second` and `max_blocks_per_minute` of a job's `config_snapshot`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+struct JobLimits {
+    #[serde(default)]
+    max_rpc_per_second: Option<u32>,
+    #[serde(default)]
+    max_blocks_per_minute: Option<u32>,
+}
+
+/// Buckets enforcing the limits of a job. They outlive a batch, so the rates hold over
+/// the whole run, and are rebuilt when the limits change.
+#[derive(Debug, Clone, Default)]
+struct JobThrottle {
+    limits: JobLimits,
+    rpc: Option<Throttle>,
+    blocks: Option<Throttle>,
+}
+
+/// Sliding window of `(sampled_at, progress_height)` used to derive the sync rate.
+#[derive(Debug, Default)]
+struct ProgressWindow {
+    samples: VecDeque<(Instant, i32)>,
+}
+
+impl<S> fmt::Debug for JobsService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobsService")
+            .field("schema", &self.schema)
+            .field("chain", &self.chain)
+            .field("indexer", &self.indexer.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: JobStore> JobsService<S> {
+    pub fn new(pool: S) -> Self {
         Self {
-            pool: Arc::new(pool),
+            read_pool: pool.clone(),
+            pool,
+            schema: SchemaFeatures::latest(),
+            chain: None,
+            indexer: None,
         }
     }
 
-    pub fn pool(&self) -> &PgPool {
-        self.pool.as_ref()
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
     }
 
-    pub async fn sync_from_config(&self, jobs: &[JobConfig]) -> Result<(), JobsError> {
-        for job in jobs {
-            let snapshot = serde_json::to_value(job)?;
-            let mut tx = self.pool.begin().await?;
-
-            sqlx::query(
-                "INSERT INTO jobs (job_id, mode, status, progress_height, config_snapshot, updated_at) \
-                 VALUES ($1, $2, 'created', 0, $3, NOW()) \
-                 ON CONFLICT (job_id) DO UPDATE SET \
-                   mode = EXCLUDED.mode, \
-                   config_snapshot = EXCLUDED.config_snapshot, \
-                   updated_at = NOW()",
-            )
-            .bind(&job.job_id)
-            .bind(&job.mode)
-            .bind(snapshot)
-            .execute(&mut *tx)
-            .await?;
-
-            sqlx::query("DELETE FROM job_addresses WHERE job_id = $1")
-                .bind(&job.job_id)
-                .execute(&mut *tx)
-                .await?;
+    /// Rejects job addresses of other networks in API requests.
+    pub fn with_chain_params(mut self, chain: ChainParams) -> Self {
+        self.chain = Some(chain);
+        self
+    }
 
-            for address in &job.addresses {
-                sqlx::query(
-                    "INSERT INTO job_addresses (job_id, address) \
-                     VALUES ($1, $2) \
-                     ON CONFLICT (job_id, address) DO NOTHING",
-                )
-                .bind(&job.job_id)
-                .bind(address)
-                .execute(&mut *tx)
-                .await?;
-            }
+    /// Indexer whose node and settings [`Self::dry_run`] fetches blocks with.
+    pub fn with_indexer(mut self, indexer: IndexerService) -> Self {
+        self.indexer = Some(indexer);
+        self
+    }
 
-            tx.commit().await?;
-        }
+    fn ensure_network_addresses(&self, addresses: &[String]) -> Result<(), JobsError> {
+        let Some(chain) = &self.chain else {
+            return Ok(());
+        };
 
-        Ok(())
+        addresses
+            .iter()
+            .try_for_each(|address| chain.check_address(address))
+            .map_err(JobsError::Validation)
     }
 
     pub async fn create(&self, request: CreateJobRequest) -> Result<JobDetails, JobsError> {
         let job = normalize_job_config(request)?;
+        self.ensure_schema_supports(&job)?;
+        self.ensure_network_addresses(&job.addresses)?;
+        let descriptor = self.watch_descriptor(&job)?;
         let snapshot = serde_json::to_value(&job)?;
-        let mut tx = self.pool.begin().await?;
-
-        let inserted = sqlx::query(
-            "INSERT INTO jobs (job_id, mode, status, progress_height, config_snapshot, updated_at) \
-             VALUES ($1, $2, 'created', 0, $3, NOW()) \
-             ON CONFLICT (job_id) DO NOTHING",
-        )
-        .bind(&job.job_id)
-        .bind(&job.mode)
-        .bind(snapshot)
-        .execute(&mut *tx)
-        .await?
-        .rows_affected();
+        let mut tx = self.pool.begin_jobs(self.schema).await?;
+
+        let inserted = tx
+            .insert_job(&NewJobRecord {
+                job_id: &job.job_id,
+                mode: &job.mode,
+                config_snapshot: &snapshot,
+                from_height: job.from_height,
+                to_height: job.to_height,
+                addresses: &job.addresses,
+            })
+            .await?;
 
-        if inserted == 0 {
-            return Err(JobsError::AlreadyExists);
+        if !inserted {
+            return Err(JobsError::AlreadyExists(job.job_id.clone()));
         }
 
-        for address in &job.addresses {
-            sqlx::query(
-                "INSERT INTO job_addresses (job_id, address) \
-                 VALUES ($1, $2) \
-                 ON CONFLICT (job_id, address) DO NOTHING",
-            )
-            .bind(&job.job_id)
-            .bind(address)
-            .execute(&mut *tx)
-            .await?;
+        if let Some((descriptor, gap_limit)) = &descriptor {
+            sync_descriptor_window(postgres(&mut tx, "descriptor jobs")?, &job.job_id, descriptor, *gap_limit).await?;
         }
 
         tx.commit().await?;
 
         if job.enabled {
-            self.start(&job.job_id).await
+            self.start(&job.job_id, None).await
         } else {
             self.get(&job.job_id).await
         }
     }
 
-    pub async fn activate_enabled_jobs(&self, jobs: &[JobConfig]) -> Result<(), JobsError> {
-        for job in jobs.iter().filter(|job| job.enabled) {
-            let status = sqlx::query_scalar::<_, String>(
-                "SELECT status
-                 FROM jobs
-                 WHERE job_id = $1",
-            )
-            .bind(&job.job_id)
-            .fetch_optional(self.pool.as_ref())
+    pub async fn delete(&self, job_id: &str) -> Result<(), JobsError> {
+        let mut tx = self.pool.begin_jobs(self.schema).await?;
+
+        let status = tx
+            .lock_job_status(job_id)
             .await?
-            .ok_or(JobsError::NotFound)?;
+            .ok_or_else(|| JobsError::NotFound(job_id.to_string()))?;
 
-            match status.as_str() {
-                "created" => {
-                    self.start(&job.job_id).await?;
-                }
-                "paused" => {
-                    self.resume(&job.job_id).await?;
-                }
-                "failed" => {
-                    self.retry(&job.job_id).await?;
-                }
-                "running" | "completed" => {}
-                _ => {}
-            }
-        }
+        ensure_deletable(job_id, &status)?;
 
-        Ok(())
-    }
+        tx.delete_job(job_id).await?;
 
-    pub async fn list(&self) -> Result<Vec<JobSummary>, JobsError> {
-        let rows: Vec<JobRow> = sqlx::query_as(
-            "SELECT job_id, mode, status, progress_height, updated_at, last_error \
-             FROM jobs \
-             ORDER BY job_id",
-        )
-        .fetch_all(self.pool.as_ref())
-        .await?;
+        tx.commit().await?;
 
-        Ok(rows.into_iter().map(JobSummary::from).collect())
+        Ok(())
     }
 
     pub async fn get(&self, job_id: &str) -> Result<JobDetails, JobsError> {
-        let row: JobDetailsRow = sqlx::query_as(
-            "SELECT job_id, mode, status, progress_height, updated_at, last_error, config_snapshot \
-             FROM jobs \
-             WHERE job_id = $1",
-        )
-        .bind(job_id)
-        .fetch_optional(self.pool.as_ref())
-        .await?
-        .ok_or(JobsError::NotFound)?;
+        let row = self
+            .pool
+            .job(self.schema, job_id)
+            .await?
+            .ok_or_else(|| JobsError::NotFound(job_id.to_string()))?;
+
+        let (remaining_blocks, eta_seconds) =
+            progress_estimate(row.progress_height, row.tip_height, row.to_height, row.blocks_per_sec);
 
         Ok(JobDetails {
             job_id: row.job_id,
             mode: row.mode,
             status: row.status,
             progress_height: row.progress_height,
+            tip_height: row.tip_height,
+            blocks_per_sec: row.blocks_per_sec,
+            remaining_blocks,
+            eta_seconds,
+            from_height: row.from_height,
+            to_height: row.to_height,
             updated_at: row.updated_at,
             last_error: row.last_error,
             config_snapshot: row.config_snapshot,
+            gap_heights: row.gap_heights,
         })
     }
 
-    pub async fn start(&self, job_id: &str) -> Result<JobDetails, JobsError> {
-        self.transition(job_id, JobAction::Start).await
+    pub async fn start(&self, job_id: &str, reason: Option<&str>) -> Result<JobDetails, JobsError> {
+        self.transition(job_id, JobAction::Start, reason).await
     }
 
-    pub async fn stop(&self, job_id: &str) -> Result<JobDetails, JobsError> {
-        self.transition(job_id, JobAction::Stop).await
+    pub async fn stop(&self, job_id: &str, reason: Option<&str>) -> Result<JobDetails, JobsError> {
+        self.transition(job_id, JobAction::Stop, reason).await
     }
 
-    pub async fn pause(&self, job_id: &str) -> Result<JobDetails, JobsError> {
-        self.transition(job_id, JobAction::Pause).await
+    pub async fn pause(&self, job_id: &str, reason: Option<&str>) -> Result<JobDetails, JobsError> {
+        self.transition(job_id, JobAction::Pause, reason).await
     }
 
-    pub async fn resume(&self, job_id: &str) -> Result<JobDetails, JobsError> {
-        self.transition(job_id, JobAction::Resume).await
+    pub async fn resume(&self, job_id: &str, reason: Option<&str>) -> Result<JobDetails, JobsError> {
+        self.transition(job_id, JobAction::Resume, reason).await
     }
 
-    pub async fn retry(&self, job_id: &str) -> Result<JobDetails, JobsError> {
-        self.transition(job_id, JobAction::Retry).await
+    pub async fn retry(&self, job_id: &str, reason: Option<&str>) -> Result<JobDetails, JobsError> {
+        self.transition(job_id, JobAction::Retry, reason).await
     }
 
-    pub async fn running_job_ids(&self) -> Result<Vec<String>, JobsError> {
-        let rows: Vec<JobIdRow> = sqlx::query_as(
-            "SELECT job_id \
-             FROM jobs \
-             WHERE status = 'running' \
-             ORDER BY job_id",
-        )
-        .fetch_all(self.pool.as_ref())
-        .await?;
+    pub async fn is_running(&self, job_id: &str) -> Result<bool, JobsError> {
+        let row = self
+            .pool
+            .job(self.schema, job_id)
+            .await?
+            .ok_or_else(|| JobsError::NotFound(job_id.to_string()))?;
 
-        Ok(rows.into_iter().map(|row| row.job_id).collect())
+        Ok(row.status == "running")
     }
 
-    pub async fn is_running(&self, job_id: &str) -> Result<bool, JobsError> {
-        let row = sqlx::query_scalar::<_, String>("SELECT status FROM jobs WHERE job_id = $1")
-            .bind(job_id)
-            .fetch_optional(self.pool.as_ref())
+    pub async fn update_progress(&self, job_id: &str, height: i32) -> Result<(), JobsError> {
+        // Progress ends a failure streak, so automatic retries start counting from zero again.
+        self.pool.update_job_progress(self.schema, job_id, height).await?;
+        Ok(())
+    }
+
+    /// Last height committed together with a block of the job, as `(height, hash)`.
+    pub async fn checkpoint(&self, job_id: &str) -> Result<Option<(i32, String)>, JobsError> {
+        if !self.schema.job_checkpoints {
+            return Ok(None);
+        }
+
+        let row = self
+            .pool
+            .job_checkpoint(job_id)
             .await?
-            .ok_or(JobsError::NotFound)?;
+            .ok_or_else(|| JobsError::NotFound(job_id.to_string()))?;
 
-        Ok(row == "running")
+        Ok(row.0.zip(row.1))
     }
 
-    pub async fn update_progress(&self, job_id: &str, height: i32) -> Result<(), JobsError> {
-        sqlx::query(
-            "UPDATE jobs \
-             SET progress_height = GREATEST(progress_height, $2), updated_at = NOW(), last_error = NULL \
-             WHERE job_id = $1",
-        )
-        .bind(job_id)
-        .bind(height)
-        .execute(self.pool.as_ref())
-        .await?;
+    /// Rewinds the progress of every job to at most `height`. A checkpoint from `height` up
+    /// moves to the canonical block at `height`, which the job checks again before resuming,
+    /// or is dropped when that height is not indexed.
+    pub async fn rewind_all_progress(&self, height: i32) -> Result<(), JobsError> {
+        self.rewind_progress(None, height).await
+    }
 
+    /// Same as [`Self::rewind_all_progress`] for `job_id` only, or every job with `None`.
+    pub async fn rewind_progress(&self, job_id: Option<&str>, height: i32) -> Result<(), JobsError> {
+        self.pool.rewind_job_progress(self.schema, job_id, height).await?;
         Ok(())
     }
 
-    pub async fn rewind_all_progress(&self, height: i32) -> Result<(), JobsError> {
-        sqlx::query(
-            "UPDATE jobs \
-             SET progress_height = LEAST(progress_height, $1), updated_at = NOW()",
-        )
-        .bind(height)
-        .execute(self.pool.as_ref())
-        .await?;
+    pub async fn mark_completed(&self, job_id: &str) -> Result<(), JobsError> {
+        let mut tx = self.pool.begin_jobs(self.schema).await?;
+        let completed = tx.lock_job_status(job_id).await?.as_deref() == Some("running");
+        if completed {
+            tx.set_job_status(&JobStatusUpdate {
+                job_id,
+                status: "completed",
+                last_error: LastError::Clear,
+                count_retry: false,
+            })
+            .await?;
+            self.record_status_change(&mut tx, job_id, "complete", "running", "completed", None)
+                .await?;
+        }
+        tx.commit().await?;
 
         Ok(())
     }
 
-    pub async fn mark_failed(&self, job_id: &str, message: &str) -> Result<(), JobsError> {
-        sqlx::query(
-            "UPDATE jobs \
-             SET status = 'failed', last_error = $2, updated_at = NOW() \
-             WHERE job_id = $1",
-        )
-        .bind(job_id)
-        .bind(message)
-        .execute(self.pool.as_ref())
+    pub async fn mark_failed(
+        &self,
+        job_id: &str,
+        category: JobErrorCategory,
+        block_height: Option<i32>,
+        message: &str,
+    ) -> Result<(), JobsError> {
+        let mut tx = self.pool.begin_jobs(self.schema).await?;
+
+        let Some(previous) = tx.lock_job_status(job_id).await? else {
+            return Ok(());
+        };
+        tx.set_job_status(&JobStatusUpdate {
+            job_id,
+            status: "failed",
+            last_error: LastError::Set(message),
+            count_retry: false,
+        })
         .await?;
+        if previous != "failed" {
+            self.record_status_change(&mut tx, job_id, "fail", &previous, "failed", Some(message))
+                .await?;
+        }
+
+        // Before `0029_job_error_panics.sql` the category check rejects `panic`; the job is still
+        // marked failed, but is not retried automatically.
+        let recordable = category != JobErrorCategory::Panic || self.schema.job_error_panics;
+        if self.schema.job_errors && recordable {
+            tx.insert_job_error(&JobErrorRecord {
+                job_id: job_id.to_string(),
+                category: category.as_str().to_string(),
+                block_height,
+                message: message.to_string(),
+            })
+            .await?;
+        }
 
+        tx.commit().await?;
         Ok(())
     }
 
-    async fn transition(&self, job_id: &str, action: JobAction) -> Result<JobDetails, JobsError> {
-        let row: JobRow = sqlx::query_as(
-            "SELECT job_id, mode, status, progress_height, updated_at, last_error \
-             FROM jobs \
-             WHERE job_id = $1",
-        )
-        .bind(job_id)
-        .fetch_optional(self.pool.as_ref())
-        .await?
-        .ok_or(JobsError::NotFound)?;
+    /// Descriptor of a `descriptor` mode job with its effective gap limit, for the configured network.
+    fn watch_descriptor(&self, job: &JobConfig) -> Result<Option<(WatchDescriptor, u32)>, JobsError> {
+        let Some(descriptor) = job.descriptor.as_deref().filter(|_| job.mode == "descriptor") else {
+            return Ok(None);
+        };
 
-        let next = transition_target(action, &row.status)?;
+        if let Some(chain) = self.chain.as_ref().filter(|chain| chain.chain != Chain::Bitcoin) {
+            return Err(JobsError::Validation(format!(
+                "descriptor mode is only supported for chain bitcoin, not {}",
+                chain.chain.name()
+            )));
+        }
+        let network = self.chain.as_ref().map_or(Network::Bitcoin, ChainParams::bitcoin_network);
+        let descriptor = WatchDescriptor::parse(descriptor, network)
+            .map_err(|reason| JobsError::Validation(format!("descriptor: {reason}")))?;
+        Ok(Some((descriptor, job.gap_limit.unwrap_or(descriptors::DEFAULT_GAP_LIMIT))))
+    }
 
-        let last_error = if matches!(action, JobAction::Start | JobAction::Resume | JobAction::Retry) {
-            None::<String>
-        } else {
-            row.last_error.clone()
-        };
+    fn ensure_schema_supports(&self, job: &JobConfig) -> Result<(), JobsError> {
+        if job.mode == "height_range" && !self.schema.jobs_height_range {
+            return Err(JobsError::Validation(
+                "height_range mode requires migration 0004_jobs_height_range; schema compat mode is active"
+                    .to_string(),
+            ));
+        }
 
-        sqlx::query(
-            "UPDATE jobs \
-             SET status = $2, updated_at = NOW(), last_error = $3 \
-             WHERE job_id = $1",
-        )
-        .bind(job_id)
-        .bind(next)
-        .bind(last_error)
-        .execute(self.pool.as_ref())
-        .await?;
+        if job.mode == "descriptor" && !self.schema.job_derived_addresses {
+            return Err(JobsError::Validation(
+                "descriptor mode requires migration 0012_job_derived_addresses; schema compat mode is active"
+                    .to_string(),
+            ));
+        }
 
-        self.get(job_id).await
-    }
-}
+        if job.mode == "export" && !(self.schema.job_exports && self.schema.jobs_height_range) {
+            return Err(JobsError::Validation(
+                "export mode requires migration 0014_job_exports; schema compat mode is active".to_string(),
+            ));
+        }
 
-impl JobsRunner {
-    pub fn new(
-        jobs: JobsService,
-        rpc: RpcClient,
-        indexer: IndexerService,
-        metrics: MetricsService,
-        config: JobsRunnerConfig,
-    ) -> Self {
-        Self {
-            jobs,
-            rpc,
-            indexer,
-            metrics,
-            config,
-            active_jobs: Arc::new(Mutex::new(HashSet::new())),
+        if job.mode == "verify" && !(self.schema.job_discrepancies && self.schema.jobs_height_range) {
+            return Err(JobsError::Validation(
+                "verify mode requires migration 0031_job_verify; schema compat mode is active".to_string(),
+            ));
         }
-    }
 
-    pub fn start(&self) {
-        let jobs = self.jobs.clone();
-        let rpc = self.rpc.clone();
-        let indexer = self.indexer.clone();
-        let metrics = self.metrics.clone();
-        let active_jobs = self.active_jobs.clone();
-        let config = self.config.clone();
+        if !job.protocols.is_empty() && !self.schema.runes {
+            return Err(JobsError::Validation(
+                "protocols require migration 0035_runes; schema compat mode is active".to_string(),
+            ));
+        }
 
-        tokio::spawn(async move {
-            let semaphore = Arc::new(Semaphore::new(config.max_jobs.max(1)));
+        Ok(())
+    }
 
-            loop {
-                if let Err(err) = schedule_running_jobs(
-                    &jobs,
-                    &rpc,
-                    &indexer,
-                    &metrics,
-                    &active_jobs,
-                    &semaphore,
-                    config.blocks_per_batch,
-                    config.reorg_depth,
-                )
-                .await
-                {
-                    warn!(component = "jobs", error = %err, message = "job scheduler iteration failed");
-                }
+    /// Queues a `job_status` event of the SSE stream in the transaction of the change.
+    async fn record_status_change(
+        &self,
+        tx: &mut S::Tx,
+        job_id: &str,
+        action: &str,
+        from_status: &str,
+        to_status: &str,
+        reason: Option<&str>,
+    ) -> Result<(), JobsError> {
+        if !self.schema.stream_events {
+            return Ok(());
+        }
 
-                tokio::time::sleep(config.poll_interval).await;
-            }
+        let payload = serde_json::json!({
+            "job_id": job_id,
+            "action": action,
+            "from_status": from_status,
+            "to_status": to_status,
+            "reason": reason,
         });
+        StreamEventsRepo
+            .insert(postgres(tx, "stream events")?, "job_status", &payload)
+            .await?;
+        Ok(())
     }
-}
 
-async fn schedule_running_jobs(
-    jobs: &JobsService,
-    rpc: &RpcClient,
-    indexer: &IndexerService,
-    metrics: &MetricsService,
-    active_jobs: &Arc<Mutex<HashSet<String>>>,
-    semaphore: &Arc<Semaphore>,
-    blocks_per_batch: u32,
-    reorg_depth: u32,
-) -> Result<(), JobsError> {
-    for job_id in jobs.running_job_ids().await? {
-        let permit = match semaphore.clone().try_acquire_owned() {
-            Ok(permit) => permit,
-            Err(_) => break,
+    /// Applies `action` to the locked job row, so of two concurrent requests for the same
+    /// transition only one succeeds and the other gets `InvalidTransition`.
+    /// The transition is recorded in `job_events` with `reason`.
+    async fn transition(
+        &self,
+        job_id: &str,
+        action: JobAction,
+        reason: Option<&str>,
+    ) -> Result<JobDetails, JobsError> {
+        if reason.is_some_and(|reason| reason.chars().count() > MAX_REASON_CHARS) {
+            return Err(JobsError::Validation(format!(
+                "reason MUST be at most {MAX_REASON_CHARS} characters"
+            )));
+        }
+
+        let (from, next) = transition_rule(action);
+        let clears_error = matches!(action, JobAction::Start | JobAction::Resume | JobAction::Retry);
+
+        // The locked row is read again after a concurrent transition commits, so the
+        // status check sees its outcome.
+        let mut tx = self.pool.begin_jobs(self.schema).await?;
+        let previous = tx
+            .lock_job_status(job_id)
+            .await?
+            .ok_or_else(|| JobsError::NotFound(job_id.to_string()))?;
+        if !from.contains(&previous.as_str()) {
+            return Err(JobsError::InvalidTransition {
+                job_id: job_id.to_string(),
+                current_status: previous,
+                requested: action.as_str().to_string(),
+            });
+        }
+
+        tx.set_job_status(&JobStatusUpdate {
+            job_id,
+            status: next,
+            last_error: if clears_error { LastError::Clear } else { LastError::Keep },
+            count_retry: matches!(action, JobAction::Retry) && self.schema.job_errors,
+        })
+        .await?;
+        if self.schema.job_events {
+            tx.insert_job_event(&JobEventRecord {
+                job_id: job_id.to_string(),
+                action: action.as_str().to_string(),
+                from_status: previous.clone(),
+                to_status: next.to_string(),
+                reason: reason.map(str::to_string),
+            })
+            .await?;
+        }
+        self.record_status_change(&mut tx, job_id, action.as_str(), &previous, next, reason)
+            .await?;
+        tx.commit().await?;
+
+        self.get(job_id).await
+    }
+}
+
+/// Job queries only PostgreSQL has: config sync, addresses, listings, estimates, errors,
+/// gaps and the runner bookkeeping.
+impl JobsService {
+    /// Serves [`Self::list_filtered`] from `pool`, e.g. a read replica; everything else,
+    /// including reads that must see a write just made, stays on the primary.
+    pub fn with_read_pool(mut self, pool: PgPool) -> Self {
+        self.read_pool = pool;
+        self
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Upserts YAML jobs in one transaction, keeping runtime state.
+    ///
+    /// Only `mode`, `config_snapshot`, the height range and addresses come from
+    /// the config; `status`, `progress_height` and `updated_at` of an unchanged
+    /// job stay as they are, so a restart mid-backfill neither resets nor
+    /// touches it. Instances syncing at the same time apply the YAML one by one.
+    /// Addresses added through the API are kept when the schema records where
+    /// each address came from.
+    pub async fn sync_from_config(&self, jobs: &[JobConfig]) -> Result<(), JobsError> {
+        let mut jobs: Vec<&JobConfig> = jobs.iter().collect();
+        // Stable row lock order, in addition to the advisory lock.
+        jobs.sort_by(|left, right| left.job_id.cmp(&right.job_id));
+        let mut watched = Vec::new();
+        for job in &jobs {
+            self.ensure_schema_supports(job)?;
+            if let Some(descriptor) = self.watch_descriptor(job)? {
+                watched.push((job.job_id.as_str(), descriptor));
+            }
+        }
+
+        let job_ids: Vec<&str> = jobs.iter().map(|job| job.job_id.as_str()).collect();
+        let modes: Vec<&str> = jobs.iter().map(|job| job.mode.as_str()).collect();
+        let mut snapshots = jobs
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()?;
+        let (address_job_ids, addresses): (Vec<&str>, Vec<&str>) = jobs
+            .iter()
+            .flat_map(|job| job.addresses.iter().map(|address| (job.job_id.as_str(), address.as_str())))
+            .unzip();
+        // Addresses of descriptor jobs are derived, not listed in the YAML.
+        let listed_job_ids: Vec<&str> = jobs
+            .iter()
+            .filter(|job| job.mode != "descriptor")
+            .map(|job| job.job_id.as_str())
+            .collect();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(JOBS_SYNC_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+
+        if self.schema.job_address_sources {
+            let api_addresses: Vec<(String, String)> = sqlx::query_as(
+                "SELECT job_id, address \
+                 FROM job_addresses \
+                 WHERE job_id = ANY($1) AND source = 'api'",
+            )
+            .bind(&listed_job_ids)
+            .fetch_all(&mut *tx)
+            .await?;
+            list_api_addresses(&jobs, &mut snapshots, &api_addresses);
+        }
+
+        sqlx::query(
+            "INSERT INTO jobs \
+             (job_id, mode, status, progress_height, config_snapshot, updated_at) \
+             SELECT job_id, mode, 'created', 0, config_snapshot, NOW() \
+             FROM UNNEST($1::TEXT[], $2::TEXT[], $3::JSONB[]) AS job(job_id, mode, config_snapshot) \
+             ON CONFLICT (job_id) DO UPDATE SET \
+               mode = EXCLUDED.mode, \
+               config_snapshot = EXCLUDED.config_snapshot, \
+               updated_at = NOW() \
+             WHERE jobs.mode IS DISTINCT FROM EXCLUDED.mode \
+                OR jobs.config_snapshot IS DISTINCT FROM EXCLUDED.config_snapshot",
+        )
+        .bind(&job_ids)
+        .bind(&modes)
+        .bind(&snapshots)
+        .execute(&mut *tx)
+        .await?;
+
+        for job in &jobs {
+            self.store_height_range(&mut tx, job).await?;
+        }
+
+        let config_sourced = if self.schema.job_address_sources { "AND a.source = 'config' " } else { "" };
+        sqlx::query(&format!(
+            "DELETE FROM job_addresses a \
+             WHERE a.job_id = ANY($1) \
+               {config_sourced}\
+               AND NOT EXISTS ( \
+                 SELECT 1 \
+                 FROM UNNEST($2::TEXT[], $3::TEXT[]) AS c(job_id, address) \
+                 WHERE c.job_id = a.job_id AND c.address = a.address \
+               )"
+        ))
+        .bind(&listed_job_ids)
+        .bind(&address_job_ids)
+        .bind(&addresses)
+        .execute(&mut *tx)
+        .await?;
+
+        if self.schema.job_derived_addresses {
+            sqlx::query("DELETE FROM job_derived_addresses WHERE job_id = ANY($1)")
+                .bind(&listed_job_ids)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO job_addresses (job_id, address) \
+             SELECT job_id, address FROM UNNEST($1::TEXT[], $2::TEXT[]) AS c(job_id, address) \
+             ON CONFLICT (job_id, address) DO NOTHING",
+        )
+        .bind(&address_job_ids)
+        .bind(&addresses)
+        .execute(&mut *tx)
+        .await?;
+
+        for (job_id, (descriptor, gap_limit)) in &watched {
+            sync_descriptor_window(&mut tx, job_id, descriptor, *gap_limit).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn add_addresses(
+        &self,
+        job_id: &str,
+        request: AddJobAddressesRequest,
+    ) -> Result<JobDetails, JobsError> {
+        let addresses = normalize_addresses(request.addresses);
+        if addresses.is_empty() {
+            return Err(JobsError::Validation("addresses MUST be non-empty".to_string()));
+        }
+        self.ensure_network_addresses(&addresses)?;
+
+        let mut tx = self.pool.begin().await?;
+        let mode = lock_job_mode(&mut tx, job_id).await?;
+        ensure_address_list(&mode)?;
+
+        let mut added = Vec::new();
+        for address in addresses {
+            let inserted = sqlx::query(store::api_address_insert(self.schema))
+                .bind(job_id)
+                .bind(&address)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            if inserted == 1 {
+                added.push(address);
+            }
+        }
+
+        sync_snapshot_addresses(&mut tx, job_id).await?;
+        tx.commit().await?;
+
+        if request.backfill {
+            for address in &added {
+                backfill_address(&self.pool, address).await?;
+            }
+        }
+
+        self.get(job_id).await
+    }
+
+    pub async fn remove_address(&self, job_id: &str, address: &str) -> Result<JobDetails, JobsError> {
+        let mut tx = self.pool.begin().await?;
+        let mode = lock_job_mode(&mut tx, job_id).await?;
+        ensure_address_list(&mode)?;
+
+        let deleted = sqlx::query("DELETE FROM job_addresses WHERE job_id = $1 AND address = $2")
+            .bind(job_id)
+            .bind(address)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if deleted == 0 {
+            return Err(JobsError::AddressNotFound {
+                job_id: job_id.to_string(),
+                address: address.to_string(),
+            });
+        }
+
+        let remaining = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM job_addresses WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        if remaining == 0 {
+            return Err(JobsError::Validation(
+                "addresses MUST be non-empty for address_list mode".to_string(),
+            ));
+        }
+
+        sync_snapshot_addresses(&mut tx, job_id).await?;
+        tx.commit().await?;
+
+        self.get(job_id).await
+    }
+
+    pub async fn activate_enabled_jobs(&self, jobs: &[JobConfig]) -> Result<(), JobsError> {
+        for job in jobs.iter().filter(|job| job.enabled) {
+            let status = sqlx::query_scalar::<_, String>(
+                "SELECT status
+                 FROM jobs
+                 WHERE job_id = $1",
+            )
+            .bind(&job.job_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| JobsError::NotFound(job.job_id.clone()))?;
+
+            match status.as_str() {
+                "created" => {
+                    self.start(&job.job_id, Some("enabled in config")).await?;
+                }
+                "paused" => {
+                    self.resume(&job.job_id, Some("enabled in config")).await?;
+                }
+                "failed" => {
+                    self.retry(&job.job_id, Some("enabled in config")).await?;
+                }
+                "running" | "completed" => {}
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn list(&self) -> Result<Vec<JobSummary>, JobsError> {
+        let rows: Vec<JobRow> = sqlx::query_as(&format!(
+            "SELECT job_id, mode, status, progress_height, {}, updated_at, last_error \
+             FROM jobs \
+             ORDER BY job_id",
+            self.optional_columns()
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(JobSummary::from).collect())
+    }
+
+    /// Page of jobs matching `filter`, with the total count of matching jobs.
+    pub async fn list_filtered(&self, filter: &JobsListFilter) -> Result<JobsPage, JobsError> {
+        let (order_by, offset, limit) = validate_jobs_list_filter(filter)?;
+        let condition = "($1::TEXT IS NULL OR status = $1) AND ($2::TEXT IS NULL OR mode = $2)";
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM jobs WHERE {condition}"))
+            .bind(&filter.status)
+            .bind(&filter.mode)
+            .fetch_one(&self.read_pool)
+            .await?;
+        let rows: Vec<JobRow> = sqlx::query_as(&format!(
+            "SELECT job_id, mode, status, progress_height, {}, updated_at, last_error \
+             FROM jobs \
+             WHERE {condition} \
+             ORDER BY {order_by} \
+             LIMIT $3 OFFSET $4",
+            self.optional_columns()
+        ))
+        .bind(&filter.status)
+        .bind(&filter.mode)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        Ok(JobsPage {
+            items: rows.into_iter().map(JobSummary::from).collect(),
+            offset,
+            limit,
+            total,
+        })
+    }
+
+    /// Estimates the remaining sync time from the recent rate, weighting the
+    /// remaining heights by the network's per-era average block weight.
+    pub async fn eta(&self, job_id: &str) -> Result<JobEta, JobsError> {
+        let details = self.get(job_id).await?;
+        let target_height = details
+            .tip_height
+            .map(|tip_height| details.to_height.map_or(tip_height, |to_height| std::cmp::min(to_height, tip_height)));
+        let weighted_chain = self.chain.as_ref().filter(|chain| chain.has_block_weight_profile());
+        let (model, eta_seconds) = match (weighted_chain, target_height) {
+            (Some(chain), Some(target_height)) => (
+                "block_weight",
+                weighted_eta(chain, details.progress_height, target_height, details.blocks_per_sec),
+            ),
+            (Some(_), None) => ("block_weight", None),
+            (None, _) => ("linear", details.eta_seconds),
         };
 
-        let should_spawn = {
-            let mut active = active_jobs.lock().await;
-            active.insert(job_id.clone())
+        Ok(JobEta {
+            job_id: details.job_id,
+            status: details.status,
+            progress_height: details.progress_height,
+            target_height,
+            remaining_blocks: details.remaining_blocks,
+            blocks_per_sec: details.blocks_per_sec,
+            model: model.to_string(),
+            eta_seconds,
+            linear_eta_seconds: details.eta_seconds,
+            estimated_completion_at: eta_seconds.map(|seconds| Utc::now() + chrono::Duration::seconds(seconds)),
+        })
+    }
+
+    /// Projects blocks, transactions, storage and duration for the job's range
+    /// without changing its state. Samples are taken from already indexed data,
+    /// so estimates stay `null` until something has been indexed. Without a known node
+    /// tip the range ends at the synced header tip.
+    pub async fn estimate(&self, job_id: &str, tip_height: Option<i32>) -> Result<JobEstimate, JobsError> {
+        let details = self.get(job_id).await?;
+        let tip_height = match tip_height {
+            Some(tip_height) => Some(tip_height),
+            None => headers::header_tip(&self.pool).await?.map(|(height, _)| height),
+        };
+        let from_height = details.from_height.unwrap_or(0);
+        let to_height = match (details.to_height, tip_height) {
+            (Some(to_height), Some(tip_height)) => Some(std::cmp::min(to_height, tip_height)),
+            (to_height, tip_height) => to_height.or(tip_height),
+        };
+        let blocks = to_height.map(|to_height| (i64::from(to_height) - i64::from(from_height) + 1).max(0));
+        let indexed_blocks = match to_height {
+            Some(to_height) if from_height <= to_height => Some(
+                sqlx::query_scalar::<_, i64>(
+                    "SELECT COUNT(*) FROM blocks WHERE status = 'canonical' AND height BETWEEN $1 AND $2",
+                )
+                .bind(from_height)
+                .bind(to_height)
+                .fetch_one(&self.pool)
+                .await?,
+            ),
+            Some(_) => Some(0),
+            None => None,
+        };
+        let blocks_to_index = blocks.zip(indexed_blocks).map(|(blocks, indexed)| (blocks - indexed).max(0));
+
+        let sample: TxSampleRow = sqlx::query_as(
+            "WITH sample AS ( \
+               SELECT height FROM blocks WHERE status = 'canonical' ORDER BY height DESC LIMIT $1 \
+             ) \
+             SELECT (SELECT COUNT(*) FROM sample) AS blocks, \
+                    (SELECT COUNT(*) FROM transactions t JOIN sample s ON s.height = t.block_height \
+                     WHERE t.status = 'confirmed') AS txs",
+        )
+        .bind(ESTIMATE_SAMPLE_BLOCKS)
+        .fetch_one(&self.pool)
+        .await?;
+        let estimated_txs = blocks_to_index
+            .filter(|_| sample.blocks > 0)
+            .map(|blocks| (blocks as f64 * sample.txs as f64 / sample.blocks as f64).round() as i64);
+        let estimated_storage_bytes = match (estimated_txs, self.bytes_per_tx().await?) {
+            (Some(txs), Some(bytes_per_tx)) => Some((txs as f64 * bytes_per_tx).round() as i64),
+            _ => None,
         };
 
-        if !should_spawn {
-            drop(permit);
-            continue;
+        let (rate_source, reference) = match details.blocks_per_sec.filter(|rate| *rate > 0.0) {
+            Some(rate) => (Some("job"), Some((details.progress_height, rate))),
+            None => match self.running_rate().await? {
+                Some(reference) => (Some("running_jobs"), Some(reference)),
+                None => (None, None),
+            },
+        };
+        let weighted_chain = self.chain.as_ref().filter(|chain| chain.has_block_weight_profile());
+        let model = if weighted_chain.is_some() { "block_weight" } else { "linear" };
+        let estimated_duration_seconds = match (to_height, blocks, blocks_to_index, reference) {
+            (_, _, Some(0), _) => Some(0),
+            (Some(to_height), Some(blocks), Some(blocks_to_index), Some((reference_height, rate))) => {
+                let range_seconds = match weighted_chain {
+                    Some(chain) => weighted_duration(chain, reference_height, from_height, to_height, Some(rate)),
+                    None => Some((blocks as f64 / rate).ceil() as i64),
+                };
+                // Already indexed heights are skipped, so only the share left to index is charged.
+                range_seconds.map(|seconds| (seconds as f64 * blocks_to_index as f64 / blocks as f64).ceil() as i64)
+            }
+            _ => None,
+        };
+
+        Ok(JobEstimate {
+            job_id: details.job_id,
+            mode: details.mode,
+            status: details.status,
+            from_height,
+            to_height,
+            blocks,
+            indexed_blocks,
+            blocks_to_index,
+            estimated_txs,
+            estimated_storage_bytes,
+            blocks_per_sec: reference.map(|(_, rate)| rate),
+            rate_source: rate_source.map(str::to_string),
+            model: model.to_string(),
+            estimated_duration_seconds,
+        })
+    }
+
+    /// On-disk bytes of indexed data per stored transaction; `None` while nothing is stored.
+    async fn bytes_per_tx(&self) -> Result<Option<f64>, JobsError> {
+        let tables: Vec<String> = INDEXED_DATA_TABLES.iter().map(|table| table.to_string()).collect();
+        let (data_bytes, mut txs): (i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(pg_total_relation_size(to_regclass(name))), 0)::BIGINT, \
+                    GREATEST(COALESCE((SELECT reltuples FROM pg_class WHERE oid = to_regclass('transactions')), 0), 0)::BIGINT \
+             FROM UNNEST($1::TEXT[]) AS name",
+        )
+        .bind(&tables)
+        .fetch_one(&self.pool)
+        .await?;
+        // Planner statistics are empty until the first ANALYZE; small tables are cheap to count.
+        if txs == 0 {
+            txs = sqlx::query_scalar("SELECT COUNT(*) FROM transactions")
+                .fetch_one(&self.pool)
+                .await?;
         }
 
-        let jobs = jobs.clone();
-        let rpc = rpc.clone();
-        let indexer = indexer.clone();
-        let metrics = metrics.clone();
-        let active_jobs = active_jobs.clone();
+        Ok((txs > 0).then(|| data_bytes as f64 / txs as f64))
+    }
 
-        tokio::spawn(async move {
-            let _permit = permit;
+    /// Most recently updated running job with a known rate, as `(progress_height, blocks_per_sec)`.
+    async fn running_rate(&self) -> Result<Option<(i32, f64)>, JobsError> {
+        if !self.schema.jobs_progress_rate {
+            return Ok(None);
+        }
 
-            if let Err(err) = execute_job_batch(
-                &jobs,
-                &rpc,
-                &indexer,
-                &metrics,
-                &job_id,
-                blocks_per_batch,
-                reorg_depth,
+        let reference = sqlx::query_as(
+            "SELECT progress_height, blocks_per_sec \
+             FROM jobs \
+             WHERE status = 'running' AND blocks_per_sec > 0 \
+             ORDER BY updated_at DESC NULLS LAST \
+             LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(reference)
+    }
+
+    /// Fetches and decodes the next `blocks` blocks the job would index, from the
+    /// job's progress up to `min(to_height, tip_height)`, and reports per-block stats
+    /// without writing anything or changing the job's state. A block that fails is
+    /// reported with its error and the run goes on with the next height. Without a
+    /// known node tip the range ends at the synced header tip.
+    pub async fn dry_run(
+        &self,
+        job_id: &str,
+        blocks: Option<u32>,
+        tip_height: Option<i32>,
+    ) -> Result<JobDryRun, JobsError> {
+        let blocks = validate_dry_run_blocks(blocks)?;
+        let details = self.get(job_id).await?;
+        let indexer = self.indexer.as_ref().ok_or(JobsError::NodeUnavailable)?;
+        let tip_height = match tip_height {
+            Some(tip_height) => Some(tip_height),
+            None => headers::header_tip(&self.pool).await?.map(|(height, _)| height),
+        };
+
+        // Same first height as the runner's next batch.
+        let start_height = details.from_height.unwrap_or(0);
+        let next_height = if details.progress_height < start_height {
+            start_height
+        } else if details.progress_height == 0
+            && !sqlx::query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM blocks WHERE height = 0 AND status = 'canonical')",
             )
+            .fetch_one(&self.pool)
+            .await?
+        {
+            0
+        } else {
+            details.progress_height.saturating_add(1)
+        };
+        let upper_height = [details.to_height, tip_height].into_iter().flatten().min();
+        let last_height = next_height.saturating_add(i32::try_from(blocks - 1).unwrap_or(i32::MAX));
+        let last_height = upper_height.map_or(last_height, |upper| std::cmp::min(upper, last_height));
+
+        let watched: Option<HashSet<String>> = match details.mode.as_str() {
+            "address_list" | "descriptor" => Some(
+                sqlx::query_scalar::<_, String>("SELECT address FROM job_addresses WHERE job_id = $1")
+                    .bind(job_id)
+                    .fetch_all(&self.pool)
+                    .await?
+                    .into_iter()
+                    .collect(),
+            ),
+            _ => None,
+        };
+
+        let mut items = Vec::new();
+        for height in next_height..=last_height {
+            let started = Instant::now();
+            let fetched = indexer.fetch_height(height as u32).await;
+            let fetch_ms = started.elapsed().as_millis() as u64;
+            let mut item = JobDryRunBlock {
+                height,
+                fetch_ms,
+                ..JobDryRunBlock::default()
+            };
+            match fetched {
+                Ok(block) => {
+                    item.hash = Some(block.hash);
+                    item.txs = block.tx.len() as u64;
+                    item.matched_outputs = watched.as_ref().map(|_| 0);
+                    for tx in &block.tx {
+                        item.inputs += tx.vin.iter().filter(|vin| vin.txid.is_some()).count() as u64;
+                        item.outputs += tx.vout.len() as u64;
+                        for vout in &tx.vout {
+                            let (_, address) = vout.script_pub_key.resolve(indexer.address_encoding());
+                            let Some(address) = address else {
+                                item.outputs_without_address += 1;
+                                continue;
+                            };
+                            if let (Some(watched), Some(matched)) = (&watched, item.matched_outputs.as_mut()) {
+                                *matched += u64::from(watched.contains(&address));
+                            }
+                        }
+                    }
+                }
+                Err(err) => item.error = Some(err.to_string()),
+            }
+            items.push(item);
+        }
+
+        Ok(JobDryRun {
+            job_id: details.job_id,
+            mode: details.mode,
+            status: details.status,
+            watched_addresses: watched.as_ref().map(|watched| watched.len() as i64),
+            errors: items.iter().filter(|item| item.error.is_some()).count() as u64,
+            blocks: items,
+        })
+    }
+
+    /// Refetches the indexed canonical block `hash` and rewrites its rows, see
+    /// [`Self::reindex_range`].
+    pub async fn reindex_block(&self, hash: &str) -> Result<BlockReindex, JobsError> {
+        let height: Option<i32> =
+            sqlx::query_scalar("SELECT height FROM blocks WHERE hash = $1 AND status = 'canonical'")
+                .bind(hash)
+                .fetch_optional(&self.pool)
+                .await?;
+        let height = height.ok_or_else(|| JobsError::Validation(format!("block {hash} is not indexed as canonical")))?;
+        self.reindex_range(height, height).await
+    }
+
+    /// Refetches the canonical blocks at `from_height..=to_height` from the node and
+    /// rewrites their transactions, inputs and outputs in place, then rebuilds UTXOs and
+    /// address balances. Job progress is kept, since the blocks stay canonical; repeating
+    /// the request writes the same rows.
+    pub async fn reindex_range(&self, from_height: i32, to_height: i32) -> Result<BlockReindex, JobsError> {
+        let blocks = validate_reindex_range(from_height, to_height)?;
+        let indexer = self.indexer.as_ref().ok_or(JobsError::NodeUnavailable)?;
+        let indexed: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM blocks WHERE status = 'canonical' AND height BETWEEN $1 AND $2",
+        )
+        .bind(from_height)
+        .bind(to_height)
+        .fetch_one(&self.pool)
+        .await?;
+        if indexed != i64::from(blocks) {
+            return Err(JobsError::Validation(format!("heights {from_height}..={to_height} are not all indexed")));
+        }
+
+        let txs = indexer
+            .rewrite_range(from_height, to_height)
             .await
-            {
-                error!(component = "jobs", job_id = %job_id, error = %err, message = "job batch failed");
-                metrics.increment_error("job_batch");
+            .map_err(|err| match err {
+                IndexerError::Rpc(err) => {
+                    warn!(component = "jobs", error = %err, message = "block reindex failed to fetch from node");
+                    JobsError::NodeUnavailable
+                }
+                IndexerError::Storage(err) => JobsError::Storage(err),
+                err @ (IndexerError::NotCanonical { .. } | IndexerError::Hook { .. } | IndexerError::Vetoed { .. }) => {
+                    JobsError::Validation(err.to_string())
+                }
+            })?;
+        info!(component = "jobs", from_height, to_height, txs, message = "blocks reindexed");
+
+        Ok(BlockReindex {
+            from_height,
+            to_height,
+            blocks,
+            txs,
+        })
+    }
+
+    /// Builds descriptors for the job's watch set so the same addresses can be
+    /// imported into a Core wallet and balances compared independently.
+    /// Marks derived addresses of a `descriptor` job that received outputs as used and
+    /// derives more, so `gap_limit` unused addresses follow the last used one on every
+    /// branch. Returns how many addresses were added; other modes are left untouched.
+    pub async fn extend_descriptor_window(&self, job_id: &str) -> Result<usize, JobsError> {
+        let mut tx = self.pool.begin().await?;
+        let snapshot = sqlx::query_scalar::<_, serde_json::Value>(
+            "SELECT config_snapshot \
+             FROM jobs \
+             WHERE job_id = $1 \
+             FOR UPDATE",
+        )
+        .bind(job_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| JobsError::NotFound(job_id.to_string()))?;
+
+        let job: JobConfig = serde_json::from_value(snapshot)?;
+        let Some((descriptor, gap_limit)) = self.watch_descriptor(&job)? else {
+            return Ok(0);
+        };
+
+        let derived = sync_descriptor_window(&mut tx, job_id, &descriptor, gap_limit).await?;
+        tx.commit().await?;
+        Ok(derived)
+    }
+
+    pub async fn descriptors(&self, job_id: &str) -> Result<JobDescriptors, JobsError> {
+        let details = self.get(job_id).await?;
+        if details.mode != "address_list" {
+            return Err(JobsError::Validation(
+                "descriptors are only available for address_list jobs".to_string(),
+            ));
+        }
+
+        // The first witness item of a two-item P2WPKH spend is the key the address commits to.
+        let rows: Vec<WatchedAddressRow> = sqlx::query_as(
+            "SELECT ja.address, \
+                    (SELECT t.decoded -> 'vin' -> i.vin -> 'txinwitness' ->> 1 \
+                     FROM tx_outputs o \
+                     JOIN tx_inputs i ON i.prev_txid = o.txid AND i.prev_vout = o.vout \
+                     JOIN transactions t ON t.txid = i.txid \
+                     WHERE o.address = ja.address \
+                       AND o.script_type = 'witness_v0_keyhash' \
+                       AND jsonb_array_length(t.decoded -> 'vin' -> i.vin -> 'txinwitness') = 2 \
+                     LIMIT 1) AS pubkey, \
+                    (SELECT MIN(t.time) \
+                     FROM tx_outputs o \
+                     JOIN transactions t ON t.txid = o.txid \
+                     WHERE o.address = ja.address AND t.status = 'confirmed') AS first_seen_time \
+             FROM job_addresses ja \
+             WHERE ja.job_id = $1 \
+             ORDER BY ja.address",
+        )
+        .bind(job_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut import_request = Vec::with_capacity(rows.len());
+        let items: Vec<JobDescriptor> = rows
+            .into_iter()
+            .map(|row| {
+                let wpkh = row.pubkey.as_deref().and_then(descriptors::wpkh_descriptor);
+                let (kind, descriptor) = match wpkh {
+                    Some(descriptor) => ("wpkh", Some(descriptor)),
+                    None => ("addr", descriptors::addr_descriptor(&row.address)),
+                };
+                let descriptor = descriptor.ok_or_else(|| {
+                    JobsError::Validation(format!("address '{}' cannot be written as a descriptor", row.address))
+                })?;
+                import_request.push(DescriptorImport {
+                    desc: descriptor.clone(),
+                    timestamp: row.first_seen_time.unwrap_or(0),
+                    label: details.job_id.clone(),
+                });
+
+                Ok(JobDescriptor {
+                    address: row.address,
+                    kind: kind.to_string(),
+                    descriptor,
+                })
+            })
+            .collect::<Result<_, JobsError>>()?;
+
+        Ok(JobDescriptors {
+            job_id: details.job_id,
+            items,
+            import_request,
+        })
+    }
+
+    /// Reserves `key` of `principal` for a job action request, or reports how an earlier
+    /// request with it went. Keys expire after 24 hours. Without `0026_job_events.sql`
+    /// every request is handled as a new one.
+    pub async fn reserve_idempotency_key(
+        &self,
+        principal: &str,
+        key: &str,
+        method: &str,
+        path: &str,
+    ) -> Result<IdempotencyKeyState, JobsError> {
+        if !self.schema.job_events {
+            return Ok(IdempotencyKeyState::Reserved);
+        }
+
+        sqlx::query("DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(hours => $1)")
+            .bind(IDEMPOTENCY_KEY_TTL_HOURS)
+            .execute(&self.pool)
+            .await?;
+        loop {
+            let reserved = sqlx::query(
+                "INSERT INTO idempotency_keys (principal, key, method, path) \
+                 VALUES ($1, $2, $3, $4) \
+                 ON CONFLICT (principal, key) DO NOTHING",
+            )
+            .bind(principal)
+            .bind(key)
+            .bind(method)
+            .bind(path)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+            if reserved > 0 {
+                return Ok(IdempotencyKeyState::Reserved);
+            }
+
+            let row: Option<IdempotencyKeyRow> = sqlx::query_as(
+                "SELECT method, path, status, body \
+                 FROM idempotency_keys \
+                 WHERE principal = $1 AND key = $2",
+            )
+            .bind(principal)
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+            // Otherwise a failed request released the key in between and it is free again.
+            if let Some(row) = row {
+                return Ok(if row.method != method || row.path != path {
+                    IdempotencyKeyState::Mismatch
+                } else if let Some(status) = row.status {
+                    IdempotencyKeyState::Completed {
+                        status: u16::try_from(status).unwrap_or(500),
+                        body: row.body.unwrap_or_default(),
+                    }
+                } else {
+                    IdempotencyKeyState::InProgress
+                });
+            }
+        }
+    }
+
+    /// Stores the response of the request that reserved `key`.
+    pub async fn complete_idempotency_key(
+        &self,
+        principal: &str,
+        key: &str,
+        status: u16,
+        body: &[u8],
+    ) -> Result<(), JobsError> {
+        if !self.schema.job_events {
+            return Ok(());
+        }
+
+        sqlx::query("UPDATE idempotency_keys SET status = $3, body = $4 WHERE principal = $1 AND key = $2")
+            .bind(principal)
+            .bind(key)
+            .bind(i32::from(status))
+            .bind(body)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Frees `key` after a request failed on the server side, so it can be retried.
+    pub async fn release_idempotency_key(&self, principal: &str, key: &str) -> Result<(), JobsError> {
+        if !self.schema.job_events {
+            return Ok(());
+        }
+
+        sqlx::query("DELETE FROM idempotency_keys WHERE principal = $1 AND key = $2 AND status IS NULL")
+            .bind(principal)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Drops `stream_events` past their retention. Runs from the jobs runner, which every
+    /// network has, although blocks and reorgs are queued there by the indexer.
+    pub async fn prune_stream_events(&self) -> Result<u64, JobsError> {
+        if !self.schema.stream_events {
+            return Ok(0);
+        }
+
+        let pruned = StreamEventsRepo::new(&self.pool)
+            .prune(&self.pool, STREAM_EVENTS_RETENTION_HOURS, MAX_STREAM_EVENTS)
+            .await?;
+        Ok(pruned)
+    }
+
+    /// Running jobs with what the scheduler orders them by.
+    async fn running_jobs(&self) -> Result<Vec<RunningJobRow>, JobsError> {
+        let to_height = if self.schema.jobs_height_range { "to_height" } else { "NULL::INT AS to_height" };
+        let tip_height = if self.schema.jobs_progress_rate { "tip_height" } else { "NULL::INT AS tip_height" };
+        let rows = sqlx::query_as(&format!(
+            "SELECT job_id, progress_height, {to_height}, {tip_height}, \
+                    COALESCE((config_snapshot ->> 'priority')::INT, 0) AS priority \
+             FROM jobs \
+             WHERE status = 'running' \
+             ORDER BY job_id"
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    pub async fn update_progress_rate(
+        &self,
+        job_id: &str,
+        tip_height: i32,
+        blocks_per_sec: Option<f64>,
+    ) -> Result<(), JobsError> {
+        if !self.schema.jobs_progress_rate {
+            return Ok(());
+        }
+
+        sqlx::query(
+            "UPDATE jobs \
+             SET tip_height = $2, blocks_per_sec = $3 \
+             WHERE job_id = $1",
+        )
+        .bind(job_id)
+        .bind(tip_height)
+        .bind(blocks_per_sec)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Moves `failed` jobs with a retry policy back to `running` once their
+    /// backoff has elapsed. Returns the ids of retried jobs.
+    pub async fn retry_failed_jobs(&self) -> Result<Vec<String>, JobsError> {
+        if !self.schema.job_errors {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<FailedJobRow> = sqlx::query_as(
+            "SELECT j.job_id, \
+                    j.retry_count, \
+                    j.updated_at, \
+                    j.config_snapshot -> 'retry' AS retry, \
+                    ( \
+                      SELECT e.category \
+                      FROM job_errors e \
+                      WHERE e.job_id = j.job_id \
+                      ORDER BY e.occurred_at DESC, e.id DESC \
+                      LIMIT 1 \
+                    ) AS last_error_category \
+             FROM jobs j \
+             WHERE j.status = 'failed' AND j.config_snapshot ? 'retry' \
+             ORDER BY j.job_id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let now = Utc::now();
+        let mut retried = Vec::new();
+        for row in rows {
+            let policy: JobRetryPolicy = serde_json::from_value(row.retry)?;
+            let Some(failed_at) = row.updated_at else {
+                continue;
+            };
+
+            if !retry_due(&policy, row.retry_count, row.last_error_category.as_deref(), failed_at, now) {
+                continue;
+            }
+
+            match self.retry(&row.job_id, Some("automatic retry")).await {
+                Ok(_) => {
+                    info!(
+                        component = "jobs",
+                        job_id = %row.job_id,
+                        attempt = row.retry_count + 1,
+                        max_attempts = policy.max_attempts,
+                        message = "job retried automatically"
+                    );
+                    retried.push(row.job_id);
+                }
+                // The job was retried or stopped manually in the meantime.
+                Err(JobsError::InvalidTransition { .. }) => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(retried)
+    }
+
+    pub async fn errors(&self, job_id: &str, limit: Option<i64>) -> Result<Vec<JobErrorItem>, JobsError> {
+        let limit = validate_errors_limit(limit)?;
+        let exists = sqlx::query_scalar::<_, String>("SELECT job_id FROM jobs WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !exists {
+            return Err(JobsError::NotFound(job_id.to_string()));
+        }
+
+        if !self.schema.job_errors {
+            return Ok(Vec::new());
+        }
+
+        let rows: Vec<JobErrorRow> = sqlx::query_as(
+            "SELECT id, job_id, occurred_at, category, block_height, retry_count, message \
+             FROM job_errors \
+             WHERE job_id = $1 \
+             ORDER BY occurred_at DESC, id DESC \
+             LIMIT $2",
+        )
+        .bind(job_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(JobErrorItem::from).collect())
+    }
+
+    /// Queues the heights without a canonical block between the start of each indexing
+    /// job and its progress, e.g. left by a crashed backfill, and drops queued heights
+    /// that were indexed since or are no longer below the progress. The job indexes them
+    /// again before its next batch. Returns the newly queued heights.
+    pub async fn scan_gaps(&self) -> Result<u64, JobsError> {
+        if !self.schema.job_gaps {
+            return Ok(0);
+        }
+
+        let jobs: Vec<GapScanRow> = sqlx::query_as(&format!(
+            "SELECT job_id, progress_height, {} FROM jobs WHERE mode <> 'verify'",
+            self.optional_columns()
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut queued = 0;
+        for job in jobs {
+            let (start_height, end_height) = gap_scan_bounds(job.progress_height, job.from_height, job.to_height);
+
+            let mut tx = self.pool.begin().await?;
+            sqlx::query(
+                "DELETE FROM job_gaps g \
+                 WHERE g.job_id = $1 \
+                   AND (g.height < $2 OR g.height > $3 \
+                        OR EXISTS (SELECT 1 FROM blocks b WHERE b.height = g.height AND b.status = 'canonical'))",
+            )
+            .bind(&job.job_id)
+            .bind(start_height)
+            .bind(end_height)
+            .execute(&mut *tx)
+            .await?;
+
+            // A job at its start height has not indexed anything yet.
+            let found = if end_height > start_height {
+                sqlx::query(
+                    "INSERT INTO job_gaps (job_id, height) \
+                     SELECT $1, h FROM generate_series($2::INT, $3::INT) AS h \
+                     WHERE NOT EXISTS (SELECT 1 FROM blocks b WHERE b.height = h AND b.status = 'canonical') \
+                     ORDER BY h \
+                     LIMIT $4 \
+                     ON CONFLICT (job_id, height) DO NOTHING",
+                )
+                .bind(&job.job_id)
+                .bind(start_height)
+                .bind(end_height)
+                .bind(MAX_GAP_HEIGHTS_PER_SCAN)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected()
+            } else {
+                0
+            };
+            tx.commit().await?;
+
+            if found > 0 {
+                warn!(
+                    component = "jobs",
+                    job_id = %job.job_id,
+                    from_height = start_height,
+                    to_height = end_height,
+                    heights = found,
+                    message = "missing heights queued for reindexing"
+                );
+            }
+            queued += found;
+        }
+
+        Ok(queued)
+    }
+
+    /// Lowest heights queued for `job_id` by [`Self::scan_gaps`].
+    pub async fn queued_gaps(&self, job_id: &str, limit: u32) -> Result<Vec<i32>, JobsError> {
+        if !self.schema.job_gaps {
+            return Ok(Vec::new());
+        }
+
+        let heights = sqlx::query_scalar("SELECT height FROM job_gaps WHERE job_id = $1 ORDER BY height LIMIT $2")
+            .bind(job_id)
+            .bind(i64::from(limit))
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(heights)
+    }
+
+    pub async fn remove_gap(&self, job_id: &str, height: i32) -> Result<(), JobsError> {
+        sqlx::query("DELETE FROM job_gaps WHERE job_id = $1 AND height = $2")
+            .bind(job_id)
+            .bind(height)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Discrepancies recorded by a `verify` job, by height; empty for other modes.
+    pub async fn discrepancies(
+        &self,
+        job_id: &str,
+        kind: Option<&str>,
+        limit: Option<i64>,
+    ) -> Result<Vec<DiscrepancyItem>, JobsError> {
+        let limit = validate_errors_limit(limit)?;
+        if let Some(kind) = kind.filter(|kind| !verify::KINDS.contains(kind)) {
+            return Err(JobsError::Validation(format!(
+                "kind MUST be one of {}: {kind}",
+                verify::KINDS.join("|")
+            )));
+        }
+        let exists = sqlx::query_scalar::<_, String>("SELECT job_id FROM jobs WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !exists {
+            return Err(JobsError::NotFound(job_id.to_string()));
+        }
+
+        if !self.schema.job_discrepancies {
+            return Ok(Vec::new());
+        }
+
+        Ok(verify::list(&self.pool, job_id, kind, limit).await?)
+    }
+
+    fn optional_columns(&self) -> String {
+        store::optional_columns(self.schema)
+    }
+
+    async fn store_height_range(&self, conn: &mut PgConnection, job: &JobConfig) -> Result<(), JobsError> {
+        store::store_height_range(conn, self.schema, &job.job_id, job.from_height, job.to_height).await?;
+        Ok(())
+    }
+}
+
+impl JobsRunnerConfig {
+    pub fn from_config(config: &IndexerConfig) -> Self {
+        Self {
+            max_jobs: config.concurrency.max_jobs as usize,
+            poll_interval: Duration::from_millis(config.poll.tip_interval_ms),
+            blocks_per_batch: config.batching.blocks_per_batch,
+            txs_per_batch: config.batching.txs_per_batch,
+            reorg_depth: config.reorg_depth,
+            gap_scan_interval: config.poll.gap_scan_interval_ms.map(Duration::from_millis),
+        }
+    }
+}
+
+impl JobsRunner {
+    pub fn new(
+        jobs: JobsService,
+        rpc: RpcClient,
+        indexer: IndexerService,
+        metrics: MetricsService,
+        config: JobsRunnerConfig,
+    ) -> Self {
+        Self {
+            jobs,
+            rpc,
+            indexer,
+            metrics,
+            config: ReloadableConfig::new(config),
+            active_jobs: Arc::new(Mutex::new(HashSet::new())),
+            progress_windows: Arc::new(Mutex::new(HashMap::new())),
+            throttles: Arc::new(Mutex::new(HashMap::new())),
+            verified_checkpoints: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// `max_jobs` is only read when the runner starts; the other settings apply from the next
+    /// scheduling pass.
+    pub fn update_config(&self, config: JobsRunnerConfig) {
+        self.config.set(config);
+    }
+
+    pub fn start(&self) {
+        let jobs = self.jobs.clone();
+        let rpc = self.rpc.clone();
+        let indexer = self.indexer.clone();
+        let metrics = self.metrics.clone();
+        let active_jobs = self.active_jobs.clone();
+        let progress_windows = self.progress_windows.clone();
+        let throttles = self.throttles.clone();
+        let verified_checkpoints = self.verified_checkpoints.clone();
+        let shared_config = self.config.clone();
+
+        tokio::spawn(async move {
+            let max_jobs = shared_config.get().max_jobs;
+            let semaphore = Arc::new(Semaphore::new(max_jobs.max(1)));
+            let mut scheduler = JobScheduler::default();
+            let mut pruned_at: Option<Instant> = None;
+            let mut gaps_scanned_at: Option<Instant> = None;
+
+            loop {
+                let config = shared_config.get();
+                let indexer = indexer.clone().with_txs_per_batch(config.txs_per_batch);
+                // A panicking iteration must not stop scheduling of every job.
+                let iteration = AssertUnwindSafe(async {
+                    if let Err(err) = jobs.retry_failed_jobs().await {
+                        warn!(component = "jobs", error = %err, message = "automatic job retry failed");
+                    }
+                    if pruned_at.is_none_or(|at| at.elapsed() >= STREAM_EVENTS_PRUNE_INTERVAL) {
+                        pruned_at = Some(Instant::now());
+                        if let Err(err) = jobs.prune_stream_events().await {
+                            warn!(component = "jobs", error = %err, message = "stream events pruning failed");
+                        }
+                    }
+                    if let Some(interval) = config.gap_scan_interval {
+                        if gaps_scanned_at.is_none_or(|at| at.elapsed() >= interval) {
+                            gaps_scanned_at = Some(Instant::now());
+                            if let Err(err) = jobs.scan_gaps().await {
+                                warn!(component = "jobs", error = %err, message = "job gap scan failed");
+                            }
+                        }
+                    }
+
+                    if let Err(err) = schedule_running_jobs(
+                        &jobs,
+                        &rpc,
+                        &indexer,
+                        &metrics,
+                        &active_jobs,
+                        &progress_windows,
+                        &throttles,
+                        &verified_checkpoints,
+                        &semaphore,
+                        &mut scheduler,
+                        config.blocks_per_batch,
+                        config.reorg_depth,
+                    )
+                    .await
+                    {
+                        warn!(component = "jobs", error = %err, message = "job scheduler iteration failed");
+                    }
+                })
+                .catch_unwind()
+                .await;
+                if let Err(panic) = iteration {
+                    error!(
+                        component = "jobs",
+                        error = %alerting::panic_message(panic.as_ref()),
+                        message = "job scheduler iteration panicked"
+                    );
+                    metrics.increment_error("job_panic");
+                }
+
+                tokio::time::sleep(config.poll_interval).await;
+            }
+        });
+    }
+}
+
+impl JobScheduler {
+    fn order(&mut self, jobs: Vec<RunningJobRow>, blocks_per_batch: u32) -> Vec<ScheduledJob> {
+        self.last_started.retain(|job_id, _| jobs.iter().any(|job| &job.job_id == job_id));
+
+        let (mut backfill, mut tip_following): (Vec<RunningJobRow>, Vec<RunningJobRow>) =
+            jobs.into_iter().partition(|job| job.is_backfill(blocks_per_batch));
+        for queue in [&mut backfill, &mut tip_following] {
+            queue.sort_by_cached_key(|job| {
+                (
+                    Reverse(job.priority),
+                    self.last_started.get(&job.job_id).copied().unwrap_or(0),
+                    job.job_id.clone(),
+                )
+            });
+        }
+
+        let (first, second) = if self.backfill_next {
+            (backfill, tip_following)
+        } else {
+            (tip_following, backfill)
+        };
+        let (mut first, mut second) = (first.into_iter(), second.into_iter());
+        let mut order = Vec::new();
+        loop {
+            let (next_first, next_second) = (first.next(), second.next());
+            if next_first.is_none() && next_second.is_none() {
+                break;
+            }
+            order.extend(next_first.into_iter().chain(next_second).map(|job| ScheduledJob {
+                backfill: job.is_backfill(blocks_per_batch),
+                job_id: job.job_id,
+            }));
+        }
+
+        order
+    }
+
+    /// Records that `job` took a slot; the other kind is preferred for the next one.
+    fn record_start(&mut self, job: &ScheduledJob) {
+        self.starts += 1;
+        self.last_started.insert(job.job_id.clone(), self.starts);
+        self.backfill_next = !job.backfill;
+    }
+}
+
+impl JobThrottle {
+    fn new(limits: JobLimits) -> Self {
+        Self {
+            limits,
+            rpc: limits.max_rpc_per_second.map(|rate| Throttle::new(f64::from(rate), rate)),
+            // No burst: blocks are spread evenly over the minute.
+            blocks: limits.max_blocks_per_minute.map(|rate| Throttle::new(f64::from(rate) / 60.0, 1)),
+        }
+    }
+
+    async fn before_block(&self) {
+        if let Some(blocks) = &self.blocks {
+            blocks.acquire().await;
+        }
+    }
+}
+
+impl RunningJobRow {
+    fn is_backfill(&self, blocks_per_batch: u32) -> bool {
+        self.to_height.is_some()
+            || self.tip_height.is_some_and(|tip_height| {
+                i64::from(tip_height) - i64::from(self.progress_height) > i64::from(blocks_per_batch)
+            })
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn schedule_running_jobs(
+    jobs: &JobsService,
+    rpc: &RpcClient,
+    indexer: &IndexerService,
+    metrics: &MetricsService,
+    active_jobs: &Arc<Mutex<HashSet<String>>>,
+    progress_windows: &Arc<Mutex<HashMap<String, ProgressWindow>>>,
+    throttles: &Arc<Mutex<HashMap<String, JobThrottle>>>,
+    verified_checkpoints: &Arc<Mutex<HashSet<String>>>,
+    semaphore: &Arc<Semaphore>,
+    scheduler: &mut JobScheduler,
+    blocks_per_batch: u32,
+    reorg_depth: u32,
+) -> Result<(), JobsError> {
+    let running = jobs.running_jobs().await?;
+    // A job that stopped running has its checkpoint checked again when it is resumed.
+    verified_checkpoints
+        .lock()
+        .await
+        .retain(|job_id| running.iter().any(|job| &job.job_id == job_id));
+    for job in scheduler.order(running, blocks_per_batch) {
+        let permit = match semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => break,
+        };
+
+        let should_spawn = {
+            let mut active = active_jobs.lock().await;
+            active.insert(job.job_id.clone())
+        };
+
+        if !should_spawn {
+            drop(permit);
+            continue;
+        }
+
+        scheduler.record_start(&job);
+        let job_id = job.job_id;
+
+        let jobs = jobs.clone();
+        let rpc = rpc.clone();
+        let indexer = indexer.clone();
+        let metrics = metrics.clone();
+        let active_jobs = active_jobs.clone();
+        let progress_windows = progress_windows.clone();
+        let throttles = throttles.clone();
+        let verified_checkpoints = verified_checkpoints.clone();
+
+        tokio::spawn(async move {
+            let _permit = permit;
+
+            let batch = AssertUnwindSafe(execute_job_batch(
+                &jobs,
+                &rpc,
+                &indexer,
+                &metrics,
+                &progress_windows,
+                &throttles,
+                &verified_checkpoints,
+                &job_id,
+                blocks_per_batch,
+                reorg_depth,
+            ))
+            .catch_unwind()
+            .await;
+            let result = match batch {
+                Ok(result) => result,
+                Err(panic) => {
+                    mark_panicked(&jobs, &metrics, &job_id, &alerting::panic_message(panic.as_ref())).await;
+                    Ok(())
+                }
+            };
+
+            if let Err(err) = result {
+                if err.is_node_warming_up() {
+                    // The job stays running and the batch is retried on the next iteration.
+                    warn!(
+                        component = "jobs",
+                        job_id = %job_id,
+                        error = %err,
+                        message = "node is warming up, batch skipped"
+                    );
+                } else {
+                    error!(component = "jobs", job_id = %job_id, error = %err, message = "job batch failed");
+                    metrics.increment_error("job_batch");
+                    alerting::report_job_failure(
+                        &job_id,
+                        err.category().as_str(),
+                        err.block_height(),
+                        &err.to_string(),
+                    );
+
+                    if let Err(mark_err) = jobs
+                        .mark_failed(&job_id, err.category(), err.block_height(), &err.to_string())
+                        .await
+                    {
+                        error!(
+                            component = "jobs",
+                            job_id = %job_id,
+                            error = %mark_err,
+                            message = "failed to mark job as failed"
+                        );
+                    }
+                }
+            }
+
+            let mut active = active_jobs.lock().await;
+            active.remove(&job_id);
+        });
+    }
+
+    Ok(())
+}
+
+/// Moves a job whose batch panicked to `failed`, so it does not stay `running` without a
+/// task; the retry policy restarts it like a transient error.
+async fn mark_panicked(jobs: &JobsService, metrics: &MetricsService, job_id: &str, panic: &str) {
+    let message = format!("batch panicked: {panic}");
+    error!(component = "jobs", job_id = %job_id, error = %message, message = "job batch panicked");
+    metrics.increment_error("job_panic");
+    alerting::report_job_failure(job_id, JobErrorCategory::Panic.as_str(), None, &message);
+
+    if let Err(err) = jobs.mark_failed(job_id, JobErrorCategory::Panic, None, &message).await {
+        error!(
+            component = "jobs",
+            job_id = %job_id,
+            error = %err,
+            message = "failed to mark panicked job as failed"
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn execute_job_batch(
+    jobs: &JobsService,
+    rpc: &RpcClient,
+    indexer: &IndexerService,
+    metrics: &MetricsService,
+    progress_windows: &Mutex<HashMap<String, ProgressWindow>>,
+    throttles: &Mutex<HashMap<String, JobThrottle>>,
+    verified_checkpoints: &Mutex<HashSet<String>>,
+    job_id: &str,
+    blocks_per_batch: u32,
+    reorg_depth: u32,
+) -> Result<(), JobExecutionError> {
+    if !jobs.is_running(job_id).await? {
+        return Ok(());
+    }
+
+    let mut details = jobs.get(job_id).await?;
+    let throttle = job_throttle(throttles, &details).await;
+    let rpc = &rpc.clone().with_throttle(throttle.rpc.clone());
+    let indexer = &indexer
+        .clone()
+        .with_rpc(rpc.clone())
+        .with_protocols(protocols::enabled(jobs.schema, &job_protocols(&details)));
+
+    if let Some(divergence_height) = indexer.reconcile_chain(reorg_depth).await? {
+        jobs.rewind_all_progress(std::cmp::max(0, divergence_height - 1))
+            .await?;
+        details = jobs.get(job_id).await?;
+    }
+
+    if details.mode == "descriptor" {
+        let derived = jobs.extend_descriptor_window(job_id).await?;
+        if derived > 0 {
+            info!(component = "jobs", job_id = %job_id, derived, message = "descriptor derivation window extended");
+        }
+    }
+
+    if details.mode != "verify" {
+        heal_gaps(jobs, indexer, metrics, &throttle, &details, blocks_per_batch).await?;
+    }
+
+    if details.mode == "export" {
+        return export_job_batch(
+            jobs,
+            rpc,
+            indexer,
+            metrics,
+            progress_windows,
+            &throttle,
+            &details,
+            blocks_per_batch,
+            reorg_depth,
+        )
+        .await;
+    }
+
+    if details.mode == "verify" {
+        return verify_job_batch(
+            jobs,
+            rpc,
+            progress_windows,
+            &throttle,
+            &details,
+            blocks_per_batch,
+            reorg_depth,
+        )
+        .await;
+    }
+
+    // Once per start: later batches follow the tip through `reconcile_chain`. After a
+    // rewind the moved checkpoint is checked again before the next batch.
+    if !verified_checkpoints.lock().await.contains(job_id) {
+        if resume_from_checkpoint(jobs, rpc, indexer, job_id).await? {
+            details = jobs.get(job_id).await?;
+        } else {
+            verified_checkpoints.lock().await.insert(job_id.to_string());
+        }
+    }
+
+    let range = details.from_height.zip(details.to_height);
+    if let Some((_, to_height)) = range {
+        if complete_if_range_done(jobs, indexer, job_id, details.progress_height, to_height).await? {
+            return Ok(());
+        }
+    }
+
+    let tip_height = i32::try_from(rpc.get_block_count().await?).map_err(|_| JobExecutionError::TipOverflow)?;
+    let start_height = range.map(|(from_height, _)| from_height).unwrap_or(0);
+    let upper_height = range.map_or(tip_height, |(_, to_height)| std::cmp::min(to_height, tip_height));
+    let next_height = if details.progress_height < start_height {
+        start_height
+    } else if details.progress_height == 0 && !indexer.has_canonical_block(0).await? {
+        0
+    } else {
+        details.progress_height.saturating_add(1)
+    };
+
+    if next_height > upper_height {
+        report_progress_rate(jobs, progress_windows, job_id, details.progress_height, tip_height).await?;
+        return Ok(());
+    }
+
+    let batch_size = i32::try_from(blocks_per_batch.max(1)).unwrap_or(i32::MAX);
+    let target_height = std::cmp::min(
+        std::cmp::max(details.progress_height, start_height - 1).saturating_add(batch_size),
+        upper_height,
+    );
+
+    // Blocks are fetched ahead into the write queue while earlier ones are written; the
+    // fetcher waits while the queue is full, so it never runs ahead of a slow database.
+    let (queue, mut fetched) = indexer.writer_pool().queue(job_id, metrics.clone());
+    let throttle = &throttle;
+    let progress_from = details.progress_height;
+    let fetch = async move {
+        for height in next_height..=target_height {
+            if queue.is_closed() {
+                break;
+            }
+            throttle.before_block().await;
+            let block = indexer.fetch_for_index(height as u32).await;
+            let failed = block.is_err();
+            if !queue.send((height, block)).await || failed {
+                break;
+            }
+        }
+    };
+    let write = async move {
+        let mut progress_height = progress_from;
+        while let Some((height, block)) = fetched.recv().await {
+            if !jobs.is_running(job_id).await? {
+                break;
+            }
+
+            let span = info_span!("index_block", block_height = height, job_id);
+            let indexed = match block {
+                Ok(block) => {
+                    indexer
+                        .persist_fetched(block, start_height, Some(job_id))
+                        .instrument(span)
+                        .await
+                }
+                Err(err) => Err(err),
+            }
+            .map_err(|err| JobExecutionError::AtHeight {
+                height,
+                source: Box::new(err.into()),
+            })?;
+
+            match indexed {
+                IndexHeightResult {
+                    outcome: PersistBlockOutcome::Indexed,
+                    tx_count,
+                } => {
+                    metrics.increment_blocks_processed(job_id, 1);
+                    metrics.increment_txs_processed(job_id, tx_count);
+                    jobs.update_progress(job_id, height).await?;
+                    progress_height = height;
+                }
+                IndexHeightResult {
+                    outcome: PersistBlockOutcome::AlreadyIndexed,
+                    ..
+                } => {
+                    jobs.update_progress(job_id, height).await?;
+                    progress_height = height;
+                }
+                IndexHeightResult {
+                    outcome: PersistBlockOutcome::WaitingForPreviousHeight,
+                    ..
+                } => {
+                    break;
+                }
+            }
+        }
+        Ok::<_, JobExecutionError>(progress_height)
+    };
+    // The writer drops the queue when it stops, which stops the fetcher as well.
+    let ((), written) = tokio::join!(fetch, write);
+    let progress_height = written?;
+
+    report_progress_rate(jobs, progress_windows, job_id, progress_height, tip_height).await?;
+
+    if let Some((_, to_height)) = range {
+        complete_if_range_done(jobs, indexer, job_id, progress_height, to_height).await?;
+    }
+
+    Ok(())
+}
+
+/// Checks the checkpoint of `job_id` against the node when the job starts or resumes.
+/// A checkpoint whose block the node no longer has at that height was orphaned while the
+/// indexer was down: the stale blocks are reorged out and the job rewinds below them.
+/// Returns whether the progress of the job was rewound.
+async fn resume_from_checkpoint(
+    jobs: &JobsService,
+    rpc: &RpcClient,
+    indexer: &IndexerService,
+    job_id: &str,
+) -> Result<bool, JobExecutionError> {
+    let Some((checkpoint_height, checkpoint_hash)) = jobs.checkpoint(job_id).await? else {
+        return Ok(false);
+    };
+
+    let tip_height = i32::try_from(rpc.get_block_count().await?).map_err(|_| JobExecutionError::TipOverflow)?;
+    if checkpoint_height <= tip_height && rpc.get_block_hash(checkpoint_height as u32).await? == checkpoint_hash {
+        return Ok(false);
+    }
+
+    warn!(
+        component = "jobs",
+        job_id = %job_id,
+        checkpoint_height,
+        checkpoint_hash = %checkpoint_hash,
+        message = "job checkpoint is not in the node's active chain, rewinding"
+    );
+    match indexer.reconcile_below(checkpoint_height).await? {
+        Some(divergence_height) => {
+            jobs.rewind_all_progress(std::cmp::max(0, divergence_height - 1)).await?;
+        }
+        None => {
+            jobs.rewind_progress(Some(job_id), std::cmp::max(0, checkpoint_height - 1)).await?;
+        }
+    }
+
+    Ok(true)
+}
+
+/// Exports the next chunk of an `export` job, indexing heights of the chunk that are not
+/// stored yet. Heights within `reorg_depth` of the tip can still be replaced, so they are
+/// exported once they are deeper.
+#[allow(clippy::too_many_arguments)]
+async fn export_job_batch(
+    jobs: &JobsService,
+    rpc: &RpcClient,
+    indexer: &IndexerService,
+    metrics: &MetricsService,
+    progress_windows: &Mutex<HashMap<String, ProgressWindow>>,
+    throttle: &JobThrottle,
+    details: &JobDetails,
+    blocks_per_batch: u32,
+    reorg_depth: u32,
+) -> Result<(), JobExecutionError> {
+    let job_id = details.job_id.as_str();
+    let job: JobConfig = serde_json::from_value(details.config_snapshot.clone()).map_err(JobsError::from)?;
+    let (Some(config), Some((from_height, to_height))) = (job.export, details.from_height.zip(details.to_height))
+    else {
+        return Err(JobsError::Validation("export job has no export config or height range".to_string()).into());
+    };
+
+    let next_height = export::exported_height(jobs.pool(), job_id)
+        .await?
+        .map_or(from_height, |height| height.saturating_add(1));
+    if next_height > to_height {
+        jobs.mark_completed(job_id).await?;
+        return Ok(());
+    }
+
+    let tip_height = i32::try_from(rpc.get_block_count().await?).map_err(|_| JobExecutionError::TipOverflow)?;
+    let upper_height = std::cmp::min(to_height, tip_height.saturating_sub(reorg_depth as i32));
+    if next_height > upper_height {
+        report_progress_rate(jobs, progress_windows, job_id, details.progress_height, tip_height).await?;
+        return Ok(());
+    }
+
+    let batch_size = i32::try_from(blocks_per_batch.max(1)).unwrap_or(i32::MAX);
+    let chunk_end = std::cmp::min(next_height.saturating_add(batch_size - 1), upper_height);
+    for height in next_height..=chunk_end {
+        if !jobs.is_running(job_id).await? {
+            return Ok(());
+        }
+        if indexer.has_canonical_block(height).await? {
+            continue;
+        }
+
+        throttle.before_block().await;
+        let indexed = indexer
+            .index_height_from(height as u32, from_height)
+            .await
+            .map_err(|err| JobExecutionError::AtHeight {
+                height,
+                source: Box::new(err.into()),
+            })?;
+        match indexed.outcome {
+            PersistBlockOutcome::Indexed => {
+                metrics.increment_blocks_processed(job_id, 1);
+                metrics.increment_txs_processed(job_id, indexed.tx_count);
+            }
+            PersistBlockOutcome::AlreadyIndexed => {}
+            PersistBlockOutcome::WaitingForPreviousHeight => return Ok(()),
+        }
+    }
+
+    let target = ExportTarget::open(&config)?;
+    let files = target
+        .write_chunk(jobs.pool(), jobs.schema, job_id, next_height, chunk_end)
+        .await
+        .map_err(|err| JobExecutionError::AtHeight {
+            height: next_height,
+            source: Box::new(err.into()),
+        })?;
+    export::record_chunk(jobs.pool(), job_id, next_height, chunk_end, &files).await?;
+    jobs.update_progress(job_id, chunk_end).await?;
+    report_progress_rate(jobs, progress_windows, job_id, chunk_end, tip_height).await?;
+    info!(
+        component = "jobs",
+        job_id = %job_id,
+        from_height = next_height,
+        to_height = chunk_end,
+        files = files.len(),
+        message = "export chunk written"
+    );
+
+    if chunk_end >= to_height {
+        jobs.mark_completed(job_id).await?;
+        info!(component = "jobs", job_id = %job_id, to_height, message = "export job completed");
+    }
+
+    Ok(())
+}
+
+/// Heights of a job that are indexed when it has no gaps: from its start height to its
+/// progress, capped by `to_height`.
+fn gap_scan_bounds(progress_height: i32, from_height: Option<i32>, to_height: Option<i32>) -> (i32, i32) {
+    let end_height = to_height.map_or(progress_height, |to_height| progress_height.min(to_height));
+    (from_height.unwrap_or(0), end_height)
+}
+
+/// Indexes up to `blocks_per_batch` heights queued for the job by [`JobsService::scan_gaps`],
+/// lowest first. Each filled height settles the UTXOs, balances and supply stats of the
+/// blocks above it as it is written, so nothing is rebuilt afterwards.
+async fn heal_gaps(
+    jobs: &JobsService,
+    indexer: &IndexerService,
+    metrics: &MetricsService,
+    throttle: &JobThrottle,
+    details: &JobDetails,
+    blocks_per_batch: u32,
+) -> Result<(), JobExecutionError> {
+    let job_id = details.job_id.as_str();
+    let heights = jobs.queued_gaps(job_id, blocks_per_batch.max(1)).await?;
+    if heights.is_empty() {
+        return Ok(());
+    }
+
+    let start_height = details.from_height.unwrap_or(0);
+    let mut healed = 0;
+    for height in heights {
+        if !jobs.is_running(job_id).await? {
+            break;
+        }
+        if !indexer.has_canonical_block(height).await? {
+            throttle.before_block().await;
+            let indexed = indexer
+                .index_height_from(height as u32, start_height)
+                .await
+                .map_err(|err| JobExecutionError::AtHeight {
+                    height,
+                    source: Box::new(err.into()),
+                })?;
+            match indexed.outcome {
+                PersistBlockOutcome::Indexed => {
+                    metrics.increment_blocks_processed(job_id, 1);
+                    metrics.increment_txs_processed(job_id, indexed.tx_count);
+                    healed += 1;
+                }
+                PersistBlockOutcome::AlreadyIndexed => {}
+                // The height below is missing too and not queued yet.
+                PersistBlockOutcome::WaitingForPreviousHeight => break,
+            }
+        }
+        jobs.remove_gap(job_id, height).await?;
+    }
+
+    if healed > 0 {
+        info!(component = "jobs", job_id = %job_id, heights = healed, message = "missing heights indexed");
+    }
+
+    Ok(())
+}
+
+/// Verifies the next chunk of a `verify` job, see [`verify::verify_height`], and replaces
+/// the discrepancies recorded for it. Only indexed heights deeper than `reorg_depth` are
+/// verified; after `to_height` the balances are checked and the job completes.
+async fn verify_job_batch(
+    jobs: &JobsService,
+    rpc: &RpcClient,
+    progress_windows: &Mutex<HashMap<String, ProgressWindow>>,
+    throttle: &JobThrottle,
+    details: &JobDetails,
+    blocks_per_batch: u32,
+    reorg_depth: u32,
+) -> Result<(), JobExecutionError> {
+    let job_id = details.job_id.as_str();
+    let Some((from_height, to_height)) = details.from_height.zip(details.to_height) else {
+        return Err(JobsError::Validation("verify job has no height range".to_string()).into());
+    };
+
+    // `progress_height` 0 of a new job also covers genesis, as for indexing jobs.
+    let next_height = if details.progress_height < from_height || details.progress_height == 0 {
+        from_height
+    } else {
+        details.progress_height.saturating_add(1)
+    };
+    let tip_height = i32::try_from(rpc.get_block_count().await?).map_err(|_| JobExecutionError::TipOverflow)?;
+    let indexed_tip: Option<i32> = sqlx::query_scalar("SELECT MAX(height) FROM blocks WHERE status = 'canonical'")
+        .fetch_one(jobs.pool())
+        .await
+        .map_err(JobsError::from)?;
+    let upper_height = std::cmp::min(
+        std::cmp::min(to_height, tip_height.saturating_sub(reorg_depth as i32)),
+        indexed_tip.unwrap_or(-1),
+    );
+
+    if next_height <= upper_height {
+        let batch_size = i32::try_from(blocks_per_batch.max(1)).unwrap_or(i32::MAX);
+        let chunk_end = std::cmp::min(
+            std::cmp::max(details.progress_height, from_height - 1).saturating_add(batch_size),
+            upper_height,
+        );
+        let history_from_genesis = verify::history_from_genesis(jobs.pool()).await?;
+        let mut discrepancies = Vec::new();
+        for height in next_height..=chunk_end {
+            if !jobs.is_running(job_id).await? {
+                return Ok(());
+            }
+            throttle.before_block().await;
+            let found = verify::verify_height(jobs.pool(), rpc, height, history_from_genesis)
+                .await
+                .map_err(|err| JobExecutionError::AtHeight {
+                    height,
+                    source: Box::new(err.into()),
+                })?;
+            discrepancies.extend(found);
+        }
+
+        verify::record(jobs.pool(), job_id, Some((next_height, chunk_end)), &discrepancies).await?;
+        jobs.update_progress(job_id, chunk_end).await?;
+        report_progress_rate(jobs, progress_windows, job_id, chunk_end, tip_height).await?;
+        if discrepancies.is_empty() {
+            info!(
+                component = "jobs",
+                job_id = %job_id,
+                from_height = next_height,
+                to_height = chunk_end,
+                message = "verify chunk consistent"
+            );
+        } else {
+            warn!(
+                component = "jobs",
+                job_id = %job_id,
+                from_height = next_height,
+                to_height = chunk_end,
+                discrepancies = discrepancies.len(),
+                message = "verify chunk has discrepancies"
+            );
+        }
+        if chunk_end < to_height {
+            return Ok(());
+        }
+    } else if next_height <= to_height {
+        report_progress_rate(jobs, progress_windows, job_id, details.progress_height, tip_height).await?;
+        return Ok(());
+    }
+
+    let balances = verify::verify_balances(jobs.pool()).await?;
+    verify::record(jobs.pool(), job_id, None, &balances).await?;
+    jobs.mark_completed(job_id).await?;
+    info!(
+        component = "jobs",
+        job_id = %job_id,
+        to_height,
+        balance_mismatches = balances.len(),
+        message = "verify job completed"
+    );
+
+    Ok(())
+}
+
+/// `protocols` of a job's `config_snapshot`.
+fn job_protocols(details: &JobDetails) -> Vec<String> {
+    details
+        .config_snapshot
+        .get("protocols")
+        .and_then(|protocols| serde_json::from_value(protocols.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Throttle of `details`' job, kept from earlier batches unless its limits changed.
+async fn job_throttle(throttles: &Mutex<HashMap<String, JobThrottle>>, details: &JobDetails) -> JobThrottle {
+    let limits: JobLimits = serde_json::from_value(details.config_snapshot.clone()).unwrap_or_default();
+    let mut throttles = throttles.lock().await;
+    if limits == JobLimits::default() {
+        throttles.remove(&details.job_id);
+        return JobThrottle::default();
+    }
+
+    throttles
+        .entry(details.job_id.clone())
+        .and_modify(|throttle| {
+            if throttle.limits != limits {
+                *throttle = JobThrottle::new(limits);
+            }
+        })
+        .or_insert_with(|| JobThrottle::new(limits))
+        .clone()
+}
+
+async fn report_progress_rate(
+    jobs: &JobsService,
+    progress_windows: &Mutex<HashMap<String, ProgressWindow>>,
+    job_id: &str,
+    progress_height: i32,
+    tip_height: i32,
+) -> Result<(), JobsError> {
+    let blocks_per_sec = progress_windows
+        .lock()
+        .await
+        .entry(job_id.to_string())
+        .or_default()
+        .record(Instant::now(), progress_height, PROGRESS_RATE_WINDOW);
+
+    jobs.update_progress_rate(job_id, tip_height, blocks_per_sec).await
+}
+
+impl ProgressWindow {
+    fn record(&mut self, at: Instant, progress_height: i32, window: Duration) -> Option<f64> {
+        // A rewind after reorg makes older samples meaningless for the rate.
+        if self
+            .samples
+            .back()
+            .is_some_and(|&(_, last_height)| progress_height < last_height)
+        {
+            self.samples.clear();
+        }
+
+        // Keep the newest sample at or before the window start as the anchor.
+        self.samples.push_back((at, progress_height));
+        while self
+            .samples
+            .get(1)
+            .is_some_and(|&(sampled_at, _)| at.duration_since(sampled_at) > window)
+        {
+            self.samples.pop_front();
+        }
+
+        let &(first_at, first_height) = self.samples.front()?;
+        let elapsed = at.duration_since(first_at).as_secs_f64();
+        if elapsed <= 0.0 {
+            return None;
+        }
+
+        Some(f64::from(progress_height - first_height) / elapsed)
+    }
+}
+
+/// Returns `(remaining_blocks, eta_seconds)` towards the tip, or towards
+/// `to_height` when it is below the tip.
+fn progress_estimate(
+    progress_height: i32,
+    tip_height: Option<i32>,
+    to_height: Option<i32>,
+    blocks_per_sec: Option<f64>,
+) -> (Option<i64>, Option<i64>) {
+    let Some(tip_height) = tip_height else {
+        return (None, None);
+    };
+
+    let target_height = to_height.map_or(tip_height, |to_height| std::cmp::min(to_height, tip_height));
+    let remaining = std::cmp::max(i64::from(target_height) - i64::from(progress_height), 0);
+    let eta_seconds = match blocks_per_sec {
+        _ if remaining == 0 => Some(0),
+        Some(rate) if rate > 0.0 => Some((remaining as f64 / rate).ceil() as i64),
+        _ => None,
+    };
+
+    (Some(remaining), eta_seconds)
+}
+
+/// Weighted ETA: the recent rate is converted to processing cost per second at
+/// the current height and applied to the cost of the remaining heights.
+fn weighted_eta(
+    chain: &ChainParams,
+    progress_height: i32,
+    target_height: i32,
+    blocks_per_sec: Option<f64>,
+) -> Option<i64> {
+    weighted_duration(
+        chain,
+        progress_height,
+        progress_height.saturating_add(1),
+        target_height,
+        blocks_per_sec,
+    )
+}
+
+/// Time to process `from_height..=to_height` at `blocks_per_sec` measured
+/// around `reference_height`, weighted by the per-era block weight.
+fn weighted_duration(
+    chain: &ChainParams,
+    reference_height: i32,
+    from_height: i32,
+    to_height: i32,
+    blocks_per_sec: Option<f64>,
+) -> Option<i64> {
+    let block_cost = |from: i32, to: i32| {
+        let blocks = (i64::from(to) - i64::from(from) + 1).max(0) as u64;
+        chain
+            .expected_weight(from, to)
+            .map(|weight| weight + blocks * BLOCK_OVERHEAD_WEIGHT)
+    };
+    let range_cost = block_cost(from_height, to_height)?;
+    if range_cost == 0 {
+        return Some(0);
+    }
+
+    let reference_cost = block_cost(reference_height, reference_height)?;
+    match blocks_per_sec {
+        Some(rate) if rate > 0.0 => Some((range_cost as f64 / (rate * reference_cost as f64)).ceil() as i64),
+        _ => None,
+    }
+}
+
+async fn complete_if_range_done(
+    jobs: &JobsService,
+    indexer: &IndexerService,
+    job_id: &str,
+    progress_height: i32,
+    to_height: i32,
+) -> Result<bool, JobExecutionError> {
+    if progress_height < to_height || !indexer.has_canonical_block(to_height).await? {
+        return Ok(false);
+    }
+
+    jobs.mark_completed(job_id).await?;
+    info!(component = "jobs", job_id = %job_id, to_height, message = "height range job completed");
+    Ok(true)
+}
+
+impl JobExecutionError {
+    fn category(&self) -> JobErrorCategory {
+        match self {
+            JobExecutionError::Jobs(JobsError::Serialization(_)) => JobErrorCategory::Parse,
+            JobExecutionError::Jobs(_) => JobErrorCategory::Storage,
+            JobExecutionError::Rpc(err) | JobExecutionError::Indexer(IndexerError::Rpc(err)) => rpc_category(err),
+            JobExecutionError::Indexer(IndexerError::Storage(_)) => JobErrorCategory::Storage,
+            // The node moved to another chain under the block.
+            JobExecutionError::Indexer(IndexerError::NotCanonical { .. }) => JobErrorCategory::Rpc,
+            // Hooks of library users: a failure may be transient, a veto is not retried.
+            JobExecutionError::Indexer(IndexerError::Hook { .. }) => JobErrorCategory::Storage,
+            JobExecutionError::Indexer(IndexerError::Vetoed { .. }) => JobErrorCategory::Parse,
+            JobExecutionError::Export(ExportError::Encode { .. }) => JobErrorCategory::Parse,
+            JobExecutionError::Export(_) => JobErrorCategory::Storage,
+            JobExecutionError::Verify(VerifyError::Rpc(err)) => rpc_category(err),
+            JobExecutionError::Verify(VerifyError::Storage(_)) => JobErrorCategory::Storage,
+            JobExecutionError::TipOverflow => JobErrorCategory::Parse,
+            JobExecutionError::AtHeight { source, .. } => source.category(),
+        }
+    }
+
+    fn block_height(&self) -> Option<i32> {
+        match self {
+            JobExecutionError::AtHeight { height, .. } => Some(*height),
+            _ => None,
+        }
+    }
+
+    /// `-28` of a node that is still starting: not a failure of the job.
+    fn is_node_warming_up(&self) -> bool {
+        match self {
+            JobExecutionError::Rpc(RpcError::WarmingUp(_))
+            | JobExecutionError::Indexer(IndexerError::Rpc(RpcError::WarmingUp(_))) => true,
+            JobExecutionError::AtHeight { source, .. } => source.is_node_warming_up(),
+            _ => false,
+        }
+    }
+}
+
+fn rpc_category(err: &RpcError) -> JobErrorCategory {
+    match err {
+        RpcError::Decode(_) => JobErrorCategory::Parse,
+        _ => JobErrorCategory::Rpc,
+    }
+}
+
+impl JobErrorCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobErrorCategory::Rpc => "rpc",
+            JobErrorCategory::Storage => "storage",
+            JobErrorCategory::Parse => "parse",
+            JobErrorCategory::Panic => "panic",
+        }
+    }
+}
+
+fn retry_due(
+    policy: &JobRetryPolicy,
+    retry_count: i32,
+    last_error_category: Option<&str>,
+    failed_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> bool {
+    // Parse errors are deterministic: retrying the same block yields the same failure.
+    if !matches!(last_error_category, Some("rpc" | "storage" | "panic")) {
+        return false;
+    }
+
+    let Ok(retry_count) = u32::try_from(retry_count) else {
+        return false;
+    };
+    if retry_count >= policy.max_attempts {
+        return false;
+    }
+
+    let backoff = chrono::Duration::from_std(policy.backoff(retry_count)).unwrap_or(chrono::Duration::MAX);
+    now >= failed_at + backoff
+}
+
+fn validate_errors_limit(limit: Option<i64>) -> Result<i64, JobsError> {
+    let limit = limit.unwrap_or(DEFAULT_ERRORS_LIMIT);
+    if !(1..=MAX_ERRORS_LIMIT).contains(&limit) {
+        return Err(JobsError::Validation(format!(
+            "limit MUST be between 1 and {MAX_ERRORS_LIMIT}"
+        )));
+    }
 
-                if let Err(mark_err) = jobs.mark_failed(&job_id, &err.to_string()).await {
-                    error!(
-                        component = "jobs",
-                        job_id = %job_id,
-                        error = %mark_err,
-                        message = "failed to mark job as failed"
-                    );
-                }
-            }
+    Ok(limit)
+}
 
-            let mut active = active_jobs.lock().await;
-            active.remove(&job_id);
-        });
+/// Returns the number of heights in the range.
+fn validate_reindex_range(from_height: i32, to_height: i32) -> Result<i32, JobsError> {
+    if from_height < 0 || to_height < from_height {
+        return Err(JobsError::Validation("from_height MUST be >= 0 and <= to_height".to_string()));
+    }
+    let blocks = to_height - from_height + 1;
+    if blocks > MAX_REINDEX_HEIGHTS {
+        return Err(JobsError::Validation(format!("a reindex MUST cover at most {MAX_REINDEX_HEIGHTS} heights")));
     }
 
-    Ok(())
+    Ok(blocks)
 }
 
-async fn execute_job_batch(
-    jobs: &JobsService,
-    rpc: &RpcClient,
-    indexer: &IndexerService,
-    metrics: &MetricsService,
-    job_id: &str,
-    blocks_per_batch: u32,
-    reorg_depth: u32,
-) -> Result<(), JobExecutionError> {
-    if !jobs.is_running(job_id).await? {
-        return Ok(());
+fn validate_dry_run_blocks(blocks: Option<u32>) -> Result<u32, JobsError> {
+    let blocks = blocks.unwrap_or(DEFAULT_DRY_RUN_BLOCKS);
+    if !(1..=MAX_DRY_RUN_BLOCKS).contains(&blocks) {
+        return Err(JobsError::Validation(format!(
+            "blocks MUST be between 1 and {MAX_DRY_RUN_BLOCKS}"
+        )));
     }
 
-    if let Some(divergence_height) = indexer.reconcile_chain(reorg_depth).await? {
-        jobs.rewind_all_progress(std::cmp::max(0, divergence_height - 1))
-            .await?;
+    Ok(blocks)
+}
+
+/// Returns the `ORDER BY` clause, offset and limit of a jobs list request.
+fn validate_jobs_list_filter(filter: &JobsListFilter) -> Result<(String, i64, i64), JobsError> {
+    if let Some(status) = filter.status.as_deref().filter(|status| !JOB_STATUSES.contains(status)) {
+        return Err(JobsError::Validation(format!(
+            "status MUST be one of {}: {status}",
+            JOB_STATUSES.join("|")
+        )));
+    }
+    if let Some(mode) = filter.mode.as_deref().filter(|mode| !JOB_MODES.contains(mode)) {
+        return Err(JobsError::Validation(format!("mode MUST be one of {}: {mode}", JOB_MODES.join("|"))));
     }
 
-    let details = jobs.get(job_id).await?;
-    let tip_height = i32::try_from(rpc.get_block_count().await?).map_err(|_| JobExecutionError::TipOverflow)?;
-    let next_height = if details.progress_height == 0 && !indexer.has_canonical_block(0).await? {
-        0
+    let sort = filter.sort.as_deref().unwrap_or("job_id");
+    let (column, direction) = match sort.strip_prefix('-') {
+        Some(column) => (column, "DESC NULLS LAST"),
+        None => (sort, "ASC NULLS LAST"),
+    };
+    if !JOB_SORT_COLUMNS.contains(&column) {
+        return Err(JobsError::Validation(format!(
+            "sort MUST be one of {}, optionally prefixed with '-': {sort}",
+            JOB_SORT_COLUMNS.join("|")
+        )));
+    }
+    // `job_id` breaks ties, so pages do not overlap.
+    let order_by = if column == "job_id" {
+        format!("job_id {direction}")
     } else {
-        details.progress_height.saturating_add(1)
+        format!("{column} {direction}, job_id")
     };
 
-    if next_height > tip_height {
-        return Ok(());
+    let offset = filter.offset.unwrap_or(0);
+    if offset < 0 {
+        return Err(JobsError::Validation("offset MUST be >= 0".to_string()));
+    }
+    let limit = filter.limit.unwrap_or(DEFAULT_JOBS_LIMIT);
+    if !(1..=MAX_JOBS_LIMIT).contains(&limit) {
+        return Err(JobsError::Validation(format!("limit MUST be between 1 and {MAX_JOBS_LIMIT}")));
     }
 
-    let batch_size = i32::try_from(blocks_per_batch.max(1)).unwrap_or(i32::MAX);
-    let target_height = std::cmp::min(
-        details.progress_height.saturating_add(batch_size),
-        tip_height,
-    );
+    Ok((order_by, offset, limit))
+}
 
-    for height in next_height..=target_height {
-        if !jobs.is_running(job_id).await? {
-            break;
+impl JobAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            JobAction::Start => "start",
+            JobAction::Stop => "stop",
+            JobAction::Pause => "pause",
+            JobAction::Resume => "resume",
+            JobAction::Retry => "retry",
         }
+    }
+}
 
-        match indexer.index_height(height as u32).await? {
-            IndexHeightResult {
-                outcome: PersistBlockOutcome::Indexed,
-                tx_count,
-            } => {
-                metrics.increment_blocks_processed(job_id, 1);
-                metrics.increment_txs_processed(job_id, tx_count);
-                jobs.update_progress(job_id, height).await?;
-            }
-            IndexHeightResult {
-                outcome: PersistBlockOutcome::AlreadyIndexed,
-                ..
-            } => {
-                jobs.update_progress(job_id, height).await?;
-            }
-            IndexHeightResult {
-                outcome: PersistBlockOutcome::WaitingForPreviousHeight,
-                ..
-            } => {
-                break;
-            }
+/// Statuses `action` may be applied in and the status it moves the job to.
+fn transition_rule(action: JobAction) -> (&'static [&'static str], &'static str) {
+    match action {
+        JobAction::Start => (&["created"], "running"),
+        JobAction::Stop => (&["running", "paused", "failed"], "created"),
+        JobAction::Pause => (&["running"], "paused"),
+        JobAction::Resume => (&["paused"], "running"),
+        JobAction::Retry => (&["failed"], "running"),
+    }
+}
+
+/// Connection of `tx` for the job tables only PostgreSQL has.
+fn postgres<'c>(tx: &'c mut impl JobStoreTx, tables: &str) -> Result<&'c mut PgConnection, sqlx::Error> {
+    tx.postgres()
+        .ok_or_else(|| sqlx::Error::Protocol(format!("PostgreSQL storage is needed for {tables}")))
+}
+
+async fn lock_job_mode(tx: &mut PgConnection, job_id: &str) -> Result<String, JobsError> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT mode \
+         FROM jobs \
+         WHERE job_id = $1 \
+         FOR UPDATE",
+    )
+    .bind(job_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| JobsError::NotFound(job_id.to_string()))
+}
+
+/// Adds the API-sourced addresses of each YAML job to its snapshot, sorted like
+/// `sync_snapshot_addresses` stores them, so `config_snapshot.addresses` keeps listing them.
+fn list_api_addresses(jobs: &[&JobConfig], snapshots: &mut [serde_json::Value], api_addresses: &[(String, String)]) {
+    for (job, snapshot) in jobs.iter().zip(snapshots.iter_mut()) {
+        let mut addresses: Vec<&str> = api_addresses
+            .iter()
+            .filter(|(job_id, _)| *job_id == job.job_id)
+            .map(|(_, address)| address.as_str())
+            .collect();
+        if addresses.is_empty() {
+            continue;
+        }
+        addresses.extend(job.addresses.iter().map(String::as_str));
+        addresses.sort_unstable();
+        addresses.dedup();
+        snapshot["addresses"] = serde_json::json!(addresses);
+    }
+}
+
+async fn sync_snapshot_addresses(tx: &mut PgConnection, job_id: &str) -> Result<(), JobsError> {
+    sqlx::query(
+        "UPDATE jobs \
+         SET config_snapshot = jsonb_set( \
+               config_snapshot, \
+               '{addresses}', \
+               (SELECT COALESCE(jsonb_agg(address ORDER BY address COLLATE \"C\"), '[]'::jsonb) \
+                FROM job_addresses \
+                WHERE job_id = $1) \
+             ), \
+             updated_at = NOW() \
+         WHERE job_id = $1",
+    )
+    .bind(job_id)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Brings the derivation window of a descriptor job up to date inside `tx`, which holds the
+/// job row lock. A descriptor whose addresses differ from the stored window starts over.
+async fn sync_descriptor_window(
+    tx: &mut PgConnection,
+    job_id: &str,
+    descriptor: &WatchDescriptor,
+    gap_limit: u32,
+) -> Result<usize, JobsError> {
+    let first_address = descriptor.derive_address(0, 0).map_err(JobsError::Validation)?;
+    let (stored_first_address, max_branch) = sqlx::query_as::<_, (Option<String>, Option<i32>)>(
+        "SELECT MAX(address) FILTER (WHERE branch = 0 AND derivation_index = 0), MAX(branch) \
+         FROM job_derived_addresses \
+         WHERE job_id = $1",
+    )
+    .bind(job_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let branch_count = descriptor.branch_count();
+    let replaced = stored_first_address.as_deref() != Some(first_address.as_str())
+        || max_branch.is_some_and(|branch| branch as usize >= branch_count);
+    if max_branch.is_some() && replaced {
+        sqlx::query("DELETE FROM job_addresses WHERE job_id = $1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM job_derived_addresses WHERE job_id = $1")
+            .bind(job_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    sqlx::query(
+        "UPDATE job_derived_addresses d \
+         SET used = TRUE \
+         WHERE d.job_id = $1 \
+           AND NOT d.used \
+           AND EXISTS (SELECT 1 FROM tx_outputs o WHERE o.address = d.address)",
+    )
+    .bind(job_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let windows: HashMap<i32, (i32, Option<i32>)> = sqlx::query_as::<_, (i32, i32, Option<i32>)>(
+        "SELECT branch, MAX(derivation_index), MAX(derivation_index) FILTER (WHERE used) \
+         FROM job_derived_addresses \
+         WHERE job_id = $1 \
+         GROUP BY branch",
+    )
+    .bind(job_id)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|(branch, derived, last_used)| (branch, (derived, last_used)))
+    .collect();
+
+    let (mut branches, mut indexes, mut addresses) = (Vec::new(), Vec::new(), Vec::new());
+    for branch in 0..branch_count {
+        let (derived, last_used) = match windows.get(&(branch as i32)) {
+            Some(&(derived, last_used)) => (Some(derived), last_used),
+            None => (None, None),
+        };
+        let start = derived.map_or(0, |index| index as u32 + 1);
+        let end = descriptors::derivation_window_end(last_used.map(|index| index as u32), gap_limit);
+        for index in start..end {
+            branches.push(branch as i32);
+            indexes.push(index as i32);
+            addresses.push(descriptor.derive_address(branch, index).map_err(JobsError::Validation)?);
         }
     }
 
+    if addresses.is_empty() {
+        return Ok(0);
+    }
+
+    sqlx::query(
+        "INSERT INTO job_derived_addresses (job_id, branch, derivation_index, address) \
+         SELECT $1, branch, derivation_index, address \
+         FROM UNNEST($2::INT[], $3::INT[], $4::TEXT[]) AS d(branch, derivation_index, address) \
+         ON CONFLICT (job_id, branch, derivation_index) DO NOTHING",
+    )
+    .bind(job_id)
+    .bind(&branches)
+    .bind(&indexes)
+    .bind(&addresses)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO job_addresses (job_id, address) \
+         SELECT $1, address FROM UNNEST($2::TEXT[]) AS d(address) \
+         ON CONFLICT (job_id, address) DO NOTHING",
+    )
+    .bind(job_id)
+    .bind(&addresses)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(addresses.len())
+}
+
+fn ensure_address_list(mode: &str) -> Result<(), JobsError> {
+    if mode != "address_list" {
+        return Err(JobsError::Validation(
+            "addresses can only be managed for address_list mode".to_string(),
+        ));
+    }
+
     Ok(())
 }
 
-fn transition_target(action: JobAction, current: &str) -> Result<&'static str, JobsError> {
-    match (action, current) {
-        (JobAction::Start, "created") => Ok("running"),
-        (JobAction::Stop, "running") => Ok("created"),
-        (JobAction::Stop, "paused") => Ok("created"),
-        (JobAction::Stop, "failed") => Ok("created"),
-        (JobAction::Pause, "running") => Ok("paused"),
-        (JobAction::Resume, "paused") => Ok("running"),
-        (JobAction::Retry, "failed") => Ok("running"),
-        _ => Err(JobsError::InvalidTransition(current.to_string())),
+fn normalize_addresses(addresses: Vec<String>) -> Vec<String> {
+    addresses
+        .into_iter()
+        .map(|address| address.trim().to_string())
+        .filter(|address| !address.is_empty())
+        .collect()
+}
+
+fn ensure_deletable(job_id: &str, status: &str) -> Result<(), JobsError> {
+    if status == "running" {
+        return Err(JobsError::InvalidTransition {
+            job_id: job_id.to_string(),
+            current_status: status.to_string(),
+            requested: "delete".to_string(),
+        });
     }
+
+    Ok(())
 }
 
 fn normalize_job_config(request: CreateJobRequest) -> Result<JobConfig, JobsError> {
@@ -591,18 +3247,16 @@ fn normalize_job_config(request: CreateJobRequest) -> Result<JobConfig, JobsErro
         return Err(JobsError::Validation("job_id MUST be non-empty".to_string()));
     }
 
-    if !matches!(request.mode.as_str(), "all_addresses" | "address_list") {
+    if !matches!(
+        request.mode.as_str(),
+        "all_addresses" | "address_list" | "height_range" | "descriptor" | "export" | "verify"
+    ) {
         return Err(JobsError::Validation(
-            "mode MUST be one of: all_addresses|address_list".to_string(),
+            "mode MUST be one of: all_addresses|address_list|height_range|descriptor|export|verify".to_string(),
         ));
     }
 
-    let addresses: Vec<String> = request
-        .addresses
-        .into_iter()
-        .map(|address| address.trim().to_string())
-        .filter(|address| !address.is_empty())
-        .collect();
+    let addresses = normalize_addresses(request.addresses);
 
     if request.mode == "address_list" && addresses.is_empty() {
         return Err(JobsError::Validation(
@@ -616,22 +3270,137 @@ fn normalize_job_config(request: CreateJobRequest) -> Result<JobConfig, JobsErro
         ));
     }
 
+    if matches!(request.mode.as_str(), "height_range" | "export" | "verify") {
+        if !addresses.is_empty() {
+            return Err(JobsError::Validation(format!(
+                "addresses MUST be empty for {} mode",
+                request.mode
+            )));
+        }
+
+        let (Some(from_height), Some(to_height)) = (request.from_height, request.to_height) else {
+            return Err(JobsError::Validation(format!(
+                "from_height and to_height MUST be set for {} mode",
+                request.mode
+            )));
+        };
+
+        if from_height < 0 || from_height > to_height {
+            return Err(JobsError::Validation(
+                "height range MUST satisfy 0 <= from_height <= to_height".to_string(),
+            ));
+        }
+    } else if request.from_height.is_some() || request.to_height.is_some() {
+        return Err(JobsError::Validation(
+            "from_height/to_height are only supported for height_range, export and verify modes".to_string(),
+        ));
+    }
+
+    if request.mode == "export" {
+        let Some(export) = &request.export else {
+            return Err(JobsError::Validation("export MUST be set for export mode".to_string()));
+        };
+        export.validate().map_err(JobsError::Validation)?;
+    } else if request.export.is_some() {
+        return Err(JobsError::Validation(
+            "export is only supported for export mode".to_string(),
+        ));
+    }
+
+    let descriptor = request
+        .descriptor
+        .map(|descriptor| descriptor.trim().to_string())
+        .filter(|descriptor| !descriptor.is_empty());
+    if request.mode == "descriptor" {
+        if descriptor.is_none() {
+            return Err(JobsError::Validation(
+                "descriptor MUST be set for descriptor mode".to_string(),
+            ));
+        }
+
+        if !addresses.is_empty() {
+            return Err(JobsError::Validation(
+                "addresses MUST be empty for descriptor mode".to_string(),
+            ));
+        }
+
+        if request
+            .gap_limit
+            .is_some_and(|gap_limit| gap_limit == 0 || gap_limit > descriptors::MAX_GAP_LIMIT)
+        {
+            return Err(JobsError::Validation(format!(
+                "gap_limit MUST be between 1 and {}",
+                descriptors::MAX_GAP_LIMIT
+            )));
+        }
+    } else if descriptor.is_some() || request.gap_limit.is_some() {
+        return Err(JobsError::Validation(
+            "descriptor/gap_limit are only supported for descriptor mode".to_string(),
+        ));
+    }
+
+    if let Some(retry) = &request.retry {
+        retry.validate().map_err(JobsError::Validation)?;
+    }
+
+    if request.max_rpc_per_second == Some(0) {
+        return Err(JobsError::Validation("max_rpc_per_second MUST be > 0".to_string()));
+    }
+    if request.max_blocks_per_minute == Some(0) {
+        return Err(JobsError::Validation("max_blocks_per_minute MUST be > 0".to_string()));
+    }
+
+    if let Some(protocol) = request
+        .protocols
+        .iter()
+        .find(|protocol| !protocols::PROTOCOLS.contains(&protocol.as_str()))
+    {
+        return Err(JobsError::Validation(format!(
+            "protocols MUST contain only {}: {protocol}",
+            protocols::PROTOCOLS.join("|")
+        )));
+    }
+    if !request.protocols.is_empty() && matches!(request.mode.as_str(), "export" | "verify") {
+        return Err(JobsError::Validation(format!(
+            "protocols are not supported for {} mode",
+            request.mode
+        )));
+    }
+
     Ok(JobConfig {
         job_id: job_id.to_string(),
         mode: request.mode,
         enabled: request.enabled,
         addresses,
+        from_height: request.from_height,
+        to_height: request.to_height,
+        descriptor,
+        gap_limit: request.gap_limit,
+        retry: request.retry,
+        export: request.export,
+        priority: request.priority,
+        max_rpc_per_second: request.max_rpc_per_second,
+        max_blocks_per_minute: request.max_blocks_per_minute,
+        protocols: request.protocols,
     })
 }
 
 impl From<JobRow> for JobSummary {
     fn from(row: JobRow) -> Self {
+        let (remaining_blocks, eta_seconds) =
+            progress_estimate(row.progress_height, row.tip_height, row.to_height, row.blocks_per_sec);
+
         Self {
             job_id: row.job_id,
             mode: row.mode,
             status: row.status,
             progress_height: row.progress_height,
-            tip_height: None,
+            tip_height: row.tip_height,
+            blocks_per_sec: row.blocks_per_sec,
+            remaining_blocks,
+            eta_seconds,
+            from_height: row.from_height,
+            to_height: row.to_height,
             updated_at: row.updated_at,
             last_error: row.last_error,
         }
@@ -644,32 +3413,110 @@ struct JobRow {
     mode: String,
     status: String,
     progress_height: i32,
+    from_height: Option<i32>,
+    to_height: Option<i32>,
+    tip_height: Option<i32>,
+    blocks_per_sec: Option<f64>,
     updated_at: Option<DateTime<Utc>>,
     last_error: Option<String>,
 }
 
+impl From<JobErrorRow> for JobErrorItem {
+    fn from(row: JobErrorRow) -> Self {
+        Self {
+            id: row.id,
+            job_id: row.job_id,
+            occurred_at: row.occurred_at,
+            category: row.category,
+            block_height: row.block_height,
+            retry_count: row.retry_count,
+            message: row.message,
+        }
+    }
+}
+
 #[derive(Debug, FromRow)]
-struct JobDetailsRow {
+struct GapScanRow {
     job_id: String,
-    mode: String,
-    status: String,
     progress_height: i32,
+    from_height: Option<i32>,
+    to_height: Option<i32>,
+}
+
+#[derive(Debug, FromRow)]
+struct FailedJobRow {
+    job_id: String,
+    retry_count: i32,
     updated_at: Option<DateTime<Utc>>,
-    last_error: Option<String>,
-    config_snapshot: serde_json::Value,
+    retry: serde_json::Value,
+    last_error_category: Option<String>,
+}
+
+#[derive(Debug, FromRow)]
+struct IdempotencyKeyRow {
+    method: String,
+    path: String,
+    status: Option<i32>,
+    body: Option<Vec<u8>>,
+}
+
+#[derive(Debug, FromRow)]
+struct JobErrorRow {
+    id: i64,
+    job_id: String,
+    occurred_at: DateTime<Utc>,
+    category: String,
+    block_height: Option<i32>,
+    retry_count: i32,
+    message: String,
+}
+
+#[derive(Debug, FromRow)]
+struct WatchedAddressRow {
+    address: String,
+    pubkey: Option<String>,
+    first_seen_time: Option<i64>,
+}
+
+#[derive(Debug, FromRow)]
+struct TxSampleRow {
+    blocks: i64,
+    txs: i64,
 }
 
 #[derive(Debug, FromRow)]
-struct JobIdRow {
+struct RunningJobRow {
     job_id: String,
+    progress_height: i32,
+    to_height: Option<i32>,
+    tip_height: Option<i32>,
+    priority: i32,
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{normalize_job_config, transition_target, CreateJobRequest, JobAction};
+    use super::{
+        ensure_address_list, ensure_deletable, gap_scan_bounds, normalize_addresses, normalize_job_config, progress_estimate,
+        retry_due, transition_rule, validate_dry_run_blocks, validate_errors_limit, validate_jobs_list_filter, validate_reindex_range,
+        weighted_duration, weighted_eta, CreateJobRequest, ExportError, JobAction, JobErrorCategory, JobExecutionError, JobScheduler, JobsError,
+        JobsListFilter, ProgressWindow, RunningJobRow,
+    };
+    use crate::modules::chain::ChainParams;
+    use crate::modules::config::{
+        BatchingConfig, ConcurrencyConfig, IndexerConfig, JobExportConfig, JobRetryPolicy, PollConfig,
+    };
+    use crate::modules::indexer::IndexerError;
+    use crate::modules::rpc::RpcError;
+    use chrono::Utc;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn validates_transitions() {
+        let transition_target = |action, current| {
+            let (from, next) = transition_rule(action);
+            from.contains(&current).then_some(next).ok_or(())
+        };
+
         assert_eq!(transition_target(JobAction::Start, "created").unwrap(), "running");
         assert_eq!(transition_target(JobAction::Stop, "running").unwrap(), "created");
         assert!(transition_target(JobAction::Stop, "created").is_err());
@@ -681,6 +3528,25 @@ mod tests {
         assert!(transition_target(JobAction::Retry, "running").is_err());
     }
 
+    #[test]
+    fn running_jobs_cannot_be_deleted() {
+        assert!(ensure_deletable("job", "running").is_err());
+        assert!(ensure_deletable("job", "created").is_ok());
+        assert!(ensure_deletable("job", "paused").is_ok());
+        assert!(ensure_deletable("job", "failed").is_ok());
+        assert!(ensure_deletable("job", "completed").is_ok());
+    }
+
+    #[test]
+    fn address_management_requires_address_list_mode() {
+        assert!(ensure_address_list("address_list").is_ok());
+        let err = ensure_address_list("all_addresses").expect_err("all_addresses should fail");
+        assert!(err.to_string().contains("address_list"));
+
+        let addresses = normalize_addresses(vec![" addr1 ".to_string(), "".to_string(), "addr2".to_string()]);
+        assert_eq!(addresses, vec!["addr1".to_string(), "addr2".to_string()]);
+    }
+
     #[test]
     fn validates_runtime_job_creation_request() {
         let err = normalize_job_config(CreateJobRequest {
@@ -688,6 +3554,16 @@ mod tests {
             mode: "all_addresses".to_string(),
             enabled: true,
             addresses: vec![],
+            from_height: None,
+            to_height: None,
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
         })
         .expect_err("empty job_id should fail");
         assert!(err.to_string().contains("job_id"));
@@ -697,8 +3573,406 @@ mod tests {
             mode: "address_list".to_string(),
             enabled: true,
             addresses: vec![],
+            from_height: None,
+            to_height: None,
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
         })
         .expect_err("empty address_list should fail");
         assert!(err.to_string().contains("addresses"));
     }
+
+    #[test]
+    fn validates_height_range_job_request() {
+        let request = |from_height, to_height| CreateJobRequest {
+            job_id: "window".to_string(),
+            mode: "height_range".to_string(),
+            enabled: true,
+            addresses: vec![],
+            from_height,
+            to_height,
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
+        };
+
+        let job = normalize_job_config(request(Some(700_000), Some(750_000))).expect("valid range");
+        assert_eq!(job.from_height, Some(700_000));
+        assert_eq!(job.to_height, Some(750_000));
+
+        assert!(normalize_job_config(request(None, Some(750_000))).is_err());
+        assert!(normalize_job_config(request(Some(750_000), Some(700_000))).is_err());
+        assert!(normalize_job_config(request(Some(-1), Some(10))).is_err());
+
+        let throttled = CreateJobRequest {
+            max_rpc_per_second: Some(50),
+            max_blocks_per_minute: Some(600),
+            ..request(Some(0), Some(10))
+        };
+        let job = normalize_job_config(throttled.clone()).expect("valid limits");
+        assert_eq!((job.max_rpc_per_second, job.max_blocks_per_minute), (Some(50), Some(600)));
+        assert!(normalize_job_config(CreateJobRequest {
+            max_rpc_per_second: Some(0),
+            ..throttled.clone()
+        })
+        .is_err());
+        assert!(normalize_job_config(CreateJobRequest {
+            max_blocks_per_minute: Some(0),
+            ..throttled
+        })
+        .is_err());
+
+        let job = normalize_job_config(CreateJobRequest {
+            protocols: vec!["runes".to_string()],
+            ..request(Some(0), Some(10))
+        })
+        .expect("known protocol");
+        assert_eq!(job.protocols, vec!["runes".to_string()]);
+        assert!(normalize_job_config(CreateJobRequest {
+            protocols: vec!["brc20".to_string()],
+            ..request(Some(0), Some(10))
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn validates_descriptor_job_request() {
+        let request = |mode: &str, descriptor: Option<&str>, gap_limit| CreateJobRequest {
+            job_id: "wallet".to_string(),
+            mode: mode.to_string(),
+            enabled: true,
+            addresses: vec![],
+            from_height: None,
+            to_height: None,
+            descriptor: descriptor.map(str::to_string),
+            gap_limit,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
+        };
+
+        let job = normalize_job_config(request("descriptor", Some(" wpkh(xpub/0/*) "), Some(5)))
+            .expect("valid descriptor job");
+        assert_eq!(job.descriptor.as_deref(), Some("wpkh(xpub/0/*)"));
+        assert_eq!(job.gap_limit, Some(5));
+
+        assert!(normalize_job_config(request("descriptor", None, None)).is_err());
+        assert!(normalize_job_config(request("descriptor", Some("wpkh(xpub/0/*)"), Some(0))).is_err());
+        assert!(normalize_job_config(request("all_addresses", Some("wpkh(xpub/0/*)"), None)).is_err());
+        assert!(normalize_job_config(request("all_addresses", None, Some(20))).is_err());
+    }
+
+    #[test]
+    fn validates_export_job_request() {
+        let export = |format: &str, destination: &str| JobExportConfig {
+            format: format.to_string(),
+            destination: destination.to_string(),
+            tables: vec!["blocks".to_string()],
+        };
+        let request = |mode: &str, export: Option<JobExportConfig>| CreateJobRequest {
+            job_id: "dump".to_string(),
+            mode: mode.to_string(),
+            enabled: true,
+            addresses: vec![],
+            from_height: Some(0),
+            to_height: Some(1_000),
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
+        };
+
+        let job = normalize_job_config(request("export", Some(export("parquet", "s3://analytics/btc"))))
+            .expect("valid export job");
+        assert_eq!(job.export.map(|export| export.format), Some("parquet".to_string()));
+
+        assert!(normalize_job_config(request("export", None)).is_err());
+        assert!(normalize_job_config(request("export", Some(export("json", "/data/export")))).is_err());
+        assert!(normalize_job_config(request("export", Some(export("csv", "data/export")))).is_err());
+        assert!(normalize_job_config(request("export", Some(export("csv", "s3:///btc")))).is_err());
+        assert!(normalize_job_config(request("height_range", Some(export("csv", "/data/export")))).is_err());
+    }
+
+    #[test]
+    fn validates_verify_job_request() {
+        let request = |from_height: Option<i32>, addresses: Vec<String>| CreateJobRequest {
+            job_id: "audit".to_string(),
+            mode: "verify".to_string(),
+            enabled: true,
+            addresses,
+            from_height,
+            to_height: Some(800_000),
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
+        };
+
+        let job = normalize_job_config(request(Some(0), vec![])).expect("valid verify job");
+        assert_eq!((job.from_height, job.to_height), (Some(0), Some(800_000)));
+        assert!(normalize_job_config(request(None, vec![])).is_err());
+        assert!(normalize_job_config(request(Some(0), vec!["bc1qexample".to_string()])).is_err());
+    }
+
+    #[test]
+    fn gap_scan_stops_at_progress_or_range_end() {
+        assert_eq!(gap_scan_bounds(500, None, None), (0, 500));
+        assert_eq!(gap_scan_bounds(500, Some(100), Some(300)), (100, 300));
+        assert_eq!(gap_scan_bounds(250, Some(100), Some(300)), (100, 250));
+    }
+
+    #[test]
+    fn progress_window_measures_rate_over_sliding_window() {
+        let window = Duration::from_secs(300);
+        let start = Instant::now();
+        let mut progress = ProgressWindow::default();
+
+        assert_eq!(progress.record(start, 100, window), None);
+        assert_eq!(progress.record(start + Duration::from_secs(10), 150, window), Some(5.0));
+        assert_eq!(progress.record(start + Duration::from_secs(410), 350, window), Some(0.5));
+        assert_eq!(progress.record(start + Duration::from_secs(420), 90, window), None);
+    }
+
+    #[test]
+    fn estimates_remaining_blocks_and_eta() {
+        assert_eq!(progress_estimate(100, None, None, Some(2.0)), (None, None));
+        assert_eq!(progress_estimate(100, Some(105), None, Some(2.0)), (Some(5), Some(3)));
+        assert_eq!(progress_estimate(100, Some(200), Some(110), None), (Some(10), None));
+        assert_eq!(progress_estimate(120, Some(110), None, Some(0.0)), (Some(0), Some(0)));
+    }
+
+    #[test]
+    fn weights_eta_by_block_weight_era() {
+        let mainnet = ChainParams::from_config(&IndexerConfig {
+            chain: "bitcoin".to_string(),
+            network: "mainnet".to_string(),
+            signet: None,
+            reorg_depth: 6,
+            poll: PollConfig {
+                tip_interval_ms: 1000,
+                mempool_interval_ms: 1000,
+                block_template_interval_ms: None,
+                gap_scan_interval_ms: None,
+            },
+            concurrency: ConcurrencyConfig {
+                max_jobs: 1,
+                rpc_parallelism: 1,
+                db_writer_parallelism: 1,
+            },
+            batching: BatchingConfig {
+                blocks_per_batch: 10,
+                txs_per_batch: 100,
+            },
+            store_raw_blocks: false,
+            block_filters: false,
+            prevout_rpc_fallback: false,
+            events: None,
+            headers: None,
+            stats: None,
+            retention: None,
+        });
+
+        // Same era: 10 blocks of 80k WU + 50k overhead at 100 blocks/sec.
+        assert_eq!(weighted_eta(&mainnet, 100_000, 100_010, Some(100.0)), Some(1));
+        assert_eq!(weighted_eta(&mainnet, 120, 110, Some(1.0)), Some(0));
+        assert_eq!(weighted_eta(&mainnet, 100, 110, None), None);
+
+        // Fast early blocks say little about full modern blocks: linear would be 790s.
+        let eta = weighted_eta(&mainnet, 10_000, 800_000, Some(1000.0)).expect("eta");
+        assert!((30_000..40_000).contains(&eta), "eta {eta}");
+
+        // A rate measured on full blocks makes a backfill of early heights faster than linear.
+        let duration = weighted_duration(&mainnet, 800_000, 10_000, 20_000, Some(1.0)).expect("duration");
+        assert!(duration < 10_001, "duration {duration}");
+    }
+
+    #[test]
+    fn categorizes_job_execution_errors() {
+        let decode = JobExecutionError::AtHeight {
+            height: 42,
+            source: Box::new(JobExecutionError::Indexer(IndexerError::Rpc(RpcError::Decode(
+                "bad json".to_string(),
+            )))),
+        };
+        assert_eq!(decode.category(), JobErrorCategory::Parse);
+        assert_eq!(decode.block_height(), Some(42));
+        assert_eq!(decode.to_string(), "rpc error: failed to decode rpc response: bad json");
+
+        let rpc = JobExecutionError::Rpc(RpcError::Http("timeout".to_string()));
+        assert_eq!(rpc.category(), JobErrorCategory::Rpc);
+        assert_eq!(rpc.block_height(), None);
+        assert!(!rpc.is_node_warming_up());
+
+        let warmup = JobExecutionError::AtHeight {
+            height: 7,
+            source: Box::new(JobExecutionError::Indexer(IndexerError::Rpc(RpcError::node(
+                RpcError::IN_WARMUP,
+                "Loading block index…".to_string(),
+            )))),
+        };
+        assert!(warmup.is_node_warming_up());
+
+        let storage = JobExecutionError::Jobs(JobsError::Storage(sqlx::Error::PoolTimedOut));
+        assert_eq!(storage.category(), JobErrorCategory::Storage);
+
+        let encode = JobExecutionError::Export(ExportError::Encode {
+            table: "blocks".to_string(),
+            message: "schema mismatch".to_string(),
+        });
+        assert_eq!(encode.category(), JobErrorCategory::Parse);
+    }
+
+    #[test]
+    fn validates_errors_limit() {
+        assert_eq!(validate_errors_limit(None).unwrap(), 50);
+        assert!(validate_errors_limit(Some(0)).is_err());
+        assert!(validate_errors_limit(Some(501)).is_err());
+    }
+
+    #[test]
+    fn validates_dry_run_blocks() {
+        assert_eq!(validate_dry_run_blocks(None).unwrap(), 10);
+        assert_eq!(validate_dry_run_blocks(Some(100)).unwrap(), 100);
+        assert!(validate_dry_run_blocks(Some(0)).is_err());
+        assert!(validate_dry_run_blocks(Some(101)).is_err());
+    }
+
+    #[test]
+    fn validates_reindex_range() {
+        assert_eq!(validate_reindex_range(800_000, 800_000).unwrap(), 1);
+        assert_eq!(validate_reindex_range(0, 999).unwrap(), 1000);
+        assert!(validate_reindex_range(-1, 5).is_err());
+        assert!(validate_reindex_range(10, 9).is_err());
+        assert!(validate_reindex_range(0, 1000).is_err());
+    }
+
+    #[test]
+    fn validates_jobs_list_filter() {
+        let filter = |status: Option<&str>, sort: Option<&str>, limit: Option<i64>| JobsListFilter {
+            status: status.map(str::to_string),
+            sort: sort.map(str::to_string),
+            limit,
+            ..JobsListFilter::default()
+        };
+
+        let (order_by, offset, limit) = validate_jobs_list_filter(&filter(None, None, None)).expect("defaults");
+        assert_eq!((order_by.as_str(), offset, limit), ("job_id ASC NULLS LAST", 0, 100));
+        let (order_by, _, _) =
+            validate_jobs_list_filter(&filter(Some("running"), Some("-updated_at"), Some(10))).expect("filter");
+        assert_eq!(order_by, "updated_at DESC NULLS LAST, job_id");
+        assert!(validate_jobs_list_filter(&filter(Some("stopped"), None, None)).is_err());
+        assert!(validate_jobs_list_filter(&filter(None, Some("last_error"), None)).is_err());
+        assert!(validate_jobs_list_filter(&filter(None, None, Some(1001))).is_err());
+    }
+
+    #[test]
+    fn retries_only_transient_errors_after_backoff() {
+        let policy = JobRetryPolicy {
+            max_attempts: 2,
+            backoff_ms: 10_000,
+            max_backoff_ms: 60_000,
+        };
+        let failed_at = Utc::now();
+        let later = |secs| failed_at + chrono::Duration::seconds(secs);
+
+        assert!(!retry_due(&policy, 0, Some("rpc"), failed_at, later(9)));
+        assert!(retry_due(&policy, 0, Some("rpc"), failed_at, later(10)));
+        assert!(!retry_due(&policy, 1, Some("storage"), failed_at, later(19)));
+        assert!(retry_due(&policy, 1, Some("storage"), failed_at, later(20)));
+        assert!(!retry_due(&policy, 2, Some("rpc"), failed_at, later(3600)));
+        assert!(!retry_due(&policy, 0, Some("parse"), failed_at, later(3600)));
+        assert!(retry_due(&policy, 0, Some("panic"), failed_at, later(10)));
+        assert!(!retry_due(&policy, 0, None, failed_at, later(3600)));
+    }
+
+    fn running_job(job_id: &str, priority: i32, progress_height: i32, tip_height: Option<i32>) -> RunningJobRow {
+        RunningJobRow {
+            job_id: job_id.to_string(),
+            progress_height,
+            to_height: None,
+            tip_height,
+            priority,
+        }
+    }
+
+    #[test]
+    fn schedules_by_priority_and_takes_turns_within_a_kind() {
+        let running = || {
+            vec![
+                running_job("a", 0, 100, Some(100)),
+                running_job("b", 0, 100, Some(101)),
+                running_job("urgent", 5, 100, None),
+            ]
+        };
+        let mut scheduler = JobScheduler::default();
+
+        let order = scheduler.order(running(), 10);
+        let ids: Vec<&str> = order.iter().map(|job| job.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["urgent", "a", "b"]);
+        assert!(order.iter().all(|job| !job.backfill));
+
+        scheduler.record_start(&order[0]);
+        scheduler.record_start(&order[1]);
+        let order = scheduler.order(running(), 10);
+        let ids: Vec<&str> = order.iter().map(|job| job.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["urgent", "b", "a"]);
+    }
+
+    #[test]
+    fn interleaves_backfill_with_tip_following_jobs() {
+        let running = || {
+            vec![
+                running_job("historical", 9, 0, Some(800_000)),
+                running_job("historical-2", 0, 0, Some(800_000)),
+                running_job("tip", 0, 799_995, Some(800_000)),
+                RunningJobRow {
+                    job_id: "range".to_string(),
+                    to_height: Some(1_000),
+                    ..running_job("range", 0, 10, None)
+                },
+            ]
+        };
+        let mut scheduler = JobScheduler::default();
+
+        let order = scheduler.order(running(), 10);
+        let ids: Vec<&str> = order.iter().map(|job| job.job_id.as_str()).collect();
+        assert_eq!(ids, vec!["tip", "historical", "historical-2", "range"]);
+
+        // With one slot the kinds take it in turns.
+        scheduler.record_start(&order[0]);
+        let order = scheduler.order(running(), 10);
+        assert_eq!(order[0].job_id, "historical");
+        scheduler.record_start(&order[0]);
+        let order = scheduler.order(running(), 10);
+        assert_eq!(order[0].job_id, "tip");
+        scheduler.record_start(&order[0]);
+        let order = scheduler.order(running(), 10);
+        assert_eq!(order[0].job_id, "historical");
+
+        let order = scheduler.order(vec![running_job("historical-2", 0, 0, Some(800_000))], 10);
+        assert_eq!(order.len(), 1);
+        assert!(!scheduler.last_started.contains_key("historical"));
+    }
 }
@@ -0,0 +1,398 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, PgConnection, PgPool, Postgres, Transaction};
+
+use crate::modules::storage::SchemaFeatures;
+
+/// Database of the job rows [`super::JobsService`] creates, deletes, moves between statuses
+/// and checkpoints. [`PgPool`] in production; [`crate::modules::storage::memory::MemoryStorage`]
+/// serves unit tests.
+///
+/// Other stores keep the columns of the [`SchemaFeatures`] they report; derived addresses of
+/// descriptor jobs and `stream_events` are written through [`JobStoreTx::postgres`] only.
+pub trait JobStore: Clone + Send + Sync + 'static {
+    type Tx: JobStoreTx;
+
+    /// Starts the transaction of a change to job rows. Writes of optional columns follow `schema`.
+    fn begin_jobs(&self, schema: SchemaFeatures) -> impl Future<Output = Result<Self::Tx, sqlx::Error>> + Send;
+
+    /// Row of `job_id`, with the columns `schema` has.
+    fn job(
+        &self,
+        schema: SchemaFeatures,
+        job_id: &str,
+    ) -> impl Future<Output = Result<Option<JobRecord>, sqlx::Error>> + Send;
+
+    /// `(checkpoint_height, checkpoint_hash)` of `job_id`, `None` when the job is missing.
+    fn job_checkpoint(
+        &self,
+        job_id: &str,
+    ) -> impl Future<Output = Result<Option<CheckpointColumns>, sqlx::Error>> + Send;
+
+    /// Raises the progress of `job_id` to `height` and ends its failure streak.
+    fn update_job_progress(
+        &self,
+        schema: SchemaFeatures,
+        job_id: &str,
+        height: i32,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Lowers the progress of `job_id`, or of every job with `None`, to at most `height`. A
+    /// checkpoint from `height` up moves to the canonical block at `height`, or is dropped
+    /// when that height is not indexed.
+    fn rewind_job_progress(
+        &self,
+        schema: SchemaFeatures,
+        job_id: Option<&str>,
+        height: i32,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+}
+
+/// Writes of one transaction of a [`JobStore`]. Dropping it without [`Self::commit`] rolls
+/// the writes back.
+pub trait JobStoreTx: Send {
+    fn commit(self) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Inserts `job` as `created` at progress 0 with its height range and addresses.
+    /// Returns `false`, writing nothing, when the job id is taken.
+    fn insert_job(&mut self, job: &NewJobRecord<'_>) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Status of `job_id`, whose row stays locked until the end of the transaction.
+    fn lock_job_status(&mut self, job_id: &str) -> impl Future<Output = Result<Option<String>, sqlx::Error>> + Send;
+
+    fn set_job_status(&mut self, update: &JobStatusUpdate<'_>) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Deletes `job_id` with its addresses, events and errors.
+    fn delete_job(&mut self, job_id: &str) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Records a status change of the job API in `job_events`.
+    fn insert_job_event(&mut self, event: &JobEventRecord) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Records a failure in `job_errors` with the current retry count of the job.
+    fn insert_job_error(&mut self, error: &JobErrorRecord) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Connection of a PostgreSQL transaction, for the tables no other store has.
+    fn postgres(&mut self) -> Option<&mut PgConnection> {
+        None
+    }
+}
+
+/// `checkpoint_height` and `checkpoint_hash` of a job, `NULL` until a block of the job is written.
+pub type CheckpointColumns = (Option<i32>, Option<String>);
+
+/// Row of `jobs`. The columns of migrations missing from the schema are `None`.
+#[derive(Debug, Clone, FromRow)]
+pub struct JobRecord {
+    pub job_id: String,
+    pub mode: String,
+    pub status: String,
+    pub progress_height: i32,
+    pub from_height: Option<i32>,
+    pub to_height: Option<i32>,
+    pub tip_height: Option<i32>,
+    pub blocks_per_sec: Option<f64>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub config_snapshot: serde_json::Value,
+    /// Rows of `job_gaps`.
+    pub gap_heights: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewJobRecord<'a> {
+    pub job_id: &'a str,
+    pub mode: &'a str,
+    pub config_snapshot: &'a serde_json::Value,
+    pub from_height: Option<i32>,
+    pub to_height: Option<i32>,
+    pub addresses: &'a [String],
+}
+
+/// New `status` of a job, which also sets `updated_at`.
+#[derive(Debug, Clone)]
+pub struct JobStatusUpdate<'a> {
+    pub job_id: &'a str,
+    pub status: &'a str,
+    pub last_error: LastError<'a>,
+    /// Counts an attempt in `retry_count`.
+    pub count_retry: bool,
+}
+
+/// What a status change does to `last_error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastError<'a> {
+    Keep,
+    Clear,
+    Set(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobEventRecord {
+    pub job_id: String,
+    pub action: String,
+    pub from_status: String,
+    pub to_status: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobErrorRecord {
+    pub job_id: String,
+    pub category: String,
+    pub block_height: Option<i32>,
+    pub message: String,
+}
+
+/// Columns of `jobs` that optional migrations add, as `NULL` without them.
+pub(super) fn optional_columns(schema: SchemaFeatures) -> String {
+    let height_range = if schema.jobs_height_range {
+        "from_height, to_height"
+    } else {
+        "NULL::INT AS from_height, NULL::INT AS to_height"
+    };
+    let progress_rate = if schema.jobs_progress_rate {
+        "tip_height, blocks_per_sec"
+    } else {
+        "NULL::INT AS tip_height, NULL::DOUBLE PRECISION AS blocks_per_sec"
+    };
+
+    format!("{height_range}, {progress_rate}")
+}
+
+/// Insert of an address added through the API; config sync leaves such rows alone.
+pub(super) fn api_address_insert(schema: SchemaFeatures) -> &'static str {
+    if schema.job_address_sources {
+        "INSERT INTO job_addresses (job_id, address, source) \
+         VALUES ($1, $2, 'api') \
+         ON CONFLICT (job_id, address) DO NOTHING"
+    } else {
+        "INSERT INTO job_addresses (job_id, address) \
+         VALUES ($1, $2) \
+         ON CONFLICT (job_id, address) DO NOTHING"
+    }
+}
+
+pub(super) async fn store_height_range(
+    conn: &mut PgConnection,
+    schema: SchemaFeatures,
+    job_id: &str,
+    from_height: Option<i32>,
+    to_height: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    if !schema.jobs_height_range {
+        return Ok(());
+    }
+
+    sqlx::query(
+        "UPDATE jobs \
+         SET from_height = $2, to_height = $3 \
+         WHERE job_id = $1",
+    )
+    .bind(job_id)
+    .bind(from_height)
+    .bind(to_height)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Job transaction of a [`PgPool`].
+pub struct PgJobsTx {
+    tx: Transaction<'static, Postgres>,
+    schema: SchemaFeatures,
+}
+
+impl JobStore for PgPool {
+    type Tx = PgJobsTx;
+
+    async fn begin_jobs(&self, schema: SchemaFeatures) -> Result<PgJobsTx, sqlx::Error> {
+        Ok(PgJobsTx {
+            tx: self.begin().await?,
+            schema,
+        })
+    }
+
+    async fn job(&self, schema: SchemaFeatures, job_id: &str) -> Result<Option<JobRecord>, sqlx::Error> {
+        let gap_heights = if schema.job_gaps {
+            "(SELECT COUNT(*) FROM job_gaps g WHERE g.job_id = jobs.job_id) AS gap_heights"
+        } else {
+            "NULL::BIGINT AS gap_heights"
+        };
+        sqlx::query_as(&format!(
+            "SELECT job_id, mode, status, progress_height, {}, updated_at, last_error, \
+                    config_snapshot, {gap_heights} \
+             FROM jobs \
+             WHERE job_id = $1",
+            optional_columns(schema)
+        ))
+        .bind(job_id)
+        .fetch_optional(self)
+        .await
+    }
+
+    async fn job_checkpoint(&self, job_id: &str) -> Result<Option<CheckpointColumns>, sqlx::Error> {
+        sqlx::query_as("SELECT checkpoint_height, checkpoint_hash FROM jobs WHERE job_id = $1")
+            .bind(job_id)
+            .fetch_optional(self)
+            .await
+    }
+
+    async fn update_job_progress(&self, schema: SchemaFeatures, job_id: &str, height: i32) -> Result<(), sqlx::Error> {
+        let query = if schema.job_errors {
+            "UPDATE jobs \
+             SET progress_height = GREATEST(progress_height, $2), updated_at = NOW(), last_error = NULL, \
+                 retry_count = 0 \
+             WHERE job_id = $1"
+        } else {
+            "UPDATE jobs \
+             SET progress_height = GREATEST(progress_height, $2), updated_at = NOW(), last_error = NULL \
+             WHERE job_id = $1"
+        };
+
+        sqlx::query(query).bind(job_id).bind(height).execute(self).await?;
+        Ok(())
+    }
+
+    async fn rewind_job_progress(
+        &self,
+        schema: SchemaFeatures,
+        job_id: Option<&str>,
+        height: i32,
+    ) -> Result<(), sqlx::Error> {
+        let query = if schema.job_checkpoints {
+            "WITH canonical AS (SELECT hash FROM blocks WHERE height = $1::INT AND status = 'canonical') \
+             UPDATE jobs \
+             SET progress_height = LEAST(progress_height, $1), updated_at = NOW(), \
+                 checkpoint_height = CASE WHEN checkpoint_height >= $1 \
+                     THEN (SELECT $1 FROM canonical) ELSE checkpoint_height END, \
+                 checkpoint_hash = CASE WHEN checkpoint_height >= $1 \
+                     THEN (SELECT hash FROM canonical) ELSE checkpoint_hash END \
+             WHERE $2::TEXT IS NULL OR job_id = $2"
+        } else {
+            "UPDATE jobs \
+             SET progress_height = LEAST(progress_height, $1), updated_at = NOW() \
+             WHERE $2::TEXT IS NULL OR job_id = $2"
+        };
+
+        sqlx::query(query).bind(height).bind(job_id).execute(self).await?;
+        Ok(())
+    }
+}
+
+impl JobStoreTx for PgJobsTx {
+    async fn commit(self) -> Result<(), sqlx::Error> {
+        self.tx.commit().await
+    }
+
+    async fn insert_job(&mut self, job: &NewJobRecord<'_>) -> Result<bool, sqlx::Error> {
+        let inserted = sqlx::query(
+            "INSERT INTO jobs \
+             (job_id, mode, status, progress_height, config_snapshot, updated_at) \
+             VALUES ($1, $2, 'created', 0, $3, NOW()) \
+             ON CONFLICT (job_id) DO NOTHING",
+        )
+        .bind(job.job_id)
+        .bind(job.mode)
+        .bind(job.config_snapshot)
+        .execute(&mut *self.tx)
+        .await?
+        .rows_affected();
+        if inserted == 0 {
+            return Ok(false);
+        }
+
+        store_height_range(&mut self.tx, self.schema, job.job_id, job.from_height, job.to_height).await?;
+        for address in job.addresses {
+            sqlx::query(api_address_insert(self.schema))
+                .bind(job.job_id)
+                .bind(address)
+                .execute(&mut *self.tx)
+                .await?;
+        }
+
+        Ok(true)
+    }
+
+    async fn lock_job_status(&mut self, job_id: &str) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT status FROM jobs WHERE job_id = $1 FOR UPDATE")
+            .bind(job_id)
+            .fetch_optional(&mut *self.tx)
+            .await
+    }
+
+    async fn set_job_status(&mut self, update: &JobStatusUpdate<'_>) -> Result<(), sqlx::Error> {
+        let retry_count = if update.count_retry {
+            ", retry_count = retry_count + 1"
+        } else {
+            ""
+        };
+        let (clear_error, error) = match update.last_error {
+            LastError::Keep => (false, None),
+            LastError::Clear => (true, None),
+            LastError::Set(message) => (false, Some(message)),
+        };
+
+        sqlx::query(&format!(
+            "UPDATE jobs \
+             SET status = $2, updated_at = NOW(), \
+                 last_error = CASE WHEN $3 THEN NULL ELSE COALESCE($4, last_error) END{retry_count} \
+             WHERE job_id = $1"
+        ))
+        .bind(update.job_id)
+        .bind(update.status)
+        .bind(clear_error)
+        .bind(error)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_job(&mut self, job_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM jobs WHERE job_id = $1")
+            .bind(job_id)
+            .execute(&mut *self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert_job_event(&mut self, event: &JobEventRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO job_events (job_id, action, from_status, to_status, reason) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&event.job_id)
+        .bind(&event.action)
+        .bind(&event.from_status)
+        .bind(&event.to_status)
+        .bind(&event.reason)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_job_error(&mut self, error: &JobErrorRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO job_errors (job_id, category, block_height, retry_count, message) \
+             SELECT job_id, $2, $3, retry_count, $4 \
+             FROM jobs \
+             WHERE job_id = $1",
+        )
+        .bind(&error.job_id)
+        .bind(&error.category)
+        .bind(error.block_height)
+        .bind(&error.message)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    fn postgres(&mut self) -> Option<&mut PgConnection> {
+        Some(&mut *self.tx)
+    }
+}
@@ -0,0 +1,190 @@
+//! Log file of `logging.file`, switched to a new file every period.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use chrono::{DateTime, Utc};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::modules::config::LogFileConfig;
+
+/// Writes to `{path}.{period}`, e.g. `indexer.log.2026-10-16` for `daily`, or to `path`
+/// itself for `never`, keeping `max_files` files.
+pub struct RollingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    max_files: usize,
+    state: Mutex<OpenFile>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rotation {
+    Daily,
+    Hourly,
+    Never,
+}
+
+struct OpenFile {
+    period: String,
+    file: File,
+}
+
+/// Writer handed out per event; the line is written under the lock, so lines of concurrent
+/// events do not interleave.
+pub struct RollingWriter<'a>(&'a RollingFile);
+
+impl Rotation {
+    fn parse(value: &str) -> Self {
+        match value {
+            "hourly" => Self::Hourly,
+            "never" => Self::Never,
+            _ => Self::Daily,
+        }
+    }
+
+    fn period(self, now: DateTime<Utc>) -> String {
+        match self {
+            Self::Daily => now.format("%Y-%m-%d").to_string(),
+            Self::Hourly => now.format("%Y-%m-%d-%H").to_string(),
+            Self::Never => String::new(),
+        }
+    }
+}
+
+impl RollingFile {
+    /// Creates the directory of the log file and opens the file of the current period.
+    pub fn open(config: &LogFileConfig) -> io::Result<Self> {
+        if let Some(dir) = config.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let rotation = Rotation::parse(&config.rotation);
+        let period = rotation.period(Utc::now());
+        let file = open_append(&period_path(&config.path, &period))?;
+
+        let rolling = Self {
+            path: config.path.clone(),
+            rotation,
+            max_files: config.max_files,
+            state: Mutex::new(OpenFile { period, file }),
+        };
+        rolling.prune();
+        Ok(rolling)
+    }
+
+    fn lock(&self) -> io::Result<MutexGuard<'_, OpenFile>> {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let period = self.rotation.period(Utc::now());
+        if period != state.period {
+            state.file = open_append(&period_path(&self.path, &period))?;
+            state.period = period;
+            self.prune();
+        }
+        Ok(state)
+    }
+
+    /// Deletes the oldest rotated files beyond `max_files`; periods sort by name.
+    fn prune(&self) {
+        if self.rotation == Rotation::Never {
+            return;
+        }
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return;
+        };
+        let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+        let prefix = format!("{}.", name.to_string_lossy());
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        let mut rotated: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+            .map(|entry| entry.path())
+            .collect();
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for path in &rotated[..excess] {
+            // Logging the failure would write to this file again.
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+fn period_path(path: &Path, period: &str) -> PathBuf {
+    if period.is_empty() {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(period);
+    PathBuf::from(name)
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+impl<'a> MakeWriter<'a> for RollingFile {
+    type Writer = RollingWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RollingWriter(self)
+    }
+}
+
+impl Write for RollingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock()?.file.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.lock()?.file.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock()?.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    #[test]
+    fn names_files_by_period_and_keeps_max_files() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap();
+        assert_eq!(Rotation::Daily.period(now), "2026-10-16");
+        assert_eq!(Rotation::Hourly.period(now), "2026-10-16-09");
+        assert_eq!(period_path(Path::new("/var/log/indexer.log"), ""), Path::new("/var/log/indexer.log"));
+        assert_eq!(
+            period_path(Path::new("/var/log/indexer.log"), "2026-10-16"),
+            Path::new("/var/log/indexer.log.2026-10-16")
+        );
+
+        let dir = tempdir().expect("tempdir");
+        let path = dir.path().join("logs").join("indexer.log");
+        fs::create_dir_all(path.parent().unwrap()).expect("log dir");
+        for day in ["2026-01-01", "2026-01-02", "2026-01-03"] {
+            fs::write(period_path(&path, day), "old\n").expect("rotated file");
+        }
+        let config = LogFileConfig {
+            path: path.clone(),
+            rotation: "daily".to_string(),
+            max_files: 2,
+        };
+
+        let rolling = RollingFile::open(&config).expect("open log file");
+        rolling.make_writer().write_all(b"line\n").expect("write");
+
+        let current = period_path(&path, &Rotation::Daily.period(Utc::now()));
+        assert_eq!(fs::read_to_string(&current).expect("current file"), "line\n");
+        assert!(!period_path(&path, "2026-01-01").exists());
+        assert!(!period_path(&path, "2026-01-02").exists());
+        assert!(period_path(&path, "2026-01-03").exists());
+    }
+}
@@ -1,12 +1,176 @@
-use tracing_subscriber::{fmt, EnvFilter};
+use std::sync::{Mutex, OnceLock};
 
-pub fn init() {
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+use tracing::info;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::layer::{Layered, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+use utoipa::ToSchema;
 
-    fmt()
-        .json()
-        .with_env_filter(filter)
-        .with_current_span(false)
-        .with_span_list(false)
-        .init();
+use crate::modules::config::LoggingConfig;
+
+pub mod file;
+pub mod otlp;
+
+type FilteredRegistry = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+
+static FILTER: OnceLock<ReloadableFilter> = OnceLock::new();
+
+/// Filter installed by [`init`], replaceable at runtime through `PUT /v1/admin/log-level`.
+struct ReloadableFilter {
+    handle: reload::Handle<EnvFilter, Registry>,
+    startup: String,
+    status: Mutex<LogFilterStatus>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct LogFilterStatus {
+    /// Directives in effect, in `RUST_LOG` syntax.
+    pub filter: String,
+    /// Directives selected at startup by `RUST_LOG` or `logging.level`/`logging.modules`.
+    pub startup_filter: String,
+    /// Last runtime change; `None` while the startup filter is in effect.
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Error)]
+pub enum LogFilterError {
+    #[error("filter MUST be EnvFilter directives like info,sqlx::query=warn: {0}")]
+    Invalid(String),
+    #[error("logging is not initialized")]
+    NotInitialized,
+    #[error("failed to replace log filter: {0}")]
+    Reload(#[from] reload::Error),
+}
+
+/// Installs the subscriber of `config`: `RUST_LOG` when set, otherwise `logging.level` with
+/// the `logging.modules` overrides, written in `logging.format` to stdout or `logging.file`.
+pub fn init(config: &LoggingConfig) -> Result<()> {
+    let filter = EnvFilter::try_from_default_env().or_else(|_| EnvFilter::try_new(filter_directives(config)))?;
+    let startup = filter.to_string();
+    let (filter, handle) = reload::Layer::new(filter);
+    let (writer, ansi) = match &config.file {
+        Some(file) => (BoxMakeWriter::new(file::RollingFile::open(file)?), false),
+        None => (BoxMakeWriter::new(std::io::stdout), true),
+    };
+
+    let output: Box<dyn Layer<FilteredRegistry> + Send + Sync> = match config.format.as_str() {
+        "pretty" => fmt::layer().pretty().with_ansi(ansi).with_writer(writer).boxed(),
+        "compact" => fmt::layer().compact().with_ansi(ansi).with_writer(writer).boxed(),
+        _ => fmt::layer()
+            .json()
+            .with_current_span(false)
+            // Carries `request_id` of the enclosing HTTP request into RPC and query events.
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        // Idle until `logging.otlp` starts the exporter, see [`otlp::start`].
+        .with(otlp::OtlpLayer)
+        .try_init()?;
+
+    let _ = FILTER.set(ReloadableFilter {
+        handle,
+        status: Mutex::new(LogFilterStatus {
+            filter: startup.clone(),
+            startup_filter: startup.clone(),
+            changed_at: None,
+        }),
+        startup,
+    });
+    Ok(())
+}
+
+pub fn filter_status() -> Result<LogFilterStatus, LogFilterError> {
+    let filter = FILTER.get().ok_or(LogFilterError::NotInitialized)?;
+    let status = filter.status.lock().unwrap_or_else(|err| err.into_inner());
+    Ok(status.clone())
+}
+
+/// Replaces the filter of every log output and of span export until the next change,
+/// [`reset_filter`] or a restart.
+pub fn set_filter(directives: &str) -> Result<LogFilterStatus, LogFilterError> {
+    let filter = FILTER.get().ok_or(LogFilterError::NotInitialized)?;
+    let next = parse_filter(directives)?;
+    replace_filter(filter, next, Some(Utc::now()))
+}
+
+/// Restores the filter selected at startup.
+pub fn reset_filter() -> Result<LogFilterStatus, LogFilterError> {
+    let filter = FILTER.get().ok_or(LogFilterError::NotInitialized)?;
+    let startup = parse_filter(&filter.startup)?;
+    replace_filter(filter, startup, None)
+}
+
+fn replace_filter(
+    filter: &ReloadableFilter,
+    next: EnvFilter,
+    changed_at: Option<DateTime<Utc>>,
+) -> Result<LogFilterStatus, LogFilterError> {
+    let mut status = filter.status.lock().unwrap_or_else(|err| err.into_inner());
+    let directives = next.to_string();
+    filter.handle.reload(next)?;
+    let previous = std::mem::replace(&mut status.filter, directives);
+    status.changed_at = changed_at;
+    info!(
+        component = "logging",
+        previous_filter = %previous,
+        filter = %status.filter,
+        message = "log filter changed"
+    );
+    Ok(status.clone())
+}
+
+fn parse_filter(directives: &str) -> Result<EnvFilter, LogFilterError> {
+    let directives = directives.trim();
+    if directives.is_empty() {
+        return Err(LogFilterError::Invalid("empty filter".to_string()));
+    }
+    EnvFilter::builder()
+        .parse(directives)
+        .map_err(|err| LogFilterError::Invalid(err.to_string()))
+}
+
+/// `EnvFilter` directives of `logging.level` and `logging.modules`, e.g. `info,sqlx::query=warn`.
+fn filter_directives(config: &LoggingConfig) -> String {
+    std::iter::once(config.level.clone())
+        .chain(config.modules.iter().map(|(module, level)| format!("{module}={level}")))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_filter_directives_from_level_and_modules() {
+        let mut config = LoggingConfig::default();
+        assert_eq!(filter_directives(&config), "info");
+
+        config.level = "warn".to_string();
+        config.modules = vec![
+            ("bitcoin_blockchain_indexer::modules::rpc".to_string(), "debug".to_string()),
+            ("sqlx::query".to_string(), "off".to_string()),
+        ];
+        let directives = filter_directives(&config);
+        assert_eq!(directives, "warn,bitcoin_blockchain_indexer::modules::rpc=debug,sqlx::query=off");
+        assert!(EnvFilter::try_new(directives).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_filter_directives() {
+        assert!(parse_filter("debug").is_ok());
+        assert!(parse_filter("info,bitcoin_blockchain_indexer::modules::rpc=trace").is_ok());
+        assert!(matches!(parse_filter("  "), Err(LogFilterError::Invalid(_))));
+        assert!(matches!(parse_filter("sqlx::query=loud"), Err(LogFilterError::Invalid(_))));
+    }
 }
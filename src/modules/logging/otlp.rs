@@ -0,0 +1,403 @@
+//! Span export in the OTLP/HTTP JSON encoding, understood by the OpenTelemetry Collector,
+//! Jaeger and Tempo on their OTLP HTTP port (4318).
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+use crate::modules::config::OtlpConfig;
+
+const TRACES_PATH: &str = "/v1/traces";
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Spans posted per request; a full batch is sent before the export interval elapses.
+const MAX_EXPORT_BATCH: usize = 512;
+const SCOPE_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// `SpanKind` values of the OTLP protocol.
+const SPAN_KIND_INTERNAL: u8 = 1;
+const SPAN_KIND_SERVER: u8 = 2;
+const SPAN_KIND_CLIENT: u8 = 3;
+/// `Status.code` of a span that saw a `warn` or `error` event.
+const STATUS_CODE_ERROR: u8 = 2;
+
+static EXPORTER: OnceLock<mpsc::Sender<Message>> = OnceLock::new();
+static DROPPED_SPANS: AtomicU64 = AtomicU64::new(0);
+
+/// Records spans once [`start`] has configured an exporter; a no-op until then, so it can be
+/// installed before the config is loaded.
+pub struct OtlpLayer;
+
+enum Message {
+    Span(SpanData),
+    Flush(oneshot::Sender<()>),
+}
+
+/// Span being recorded, kept in the extensions of the tracing span until it closes.
+#[derive(Debug, Clone)]
+struct SpanData {
+    trace_id: [u8; 16],
+    span_id: [u8; 8],
+    parent_span_id: Option<[u8; 8]>,
+    name: &'static str,
+    start: SystemTime,
+    end: SystemTime,
+    attributes: Vec<(&'static str, Value)>,
+    error: Option<String>,
+}
+
+/// Starts the background exporter of `config`; spans closed before it are not exported.
+pub fn start(config: &OtlpConfig) -> anyhow::Result<()> {
+    let client = reqwest::Client::builder().timeout(EXPORT_TIMEOUT).build()?;
+    let (tx, rx) = mpsc::channel(config.max_queue_size);
+    if EXPORTER.set(tx).is_err() {
+        anyhow::bail!("otlp exporter is already running");
+    }
+
+    let url = format!("{}{TRACES_PATH}", config.endpoint);
+    tokio::spawn(export_loop(
+        client,
+        url,
+        config.service_name.clone(),
+        Duration::from_millis(config.export_interval_ms),
+        rx,
+    ));
+    Ok(())
+}
+
+/// Sends the spans queued so far, e.g. before the process exits.
+pub async fn flush() {
+    let Some(exporter) = EXPORTER.get() else {
+        return;
+    };
+    let (tx, rx) = oneshot::channel();
+    if exporter.send(Message::Flush(tx)).await.is_ok() {
+        let _ = tokio::time::timeout(EXPORT_TIMEOUT, rx).await;
+    }
+}
+
+async fn export_loop(
+    client: reqwest::Client,
+    url: String,
+    service_name: String,
+    interval: Duration,
+    mut rx: mpsc::Receiver<Message>,
+) {
+    let mut batch = Vec::new();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        let flushed = tokio::select! {
+            message = rx.recv() => match message {
+                Some(Message::Span(span)) => {
+                    batch.push(span);
+                    if batch.len() < MAX_EXPORT_BATCH {
+                        continue;
+                    }
+                    None
+                }
+                Some(Message::Flush(done)) => Some(done),
+                None => return,
+            },
+            _ = ticker.tick() => None,
+        };
+
+        if !batch.is_empty() {
+            export(&client, &url, &service_name, std::mem::take(&mut batch)).await;
+        }
+        if let Some(done) = flushed {
+            let _ = done.send(());
+        }
+    }
+}
+
+async fn export(client: &reqwest::Client, url: &str, service_name: &str, spans: Vec<SpanData>) {
+    let count = spans.len();
+    let dropped = DROPPED_SPANS.swap(0, Ordering::Relaxed);
+    let body = export_request(service_name, &spans);
+    let result = client
+        .post(url)
+        .json(&body)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status);
+    // Logged outside of any span, so the failure is not exported itself.
+    match result {
+        Ok(_) if dropped > 0 => tracing::warn!(
+            component = "otlp",
+            dropped_spans = dropped,
+            message = "otlp queue was full, spans dropped"
+        ),
+        Ok(_) => {}
+        Err(err) => tracing::warn!(
+            component = "otlp",
+            error = %err,
+            spans = count,
+            dropped_spans = dropped,
+            message = "otlp export failed, spans dropped"
+        ),
+    }
+}
+
+/// `ExportTraceServiceRequest` in the JSON mapping of OTLP: IDs are hex, nanosecond
+/// timestamps are decimal strings.
+fn export_request(service_name: &str, spans: &[SpanData]) -> Value {
+    let spans: Vec<Value> = spans.iter().map(span_json).collect();
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", &Value::from(service_name))],
+            },
+            "scopeSpans": [{
+                "scope": { "name": SCOPE_NAME, "version": env!("CARGO_PKG_VERSION") },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn span_json(span: &SpanData) -> Value {
+    let mut value = json!({
+        "traceId": hex::encode(span.trace_id),
+        "spanId": hex::encode(span.span_id),
+        "name": span.name,
+        "kind": span_kind(span.name),
+        "startTimeUnixNano": unix_nanos(span.start).to_string(),
+        "endTimeUnixNano": unix_nanos(span.end).to_string(),
+        "attributes": span
+            .attributes
+            .iter()
+            .map(|(key, value)| attribute(key, value))
+            .collect::<Vec<_>>(),
+    });
+    if let Some(parent) = span.parent_span_id {
+        value["parentSpanId"] = Value::from(hex::encode(parent));
+    }
+    if let Some(message) = &span.error {
+        value["status"] = json!({ "code": STATUS_CODE_ERROR, "message": message });
+    }
+    value
+}
+
+fn attribute(key: &str, value: &Value) -> Value {
+    let value = match value {
+        Value::Bool(value) => json!({ "boolValue": value }),
+        Value::Number(number) if number.is_i64() || number.is_u64() => json!({ "intValue": number.to_string() }),
+        Value::Number(number) => json!({ "doubleValue": number.as_f64() }),
+        Value::String(value) => json!({ "stringValue": value }),
+        other => json!({ "stringValue": other.to_string() }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+/// Requests served by the API are server spans, calls to the node are client spans.
+fn span_kind(name: &str) -> u8 {
+    match name {
+        "http_request" => SPAN_KIND_SERVER,
+        "rpc_call" | "rpc_batch" | "rest_call" => SPAN_KIND_CLIENT,
+        _ => SPAN_KIND_INTERNAL,
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos()).unwrap_or_default()
+}
+
+/// Random per-process seed mixed with a sequence number; IDs only have to be unique.
+fn random_u64() -> u64 {
+    static SEED: OnceLock<RandomState> = OnceLock::new();
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = SEED.get_or_init(RandomState::new).build_hasher();
+    hasher.write_u64(SEQUENCE.fetch_add(1, Ordering::Relaxed));
+    hasher.finish()
+}
+
+impl<S> Layer<S> for OtlpLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if EXPORTER.get().is_none() {
+            return;
+        }
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let parent = span
+            .parent()
+            .and_then(|parent| parent.extensions().get::<SpanData>().map(|data| (data.trace_id, data.span_id)));
+        let trace_id = match parent {
+            Some((trace_id, _)) => trace_id,
+            None => {
+                let mut trace_id = [0; 16];
+                trace_id[..8].copy_from_slice(&random_u64().to_be_bytes());
+                trace_id[8..].copy_from_slice(&random_u64().to_be_bytes());
+                trace_id
+            }
+        };
+        let now = SystemTime::now();
+        let mut data = SpanData {
+            trace_id,
+            span_id: random_u64().to_be_bytes(),
+            parent_span_id: parent.map(|(_, span_id)| span_id),
+            name: attrs.metadata().name(),
+            start: now,
+            end: now,
+            attributes: Vec::new(),
+            error: None,
+        };
+        attrs.record(&mut AttributeVisitor(&mut data.attributes));
+        span.extensions_mut().insert(data);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions.get_mut::<SpanData>() {
+            values.record(&mut AttributeVisitor(&mut data.attributes));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if *event.metadata().level() > Level::WARN {
+            return;
+        }
+        let Some(span) = ctx.event_span(event) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(data) = extensions.get_mut::<SpanData>() {
+            let mut message = MessageVisitor(None);
+            event.record(&mut message);
+            data.error = Some(message.0.unwrap_or_else(|| event.metadata().name().to_string()));
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(exporter) = EXPORTER.get() else {
+            return;
+        };
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(mut data) = span.extensions_mut().remove::<SpanData>() else {
+            return;
+        };
+        data.end = SystemTime::now();
+        if exporter.try_send(Message::Span(data)).is_err() {
+            DROPPED_SPANS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Collects span fields as OTLP attributes; a field recorded again replaces its value.
+struct AttributeVisitor<'a>(&'a mut Vec<(&'static str, Value)>);
+
+impl AttributeVisitor<'_> {
+    fn insert(&mut self, field: &Field, value: Value) {
+        match self.0.iter_mut().find(|(key, _)| *key == field.name()) {
+            Some((_, existing)) => *existing = value,
+            None => self.0.push((field.name(), value)),
+        }
+    }
+}
+
+impl Visit for AttributeVisitor<'_> {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.insert(field, Value::from(format!("{value:?}")));
+    }
+}
+
+/// `message` of an event, or its `error` field when it has no message.
+struct MessageVisitor(Option<String>);
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" || (field.name() == "error" && self.0.is_none()) {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" || (field.name() == "error" && self.0.is_none()) {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_spans_in_the_otlp_json_mapping() {
+        let start = UNIX_EPOCH + Duration::from_millis(1_500);
+        let span = SpanData {
+            trace_id: [0xab; 16],
+            span_id: [0x01; 8],
+            parent_span_id: Some([0x02; 8]),
+            name: "rpc_call",
+            start,
+            end: start + Duration::from_millis(20),
+            attributes: vec![
+                ("rpc_method", Value::from("getblock")),
+                ("rpc_id", Value::from(7u64)),
+                ("ok", Value::from(true)),
+            ],
+            error: Some("rpc call failed".to_string()),
+        };
+
+        let request = export_request("indexer-test", &[span]);
+        let resource = &request["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0],
+            json!({ "key": "service.name", "value": { "stringValue": "indexer-test" } })
+        );
+        let span = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "ab".repeat(16));
+        assert_eq!(span["spanId"], "0101010101010101");
+        assert_eq!(span["parentSpanId"], "0202020202020202");
+        assert_eq!(span["kind"], SPAN_KIND_CLIENT);
+        assert_eq!(span["startTimeUnixNano"], "1500000000");
+        assert_eq!(span["endTimeUnixNano"], "1520000000");
+        assert_eq!(span["attributes"][1], json!({ "key": "rpc_id", "value": { "intValue": "7" } }));
+        assert_eq!(span["attributes"][2], json!({ "key": "ok", "value": { "boolValue": true } }));
+        assert_eq!(span["status"], json!({ "code": STATUS_CODE_ERROR, "message": "rpc call failed" }));
+    }
+}
@@ -1,18 +1,24 @@
 use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sqlx::{PgPool, Row};
 use thiserror::Error;
-use tracing::warn;
+use tracing::{info, warn};
 
+use crate::modules::config::IndexerConfig;
 use crate::modules::indexer::RpcTransaction;
+use crate::modules::reload::ReloadableConfig;
 use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::scripts::AddressEncoding;
 use crate::modules::storage::repo::{
-    TransactionRecord, TransactionsRepo, TxInputRecord, TxInputsRepo, TxOutputRecord, TxOutputsRepo,
+    AddressLookupRepo, DoubleSpendsRepo, OutboxRepo, TransactionRecord, TransactionsRepo, TxInputRecord, TxInputsRepo, TxOutputRecord,
+    TxOutputsRepo, TxReplacementsRepo,
 };
+use crate::modules::storage::SchemaFeatures;
 
 #[derive(Debug, Error)]
 pub enum MempoolError {
@@ -31,12 +37,79 @@ pub struct MempoolRunnerConfig {
 pub struct MempoolRunner {
     rpc: RpcClient,
     pool: PgPool,
-    config: MempoolRunnerConfig,
+    config: ReloadableConfig<MempoolRunnerConfig>,
+    encoding: AddressEncoding,
+    schema: SchemaFeatures,
+    outbox: bool,
+    store_decoded: bool,
+    store_witness: bool,
+    /// End of the last successful poll of the loop started by [`Self::start`].
+    last_synced_at: Arc<RwLock<Option<DateTime<Utc>>>>,
+}
+
+impl MempoolRunnerConfig {
+    pub fn from_config(config: &IndexerConfig) -> Self {
+        Self {
+            poll_interval: Duration::from_millis(config.poll.mempool_interval_ms),
+        }
+    }
 }
 
 impl MempoolRunner {
     pub fn new(rpc: RpcClient, pool: PgPool, config: MempoolRunnerConfig) -> Self {
-        Self { rpc, pool, config }
+        Self {
+            rpc,
+            pool,
+            config: ReloadableConfig::new(config),
+            encoding: AddressEncoding::Bitcoin(bitcoin::Network::Bitcoin),
+            schema: SchemaFeatures::latest(),
+            outbox: false,
+            store_decoded: true,
+            store_witness: false,
+            last_synced_at: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Queues `double_spends` events in `event_outbox` for the sink publisher. Off by default.
+    pub fn with_outbox(mut self, enabled: bool) -> Self {
+        self.outbox = enabled;
+        self
+    }
+
+    /// Stores the full node JSON of mempool transactions, see [`TransactionsRepo::with_decoded`].
+    pub fn with_decoded_tx(mut self, enabled: bool) -> Self {
+        self.store_decoded = enabled;
+        self
+    }
+
+    /// Stores the witness stacks of inputs in `tx_inputs.witness`. Off by default.
+    pub fn with_witness(mut self, enabled: bool) -> Self {
+        self.store_witness = enabled;
+        self
+    }
+
+    /// Network output addresses are encoded for; Bitcoin mainnet by default.
+    pub fn with_network(mut self, encoding: impl Into<AddressEncoding>) -> Self {
+        self.encoding = encoding.into();
+        self
+    }
+
+    pub fn update_config(&self, config: MempoolRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.config.get().poll_interval
+    }
+
+    /// `None` until the first poll succeeded, e.g. while the node is unreachable.
+    pub fn last_synced_at(&self) -> Option<DateTime<Utc>> {
+        *self.last_synced_at.read().unwrap_or_else(|err| err.into_inner())
     }
 
     pub fn start(&self) {
@@ -44,11 +117,14 @@ impl MempoolRunner {
 
         tokio::spawn(async move {
             loop {
-                if let Err(err) = runner.sync_once().await {
-                    warn!(component = "mempool", error = %err, message = "mempool sync failed");
+                match runner.sync_once().await {
+                    Ok(()) => {
+                        *runner.last_synced_at.write().unwrap_or_else(|err| err.into_inner()) = Some(Utc::now());
+                    }
+                    Err(err) => warn!(component = "mempool", error = %err, message = "mempool sync failed"),
                 }
 
-                tokio::time::sleep(runner.config.poll_interval).await;
+                tokio::time::sleep(runner.poll_interval()).await;
             }
         });
     }
@@ -82,6 +158,13 @@ impl MempoolRunner {
         Ok(())
     }
 
+    /// Persists a transaction relayed through the API before the next poll would find it;
+    /// from then on it is followed like any other mempool transaction.
+    pub async fn track(&self, txid: &str) -> Result<(), MempoolError> {
+        let tx = self.rpc.get_raw_transaction_verbose(txid).await?;
+        self.persist_mempool_transaction(&tx).await
+    }
+
     async fn list_known_mempool_txids(&self) -> Result<HashSet<String>, sqlx::Error> {
         let rows = sqlx::query(
             "SELECT txid \
@@ -114,14 +197,17 @@ impl MempoolRunner {
             return Ok(());
         }
 
-        let tx_repo = TransactionsRepo::new(&self.pool);
-        let inputs_repo = TxInputsRepo::new(&self.pool);
-        let outputs_repo = TxOutputsRepo::new(&self.pool);
+        let tx_repo = TransactionsRepo::new(&self.pool)
+            .with_schema_features(self.schema)
+            .with_decoded(self.store_decoded);
+        let inputs_repo = TxInputsRepo::new(&self.pool).with_schema_features(self.schema);
+        let address_lookup = AddressLookupRepo::new(&self.pool);
+        let outputs_repo = TxOutputsRepo::new(&self.pool).with_schema_features(self.schema);
         let now = Utc::now().timestamp();
 
         tx_repo
             .upsert(
-                &mut *db_tx,
+                &mut db_tx,
                 &TransactionRecord {
                     txid: tx.txid.clone(),
                     block_height: None,
@@ -130,12 +216,21 @@ impl MempoolRunner {
                     time: now,
                     status: "mempool".to_string(),
                     decoded: serde_json::to_value(tx).unwrap_or(Value::Null),
+                    vsize: tx.vsize,
+                    weight: tx.weight,
+                    version: tx.version,
+                    locktime: tx.locktime,
                 },
             )
             .await?;
 
         for (idx, vin) in tx.vin.iter().enumerate() {
             if let (Some(prev_txid), Some(prev_vout)) = (vin.txid.as_ref(), vin.vout) {
+                let prevout = if self.schema.input_prevouts {
+                    address_lookup.output(&mut *db_tx, prev_txid, prev_vout).await?
+                } else {
+                    None
+                };
                 inputs_repo
                     .insert(
                         &mut *db_tx,
@@ -145,6 +240,8 @@ impl MempoolRunner {
                             prev_txid: prev_txid.clone(),
                             prev_vout,
                             sequence: vin.sequence,
+                            prevout,
+                            witness: vin.txinwitness.clone().filter(|_| self.store_witness),
                         },
                     )
                     .await?;
@@ -152,11 +249,7 @@ impl MempoolRunner {
         }
 
         for vout in &tx.vout {
-            let address = vout
-                .script_pub_key
-                .address
-                .clone()
-                .or_else(|| vout.script_pub_key.addresses.as_ref().and_then(|list| list.first().cloned()));
+            let (script_type, address) = vout.script_pub_key.resolve(self.encoding);
 
             outputs_repo
                 .insert(
@@ -164,15 +257,50 @@ impl MempoolRunner {
                     &TxOutputRecord {
                         txid: tx.txid.clone(),
                         vout: vout.n,
-                        value_sats: btc_to_sats(vout.value),
-                        script_type: vout.script_pub_key.script_type.clone(),
+                        value_sats: vout.value_sats,
+                        script_type,
                         address,
                         script_hex: vout.script_pub_key.hex.clone(),
+                        block_height: None,
                     },
                 )
                 .await?;
         }
 
+        if self.schema.double_spends {
+            let conflicts = DoubleSpendsRepo::new(&self.pool)
+                .record_conflicts(&mut *db_tx, &tx.txid, "mempool", None)
+                .await?;
+            for conflict in &conflicts {
+                warn!(
+                    component = "mempool",
+                    txid = %conflict.txid,
+                    conflicting_txid = %conflict.conflicting_txid,
+                    conflicting_status = %conflict.conflicting_status,
+                    outpoint = %format!("{}:{}", conflict.prev_txid, conflict.prev_vout),
+                    message = "double spend detected"
+                );
+                if self.outbox && self.schema.event_outbox {
+                    OutboxRepo::new(&self.pool).insert(&mut *db_tx, &conflict.outbox_event()).await?;
+                }
+            }
+
+            // Only a mempool conflict can be replaced: the node accepted the new spend in its place.
+            if self.schema.tx_replacements {
+                let replacements = TxReplacementsRepo::new(&self.pool);
+                for conflict in conflicts.iter().filter(|conflict| conflict.conflicting_status == "mempool") {
+                    if replacements.record(&mut *db_tx, &conflict.conflicting_txid, &tx.txid).await? {
+                        info!(
+                            component = "mempool",
+                            replaced_txid = %conflict.conflicting_txid,
+                            replacement_txid = %tx.txid,
+                            message = "transaction replaced"
+                        );
+                    }
+                }
+            }
+        }
+
         db_tx.commit().await?;
         Ok(())
     }
@@ -205,10 +333,6 @@ fn diff_dropped_txids(current: &HashSet<String>, known: &HashSet<String>) -> Vec
     values
 }
 
-fn btc_to_sats(value: f64) -> i64 {
-    (value * 100_000_000.0).round() as i64
-}
-
 #[derive(Debug, Deserialize, Serialize)]
 pub struct MempoolAddressMatch {
     pub txid: String,
@@ -245,7 +369,7 @@ pub async fn list_mempool_txids_for_address(
 mod tests {
     use std::collections::HashSet;
 
-    use super::{btc_to_sats, diff_dropped_txids, diff_new_txids};
+    use super::{diff_dropped_txids, diff_new_txids};
 
     #[test]
     fn detects_new_txids() {
@@ -262,10 +386,4 @@ mod tests {
 
         assert_eq!(diff_dropped_txids(&current, &known), vec!["c".to_string()]);
     }
-
-    #[test]
-    fn converts_btc_to_sats() {
-        assert_eq!(btc_to_sats(0.00000001), 1);
-        assert_eq!(btc_to_sats(1.5), 150_000_000);
-    }
 }
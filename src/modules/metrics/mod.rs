@@ -4,6 +4,9 @@ use std::sync::{Arc, Mutex};
 
 use sqlx::{FromRow, PgPool};
 
+use crate::modules::alerting;
+use crate::modules::headers;
+
 const HISTOGRAM_BUCKETS: [f64; 11] = [0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
 
 #[derive(Debug, Clone, Default)]
@@ -19,6 +22,8 @@ struct MetricsInner {
     errors_total: Mutex<HashMap<String, u64>>,
     blocks_processed_total: Mutex<HashMap<String, u64>>,
     txs_processed_total: Mutex<HashMap<String, u64>>,
+    write_queue_depth: Mutex<HashMap<String, u64>>,
+    write_backpressure_total: Mutex<HashMap<String, u64>>,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +39,13 @@ struct JobMetricsRow {
     progress_height: i32,
 }
 
+#[derive(Debug, FromRow)]
+struct ReplicationSlotMetricsRow {
+    slot_name: String,
+    active: bool,
+    retained_wal_bytes: Option<i64>,
+}
+
 impl MetricsService {
     pub fn new() -> Self {
         Self::default()
@@ -53,6 +65,7 @@ impl MetricsService {
 
     pub fn increment_error(&self, error_type: &str) {
         increment_counter(&self.inner.errors_total, error_type, 1);
+        alerting::record_error(error_type);
     }
 
     pub fn increment_blocks_processed(&self, job_id: &str, count: u64) {
@@ -63,6 +76,17 @@ impl MetricsService {
         increment_counter(&self.inner.txs_processed_total, job_id, count);
     }
 
+    /// Fetched blocks of `job_id` waiting for a DB writer.
+    pub fn set_write_queue_depth(&self, job_id: &str, depth: u64) {
+        let mut guard = self.inner.write_queue_depth.lock().expect("metrics gauge mutex poisoned");
+        guard.insert(job_id.to_string(), depth);
+    }
+
+    /// Counts a fetched block of `job_id` that had to wait for room in the write queue.
+    pub fn increment_write_backpressure(&self, job_id: &str) {
+        increment_counter(&self.inner.write_backpressure_total, job_id, 1);
+    }
+
     pub async fn render(&self, pool: &PgPool) -> Result<String, sqlx::Error> {
         let tip_height = sqlx::query_scalar::<_, i32>(
             "SELECT tip_height
@@ -73,6 +97,7 @@ impl MetricsService {
         )
         .fetch_optional(pool)
         .await?;
+        let header_height = headers::header_tip(pool).await?.map(|(height, _)| height);
         let jobs: Vec<JobMetricsRow> = sqlx::query_as(
             "SELECT job_id, progress_height
              FROM jobs
@@ -80,6 +105,20 @@ impl MetricsService {
         )
         .fetch_all(pool)
         .await?;
+        let slots: Vec<ReplicationSlotMetricsRow> = sqlx::query_as(
+            "SELECT slot_name::TEXT AS slot_name,
+                    active,
+                    pg_wal_lsn_diff(
+                      CASE WHEN pg_is_in_recovery() THEN pg_last_wal_replay_lsn() ELSE pg_current_wal_lsn() END,
+                      restart_lsn
+                    )::BIGINT AS retained_wal_bytes
+             FROM pg_replication_slots
+             WHERE slot_type = 'logical'
+               AND database = current_database()
+             ORDER BY slot_name",
+        )
+        .fetch_all(pool)
+        .await?;
 
         let mut output = String::new();
 
@@ -88,6 +127,10 @@ impl MetricsService {
         let tip_value = tip_height.unwrap_or_default();
         let _ = writeln!(output, "indexer_tip_height {}", tip_value);
 
+        output.push_str("# HELP indexer_header_height Highest header synced by the header-first phase.\n");
+        output.push_str("# TYPE indexer_header_height gauge\n");
+        let _ = writeln!(output, "indexer_header_height {}", header_height.unwrap_or_default());
+
         output.push_str("# HELP indexer_progress_height Indexed progress height by job.\n");
         output.push_str("# TYPE indexer_progress_height gauge\n");
         for job in &jobs {
@@ -99,10 +142,11 @@ impl MetricsService {
             );
         }
 
-        output.push_str("# HELP indexer_lag_blocks Lag in blocks between node tip and job progress.\n");
+        output.push_str("# HELP indexer_lag_blocks Lag in blocks between node tip (or header tip) and job progress.\n");
         output.push_str("# TYPE indexer_lag_blocks gauge\n");
         for job in &jobs {
             let lag = tip_height
+                .or(header_height)
                 .map(|tip| (tip.saturating_sub(job.progress_height)).max(0))
                 .unwrap_or(0);
             let _ = writeln!(
@@ -113,6 +157,30 @@ impl MetricsService {
             );
         }
 
+        output.push_str("# HELP indexer_replication_slot_active Whether a consumer is connected to the logical replication slot.\n");
+        output.push_str("# TYPE indexer_replication_slot_active gauge\n");
+        for slot in &slots {
+            let _ = writeln!(
+                output,
+                "indexer_replication_slot_active{{slot_name=\"{}\"}} {}",
+                escape_label_value(&slot.slot_name),
+                u8::from(slot.active)
+            );
+        }
+
+        output.push_str(
+            "# HELP indexer_replication_slot_retained_wal_bytes WAL retained by the logical replication slot.\n",
+        );
+        output.push_str("# TYPE indexer_replication_slot_retained_wal_bytes gauge\n");
+        for slot in &slots {
+            let _ = writeln!(
+                output,
+                "indexer_replication_slot_retained_wal_bytes{{slot_name=\"{}\"}} {}",
+                escape_label_value(&slot.slot_name),
+                slot.retained_wal_bytes.unwrap_or_default()
+            );
+        }
+
         render_counter_family(
             &mut output,
             "indexer_blocks_processed_total",
@@ -127,6 +195,20 @@ impl MetricsService {
             "job_id",
             snapshot_counters(&self.inner.txs_processed_total),
         );
+        render_gauge_family(
+            &mut output,
+            "indexer_write_queue_depth",
+            "Fetched blocks waiting for a database writer by job.",
+            "job_id",
+            snapshot_counters(&self.inner.write_queue_depth),
+        );
+        render_counter_family(
+            &mut output,
+            "indexer_write_backpressure_total",
+            "Total number of fetched blocks that waited for room in the write queue by job.",
+            "job_id",
+            snapshot_counters(&self.inner.write_backpressure_total),
+        );
         render_counter_family(
             &mut output,
             "indexer_rpc_requests_total",
@@ -228,6 +310,21 @@ fn render_counter_family(
     }
 }
 
+fn render_gauge_family(output: &mut String, metric: &str, help: &str, label_name: &str, items: Vec<(String, u64)>) {
+    let _ = writeln!(output, "# HELP {} {}", metric, help);
+    let _ = writeln!(output, "# TYPE {} gauge", metric);
+    for (label_value, value) in items {
+        let _ = writeln!(
+            output,
+            "{}{{{}=\"{}\"}} {}",
+            metric,
+            label_name,
+            escape_label_value(&label_value),
+            value
+        );
+    }
+}
+
 fn render_histogram_family(
     output: &mut String,
     metric: &str,
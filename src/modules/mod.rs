@@ -1,11 +1,38 @@
+pub mod alerting;
+pub mod amount;
 pub mod api;
+pub mod auth;
+pub mod broadcast;
+pub mod chain;
 pub mod config;
 pub mod data;
+pub mod descriptors;
+pub mod events;
+pub mod export;
+pub mod fees;
+pub mod filters;
+pub mod headers;
 pub mod indexer;
 pub mod jobs;
 pub mod logging;
 pub mod mempool;
 pub mod metrics;
+pub mod node_info;
 pub mod nodes;
+pub mod profiling;
+pub mod protocols;
+pub mod rate_limit;
+pub mod reload;
+pub mod replication;
+pub mod retention;
 pub mod rpc;
+pub mod scan;
+pub mod scripts;
+pub mod sink;
+pub mod stats;
+pub mod status;
 pub mod storage;
+pub mod templates;
+pub mod tls;
+pub mod uptime;
+pub mod verify;
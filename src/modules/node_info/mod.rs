@@ -0,0 +1,278 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::modules::chain::RpcBlockchainInfo;
+use crate::modules::rpc::RpcClient;
+
+/// How long a fetched [`NodeInfo`] is served before the node is asked again.
+pub const NODE_INFO_TTL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum NodeInfoError {
+    #[error("node unavailable: {0}")]
+    NodeUnavailable(String),
+}
+
+/// Subset of the `getnetworkinfo` response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcNetworkInfo {
+    pub version: u64,
+    pub subversion: String,
+    #[serde(rename = "protocolversion")]
+    pub protocol_version: u64,
+    pub connections: u32,
+    #[serde(default)]
+    pub connections_in: Option<u32>,
+    #[serde(default)]
+    pub connections_out: Option<u32>,
+    #[serde(default = "default_network_active", rename = "networkactive")]
+    pub network_active: bool,
+    #[serde(rename = "relayfee", with = "crate::modules::amount::btc")]
+    pub relay_fee: i64,
+    /// A string before Bitcoin Core 28, a list of strings since.
+    #[serde(default)]
+    pub warnings: Value,
+}
+
+fn default_network_active() -> bool {
+    true
+}
+
+/// Subset of the `getmempoolinfo` response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcMempoolInfo {
+    #[serde(default = "default_loaded")]
+    pub loaded: bool,
+    pub size: u64,
+    pub bytes: u64,
+    pub usage: u64,
+    #[serde(rename = "maxmempool")]
+    pub max_mempool: u64,
+    #[serde(rename = "mempoolminfee", with = "crate::modules::amount::btc")]
+    pub mempool_min_fee: i64,
+}
+
+fn default_loaded() -> bool {
+    true
+}
+
+/// Node state for dashboards, from `getblockchaininfo`, `getnetworkinfo` and `getmempoolinfo`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeInfo {
+    pub blockchain: NodeBlockchainInfo,
+    pub network: NodeNetworkInfo,
+    pub mempool: NodeMempoolInfo,
+    /// When the node was asked; responses are reused for up to 5 seconds.
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeBlockchainInfo {
+    pub chain: String,
+    pub blocks: u64,
+    pub headers: u64,
+    pub best_block_hash: String,
+    pub difficulty: f64,
+    pub median_time: i64,
+    pub verification_progress: f64,
+    pub initial_block_download: bool,
+    pub size_on_disk: u64,
+    pub pruned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeNetworkInfo {
+    pub version: u64,
+    pub subversion: String,
+    pub protocol_version: u64,
+    pub connections: u32,
+    pub connections_in: Option<u32>,
+    pub connections_out: Option<u32>,
+    pub network_active: bool,
+    pub relay_fee_sat_vb: f64,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct NodeMempoolInfo {
+    pub loaded: bool,
+    pub tx_count: u64,
+    /// Sum of virtual sizes.
+    pub vsize_bytes: u64,
+    /// Memory used by the node's mempool.
+    pub usage_bytes: u64,
+    pub max_bytes: u64,
+    pub min_fee_sat_vb: f64,
+}
+
+/// Reads node state through the network's RPC client and caches it for [`NODE_INFO_TTL`],
+/// so dashboards polling the API do not turn into RPC load on the node.
+#[derive(Clone)]
+pub struct NodeInfoService {
+    rpc: Option<RpcClient>,
+    cached: Arc<Mutex<Option<(Instant, NodeInfo)>>>,
+}
+
+impl fmt::Debug for NodeInfoService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeInfoService")
+            .field("rpc", &self.rpc.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for NodeInfoService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeInfoService {
+    pub fn new() -> Self {
+        Self {
+            rpc: None,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn with_rpc(mut self, rpc: RpcClient) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+
+    /// Cached node state; concurrent callers after expiry wait for a single refresh.
+    /// Failures are not cached.
+    pub async fn info(&self) -> Result<NodeInfo, NodeInfoError> {
+        let mut cached = self.cached.lock().await;
+        if let Some((fetched, info)) = cached.as_ref() {
+            if fetched.elapsed() < NODE_INFO_TTL {
+                return Ok(info.clone());
+            }
+        }
+
+        let info = self.fetch().await?;
+        *cached = Some((Instant::now(), info.clone()));
+        Ok(info)
+    }
+
+    async fn fetch(&self) -> Result<NodeInfo, NodeInfoError> {
+        let Some(rpc) = &self.rpc else {
+            return Err(NodeInfoError::NodeUnavailable("rpc client is not configured".to_string()));
+        };
+        let (blockchain, network, mempool) =
+            tokio::try_join!(rpc.get_blockchain_info(), rpc.get_network_info(), rpc.get_mempool_info())
+                .map_err(|err| NodeInfoError::NodeUnavailable(err.to_string()))?;
+
+        Ok(node_info(blockchain, network, mempool, Utc::now()))
+    }
+}
+
+fn node_info(
+    blockchain: RpcBlockchainInfo,
+    network: RpcNetworkInfo,
+    mempool: RpcMempoolInfo,
+    fetched_at: DateTime<Utc>,
+) -> NodeInfo {
+    let warnings = match network.warnings {
+        Value::String(warning) if warning.is_empty() => Vec::new(),
+        Value::String(warning) => vec![warning],
+        Value::Array(warnings) => warnings
+            .into_iter()
+            .filter_map(|warning| warning.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    NodeInfo {
+        blockchain: NodeBlockchainInfo {
+            chain: blockchain.chain,
+            blocks: blockchain.blocks,
+            headers: blockchain.headers,
+            best_block_hash: blockchain.best_block_hash,
+            difficulty: blockchain.difficulty,
+            median_time: blockchain.median_time,
+            verification_progress: blockchain.verification_progress,
+            initial_block_download: blockchain.initial_block_download,
+            size_on_disk: blockchain.size_on_disk,
+            pruned: blockchain.pruned,
+        },
+        network: NodeNetworkInfo {
+            version: network.version,
+            subversion: network.subversion,
+            protocol_version: network.protocol_version,
+            connections: network.connections,
+            connections_in: network.connections_in,
+            connections_out: network.connections_out,
+            network_active: network.network_active,
+            relay_fee_sat_vb: sat_per_vbyte(network.relay_fee),
+            warnings,
+        },
+        mempool: NodeMempoolInfo {
+            loaded: mempool.loaded,
+            tx_count: mempool.size,
+            vsize_bytes: mempool.bytes,
+            usage_bytes: mempool.usage,
+            max_bytes: mempool.max_mempool,
+            min_fee_sat_vb: sat_per_vbyte(mempool.mempool_min_fee),
+        },
+        fetched_at,
+    }
+}
+
+/// Fee rate in sat/vB of a node rate in satoshis per kvB.
+fn sat_per_vbyte(sats_per_kvb: i64) -> f64 {
+    sats_per_kvb as f64 / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_node_responses() {
+        let blockchain: RpcBlockchainInfo = serde_json::from_str(
+            r#"{"chain":"main","blocks":840000,"headers":840001,"bestblockhash":"00ab","difficulty":8.6e13,
+                "mediantime":1713571533,"verificationprogress":0.9999,"initialblockdownload":false,
+                "size_on_disk":650000000000,"pruned":false}"#,
+        )
+        .expect("blockchain info");
+        let network: RpcNetworkInfo = serde_json::from_str(
+            r#"{"version":270000,"subversion":"/Satoshi:27.0.0/","protocolversion":70016,"connections":10,
+                "connections_in":0,"connections_out":10,"networkactive":true,"relayfee":0.00001000,
+                "warnings":["This is a pre-release test build"]}"#,
+        )
+        .expect("network info");
+        let mempool: RpcMempoolInfo = serde_json::from_str(
+            r#"{"loaded":true,"size":3,"bytes":750,"usage":4096,"maxmempool":300000000,"mempoolminfee":0.00001500}"#,
+        )
+        .expect("mempool info");
+
+        let info = node_info(blockchain, network, mempool, Utc::now());
+        assert_eq!(info.blockchain.blocks, 840_000);
+        assert_eq!(info.blockchain.best_block_hash, "00ab");
+        assert_eq!(info.network.relay_fee_sat_vb, 1.0);
+        assert_eq!(info.network.warnings, vec!["This is a pre-release test build".to_string()]);
+        assert_eq!(info.mempool.tx_count, 3);
+        assert_eq!(info.mempool.min_fee_sat_vb, 1.5);
+
+        let legacy: RpcNetworkInfo = serde_json::from_str(
+            r#"{"version":250000,"subversion":"/Satoshi:25.0.0/","protocolversion":70016,"connections":8,
+                "relayfee":0.00001000,"warnings":""}"#,
+        )
+        .expect("legacy network info");
+        assert!(legacy.connections_in.is_none());
+        let mempool: RpcMempoolInfo =
+            serde_json::from_str(r#"{"size":0,"bytes":0,"usage":0,"maxmempool":1,"mempoolminfee":0.00001}"#)
+                .expect("mempool info");
+        let blockchain: RpcBlockchainInfo = serde_json::from_str(r#"{"chain":"regtest"}"#).expect("blockchain info");
+        assert!(node_info(blockchain, legacy, mempool, Utc::now()).network.warnings.is_empty());
+    }
+}
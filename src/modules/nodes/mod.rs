@@ -7,8 +7,9 @@ use thiserror::Error;
 use tracing::warn;
 use utoipa::ToSchema;
 
-use crate::modules::config::RpcConfig;
+use crate::modules::config::{IndexerConfig, RpcConfig};
 use crate::modules::metrics::MetricsService;
+use crate::modules::reload::ReloadableConfig;
 use crate::modules::rpc::{RpcClient, RpcError};
 
 const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 5_000;
@@ -16,10 +17,10 @@ const DEFAULT_REQUEST_TIMEOUT_MS: u64 = 30_000;
 
 #[derive(Debug, Error)]
 pub enum NodesError {
-    #[error("node not found")]
-    NotFound,
-    #[error("node already exists")]
-    AlreadyExists,
+    #[error("node '{0}' not found")]
+    NotFound(String),
+    #[error("node '{0}' already exists")]
+    AlreadyExists(String),
     #[error("validation error: {0}")]
     Validation(String),
     #[error(transparent)]
@@ -72,7 +73,7 @@ pub struct NodesService {
 pub struct NodesRunner {
     pool: PgPool,
     metrics: MetricsService,
-    config: NodesRunnerConfig,
+    config: ReloadableConfig<NodesRunnerConfig>,
 }
 
 impl NodesService {
@@ -96,7 +97,7 @@ impl NodesService {
         .bind(&rpc.node_id)
         .bind(&rpc.url)
         .bind(&rpc.auth.username)
-        .bind(&rpc.auth.password)
+        .bind(rpc.auth.password.as_str())
         .bind(rpc.insecure_skip_verify)
         .execute(&self.pool)
         .await?;
@@ -123,7 +124,7 @@ impl NodesService {
         .rows_affected();
 
         if inserted == 0 {
-            return Err(NodesError::AlreadyExists);
+            return Err(NodesError::AlreadyExists(node.node_id.clone()));
         }
 
         self.get(&node.node_id).await
@@ -174,7 +175,7 @@ impl NodesService {
         .bind(node_id)
         .fetch_optional(&self.pool)
         .await?
-        .ok_or(NodesError::NotFound)?;
+        .ok_or_else(|| NodesError::NotFound(node_id.to_string()))?;
 
         let mut details = serde_json::json!({
             "url": row.url,
@@ -225,9 +226,29 @@ impl NodesService {
     }
 }
 
+impl NodesRunnerConfig {
+    pub fn from_config(config: &IndexerConfig) -> Self {
+        Self {
+            poll_interval: Duration::from_millis(config.poll.tip_interval_ms),
+        }
+    }
+}
+
 impl NodesRunner {
     pub fn new(pool: PgPool, metrics: MetricsService, config: NodesRunnerConfig) -> Self {
-        Self { pool, metrics, config }
+        Self {
+            pool,
+            metrics,
+            config: ReloadableConfig::new(config),
+        }
+    }
+
+    pub fn update_config(&self, config: NodesRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.config.get().poll_interval
     }
 
     pub fn start(&self) {
@@ -240,7 +261,7 @@ impl NodesRunner {
                     warn!(component = "nodes", error = %err, message = "node health sync failed");
                 }
 
-                tokio::time::sleep(runner.config.poll_interval).await;
+                tokio::time::sleep(runner.poll_interval()).await;
             }
         });
     }
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use thiserror::Error;
+use tokio::runtime::Handle;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+pub const DEFAULT_PROFILE_SECONDS: u64 = 10;
+pub const MAX_PROFILE_SECONDS: u64 = 60;
+pub const DEFAULT_PROFILE_FREQUENCY: u32 = 99;
+pub const MAX_PROFILE_FREQUENCY: u32 = 1000;
+
+#[derive(Debug, Error)]
+pub enum ProfilingError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error("a CPU profile capture is already running")]
+    Busy,
+    #[error("CPU profiling is not compiled in; build with `--features profiling`")]
+    Disabled,
+    #[error("CPU profile capture failed: {0}")]
+    Capture(String),
+}
+
+/// Per-worker counters of the tokio scheduler, accumulated since the runtime started.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct WorkerMetrics {
+    pub worker: usize,
+    pub busy_ms: u64,
+    /// Times the worker went idle waiting for tasks.
+    pub park_count: u64,
+    pub park_unpark_count: u64,
+    /// Only reported by builds with `--cfg tokio_unstable`.
+    pub mean_poll_time_us: Option<u64>,
+}
+
+/// Threads of `spawn_blocking`; only reported by builds with `--cfg tokio_unstable`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct BlockingPoolMetrics {
+    pub threads: usize,
+    pub idle_threads: usize,
+    /// Tasks waiting for a free blocking thread; non-zero means the pool is saturated.
+    pub queue_depth: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RuntimeMetrics {
+    pub workers: usize,
+    pub alive_tasks: usize,
+    /// Tasks scheduled from outside the runtime and not yet picked up by a worker.
+    pub global_queue_depth: usize,
+    pub uptime_seconds: u64,
+    /// Share of worker time spent polling tasks since startup, from 0 to 1.
+    pub busy_ratio: f64,
+    pub worker_stats: Vec<WorkerMetrics>,
+    pub blocking_pool: Option<BlockingPoolMetrics>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfilingService {
+    started_at: Instant,
+    capture: Arc<Mutex<()>>,
+}
+
+impl Default for ProfilingService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProfilingService {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            capture: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Snapshot of the runtime the caller runs on.
+    pub fn runtime_metrics(&self) -> RuntimeMetrics {
+        let metrics = Handle::current().metrics();
+        let uptime = self.started_at.elapsed();
+
+        let worker_stats: Vec<WorkerMetrics> = (0..metrics.num_workers())
+            .map(|worker| WorkerMetrics {
+                worker,
+                busy_ms: metrics.worker_total_busy_duration(worker).as_millis() as u64,
+                park_count: metrics.worker_park_count(worker),
+                park_unpark_count: metrics.worker_park_unpark_count(worker),
+                #[cfg(tokio_unstable)]
+                mean_poll_time_us: Some(metrics.worker_mean_poll_time(worker).as_micros() as u64),
+                #[cfg(not(tokio_unstable))]
+                mean_poll_time_us: None,
+            })
+            .collect();
+        let busy_ms: u64 = worker_stats.iter().map(|worker| worker.busy_ms).sum();
+        let capacity_ms = uptime.as_millis() as f64 * metrics.num_workers() as f64;
+
+        #[cfg(tokio_unstable)]
+        let blocking_pool = Some(BlockingPoolMetrics {
+            threads: metrics.num_blocking_threads(),
+            idle_threads: metrics.num_idle_blocking_threads(),
+            queue_depth: metrics.blocking_queue_depth(),
+        });
+        #[cfg(not(tokio_unstable))]
+        let blocking_pool = None;
+
+        RuntimeMetrics {
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            global_queue_depth: metrics.global_queue_depth(),
+            uptime_seconds: uptime.as_secs(),
+            busy_ratio: if capacity_ms > 0.0 {
+                (busy_ms as f64 / capacity_ms).min(1.0)
+            } else {
+                0.0
+            },
+            worker_stats,
+            blocking_pool,
+        }
+    }
+
+    /// Samples the whole process for `seconds` and returns the profile in the protobuf
+    /// format of `go tool pprof`. Only one capture runs at a time.
+    pub async fn capture_cpu_profile(&self, seconds: Option<u64>, frequency: Option<u32>) -> Result<Vec<u8>, ProfilingError> {
+        let seconds = seconds.unwrap_or(DEFAULT_PROFILE_SECONDS);
+        if seconds == 0 || seconds > MAX_PROFILE_SECONDS {
+            return Err(ProfilingError::Validation(format!(
+                "seconds MUST be between 1 and {MAX_PROFILE_SECONDS}"
+            )));
+        }
+        let frequency = frequency.unwrap_or(DEFAULT_PROFILE_FREQUENCY);
+        if frequency == 0 || frequency > MAX_PROFILE_FREQUENCY {
+            return Err(ProfilingError::Validation(format!(
+                "frequency MUST be between 1 and {MAX_PROFILE_FREQUENCY}"
+            )));
+        }
+
+        let _capture = self.capture.try_lock().map_err(|_| ProfilingError::Busy)?;
+        capture(Duration::from_secs(seconds), frequency).await
+    }
+}
+
+#[cfg(feature = "profiling")]
+async fn capture(duration: Duration, frequency: u32) -> Result<Vec<u8>, ProfilingError> {
+    use pprof::protos::Message;
+
+    // The profiler guard is not `Send`, so the sampling window is waited out on a blocking thread.
+    tokio::task::spawn_blocking(move || {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(frequency as i32)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()
+            .map_err(|err| ProfilingError::Capture(err.to_string()))?;
+        std::thread::sleep(duration);
+        let profile = guard
+            .report()
+            .build()
+            .and_then(|report| report.pprof())
+            .map_err(|err| ProfilingError::Capture(err.to_string()))?;
+        Ok(profile.encode_to_vec())
+    })
+    .await
+    .map_err(|err| ProfilingError::Capture(err.to_string()))?
+}
+
+#[cfg(not(feature = "profiling"))]
+async fn capture(_duration: Duration, _frequency: u32) -> Result<Vec<u8>, ProfilingError> {
+    Err(ProfilingError::Disabled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProfilingError, ProfilingService};
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn reports_runtime_metrics_and_validates_profile_window() {
+        let service = ProfilingService::new();
+        let metrics = service.runtime_metrics();
+        assert_eq!(metrics.workers, 2);
+        assert_eq!(metrics.worker_stats.len(), 2);
+        assert!((0.0..=1.0).contains(&metrics.busy_ratio));
+
+        assert!(matches!(
+            service.capture_cpu_profile(Some(0), None).await,
+            Err(ProfilingError::Validation(_))
+        ));
+        assert!(matches!(
+            service.capture_cpu_profile(Some(61), None).await,
+            Err(ProfilingError::Validation(_))
+        ));
+        assert!(matches!(
+            service.capture_cpu_profile(None, Some(5000)).await,
+            Err(ProfilingError::Validation(_))
+        ));
+    }
+}
@@ -0,0 +1,70 @@
+//! Protocol plugins: metaprotocols embedded in transactions, indexed into their own tables
+//! by [`IndexerPipeline`](crate::modules::indexer::IndexerPipeline) in the database
+//! transaction that writes a block. Jobs enable them by name in `protocols`.
+
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+use sqlx::PgConnection;
+
+use crate::modules::indexer::RpcTransaction;
+use crate::modules::scripts::AddressEncoding;
+use crate::modules::storage::SchemaFeatures;
+
+pub mod runes;
+
+pub const PROTOCOL_RUNES: &str = "runes";
+pub const PROTOCOLS: [&str; 1] = [PROTOCOL_RUNES];
+
+/// Transaction handed to protocol indexers, in block order.
+#[derive(Debug, Clone, Copy)]
+pub struct ProtocolTx<'a> {
+    pub block_height: i32,
+    pub position_in_block: usize,
+    pub tx: &'a RpcTransaction,
+    /// Encoding of output addresses, see [`RpcScriptPubKey::resolve`](crate::modules::indexer::RpcScriptPubKey::resolve).
+    pub encoding: AddressEncoding,
+}
+
+/// Indexer of one protocol. Blocks reach it in height order; when blocks are orphaned,
+/// [`Self::rollback`] drops what it indexed for them before they are written again.
+pub trait ProtocolIndexer: Send + Sync {
+    /// Name jobs enable it by, also recorded in the provenance stages of a block.
+    fn name(&self) -> &'static str;
+
+    /// Indexes `tx` in the database transaction that writes it, after its inputs and outputs.
+    fn index_transaction<'a>(
+        &'a self,
+        conn: &'a mut PgConnection,
+        tx: ProtocolTx<'a>,
+    ) -> BoxFuture<'a, Result<(), sqlx::Error>>;
+
+    /// Forgets everything indexed at `from_height` and above.
+    fn rollback<'a>(&'a self, conn: &'a mut PgConnection, from_height: i32) -> BoxFuture<'a, Result<(), sqlx::Error>>;
+}
+
+/// Built-in indexers whose tables exist with `schema`.
+pub fn available(schema: SchemaFeatures) -> Vec<Arc<dyn ProtocolIndexer>> {
+    let mut protocols: Vec<Arc<dyn ProtocolIndexer>> = Vec::new();
+    if schema.runes {
+        protocols.push(Arc::new(runes::RunesIndexer));
+    }
+    protocols
+}
+
+/// Indexers of `names` among [`available`]; names without tables are skipped.
+pub fn enabled(schema: SchemaFeatures, names: &[String]) -> Vec<Arc<dyn ProtocolIndexer>> {
+    available(schema)
+        .into_iter()
+        .filter(|protocol| names.iter().any(|name| name == protocol.name()))
+        .collect()
+}
+
+/// Rolls back every available protocol, whichever jobs enabled it, since any of them may
+/// have indexed the orphaned blocks.
+pub async fn rollback_all(conn: &mut PgConnection, schema: SchemaFeatures, from_height: i32) -> Result<(), sqlx::Error> {
+    for protocol in available(schema) {
+        protocol.rollback(&mut *conn, from_height).await?;
+    }
+    Ok(())
+}
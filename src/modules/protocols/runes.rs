@@ -0,0 +1,786 @@
+//! Runes: fungible tokens etched, minted and transferred by a runestone, the first
+//! `OP_RETURN OP_13` output of a transaction. Balances live on outputs: `rune_outputs`
+//! holds the runes each output carries until it is spent (`0035_runes.sql`).
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+
+use bitcoin::blockdata::opcodes::all::{OP_PUSHNUM_13, OP_RETURN};
+use bitcoin::script::{Instruction, Script};
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt;
+use sqlx::{FromRow, PgConnection};
+
+use super::{ProtocolIndexer, ProtocolTx, PROTOCOL_RUNES};
+use crate::modules::indexer::RpcTransaction;
+use crate::modules::scripts::AddressEncoding;
+
+const TAG_BODY: u128 = 0;
+const TAG_DIVISIBILITY: u128 = 1;
+const TAG_FLAGS: u128 = 2;
+const TAG_SPACERS: u128 = 3;
+const TAG_RUNE: u128 = 4;
+const TAG_SYMBOL: u128 = 5;
+const TAG_PREMINE: u128 = 6;
+const TAG_CAP: u128 = 8;
+const TAG_AMOUNT: u128 = 10;
+const TAG_HEIGHT_START: u128 = 12;
+const TAG_HEIGHT_END: u128 = 14;
+const TAG_OFFSET_START: u128 = 16;
+const TAG_OFFSET_END: u128 = 18;
+const TAG_MINT: u128 = 20;
+const TAG_POINTER: u128 = 22;
+
+const FLAG_ETCHING: u128 = 1;
+const FLAG_TERMS: u128 = 1 << 1;
+const FLAG_TURBO: u128 = 1 << 2;
+
+const MAX_DIVISIBILITY: u128 = 38;
+const MAX_SPACERS: u128 = 0b0000_0111_1111_1111_1111_1111_1111_1111;
+/// Names from here on are assigned to etchings without a name and cannot be etched.
+const RESERVED_RUNE: u128 = 6_402_364_363_415_443_603_228_541_259_936_211_926;
+
+/// Etching block and transaction index of a rune.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RuneId {
+    pub block: u64,
+    pub tx: u32,
+}
+
+impl RuneId {
+    fn new(block: u64, tx: u32) -> Option<Self> {
+        (block > 0 || tx == 0).then_some(Self { block, tx })
+    }
+
+    /// Id of the next edict, whose id is delta-encoded against the previous one.
+    fn next(self, block: u128, tx: u128) -> Option<Self> {
+        let block = self.block.checked_add(u64::try_from(block).ok()?)?;
+        let tx = if block == self.block {
+            self.tx.checked_add(u32::try_from(tx).ok()?)?
+        } else {
+            u32::try_from(tx).ok()?
+        };
+        Self::new(block, tx)
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (block, tx) = value.split_once(':')?;
+        Self::new(block.parse().ok()?, tx.parse().ok()?)
+    }
+}
+
+impl fmt::Display for RuneId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.block, self.tx)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edict {
+    pub id: RuneId,
+    /// `0` moves the whole unallocated balance.
+    pub amount: u128,
+    /// Index of the output; the number of outputs splits the amount over all that are not `OP_RETURN`.
+    pub output: u32,
+}
+
+/// Open mint of an etched rune.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Terms {
+    pub amount: Option<u128>,
+    pub cap: Option<u128>,
+    pub height_start: Option<u64>,
+    pub height_end: Option<u64>,
+    pub offset_start: Option<u64>,
+    pub offset_end: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Etching {
+    pub rune: Option<u128>,
+    pub divisibility: Option<u8>,
+    pub spacers: Option<u32>,
+    pub symbol: Option<char>,
+    pub premine: Option<u128>,
+    pub terms: Option<Terms>,
+    pub turbo: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Artifact {
+    Runestone {
+        edicts: Vec<Edict>,
+        etching: Option<Box<Etching>>,
+        mint: Option<RuneId>,
+        /// Output receiving the runes left unallocated; the first output that is not `OP_RETURN` otherwise.
+        pointer: Option<u32>,
+    },
+    /// Malformed runestone: the runes of the inputs are burned, a named etching creates a
+    /// rune without premine and terms, and a mint counts against the cap but is burned.
+    Cenotaph { etching: Option<u128>, mint: Option<RuneId> },
+}
+
+impl Artifact {
+    fn mint(&self) -> Option<RuneId> {
+        match self {
+            Artifact::Runestone { mint, .. } | Artifact::Cenotaph { mint, .. } => *mint,
+        }
+    }
+}
+
+/// Runestone of `tx`, if one of its outputs carries one.
+pub fn decipher(tx: &RpcTransaction) -> Option<Artifact> {
+    let payload = tx.vout.iter().find_map(|vout| runestone_payload(&vout.script_pub_key.hex))?;
+    let Some(payload) = payload else {
+        return Some(Artifact::Cenotaph { etching: None, mint: None });
+    };
+    let Some(integers) = decode_integers(&payload) else {
+        return Some(Artifact::Cenotaph { etching: None, mint: None });
+    };
+
+    let outputs = tx.vout.len();
+    let mut fields: HashMap<u128, VecDeque<u128>> = HashMap::new();
+    let mut edicts = Vec::new();
+    let mut flawed = false;
+    let mut idx = 0;
+    while idx < integers.len() {
+        let tag = integers[idx];
+        if tag == TAG_BODY {
+            let mut id = RuneId::default();
+            for chunk in integers[idx + 1..].chunks(4) {
+                let edict = match chunk {
+                    [block, tx, amount, output] => id.next(*block, *tx).and_then(|next| {
+                        id = next;
+                        let output = u32::try_from(*output).ok().filter(|output| *output as usize <= outputs)?;
+                        Some(Edict {
+                            id: next,
+                            amount: *amount,
+                            output,
+                        })
+                    }),
+                    _ => None,
+                };
+                match edict {
+                    Some(edict) => edicts.push(edict),
+                    None => {
+                        flawed = true;
+                        break;
+                    }
+                }
+            }
+            break;
+        }
+
+        let Some(&value) = integers.get(idx + 1) else {
+            flawed = true;
+            break;
+        };
+        fields.entry(tag).or_default().push_back(value);
+        idx += 2;
+    }
+
+    let mint = take(&mut fields, TAG_MINT, 2, |values| {
+        RuneId::new(u64::try_from(values[0]).ok()?, u32::try_from(values[1]).ok()?)
+    });
+    let pointer = take(&mut fields, TAG_POINTER, 1, |values| {
+        u32::try_from(values[0]).ok().filter(|pointer| (*pointer as usize) < outputs)
+    });
+    let divisibility = take(&mut fields, TAG_DIVISIBILITY, 1, |values| {
+        u8::try_from(values[0]).ok().filter(|value| u128::from(*value) <= MAX_DIVISIBILITY)
+    });
+    let spacers = take(&mut fields, TAG_SPACERS, 1, |values| {
+        u32::try_from(values[0]).ok().filter(|value| u128::from(*value) <= MAX_SPACERS)
+    });
+    let symbol = take(&mut fields, TAG_SYMBOL, 1, |values| char::from_u32(u32::try_from(values[0]).ok()?));
+    let rune = take(&mut fields, TAG_RUNE, 1, |values| Some(values[0]));
+    let premine = take(&mut fields, TAG_PREMINE, 1, |values| Some(values[0]));
+    let cap = take(&mut fields, TAG_CAP, 1, |values| Some(values[0]));
+    let amount = take(&mut fields, TAG_AMOUNT, 1, |values| Some(values[0]));
+    let height_start = take(&mut fields, TAG_HEIGHT_START, 1, |values| u64::try_from(values[0]).ok());
+    let height_end = take(&mut fields, TAG_HEIGHT_END, 1, |values| u64::try_from(values[0]).ok());
+    let offset_start = take(&mut fields, TAG_OFFSET_START, 1, |values| u64::try_from(values[0]).ok());
+    let offset_end = take(&mut fields, TAG_OFFSET_END, 1, |values| u64::try_from(values[0]).ok());
+    let flags = take(&mut fields, TAG_FLAGS, 1, |values| Some(values[0])).unwrap_or_default();
+
+    // Unknown flags and even tags are features this decoder does not know, so the message
+    // cannot be interpreted safely.
+    flawed |= flags & !(FLAG_ETCHING | FLAG_TERMS | FLAG_TURBO) != 0;
+    flawed |= fields.keys().any(|tag| tag % 2 == 0);
+
+    let etching = (flags & FLAG_ETCHING != 0).then(|| Etching {
+        rune,
+        divisibility,
+        spacers,
+        symbol,
+        premine,
+        terms: (flags & FLAG_TERMS != 0).then_some(Terms {
+            amount,
+            cap,
+            height_start,
+            height_end,
+            offset_start,
+            offset_end,
+        }),
+        turbo: flags & FLAG_TURBO != 0,
+    });
+    if let Some(etching) = &etching {
+        let minted = etching
+            .terms
+            .as_ref()
+            .map_or(Some(0), |terms| terms.cap.unwrap_or_default().checked_mul(terms.amount.unwrap_or_default()));
+        flawed |= minted.and_then(|minted| minted.checked_add(etching.premine.unwrap_or_default())).is_none();
+    }
+
+    if flawed {
+        return Some(Artifact::Cenotaph {
+            etching: etching.and_then(|etching| etching.rune),
+            mint,
+        });
+    }
+
+    Some(Artifact::Runestone {
+        edicts,
+        etching: etching.map(Box::new),
+        mint,
+        pointer,
+    })
+}
+
+/// Data pushes of a runestone script; `Some(None)` when it carries something else.
+fn runestone_payload(script_hex: &str) -> Option<Option<Vec<u8>>> {
+    let bytes = hex::decode(script_hex).ok()?;
+    let script = Script::from_bytes(&bytes);
+    let mut instructions = script.instructions();
+    if instructions.next() != Some(Ok(Instruction::Op(OP_RETURN))) {
+        return None;
+    }
+    if instructions.next() != Some(Ok(Instruction::Op(OP_PUSHNUM_13))) {
+        return None;
+    }
+
+    let mut payload = Vec::new();
+    for instruction in instructions {
+        match instruction {
+            Ok(Instruction::PushBytes(push)) => payload.extend_from_slice(push.as_bytes()),
+            Ok(Instruction::Op(_)) | Err(_) => return Some(None),
+        }
+    }
+    Some(Some(payload))
+}
+
+/// LEB128 integers of a payload; `None` if one is truncated or exceeds `u128`.
+fn decode_integers(payload: &[u8]) -> Option<Vec<u128>> {
+    let mut integers = Vec::new();
+    let mut value: u128 = 0;
+    let mut shift = 0;
+    for (idx, byte) in payload.iter().enumerate() {
+        if shift > 126 || (shift == 126 && byte & 0x7f > 0b11) {
+            return None;
+        }
+        value |= u128::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            integers.push(value);
+            value = 0;
+            shift = 0;
+        } else if idx == payload.len() - 1 {
+            return None;
+        } else {
+            shift += 7;
+        }
+    }
+    Some(integers)
+}
+
+/// Removes the first `count` values of `tag` if `parse` accepts them; values it rejects
+/// stay, so an even tag with an invalid value makes the runestone a cenotaph.
+fn take<T>(
+    fields: &mut HashMap<u128, VecDeque<u128>>,
+    tag: u128,
+    count: usize,
+    parse: impl FnOnce(&[u128]) -> Option<T>,
+) -> Option<T> {
+    let values = fields.get_mut(&tag)?;
+    if values.len() < count {
+        return None;
+    }
+    let taken: Vec<u128> = values.iter().take(count).copied().collect();
+    let parsed = parse(&taken)?;
+    values.drain(..count);
+    if values.is_empty() {
+        fields.remove(&tag);
+    }
+    Some(parsed)
+}
+
+/// Name of a rune, `A` for 0, `Z` for 25, `AA` for 26 and so on.
+pub fn rune_name(rune: u128) -> String {
+    let mut n = rune;
+    if n == u128::MAX {
+        return "BCGDENLQRQWDSLRUGSNLBTMFIJAV".to_string();
+    }
+
+    n += 1;
+    let mut name = Vec::new();
+    while n > 0 {
+        name.push(b'A' + ((n - 1) % 26) as u8);
+        n = (n - 1) / 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// `name` with `•` after each letter whose bit is set in `spacers`.
+pub fn spaced_name(name: &str, spacers: u32) -> String {
+    let mut spaced = String::new();
+    for (idx, letter) in name.chars().enumerate() {
+        spaced.push(letter);
+        if idx + 1 < name.len() && spacers & (1 << idx) != 0 {
+            spaced.push('•');
+        }
+    }
+    spaced
+}
+
+/// Runes per output after the edicts of `artifact` moved `unallocated`: the balances of
+/// the inputs plus what the transaction minted and premined. Runes on `OP_RETURN` outputs
+/// and runes without a destination are burned, i.e. left out.
+pub fn allocate(
+    mut unallocated: BTreeMap<RuneId, u128>,
+    artifact: Option<&Artifact>,
+    etched: Option<RuneId>,
+    op_return: &[bool],
+) -> Vec<BTreeMap<RuneId, u128>> {
+    let mut allocated = vec![BTreeMap::new(); op_return.len()];
+    let pointer = match artifact {
+        Some(Artifact::Cenotaph { .. }) => return allocated,
+        Some(Artifact::Runestone { edicts, pointer, .. }) => {
+            for edict in edicts {
+                let id = if edict.id == RuneId::default() {
+                    match etched {
+                        Some(id) => id,
+                        None => continue,
+                    }
+                } else {
+                    edict.id
+                };
+                let Some(balance) = unallocated.get_mut(&id) else {
+                    continue;
+                };
+
+                let output = edict.output as usize;
+                let mut grants = Vec::new();
+                if output == op_return.len() {
+                    let destinations: Vec<usize> = (0..op_return.len()).filter(|idx| !op_return[*idx]).collect();
+                    if edict.amount == 0 && !destinations.is_empty() {
+                        let count = destinations.len() as u128;
+                        let (each, remainder) = (*balance / count, *balance % count);
+                        for (idx, output) in destinations.into_iter().enumerate() {
+                            grants.push((output, each + u128::from((idx as u128) < remainder)));
+                        }
+                    } else {
+                        grants.extend(destinations.into_iter().map(|output| (output, edict.amount)));
+                    }
+                } else {
+                    grants.push((output, if edict.amount == 0 { *balance } else { edict.amount }));
+                }
+
+                for (output, amount) in grants {
+                    let amount = amount.min(*balance);
+                    if amount > 0 {
+                        *balance -= amount;
+                        *allocated[output].entry(id).or_default() += amount;
+                    }
+                }
+            }
+            pointer.map(|pointer| pointer as usize)
+        }
+        None => None,
+    };
+
+    if let Some(output) = pointer.or_else(|| op_return.iter().position(|op_return| !op_return)) {
+        for (id, balance) in unallocated {
+            if balance > 0 {
+                *allocated[output].entry(id).or_default() += balance;
+            }
+        }
+    }
+    for (output, op_return) in op_return.iter().enumerate() {
+        if *op_return {
+            allocated[output].clear();
+        }
+    }
+    allocated
+}
+
+/// Height of the first block that may carry runestones on the chain of `encoding`; `None`
+/// on chains without Runes.
+fn first_rune_height(encoding: AddressEncoding) -> Option<i32> {
+    match encoding {
+        AddressEncoding::Bitcoin(bitcoin::Network::Bitcoin) => Some(840_000),
+        AddressEncoding::Bitcoin(bitcoin::Network::Testnet) => Some(2_520_000),
+        AddressEncoding::Bitcoin(_) => Some(0),
+        AddressEncoding::Base58 { .. } => None,
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct MintTermsRow {
+    block_height: i32,
+    terms: bool,
+    mint_amount: Option<String>,
+    mint_cap: Option<String>,
+    mint_height_start: Option<i64>,
+    mint_height_end: Option<i64>,
+    mint_offset_start: Option<i64>,
+    mint_offset_end: Option<i64>,
+    mints: i64,
+}
+
+/// Built-in [`ProtocolIndexer`] of Runes.
+///
+/// Etchings are accepted without checking the commitment of the name in a tapscript of
+/// an input or the minimum name length at the height; a name that is already etched
+/// makes the etching invalid.
+pub struct RunesIndexer;
+
+impl RunesIndexer {
+    async fn index(&self, conn: &mut PgConnection, ptx: ProtocolTx<'_>) -> Result<(), sqlx::Error> {
+        let Some(first_height) = first_rune_height(ptx.encoding) else {
+            return Ok(());
+        };
+        if ptx.block_height < first_height {
+            return Ok(());
+        }
+
+        let tx = ptx.tx;
+        let mut unallocated = self.spend_inputs(conn, ptx).await?;
+        let artifact = decipher(tx);
+        if artifact.is_none() && unallocated.is_empty() {
+            return Ok(());
+        }
+
+        let mut etched = None;
+        if let Some(artifact) = &artifact {
+            if let Some(id) = artifact.mint() {
+                if let Some(amount) = self.mint(conn, ptx, id).await? {
+                    *unallocated.entry(id).or_default() += amount;
+                }
+            }
+
+            let id = RuneId {
+                block: ptx.block_height as u64,
+                tx: ptx.position_in_block as u32,
+            };
+            let etching = match artifact {
+                Artifact::Runestone {
+                    etching: Some(etching), ..
+                } => Some(etching.as_ref().clone()),
+                Artifact::Cenotaph {
+                    etching: Some(rune), ..
+                } => Some(Etching {
+                    rune: Some(*rune),
+                    ..Etching::default()
+                }),
+                _ => None,
+            };
+            if let Some(etching) = etching {
+                if self.etch(conn, ptx, id, &etching).await? {
+                    etched = Some(id);
+                    if matches!(artifact, Artifact::Runestone { .. }) {
+                        *unallocated.entry(id).or_default() += etching.premine.unwrap_or_default();
+                    }
+                }
+            }
+        }
+
+        let op_return: Vec<bool> = tx.vout.iter().map(|vout| vout.script_pub_key.hex.starts_with("6a")).collect();
+        let allocated = allocate(unallocated, artifact.as_ref(), etched, &op_return);
+        for (vout, balances) in tx.vout.iter().zip(allocated) {
+            if balances.is_empty() {
+                continue;
+            }
+            let (_, address) = vout.script_pub_key.resolve(ptx.encoding);
+            for (id, amount) in balances {
+                sqlx::query(
+                    "INSERT INTO rune_outputs (txid, vout, rune_id, amount, address, block_height) \
+                     VALUES ($1, $2, $3, $4::NUMERIC, $5, $6) \
+                     ON CONFLICT (txid, vout, rune_id) DO UPDATE SET amount = EXCLUDED.amount",
+                )
+                .bind(&tx.txid)
+                .bind(vout.n)
+                .bind(id.to_string())
+                .bind(amount.to_string())
+                .bind(&address)
+                .bind(ptx.block_height)
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marks the rune outputs spent by `tx` and returns the runes they carried.
+    async fn spend_inputs(&self, conn: &mut PgConnection, ptx: ProtocolTx<'_>) -> Result<BTreeMap<RuneId, u128>, sqlx::Error> {
+        let (txids, vouts): (Vec<String>, Vec<i32>) = ptx
+            .tx
+            .vin
+            .iter()
+            .filter_map(|vin| Some((vin.txid.clone()?, vin.vout?)))
+            .unzip();
+        let mut balances = BTreeMap::new();
+        if txids.is_empty() {
+            return Ok(balances);
+        }
+
+        let spent: Vec<(String, String)> = sqlx::query_as(
+            "UPDATE rune_outputs r \
+             SET spent_txid = $3, spent_height = $4 \
+             FROM UNNEST($1::TEXT[], $2::INT[]) AS i(txid, vout) \
+             WHERE r.txid = i.txid AND r.vout = i.vout AND r.spent_txid IS NULL \
+             RETURNING r.rune_id, r.amount::TEXT",
+        )
+        .bind(&txids)
+        .bind(&vouts)
+        .bind(&ptx.tx.txid)
+        .bind(ptx.block_height)
+        .fetch_all(&mut *conn)
+        .await?;
+        for (rune_id, amount) in spent {
+            if let (Some(id), Ok(amount)) = (RuneId::parse(&rune_id), amount.parse::<u128>()) {
+                let balance: &mut u128 = balances.entry(id).or_default();
+                *balance = balance.saturating_add(amount);
+            }
+        }
+        Ok(balances)
+    }
+
+    /// Records a mint of `id` and returns its amount when the terms of the rune allow it.
+    async fn mint(&self, conn: &mut PgConnection, ptx: ProtocolTx<'_>, id: RuneId) -> Result<Option<u128>, sqlx::Error> {
+        let terms: Option<MintTermsRow> = sqlx::query_as(
+            "SELECT r.block_height, r.terms, r.mint_amount::TEXT AS mint_amount, r.mint_cap::TEXT AS mint_cap, \
+               r.mint_height_start, r.mint_height_end, r.mint_offset_start, r.mint_offset_end, \
+               (SELECT COUNT(*) FROM rune_mints m WHERE m.rune_id = r.rune_id) AS mints \
+             FROM runes r \
+             WHERE r.rune_id = $1",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&mut *conn)
+        .await?;
+        let Some(terms) = terms.filter(|terms| terms.terms) else {
+            return Ok(None);
+        };
+
+        let height = i64::from(ptx.block_height);
+        let etched_at = i64::from(terms.block_height);
+        let start = [terms.mint_height_start, terms.mint_offset_start.map(|offset| etched_at + offset)]
+            .into_iter()
+            .flatten()
+            .max();
+        let end = [terms.mint_height_end, terms.mint_offset_end.map(|offset| etched_at + offset)]
+            .into_iter()
+            .flatten()
+            .min();
+        let cap: u128 = terms.mint_cap.and_then(|cap| cap.parse().ok()).unwrap_or_default();
+        if start.is_some_and(|start| height < start)
+            || end.is_some_and(|end| height >= end)
+            || u128::try_from(terms.mints).unwrap_or_default() >= cap
+        {
+            return Ok(None);
+        }
+
+        let amount: u128 = terms.mint_amount.and_then(|amount| amount.parse().ok()).unwrap_or_default();
+        sqlx::query("INSERT INTO rune_mints (rune_id, txid, block_height, amount) VALUES ($1, $2, $3, $4::NUMERIC)")
+            .bind(id.to_string())
+            .bind(&ptx.tx.txid)
+            .bind(ptx.block_height)
+            .bind(amount.to_string())
+            .execute(&mut *conn)
+            .await?;
+        Ok(Some(amount))
+    }
+
+    /// Stores the rune etched by `etching` under `id`; `false` if its name is reserved or taken.
+    async fn etch(&self, conn: &mut PgConnection, ptx: ProtocolTx<'_>, id: RuneId, etching: &Etching) -> Result<bool, sqlx::Error> {
+        let rune = match etching.rune {
+            Some(rune) if rune >= RESERVED_RUNE => return Ok(false),
+            Some(rune) => rune,
+            None => RESERVED_RUNE + ((u128::from(id.block) << 32) | u128::from(id.tx)),
+        };
+        let name = rune_name(rune);
+        let spaced = spaced_name(&name, etching.spacers.unwrap_or_default());
+        let terms = etching.terms.as_ref();
+        let inserted = sqlx::query(
+            "INSERT INTO runes (rune_id, name, spaced_name, block_height, etching_txid, divisibility, symbol, premine, \
+               terms, mint_amount, mint_cap, mint_height_start, mint_height_end, mint_offset_start, mint_offset_end, turbo) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8::NUMERIC, $9, $10::NUMERIC, $11::NUMERIC, $12, $13, $14, $15, $16) \
+             ON CONFLICT DO NOTHING",
+        )
+        .bind(id.to_string())
+        .bind(&name)
+        .bind(&spaced)
+        .bind(ptx.block_height)
+        .bind(&ptx.tx.txid)
+        .bind(i16::from(etching.divisibility.unwrap_or_default()))
+        .bind(etching.symbol.map(String::from))
+        .bind(etching.premine.unwrap_or_default().to_string())
+        .bind(terms.is_some())
+        .bind(terms.and_then(|terms| terms.amount).map(|amount| amount.to_string()))
+        .bind(terms.and_then(|terms| terms.cap).map(|cap| cap.to_string()))
+        .bind(terms.and_then(|terms| terms.height_start).and_then(|height| i64::try_from(height).ok()))
+        .bind(terms.and_then(|terms| terms.height_end).and_then(|height| i64::try_from(height).ok()))
+        .bind(terms.and_then(|terms| terms.offset_start).and_then(|offset| i64::try_from(offset).ok()))
+        .bind(terms.and_then(|terms| terms.offset_end).and_then(|offset| i64::try_from(offset).ok()))
+        .bind(etching.turbo)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        Ok(inserted > 0)
+    }
+}
+
+impl ProtocolIndexer for RunesIndexer {
+    fn name(&self) -> &'static str {
+        PROTOCOL_RUNES
+    }
+
+    fn index_transaction<'a>(
+        &'a self,
+        conn: &'a mut PgConnection,
+        tx: ProtocolTx<'a>,
+    ) -> BoxFuture<'a, Result<(), sqlx::Error>> {
+        self.index(conn, tx).boxed()
+    }
+
+    fn rollback<'a>(&'a self, conn: &'a mut PgConnection, from_height: i32) -> BoxFuture<'a, Result<(), sqlx::Error>> {
+        async move {
+            for statement in [
+                "DELETE FROM rune_outputs WHERE block_height >= $1",
+                "UPDATE rune_outputs SET spent_txid = NULL, spent_height = NULL WHERE spent_height >= $1",
+                "DELETE FROM rune_mints WHERE block_height >= $1",
+                "DELETE FROM runes WHERE block_height >= $1",
+            ] {
+                sqlx::query(statement).bind(from_height).execute(&mut *conn).await?;
+            }
+            Ok(())
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::indexer::{RpcScriptPubKey, RpcVout};
+
+    fn vout(n: i32, hex: &str) -> RpcVout {
+        RpcVout {
+            n,
+            value_sats: 0,
+            script_pub_key: RpcScriptPubKey {
+                script_type: "nonstandard".to_string(),
+                hex: hex.to_string(),
+                address: None,
+                addresses: None,
+            },
+        }
+    }
+
+    fn runestone_tx(payload: &[u8], outputs: usize) -> RpcTransaction {
+        let mut script = vec![0x6a, 0x5d, payload.len() as u8];
+        script.extend_from_slice(payload);
+        let mut vouts = vec![vout(0, &hex::encode(script))];
+        vouts.extend((1..outputs).map(|n| vout(n as i32, "0014751e76e8199196d454941c45d1b3a323f1433bd6")));
+        RpcTransaction {
+            txid: "tx".to_string(),
+            vin: Vec::new(),
+            vout: vouts,
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
+        }
+    }
+
+    #[test]
+    fn deciphers_edicts_and_etchings() {
+        // Edicts of 840000:3 delta-encoded: 10 to output 1, then all of 840000:5 to output 2.
+        let tx = runestone_tx(&[0, 0xc0, 0xa2, 0x33, 3, 10, 1, 0, 2, 0, 2], 3);
+        let Some(Artifact::Runestone { edicts, etching, .. }) = decipher(&tx) else {
+            panic!("runestone expected");
+        };
+        assert_eq!(etching, None);
+        assert_eq!(
+            edicts,
+            vec![
+                Edict { id: RuneId { block: 840_000, tx: 3 }, amount: 10, output: 1 },
+                Edict { id: RuneId { block: 840_000, tx: 5 }, amount: 0, output: 2 },
+            ]
+        );
+
+        // Etching flag, rune 26 ("AA"), premine 1000.
+        let tx = runestone_tx(&[2, 1, 4, 26, 6, 0xe8, 0x07], 2);
+        let Some(Artifact::Runestone { etching: Some(etching), .. }) = decipher(&tx) else {
+            panic!("etching expected");
+        };
+        assert_eq!((etching.rune.map(rune_name), etching.premine), (Some("AA".to_string()), Some(1000)));
+
+        // An unknown even tag and an edict to a missing output are cenotaphs.
+        assert!(matches!(decipher(&runestone_tx(&[30, 1], 2)), Some(Artifact::Cenotaph { .. })));
+        assert!(matches!(decipher(&runestone_tx(&[0, 1, 0, 5, 9], 2)), Some(Artifact::Cenotaph { .. })));
+        assert!(decipher(&runestone_tx(&[], 1)).is_some());
+        assert_eq!(decipher(&RpcTransaction { vout: vec![vout(0, "6a0100")], ..runestone_tx(&[], 1) }), None);
+    }
+
+    #[test]
+    fn allocates_edicts_and_leftovers() {
+        let id = RuneId { block: 840_000, tx: 1 };
+        let runestone = |edicts: Vec<Edict>, pointer: Option<u32>| Artifact::Runestone {
+            edicts,
+            etching: None,
+            mint: None,
+            pointer,
+        };
+        let outputs = [true, false, false];
+
+        // 30 to output 1, the remaining 70 to the first output that is not OP_RETURN.
+        let allocated = allocate(
+            BTreeMap::from([(id, 100)]),
+            Some(&runestone(vec![Edict { id, amount: 30, output: 1 }], None)),
+            None,
+            &outputs,
+        );
+        assert_eq!(allocated[1].get(&id), Some(&100));
+        assert!(allocated[2].is_empty());
+
+        // Split evenly over outputs 1 and 2, the odd one to the first of them.
+        let allocated = allocate(
+            BTreeMap::from([(id, 101)]),
+            Some(&runestone(vec![Edict { id, amount: 0, output: 3 }], None)),
+            None,
+            &outputs,
+        );
+        assert_eq!((allocated[1].get(&id), allocated[2].get(&id)), (Some(&51), Some(&50)));
+
+        // Leftovers go to the pointer; runes sent to OP_RETURN are burned.
+        let allocated = allocate(
+            BTreeMap::from([(id, 10)]),
+            Some(&runestone(vec![Edict { id, amount: 4, output: 0 }], Some(2))),
+            None,
+            &outputs,
+        );
+        assert_eq!((allocated[0].get(&id), allocated[2].get(&id)), (None, Some(&6)));
+
+        let cenotaph = Artifact::Cenotaph { etching: None, mint: None };
+        assert!(allocate(BTreeMap::from([(id, 10)]), Some(&cenotaph), None, &outputs).iter().all(BTreeMap::is_empty));
+    }
+
+    #[test]
+    fn names_runes() {
+        assert_eq!(rune_name(0), "A");
+        assert_eq!(rune_name(25), "Z");
+        assert_eq!(rune_name(26), "AA");
+        assert_eq!(spaced_name("UNCOMMONGOODS", 0b1000_0000), "UNCOMMON•GOODS");
+    }
+}
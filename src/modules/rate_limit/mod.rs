@@ -0,0 +1,265 @@
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::modules::config::{RateLimitConfig, RateLimitRule};
+
+/// Buckets kept per rule. Past it the least recently used bucket is dropped, so many
+/// clients cannot grow the map without bound.
+const MAX_BUCKETS: usize = 10_000;
+
+/// Per-IP and per-credential token buckets of the HTTP API.
+///
+/// Disabled (every request allowed) for a rule that is not configured; cloning
+/// shares the buckets.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    per_ip: Option<Arc<Buckets>>,
+    per_credential: Option<Arc<Buckets>>,
+}
+
+/// Token bucket pacing outgoing work, such as the RPC calls and blocks of a throttled
+/// job: [`Throttle::acquire`] waits for a token instead of rejecting. Cloning shares the bucket.
+#[derive(Debug, Clone)]
+pub struct Throttle {
+    rate: f64,
+    burst: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    rule: RateLimitRule,
+    buckets: Mutex<LruBuckets>,
+}
+
+/// Buckets with the order they were last used in, so evicting the least recently used
+/// one is a lookup rather than a scan of every bucket.
+#[derive(Debug, Default)]
+struct LruBuckets {
+    entries: HashMap<String, (Bucket, u64)>,
+    by_use: BTreeMap<u64, String>,
+    next_use: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            per_ip: config.per_ip.map(|rule| Arc::new(Buckets::new(rule))),
+            per_credential: config.per_credential.map(|rule| Arc::new(Buckets::new(rule))),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.per_ip.is_some() || self.per_credential.is_some()
+    }
+
+    /// Takes a token from the bucket of `client_ip`, or returns how long the client has
+    /// to wait before the request would be allowed. Checked before authentication, so
+    /// failed logins count too.
+    pub fn check_ip(&self, client_ip: IpAddr) -> Result<(), Duration> {
+        self.check_ip_at(client_ip, Instant::now())
+    }
+
+    /// Takes a token from the bucket of an authenticated `credential`.
+    pub fn check_credential(&self, credential: &str) -> Result<(), Duration> {
+        self.check_credential_at(credential, Instant::now())
+    }
+
+    pub fn limits_ip(&self) -> bool {
+        self.per_ip.is_some()
+    }
+
+    pub fn limits_credential(&self) -> bool {
+        self.per_credential.is_some()
+    }
+
+    fn check_ip_at(&self, client_ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        match &self.per_ip {
+            Some(buckets) => buckets.take(&client_ip.to_string(), now),
+            None => Ok(()),
+        }
+    }
+
+    fn check_credential_at(&self, credential: &str, now: Instant) -> Result<(), Duration> {
+        match &self.per_credential {
+            Some(buckets) => buckets.take(credential, now),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Buckets {
+    fn new(rule: RateLimitRule) -> Self {
+        Self {
+            rule,
+            buckets: Mutex::new(LruBuckets::default()),
+        }
+    }
+
+    fn take(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let rate = f64::from(self.rule.requests_per_sec);
+        let burst = f64::from(self.rule.burst);
+        let mut guard = self.buckets.lock().unwrap_or_else(|err| err.into_inner());
+        let state = &mut *guard;
+        let used = state.next_use;
+        state.next_use += 1;
+
+        if let Some((bucket, last_use)) = state.entries.get_mut(key) {
+            state.by_use.remove(last_use);
+            *last_use = used;
+            state.by_use.insert(used, key.to_string());
+            return bucket.take(rate, burst, now);
+        }
+
+        if state.entries.len() >= MAX_BUCKETS {
+            if let Some((_, oldest)) = state.by_use.pop_first() {
+                state.entries.remove(&oldest);
+            }
+        }
+        let mut bucket = Bucket {
+            tokens: burst,
+            updated_at: now,
+        };
+        let result = bucket.take(rate, burst, now);
+        state.entries.insert(key.to_string(), (bucket, used));
+        state.by_use.insert(used, key.to_string());
+        result
+    }
+}
+
+impl Throttle {
+    /// `rate` tokens per second; up to `burst` are taken without waiting after idling.
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate,
+            burst,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                updated_at: Instant::now(),
+            })),
+        }
+    }
+
+    /// Waits until a token is available and takes it.
+    pub async fn acquire(&self) {
+        while let Err(wait) = self.take_at(Instant::now()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn take_at(&self, now: Instant) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|err| err.into_inner());
+        bucket.take(self.rate, self.burst, now)
+    }
+}
+
+impl Bucket {
+    /// Takes a token, or returns how long until one is available.
+    fn take(&mut self, rate: f64, burst: f64, now: Instant) -> Result<(), Duration> {
+        self.tokens = self.refilled(rate, burst, now);
+        self.updated_at = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+
+    fn refilled(&self, rate: f64, burst: f64, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        (self.tokens + elapsed * rate).min(burst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, Instant};
+
+    use super::{RateLimiter, Throttle, MAX_BUCKETS};
+    use crate::modules::config::{RateLimitConfig, RateLimitRule};
+
+    #[test]
+    fn limits_per_ip_and_per_credential_and_refills() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            per_ip: Some(RateLimitRule {
+                requests_per_sec: 2,
+                burst: 2,
+            }),
+            per_credential: Some(RateLimitRule {
+                requests_per_sec: 1,
+                burst: 3,
+            }),
+        });
+        let dashboard = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let now = Instant::now();
+
+        assert!(limiter.check_ip_at(dashboard, now).is_ok());
+        assert!(limiter.check_ip_at(dashboard, now).is_ok());
+        let retry_after = limiter.check_ip_at(dashboard, now).expect_err("ip bucket empty");
+        assert_eq!(retry_after, Duration::from_millis(500));
+        assert!(limiter.check_ip_at(other, now).is_ok());
+        assert!(limiter.check_credential_at("token:etl", now).is_ok());
+        assert!(limiter.check_ip_at(dashboard, now + Duration::from_millis(500)).is_ok());
+
+        let later = now + Duration::from_secs(10);
+        assert!(limiter.check_credential_at("token:etl", later).is_ok());
+        assert!(limiter.check_credential_at("token:etl", later).is_ok());
+        assert!(limiter.check_credential_at("token:etl", later).is_ok());
+        assert!(limiter.check_credential_at("token:etl", later).is_err());
+        assert!(!RateLimiter::default().is_enabled());
+        assert!(RateLimiter::default().check_ip(dashboard).is_ok());
+        assert!(RateLimiter::default().check_credential("token:etl").is_ok());
+    }
+
+    #[test]
+    fn bounds_buckets_of_busy_clients() {
+        let limiter = RateLimiter::new(&RateLimitConfig {
+            per_ip: Some(RateLimitRule {
+                requests_per_sec: 1,
+                burst: 5,
+            }),
+            per_credential: None,
+        });
+        let now = Instant::now();
+        let dashboard = IpAddr::V4(Ipv4Addr::from(u32::MAX));
+        for n in 0..MAX_BUCKETS as u32 + 100 {
+            let ip = IpAddr::V4(Ipv4Addr::from(n));
+            let at = now + Duration::from_micros(u64::from(n));
+            assert!(limiter.check_ip_at(ip, at).is_ok());
+            if n % 1_000 == 0 {
+                let _ = limiter.check_ip_at(dashboard, at);
+            }
+        }
+
+        let buckets = limiter.per_ip.as_ref().expect("per-ip rule").buckets.lock().unwrap();
+        assert_eq!(buckets.entries.len(), MAX_BUCKETS);
+        assert_eq!(buckets.by_use.len(), MAX_BUCKETS);
+        assert!(!buckets.entries.contains_key("0.0.0.0"));
+        assert!(buckets.entries.contains_key(&dashboard.to_string()), "recently used bucket is kept");
+        assert!(buckets.entries.contains_key(&Ipv4Addr::from(MAX_BUCKETS as u32 + 99).to_string()));
+    }
+
+    #[test]
+    fn throttle_paces_after_the_burst() {
+        let throttle = Throttle::new(0.5, 2);
+        let now = Instant::now() + Duration::from_secs(1);
+
+        assert!(throttle.take_at(now).is_ok());
+        assert!(throttle.clone().take_at(now).is_ok());
+        assert_eq!(throttle.take_at(now), Err(Duration::from_secs(2)));
+        assert!(throttle.take_at(now + Duration::from_secs(2)).is_ok());
+        assert!(throttle.take_at(now + Duration::from_secs(3)).is_err());
+    }
+}
@@ -0,0 +1,588 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use chrono::Utc;
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::modules::auth::AuthChain;
+use crate::modules::config::{AppConfig, ConfigError, IndexerConfig, JobConfig, TlsConfig};
+use crate::modules::events::{EventsRunner, EventsRunnerConfig};
+use crate::modules::headers::{HeaderSyncRunner, HeaderSyncRunnerConfig};
+use crate::modules::jobs::{JobsError, JobsRunner, JobsRunnerConfig, JobsService};
+use crate::modules::mempool::{MempoolRunner, MempoolRunnerConfig};
+use crate::modules::nodes::{NodesError, NodesRunner, NodesRunnerConfig, NodesService};
+use crate::modules::retention::{RetentionRunnerConfig, RetentionService};
+use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::stats::{StatsRunner, StatsRunnerConfig};
+use crate::modules::templates::{BlockTemplateRunner, BlockTemplateRunnerConfig};
+use crate::modules::tls::{self, CertificateStatus, ServerCertResolver, TlsError};
+
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error(transparent)]
+    Jobs(#[from] JobsError),
+    #[error(transparent)]
+    Tls(#[from] TlsError),
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    #[error(transparent)]
+    Nodes(#[from] NodesError),
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ReloadReport {
+    /// Jobs added to or changed in the YAML and synced to the database.
+    pub jobs_synced: Vec<String>,
+    /// Jobs switched to `enabled: false` that were paused.
+    pub jobs_paused: Vec<String>,
+    /// Jobs no longer present in the YAML; they are left as is and can be deleted via the API.
+    pub jobs_not_in_config: Vec<String>,
+    /// Applied runtime settings, e.g. `indexer.poll.tip_interval_ms: 5000 -> 1000`.
+    pub settings_changed: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct CredentialsReport {
+    /// Credential settings whose value changed, e.g. `rpc.auth` or `instances.testnet.rpc.mtls`.
+    pub changed: Vec<String>,
+    /// RPC clients rebuilt with the re-read password and mTLS files, one per network.
+    pub rpc_clients_rebuilt: usize,
+    /// The API serves the re-read `server.tls` pair to new connections; `false` without
+    /// `server.tls.enabled`.
+    pub server_certificate_reloaded: bool,
+    /// Certificates of `server.tls` and `rpc.mtls` checked before anything was applied.
+    pub certificates: Vec<CertificateStatus>,
+}
+
+/// Settings of a background runner that [`ConfigReloader`] can replace while it runs.
+/// Runners take a copy with [`Self::get`] each time they start a pass, so a reload never
+/// changes the settings of a pass in progress and applies from the next one.
+#[derive(Debug)]
+pub struct ReloadableConfig<T> {
+    inner: Arc<RwLock<T>>,
+}
+
+impl<T> Clone for ReloadableConfig<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T: Clone> ReloadableConfig<T> {
+    pub fn new(config: T) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(config)),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.inner.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    pub fn set(&self, config: T) {
+        *self.inner.write().unwrap_or_else(|err| err.into_inner()) = config;
+    }
+}
+
+/// Re-reads the YAML config and applies the parts that do not need a restart.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    path: PathBuf,
+    current: Arc<Mutex<AppConfig>>,
+    jobs: JobsService,
+    jobs_runner: Option<JobsRunner>,
+    mempool_runner: Option<MempoolRunner>,
+    nodes_runner: Option<NodesRunner>,
+    block_template_runner: Option<BlockTemplateRunner>,
+    events_runner: Option<EventsRunner>,
+    header_sync_runner: Option<HeaderSyncRunner>,
+    stats_runner: Option<StatsRunner>,
+    retention: Option<RetentionService>,
+    auth: Option<AuthChain>,
+    admin_auth: Option<AuthChain>,
+    /// Clients of the primary network and of `instances`, in config order.
+    rpc_clients: Vec<RpcClient>,
+    nodes: Option<NodesService>,
+    server_certificates: Option<ServerCertResolver>,
+}
+
+impl fmt::Debug for ConfigReloader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigReloader").field("path", &self.path).finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Default)]
+struct JobsDiff {
+    synced: Vec<JobConfig>,
+    disabled: Vec<String>,
+    not_in_config: Vec<String>,
+}
+
+impl ConfigReloader {
+    pub fn new(path: PathBuf, config: AppConfig, jobs: JobsService) -> Self {
+        Self {
+            path,
+            current: Arc::new(Mutex::new(config)),
+            jobs,
+            jobs_runner: None,
+            mempool_runner: None,
+            nodes_runner: None,
+            block_template_runner: None,
+            events_runner: None,
+            header_sync_runner: None,
+            stats_runner: None,
+            retention: None,
+            auth: None,
+            admin_auth: None,
+            rpc_clients: Vec::new(),
+            nodes: None,
+            server_certificates: None,
+        }
+    }
+
+    pub fn with_jobs_runner(mut self, runner: JobsRunner) -> Self {
+        self.jobs_runner = Some(runner);
+        self
+    }
+
+    pub fn with_mempool_runner(mut self, runner: MempoolRunner) -> Self {
+        self.mempool_runner = Some(runner);
+        self
+    }
+
+    pub fn with_nodes_runner(mut self, runner: NodesRunner) -> Self {
+        self.nodes_runner = Some(runner);
+        self
+    }
+
+    pub fn with_block_template_runner(mut self, runner: Option<BlockTemplateRunner>) -> Self {
+        self.block_template_runner = runner;
+        self
+    }
+
+    pub fn with_events_runner(mut self, runner: Option<EventsRunner>) -> Self {
+        self.events_runner = runner;
+        self
+    }
+
+    pub fn with_header_sync_runner(mut self, runner: Option<HeaderSyncRunner>) -> Self {
+        self.header_sync_runner = runner;
+        self
+    }
+
+    pub fn with_stats_runner(mut self, runner: Option<StatsRunner>) -> Self {
+        self.stats_runner = runner;
+        self
+    }
+
+    pub fn with_retention(mut self, retention: Option<RetentionService>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Chain of the API router that [`Self::reload_credentials`] rebuilds.
+    pub fn with_auth(mut self, auth: AuthChain) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Chain of the `server.admin` listener, rebuilt from `server.admin.auth`.
+    pub fn with_admin_auth(mut self, auth: Option<AuthChain>) -> Self {
+        self.admin_auth = auth;
+        self
+    }
+
+    /// RPC clients of the primary network followed by those of `instances`, in config order.
+    pub fn with_rpc_clients(mut self, rpc_clients: Vec<RpcClient>) -> Self {
+        self.rpc_clients = rpc_clients;
+        self
+    }
+
+    /// Registry whose primary node entry gets the rotated RPC password.
+    pub fn with_nodes_service(mut self, nodes: NodesService) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Certificate of the HTTPS listeners, swapped for the re-read `server.tls` pair.
+    pub fn with_server_certificates(mut self, certificates: Option<ServerCertResolver>) -> Self {
+        self.server_certificates = certificates;
+        self
+    }
+
+    /// Loads the config from disk and applies job changes, poll intervals, batching, event levels,
+    /// the header batch size and retention settings.
+    ///
+    /// Nothing is applied when the new config changes settings that need a restart.
+    pub async fn reload(&self) -> Result<ReloadReport, ReloadError> {
+        let mut current = self.current.lock().await;
+        let next = AppConfig::load_from_path(&self.path)?;
+        current.ensure_reloadable(&next)?;
+
+        let diff = diff_jobs(&current.jobs, &next.jobs);
+        self.jobs.sync_from_config(&diff.synced).await?;
+        self.jobs.activate_enabled_jobs(&diff.synced).await?;
+
+        let mut jobs_paused = Vec::new();
+        for job_id in &diff.disabled {
+            if self.jobs.get(job_id).await?.status == "running" {
+                self.jobs.pause(job_id, Some("disabled in config")).await?;
+                jobs_paused.push(job_id.clone());
+            }
+        }
+
+        let settings_changed = diff_settings(&current.indexer, &next.indexer);
+        self.apply_settings(&next.indexer);
+
+        let report = ReloadReport {
+            jobs_synced: diff.synced.into_iter().map(|job| job.job_id).collect(),
+            jobs_paused,
+            jobs_not_in_config: diff.not_in_config,
+            settings_changed,
+        };
+        *current = next;
+
+        info!(
+            component = "config",
+            path = %self.path.display(),
+            jobs_synced = report.jobs_synced.len(),
+            jobs_paused = report.jobs_paused.len(),
+            settings_changed = ?report.settings_changed,
+            message = "configuration reloaded"
+        );
+
+        Ok(report)
+    }
+
+    /// Re-reads `server.auth`, `server.admin.auth`, `server.tls`, `rpc.auth` and `rpc.mtls` of
+    /// every network, with `password_file` secrets and certificate files, and switches the API
+    /// auth chains, the HTTPS certificate and the RPC clients to them. Other changes of the config are left to [`Self::reload`].
+    ///
+    /// Nothing is applied when the config is invalid, a certificate fails the TLS preflight
+    /// or an RPC client cannot be built.
+    pub async fn reload_credentials(&self) -> Result<CredentialsReport, ReloadError> {
+        let mut current = self.current.lock().await;
+        let next = AppConfig::load_from_path(&self.path)?;
+
+        let mut rotated = current.clone();
+        rotated.server.auth = next.server.auth;
+        if let (Some(admin), Some(source)) = (&mut rotated.server.admin, &next.server.admin) {
+            admin.auth = source.auth.clone();
+        }
+        // Switching HTTPS on or off rebinds the listener, so it needs a restart.
+        rotated.server.tls = TlsConfig {
+            enabled: current.server.tls.enabled,
+            ..next.server.tls
+        };
+        rotated.rpc.auth = next.rpc.auth;
+        rotated.rpc.mtls = next.rpc.mtls;
+        for instance in &mut rotated.instances {
+            if let Some(source) = next.instances.iter().find(|source| source.name == instance.name) {
+                instance.rpc.auth = source.rpc.auth.clone();
+                instance.rpc.mtls = source.rpc.mtls.clone();
+            }
+        }
+
+        let certificates = tls::preflight(&rotated, Utc::now())?;
+        tls::warn_expiring(&certificates);
+        let rpcs = std::iter::once(&rotated.rpc).chain(rotated.instances.iter().map(|instance| &instance.rpc));
+        let sources = self
+            .rpc_clients
+            .iter()
+            .zip(rpcs)
+            .map(|(client, config)| Ok((client, RpcClient::from_config(config)?)))
+            .collect::<Result<Vec<_>, RpcError>>()?;
+        let server_key = self
+            .server_certificates
+            .as_ref()
+            .map(|certificates| {
+                tls::load_key_pair(&rotated.server.tls.cert_path, &rotated.server.tls.key_path)
+                    .map(|key| (certificates, key))
+            })
+            .transpose()?;
+        if let Some(nodes) = &self.nodes {
+            nodes.ensure_primary_node(&rotated.rpc).await?;
+        }
+
+        for (client, source) in &sources {
+            client.adopt_credentials(source);
+        }
+        let server_certificate_reloaded = server_key.is_some();
+        if let Some((certificates, key)) = server_key {
+            certificates.replace(key);
+        }
+        if let Some(auth) = &self.auth {
+            auth.reload(&rotated.server.auth);
+        }
+        if let (Some(auth), Some(admin)) = (&self.admin_auth, &rotated.server.admin) {
+            auth.reload(&admin.auth);
+        }
+
+        let report = CredentialsReport {
+            changed: diff_credentials(&current, &rotated),
+            rpc_clients_rebuilt: sources.len(),
+            server_certificate_reloaded,
+            certificates,
+        };
+        *current = rotated;
+
+        info!(
+            component = "config",
+            path = %self.path.display(),
+            changed = ?report.changed,
+            rpc_clients_rebuilt = report.rpc_clients_rebuilt,
+            server_certificate_reloaded = report.server_certificate_reloaded,
+            message = "credentials reloaded"
+        );
+
+        Ok(report)
+    }
+
+    /// Modification times of the credential files of the applied config; a missing file
+    /// has `None`.
+    pub async fn credential_files_state(&self) -> Vec<(PathBuf, Option<SystemTime>)> {
+        let files = self.current.lock().await.credential_files();
+        files
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok();
+                (path, modified)
+            })
+            .collect()
+    }
+
+    fn apply_settings(&self, indexer: &IndexerConfig) {
+        if let Some(runner) = &self.jobs_runner {
+            runner.update_config(JobsRunnerConfig::from_config(indexer));
+        }
+        if let Some(runner) = &self.mempool_runner {
+            runner.update_config(MempoolRunnerConfig::from_config(indexer));
+        }
+        if let Some(runner) = &self.nodes_runner {
+            runner.update_config(NodesRunnerConfig::from_config(indexer));
+        }
+        if let (Some(runner), Some(config)) = (
+            &self.block_template_runner,
+            BlockTemplateRunnerConfig::from_config(indexer),
+        ) {
+            runner.update_config(config);
+        }
+        if let (Some(runner), Some(config)) = (&self.events_runner, EventsRunnerConfig::from_config(indexer)) {
+            runner.update_config(config);
+        }
+        if let (Some(runner), Some(config)) = (
+            &self.header_sync_runner,
+            HeaderSyncRunnerConfig::from_config(indexer),
+        ) {
+            runner.update_config(config);
+        }
+        if let (Some(runner), Some(config)) = (&self.stats_runner, StatsRunnerConfig::from_config(indexer)) {
+            runner.update_config(config);
+        }
+        if let (Some(retention), Some(config)) = (&self.retention, RetentionRunnerConfig::from_config(indexer)) {
+            retention.update_config(config);
+        }
+    }
+}
+
+/// `next` is `current` with rotated credentials, so their instances line up.
+fn diff_credentials(current: &AppConfig, next: &AppConfig) -> Vec<String> {
+    let mut changed = Vec::new();
+    if current.server.auth != next.server.auth {
+        changed.push("server.auth".to_string());
+    }
+    let admin_auth = |config: &AppConfig| config.server.admin.as_ref().map(|admin| admin.auth.clone());
+    if admin_auth(current) != admin_auth(next) {
+        changed.push("server.admin.auth".to_string());
+    }
+    if current.server.tls != next.server.tls {
+        changed.push("server.tls".to_string());
+    }
+
+    let prefixes = std::iter::once(String::new())
+        .chain(current.instances.iter().map(|instance| format!("instances.{}.", instance.name)));
+    let current_rpcs = std::iter::once(&current.rpc).chain(current.instances.iter().map(|instance| &instance.rpc));
+    let next_rpcs = std::iter::once(&next.rpc).chain(next.instances.iter().map(|instance| &instance.rpc));
+    for ((prefix, rpc), next_rpc) in prefixes.zip(current_rpcs).zip(next_rpcs) {
+        if rpc.auth != next_rpc.auth {
+            changed.push(format!("{prefix}rpc.auth"));
+        }
+        if rpc.mtls != next_rpc.mtls {
+            changed.push(format!("{prefix}rpc.mtls"));
+        }
+    }
+
+    changed
+}
+
+fn diff_jobs(current: &[JobConfig], next: &[JobConfig]) -> JobsDiff {
+    let current_by_id: HashMap<&str, &JobConfig> =
+        current.iter().map(|job| (job.job_id.as_str(), job)).collect();
+    let mut diff = JobsDiff::default();
+
+    for job in next {
+        match current_by_id.get(job.job_id.as_str()) {
+            Some(previous) if *previous == job => {}
+            Some(previous) => {
+                if previous.enabled && !job.enabled {
+                    diff.disabled.push(job.job_id.clone());
+                }
+                diff.synced.push(job.clone());
+            }
+            None => diff.synced.push(job.clone()),
+        }
+    }
+
+    diff.not_in_config = current
+        .iter()
+        .filter(|job| !next.iter().any(|candidate| candidate.job_id == job.job_id))
+        .map(|job| job.job_id.clone())
+        .collect();
+
+    diff
+}
+
+fn diff_settings(current: &IndexerConfig, next: &IndexerConfig) -> Vec<String> {
+    let mut changed = Vec::new();
+    let mut compare = |name: &str, before: String, after: String| {
+        if before != after {
+            changed.push(format!("{name}: {before} -> {after}"));
+        }
+    };
+
+    compare(
+        "indexer.poll.tip_interval_ms",
+        current.poll.tip_interval_ms.to_string(),
+        next.poll.tip_interval_ms.to_string(),
+    );
+    compare(
+        "indexer.poll.mempool_interval_ms",
+        current.poll.mempool_interval_ms.to_string(),
+        next.poll.mempool_interval_ms.to_string(),
+    );
+    compare(
+        "indexer.poll.block_template_interval_ms",
+        optional_interval(current.poll.block_template_interval_ms),
+        optional_interval(next.poll.block_template_interval_ms),
+    );
+    compare(
+        "indexer.poll.gap_scan_interval_ms",
+        optional_interval(current.poll.gap_scan_interval_ms),
+        optional_interval(next.poll.gap_scan_interval_ms),
+    );
+    compare(
+        "indexer.batching.blocks_per_batch",
+        current.batching.blocks_per_batch.to_string(),
+        next.batching.blocks_per_batch.to_string(),
+    );
+    compare(
+        "indexer.batching.txs_per_batch",
+        current.batching.txs_per_batch.to_string(),
+        next.batching.txs_per_batch.to_string(),
+    );
+    if let (Some(before), Some(after)) = (&current.events, &next.events) {
+        compare("indexer.events.levels", before.levels.join(","), after.levels.join(","));
+        compare(
+            "indexer.events.finality_depth",
+            before.finality_depth.to_string(),
+            after.finality_depth.to_string(),
+        );
+    }
+    if let (Some(before), Some(after)) = (&current.headers, &next.headers) {
+        compare(
+            "indexer.headers.batch_size",
+            before.batch_size.to_string(),
+            after.batch_size.to_string(),
+        );
+    }
+    if let (Some(before), Some(after)) = (&current.stats, &next.stats) {
+        compare(
+            "indexer.stats.interval_ms",
+            before.interval_ms.to_string(),
+            after.interval_ms.to_string(),
+        );
+    }
+    if let (Some(before), Some(after)) = (&current.retention, &next.retention) {
+        compare(
+            "indexer.retention.keep_blocks",
+            before.keep_blocks.to_string(),
+            after.keep_blocks.to_string(),
+        );
+        compare(
+            "indexer.retention.interval_ms",
+            before.interval_ms.to_string(),
+            after.interval_ms.to_string(),
+        );
+        compare(
+            "indexer.retention.batch_blocks",
+            before.batch_blocks.to_string(),
+            after.batch_blocks.to_string(),
+        );
+    }
+
+    changed
+}
+
+fn optional_interval(value: Option<u64>) -> String {
+    value.map_or_else(|| "disabled".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_jobs;
+    use crate::modules::config::JobConfig;
+
+    fn job(job_id: &str, enabled: bool, addresses: &[&str]) -> JobConfig {
+        JobConfig {
+            job_id: job_id.to_string(),
+            mode: "address_list".to_string(),
+            enabled,
+            addresses: addresses.iter().map(|address| address.to_string()).collect(),
+            from_height: None,
+            to_height: None,
+            descriptor: None,
+            gap_limit: None,
+            retry: None,
+            export: None,
+            priority: None,
+            max_rpc_per_second: None,
+            max_blocks_per_minute: None,
+            protocols: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn diffs_job_configs() {
+        let current = vec![
+            job("same", true, &["a"]),
+            job("changed", true, &["a"]),
+            job("disabled", true, &["a"]),
+            job("removed", true, &["a"]),
+        ];
+        let next = vec![
+            job("same", true, &["a"]),
+            job("changed", true, &["a", "b"]),
+            job("disabled", false, &["a"]),
+            job("added", false, &["c"]),
+        ];
+
+        let diff = diff_jobs(&current, &next);
+
+        let synced: Vec<&str> = diff.synced.iter().map(|job| job.job_id.as_str()).collect();
+        assert_eq!(synced, vec!["changed", "disabled", "added"]);
+        assert_eq!(diff.disabled, vec!["disabled".to_string()]);
+        assert_eq!(diff.not_in_config, vec!["removed".to_string()]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::config::ReplicationConfig;
+use crate::modules::metrics::MetricsService;
+
+#[derive(Debug, Error)]
+pub enum ReplicationError {
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReplicationSlotStatus {
+    pub slot_name: String,
+    pub plugin: Option<String>,
+    pub active: bool,
+    /// `reserved`, `extended`, `unreserved` or `lost` as reported by `pg_replication_slots`.
+    pub wal_status: Option<String>,
+    /// WAL the server keeps for this slot (`restart_lsn` behind the current LSN).
+    pub retained_wal_bytes: Option<i64>,
+    /// Changes the consumer has not confirmed yet (`confirmed_flush_lsn` behind the current LSN).
+    pub flush_lag_bytes: Option<i64>,
+    pub stalled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReplicationStatus {
+    pub publication: String,
+    pub publication_exists: bool,
+    pub tables: Vec<String>,
+    pub wal_level: String,
+    pub max_slot_lag_bytes: u64,
+    pub slots: Vec<ReplicationSlotStatus>,
+}
+
+/// Manages the publication downstream CDC consumers subscribe to and reports
+/// the health of logical replication slots in the indexer database.
+#[derive(Debug, Clone)]
+pub struct ReplicationService {
+    pool: PgPool,
+    config: ReplicationConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplicationRunnerConfig {
+    pub check_interval: Duration,
+}
+
+#[derive(Clone)]
+pub struct ReplicationRunner {
+    replication: ReplicationService,
+    metrics: MetricsService,
+    config: ReplicationRunnerConfig,
+}
+
+impl ReplicationService {
+    pub fn new(pool: PgPool, config: ReplicationConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Creates the publication or resets its table list to the configured one.
+    pub async fn ensure_publication(&self) -> Result<(), ReplicationError> {
+        let exists = self.publication_exists().await?;
+        // Identifiers cannot be bound; publication and table names are validated in config.
+        let tables = self.config.tables.join(", ");
+        let statement = if exists {
+            format!("ALTER PUBLICATION {} SET TABLE {tables}", self.config.publication)
+        } else {
+            format!("CREATE PUBLICATION {} FOR TABLE {tables}", self.config.publication)
+        };
+        sqlx::query(&statement).execute(&self.pool).await?;
+        // Partitioned chain tables are published under their own names, not their partitions'.
+        sqlx::query(&format!(
+            "ALTER PUBLICATION {} SET (publish_via_partition_root = true)",
+            self.config.publication
+        ))
+        .execute(&self.pool)
+        .await?;
+
+        let wal_level = self.wal_level().await?;
+        if wal_level != "logical" {
+            warn!(
+                component = "replication",
+                publication = %self.config.publication,
+                wal_level = %wal_level,
+                message = "wal_level is not 'logical', consumers cannot create replication slots"
+            );
+        }
+
+        info!(
+            component = "replication",
+            publication = %self.config.publication,
+            tables = %tables,
+            created = !exists,
+            message = "replication publication configured"
+        );
+        Ok(())
+    }
+
+    pub async fn status(&self) -> Result<ReplicationStatus, ReplicationError> {
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT tablename::TEXT
+             FROM pg_publication_tables
+             WHERE pubname = $1
+             ORDER BY tablename",
+        )
+        .bind(&self.config.publication)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let rows: Vec<ReplicationSlotRow> = sqlx::query_as(
+            "SELECT s.slot_name::TEXT AS slot_name,
+                    s.plugin::TEXT AS plugin,
+                    s.active,
+                    s.wal_status,
+                    pg_wal_lsn_diff(lsn.current_lsn, s.restart_lsn)::BIGINT AS retained_wal_bytes,
+                    pg_wal_lsn_diff(lsn.current_lsn, s.confirmed_flush_lsn)::BIGINT AS flush_lag_bytes
+             FROM pg_replication_slots s,
+                  LATERAL (
+                    SELECT CASE WHEN pg_is_in_recovery() THEN pg_last_wal_replay_lsn() ELSE pg_current_wal_lsn() END
+                      AS current_lsn
+                  ) lsn
+             WHERE s.slot_type = 'logical'
+               AND s.database = current_database()
+             ORDER BY s.slot_name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ReplicationStatus {
+            publication: self.config.publication.clone(),
+            publication_exists: self.publication_exists().await?,
+            tables,
+            wal_level: self.wal_level().await?,
+            max_slot_lag_bytes: self.config.max_slot_lag_bytes,
+            slots: rows
+                .into_iter()
+                .map(|row| ReplicationSlotStatus {
+                    stalled: slot_stalled(
+                        row.retained_wal_bytes,
+                        row.wal_status.as_deref(),
+                        self.config.max_slot_lag_bytes,
+                    ),
+                    slot_name: row.slot_name,
+                    plugin: row.plugin,
+                    active: row.active,
+                    wal_status: row.wal_status,
+                    retained_wal_bytes: row.retained_wal_bytes,
+                    flush_lag_bytes: row.flush_lag_bytes,
+                })
+                .collect(),
+        })
+    }
+
+    async fn publication_exists(&self) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM pg_publication WHERE pubname = $1)")
+            .bind(&self.config.publication)
+            .fetch_one(&self.pool)
+            .await
+    }
+
+    async fn wal_level(&self) -> Result<String, sqlx::Error> {
+        sqlx::query_scalar("SELECT current_setting('wal_level')")
+            .fetch_one(&self.pool)
+            .await
+    }
+}
+
+impl ReplicationRunnerConfig {
+    pub fn from_config(config: &ReplicationConfig) -> Self {
+        Self {
+            check_interval: Duration::from_millis(config.check_interval_ms),
+        }
+    }
+}
+
+impl ReplicationRunner {
+    pub fn new(replication: ReplicationService, metrics: MetricsService, config: ReplicationRunnerConfig) -> Self {
+        Self {
+            replication,
+            metrics,
+            config,
+        }
+    }
+
+    pub fn start(&self) {
+        let runner = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = runner.check_once().await {
+                    warn!(component = "replication", error = %err, message = "replication slot check failed");
+                }
+
+                tokio::time::sleep(runner.config.check_interval).await;
+            }
+        });
+    }
+
+    /// Warns about slots that hold back more WAL than allowed, returning their names.
+    pub async fn check_once(&self) -> Result<Vec<String>, ReplicationError> {
+        let status = self.replication.status().await?;
+        let mut stalled = Vec::new();
+
+        for slot in status.slots.into_iter().filter(|slot| slot.stalled) {
+            self.metrics.increment_error("replication_slot_lag");
+            warn!(
+                component = "replication",
+                slot_name = %slot.slot_name,
+                active = slot.active,
+                wal_status = ?slot.wal_status,
+                retained_wal_bytes = ?slot.retained_wal_bytes,
+                max_slot_lag_bytes = status.max_slot_lag_bytes,
+                message = "replication slot is stalling WAL"
+            );
+            stalled.push(slot.slot_name);
+        }
+
+        Ok(stalled)
+    }
+}
+
+fn slot_stalled(retained_wal_bytes: Option<i64>, wal_status: Option<&str>, max_slot_lag_bytes: u64) -> bool {
+    if matches!(wal_status, Some("unreserved" | "lost")) {
+        return true;
+    }
+
+    retained_wal_bytes.is_some_and(|bytes| bytes > 0 && bytes as u64 > max_slot_lag_bytes)
+}
+
+#[derive(Debug, FromRow)]
+struct ReplicationSlotRow {
+    slot_name: String,
+    plugin: Option<String>,
+    active: bool,
+    wal_status: Option<String>,
+    retained_wal_bytes: Option<i64>,
+    flush_lag_bytes: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slot_stalled;
+
+    #[test]
+    fn detects_stalled_slots() {
+        assert!(!slot_stalled(Some(1024), Some("reserved"), 4096));
+        assert!(!slot_stalled(None, None, 4096));
+        assert!(slot_stalled(Some(8192), Some("reserved"), 4096));
+        assert!(slot_stalled(Some(0), Some("lost"), 4096));
+        assert!(slot_stalled(None, Some("unreserved"), 4096));
+    }
+}
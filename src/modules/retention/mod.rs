@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::config::IndexerConfig;
+use crate::modules::reload::ReloadableConfig;
+use crate::modules::storage::SchemaFeatures;
+
+/// Serializes pruning passes of the runner and `POST /v1/admin/prune` across instances.
+const PRUNE_LOCK_KEY: i64 = -4;
+
+#[derive(Debug, Error)]
+pub enum RetentionError {
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Result of one [`RetentionService::prune`] call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PruneReport {
+    /// Height up to which transaction detail is pruned; `null` before the first pass.
+    pub pruned_height: Option<i32>,
+    /// Heights pruned by this call.
+    pub heights: u64,
+    /// Transactions whose `decoded` lost the `vin` and `vout` arrays.
+    pub transactions: u64,
+    /// Outputs whose `script_hex` was cleared.
+    pub outputs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetentionRunnerConfig {
+    pub keep_blocks: u32,
+    pub batch_blocks: u32,
+    pub interval: Duration,
+}
+
+/// Drops the bulky part of transaction detail below the last `keep_blocks` canonical
+/// blocks: the `vin`/`vout` arrays of `transactions.decoded` and `tx_outputs.script_hex`.
+/// Rows themselves stay, so balances, UTXOs, fees and stats are unaffected.
+#[derive(Debug, Clone)]
+pub struct RetentionService {
+    pool: PgPool,
+    schema: SchemaFeatures,
+    config: ReloadableConfig<RetentionRunnerConfig>,
+}
+
+#[derive(Clone)]
+pub struct RetentionRunner {
+    retention: RetentionService,
+}
+
+impl RetentionRunnerConfig {
+    /// Returns `None` when retention is disabled.
+    pub fn from_config(config: &IndexerConfig) -> Option<Self> {
+        config.retention.as_ref().map(|retention| Self {
+            keep_blocks: retention.keep_blocks,
+            batch_blocks: retention.batch_blocks,
+            interval: Duration::from_millis(retention.interval_ms),
+        })
+    }
+}
+
+impl RetentionService {
+    pub fn new(pool: PgPool, config: RetentionRunnerConfig) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+            config: ReloadableConfig::new(config),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Replaces the retention settings; the next pruning pass uses them.
+    pub fn update_config(&self, config: RetentionRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn current_config(&self) -> RetentionRunnerConfig {
+        self.config.get()
+    }
+
+    /// Prunes every height below the retained blocks, `batch_blocks` heights per
+    /// database transaction.
+    pub async fn prune(&self) -> Result<PruneReport, RetentionError> {
+        let mut report = PruneReport::default();
+        if !self.schema.retention {
+            return Ok(report);
+        }
+
+        loop {
+            let batch = self.prune_batch().await?;
+            let done = batch.heights == 0;
+            report.pruned_height = batch.pruned_height;
+            report.heights += batch.heights;
+            report.transactions += batch.transactions;
+            report.outputs += batch.outputs;
+            if done {
+                break;
+            }
+        }
+
+        if report.heights > 0 {
+            info!(
+                component = "retention",
+                pruned_height = report.pruned_height,
+                heights = report.heights,
+                transactions = report.transactions,
+                outputs = report.outputs,
+                message = "transaction detail pruned"
+            );
+        }
+
+        Ok(report)
+    }
+
+    async fn prune_batch(&self) -> Result<PruneReport, sqlx::Error> {
+        let config = self.current_config();
+        let mut db_tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(PRUNE_LOCK_KEY)
+            .execute(&mut *db_tx)
+            .await?;
+
+        let pruned_height: Option<i32> = sqlx::query_scalar("SELECT pruned_height FROM pruning_state")
+            .fetch_optional(&mut *db_tx)
+            .await?;
+        let (tip, lowest): (Option<i32>, Option<i32>) =
+            sqlx::query_as("SELECT MAX(height), MIN(height) FROM blocks WHERE status = 'canonical'")
+                .fetch_one(&mut *db_tx)
+                .await?;
+        let mut report = PruneReport {
+            pruned_height,
+            ..PruneReport::default()
+        };
+        let (Some(tip), Some(lowest)) = (tip, lowest) else {
+            return Ok(report);
+        };
+
+        let keep_blocks = i32::try_from(config.keep_blocks).unwrap_or(i32::MAX);
+        let batch_blocks = i32::try_from(config.batch_blocks.max(1)).unwrap_or(i32::MAX);
+        let from_height = pruned_height.map_or(lowest, |height| height.saturating_add(1));
+        let to_height = std::cmp::min(
+            from_height.saturating_add(batch_blocks - 1),
+            tip.saturating_sub(keep_blocks),
+        );
+        if to_height < from_height {
+            return Ok(report);
+        }
+
+        report.transactions = sqlx::query(
+            "UPDATE transactions
+             SET decoded = decoded - 'vin' - 'vout'
+             WHERE block_height BETWEEN $1 AND $2
+               AND (decoded ? 'vin' OR decoded ? 'vout')",
+        )
+        .bind(from_height)
+        .bind(to_height)
+        .execute(&mut *db_tx)
+        .await?
+        .rows_affected();
+        report.outputs = self.prune_outputs(&mut db_tx, from_height, to_height).await?;
+        sqlx::query(
+            "INSERT INTO pruning_state (singleton, pruned_height, pruned_at) VALUES (TRUE, $1, NOW())
+             ON CONFLICT (singleton) DO UPDATE SET pruned_height = EXCLUDED.pruned_height, pruned_at = NOW()",
+        )
+        .bind(to_height)
+        .execute(&mut *db_tx)
+        .await?;
+        db_tx.commit().await?;
+
+        report.pruned_height = Some(to_height);
+        report.heights = (to_height - from_height + 1) as u64;
+        Ok(report)
+    }
+
+    async fn prune_outputs(
+        &self,
+        conn: &mut PgConnection,
+        from_height: i32,
+        to_height: i32,
+    ) -> Result<u64, sqlx::Error> {
+        // Partitioned outputs carry their height, which also limits the update to their partitions.
+        let statement = if self.schema.chain_partitions {
+            "UPDATE tx_outputs
+             SET script_hex = ''
+             WHERE block_height BETWEEN $1 AND $2 AND script_hex <> ''"
+        } else {
+            "UPDATE tx_outputs o
+             SET script_hex = ''
+             FROM transactions t
+             WHERE t.txid = o.txid AND t.block_height BETWEEN $1 AND $2 AND o.script_hex <> ''"
+        };
+        let pruned = sqlx::query(statement)
+            .bind(from_height)
+            .bind(to_height)
+            .execute(conn)
+            .await?
+            .rows_affected();
+
+        Ok(pruned)
+    }
+}
+
+impl RetentionRunner {
+    pub fn new(retention: RetentionService) -> Self {
+        Self { retention }
+    }
+
+    pub fn service(&self) -> &RetentionService {
+        &self.retention
+    }
+
+    pub fn start(&self) {
+        if !self.retention.schema.retention {
+            warn!(component = "retention", message = "pruning_state table is missing, pruning disabled");
+            return;
+        }
+
+        let runner = self.clone();
+        let config = runner.retention.current_config();
+        info!(
+            component = "retention",
+            keep_blocks = config.keep_blocks,
+            batch_blocks = config.batch_blocks,
+            interval_ms = config.interval.as_millis() as u64,
+            message = "retention pruning started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = runner.retention.prune().await {
+                    warn!(component = "retention", error = %err, message = "pruning failed");
+                }
+
+                tokio::time::sleep(runner.retention.current_config().interval).await;
+            }
+        });
+    }
+}
@@ -1,6 +1,7 @@
 use std::error::Error;
+use std::future::Future;
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
 use reqwest::{Certificate, Client, Identity};
@@ -8,10 +9,21 @@ use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, info, info_span, Instrument};
+use zeroize::Zeroizing;
 
+use crate::modules::chain::RpcBlockchainInfo;
 use crate::modules::config::RpcConfig;
-use crate::modules::indexer::{RpcBlock, RpcTransaction};
+use crate::modules::indexer::{RpcBlock, RpcBlockHeader, RpcTransaction};
 use crate::modules::metrics::MetricsService;
+use crate::modules::node_info::{RpcMempoolInfo, RpcNetworkInfo};
+use crate::modules::rate_limit::Throttle;
+use crate::modules::scan::RpcScanTxOutSet;
+use crate::modules::templates::RpcBlockTemplate;
+
+/// `rpc_method` label of block fetches through the REST interface.
+const REST_BLOCK_METHOD: &str = "rest_block";
 
 #[derive(Debug, Error)]
 pub enum RpcError {
@@ -23,23 +35,98 @@ pub enum RpcError {
     InvalidIdentity(reqwest::Error),
     #[error("http error: {0}")]
     Http(String),
+    #[error("failed to decode rpc response: {0}")]
+    Decode(String),
+    /// Malformed JSON-RPC reply, e.g. without `result`, or a failure on this side of the call.
     #[error("rpc error: {0}")]
     Rpc(String),
+    /// Error object answered by the node; codes with their own variant are mapped by [`RpcError::node`].
+    #[error("rpc error {code}: {message}")]
+    Node { code: i64, message: String },
+    /// `RPC_IN_WARMUP` (-28): the node is still loading its block index or verifying blocks.
+    #[error("node is warming up: {0}")]
+    WarmingUp(String),
+    /// `RPC_INVALID_ADDRESS_OR_KEY` (-5): unknown block, transaction or address.
+    #[error("not found: {0}")]
+    NotFound(String),
+}
+
+impl RpcError {
+    pub const IN_WARMUP: i64 = -28;
+    pub const INVALID_ADDRESS_OR_KEY: i64 = -5;
+
+    /// Typed error of a JSON-RPC error object.
+    pub fn node(code: i64, message: String) -> Self {
+        match code {
+            Self::IN_WARMUP => RpcError::WarmingUp(message),
+            Self::INVALID_ADDRESS_OR_KEY => RpcError::NotFound(message),
+            _ => RpcError::Node { code, message },
+        }
+    }
+
+    /// Message of an error the node answered about the request itself, as opposed to
+    /// transport failures and a node that is still starting.
+    pub fn node_message(&self) -> Option<&str> {
+        match self {
+            RpcError::Node { message, .. } | RpcError::NotFound(message) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// Node calls of the indexer, so it can run against canned fixtures or another transport.
+/// [`RpcClient`] implements it over JSON-RPC.
+pub trait BitcoinRpc: Clone + Send + Sync + 'static {
+    fn get_block_count(&self) -> impl Future<Output = Result<u64, RpcError>> + Send;
+
+    fn get_block_hash(&self, height: u32) -> impl Future<Output = Result<String, RpcError>> + Send;
+
+    fn get_block_verbose2(&self, hash: &str) -> impl Future<Output = Result<RpcBlock, RpcError>> + Send;
+
+    /// Like [`Self::get_block_verbose2`] with `prevout` on every input.
+    fn get_block_verbose3(&self, hash: &str) -> impl Future<Output = Result<RpcBlock, RpcError>> + Send;
+
+    /// Serialized block as hex.
+    fn get_block_hex(&self, hash: &str) -> impl Future<Output = Result<String, RpcError>> + Send;
+
+    fn get_block_header(&self, hash: &str) -> impl Future<Output = Result<RpcBlockHeader, RpcError>> + Send;
+
+    fn get_raw_transaction_verbose(
+        &self,
+        txid: &str,
+    ) -> impl Future<Output = Result<RpcTransaction, RpcError>> + Send;
+
+    fn get_raw_mempool(&self) -> impl Future<Output = Result<Vec<String>, RpcError>> + Send;
 }
 
 #[derive(Clone)]
 pub struct RpcClient {
-    client: Client,
+    /// Shared by all clones, so [`Self::adopt_credentials`] reaches every user of the client.
+    transport: Arc<RwLock<Transport>>,
     url: String,
-    username: String,
-    password: String,
+    /// Base URL of the `-rest` interface that blocks are fetched from, see [`Self::with_rest_url`].
+    rest_url: Option<String>,
     id: Arc<AtomicU64>,
     metrics: Option<MetricsService>,
+    /// Paces calls of this client and its clones, see [`Self::with_throttle`].
+    throttle: Option<Throttle>,
+    /// Node-wide pace shared by every clone, see [`Self::with_rate_limit`].
+    rate_limit: Option<Throttle>,
+    /// Requests in flight to the node, see [`Self::with_parallelism`].
+    parallelism: Option<Arc<Semaphore>>,
+}
+
+/// HTTP client with the TLS identity of the node and the credentials sent with every call.
+#[derive(Clone)]
+struct Transport {
+    client: Client,
+    username: String,
+    password: Zeroizing<String>,
 }
 
 impl RpcClient {
     pub fn from_config(config: &RpcConfig) -> Result<Self, RpcError> {
-        Self::new(
+        let client = Self::new(
             &config.url,
             &config.auth.username,
             &config.auth.password,
@@ -55,7 +142,10 @@ impl RpcClient {
                         mtls.client_key_path.clone(),
                     )
                 }),
-        )
+        )?;
+        Ok(client
+            .with_rest_url(config.rest_url.clone())
+            .with_rate_limit(config.max_requests_per_second))
     }
 
     pub fn new(
@@ -96,12 +186,18 @@ impl RpcClient {
         let client = builder.build()?;
 
         Ok(Self {
-            client,
+            transport: Arc::new(RwLock::new(Transport {
+                client,
+                username: username.to_string(),
+                password: Zeroizing::new(password.to_string()),
+            })),
             url: url.to_string(),
-            username: username.to_string(),
-            password: password.to_string(),
+            rest_url: None,
             id: Arc::new(AtomicU64::new(1)),
             metrics: None,
+            throttle: None,
+            rate_limit: None,
+            parallelism: None,
         })
     }
 
@@ -110,10 +206,73 @@ impl RpcClient {
         self
     }
 
+    /// Fetches blocks (`getblock` with verbosity 2/3 or as hex) from the bitcoind REST
+    /// interface at `rest_url` instead of JSON-RPC; other calls still use JSON-RPC.
+    pub fn with_rest_url(mut self, rest_url: Option<String>) -> Self {
+        self.rest_url = rest_url;
+        self
+    }
+
+    /// Every JSON-RPC or REST request first takes a token from `throttle`; a batch takes
+    /// one per call.
+    pub fn with_throttle(mut self, throttle: Option<Throttle>) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Caps calls of this client and all its clones, including ones given their own
+    /// [`Self::with_throttle`], at `requests_per_second`; bursts up to the same count.
+    pub fn with_rate_limit(mut self, requests_per_second: Option<u32>) -> Self {
+        self.rate_limit = requests_per_second.map(|rate| Throttle::new(f64::from(rate), rate));
+        self
+    }
+
+    /// At most `limit` HTTP requests of this client and its clones are in flight; the rest
+    /// wait, so parallel jobs cannot overflow the node's RPC work queue.
+    pub fn with_parallelism(mut self, limit: u16) -> Self {
+        self.parallelism = Some(Arc::new(Semaphore::new(usize::from(limit.max(1)))));
+        self
+    }
+
+    /// Switches this client and all its clones to the HTTP client and credentials of `source`,
+    /// e.g. one built from a config with a rotated password or mTLS certificate. Calls in
+    /// flight finish with the previous ones.
+    pub fn adopt_credentials(&self, source: &RpcClient) {
+        let next = source.transport();
+        *self.transport.write().unwrap_or_else(|err| err.into_inner()) = next;
+    }
+
+    fn transport(&self) -> Transport {
+        self.transport.read().unwrap_or_else(|err| err.into_inner()).clone()
+    }
+
+    /// Waits for the job and node-wide rate limits, then for a free request slot, which is
+    /// held until the returned permit is dropped.
+    async fn admit(&self, calls: usize) -> Option<OwnedSemaphorePermit> {
+        for throttle in [&self.throttle, &self.rate_limit].into_iter().flatten() {
+            for _ in 0..calls {
+                throttle.acquire().await;
+            }
+        }
+        let parallelism = self.parallelism.clone()?;
+        // The semaphore is never closed.
+        parallelism.acquire_owned().await.ok()
+    }
+
     pub async fn call<T>(&self, method: &str, params: Value) -> Result<T, RpcError>
     where
         T: DeserializeOwned,
     {
+        self.call_with_timeout(method, params, None).await
+    }
+
+    /// Like [`Self::call`], with `timeout` replacing `rpc.timeouts.request_ms` for calls the
+    /// node is known to answer slowly.
+    pub async fn call_with_timeout<T>(&self, method: &str, params: Value, timeout: Option<Duration>) -> Result<T, RpcError>
+    where
+        T: DeserializeOwned,
+    {
+        let _slot = self.admit(1).await;
         let started = Instant::now();
         let id = self.id.fetch_add(1, Ordering::Relaxed);
         let request = RpcRequest {
@@ -123,36 +282,152 @@ impl RpcClient {
             params,
         };
 
+        let transport = self.transport();
         let result = async {
-            let response = self
+            let mut builder = transport
                 .client
                 .post(&self.url)
-                .basic_auth(&self.username, Some(&self.password))
-                .json(&request)
-                .send()
-                .await?
-                .error_for_status()?;
+                .basic_auth(&transport.username, Some(transport.password.as_str()))
+                .json(&request);
+            if let Some(timeout) = timeout {
+                builder = builder.timeout(timeout);
+            }
+            let response = builder.send().await?;
+            // bitcoind answers a failed call with `500` and the JSON-RPC error in the body.
+            if response.status() == reqwest::StatusCode::INTERNAL_SERVER_ERROR {
+                let body = response.text().await?;
+                return Err(match serde_json::from_str::<RpcResponse<Value>>(&body) {
+                    Ok(RpcResponse { error: Some(error), .. }) => error.into(),
+                    _ => RpcError::Http(format!("status=500; {}", body.trim())),
+                });
+            }
 
-            let payload: RpcResponse<T> = response.json().await?;
+            let payload: RpcResponse<T> = response.error_for_status()?.json().await?;
             if let Some(error) = payload.error {
-                return Err(RpcError::Rpc(error.message));
+                return Err(error.into());
             }
 
             payload
                 .result
                 .ok_or_else(|| RpcError::Rpc("missing result".to_string()))
         }
+        .instrument(info_span!("rpc_call", rpc_method = method, rpc_id = id))
+        .await;
+
+        self.record_call(method, id, started, result.is_ok());
+        result
+    }
+
+    /// Sends one JSON-RPC batch with a `method` call per entry of `params` and returns
+    /// the results in the same order; an error of any call fails the batch.
+    pub async fn call_batch<T>(&self, method: &str, params: Vec<Value>) -> Result<Vec<T>, RpcError>
+    where
+        T: DeserializeOwned,
+    {
+        if params.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let count = params.len();
+        let _slot = self.admit(count).await;
+        let started = Instant::now();
+        let first_id = self.id.fetch_add(count as u64, Ordering::Relaxed);
+        let requests: Vec<RpcRequest> = params
+            .into_iter()
+            .enumerate()
+            .map(|(offset, params)| RpcRequest {
+                jsonrpc: "1.0",
+                id: first_id + offset as u64,
+                method,
+                params,
+            })
+            .collect();
+
+        let transport = self.transport();
+        let result = async {
+            let responses: Vec<RpcResponse<T>> = transport
+                .client
+                .post(&self.url)
+                .basic_auth(&transport.username, Some(transport.password.as_str()))
+                .json(&requests)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+
+            // Replies of a batch may come in any order.
+            let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+            for response in responses {
+                if let Some(error) = response.error {
+                    return Err(error.into());
+                }
+                let slot = response
+                    .id
+                    .and_then(|id| id.checked_sub(first_id))
+                    .and_then(|offset| results.get_mut(usize::try_from(offset).ok()?))
+                    .ok_or_else(|| RpcError::Rpc("unexpected id in batch response".to_string()))?;
+                *slot = response.result;
+            }
+
+            results
+                .into_iter()
+                .map(|result| result.ok_or_else(|| RpcError::Rpc("missing result".to_string())))
+                .collect()
+        }
+        .instrument(info_span!("rpc_batch", rpc_method = method, rpc_id = first_id, rpc_batch_size = count))
+        .await;
+
+        self.record_call(method, first_id, started, result.is_ok());
+        result
+    }
+
+    /// `GET {rest_url}/rest/{path}`; the REST interface takes no credentials.
+    async fn rest_get(&self, rest_url: &str, path: &str) -> Result<String, RpcError> {
+        let _slot = self.admit(1).await;
+        let started = Instant::now();
+        let id = self.id.fetch_add(1, Ordering::Relaxed);
+
+        let client = self.transport().client;
+        let result = async {
+            let response = client.get(format!("{rest_url}/rest/{path}")).send().await?;
+            let status = response.status();
+            let body = response.text().await?;
+            if !status.is_success() {
+                // bitcoind explains REST errors in a plain text body.
+                return Err(RpcError::Http(format!("rest {path}; status={status}; {}", body.trim())));
+            }
+            Ok(body)
+        }
+        .instrument(info_span!("rest_call", rest_path = path, rpc_id = id))
         .await;
 
+        self.record_call(REST_BLOCK_METHOD, id, started, result.is_ok());
+        result
+    }
+
+    async fn rest_block(&self, rest_url: &str, hash: &str) -> Result<RpcBlock, RpcError> {
+        let body = self.rest_get(rest_url, &format!("block/{hash}.json")).await?;
+        serde_json::from_str(&body).map_err(|err| RpcError::Decode(format!("rest block {hash}: {err}")))
+    }
+
+    fn record_call(&self, method: &str, id: u64, started: Instant, ok: bool) {
+        debug!(
+            component = "rpc",
+            rpc_method = method,
+            rpc_id = id,
+            latency_ms = started.elapsed().as_secs_f64() * 1000.0,
+            ok,
+            message = "rpc call completed"
+        );
+
         if let Some(metrics) = &self.metrics {
             metrics.increment_rpc_request(method);
             metrics.observe_rpc_request_duration(method, started.elapsed().as_secs_f64());
-            if result.is_err() {
+            if !ok {
                 metrics.increment_error("rpc");
             }
         }
-
-        result
     }
 
     pub async fn get_block_hash(&self, height: u32) -> Result<String, RpcError> {
@@ -170,9 +445,46 @@ impl RpcClient {
     }
 
     pub async fn get_block_verbose2(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+        if let Some(rest_url) = &self.rest_url {
+            return self.rest_block(rest_url, hash).await;
+        }
         self.call("getblock", serde_json::json!([hash, 2])).await
     }
 
+    /// Adds `prevout` to every input; nodes older than Core 23 answer as with verbosity 2.
+    /// Through REST the block has `prevout` only when the node's `/rest/block` includes it.
+    pub async fn get_block_verbose3(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+        if let Some(rest_url) = &self.rest_url {
+            return self.rest_block(rest_url, hash).await;
+        }
+        self.call("getblock", serde_json::json!([hash, 3])).await
+    }
+
+    /// Serialized block; the bool verbose flag is understood by every supported node.
+    pub async fn get_block_hex(&self, hash: &str) -> Result<String, RpcError> {
+        if let Some(rest_url) = &self.rest_url {
+            let hex = self.rest_get(rest_url, &format!("block/{hash}.hex")).await?;
+            return Ok(hex.trim().to_string());
+        }
+        self.call("getblock", serde_json::json!([hash, false])).await
+    }
+
+    pub async fn get_block_header(&self, hash: &str) -> Result<RpcBlockHeader, RpcError> {
+        self.call("getblockheader", serde_json::json!([hash, true])).await
+    }
+
+    /// Hashes of the active chain at `heights`, in one batch.
+    pub async fn get_block_hashes(&self, heights: &[u32]) -> Result<Vec<String>, RpcError> {
+        let params = heights.iter().map(|height| serde_json::json!([height])).collect();
+        self.call_batch("getblockhash", params).await
+    }
+
+    /// Headers of the blocks with `hashes`, in one batch.
+    pub async fn get_block_headers(&self, hashes: &[String]) -> Result<Vec<RpcBlockHeader>, RpcError> {
+        let params = hashes.iter().map(|hash| serde_json::json!([hash, true])).collect();
+        self.call_batch("getblockheader", params).await
+    }
+
     pub async fn get_raw_transaction(&self, txid: &str, verbose: bool) -> Result<Value, RpcError> {
         self.call("getrawtransaction", serde_json::json!([txid, verbose]))
             .await
@@ -183,9 +495,94 @@ impl RpcClient {
             .await
     }
 
+    /// Relays a raw transaction; a reject reason of the node is returned as [`RpcError::Node`].
+    pub async fn send_raw_transaction(&self, raw_hex: &str) -> Result<String, RpcError> {
+        self.call("sendrawtransaction", serde_json::json!([raw_hex])).await
+    }
+
+    /// `scantxoutset start` over `scan_objects`; the node runs one scan at a time and
+    /// answers another start with [`RpcError::Node`].
+    pub async fn scan_tx_out_set(&self, scan_objects: Value, timeout: Duration) -> Result<RpcScanTxOutSet, RpcError> {
+        self.call_with_timeout("scantxoutset", serde_json::json!(["start", scan_objects]), Some(timeout))
+            .await
+    }
+
     pub async fn get_raw_mempool(&self) -> Result<Vec<String>, RpcError> {
         self.call("getrawmempool", serde_json::json!([])).await
     }
+
+    pub async fn get_blockchain_info(&self) -> Result<RpcBlockchainInfo, RpcError> {
+        self.call("getblockchaininfo", serde_json::json!([])).await
+    }
+
+    /// Polls `getblockchaininfo` every `interval` while the node answers
+    /// [`RpcError::WarmingUp`], for at most `timeout`. Other errors end the wait at once;
+    /// after `timeout` the last warm-up error is returned.
+    pub async fn wait_until_ready(&self, timeout: Duration, interval: Duration) -> Result<RpcBlockchainInfo, RpcError> {
+        let started = Instant::now();
+        loop {
+            match self.get_blockchain_info().await {
+                Err(RpcError::WarmingUp(status)) if started.elapsed() + interval <= timeout => {
+                    info!(
+                        component = "rpc",
+                        status = %status,
+                        waited_ms = started.elapsed().as_millis() as u64,
+                        timeout_ms = timeout.as_millis() as u64,
+                        message = "node is warming up, waiting"
+                    );
+                    tokio::time::sleep(interval).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub async fn get_network_info(&self) -> Result<RpcNetworkInfo, RpcError> {
+        self.call("getnetworkinfo", serde_json::json!([])).await
+    }
+
+    pub async fn get_mempool_info(&self) -> Result<RpcMempoolInfo, RpcError> {
+        self.call("getmempoolinfo", serde_json::json!([])).await
+    }
+
+    pub async fn get_block_template(&self) -> Result<RpcBlockTemplate, RpcError> {
+        self.call("getblocktemplate", serde_json::json!([{ "rules": ["segwit"] }]))
+            .await
+    }
+}
+
+impl BitcoinRpc for RpcClient {
+    async fn get_block_count(&self) -> Result<u64, RpcError> {
+        RpcClient::get_block_count(self).await
+    }
+
+    async fn get_block_hash(&self, height: u32) -> Result<String, RpcError> {
+        RpcClient::get_block_hash(self, height).await
+    }
+
+    async fn get_block_verbose2(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+        RpcClient::get_block_verbose2(self, hash).await
+    }
+
+    async fn get_block_verbose3(&self, hash: &str) -> Result<RpcBlock, RpcError> {
+        RpcClient::get_block_verbose3(self, hash).await
+    }
+
+    async fn get_block_hex(&self, hash: &str) -> Result<String, RpcError> {
+        RpcClient::get_block_hex(self, hash).await
+    }
+
+    async fn get_block_header(&self, hash: &str) -> Result<RpcBlockHeader, RpcError> {
+        RpcClient::get_block_header(self, hash).await
+    }
+
+    async fn get_raw_transaction_verbose(&self, txid: &str) -> Result<RpcTransaction, RpcError> {
+        RpcClient::get_raw_transaction_verbose(self, txid).await
+    }
+
+    async fn get_raw_mempool(&self) -> Result<Vec<String>, RpcError> {
+        RpcClient::get_raw_mempool(self).await
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -198,18 +595,31 @@ struct RpcRequest<'a> {
 
 #[derive(Debug, Deserialize)]
 struct RpcResponse<T> {
+    #[serde(default)]
+    id: Option<u64>,
     result: Option<T>,
     error: Option<RpcResponseError>,
-    id: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 struct RpcResponseError {
+    #[serde(default)]
+    code: i64,
     message: String,
 }
 
+impl From<RpcResponseError> for RpcError {
+    fn from(error: RpcResponseError) -> Self {
+        RpcError::node(error.code, error.message)
+    }
+}
+
 impl From<reqwest::Error> for RpcError {
     fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            return RpcError::Decode(describe_reqwest_error(&err));
+        }
+
         RpcError::Http(describe_reqwest_error(&err))
     }
 }
@@ -250,7 +660,9 @@ fn describe_reqwest_error(err: &reqwest::Error) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::RpcRequest;
+    use std::time::Duration;
+
+    use super::{RpcClient, RpcError, RpcRequest, RpcResponse};
 
     #[test]
     fn rpc_request_serializes() {
@@ -264,5 +676,50 @@ mod tests {
         let body = serde_json::to_string(&req).expect("serialize");
         assert!(body.contains("getblockhash"));
         assert!(body.contains("\"jsonrpc\":\"1.0\""));
+
+        let batch: Vec<RpcResponse<String>> = serde_json::from_str(
+            r#"[{"result":"hash-b","error":null,"id":8},{"result":"hash-a","error":null,"id":7}]"#,
+        )
+        .expect("deserialize batch");
+        assert_eq!(batch[0].id, Some(8));
+        assert_eq!(batch[1].result.as_deref(), Some("hash-a"));
+    }
+
+    #[test]
+    fn maps_node_error_codes() {
+        let reply: RpcResponse<String> = serde_json::from_str(
+            r#"{"result":null,"error":{"code":-28,"message":"Loading block index…"},"id":1}"#,
+        )
+        .expect("deserialize warmup");
+        let err = RpcError::from(reply.error.expect("error object"));
+        assert!(matches!(err, RpcError::WarmingUp(_)));
+        assert_eq!(err.node_message(), None);
+
+        let reply: RpcResponse<String> =
+            serde_json::from_str(r#"{"result":null,"error":{"code":-5,"message":"Block not found"},"id":2}"#)
+                .expect("deserialize not found");
+        let err = RpcError::from(reply.error.expect("error object"));
+        assert!(matches!(err, RpcError::NotFound(_)));
+        assert_eq!(err.node_message(), Some("Block not found"));
+
+        let err = RpcError::node(-26, "min relay fee not met".to_string());
+        assert_eq!(err.to_string(), "rpc error -26: min relay fee not met");
+        assert_eq!(err.node_message(), Some("min relay fee not met"));
+    }
+
+    #[tokio::test]
+    async fn admits_at_most_parallelism_requests() {
+        let rpc = RpcClient::new("http://127.0.0.1:1", "user", "pass", false, 1_000, 1_000, None)
+            .expect("rpc client")
+            .with_parallelism(2);
+        let clone = rpc.clone();
+
+        let first = rpc.admit(1).await.expect("first slot");
+        let _second = clone.admit(1).await.expect("second slot");
+        assert!(tokio::time::timeout(Duration::from_millis(50), rpc.admit(1)).await.is_err());
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_millis(50), rpc.admit(1)).await;
+        assert!(matches!(third, Ok(Some(_))));
     }
 }
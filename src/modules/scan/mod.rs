@@ -0,0 +1,281 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tracing::info;
+use utoipa::ToSchema;
+
+use crate::modules::rpc::RpcClient;
+
+pub const MAX_SCAN_DESCRIPTORS: usize = 100;
+/// Upper bound of `range`: the node derives `range` keys per ranged descriptor before the scan.
+pub const MAX_SCAN_RANGE: u32 = 100_000;
+/// Scans waiting behind the running one; further requests fail with [`ScanError::QueueFull`].
+pub const MAX_QUEUED_SCANS: usize = 4;
+/// A full UTXO set pass takes minutes on mainnet, far above `rpc.timeouts.request_ms`.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+#[derive(Debug, Error)]
+pub enum ScanError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error("scan rejected by node: {0}")]
+    Rejected(String),
+    #[error("scan queue is full")]
+    QueueFull,
+    #[error("a scan started outside the indexer is still running on the node")]
+    NodeBusy,
+    #[error("node unavailable: {0}")]
+    NodeUnavailable(String),
+}
+
+/// Subset of the `scantxoutset start` response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcScanTxOutSet {
+    #[serde(default = "default_success")]
+    pub success: bool,
+    pub height: u64,
+    #[serde(rename = "bestblock")]
+    pub best_block: String,
+    pub unspents: Vec<RpcScanUnspent>,
+    #[serde(with = "crate::modules::amount::btc")]
+    pub total_amount: i64,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcScanUnspent {
+    pub txid: String,
+    pub vout: u32,
+    #[serde(rename = "scriptPubKey")]
+    pub script_pub_key: String,
+    pub desc: String,
+    #[serde(with = "crate::modules::amount::btc")]
+    pub amount: i64,
+    #[serde(default)]
+    pub coinbase: bool,
+    pub height: u64,
+}
+
+/// UTXOs of the scanned descriptors in the node's chainstate at `height`.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScanResult {
+    pub height: u64,
+    pub best_block: String,
+    pub items: Vec<ScanUtxo>,
+    pub total_sats: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScanUtxo {
+    pub txid: String,
+    pub vout: u32,
+    pub script_hex: String,
+    /// Inferred descriptor of the output script, with the key origin of the matched descriptor.
+    pub descriptor: String,
+    pub value_sats: i64,
+    pub coinbase: bool,
+    pub block_height: u64,
+}
+
+/// Runs `scantxoutset` on the network's node. The node allows one scan at a time, so
+/// requests wait in a short queue and a scan keeps its slot until the node answers even
+/// if the client that started it goes away.
+#[derive(Clone)]
+pub struct ScanService {
+    rpc: Option<RpcClient>,
+    slot: Arc<Semaphore>,
+    pending: Arc<AtomicUsize>,
+}
+
+impl fmt::Debug for ScanService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScanService")
+            .field("rpc", &self.rpc.is_some())
+            .field("pending", &self.pending.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for ScanService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanService {
+    pub fn new() -> Self {
+        Self {
+            rpc: None,
+            slot: Arc::new(Semaphore::new(1)),
+            pending: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Node the scans run on; without it every scan fails with [`ScanError::NodeUnavailable`].
+    pub fn with_rpc(mut self, rpc: RpcClient) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+
+    /// Scans the UTXO set for `descriptors`, deriving `range` keys of ranged ones
+    /// (node default of 1000 when `None`).
+    pub async fn scan(&self, descriptors: &[String], range: Option<u32>) -> Result<ScanResult, ScanError> {
+        let scan_objects = scan_objects(descriptors, range)?;
+        let Some(rpc) = self.rpc.clone() else {
+            return Err(ScanError::NodeUnavailable("rpc client is not configured".to_string()));
+        };
+
+        let pending = PendingScan::enter(&self.pending)?;
+        let permit = self
+            .slot
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|_| ScanError::NodeUnavailable("scan queue is closed".to_string()))?;
+        let count = descriptors.len();
+        let scan = tokio::spawn(async move {
+            let _permit = permit;
+            let _pending = pending;
+            rpc.scan_tx_out_set(scan_objects, SCAN_TIMEOUT).await
+        });
+        let outcome = scan
+            .await
+            .map_err(|err| ScanError::NodeUnavailable(format!("scan task failed: {err}")))?;
+
+        let response = match outcome {
+            Ok(response) => response,
+            Err(err) => {
+                return Err(match err.node_message() {
+                    // Same code as an invalid descriptor, told apart by the message only.
+                    Some(reason) if reason.contains("already in progress") => ScanError::NodeBusy,
+                    Some(reason) => ScanError::Rejected(reason.to_string()),
+                    None => ScanError::NodeUnavailable(err.to_string()),
+                });
+            }
+        };
+        if !response.success {
+            return Err(ScanError::NodeUnavailable("scan was aborted on the node".to_string()));
+        }
+        info!(
+            component = "scan",
+            descriptors = count,
+            height = response.height,
+            unspents = response.unspents.len(),
+            message = "utxo set scanned"
+        );
+
+        Ok(ScanResult {
+            height: response.height,
+            best_block: response.best_block,
+            items: response
+                .unspents
+                .into_iter()
+                .map(|unspent| ScanUtxo {
+                    txid: unspent.txid,
+                    vout: unspent.vout,
+                    script_hex: unspent.script_pub_key,
+                    descriptor: unspent.desc,
+                    value_sats: unspent.amount,
+                    coinbase: unspent.coinbase,
+                    block_height: unspent.height,
+                })
+                .collect(),
+            total_sats: response.total_amount,
+        })
+    }
+}
+
+/// Counts a scan from the request until the node answers.
+struct PendingScan(Arc<AtomicUsize>);
+
+impl PendingScan {
+    fn enter(pending: &Arc<AtomicUsize>) -> Result<Self, ScanError> {
+        let limit = MAX_QUEUED_SCANS + 1;
+        pending
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| (count < limit).then_some(count + 1))
+            .map_err(|_| ScanError::QueueFull)?;
+        Ok(Self(pending.clone()))
+    }
+}
+
+impl Drop for PendingScan {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// `scanobjects` argument: plain descriptors, or `{desc, range}` objects when `range` is set.
+fn scan_objects(descriptors: &[String], range: Option<u32>) -> Result<Value, ScanError> {
+    if descriptors.is_empty() {
+        return Err(ScanError::Validation("descriptors MUST NOT be empty".to_string()));
+    }
+    if descriptors.len() > MAX_SCAN_DESCRIPTORS {
+        return Err(ScanError::Validation(format!(
+            "descriptors MUST contain at most {MAX_SCAN_DESCRIPTORS} entries"
+        )));
+    }
+    if let Some(range) = range {
+        if range == 0 || range > MAX_SCAN_RANGE {
+            return Err(ScanError::Validation(format!("range MUST be between 1 and {MAX_SCAN_RANGE}")));
+        }
+    }
+
+    let objects = descriptors
+        .iter()
+        .map(|descriptor| {
+            let descriptor = descriptor.trim();
+            if descriptor.is_empty() {
+                return Err(ScanError::Validation("descriptor MUST NOT be empty".to_string()));
+            }
+            Ok(match range {
+                Some(range) => serde_json::json!({ "desc": descriptor, "range": range }),
+                None => Value::String(descriptor.to_string()),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Value::Array(objects))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_scan_objects() {
+        let descriptors = vec![" addr(bc1qexample) ".to_string(), "wpkh(xpub/0/*)".to_string()];
+        assert_eq!(
+            scan_objects(&descriptors, None).expect("plain"),
+            serde_json::json!(["addr(bc1qexample)", "wpkh(xpub/0/*)"])
+        );
+        assert_eq!(
+            scan_objects(&descriptors[1..], Some(50)).expect("ranged"),
+            serde_json::json!([{ "desc": "wpkh(xpub/0/*)", "range": 50 }])
+        );
+
+        assert!(matches!(scan_objects(&[], None), Err(ScanError::Validation(_))));
+        assert!(matches!(scan_objects(&[" ".to_string()], None), Err(ScanError::Validation(_))));
+        assert!(matches!(scan_objects(&descriptors, Some(0)), Err(ScanError::Validation(_))));
+        let too_many = vec!["addr(x)".to_string(); MAX_SCAN_DESCRIPTORS + 1];
+        assert!(matches!(scan_objects(&too_many, None), Err(ScanError::Validation(_))));
+    }
+
+    #[test]
+    fn pending_scans_are_bounded() {
+        let pending = Arc::new(AtomicUsize::new(0));
+        let held: Vec<_> = (0..=MAX_QUEUED_SCANS)
+            .map(|_| PendingScan::enter(&pending).expect("slot"))
+            .collect();
+        assert!(matches!(PendingScan::enter(&pending), Err(ScanError::QueueFull)));
+        drop(held);
+        assert_eq!(pending.load(Ordering::Relaxed), 0);
+    }
+}
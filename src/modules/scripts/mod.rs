@@ -0,0 +1,201 @@
+use bitcoin::bech32::{segwit, Hrp};
+use bitcoin::{Address, Network, Script, WitnessProgram};
+
+/// Output script decoded by the indexer instead of taken from the node JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptInfo {
+    /// Same names as `scriptPubKey.type` of Bitcoin Core, so rows stay comparable with
+    /// those indexed from node JSON: `pubkey`, `pubkeyhash`, `scripthash`,
+    /// `witness_v0_keyhash`, `witness_v0_scripthash`, `witness_v1_taproot`, `anchor`,
+    /// `witness_unknown`, `multisig`, `nulldata` or `nonstandard`.
+    pub script_type: &'static str,
+    /// Encoded for `network`; `None` for scripts without an address form.
+    pub address: Option<String>,
+}
+
+/// How output scripts are written as addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressEncoding {
+    /// Address rules of a Bitcoin network.
+    Bitcoin(Network),
+    /// Version bytes and bech32 HRP of a chain derived from Bitcoin; `bech32_hrp` is
+    /// `None` on chains without segwit, whose witness programs get no address.
+    Base58 {
+        pubkey_hash: u8,
+        script_hash: u8,
+        bech32_hrp: Option<&'static str>,
+    },
+}
+
+impl From<Network> for AddressEncoding {
+    fn from(network: Network) -> Self {
+        Self::Bitcoin(network)
+    }
+}
+
+/// Classifies a `scriptPubKey` given in hex; `None` when the hex does not decode.
+pub fn classify_script(script_hex: &str, encoding: impl Into<AddressEncoding>) -> Option<ScriptInfo> {
+    let bytes = hex::decode(script_hex).ok()?;
+    let script = Script::from_bytes(&bytes);
+    let address = encode_address(script, encoding.into());
+    let witness_program = script
+        .witness_version()
+        .filter(|_| script.is_witness_program())
+        .and_then(|version| WitnessProgram::new(version, &bytes[2..]).ok());
+
+    let script_type = if script.is_p2pk() {
+        "pubkey"
+    } else if script.is_p2pkh() {
+        "pubkeyhash"
+    } else if script.is_p2sh() {
+        "scripthash"
+    } else if script.is_p2wpkh() {
+        "witness_v0_keyhash"
+    } else if script.is_p2wsh() {
+        "witness_v0_scripthash"
+    } else if script.is_p2tr() {
+        "witness_v1_taproot"
+    } else if address.is_some() && witness_program.is_some_and(|program| program.is_p2a()) {
+        "anchor"
+    } else if address.is_some() && script.is_witness_program() {
+        "witness_unknown"
+    } else if script.is_multisig() {
+        "multisig"
+    } else if script.is_op_return() && Script::from_bytes(&bytes[1..]).is_push_only() {
+        "nulldata"
+    } else {
+        "nonstandard"
+    };
+
+    Some(ScriptInfo { script_type, address })
+}
+
+fn encode_address(script: &Script, encoding: AddressEncoding) -> Option<String> {
+    let (pubkey_hash, script_hash, bech32_hrp) = match encoding {
+        AddressEncoding::Bitcoin(network) => {
+            return Address::from_script(script, network).ok().map(|address| address.to_string());
+        }
+        AddressEncoding::Base58 {
+            pubkey_hash,
+            script_hash,
+            bech32_hrp,
+        } => (pubkey_hash, script_hash, bech32_hrp),
+    };
+
+    let bytes = script.as_bytes();
+    if script.is_p2pkh() {
+        Some(base58_address(pubkey_hash, &bytes[3..23]))
+    } else if script.is_p2sh() {
+        Some(base58_address(script_hash, &bytes[2..22]))
+    } else if script.is_witness_program() {
+        let version = script.witness_version()?;
+        let program = WitnessProgram::new(version, &bytes[2..]).ok()?;
+        let hrp = Hrp::parse(bech32_hrp?).ok()?;
+        segwit::encode(hrp, version.into(), program.program().as_bytes()).ok()
+    } else {
+        None
+    }
+}
+
+fn base58_address(version: u8, hash: &[u8]) -> String {
+    let mut payload = Vec::with_capacity(1 + hash.len());
+    payload.push(version);
+    payload.extend_from_slice(hash);
+    bitcoin::base58::encode_check(&payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::Network;
+
+    use super::{classify_script, AddressEncoding};
+
+    fn classify(script_hex: &str, network: Network) -> (&'static str, Option<String>) {
+        let info = classify_script(script_hex, network).expect("valid hex");
+        (info.script_type, info.address)
+    }
+
+    #[test]
+    fn classifies_scripts_and_derives_addresses_for_network() {
+        assert_eq!(
+            classify("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac", Network::Bitcoin),
+            ("pubkeyhash", Some("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa".to_string()))
+        );
+        assert_eq!(
+            classify("a914748284390f9e263a4b766a75d0633c50426eb87587", Network::Bitcoin),
+            ("scripthash", Some("3CK4fEwbMP7heJarmU4eqA3sMbVJyEnU3V".to_string()))
+        );
+        assert_eq!(
+            classify("0014751e76e8199196d454941c45d1b3a323f1433bd6", Network::Bitcoin),
+            ("witness_v0_keyhash", Some("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string()))
+        );
+        assert_eq!(
+            classify("0014751e76e8199196d454941c45d1b3a323f1433bd6", Network::Regtest),
+            ("witness_v0_keyhash", Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string()))
+        );
+        assert_eq!(
+            classify(
+                "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+                Network::Testnet
+            ),
+            (
+                "witness_v1_taproot",
+                Some("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c".to_string())
+            )
+        );
+        assert_eq!(classify("51024e73", Network::Bitcoin), ("anchor", Some("bc1pfeessrawgf".to_string())));
+        assert_eq!(classify("6a0568656c6c6f", Network::Bitcoin), ("nulldata", None));
+
+        let bare_multisig = concat!(
+            "5121",
+            "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "21",
+            "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+            "52ae"
+        );
+        assert_eq!(classify(bare_multisig, Network::Bitcoin), ("multisig", None));
+        assert_eq!(classify("00", Network::Bitcoin), ("nonstandard", None));
+        assert!(classify_script("0014zz", Network::Bitcoin).is_none());
+    }
+
+    #[test]
+    fn encodes_addresses_with_prefixes_of_other_chains() {
+        let litecoin = AddressEncoding::Base58 {
+            pubkey_hash: 0x30,
+            script_hash: 0x32,
+            bech32_hrp: Some("ltc"),
+        };
+        let dogecoin = AddressEncoding::Base58 {
+            pubkey_hash: 0x1e,
+            script_hash: 0x16,
+            bech32_hrp: None,
+        };
+        let classify = |script_hex: &str, encoding: AddressEncoding| {
+            let info = classify_script(script_hex, encoding).expect("valid hex");
+            (info.script_type, info.address)
+        };
+
+        assert_eq!(
+            classify("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac", litecoin),
+            ("pubkeyhash", Some("LUEweDxDA4WhvWiNXXSxjM9CYzHPJv4QQF".to_string()))
+        );
+        assert_eq!(
+            classify("a914748284390f9e263a4b766a75d0633c50426eb87587", litecoin),
+            ("scripthash", Some("MJXCy8MZJVy8SorksM3zeoJGgJ5m2itsWw".to_string()))
+        );
+        assert_eq!(
+            classify("0014751e76e8199196d454941c45d1b3a323f1433bd6", litecoin),
+            ("witness_v0_keyhash", Some("ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9".to_string()))
+        );
+        assert_eq!(
+            classify("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac", dogecoin),
+            ("pubkeyhash", Some("DEA5vGb2NpAwCiCp5yTE16F3DueQUVivQp".to_string()))
+        );
+        assert_eq!(
+            classify("a914748284390f9e263a4b766a75d0633c50426eb87587", dogecoin),
+            ("scripthash", Some("A34KQ61VRSzbYfxLBbj55HgF4AsM5raT9B".to_string()))
+        );
+        assert_eq!(classify("0014751e76e8199196d454941c45d1b3a323f1433bd6", dogecoin), ("witness_v0_keyhash", None));
+        assert_eq!(classify("51024e73", dogecoin), ("nonstandard", None));
+    }
+}
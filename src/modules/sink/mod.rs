@@ -0,0 +1,351 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use crate::modules::config::SinkConfig;
+use crate::modules::metrics::MetricsService;
+use crate::modules::storage::SchemaFeatures;
+
+/// Held by the process that publishes the outbox, so replicas sharing a database
+/// publish each event once per round and in `id` order.
+const SINK_PUBLISH_LOCK_KEY: i64 = -2;
+
+#[derive(Debug, Error)]
+pub enum SinkError {
+    #[error("sink kind {0} is not compiled in; build with `--features {0}`")]
+    Disabled(String),
+    #[error("failed to connect to sink: {0}")]
+    Connect(String),
+    #[error("failed to publish outbox event {id}: {message}")]
+    Publish { id: i64, message: String },
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Event queued in `event_outbox` by the indexer pipeline.
+#[derive(Debug, Clone, FromRow)]
+pub struct OutboxEvent {
+    pub id: i64,
+    /// `blocks`, `transactions`, `address_activity` or `double_spends`.
+    pub stream: String,
+    pub event_key: String,
+    pub payload: Value,
+}
+
+impl OutboxEvent {
+    /// Kafka topic or NATS subject of the event.
+    pub fn topic(&self, topic_prefix: &str) -> String {
+        format!("{topic_prefix}.{}", self.stream)
+    }
+
+    /// Message body: the payload with the outbox `id`, which consumers deduplicate redeliveries by.
+    pub fn body(&self) -> Vec<u8> {
+        let mut payload = self.payload.clone();
+        if let Value::Object(fields) = &mut payload {
+            fields.insert("id".to_string(), Value::from(self.id));
+        }
+        serde_json::to_vec(&payload).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SinkRunnerConfig {
+    pub poll_interval: Duration,
+    pub batch_size: i64,
+    pub retain_published_hours: i32,
+    pub topic_prefix: String,
+}
+
+impl SinkRunnerConfig {
+    pub fn from_config(config: &SinkConfig) -> Self {
+        Self {
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+            batch_size: i64::from(config.batch_size),
+            retain_published_hours: i32::try_from(config.retain_published_hours).unwrap_or(i32::MAX),
+            topic_prefix: config.topic_prefix.clone(),
+        }
+    }
+}
+
+/// Publishes `event_outbox` rows to Kafka or NATS JetStream and marks them published
+/// once the broker acknowledged them; unacknowledged rows are retried on the next poll,
+/// so delivery is at-least-once.
+#[derive(Clone)]
+pub struct SinkRunner {
+    pool: PgPool,
+    metrics: MetricsService,
+    schema: SchemaFeatures,
+    sink: SinkConfig,
+    config: SinkRunnerConfig,
+    client: Arc<Mutex<Option<SinkClient>>>,
+}
+
+impl SinkRunner {
+    /// Fails when the configured sink kind is not compiled into this build.
+    pub fn new(pool: PgPool, metrics: MetricsService, sink: SinkConfig) -> Result<Self, SinkError> {
+        if !kind_compiled_in(&sink.kind) {
+            return Err(SinkError::Disabled(sink.kind));
+        }
+
+        Ok(Self {
+            pool,
+            metrics,
+            schema: SchemaFeatures::latest(),
+            config: SinkRunnerConfig::from_config(&sink),
+            sink,
+            client: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn start(&self) {
+        if !self.schema.event_outbox {
+            warn!(component = "sink", message = "event_outbox table is missing, sink disabled");
+            return;
+        }
+
+        let runner = self.clone();
+        info!(
+            component = "sink",
+            kind = %runner.sink.kind,
+            topic_prefix = %runner.config.topic_prefix,
+            message = "event sink started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                let published = match runner.publish_once().await {
+                    Ok(published) => published,
+                    Err(err) => {
+                        runner.metrics.increment_error("sink_publish");
+                        warn!(component = "sink", error = %err, message = "event sink publish failed");
+                        0
+                    }
+                };
+
+                // A full batch means a backlog, which is drained without waiting.
+                if (published as i64) < runner.config.batch_size {
+                    tokio::time::sleep(runner.config.poll_interval).await;
+                }
+            }
+        });
+    }
+
+    /// Publishes the oldest unpublished batch, returning how many events were acknowledged.
+    pub async fn publish_once(&self) -> Result<u64, SinkError> {
+        let mut db_tx = self.pool.begin().await?;
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_xact_lock($1)")
+            .bind(SINK_PUBLISH_LOCK_KEY)
+            .fetch_one(&mut *db_tx)
+            .await?;
+        if !locked {
+            db_tx.commit().await?;
+            return Ok(0);
+        }
+
+        sqlx::query("DELETE FROM event_outbox WHERE published_at < NOW() - make_interval(hours => $1)")
+            .bind(self.config.retain_published_hours)
+            .execute(&mut *db_tx)
+            .await?;
+
+        let events: Vec<OutboxEvent> = sqlx::query_as(
+            "SELECT id, stream, event_key, payload \
+             FROM event_outbox \
+             WHERE published_at IS NULL \
+             ORDER BY id \
+             LIMIT $1",
+        )
+        .bind(self.config.batch_size)
+        .fetch_all(&mut *db_tx)
+        .await?;
+        if events.is_empty() {
+            db_tx.commit().await?;
+            return Ok(0);
+        }
+
+        let mut connected = self.client.lock().await;
+        let client = match connected.take() {
+            Some(client) => client,
+            None => SinkClient::connect(&self.sink).await?,
+        };
+        let (acknowledged, error) = client.publish(&self.config.topic_prefix, &events).await;
+        *connected = Some(client);
+
+        let ids: Vec<i64> = events[..acknowledged].iter().map(|event| event.id).collect();
+        sqlx::query("UPDATE event_outbox SET published_at = NOW() WHERE id = ANY($1)")
+            .bind(&ids)
+            .execute(&mut *db_tx)
+            .await?;
+        db_tx.commit().await?;
+
+        match error {
+            Some(err) => Err(err),
+            None => Ok(acknowledged as u64),
+        }
+    }
+}
+
+fn kind_compiled_in(kind: &str) -> bool {
+    (kind == "kafka" && cfg!(feature = "kafka")) || (kind == "nats" && cfg!(feature = "nats"))
+}
+
+enum SinkClient {
+    #[cfg(feature = "kafka")]
+    Kafka(rdkafka::producer::FutureProducer),
+    #[cfg(feature = "nats")]
+    Nats(async_nats::jetstream::Context),
+}
+
+impl SinkClient {
+    async fn connect(config: &SinkConfig) -> Result<Self, SinkError> {
+        match config.kind.as_str() {
+            #[cfg(feature = "kafka")]
+            "kafka" => rdkafka::ClientConfig::new()
+                .set("bootstrap.servers", config.servers.join(","))
+                // Keeps per-partition order across producer retries.
+                .set("enable.idempotence", "true")
+                .set("message.timeout.ms", "30000")
+                .create()
+                .map(SinkClient::Kafka)
+                .map_err(|err| SinkError::Connect(err.to_string())),
+            #[cfg(feature = "nats")]
+            "nats" => async_nats::connect(config.servers.as_slice())
+                .await
+                .map(|client| SinkClient::Nats(async_nats::jetstream::new(client)))
+                .map_err(|err| SinkError::Connect(err.to_string())),
+            kind => Err(SinkError::Disabled(kind.to_string())),
+        }
+    }
+
+    /// Sends `events` without waiting between them, then awaits the acknowledgements in order.
+    /// Returns how many leading events were acknowledged and the error that stopped the rest.
+    #[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(unused_variables))]
+    async fn publish(&self, topic_prefix: &str, events: &[OutboxEvent]) -> (usize, Option<SinkError>) {
+        match *self {
+            #[cfg(feature = "kafka")]
+            SinkClient::Kafka(ref producer) => publish_kafka(producer, topic_prefix, events).await,
+            #[cfg(feature = "nats")]
+            SinkClient::Nats(ref jetstream) => publish_nats(jetstream, topic_prefix, events).await,
+        }
+    }
+}
+
+#[cfg(feature = "kafka")]
+async fn publish_kafka(
+    producer: &rdkafka::producer::FutureProducer,
+    topic_prefix: &str,
+    events: &[OutboxEvent],
+) -> (usize, Option<SinkError>) {
+    use rdkafka::message::{Header, OwnedHeaders};
+    use rdkafka::producer::FutureRecord;
+
+    let mut deliveries = Vec::with_capacity(events.len());
+    for event in events {
+        let topic = event.topic(topic_prefix);
+        let body = event.body();
+        let id = event.id.to_string();
+        let record = FutureRecord::to(&topic)
+            .key(&event.event_key)
+            .payload(&body)
+            .headers(OwnedHeaders::new().insert(Header {
+                key: "event-id",
+                value: Some(&id),
+            }));
+        match producer.send_result(record) {
+            Ok(delivery) => deliveries.push(async move {
+                match delivery.await {
+                    Ok(Ok(_)) => Ok(()),
+                    Ok(Err((err, _))) => Err(err.to_string()),
+                    Err(_) => Err("delivery canceled".to_string()),
+                }
+            }),
+            Err((err, _)) => return await_acknowledged(deliveries, events, Some(publish_error(event, err))).await,
+        }
+    }
+
+    await_acknowledged(deliveries, events, None).await
+}
+
+#[cfg(feature = "nats")]
+async fn publish_nats(
+    jetstream: &async_nats::jetstream::Context,
+    topic_prefix: &str,
+    events: &[OutboxEvent],
+) -> (usize, Option<SinkError>) {
+    use async_nats::jetstream::context::Publish;
+
+    let mut acks = Vec::with_capacity(events.len());
+    for event in events {
+        let publish = Publish::build()
+            .payload(event.body().into())
+            // JetStream drops redeliveries with the same id inside its duplicate window.
+            .message_id(event.id.to_string())
+            .header("Event-Key", event.event_key.as_str());
+        match jetstream.send_publish(event.topic(topic_prefix), publish).await {
+            Ok(ack) => acks.push(async move { ack.await.map(|_| ()) }),
+            Err(err) => return await_acknowledged(acks, events, Some(publish_error(event, err))).await,
+        }
+    }
+
+    await_acknowledged(acks, events, None).await
+}
+
+/// Awaits the acknowledgements of the leading `events` that were sent, in order, stopping
+/// at the first failure; `send_error` is what stopped sending the rest.
+#[cfg(any(feature = "kafka", feature = "nats"))]
+async fn await_acknowledged<F, E>(
+    pending: Vec<F>,
+    events: &[OutboxEvent],
+    send_error: Option<SinkError>,
+) -> (usize, Option<SinkError>)
+where
+    F: std::future::Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    let sent = pending.len();
+    for (acknowledged, ack) in pending.into_iter().enumerate() {
+        if let Err(err) = ack.await {
+            return (acknowledged, Some(publish_error(&events[acknowledged], err)));
+        }
+    }
+
+    (sent, send_error)
+}
+
+#[cfg(any(feature = "kafka", feature = "nats"))]
+fn publish_error(event: &OutboxEvent, err: impl std::fmt::Display) -> SinkError {
+    SinkError::Publish {
+        id: event.id,
+        message: err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kind_compiled_in, OutboxEvent};
+
+    #[test]
+    fn builds_topic_and_body_with_outbox_id() {
+        let event = OutboxEvent {
+            id: 42,
+            stream: "blocks".to_string(),
+            event_key: "blockhash".to_string(),
+            payload: serde_json::json!({ "type": "block_connected", "height": 7 }),
+        };
+
+        assert_eq!(event.topic("btc.mainnet"), "btc.mainnet.blocks");
+        let body: serde_json::Value = serde_json::from_slice(&event.body()).expect("json body");
+        assert_eq!(body, serde_json::json!({ "type": "block_connected", "height": 7, "id": 42 }));
+        assert_eq!(kind_compiled_in("kafka"), cfg!(feature = "kafka"));
+        assert!(!kind_compiled_in("redis"));
+    }
+}
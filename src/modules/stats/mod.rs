@@ -0,0 +1,479 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::config::IndexerConfig;
+use crate::modules::reload::ReloadableConfig;
+use crate::modules::storage::SchemaFeatures;
+
+pub mod supply;
+
+pub use supply::{BlockSupplyItem, SupplyResponse, UtxoAgeBucket};
+
+/// Canonical blocks aggregated into `block_stats` per statement; the runner repeats
+/// full passes without waiting until it caught up.
+const BLOCKS_PER_PASS: i64 = 500;
+const DEFAULT_STATS_LIMIT: i64 = 100;
+const MAX_STATS_LIMIT: i64 = 1000;
+/// Accepted values of the `granularity` filter.
+pub const STATS_GRANULARITIES: &[&str] = &["day", "block"];
+
+#[derive(Debug, Error)]
+pub enum StatsError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Rollup of one UTC day or one block.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct StatsItem {
+    /// UTC day of the block time.
+    pub day: NaiveDate,
+    /// Block height; `null` for daily items.
+    pub height: Option<i32>,
+    pub blocks: i32,
+    pub tx_count: i64,
+    /// Value of all outputs created, coinbase included.
+    pub output_sats: i64,
+    /// `null` when the fee of any transaction is unknown.
+    pub fees_sats: Option<i64>,
+    /// Distinct addresses receiving or spending outputs.
+    pub active_addresses: i64,
+    /// Total fee over total vsize of transactions with a known fee and size, in sat/vB.
+    pub avg_fee_rate_sat_vb: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StatsPage {
+    /// `day` or `block`.
+    pub granularity: String,
+    pub items: Vec<StatsItem>,
+    pub offset: i64,
+    pub limit: i64,
+    pub total: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StatsFilter {
+    /// First UTC day, `YYYY-MM-DD`, inclusive.
+    pub from: Option<String>,
+    /// Last UTC day, `YYYY-MM-DD`, inclusive.
+    pub to: Option<String>,
+    /// `day` (default) or `block`.
+    pub granularity: Option<String>,
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsService {
+    pool: PgPool,
+    schema: SchemaFeatures,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsRunnerConfig {
+    pub interval: Duration,
+}
+
+/// Result of one [`StatsRunner::aggregate_once`] pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StatsReport {
+    /// `block_stats` rows dropped because their block was orphaned.
+    pub dropped_blocks: u64,
+    /// Canonical blocks aggregated into `block_stats`.
+    pub aggregated_blocks: u64,
+    /// Days recomputed into `daily_stats`.
+    pub rolled_up_days: u64,
+}
+
+/// Keeps `block_stats` in line with the canonical chain and rolls changed days up into
+/// `daily_stats`. Reads only what the indexer already persisted, so it can be enabled on
+/// an indexed database and catches up from the lowest missing height.
+#[derive(Clone)]
+pub struct StatsRunner {
+    pool: PgPool,
+    schema: SchemaFeatures,
+    config: ReloadableConfig<StatsRunnerConfig>,
+}
+
+impl StatsService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Daily or per-block rollups between `from` and `to`, oldest first.
+    pub async fn list(&self, filter: StatsFilter) -> Result<StatsPage, StatsError> {
+        let from = parse_day("from", filter.from.as_deref())?;
+        let to = parse_day("to", filter.to.as_deref())?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err(StatsError::Validation("from MUST be <= to".to_string()));
+            }
+        }
+        let granularity = filter.granularity.unwrap_or_else(|| "day".to_string());
+        if !STATS_GRANULARITIES.contains(&granularity.as_str()) {
+            return Err(StatsError::Validation(format!(
+                "granularity MUST be one of: {}",
+                STATS_GRANULARITIES.join("|")
+            )));
+        }
+        let offset = filter.offset.unwrap_or(0);
+        if offset < 0 {
+            return Err(StatsError::Validation("offset MUST be >= 0".to_string()));
+        }
+        let limit = filter.limit.unwrap_or(DEFAULT_STATS_LIMIT);
+        if !(1..=MAX_STATS_LIMIT).contains(&limit) {
+            return Err(StatsError::Validation(format!(
+                "limit MUST be between 1 and {MAX_STATS_LIMIT}"
+            )));
+        }
+
+        let mut page = StatsPage {
+            granularity,
+            items: Vec::new(),
+            offset,
+            limit,
+            total: 0,
+        };
+        if !self.schema.chain_stats {
+            return Ok(page);
+        }
+
+        let (table, height, blocks, order) = if page.granularity == "block" {
+            ("block_stats", "height", "1", "height")
+        } else {
+            ("daily_stats", "NULL::INT", "blocks", "day")
+        };
+        page.total = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {table} WHERE ($1::DATE IS NULL OR day >= $1) AND ($2::DATE IS NULL OR day <= $2)"
+        ))
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let rows: Vec<StatsRow> = sqlx::query_as(&format!(
+            "SELECT day, {height} AS height, {blocks} AS blocks, tx_count::BIGINT AS tx_count, output_sats, fees_sats,
+                    active_addresses::BIGINT AS active_addresses, rated_fees_sats, rated_vsize
+             FROM {table}
+             WHERE ($1::DATE IS NULL OR day >= $1) AND ($2::DATE IS NULL OR day <= $2)
+             ORDER BY {order}
+             OFFSET $3
+             LIMIT $4"
+        ))
+        .bind(from)
+        .bind(to)
+        .bind(offset)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        page.items = rows.into_iter().map(StatsItem::from).collect();
+
+        Ok(page)
+    }
+}
+
+impl StatsRunnerConfig {
+    /// Returns `None` when the stats aggregator is disabled.
+    pub fn from_config(config: &IndexerConfig) -> Option<Self> {
+        config.stats.as_ref().map(|stats| Self {
+            interval: Duration::from_millis(stats.interval_ms),
+        })
+    }
+}
+
+impl StatsRunner {
+    pub fn new(pool: PgPool, config: StatsRunnerConfig) -> Self {
+        Self {
+            pool,
+            schema: SchemaFeatures::latest(),
+            config: ReloadableConfig::new(config),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn update_config(&self, config: StatsRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn current_config(&self) -> StatsRunnerConfig {
+        self.config.get()
+    }
+
+    pub fn start(&self) {
+        if !self.schema.chain_stats {
+            warn!(component = "stats", message = "block_stats table is missing, stats aggregator disabled");
+            return;
+        }
+
+        let runner = self.clone();
+        info!(
+            component = "stats",
+            interval_ms = runner.current_config().interval.as_millis() as u64,
+            message = "stats aggregator started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                // A full block batch means the aggregator is behind: keep going without waiting.
+                loop {
+                    match runner.aggregate_once().await {
+                        Ok(report) if report.aggregated_blocks >= BLOCKS_PER_PASS as u64 => {}
+                        Ok(_) => break,
+                        Err(err) => {
+                            warn!(component = "stats", error = %err, message = "stats aggregation failed");
+                            break;
+                        }
+                    }
+                }
+
+                tokio::time::sleep(runner.current_config().interval).await;
+            }
+        });
+    }
+
+    /// Drops stats of orphaned blocks, aggregates up to [`BLOCKS_PER_PASS`] canonical
+    /// blocks without stats and recomputes the days they belong to.
+    pub async fn aggregate_once(&self) -> Result<StatsReport, StatsError> {
+        if !self.schema.chain_stats {
+            return Ok(StatsReport::default());
+        }
+
+        let mut report = StatsReport {
+            dropped_blocks: self.drop_orphaned().await?,
+            aggregated_blocks: self.aggregate_blocks().await?,
+            rolled_up_days: 0,
+        };
+
+        let days: Vec<NaiveDate> =
+            sqlx::query_scalar("SELECT DISTINCT day FROM block_stats WHERE NOT rolled_up ORDER BY day")
+                .fetch_all(&self.pool)
+                .await?;
+        for day in days {
+            self.roll_up(day).await?;
+            report.rolled_up_days += 1;
+        }
+
+        if report != StatsReport::default() {
+            info!(
+                component = "stats",
+                dropped_blocks = report.dropped_blocks,
+                aggregated_blocks = report.aggregated_blocks,
+                rolled_up_days = report.rolled_up_days,
+                message = "stats aggregated"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Stats can only go stale at heights that have an orphaned block; the other blocks
+    /// of an affected day are marked for a new rollup and a day left without blocks is dropped.
+    async fn drop_orphaned(&self) -> Result<u64, sqlx::Error> {
+        let dropped: i64 = sqlx::query_scalar(
+            "WITH stale AS (
+               DELETE FROM block_stats s
+               WHERE s.height IN (SELECT height FROM blocks WHERE status = 'orphaned')
+                 AND NOT EXISTS (SELECT 1 FROM blocks b WHERE b.hash = s.hash AND b.status = 'canonical')
+               RETURNING s.height, s.day
+             ),
+             pending AS (
+               UPDATE block_stats
+               SET rolled_up = FALSE
+               WHERE day IN (SELECT day FROM stale) AND height NOT IN (SELECT height FROM stale)
+               RETURNING day
+             ),
+             emptied AS (
+               DELETE FROM daily_stats
+               WHERE day IN (SELECT day FROM stale) AND day NOT IN (SELECT day FROM pending)
+             )
+             SELECT COUNT(*) FROM stale",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(dropped as u64)
+    }
+
+    async fn aggregate_blocks(&self) -> Result<u64, sqlx::Error> {
+        let (block_fees, tx_fee) = if self.schema.transaction_fees {
+            ("b.total_fees_sats", "t.fee_sats")
+        } else {
+            ("NULL::BIGINT", "NULL::BIGINT")
+        };
+        let rated = format!("{tx_fee} IS NOT NULL AND (t.decoded->>'vsize')::BIGINT > 0");
+        let aggregated = sqlx::query(&format!(
+            "INSERT INTO block_stats
+               (height, hash, day, time, tx_count, output_sats, fees_sats, rated_fees_sats, rated_vsize, active_addresses)
+             SELECT b.height, b.hash, (to_timestamp(b.time) AT TIME ZONE 'UTC')::DATE, b.time,
+                    txs.tx_count, outs.output_sats, b.total_fees_sats, txs.rated_fees_sats, txs.rated_vsize, active.addresses
+             FROM (
+               SELECT b.height, b.hash, b.time, {block_fees} AS total_fees_sats
+               FROM blocks b
+               WHERE b.status = 'canonical'
+                 AND NOT EXISTS (SELECT 1 FROM block_stats s WHERE s.height = b.height)
+               ORDER BY b.height
+               LIMIT $1
+             ) b
+             CROSS JOIN LATERAL (
+               SELECT COUNT(*)::INT AS tx_count,
+                      COALESCE(SUM({tx_fee}) FILTER (WHERE {rated}), 0)::BIGINT AS rated_fees_sats,
+                      COALESCE(SUM((t.decoded->>'vsize')::BIGINT) FILTER (WHERE {rated}), 0)::BIGINT AS rated_vsize
+               FROM transactions t
+               WHERE t.block_height = b.height AND t.status = 'confirmed'
+             ) txs
+             CROSS JOIN LATERAL (
+               SELECT COALESCE(SUM(o.value_sats), 0)::BIGINT AS output_sats
+               FROM transactions t
+               JOIN tx_outputs o ON o.txid = t.txid
+               WHERE t.block_height = b.height AND t.status = 'confirmed'
+             ) outs
+             CROSS JOIN LATERAL (
+               SELECT COUNT(DISTINCT address)::INT AS addresses
+               FROM ({}) a
+             ) active
+             ON CONFLICT (height) DO NOTHING",
+            active_addresses_sql("t.block_height = b.height")
+        ))
+        .bind(BLOCKS_PER_PASS)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(aggregated)
+    }
+
+    /// Marks the blocks of `day` rolled up before summing them, so a block stored
+    /// concurrently either makes it into this rollup or keeps the day pending.
+    async fn roll_up(&self, day: NaiveDate) -> Result<(), sqlx::Error> {
+        let mut db_tx = self.pool.begin().await?;
+        sqlx::query("UPDATE block_stats SET rolled_up = TRUE WHERE day = $1 AND NOT rolled_up")
+            .bind(day)
+            .execute(&mut *db_tx)
+            .await?;
+        sqlx::query(&format!(
+            "INSERT INTO daily_stats
+               (day, blocks, first_height, last_height, tx_count, output_sats, fees_sats,
+                rated_fees_sats, rated_vsize, active_addresses, updated_at)
+             SELECT s.day, COUNT(*), MIN(s.height), MAX(s.height), SUM(s.tx_count), SUM(s.output_sats),
+                    CASE WHEN COUNT(*) = COUNT(s.fees_sats) THEN SUM(s.fees_sats) END,
+                    SUM(s.rated_fees_sats), SUM(s.rated_vsize),
+                    (SELECT COUNT(DISTINCT address) FROM ({}) a),
+                    NOW()
+             FROM block_stats s
+             WHERE s.day = $1
+             GROUP BY s.day
+             ON CONFLICT (day) DO UPDATE SET
+               blocks = EXCLUDED.blocks,
+               first_height = EXCLUDED.first_height,
+               last_height = EXCLUDED.last_height,
+               tx_count = EXCLUDED.tx_count,
+               output_sats = EXCLUDED.output_sats,
+               fees_sats = EXCLUDED.fees_sats,
+               rated_fees_sats = EXCLUDED.rated_fees_sats,
+               rated_vsize = EXCLUDED.rated_vsize,
+               active_addresses = EXCLUDED.active_addresses,
+               updated_at = EXCLUDED.updated_at",
+            active_addresses_sql("t.block_height IN (SELECT height FROM block_stats WHERE day = $1)")
+        ))
+        .bind(day)
+        .execute(&mut *db_tx)
+        .await?;
+        db_tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+/// Addresses of outputs created and spent by confirmed transactions matching `blocks`,
+/// a condition on `t`; one row per occurrence.
+fn active_addresses_sql(blocks: &str) -> String {
+    format!(
+        "SELECT o.address
+         FROM transactions t
+         JOIN tx_outputs o ON o.txid = t.txid
+         WHERE {blocks} AND t.status = 'confirmed' AND o.address IS NOT NULL
+         UNION ALL
+         SELECT prev.address
+         FROM transactions t
+         JOIN tx_inputs i ON i.txid = t.txid
+         JOIN tx_outputs prev ON prev.txid = i.prev_txid AND prev.vout = i.prev_vout
+         WHERE {blocks} AND t.status = 'confirmed' AND prev.address IS NOT NULL"
+    )
+}
+
+fn parse_day(name: &str, value: Option<&str>) -> Result<Option<NaiveDate>, StatsError> {
+    value
+        .map(|value| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .map_err(|_| StatsError::Validation(format!("{name} MUST be a date in YYYY-MM-DD format")))
+        })
+        .transpose()
+}
+
+#[derive(Debug, FromRow)]
+struct StatsRow {
+    day: NaiveDate,
+    height: Option<i32>,
+    blocks: i32,
+    tx_count: i64,
+    output_sats: i64,
+    fees_sats: Option<i64>,
+    active_addresses: i64,
+    rated_fees_sats: i64,
+    rated_vsize: i64,
+}
+
+impl From<StatsRow> for StatsItem {
+    fn from(row: StatsRow) -> Self {
+        Self {
+            day: row.day,
+            height: row.height,
+            blocks: row.blocks,
+            tx_count: row.tx_count,
+            output_sats: row.output_sats,
+            fees_sats: row.fees_sats,
+            active_addresses: row.active_addresses,
+            avg_fee_rate_sat_vb: (row.rated_vsize > 0).then(|| row.rated_fees_sats as f64 / row.rated_vsize as f64),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::parse_day;
+
+    #[test]
+    fn parses_utc_days() {
+        assert_eq!(parse_day("from", None).expect("no day"), None);
+        assert_eq!(
+            parse_day("from", Some("2024-04-20")).expect("valid day"),
+            NaiveDate::from_ymd_opt(2024, 4, 20)
+        );
+        let err = parse_day("to", Some("2024-04-31")).expect_err("invalid day");
+        assert!(err.to_string().contains("to MUST be a date in YYYY-MM-DD format"));
+        assert!(parse_day("from", Some("1713571200")).is_err());
+    }
+}
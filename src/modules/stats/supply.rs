@@ -0,0 +1,373 @@
+//! Circulating supply and coin age, kept by the indexer in the database transaction that
+//! writes a block: `block_supply` gets a row per canonical block and `utxo_age_days`
+//! holds the unspent outputs by the UTC day they were created.
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use sqlx::{FromRow, PgConnection};
+use utoipa::ToSchema;
+
+use super::{StatsError, StatsService};
+
+const DEFAULT_SUPPLY_BLOCKS: i64 = 10;
+const MAX_SUPPLY_BLOCKS: i64 = 1000;
+const SATS_DAYS_PER_COIN_DAY: f64 = 100_000_000.0 * 86_400.0;
+/// Upper bounds of the age buckets in days, exclusive; the last bucket is open.
+const AGE_BUCKETS: &[(&str, Option<i64>)] = &[
+    ("<1d", Some(1)),
+    ("1d-1w", Some(7)),
+    ("1w-1m", Some(30)),
+    ("1m-6m", Some(182)),
+    ("6m-1y", Some(365)),
+    ("1y-2y", Some(730)),
+    ("2y-5y", Some(1826)),
+    (">5y", None),
+];
+
+/// Supply movement of one canonical block.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+pub struct BlockSupplyItem {
+    pub height: i32,
+    pub hash: String,
+    pub time: i64,
+    /// Value of the outputs created, coinbase included, without `OP_RETURN` outputs.
+    pub created_sats: i64,
+    pub spent_sats: i64,
+    /// Inputs whose spent output is not stored, left out of `spent_sats` and coin-days.
+    pub unknown_inputs: i32,
+    /// Spent value in BTC times the days since it was created.
+    pub coin_days_destroyed: f64,
+    /// Unspent value after the block.
+    pub supply_sats: i64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UtxoAgeBucket {
+    /// `<1d`, `1d-1w`, `1w-1m`, `1m-6m`, `6m-1y`, `1y-2y`, `2y-5y` or `>5y`.
+    pub age: String,
+    pub value_sats: i64,
+    pub outputs: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SupplyResponse {
+    /// Highest block with supply stats; `null` before the first one.
+    pub height: Option<i32>,
+    /// Value of all unspent outputs.
+    pub supply_sats: i64,
+    pub utxo_count: i64,
+    /// Unspent outputs by age relative to the UTC day of `height`, youngest first.
+    pub age_buckets: Vec<UtxoAgeBucket>,
+    /// Latest blocks, newest first.
+    pub blocks: Vec<BlockSupplyItem>,
+}
+
+#[derive(Debug, FromRow)]
+struct AgeDayRow {
+    day: NaiveDate,
+    value_sats: i64,
+    outputs: i64,
+}
+
+impl StatsService {
+    /// Current supply and UTXO ages with the supply stats of the latest `blocks` blocks;
+    /// empty without `0021_supply_stats.sql`.
+    pub async fn supply(&self, blocks: Option<i64>) -> Result<SupplyResponse, StatsError> {
+        let blocks = blocks.unwrap_or(DEFAULT_SUPPLY_BLOCKS);
+        if !(0..=MAX_SUPPLY_BLOCKS).contains(&blocks) {
+            return Err(StatsError::Validation(format!(
+                "blocks MUST be between 0 and {MAX_SUPPLY_BLOCKS}"
+            )));
+        }
+
+        let mut response = SupplyResponse {
+            height: None,
+            supply_sats: 0,
+            utxo_count: 0,
+            age_buckets: Vec::new(),
+            blocks: Vec::new(),
+        };
+        if !self.schema.supply_stats {
+            return Ok(response);
+        }
+
+        let tip: Option<(i32, i64)> = sqlx::query_as("SELECT height, time FROM block_supply ORDER BY height DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await?;
+        let days: Vec<AgeDayRow> = sqlx::query_as("SELECT day, value_sats, outputs FROM utxo_age_days ORDER BY day")
+            .fetch_all(&self.pool)
+            .await?;
+        response.blocks = sqlx::query_as(
+            "SELECT height, hash, time, created_sats, spent_sats, unknown_inputs, coin_days_destroyed, supply_sats
+             FROM block_supply
+             ORDER BY height DESC
+             LIMIT $1",
+        )
+        .bind(blocks)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let tip_day = tip
+            .and_then(|(_, time)| chrono::DateTime::from_timestamp(time, 0))
+            .map(|time| time.date_naive())
+            .or_else(|| days.last().map(|row| row.day));
+        response.height = tip.map(|(height, _)| height);
+        response.age_buckets = AGE_BUCKETS
+            .iter()
+            .map(|(age, _)| UtxoAgeBucket {
+                age: age.to_string(),
+                value_sats: 0,
+                outputs: 0,
+            })
+            .collect();
+        for row in days {
+            response.supply_sats += row.value_sats;
+            response.utxo_count += row.outputs;
+            let age_days = tip_day.map_or(0, |tip_day| (tip_day - row.day).num_days());
+            let bucket = &mut response.age_buckets[age_bucket(age_days)];
+            bucket.value_sats += row.value_sats;
+            bucket.outputs += row.outputs;
+        }
+
+        Ok(response)
+    }
+}
+
+/// Index in [`AGE_BUCKETS`] of outputs `age_days` old; outputs from after the tip day
+/// count as the youngest.
+fn age_bucket(age_days: i64) -> usize {
+    AGE_BUCKETS
+        .iter()
+        .position(|(_, below)| below.is_none_or(|below| age_days < below))
+        .unwrap_or(AGE_BUCKETS.len() - 1)
+}
+
+/// Records the supply stats of a canonical block once its transactions are written, and
+/// moves its created and spent outputs in `utxo_age_days`. A block already recorded with
+/// the same hash, as when it is rewritten, is left alone. A block written below recorded
+/// ones, as by a height range job, starts from the supply of the row below it and then
+/// moves the rows above, which were recorded without it, see [`settle_rows_above`].
+pub(crate) async fn record_block(conn: &mut PgConnection, hash: &str, height: i32, time: i64) -> Result<(), sqlx::Error> {
+    let (recorded, above): (bool, bool) = sqlx::query_as(
+        "SELECT EXISTS (SELECT 1 FROM block_supply WHERE height = $1 AND hash = $2),
+                EXISTS (SELECT 1 FROM block_supply WHERE height > $1)",
+    )
+    .bind(height)
+    .bind(hash)
+    .fetch_one(&mut *conn)
+    .await?;
+    if recorded {
+        return Ok(());
+    }
+
+    // Statements of a query see the tables as they were before it, so the supply after
+    // the block is the one before plus the deltas of this block. Below recorded rows the
+    // ages already count the blocks above, so the supply before is the row below instead.
+    let moved_sats: i64 = sqlx::query_scalar(
+        "WITH created AS (
+           SELECT (to_timestamp($3) AT TIME ZONE 'UTC')::DATE AS day, o.value_sats
+           FROM transactions t
+           JOIN tx_outputs o ON o.txid = t.txid
+           WHERE t.block_height = $2 AND t.block_hash = $1 AND t.status = 'confirmed' AND o.script_type <> 'nulldata'
+         ),
+         inputs AS (
+           SELECT o.value_sats, pt.time
+           FROM transactions t
+           JOIN tx_inputs i ON i.txid = t.txid
+           LEFT JOIN tx_outputs o ON o.txid = i.prev_txid AND o.vout = i.prev_vout
+           LEFT JOIN transactions pt ON pt.txid = i.prev_txid AND pt.status = 'confirmed'
+           WHERE t.block_height = $2 AND t.block_hash = $1 AND t.status = 'confirmed'
+         ),
+         spent AS (
+           SELECT (to_timestamp(time) AT TIME ZONE 'UTC')::DATE AS day, value_sats, time
+           FROM inputs
+           WHERE value_sats IS NOT NULL AND time IS NOT NULL
+         ),
+         deltas AS (
+           SELECT day, value_sats, 1 AS outputs FROM created
+           UNION ALL
+           SELECT day, -value_sats, -1 FROM spent
+         ),
+         ages AS (
+           INSERT INTO utxo_age_days (day, value_sats, outputs)
+           SELECT day, SUM(value_sats), SUM(outputs) FROM deltas GROUP BY day
+           ON CONFLICT (day) DO UPDATE SET
+             value_sats = utxo_age_days.value_sats + EXCLUDED.value_sats,
+             outputs = utxo_age_days.outputs + EXCLUDED.outputs
+         ),
+         totals AS (
+           SELECT (SELECT COALESCE(SUM(value_sats), 0) FROM created)::BIGINT AS created_sats,
+                  (SELECT COALESCE(SUM(value_sats), 0) FROM spent)::BIGINT AS spent_sats,
+                  (SELECT COUNT(*) FROM inputs WHERE value_sats IS NULL OR time IS NULL)::INT AS unknown_inputs,
+                  (SELECT COALESCE(SUM(value_sats::DOUBLE PRECISION * GREATEST($3 - time, 0)), 0) FROM spent) / $4
+                    AS coin_days_destroyed,
+                  CASE WHEN $5
+                    THEN COALESCE((SELECT supply_sats FROM block_supply WHERE height < $2 ORDER BY height DESC LIMIT 1), 0)
+                    ELSE (SELECT COALESCE(SUM(value_sats), 0) FROM utxo_age_days)::BIGINT
+                  END AS supply_before
+         )
+         INSERT INTO block_supply
+           (height, hash, time, created_sats, spent_sats, unknown_inputs, coin_days_destroyed, supply_sats)
+         SELECT $2, $1, $3, created_sats, spent_sats, unknown_inputs, coin_days_destroyed,
+                supply_before + created_sats - spent_sats
+         FROM totals
+         ON CONFLICT (height) DO UPDATE SET
+           hash = EXCLUDED.hash,
+           time = EXCLUDED.time,
+           created_sats = EXCLUDED.created_sats,
+           spent_sats = EXCLUDED.spent_sats,
+           unknown_inputs = EXCLUDED.unknown_inputs,
+           coin_days_destroyed = EXCLUDED.coin_days_destroyed,
+           supply_sats = EXCLUDED.supply_sats
+         RETURNING created_sats - spent_sats",
+    )
+    .bind(hash)
+    .bind(height)
+    .bind(time)
+    .bind(SATS_DAYS_PER_COIN_DAY)
+    .bind(above)
+    .fetch_one(&mut *conn)
+    .await?;
+    if above {
+        settle_rows_above(conn, hash, height, time, moved_sats).await?;
+    }
+
+    Ok(())
+}
+
+/// Moves the `block_supply` rows above a block recorded below them by `moved_sats`, the
+/// value it created less the value it spent. Its outputs spent by those blocks were
+/// unknown inputs to them: they now count as spent there, leave the supply from there
+/// up and leave `utxo_age_days`.
+async fn settle_rows_above(
+    conn: &mut PgConnection,
+    hash: &str,
+    height: i32,
+    time: i64,
+    moved_sats: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "WITH late_spends AS (
+           SELECT st.block_height AS height, st.time, o.value_sats
+           FROM transactions t
+           JOIN tx_outputs o ON o.txid = t.txid
+           JOIN tx_inputs i ON i.prev_txid = o.txid AND i.prev_vout = o.vout
+           JOIN transactions st ON st.txid = i.txid AND st.status = 'confirmed' AND st.block_height > $2
+           WHERE t.block_height = $2 AND t.block_hash = $1 AND t.status = 'confirmed'
+         ),
+         per_block AS (
+           SELECT height,
+                  SUM(value_sats)::BIGINT AS spent_sats,
+                  COUNT(*)::INT AS inputs,
+                  SUM(value_sats::DOUBLE PRECISION * GREATEST(time - $3, 0)) / $4 AS coin_days_destroyed
+           FROM late_spends
+           GROUP BY height
+         ),
+         ages AS (
+           UPDATE utxo_age_days a
+           SET value_sats = a.value_sats - p.spent_sats, outputs = a.outputs - p.inputs
+           FROM (SELECT SUM(spent_sats)::BIGINT AS spent_sats, SUM(inputs)::BIGINT AS inputs FROM per_block) p
+           WHERE a.day = (to_timestamp($3) AT TIME ZONE 'UTC')::DATE AND p.inputs > 0
+         )
+         UPDATE block_supply s
+         SET supply_sats = s.supply_sats + $5
+               - COALESCE((SELECT SUM(p.spent_sats) FROM per_block p WHERE p.height <= s.height), 0),
+             spent_sats = s.spent_sats + COALESCE((SELECT p.spent_sats FROM per_block p WHERE p.height = s.height), 0),
+             unknown_inputs = s.unknown_inputs - COALESCE((SELECT p.inputs FROM per_block p WHERE p.height = s.height), 0),
+             coin_days_destroyed = s.coin_days_destroyed
+               + COALESCE((SELECT p.coin_days_destroyed FROM per_block p WHERE p.height = s.height), 0)
+         WHERE s.height > $2",
+    )
+    .bind(hash)
+    .bind(height)
+    .bind(time)
+    .bind(SATS_DAYS_PER_COIN_DAY)
+    .bind(moved_sats)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Rebuilds `utxo_age_days` from the stored outputs after UTXOs were replayed, as after a
+/// reorg or `rewrite`, and records the canonical blocks without a `block_supply` row, as
+/// ones made before `0021_supply_stats.sql`: the rows from the lowest of them up are
+/// recomputed in height order. Runs under the chain state lock.
+pub(crate) async fn reconcile(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    let unrecorded: Option<i32> = sqlx::query_scalar(
+        "SELECT MIN(b.height)
+         FROM blocks b
+         WHERE b.status = 'canonical'
+           AND NOT EXISTS (SELECT 1 FROM block_supply s WHERE s.height = b.height AND s.hash = b.hash)",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let Some(unrecorded) = unrecorded else {
+        return rebuild_ages_below(conn, i32::MAX).await;
+    };
+
+    forget_from(conn, unrecorded).await?;
+    rebuild_ages_below(conn, unrecorded).await?;
+    let blocks: Vec<(i32, String, i64)> = sqlx::query_as(
+        "SELECT height, hash, time FROM blocks WHERE status = 'canonical' AND height >= $1 ORDER BY height",
+    )
+    .bind(unrecorded)
+    .fetch_all(&mut *conn)
+    .await?;
+    for (height, hash, time) in blocks {
+        record_block(conn, &hash, height, time).await?;
+    }
+
+    Ok(())
+}
+
+/// Recomputes `utxo_age_days` from the stored outputs of canonical blocks below
+/// `below_height` that no transaction of those blocks spends.
+async fn rebuild_ages_below(conn: &mut PgConnection, below_height: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM utxo_age_days").execute(&mut *conn).await?;
+    sqlx::query(
+        "INSERT INTO utxo_age_days (day, value_sats, outputs)
+         SELECT (to_timestamp(t.time) AT TIME ZONE 'UTC')::DATE, SUM(o.value_sats), COUNT(*)
+         FROM tx_outputs o
+         JOIN transactions t ON t.txid = o.txid AND t.status = 'confirmed' AND t.block_height < $1
+         WHERE o.script_type <> 'nulldata'
+           AND NOT EXISTS (
+             SELECT 1
+             FROM tx_inputs i
+             JOIN transactions st ON st.txid = i.txid AND st.status = 'confirmed' AND st.block_height < $1
+             WHERE i.prev_txid = o.txid AND i.prev_vout = o.vout
+           )
+         GROUP BY 1",
+    )
+    .bind(below_height)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Drops the supply stats of orphaned heights, from `from_height` up.
+pub(crate) async fn forget_from(conn: &mut PgConnection, from_height: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM block_supply WHERE height >= $1")
+        .bind(from_height)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{age_bucket, AGE_BUCKETS};
+
+    #[test]
+    fn buckets_utxo_ages() {
+        let label = |age_days| AGE_BUCKETS[age_bucket(age_days)].0;
+        assert_eq!(label(-1), "<1d");
+        assert_eq!(label(0), "<1d");
+        assert_eq!(label(1), "1d-1w");
+        assert_eq!(label(30), "1m-6m");
+        assert_eq!(label(364), "6m-1y");
+        assert_eq!(label(1825), "2y-5y");
+        assert_eq!(label(1826), ">5y");
+    }
+}
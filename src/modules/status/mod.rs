@@ -0,0 +1,351 @@
+use std::fmt;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use utoipa::ToSchema;
+
+use crate::modules::alerting::rules::{AlertStates, AlertStatus};
+use crate::modules::headers;
+use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::storage::{self, StorageError};
+
+/// Upper bound for each probe so a stuck node or pool does not stall status checks.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Lag tolerated as synced: a new tip is picked up on the next runner iteration.
+const SYNCED_LAG_BLOCKS: i64 = 1;
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SyncStatus {
+    /// `ok` when caught up with the node, `syncing` when behind, `degraded`
+    /// when the node is unreachable and `down` when the database is.
+    pub status: String,
+    pub node_tip_height: Option<i64>,
+    pub node_tip_hash: Option<String>,
+    pub indexed_height: Option<i32>,
+    pub indexed_hash: Option<String>,
+    /// Highest header of the header-first phase; `null` when it is disabled or nothing is synced yet.
+    pub header_height: Option<i32>,
+    /// `node_tip_height - indexed_height`, with `header_height` standing in for the tip
+    /// while the node is unreachable; `null` unless both heights are known.
+    pub sync_lag_blocks: Option<i64>,
+    /// Transactions currently tracked as `mempool` by the indexer.
+    pub mempool_tx_count: Option<i64>,
+    pub database_reachable: bool,
+    pub database_error: Option<String>,
+    pub rpc_reachable: bool,
+    pub rpc_error: Option<String>,
+    /// Pending and firing alerts of `alerting.rules`; empty when no rules are configured.
+    pub alerts: Vec<AlertStatus>,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Readiness {
+    /// `ready` when every check passed and the instance is not draining, `starting` while a
+    /// degraded start still waits for storage, otherwise `not_ready`.
+    pub status: String,
+    /// Set during shutdown or maintenance so load balancers stop routing here.
+    pub draining: bool,
+    pub database_reachable: bool,
+    pub database_error: Option<String>,
+    pub rpc_reachable: bool,
+    pub rpc_error: Option<String>,
+    /// `false` while embedded migrations are missing from `_sqlx_migrations`;
+    /// always `true` when the check is disabled in schema compat mode.
+    pub migrations_applied: bool,
+    pub pending_migrations: Vec<i64>,
+    pub migrations_error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl Readiness {
+    /// Reported before bootstrap has storage; no dependency has been checked yet.
+    pub fn starting(draining: bool, database_error: Option<String>) -> Self {
+        Self {
+            status: "starting".to_string(),
+            draining,
+            database_reachable: false,
+            database_error,
+            rpc_reachable: false,
+            rpc_error: None,
+            migrations_applied: false,
+            pending_migrations: Vec::new(),
+            migrations_error: None,
+            checked_at: Utc::now(),
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.status == "ready"
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DrainStatus {
+    pub draining: bool,
+    pub draining_since: Option<DateTime<Utc>>,
+}
+
+/// Drain flag shared by the HTTP server, readiness and the admin API. While it
+/// is set readiness fails and responses carry `Connection: close`.
+#[derive(Debug, Clone, Default)]
+pub struct DrainState {
+    since: Arc<RwLock<Option<DateTime<Utc>>>>,
+}
+
+impl DrainState {
+    /// Keeps the original start time when already draining.
+    pub fn start(&self) -> DrainStatus {
+        let mut since = self.since.write().unwrap_or_else(|err| err.into_inner());
+        since.get_or_insert_with(Utc::now);
+        drain_status(*since)
+    }
+
+    pub fn stop(&self) -> DrainStatus {
+        let mut since = self.since.write().unwrap_or_else(|err| err.into_inner());
+        *since = None;
+        drain_status(None)
+    }
+
+    pub fn status(&self) -> DrainStatus {
+        drain_status(*self.since.read().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.since.read().unwrap_or_else(|err| err.into_inner()).is_some()
+    }
+}
+
+fn drain_status(since: Option<DateTime<Utc>>) -> DrainStatus {
+    DrainStatus {
+        draining: since.is_some(),
+        draining_since: since,
+    }
+}
+
+/// Probes the node and the database to report indexer sync state.
+#[derive(Clone)]
+pub struct StatusService {
+    pool: PgPool,
+    rpc: Option<RpcClient>,
+    check_migrations: bool,
+    drain: DrainState,
+    alerts: AlertStates,
+}
+
+impl fmt::Debug for StatusService {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatusService")
+            .field("rpc", &self.rpc.is_some())
+            .field("check_migrations", &self.check_migrations)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StatusService {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            pool,
+            rpc: None,
+            check_migrations: true,
+            drain: DrainState::default(),
+            alerts: AlertStates::default(),
+        }
+    }
+
+    /// Alert states reported by [`Self::report`], kept by the alert rules runner.
+    pub fn with_alerts(mut self, alerts: AlertStates) -> Self {
+        self.alerts = alerts;
+        self
+    }
+
+    pub fn with_rpc(mut self, rpc: RpcClient) -> Self {
+        self.rpc = Some(rpc);
+        self
+    }
+
+    pub fn drain(&self) -> &DrainState {
+        &self.drain
+    }
+
+    /// Shares a drain flag created before the service, e.g. by the startup router.
+    pub fn with_drain(mut self, drain: DrainState) -> Self {
+        self.drain = drain;
+        self
+    }
+
+    /// Disabled in schema compat mode, where migrations are deferred on purpose.
+    pub fn with_migrations_check(mut self, enabled: bool) -> Self {
+        self.check_migrations = enabled;
+        self
+    }
+
+    /// Never fails: unreachable dependencies are reported in the result.
+    pub async fn report(&self) -> SyncStatus {
+        let (node, database) = tokio::join!(self.node_tip(), self.database_state());
+        let (node_tip, rpc_error) = split_result(node);
+        let (database, database_error) = split_result(database);
+        let indexed = database.as_ref().and_then(|state| state.indexed.as_ref());
+
+        let header_height = database.as_ref().and_then(|state| state.header_height);
+        let sync_lag_blocks = node_tip
+            .as_ref()
+            .map(|(tip_height, _)| *tip_height)
+            .or(header_height.map(i64::from))
+            .zip(indexed)
+            .map(|(tip_height, indexed)| (tip_height - i64::from(indexed.height)).max(0));
+
+        SyncStatus {
+            status: classify(database.is_some(), node_tip.is_some(), sync_lag_blocks).to_string(),
+            node_tip_height: node_tip.as_ref().map(|(height, _)| *height),
+            node_tip_hash: node_tip.as_ref().map(|(_, hash)| hash.clone()),
+            indexed_height: indexed.map(|block| block.height),
+            indexed_hash: indexed.map(|block| block.hash.clone()),
+            header_height,
+            sync_lag_blocks,
+            mempool_tx_count: database.as_ref().map(|state| state.mempool_tx_count),
+            database_reachable: database.is_some(),
+            database_error,
+            rpc_reachable: node_tip.is_some(),
+            rpc_error,
+            alerts: self.alerts.list(),
+            checked_at: Utc::now(),
+        }
+    }
+
+    /// Whether the instance can serve traffic: not draining, database and node
+    /// reachable and the schema migrated. Like `report`, failed checks end up in the result.
+    pub async fn readiness(&self) -> Readiness {
+        let (node, database, migrations) =
+            tokio::join!(self.node_tip(), self.ping_database(), self.pending_migrations());
+        let (_, rpc_error) = split_result(node);
+        let (_, database_error) = split_result(database);
+        let (pending_migrations, migrations_error) = split_result(migrations);
+        let pending_migrations = pending_migrations.unwrap_or_default();
+        let database_reachable = database_error.is_none();
+        let rpc_reachable = rpc_error.is_none();
+        let migrations_applied = migrations_error.is_none() && pending_migrations.is_empty();
+        let draining = self.drain.is_draining();
+        let ready = !draining && database_reachable && rpc_reachable && migrations_applied;
+
+        Readiness {
+            status: if ready { "ready" } else { "not_ready" }.to_string(),
+            draining,
+            database_reachable,
+            database_error,
+            rpc_reachable,
+            rpc_error,
+            migrations_applied,
+            pending_migrations,
+            migrations_error,
+            checked_at: Utc::now(),
+        }
+    }
+
+    async fn node_tip(&self) -> Result<(i64, String), RpcError> {
+        let Some(rpc) = &self.rpc else {
+            return Err(RpcError::Rpc("rpc client is not configured".to_string()));
+        };
+
+        tokio::time::timeout(PROBE_TIMEOUT, async {
+            let tip_height = rpc.get_block_count().await?;
+            let height = u32::try_from(tip_height)
+                .map_err(|_| RpcError::Rpc("tip height exceeds u32 range".to_string()))?;
+            let tip_hash = rpc.get_block_hash(height).await?;
+            Ok((i64::from(height), tip_hash))
+        })
+        .await
+        .unwrap_or_else(|_| Err(RpcError::Rpc("node status request timed out".to_string())))
+    }
+
+    async fn database_state(&self) -> Result<DatabaseState, sqlx::Error> {
+        tokio::time::timeout(PROBE_TIMEOUT, self.query_database_state())
+            .await
+            .unwrap_or(Err(sqlx::Error::PoolTimedOut))
+    }
+
+    async fn ping_database(&self) -> Result<(), sqlx::Error> {
+        tokio::time::timeout(PROBE_TIMEOUT, sqlx::query("SELECT 1").execute(&self.pool))
+            .await
+            .unwrap_or(Err(sqlx::Error::PoolTimedOut))
+            .map(|_| ())
+    }
+
+    async fn pending_migrations(&self) -> Result<Vec<i64>, StorageError> {
+        if !self.check_migrations {
+            return Ok(Vec::new());
+        }
+
+        tokio::time::timeout(PROBE_TIMEOUT, storage::pending_migrations(&self.pool))
+            .await
+            .unwrap_or(Err(StorageError::Connection(sqlx::Error::PoolTimedOut)))
+    }
+
+    async fn query_database_state(&self) -> Result<DatabaseState, sqlx::Error> {
+        let indexed: Option<IndexedTipRow> = sqlx::query_as(
+            "SELECT height, hash
+             FROM blocks
+             WHERE status = 'canonical'
+             ORDER BY height DESC
+             LIMIT 1",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        let mempool_tx_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE status = 'mempool'")
+            .fetch_one(&self.pool)
+            .await?;
+        let header_height = headers::header_tip(&self.pool).await?.map(|(height, _)| height);
+
+        Ok(DatabaseState {
+            indexed,
+            header_height,
+            mempool_tx_count,
+        })
+    }
+}
+
+fn classify(database_reachable: bool, rpc_reachable: bool, sync_lag_blocks: Option<i64>) -> &'static str {
+    match (database_reachable, rpc_reachable) {
+        (false, _) => "down",
+        (true, false) => "degraded",
+        _ if sync_lag_blocks.is_some_and(|lag| lag <= SYNCED_LAG_BLOCKS) => "ok",
+        _ => "syncing",
+    }
+}
+
+fn split_result<T, E: ToString>(result: Result<T, E>) -> (Option<T>, Option<String>) {
+    match result {
+        Ok(value) => (Some(value), None),
+        Err(err) => (None, Some(err.to_string())),
+    }
+}
+
+struct DatabaseState {
+    indexed: Option<IndexedTipRow>,
+    header_height: Option<i32>,
+    mempool_tx_count: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct IndexedTipRow {
+    height: i32,
+    hash: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::classify;
+
+    #[test]
+    fn classifies_sync_status() {
+        assert_eq!(classify(true, true, Some(0)), "ok");
+        assert_eq!(classify(true, true, Some(1)), "ok");
+        assert_eq!(classify(true, true, Some(5)), "syncing");
+        assert_eq!(classify(true, true, None), "syncing");
+        assert_eq!(classify(true, false, Some(0)), "degraded");
+        assert_eq!(classify(false, true, None), "down");
+    }
+}
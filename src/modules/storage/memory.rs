@@ -0,0 +1,424 @@
+//! In-memory [`ChainStore`] and [`JobStore`] for unit tests of the indexer pipeline and
+//! of jobs without PostgreSQL.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use chrono::Utc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+use crate::modules::jobs::store::{
+    CheckpointColumns, JobErrorRecord, JobEventRecord, JobRecord, JobStatusUpdate, JobStore, JobStoreTx, LastError, NewJobRecord,
+};
+
+use crate::modules::storage::repo::{
+    reduce_decoded, BlockRecord, PrevoutRecord, TransactionRecord, TxInputRecord, TxOutputRecord, UtxoCreateRecord,
+};
+use crate::modules::storage::store::{ChainStore, ChainStoreTx};
+use crate::modules::storage::SchemaFeatures;
+
+/// Rows of the chain and job tables, keyed like their primary keys.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTables {
+    /// By hash.
+    pub blocks: BTreeMap<String, MemoryBlock>,
+    /// By txid.
+    pub transactions: BTreeMap<String, MemoryTransaction>,
+    pub tx_inputs: BTreeMap<(String, i32), TxInputRecord>,
+    pub tx_outputs: BTreeMap<(String, i32), TxOutputRecord>,
+    pub utxos: BTreeMap<(String, i32), MemoryUtxo>,
+    pub balances: BTreeMap<String, i64>,
+    /// `(time, balance_sats)` by address and block height.
+    pub balance_history: BTreeMap<(String, i32), (i64, i64)>,
+    /// `(checkpoint_height, checkpoint_hash)` by job id.
+    pub checkpoints: HashMap<String, (i32, String)>,
+    /// By job id.
+    pub jobs: BTreeMap<String, MemoryJob>,
+    /// `(job_id, address)`.
+    pub job_addresses: BTreeSet<(String, String)>,
+    pub job_events: Vec<JobEventRecord>,
+    pub job_errors: Vec<JobErrorRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryBlock {
+    pub record: BlockRecord,
+    pub total_fees_sats: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryTransaction {
+    pub record: TransactionRecord,
+    pub fee_sats: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryUtxo {
+    pub record: UtxoCreateRecord,
+    pub spent_in_txid: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryJob {
+    pub record: JobRecord,
+    pub retry_count: i32,
+}
+
+impl MemoryTables {
+    fn canonical_block_hash_at_height(&self, height: i32) -> Option<String> {
+        self.blocks
+            .values()
+            .find(|block| block.record.height == height && block.record.status == "canonical")
+            .map(|block| block.record.hash.clone())
+    }
+}
+
+/// Chain tables in memory. A write transaction works on a copy of the tables and holds
+/// them until it ends, which serializes writers like the chain state lock of PostgreSQL;
+/// dropping it without commit discards the copy.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    tables: Arc<Mutex<MemoryTables>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Features of the tables: transaction fees, job height ranges, errors, events and
+    /// checkpoints, and spent outputs on inputs on top of [`SchemaFeatures::baseline`].
+    pub fn schema_features() -> SchemaFeatures {
+        SchemaFeatures {
+            transaction_fees: true,
+            jobs_height_range: true,
+            job_errors: true,
+            job_events: true,
+            job_checkpoints: true,
+            input_prevouts: true,
+            ..SchemaFeatures::baseline()
+        }
+    }
+
+    /// Copy of the committed tables.
+    pub async fn tables(&self) -> MemoryTables {
+        self.tables.lock().await.clone()
+    }
+}
+
+pub struct MemoryTx {
+    committed: OwnedMutexGuard<MemoryTables>,
+    pending: MemoryTables,
+    schema: SchemaFeatures,
+}
+
+impl ChainStore for MemoryStorage {
+    type Tx = MemoryTx;
+
+    async fn begin_write(&self, schema: SchemaFeatures) -> Result<MemoryTx, sqlx::Error> {
+        let committed = self.tables.clone().lock_owned().await;
+        Ok(MemoryTx {
+            pending: committed.clone(),
+            committed,
+            schema,
+        })
+    }
+
+    async fn canonical_block_hash_at_height(&self, height: i32) -> Result<Option<String>, sqlx::Error> {
+        Ok(self.tables.lock().await.canonical_block_hash_at_height(height))
+    }
+
+    async fn stored_outpoints(&self, outpoints: &[(String, i32)]) -> Result<Vec<(String, i32)>, sqlx::Error> {
+        let tables = self.tables.lock().await;
+        Ok(outpoints
+            .iter()
+            .filter(|outpoint| tables.tx_outputs.contains_key(*outpoint))
+            .cloned()
+            .collect())
+    }
+}
+
+impl ChainStoreTx for MemoryTx {
+    async fn commit(mut self) -> Result<(), sqlx::Error> {
+        *self.committed = self.pending;
+        Ok(())
+    }
+
+    /// The transaction already holds the tables.
+    async fn lock_chain_state(&mut self) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
+
+    async fn lock_height(&mut self, _height: i32) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
+
+    async fn canonical_block_hash_at_height(&mut self, height: i32) -> Result<Option<String>, sqlx::Error> {
+        Ok(self.pending.canonical_block_hash_at_height(height))
+    }
+
+    async fn upsert_block(&mut self, block: &BlockRecord) -> Result<(), sqlx::Error> {
+        let total_fees_sats = self
+            .pending
+            .blocks
+            .get(&block.hash)
+            .and_then(|stored| stored.total_fees_sats);
+        self.pending.blocks.insert(block.hash.clone(), MemoryBlock {
+            record: block.clone(),
+            total_fees_sats,
+        });
+        Ok(())
+    }
+
+    async fn set_block_total_fees(&mut self, hash: &str, total_fees_sats: Option<i64>) -> Result<(), sqlx::Error> {
+        if let Some(block) = self.pending.blocks.get_mut(hash) {
+            block.total_fees_sats = total_fees_sats;
+        }
+        Ok(())
+    }
+
+    async fn upsert_transaction(&mut self, tx: &TransactionRecord, store_decoded: bool) -> Result<(), sqlx::Error> {
+        let mut record = tx.clone();
+        if !store_decoded {
+            record.decoded = reduce_decoded(&tx.decoded);
+        }
+        let fee_sats = self.pending.transactions.get(&tx.txid).and_then(|stored| stored.fee_sats);
+        self.pending
+            .transactions
+            .insert(tx.txid.clone(), MemoryTransaction { record, fee_sats });
+        Ok(())
+    }
+
+    async fn set_transaction_fee(&mut self, txid: &str, fee_sats: Option<i64>) -> Result<(), sqlx::Error> {
+        if let Some(tx) = self.pending.transactions.get_mut(txid) {
+            tx.fee_sats = fee_sats;
+        }
+        Ok(())
+    }
+
+    async fn insert_input(&mut self, input: &TxInputRecord) -> Result<(), sqlx::Error> {
+        let mut record = input.clone();
+        record.prevout = record.prevout.filter(|_| self.schema.input_prevouts);
+        let stored = self
+            .pending
+            .tx_inputs
+            .entry((input.txid.clone(), input.vin))
+            .or_insert_with(|| record.clone());
+        stored.prevout = stored.prevout.take().or(record.prevout);
+        Ok(())
+    }
+
+    async fn insert_output(&mut self, output: &TxOutputRecord) -> Result<(), sqlx::Error> {
+        self.pending
+            .tx_outputs
+            .entry((output.txid.clone(), output.vout))
+            .or_insert_with(|| output.clone());
+        Ok(())
+    }
+
+    async fn stored_output(&mut self, txid: &str, vout: i32) -> Result<Option<PrevoutRecord>, sqlx::Error> {
+        Ok(self
+            .pending
+            .tx_outputs
+            .get(&(txid.to_string(), vout))
+            .map(|output| PrevoutRecord {
+                value_sats: output.value_sats,
+                script_type: output.script_type.clone(),
+                address: output.address.clone(),
+            }))
+    }
+
+    async fn insert_unspent(&mut self, utxo: &UtxoCreateRecord) -> Result<bool, sqlx::Error> {
+        let key = (utxo.out_txid.clone(), utxo.out_vout);
+        if self.pending.utxos.contains_key(&key) {
+            return Ok(false);
+        }
+
+        self.pending.utxos.insert(key, MemoryUtxo {
+            record: utxo.clone(),
+            spent_in_txid: None,
+        });
+        Ok(true)
+    }
+
+    async fn mark_spent(&mut self, out_txid: &str, out_vout: i32, spent_in_txid: &str) -> Result<bool, sqlx::Error> {
+        match self.pending.utxos.get_mut(&(out_txid.to_string(), out_vout)) {
+            Some(utxo) if utxo.spent_in_txid.is_none() => {
+                utxo.spent_in_txid = Some(spent_in_txid.to_string());
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn add_balance_delta(&mut self, address: &str, delta_sats: i64) -> Result<(), sqlx::Error> {
+        *self.pending.balances.entry(address.to_string()).or_insert(0) += delta_sats;
+        Ok(())
+    }
+
+    async fn current_balance(&mut self, address: &str) -> Result<Option<i64>, sqlx::Error> {
+        Ok(self.pending.balances.get(address).copied())
+    }
+
+    async fn upsert_balance_snapshot(
+        &mut self,
+        address: &str,
+        block_height: i32,
+        time: i64,
+        balance_sats: i64,
+    ) -> Result<(), sqlx::Error> {
+        self.pending
+            .balance_history
+            .insert((address.to_string(), block_height), (time, balance_sats));
+        Ok(())
+    }
+
+    /// Like `JobCheckpointsRepo::save`, a checkpoint above `height` is kept.
+    async fn save_checkpoint(&mut self, job_id: &str, height: i32, hash: &str) -> Result<(), sqlx::Error> {
+        let checkpoint = self
+            .pending
+            .checkpoints
+            .entry(job_id.to_string())
+            .or_insert_with(|| (height, hash.to_string()));
+        if checkpoint.0 <= height {
+            *checkpoint = (height, hash.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl JobStore for MemoryStorage {
+    type Tx = MemoryTx;
+
+    async fn begin_jobs(&self, schema: SchemaFeatures) -> Result<MemoryTx, sqlx::Error> {
+        self.begin_write(schema).await
+    }
+
+    async fn job(&self, _schema: SchemaFeatures, job_id: &str) -> Result<Option<JobRecord>, sqlx::Error> {
+        Ok(self.tables.lock().await.jobs.get(job_id).map(|job| job.record.clone()))
+    }
+
+    async fn job_checkpoint(&self, job_id: &str) -> Result<Option<CheckpointColumns>, sqlx::Error> {
+        let tables = self.tables.lock().await;
+        if !tables.jobs.contains_key(job_id) {
+            return Ok(None);
+        }
+
+        let checkpoint = tables.checkpoints.get(job_id).cloned();
+        Ok(Some(checkpoint.map_or((None, None), |(height, hash)| (Some(height), Some(hash)))))
+    }
+
+    async fn update_job_progress(&self, schema: SchemaFeatures, job_id: &str, height: i32) -> Result<(), sqlx::Error> {
+        if let Some(job) = self.tables.lock().await.jobs.get_mut(job_id) {
+            job.record.progress_height = job.record.progress_height.max(height);
+            job.record.updated_at = Some(Utc::now());
+            job.record.last_error = None;
+            if schema.job_errors {
+                job.retry_count = 0;
+            }
+        }
+        Ok(())
+    }
+
+    async fn rewind_job_progress(
+        &self,
+        schema: SchemaFeatures,
+        job_id: Option<&str>,
+        height: i32,
+    ) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables.lock().await;
+        let canonical = tables.canonical_block_hash_at_height(height);
+        let MemoryTables { jobs, checkpoints, .. } = &mut *tables;
+        for job in jobs.values_mut().filter(|job| job_id.is_none_or(|id| id == job.record.job_id)) {
+            job.record.progress_height = job.record.progress_height.min(height);
+            job.record.updated_at = Some(Utc::now());
+            if !schema.job_checkpoints {
+                continue;
+            }
+            if checkpoints.get(&job.record.job_id).is_some_and(|(checkpoint, _)| *checkpoint >= height) {
+                match &canonical {
+                    Some(hash) => checkpoints.insert(job.record.job_id.clone(), (height, hash.clone())),
+                    None => checkpoints.remove(&job.record.job_id),
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl JobStoreTx for MemoryTx {
+    async fn commit(mut self) -> Result<(), sqlx::Error> {
+        *self.committed = self.pending;
+        Ok(())
+    }
+
+    async fn insert_job(&mut self, job: &NewJobRecord<'_>) -> Result<bool, sqlx::Error> {
+        if self.pending.jobs.contains_key(job.job_id) {
+            return Ok(false);
+        }
+
+        let height_range = self.schema.jobs_height_range;
+        let record = JobRecord {
+            job_id: job.job_id.to_string(),
+            mode: job.mode.to_string(),
+            status: "created".to_string(),
+            progress_height: 0,
+            from_height: job.from_height.filter(|_| height_range),
+            to_height: job.to_height.filter(|_| height_range),
+            tip_height: None,
+            blocks_per_sec: None,
+            updated_at: Some(Utc::now()),
+            last_error: None,
+            config_snapshot: job.config_snapshot.clone(),
+            gap_heights: None,
+        };
+        self.pending.jobs.insert(job.job_id.to_string(), MemoryJob { record, retry_count: 0 });
+        for address in job.addresses {
+            self.pending.job_addresses.insert((job.job_id.to_string(), address.clone()));
+        }
+        Ok(true)
+    }
+
+    /// The transaction already holds the tables.
+    async fn lock_job_status(&mut self, job_id: &str) -> Result<Option<String>, sqlx::Error> {
+        Ok(self.pending.jobs.get(job_id).map(|job| job.record.status.clone()))
+    }
+
+    async fn set_job_status(&mut self, update: &JobStatusUpdate<'_>) -> Result<(), sqlx::Error> {
+        if let Some(job) = self.pending.jobs.get_mut(update.job_id) {
+            job.record.status = update.status.to_string();
+            job.record.updated_at = Some(Utc::now());
+            match update.last_error {
+                LastError::Keep => {}
+                LastError::Clear => job.record.last_error = None,
+                LastError::Set(message) => job.record.last_error = Some(message.to_string()),
+            }
+            if update.count_retry {
+                job.retry_count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like the `ON DELETE CASCADE` of the job tables.
+    async fn delete_job(&mut self, job_id: &str) -> Result<(), sqlx::Error> {
+        self.pending.jobs.remove(job_id);
+        self.pending.checkpoints.remove(job_id);
+        self.pending.job_addresses.retain(|(id, _)| id != job_id);
+        self.pending.job_events.retain(|event| event.job_id != job_id);
+        self.pending.job_errors.retain(|error| error.job_id != job_id);
+        Ok(())
+    }
+
+    async fn insert_job_event(&mut self, event: &JobEventRecord) -> Result<(), sqlx::Error> {
+        self.pending.job_events.push(event.clone());
+        Ok(())
+    }
+
+    async fn insert_job_error(&mut self, error: &JobErrorRecord) -> Result<(), sqlx::Error> {
+        if self.pending.jobs.contains_key(&error.job_id) {
+            self.pending.job_errors.push(error.clone());
+        }
+        Ok(())
+    }
+}
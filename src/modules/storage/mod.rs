@@ -1,112 +1,599 @@
 use std::env;
-use std::fs;
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
+use sqlx::migrate::{MigrateError, Migrator};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 use sqlx::{PgPool, Pool, Postgres};
 use thiserror::Error;
+use tracing::info;
 
-const DEFAULT_MIGRATIONS_PATH: &str = "migrations";
+use crate::modules::config::DatabaseConfig;
 
+pub mod memory;
 pub mod repo;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
 
 #[derive(Debug, Error)]
 pub enum StorageError {
-    #[error("DATABASE_URL is not set")]
-    MissingDatabaseUrl,
+    #[error("{0} is not set")]
+    MissingDatabaseUrl(String),
+    #[error("SQLite databases are only supported by `backfill`; this command needs PostgreSQL")]
+    SqliteUnsupported,
     #[error("failed to connect to database: {0}")]
     Connection(#[from] sqlx::Error),
     #[error("failed to read migrations from '{path}': {source}")]
     MigrationsRead {
         path: String,
         #[source]
-        source: std::io::Error,
+        source: MigrateError,
     },
     #[error("failed to apply migrations: {0}")]
-    Migration(sqlx::Error),
+    Migration(#[from] MigrateError),
 }
 
 #[derive(Clone)]
 pub struct Storage {
     pool: PgPool,
+    /// Read-only pool of `database.replica_url` for API queries.
+    replica: Option<PgPool>,
+    compat_mode: bool,
+}
+
+/// Schema capabilities added by migrations that the binary can run without.
+///
+/// With `SCHEMA_COMPAT_MODE` enabled migrations are deferred and features are
+/// detected from the live schema, so read paths can fall back for missing columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaFeatures {
+    /// `jobs.from_height` / `jobs.to_height` from `0004_jobs_height_range.sql`.
+    pub jobs_height_range: bool,
+    /// `jobs.tip_height` / `jobs.blocks_per_sec` from `0005_jobs_progress_rate.sql`.
+    pub jobs_progress_rate: bool,
+    /// `process_events` table from `0006_process_events.sql`.
+    pub process_events: bool,
+    /// `job_errors` table and `jobs.retry_count` from `0007_job_errors.sql`.
+    pub job_errors: bool,
+    /// `block_templates` table from `0008_block_templates.sql`.
+    pub block_templates: bool,
+    /// `tx_events` table from `0010_tx_events.sql`.
+    pub tx_events: bool,
+    /// `transactions.fee_sats` / `blocks.total_fees_sats` from `0011_transaction_fees.sql`.
+    pub transaction_fees: bool,
+    /// `job_derived_addresses` table and the `descriptor` job mode from `0012_job_derived_addresses.sql`.
+    pub job_derived_addresses: bool,
+    /// `event_outbox` table from `0013_event_outbox.sql`.
+    pub event_outbox: bool,
+    /// `job_exports` table and the `export` job mode from `0014_job_exports.sql`.
+    pub job_exports: bool,
+    /// `jobs.checkpoint_height` / `jobs.checkpoint_hash` from `0015_job_checkpoints.sql`.
+    pub job_checkpoints: bool,
+    /// `block_headers` table from `0016_block_headers.sql`.
+    pub block_headers: bool,
+    /// `double_spends` table and the `double_spends` outbox stream from `0017_double_spends.sql`.
+    pub double_spends: bool,
+    /// `tx_replacements` table from `0018_tx_replacements.sql`.
+    pub tx_replacements: bool,
+    /// `block_stats` / `daily_stats` tables from `0020_chain_stats.sql`.
+    pub chain_stats: bool,
+    /// `block_supply` / `utxo_age_days` from `0021_supply_stats.sql`.
+    pub supply_stats: bool,
+    /// `blocks` / `transactions` / `tx_outputs` range-partitioned by height by `0022_chain_partitions.sql`.
+    /// The migration only converts an empty database, so this is detected on every start.
+    pub chain_partitions: bool,
+    /// `pruning_state` table from `0023_retention.sql`.
+    pub retention: bool,
+    /// `block_raw` table from `0024_block_raw.sql`.
+    pub block_raw: bool,
+    /// `block_filters` table from `0025_block_filters.sql`.
+    pub block_filters: bool,
+    /// `job_events` / `idempotency_keys` tables from `0026_job_events.sql`.
+    pub job_events: bool,
+    /// `stream_events` table from `0027_stream_events.sql`.
+    pub stream_events: bool,
+    /// `broadcasts` table from `0028_broadcasts.sql`.
+    pub broadcasts: bool,
+    /// `panic` category of `job_errors` from `0029_job_error_panics.sql`.
+    pub job_error_panics: bool,
+    /// `block_write_progress` table from `0030_block_write_progress.sql`.
+    pub block_write_progress: bool,
+    /// `job_discrepancies` table and the `verify` job mode from `0031_job_verify.sql`.
+    pub job_discrepancies: bool,
+    /// `job_gaps` table from `0032_job_gaps.sql`.
+    pub job_gaps: bool,
+    /// `tx_inputs.prev_value_sats` / `prev_script_type` / `prev_address` from `0033_input_prevouts.sql`.
+    pub input_prevouts: bool,
+    /// `transactions.vsize` / `weight` / `version` / `locktime` and `tx_inputs.witness`
+    /// from `0034_transaction_details.sql`.
+    pub transaction_details: bool,
+    /// `runes` / `rune_mints` / `rune_outputs` from `0035_runes.sql`.
+    pub runes: bool,
+    /// `job_addresses.source` from `0036_job_address_sources.sql`.
+    pub job_address_sources: bool,
+    /// `incomplete` status of `transactions` from `0037_incomplete_transactions.sql`.
+    pub incomplete_transactions: bool,
+}
+
+impl SchemaFeatures {
+    /// Schema of `0001_init.sql` to `0003_nodes_registry.sql` with none of the optional
+    /// features; stores other than PostgreSQL report theirs on top of it.
+    pub fn baseline() -> Self {
+        Self {
+            jobs_height_range: false,
+            jobs_progress_rate: false,
+            process_events: false,
+            job_errors: false,
+            block_templates: false,
+            tx_events: false,
+            transaction_fees: false,
+            job_derived_addresses: false,
+            event_outbox: false,
+            job_exports: false,
+            job_checkpoints: false,
+            block_headers: false,
+            double_spends: false,
+            tx_replacements: false,
+            chain_stats: false,
+            supply_stats: false,
+            chain_partitions: false,
+            retention: false,
+            block_raw: false,
+            block_filters: false,
+            job_events: false,
+            stream_events: false,
+            broadcasts: false,
+            job_error_panics: false,
+            block_write_progress: false,
+            job_discrepancies: false,
+            job_gaps: false,
+            input_prevouts: false,
+            transaction_details: false,
+            runes: false,
+            job_address_sources: false,
+            incomplete_transactions: false,
+        }
+    }
+
+    pub fn latest() -> Self {
+        Self {
+            jobs_height_range: true,
+            jobs_progress_rate: true,
+            process_events: true,
+            job_errors: true,
+            block_templates: true,
+            tx_events: true,
+            transaction_fees: true,
+            job_derived_addresses: true,
+            event_outbox: true,
+            job_exports: true,
+            job_checkpoints: true,
+            block_headers: true,
+            double_spends: true,
+            tx_replacements: true,
+            chain_stats: true,
+            supply_stats: true,
+            chain_partitions: true,
+            retention: true,
+            block_raw: true,
+            block_filters: true,
+            job_events: true,
+            stream_events: true,
+            broadcasts: true,
+            job_error_panics: true,
+            block_write_progress: true,
+            job_discrepancies: true,
+            job_gaps: true,
+            input_prevouts: true,
+            transaction_details: true,
+            runes: true,
+            job_address_sources: true,
+            incomplete_transactions: true,
+        }
+    }
+}
+
+impl Default for SchemaFeatures {
+    fn default() -> Self {
+        Self::latest()
+    }
 }
 
 impl Storage {
-    pub async fn connect() -> Result<Self, StorageError> {
-        let database_url = env::var("DATABASE_URL").map_err(|_| StorageError::MissingDatabaseUrl)?;
-        let pool = PgPool::connect(&database_url).await?;
-        Ok(Self { pool })
+    /// Connects with the URL and pool settings of the `database` config section, and to the
+    /// read replica when one is configured.
+    /// The serving path (API, jobs, runners) writes through [`PgPool`] only, so a SQLite URL
+    /// is rejected here instead of failing later as an unparsable PostgreSQL URL.
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, StorageError> {
+        let database_url = database_url(config)?;
+        if is_sqlite_url(&database_url) {
+            return Err(StorageError::SqliteUnsupported);
+        }
+        let pool_options = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .min_connections(config.min_connections)
+            .acquire_timeout(Duration::from_millis(config.acquire_timeout_ms))
+            .idle_timeout(Duration::from_millis(config.idle_timeout_ms));
+        let pool = pool_options
+            .clone()
+            .connect_with(connect_options(&database_url, config)?)
+            .await?;
+
+        let replica_url = match (&config.replica_url, &config.replica_url_env) {
+            (Some(url), _) => Some(url.to_string()),
+            (None, Some(env_name)) => {
+                Some(env::var(env_name).map_err(|_| StorageError::MissingDatabaseUrl(env_name.clone()))?)
+            }
+            (None, None) => None,
+        };
+        let replica = match replica_url {
+            Some(url) => {
+                // Queries routed to the replica never write, even when it points at a primary.
+                let options = connect_options(&url, config)?.options([("default_transaction_read_only", "on")]);
+                Some(pool_options.connect_with(options).await?)
+            }
+            None => None,
+        };
+
+        let compat_mode = env::var("SCHEMA_COMPAT_MODE")
+            .map(|value| parse_flag(&value))
+            .unwrap_or(false);
+        Ok(Self {
+            pool,
+            replica,
+            compat_mode,
+        })
+    }
+
+    /// Storage of an additional instance: the same database with `search_path` set to
+    /// `schema` and the same pool settings, so queries and migrations use the tables of that schema. The schema
+    /// is created when missing; `schema` MUST be a plain identifier, which config
+    /// validation guarantees.
+    pub async fn with_schema(&self, schema: &str) -> Result<Self, StorageError> {
+        sqlx::query(&format!("CREATE SCHEMA IF NOT EXISTS {schema}"))
+            .execute(&self.pool)
+            .await?;
+        let pool = schema_pool(&self.pool, schema).await?;
+        let replica = match &self.replica {
+            Some(replica) => Some(schema_pool(replica, schema).await?),
+            None => None,
+        };
+        Ok(Self {
+            pool,
+            replica,
+            compat_mode: self.compat_mode,
+        })
+    }
+
+    pub fn compat_mode(&self) -> bool {
+        self.compat_mode
+    }
+
+    /// Applies migrations, or in compat mode leaves the schema untouched and
+    /// reports which optional features it already has.
+    pub async fn prepare_schema(&self) -> Result<SchemaFeatures, StorageError> {
+        if !self.compat_mode {
+            self.apply_migrations().await?;
+            let chain_partitions = chain_partitioned(&self.pool).await?;
+            info!(component = "storage", chain_partitions, message = "chain table layout detected");
+            return Ok(SchemaFeatures {
+                chain_partitions,
+                ..SchemaFeatures::latest()
+            });
+        }
+
+        let features = self.detect_schema_features().await?;
+        info!(
+            component = "storage",
+            jobs_height_range = features.jobs_height_range,
+            jobs_progress_rate = features.jobs_progress_rate,
+            process_events = features.process_events,
+            job_errors = features.job_errors,
+            block_templates = features.block_templates,
+            tx_events = features.tx_events,
+            transaction_fees = features.transaction_fees,
+            job_derived_addresses = features.job_derived_addresses,
+            event_outbox = features.event_outbox,
+            job_exports = features.job_exports,
+            job_checkpoints = features.job_checkpoints,
+            block_headers = features.block_headers,
+            double_spends = features.double_spends,
+            tx_replacements = features.tx_replacements,
+            chain_stats = features.chain_stats,
+            supply_stats = features.supply_stats,
+            chain_partitions = features.chain_partitions,
+            retention = features.retention,
+            block_raw = features.block_raw,
+            block_filters = features.block_filters,
+            job_events = features.job_events,
+            stream_events = features.stream_events,
+            broadcasts = features.broadcasts,
+            job_error_panics = features.job_error_panics,
+            block_write_progress = features.block_write_progress,
+            job_discrepancies = features.job_discrepancies,
+            job_gaps = features.job_gaps,
+            input_prevouts = features.input_prevouts,
+            transaction_details = features.transaction_details,
+            runes = features.runes,
+            job_address_sources = features.job_address_sources,
+            incomplete_transactions = features.incomplete_transactions,
+            message = "schema compat mode enabled, migrations deferred"
+        );
+        Ok(features)
+    }
+
+    pub async fn detect_schema_features(&self) -> Result<SchemaFeatures, StorageError> {
+        let jobs_height_range = column_exists(&self.pool, "jobs", "from_height").await?
+            && column_exists(&self.pool, "jobs", "to_height").await?;
+        let jobs_progress_rate = column_exists(&self.pool, "jobs", "tip_height").await?
+            && column_exists(&self.pool, "jobs", "blocks_per_sec").await?;
+        let process_events = column_exists(&self.pool, "process_events", "last_seen_at").await?;
+        let job_errors = column_exists(&self.pool, "job_errors", "category").await?
+            && column_exists(&self.pool, "jobs", "retry_count").await?;
+        let block_templates = column_exists(&self.pool, "block_templates", "total_fees_sats").await?;
+        let tx_events = column_exists(&self.pool, "tx_events", "level").await?;
+        let transaction_fees = column_exists(&self.pool, "transactions", "fee_sats").await?
+            && column_exists(&self.pool, "blocks", "total_fees_sats").await?;
+        let job_derived_addresses = column_exists(&self.pool, "job_derived_addresses", "used").await?;
+        let event_outbox = column_exists(&self.pool, "event_outbox", "published_at").await?;
+        let job_exports = column_exists(&self.pool, "job_exports", "location").await?;
+        let job_checkpoints = column_exists(&self.pool, "jobs", "checkpoint_hash").await?;
+        let block_headers = column_exists(&self.pool, "block_headers", "prev_hash").await?;
+        let double_spends = column_exists(&self.pool, "double_spends", "conflicting_txid").await?;
+        let tx_replacements = column_exists(&self.pool, "tx_replacements", "replacement_txid").await?;
+        let chain_stats = column_exists(&self.pool, "block_stats", "rolled_up").await?
+            && column_exists(&self.pool, "daily_stats", "active_addresses").await?;
+        let supply_stats = column_exists(&self.pool, "block_supply", "coin_days_destroyed").await?
+            && column_exists(&self.pool, "utxo_age_days", "outputs").await?;
+        let chain_partitions = chain_partitioned(&self.pool).await?;
+        let retention = column_exists(&self.pool, "pruning_state", "pruned_height").await?;
+        let block_raw = column_exists(&self.pool, "block_raw", "raw").await?;
+        let block_filters = column_exists(&self.pool, "block_filters", "complete").await?;
+        let job_events = column_exists(&self.pool, "job_events", "reason").await?
+            && column_exists(&self.pool, "idempotency_keys", "status").await?;
+        let stream_events = column_exists(&self.pool, "stream_events", "kind").await?;
+        let broadcasts = column_exists(&self.pool, "broadcasts", "status").await?;
+        let job_error_panics = job_errors && job_errors_accept_panics(&self.pool).await?;
+        let block_write_progress = column_exists(&self.pool, "block_write_progress", "next_tx").await?;
+        let job_discrepancies = column_exists(&self.pool, "job_discrepancies", "detail").await?;
+        let job_gaps = column_exists(&self.pool, "job_gaps", "detected_at").await?;
+        let input_prevouts = column_exists(&self.pool, "tx_inputs", "prev_address").await?;
+        let transaction_details = column_exists(&self.pool, "transactions", "weight").await?
+            && column_exists(&self.pool, "tx_inputs", "witness").await?;
+        let runes = column_exists(&self.pool, "rune_outputs", "spent_height").await?;
+        let job_address_sources = column_exists(&self.pool, "job_addresses", "source").await?;
+        let incomplete_transactions = transactions_accept_incomplete(&self.pool).await?;
+
+        Ok(SchemaFeatures {
+            jobs_height_range,
+            jobs_progress_rate,
+            process_events,
+            job_errors,
+            block_templates,
+            tx_events,
+            transaction_fees,
+            job_derived_addresses,
+            event_outbox,
+            job_exports,
+            job_checkpoints,
+            block_headers,
+            double_spends,
+            tx_replacements,
+            chain_stats,
+            supply_stats,
+            chain_partitions,
+            retention,
+            block_raw,
+            block_filters,
+            job_events,
+            stream_events,
+            broadcasts,
+            job_error_panics,
+            block_write_progress,
+            job_discrepancies,
+            job_gaps,
+            input_prevouts,
+            transaction_details,
+            runes,
+            job_address_sources,
+            incomplete_transactions,
+        })
     }
 
     pub fn pool(&self) -> &Pool<Postgres> {
         &self.pool
     }
 
+    /// Pool for API queries that tolerate replication lag: the replica when configured,
+    /// the primary otherwise.
+    pub fn read_pool(&self) -> &Pool<Postgres> {
+        self.replica.as_ref().unwrap_or(&self.pool)
+    }
+
+    /// Applies pending migrations and records them in `_sqlx_migrations`.
+    ///
+    /// Already applied versions are skipped; one whose file changed since it was
+    /// applied fails the checksum check. Concurrent instances serialize on an
+    /// advisory lock, so only one of them applies a given version.
     pub async fn apply_migrations(&self) -> Result<(), StorageError> {
-        let path = env::var("MIGRATIONS_PATH").unwrap_or_else(|_| DEFAULT_MIGRATIONS_PATH.to_string());
-        self.apply_migrations_from(Path::new(&path)).await
+        let migrator = load_migrator().await?;
+        migrator.run(&self.pool).await?;
+
+        info!(
+            component = "storage",
+            latest_version = migrator.iter().map(|migration| migration.version).max(),
+            message = "migrations applied"
+        );
+        Ok(())
     }
+}
 
-    async fn apply_migrations_from(&self, path: &Path) -> Result<(), StorageError> {
-        let mut entries: Vec<_> = fs::read_dir(path)
-            .map_err(|source| StorageError::MigrationsRead {
-                path: path.display().to_string(),
-                source,
-            })?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry
-                    .path()
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext.eq_ignore_ascii_case("sql"))
-                    .unwrap_or(false)
-            })
-            .collect();
+/// Versions known to this binary that are not recorded as applied in `_sqlx_migrations`.
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<i64>, StorageError> {
+    let migrator = load_migrator().await?;
+    let tracked: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await?;
+    let applied: Vec<i64> = if tracked {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
 
-        entries.sort_by_key(|entry| entry.path());
+    Ok(migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .filter(|version| !applied.contains(version))
+        .collect())
+}
 
-        for entry in entries {
-            let sql = fs::read_to_string(entry.path()).map_err(|source| StorageError::MigrationsRead {
-                path: entry.path().display().to_string(),
-                source,
-            })?;
+/// URL of the `database` config section: `url`, or else the env var named by `url_env`.
+pub fn database_url(config: &DatabaseConfig) -> Result<String, StorageError> {
+    match &config.url {
+        Some(url) => Ok(url.to_string()),
+        None => env::var(&config.url_env).map_err(|_| StorageError::MissingDatabaseUrl(config.url_env.clone())),
+    }
+}
 
-            for statement in split_sql_statements(&sql) {
-                sqlx::query(statement)
-                    .execute(&self.pool)
-                    .await
-                    .map_err(StorageError::Migration)?;
-            }
-        }
+/// Whether `url` names a SQLite database (`sqlite:regtest.db`), which only `backfill`
+/// can write to, in builds with the `sqlite` feature.
+pub fn is_sqlite_url(url: &str) -> bool {
+    url.starts_with("sqlite:")
+}
 
-        Ok(())
+/// Migrations from `MIGRATIONS_PATH` when set, otherwise the ones embedded at build time.
+async fn load_migrator() -> Result<Migrator, StorageError> {
+    let mut migrator = match env::var("MIGRATIONS_PATH") {
+        Ok(path) => Migrator::new(Path::new(&path))
+            .await
+            .map_err(|source| StorageError::MigrationsRead { path, source })?,
+        Err(_) => sqlx::migrate!(),
+    };
+    // Migrations are additive: a previous version may start on a schema migrated by a newer one.
+    migrator.set_ignore_missing(true);
+
+    Ok(migrator)
+}
+
+async fn column_exists(pool: &PgPool, table: &str, column: &str) -> Result<bool, StorageError> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS ( \
+           SELECT 1 FROM information_schema.columns \
+           WHERE table_schema = current_schema() AND table_name = $1 AND column_name = $2 \
+         )",
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists)
+}
+
+/// Whether the category check of `job_errors` allows `panic`, as changed by `0029_job_error_panics.sql`.
+async fn job_errors_accept_panics(pool: &PgPool) -> Result<bool, StorageError> {
+    let accepts: bool = sqlx::query_scalar(
+        "SELECT EXISTS ( \
+           SELECT 1 FROM pg_constraint c \
+           JOIN pg_class t ON t.oid = c.conrelid \
+           JOIN pg_namespace n ON n.oid = t.relnamespace \
+           WHERE n.nspname = current_schema() AND t.relname = 'job_errors' \
+             AND c.conname = 'job_errors_category_check' \
+             AND pg_get_constraintdef(c.oid) LIKE '%panic%' \
+         )",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(accepts)
+}
+
+/// Whether `0037_incomplete_transactions.sql` widened the status check of `transactions`.
+async fn transactions_accept_incomplete(pool: &PgPool) -> Result<bool, StorageError> {
+    let accepts: bool = sqlx::query_scalar(
+        "SELECT EXISTS ( \
+           SELECT 1 FROM pg_constraint c \
+           JOIN pg_class t ON t.oid = c.conrelid \
+           JOIN pg_namespace n ON n.oid = t.relnamespace \
+           WHERE n.nspname = current_schema() AND t.relname = 'transactions' \
+             AND c.conname = 'transactions_status_check' \
+             AND pg_get_constraintdef(c.oid) LIKE '%incomplete%' \
+         )",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(accepts)
+}
+
+/// Whether `0022_chain_partitions.sql` converted the chain tables; it leaves a populated
+/// database with the plain layout.
+async fn chain_partitioned(pool: &PgPool) -> Result<bool, StorageError> {
+    if !column_exists(pool, "chain_partitioning", "blocks_per_partition").await? {
+        return Ok(false);
     }
+
+    let partitioned: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM chain_partitioning)")
+        .fetch_one(pool)
+        .await?;
+    Ok(partitioned)
+}
+
+fn connect_options(url: &str, config: &DatabaseConfig) -> Result<PgConnectOptions, sqlx::Error> {
+    let options = PgConnectOptions::from_str(url)?;
+    Ok(match config.statement_timeout_ms {
+        Some(timeout_ms) => options.options([("statement_timeout", timeout_ms.to_string())]),
+        None => options,
+    })
+}
+
+async fn schema_pool(pool: &PgPool, schema: &str) -> Result<PgPool, sqlx::Error> {
+    let options = (*pool.connect_options()).clone().options([("search_path", schema)]);
+    pool.options().clone().connect_with(options).await
 }
 
-fn split_sql_statements(sql: &str) -> Vec<&str> {
-    sql.split(';')
-        .map(str::trim)
-        .filter(|statement| !statement.is_empty())
-        .collect()
+fn parse_flag(value: &str) -> bool {
+    matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes" | "on")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::split_sql_statements;
+    use super::{is_sqlite_url, parse_flag};
+
+    #[test]
+    fn embeds_versioned_migrations_in_order() {
+        let migrator = sqlx::migrate!();
+        let versions: Vec<i64> = migrator.iter().map(|migration| migration.version).collect();
+        let files = std::fs::read_dir("migrations")
+            .expect("migrations dir")
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "sql"))
+            })
+            .count();
+
+        assert_eq!(versions.len(), files);
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]), "duplicate or unordered versions: {versions:?}");
+    }
 
     #[test]
-    fn splits_multiple_statements() {
-        let sql = "CREATE TABLE a(id INT); INSERT INTO a VALUES (1);";
-        let parts = split_sql_statements(sql);
-        assert_eq!(parts.len(), 2);
-        assert_eq!(parts[0], "CREATE TABLE a(id INT)");
-        assert_eq!(parts[1], "INSERT INTO a VALUES (1)");
+    fn parses_compat_mode_flag() {
+        assert!(parse_flag("true"));
+        assert!(parse_flag(" 1 "));
+        assert!(!parse_flag("false"));
+        assert!(!parse_flag(""));
     }
 
     #[test]
-    fn skips_empty_segments() {
-        let sql = ";;  \n  ;SELECT 1;  ;";
-        let parts = split_sql_statements(sql);
-        assert_eq!(parts, vec!["SELECT 1"]);
+    fn recognizes_sqlite_urls() {
+        assert!(is_sqlite_url("sqlite:regtest.db"));
+        assert!(is_sqlite_url("sqlite::memory:"));
+        assert!(!is_sqlite_url("postgres://indexer@localhost/indexer"));
     }
 }
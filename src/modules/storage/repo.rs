@@ -1,5 +1,19 @@
+use std::collections::HashMap;
+
 use serde_json::Value;
-use sqlx::{Executor, PgPool, Postgres, Row};
+use sqlx::{Executor, PgConnection, PgPool, Postgres, QueryBuilder, Row};
+
+use crate::modules::storage::SchemaFeatures;
+
+/// Class of the advisory locks that serialize upserts of the same txid with partitioned chain
+/// tables, where `txid` cannot be unique across partitions; the second key is the hash of the
+/// txid, so writers of different transactions do not wait for each other. The two-key form
+/// does not overlap the single-key locks. Held until the writing transaction commits.
+const TRANSACTIONS_WRITE_LOCK_CLASS: i32 = 3;
+
+/// Fields of `transactions.decoded` that queries read back (`vsize` for fee rates); the
+/// only ones stored without `storage.store_decoded_tx`.
+const DECODED_QUERIED_FIELDS: &[&str] = &["vsize"];
 
 #[derive(Debug, Clone)]
 pub struct BlockRecord {
@@ -20,6 +34,10 @@ pub struct TransactionRecord {
     pub time: i64,
     pub status: String,
     pub decoded: Value,
+    pub vsize: Option<i64>,
+    pub weight: Option<i64>,
+    pub version: Option<i64>,
+    pub locktime: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
@@ -30,6 +48,9 @@ pub struct TxOutputRecord {
     pub script_type: String,
     pub address: Option<String>,
     pub script_hex: String,
+    /// Height of the confirming block, `None` for unconfirmed transactions; the partition
+    /// key of partitioned chain tables and not stored otherwise.
+    pub block_height: Option<i32>,
 }
 
 #[derive(Debug, Clone)]
@@ -39,29 +60,56 @@ pub struct TxInputRecord {
     pub prev_txid: String,
     pub prev_vout: i32,
     pub sequence: i64,
+    /// Spent output, when it is stored or was resolved from the node.
+    pub prevout: Option<PrevoutRecord>,
+    /// Witness stack items as hex; only kept with `storage.store_witness`.
+    pub witness: Option<Vec<String>>,
+}
+
+/// Output an input spends, kept on its `tx_inputs` row (`0033_input_prevouts.sql`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrevoutRecord {
+    pub value_sats: i64,
+    pub script_type: String,
+    pub address: Option<String>,
 }
 
-pub struct BlocksRepo;
+pub struct BlocksRepo {
+    schema: SchemaFeatures,
+}
 
 impl BlocksRepo {
     pub fn new(_pool: &PgPool) -> Self {
-        Self
+        Self {
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
     }
 
     pub async fn upsert<'e, E>(&self, executor: E, block: &BlockRecord) -> Result<(), sqlx::Error>
     where
         E: Executor<'e, Database = Postgres>,
     {
-        sqlx::query(
+        // A hash always has the same height, so `(height, hash)` is as unique as `hash`.
+        let conflict = if self.schema.chain_partitions {
+            "(height, hash)"
+        } else {
+            "(hash)"
+        };
+        sqlx::query(&format!(
             "INSERT INTO blocks (height, hash, prev_hash, time, status, meta)
              VALUES ($1, $2, $3, $4, $5, $6)
-             ON CONFLICT (hash) DO UPDATE SET
+             ON CONFLICT {conflict} DO UPDATE SET
                height = EXCLUDED.height,
                prev_hash = EXCLUDED.prev_hash,
                time = EXCLUDED.time,
                status = EXCLUDED.status,
-               meta = EXCLUDED.meta",
-        )
+               meta = EXCLUDED.meta"
+        ))
         .bind(block.height)
         .bind(&block.hash)
         .bind(&block.prev_hash)
@@ -73,59 +121,180 @@ impl BlocksRepo {
 
         Ok(())
     }
+
+    pub async fn set_total_fees<'e, E>(
+        &self,
+        executor: E,
+        hash: &str,
+        total_fees_sats: Option<i64>,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query("UPDATE blocks SET total_fees_sats = $2 WHERE hash = $1")
+            .bind(hash)
+            .bind(total_fees_sats)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
 }
 
-pub struct TransactionsRepo;
+pub struct TransactionsRepo {
+    schema: SchemaFeatures,
+    store_decoded: bool,
+}
 
 impl TransactionsRepo {
     pub fn new(_pool: &PgPool) -> Self {
-        Self
+        Self {
+            schema: SchemaFeatures::latest(),
+            store_decoded: true,
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Whether `decoded` is stored in full or reduced to [`DECODED_QUERIED_FIELDS`]. On by default.
+    pub fn with_decoded(mut self, enabled: bool) -> Self {
+        self.store_decoded = enabled;
+        self
+    }
+
+    /// With partitioned chain tables the row is updated in place, moving to the partition
+    /// of the new height, or inserted when the txid is unknown.
+    pub async fn upsert(&self, conn: &mut PgConnection, tx: &TransactionRecord) -> Result<(), sqlx::Error> {
+        let mut columns = vec!["block_height", "block_hash", "position_in_block", "time", "status", "decoded"];
+        if self.schema.transaction_details {
+            columns.extend(["vsize", "weight", "version", "locktime"]);
+        }
+        let params: Vec<String> = (1..=columns.len() + 1).map(|n| format!("${n}")).collect();
+        let params = params.join(", ");
+
+        let statement = if self.schema.chain_partitions {
+            sqlx::query("SELECT pg_advisory_xact_lock($1, hashtext($2))")
+                .bind(TRANSACTIONS_WRITE_LOCK_CLASS)
+                .bind(&tx.txid)
+                .execute(&mut *conn)
+                .await?;
+            let assignments: Vec<String> = columns
+                .iter()
+                .enumerate()
+                .map(|(idx, column)| format!("{column} = ${}", idx + 2))
+                .collect();
+            format!(
+                "WITH updated AS (
+                   UPDATE transactions SET {}
+                   WHERE txid = $1
+                   RETURNING txid
+                 )
+                 INSERT INTO transactions (txid, {})
+                 SELECT {params}
+                 WHERE NOT EXISTS (SELECT 1 FROM updated)",
+                assignments.join(", "),
+                columns.join(", ")
+            )
+        } else {
+            let assignments: Vec<String> = columns
+                .iter()
+                .map(|column| format!("{column} = EXCLUDED.{column}"))
+                .collect();
+            format!(
+                "INSERT INTO transactions (txid, {})
+                 VALUES ({params})
+                 ON CONFLICT (txid) DO UPDATE SET {}",
+                columns.join(", "),
+                assignments.join(", ")
+            )
+        };
+        let reduced;
+        let decoded = if self.store_decoded {
+            &tx.decoded
+        } else {
+            reduced = reduce_decoded(&tx.decoded);
+            &reduced
+        };
+        let mut query = sqlx::query(&statement)
+            .bind(&tx.txid)
+            .bind(tx.block_height)
+            .bind(&tx.block_hash)
+            .bind(tx.position_in_block)
+            .bind(tx.time)
+            .bind(&tx.status)
+            .bind(decoded);
+        if self.schema.transaction_details {
+            query = query.bind(tx.vsize).bind(tx.weight).bind(tx.version).bind(tx.locktime);
+        }
+        query.execute(conn).await?;
+
+        Ok(())
     }
 
-    pub async fn upsert<'e, E>(&self, executor: E, tx: &TransactionRecord) -> Result<(), sqlx::Error>
+    pub async fn set_fee<'e, E>(&self, executor: E, txid: &str, fee_sats: Option<i64>) -> Result<(), sqlx::Error>
     where
         E: Executor<'e, Database = Postgres>,
     {
-        sqlx::query(
-            "INSERT INTO transactions (txid, block_height, block_hash, position_in_block, time, status, decoded)
-             VALUES ($1, $2, $3, $4, $5, $6, $7)
-             ON CONFLICT (txid) DO UPDATE SET
-               block_height = EXCLUDED.block_height,
-               block_hash = EXCLUDED.block_hash,
-               position_in_block = EXCLUDED.position_in_block,
-               time = EXCLUDED.time,
-               status = EXCLUDED.status,
-               decoded = EXCLUDED.decoded",
-        )
-        .bind(&tx.txid)
-        .bind(tx.block_height)
-        .bind(&tx.block_hash)
-        .bind(tx.position_in_block)
-        .bind(tx.time)
-        .bind(&tx.status)
-        .bind(&tx.decoded)
-        .execute(executor)
-        .await?;
+        sqlx::query("UPDATE transactions SET fee_sats = $2 WHERE txid = $1")
+            .bind(txid)
+            .bind(fee_sats)
+            .execute(executor)
+            .await?;
 
         Ok(())
     }
 }
 
-pub struct TxOutputsRepo;
+pub struct TxOutputsRepo {
+    schema: SchemaFeatures,
+}
 
 impl TxOutputsRepo {
     pub fn new(_pool: &PgPool) -> Self {
-        Self
+        Self {
+            schema: SchemaFeatures::latest(),
+        }
     }
 
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Stored outputs are kept as they are; with partitioned chain tables they follow the
+    /// height of their transaction, which [`TransactionsRepo::upsert`] has to write first.
     pub async fn insert<'e, E>(&self, executor: E, output: &TxOutputRecord) -> Result<(), sqlx::Error>
     where
         E: Executor<'e, Database = Postgres>,
     {
+        if !self.schema.chain_partitions {
+            sqlx::query(
+                "INSERT INTO tx_outputs (txid, vout, value_sats, script_type, address, script_hex)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (txid, vout) DO NOTHING",
+            )
+            .bind(&output.txid)
+            .bind(output.vout)
+            .bind(output.value_sats)
+            .bind(&output.script_type)
+            .bind(&output.address)
+            .bind(&output.script_hex)
+            .execute(executor)
+            .await?;
+            return Ok(());
+        }
+
         sqlx::query(
-            "INSERT INTO tx_outputs (txid, vout, value_sats, script_type, address, script_hex)
-             VALUES ($1, $2, $3, $4, $5, $6)
-             ON CONFLICT (txid, vout) DO NOTHING",
+            "WITH moved AS (
+               UPDATE tx_outputs SET block_height = $7
+               WHERE txid = $1 AND vout = $2 AND block_height IS DISTINCT FROM $7
+             )
+             INSERT INTO tx_outputs (txid, vout, value_sats, script_type, address, script_hex, block_height)
+             SELECT $1, $2, $3, $4, $5, $6, $7
+             WHERE NOT EXISTS (SELECT 1 FROM tx_outputs WHERE txid = $1 AND vout = $2)",
         )
         .bind(&output.txid)
         .bind(output.vout)
@@ -133,36 +302,86 @@ impl TxOutputsRepo {
         .bind(&output.script_type)
         .bind(&output.address)
         .bind(&output.script_hex)
+        .bind(output.block_height)
         .execute(executor)
         .await?;
 
         Ok(())
     }
+
+    pub async fn value_sats(
+        &self,
+        executor: impl Executor<'_, Database = Postgres>,
+        txid: &str,
+        vout: i32,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT value_sats FROM tx_outputs WHERE txid = $1 AND vout = $2")
+            .bind(txid)
+            .bind(vout)
+            .fetch_optional(executor)
+            .await
+    }
 }
 
-pub struct TxInputsRepo;
+pub struct TxInputsRepo {
+    schema: SchemaFeatures,
+}
 
 impl TxInputsRepo {
     pub fn new(_pool: &PgPool) -> Self {
-        Self
+        Self {
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
     }
 
+    /// An existing row keeps its spent output and witness, and gets those of `input` if it
+    /// had none, e.g. when a mempool transaction confirms after the output it spends was indexed.
     pub async fn insert<'e, E>(&self, executor: E, input: &TxInputRecord) -> Result<(), sqlx::Error>
     where
         E: Executor<'e, Database = Postgres>,
     {
-        sqlx::query(
-            "INSERT INTO tx_inputs (txid, vin, prev_txid, prev_vout, sequence)
-             VALUES ($1, $2, $3, $4, $5)
-             ON CONFLICT (txid, vin) DO NOTHING",
-        )
-        .bind(&input.txid)
-        .bind(input.vin)
-        .bind(&input.prev_txid)
-        .bind(input.prev_vout)
-        .bind(input.sequence)
-        .execute(executor)
-        .await?;
+        let mut columns = vec!["txid", "vin", "prev_txid", "prev_vout", "sequence"];
+        let mut kept = Vec::new();
+        if self.schema.input_prevouts {
+            kept.extend(["prev_value_sats", "prev_script_type", "prev_address"]);
+        }
+        if self.schema.transaction_details {
+            kept.push("witness");
+        }
+        columns.extend(&kept);
+
+        let mut builder = QueryBuilder::<Postgres>::new(format!("INSERT INTO tx_inputs ({}) VALUES (", columns.join(", ")));
+        let mut values = builder.separated(", ");
+        values.push_bind(&input.txid);
+        values.push_bind(input.vin);
+        values.push_bind(&input.prev_txid);
+        values.push_bind(input.prev_vout);
+        values.push_bind(input.sequence);
+        if self.schema.input_prevouts {
+            let prevout = input.prevout.as_ref();
+            values.push_bind(prevout.map(|prevout| prevout.value_sats));
+            values.push_bind(prevout.map(|prevout| prevout.script_type.clone()));
+            values.push_bind(prevout.and_then(|prevout| prevout.address.clone()));
+        }
+        if self.schema.transaction_details {
+            values.push_bind(&input.witness);
+        }
+        builder.push(") ON CONFLICT (txid, vin) DO ");
+        if kept.is_empty() {
+            builder.push("NOTHING");
+        } else {
+            let updates: Vec<String> = kept
+                .iter()
+                .map(|column| format!("{column} = COALESCE(tx_inputs.{column}, EXCLUDED.{column})"))
+                .collect();
+            builder.push(format!("UPDATE SET {}", updates.join(", ")));
+        }
+        builder.build().execute(executor).await?;
 
         Ok(())
     }
@@ -307,29 +526,583 @@ impl AddressLookupRepo {
         Self
     }
 
-    pub async fn output_address_value(
+    /// Stored output `txid:vout`, see [`PrevoutRecord`].
+    pub async fn output(
         &self,
         executor: impl Executor<'_, Database = Postgres>,
         txid: &str,
         vout: i32,
-    ) -> Result<Option<(String, i64)>, sqlx::Error> {
-        let row = sqlx::query(
-            "SELECT address, value_sats \
-             FROM tx_outputs \
-             WHERE txid = $1 AND vout = $2 AND address IS NOT NULL",
+    ) -> Result<Option<PrevoutRecord>, sqlx::Error> {
+        let row = sqlx::query("SELECT value_sats, script_type, address FROM tx_outputs WHERE txid = $1 AND vout = $2")
+            .bind(txid)
+            .bind(vout)
+            .fetch_optional(executor)
+            .await?;
+
+        Ok(row.map(|r| PrevoutRecord {
+            value_sats: r.get("value_sats"),
+            script_type: r.get("script_type"),
+            address: r.get("address"),
+        }))
+    }
+}
+
+/// Event of persisted chain data queued in `event_outbox` for the sink publisher.
+#[derive(Debug, Clone)]
+pub struct OutboxEventRecord {
+    /// `blocks`, `transactions`, `address_activity` or `double_spends`.
+    pub stream: &'static str,
+    /// Partitioning key: block hash, txid, address or spent outpoint.
+    pub event_key: String,
+    pub payload: Value,
+}
+
+pub struct OutboxRepo;
+
+impl OutboxRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    pub async fn insert<'e, E>(&self, executor: E, event: &OutboxEventRecord) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query("INSERT INTO event_outbox (stream, event_key, payload) VALUES ($1, $2, $3)")
+            .bind(event.stream)
+            .bind(&event.event_key)
+            .bind(&event.payload)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Events of the SSE stream in `stream_events`: `job_status`, `block_connected` or `reorg`.
+pub struct StreamEventsRepo;
+
+impl StreamEventsRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    pub async fn insert<'e, E>(&self, executor: E, kind: &str, payload: &Value) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query("INSERT INTO stream_events (kind, payload) VALUES ($1, $2)")
+            .bind(kind)
+            .bind(payload)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Drops events older than `retention_hours` and all but the latest `keep` events.
+    pub async fn prune<'e, E>(&self, executor: E, retention_hours: i32, keep: i64) -> Result<u64, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result = sqlx::query(
+            "DELETE FROM stream_events \
+             WHERE created_at < NOW() - make_interval(hours => $1) \
+                OR id <= (SELECT MAX(id) FROM stream_events) - $2",
+        )
+        .bind(retention_hours)
+        .bind(keep)
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Outpoint spent by `txid` that the known `conflicting_txid` spends too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoubleSpendRecord {
+    pub prev_txid: String,
+    pub prev_vout: i32,
+    pub txid: String,
+    /// `mempool` or `confirmed`.
+    pub txid_status: String,
+    pub conflicting_txid: String,
+    pub conflicting_status: String,
+    pub block_height: Option<i32>,
+}
+
+impl DoubleSpendRecord {
+    /// `double_spends` sink event, keyed by the outpoint so both spends land in one partition.
+    pub fn outbox_event(&self) -> OutboxEventRecord {
+        OutboxEventRecord {
+            stream: "double_spends",
+            event_key: format!("{}:{}", self.prev_txid, self.prev_vout),
+            payload: serde_json::json!({
+                "type": "double_spend",
+                "prev_txid": self.prev_txid,
+                "prev_vout": self.prev_vout,
+                "txid": self.txid,
+                "txid_status": self.txid_status,
+                "conflicting_txid": self.conflicting_txid,
+                "conflicting_status": self.conflicting_status,
+                "block_height": self.block_height,
+            }),
+        }
+    }
+}
+
+pub struct DoubleSpendsRepo;
+
+impl DoubleSpendsRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    /// Records every outpoint the stored inputs of `txid` share with another mempool or
+    /// confirmed transaction; returns only conflicts not recorded before.
+    pub async fn record_conflicts<'e, E>(
+        &self,
+        executor: E,
+        txid: &str,
+        txid_status: &str,
+        block_height: Option<i32>,
+    ) -> Result<Vec<DoubleSpendRecord>, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let rows = sqlx::query(
+            "INSERT INTO double_spends \
+               (prev_txid, prev_vout, txid, txid_status, conflicting_txid, conflicting_status, block_height) \
+             SELECT i.prev_txid, i.prev_vout, i.txid, $2, other.txid, t.status, $3 \
+             FROM tx_inputs i \
+             JOIN tx_inputs other \
+               ON other.prev_txid = i.prev_txid AND other.prev_vout = i.prev_vout AND other.txid <> i.txid \
+             JOIN transactions t ON t.txid = other.txid AND t.status IN ('mempool', 'confirmed') \
+             WHERE i.txid = $1 \
+             ORDER BY i.vin, other.txid \
+             ON CONFLICT DO NOTHING \
+             RETURNING prev_txid, prev_vout, txid, txid_status, conflicting_txid, conflicting_status, block_height",
         )
         .bind(txid)
-        .bind(vout)
+        .bind(txid_status)
+        .bind(block_height)
+        .fetch_all(executor)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| DoubleSpendRecord {
+                prev_txid: row.get("prev_txid"),
+                prev_vout: row.get("prev_vout"),
+                txid: row.get("txid"),
+                txid_status: row.get("txid_status"),
+                conflicting_txid: row.get("conflicting_txid"),
+                conflicting_status: row.get("conflicting_status"),
+                block_height: row.get("block_height"),
+            })
+            .collect())
+    }
+}
+
+pub struct TxReplacementsRepo;
+
+impl TxReplacementsRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    /// Links `replaced_txid` to `replacement_txid` when the replacement pays a higher fee,
+    /// or when the fee of either is unknown because a spent output is not indexed. Fees are
+    /// computed from the stored inputs and outputs; returns whether a link was recorded.
+    pub async fn record<'e, E>(
+        &self,
+        executor: E,
+        replaced_txid: &str,
+        replacement_txid: &str,
+    ) -> Result<bool, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let result = sqlx::query(
+            "WITH fees AS ( \
+               SELECT tx.txid, \
+                      (SELECT CASE WHEN COUNT(*) > 0 AND COUNT(*) = COUNT(p.value_sats) THEN SUM(p.value_sats) END \
+                       FROM tx_inputs i \
+                       LEFT JOIN tx_outputs p ON p.txid = i.prev_txid AND p.vout = i.prev_vout \
+                       WHERE i.txid = tx.txid) \
+                      - (SELECT COALESCE(SUM(o.value_sats), 0) FROM tx_outputs o WHERE o.txid = tx.txid) AS fee_sats \
+               FROM UNNEST(ARRAY[$1, $2]::TEXT[]) AS tx(txid) \
+             ) \
+             INSERT INTO tx_replacements (replaced_txid, replacement_txid, replaced_fee_sats, replacement_fee_sats) \
+             SELECT $1, $2, replaced.fee_sats, replacement.fee_sats \
+             FROM fees replaced, fees replacement \
+             WHERE replaced.txid = $1 AND replacement.txid = $2 \
+               AND (replaced.fee_sats IS NULL OR replacement.fee_sats IS NULL OR replacement.fee_sats > replaced.fee_sats) \
+             ON CONFLICT (replaced_txid) DO NOTHING",
+        )
+        .bind(replaced_txid)
+        .bind(replacement_txid)
+        .execute(executor)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+pub struct JobCheckpointsRepo;
+
+impl JobCheckpointsRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    /// Moves the checkpoint of `job_id` and its progress up to `height`; a checkpoint
+    /// above `height` is kept.
+    pub async fn save<'e, E>(&self, executor: E, job_id: &str, height: i32, hash: &str) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE jobs \
+             SET checkpoint_height = $2, checkpoint_hash = $3, \
+                 progress_height = GREATEST(progress_height, $2), updated_at = NOW() \
+             WHERE job_id = $1 AND COALESCE(checkpoint_height, -1) <= $2",
+        )
+        .bind(job_id)
+        .bind(height)
+        .bind(hash)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub struct BlockRawRepo;
+
+impl BlockRawRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    /// Stores the serialized block Snappy-compressed; a block already stored under `hash`
+    /// is kept.
+    pub async fn insert<'e, E>(&self, executor: E, hash: &str, height: i32, raw: &[u8]) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let size = i32::try_from(raw.len())
+            .map_err(|_| sqlx::Error::Protocol(format!("raw block {hash} exceeds i32 range")))?;
+        let compressed = snap::raw::Encoder::new()
+            .compress_vec(raw)
+            .map_err(|err| sqlx::Error::Protocol(format!("raw block {hash} cannot be compressed: {err}")))?;
+        sqlx::query(
+            "INSERT INTO block_raw (hash, height, size, raw) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (hash) DO NOTHING",
+        )
+        .bind(hash)
+        .bind(height)
+        .bind(size)
+        .bind(compressed)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Partly written block of `block_write_progress`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockWriteProgressRecord {
+    pub hash: String,
+    pub height: i32,
+    pub tx_count: i32,
+    pub next_tx: i32,
+    pub total_fees_sats: Option<i64>,
+}
+
+pub struct BlockWriteProgressRepo;
+
+impl BlockWriteProgressRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    /// The partly written block at `height`, if any.
+    pub async fn at_height<'e, E>(&self, executor: E, height: i32) -> Result<Option<BlockWriteProgressRecord>, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let row = sqlx::query(
+            "SELECT hash, height, tx_count, next_tx, total_fees_sats \
+             FROM block_write_progress \
+             WHERE height = $1 \
+             ORDER BY updated_at DESC \
+             LIMIT 1",
+        )
+        .bind(height)
         .fetch_optional(executor)
         .await?;
 
-        Ok(row.map(|r| (r.get::<String, _>("address"), r.get::<i64, _>("value_sats"))))
+        row.map(|row| {
+            Ok(BlockWriteProgressRecord {
+                hash: row.try_get("hash")?,
+                height: row.try_get("height")?,
+                tx_count: row.try_get("tx_count")?,
+                next_tx: row.try_get("next_tx")?,
+                total_fees_sats: row.try_get("total_fees_sats")?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn save<'e, E>(&self, executor: E, progress: &BlockWriteProgressRecord) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "INSERT INTO block_write_progress (hash, height, tx_count, next_tx, total_fees_sats) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (hash) DO UPDATE \
+             SET next_tx = EXCLUDED.next_tx, total_fees_sats = EXCLUDED.total_fees_sats, updated_at = NOW()",
+        )
+        .bind(&progress.hash)
+        .bind(progress.height)
+        .bind(progress.tx_count)
+        .bind(progress.next_tx)
+        .bind(progress.total_fees_sats)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete<'e, E>(&self, executor: E, hash: &str) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM block_write_progress WHERE hash = $1")
+            .bind(hash)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Confirms the `incomplete` transactions of the block, in the transaction that writes
+    /// its last chunk and its row.
+    pub async fn confirm_transactions<'e, E>(&self, executor: E, height: i32, hash: &str) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "UPDATE transactions \
+             SET status = 'confirmed' \
+             WHERE block_height = $1 AND block_hash = $2 AND status = 'incomplete'",
+        )
+        .bind(height)
+        .bind(hash)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drops the partly written `stale` block: its transactions are orphaned, the outputs
+    /// they spent are unspent again, the ones they created are removed, address balances
+    /// get both changes back and the snapshots at its height, which has no canonical block
+    /// yet, are deleted.
+    pub async fn discard(&self, conn: &mut PgConnection, stale: &BlockWriteProgressRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "WITH stale AS ( \
+               SELECT txid FROM transactions \
+               WHERE block_height = $1 AND block_hash = $2 AND status = 'incomplete' \
+             ), changes AS ( \
+               SELECT u.address, -u.value_sats AS delta_sats \
+               FROM stale s \
+               JOIN utxos_current u ON u.out_txid = s.txid \
+               UNION ALL \
+               SELECT u.address, u.value_sats \
+               FROM stale s \
+               JOIN tx_inputs i ON i.txid = s.txid \
+               JOIN utxos_current u \
+                 ON u.out_txid = i.prev_txid AND u.out_vout = i.prev_vout AND u.spent_in_txid = s.txid \
+             ) \
+             UPDATE address_balance_current b \
+             SET balance_sats = b.balance_sats + d.delta_sats, updated_at = NOW() \
+             FROM (SELECT address, SUM(delta_sats)::BIGINT AS delta_sats FROM changes GROUP BY address) d \
+             WHERE b.address = d.address",
+        )
+        .bind(stale.height)
+        .bind(&stale.hash)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            "UPDATE utxos_current u \
+             SET status = 'unspent', spent_in_txid = NULL \
+             FROM transactions t \
+             JOIN tx_inputs i ON i.txid = t.txid \
+             WHERE t.block_height = $1 AND t.block_hash = $2 AND t.status = 'incomplete' \
+               AND u.out_txid = i.prev_txid AND u.out_vout = i.prev_vout AND u.spent_in_txid = t.txid",
+        )
+        .bind(stale.height)
+        .bind(&stale.hash)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM utxos_current u \
+             USING transactions t \
+             WHERE t.block_height = $1 AND t.block_hash = $2 AND t.status = 'incomplete' \
+               AND u.out_txid = t.txid",
+        )
+        .bind(stale.height)
+        .bind(&stale.hash)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("DELETE FROM address_balance_history WHERE block_height = $1")
+            .bind(stale.height)
+            .execute(&mut *conn)
+            .await?;
+
+        sqlx::query(
+            "UPDATE transactions \
+             SET status = 'orphaned' \
+             WHERE block_height = $1 AND block_hash = $2 AND status = 'incomplete'",
+        )
+        .bind(stale.height)
+        .bind(&stale.hash)
+        .execute(&mut *conn)
+        .await?;
+
+        self.delete(&mut *conn, &stale.hash).await
+    }
+
+    /// Drops the markers at and above `height`, whose transactions a reorg orphaned.
+    pub async fn delete_from<'e, E>(&self, executor: E, height: i32) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query("DELETE FROM block_write_progress WHERE height >= $1")
+            .bind(height)
+            .execute(executor)
+            .await?;
+
+        Ok(())
     }
 }
 
+/// BIP158 filter of a block with its BIP157 header.
+#[derive(Debug, Clone)]
+pub struct BlockFilterRecord {
+    pub hash: String,
+    pub height: i32,
+    pub filter: Vec<u8>,
+    pub header: Option<String>,
+    pub complete: bool,
+}
+
+pub struct BlockFiltersRepo;
+
+impl BlockFiltersRepo {
+    pub fn new(_pool: &PgPool) -> Self {
+        Self
+    }
+
+    /// Scripts of the indexed outputs `outpoints` spend, keyed by `(txid, vout)`; outputs
+    /// that are not indexed or whose script was pruned are left out.
+    pub async fn spent_scripts(
+        &self,
+        executor: impl Executor<'_, Database = Postgres>,
+        outpoints: &[(String, i32)],
+    ) -> Result<HashMap<(String, i32), String>, sqlx::Error> {
+        if outpoints.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let (txids, vouts): (Vec<&str>, Vec<i32>) =
+            outpoints.iter().map(|(txid, vout)| (txid.as_str(), *vout)).unzip();
+        let rows = sqlx::query(
+            "SELECT o.txid, o.vout, o.script_hex \
+             FROM tx_outputs o \
+             JOIN UNNEST($1::text[], $2::int[]) AS spent(txid, vout) \
+               ON o.txid = spent.txid AND o.vout = spent.vout \
+             WHERE o.script_hex <> ''",
+        )
+        .bind(txids)
+        .bind(vouts)
+        .fetch_all(executor)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    (row.get::<String, _>("txid"), row.get::<i32, _>("vout")),
+                    row.get::<String, _>("script_hex"),
+                )
+            })
+            .collect())
+    }
+
+    /// Filter header of the block with `hash`; `None` when its filter or header is not stored.
+    pub async fn header(
+        &self,
+        executor: impl Executor<'_, Database = Postgres>,
+        hash: &str,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let header: Option<Option<String>> = sqlx::query_scalar("SELECT header FROM block_filters WHERE hash = $1")
+            .bind(hash)
+            .fetch_optional(executor)
+            .await?;
+
+        Ok(header.flatten())
+    }
+
+    /// Stores the filter; a block indexed again replaces it, e.g. once its spent scripts are known.
+    pub async fn upsert<'e, E>(&self, executor: E, record: &BlockFilterRecord) -> Result<(), sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "INSERT INTO block_filters (hash, height, filter, header, complete) VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (hash) DO UPDATE SET filter = EXCLUDED.filter, header = EXCLUDED.header, \
+             complete = EXCLUDED.complete",
+        )
+        .bind(&record.hash)
+        .bind(record.height)
+        .bind(&record.filter)
+        .bind(&record.header)
+        .bind(record.complete)
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+pub(crate) fn reduce_decoded(decoded: &Value) -> Value {
+    let fields = decoded
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| DECODED_QUERIED_FIELDS.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    Value::Object(fields)
+}
+
+/// Serialized block from a `block_raw.raw` value.
+pub fn decompress_raw_block(compressed: &[u8]) -> Result<Vec<u8>, snap::Error> {
+    snap::raw::Decoder::new().decompress_vec(compressed)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{BlockRecord, TransactionRecord};
+    use super::{decompress_raw_block, reduce_decoded, BlockRecord, TransactionRecord};
 
     #[test]
     fn block_record_is_sendable() {
@@ -355,8 +1128,35 @@ mod tests {
             time: 0,
             status: "confirmed".to_string(),
             decoded: serde_json::json!({}),
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
         };
 
         let _ = tx.clone();
     }
+
+    #[test]
+    fn raw_block_round_trips_through_compression() {
+        let raw = [vec![0u8; 80], b"block body".repeat(20)].concat();
+        let compressed = snap::raw::Encoder::new().compress_vec(&raw).expect("compress");
+
+        assert!(compressed.len() < raw.len());
+        assert_eq!(decompress_raw_block(&compressed).expect("decompress"), raw);
+        assert!(decompress_raw_block(b"not snappy").is_err());
+    }
+
+    #[test]
+    fn reduced_decoded_keeps_only_queried_fields() {
+        let decoded = serde_json::json!({
+            "txid": "t",
+            "vin": [{ "txid": "p", "vout": 0, "sequence": 0 }],
+            "vout": [],
+            "vsize": 141,
+        });
+
+        assert_eq!(reduce_decoded(&decoded), serde_json::json!({ "vsize": 141 }));
+        assert_eq!(reduce_decoded(&serde_json::Value::Null), serde_json::json!({}));
+    }
 }
@@ -0,0 +1,468 @@
+//! SQLite [`ChainStore`] for running the indexer against regtest without PostgreSQL.
+//!
+//! Only the chain tables are kept, see `migrations/sqlite`; jobs, the API and the runners
+//! still need PostgreSQL, so the store is used by `backfill` alone.
+
+use std::str::FromStr;
+
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Row, Sqlite, SqlitePool, Transaction};
+
+use crate::modules::storage::repo::{
+    reduce_decoded, BlockRecord, PrevoutRecord, TransactionRecord, TxInputRecord, TxOutputRecord, UtxoCreateRecord,
+};
+use crate::modules::storage::store::{ChainStore, ChainStoreTx};
+use crate::modules::storage::{SchemaFeatures, StorageError};
+
+/// Chain tables in a SQLite file. The pool has a single connection, so writers of blocks
+/// run one after another and the chain state and height locks have nothing to do.
+#[derive(Clone)]
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Opens the database at `url`, creating the file when missing, and applies the
+    /// migrations of `migrations/sqlite`.
+    pub async fn connect(url: &str) -> Result<Self, StorageError> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+        sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Features of the SQLite tables: transaction fees and spent outputs on inputs on top
+    /// of [`SchemaFeatures::baseline`].
+    pub fn schema_features() -> SchemaFeatures {
+        SchemaFeatures {
+            transaction_fees: true,
+            input_prevouts: true,
+            ..SchemaFeatures::baseline()
+        }
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+}
+
+pub struct SqliteChainTx {
+    tx: Transaction<'static, Sqlite>,
+    schema: SchemaFeatures,
+}
+
+impl ChainStore for SqliteStore {
+    type Tx = SqliteChainTx;
+
+    async fn begin_write(&self, schema: SchemaFeatures) -> Result<SqliteChainTx, sqlx::Error> {
+        Ok(SqliteChainTx {
+            tx: self.pool.begin().await?,
+            schema,
+        })
+    }
+
+    async fn canonical_block_hash_at_height(&self, height: i32) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT hash FROM blocks WHERE height = ?1 AND status = 'canonical' LIMIT 1")
+            .bind(height)
+            .fetch_optional(&self.pool)
+            .await
+    }
+
+    async fn stored_outpoints(&self, outpoints: &[(String, i32)]) -> Result<Vec<(String, i32)>, sqlx::Error> {
+        let mut stored = Vec::new();
+        for (txid, vout) in outpoints {
+            let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM tx_outputs WHERE txid = ?1 AND vout = ?2)")
+                .bind(txid)
+                .bind(vout)
+                .fetch_one(&self.pool)
+                .await?;
+            if exists {
+                stored.push((txid.clone(), *vout));
+            }
+        }
+
+        Ok(stored)
+    }
+}
+
+impl ChainStoreTx for SqliteChainTx {
+    async fn commit(self) -> Result<(), sqlx::Error> {
+        self.tx.commit().await
+    }
+
+    async fn lock_chain_state(&mut self) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
+
+    async fn lock_height(&mut self, _height: i32) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
+
+    async fn canonical_block_hash_at_height(&mut self, height: i32) -> Result<Option<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT hash FROM blocks WHERE height = ?1 AND status = 'canonical' LIMIT 1")
+            .bind(height)
+            .fetch_optional(&mut *self.tx)
+            .await
+    }
+
+    async fn upsert_block(&mut self, block: &BlockRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO blocks (height, hash, prev_hash, time, status, meta)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (hash) DO UPDATE SET
+               height = excluded.height,
+               prev_hash = excluded.prev_hash,
+               time = excluded.time,
+               status = excluded.status,
+               meta = excluded.meta",
+        )
+        .bind(block.height)
+        .bind(&block.hash)
+        .bind(&block.prev_hash)
+        .bind(block.time)
+        .bind(&block.status)
+        .bind(&block.meta)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_block_total_fees(&mut self, hash: &str, total_fees_sats: Option<i64>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE blocks SET total_fees_sats = ?2 WHERE hash = ?1")
+            .bind(hash)
+            .bind(total_fees_sats)
+            .execute(&mut *self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn upsert_transaction(&mut self, tx: &TransactionRecord, store_decoded: bool) -> Result<(), sqlx::Error> {
+        let decoded: Value = if store_decoded {
+            tx.decoded.clone()
+        } else {
+            reduce_decoded(&tx.decoded)
+        };
+        sqlx::query(
+            "INSERT INTO transactions (txid, block_height, block_hash, position_in_block, time, status, decoded)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (txid) DO UPDATE SET
+               block_height = excluded.block_height,
+               block_hash = excluded.block_hash,
+               position_in_block = excluded.position_in_block,
+               time = excluded.time,
+               status = excluded.status,
+               decoded = excluded.decoded",
+        )
+        .bind(&tx.txid)
+        .bind(tx.block_height)
+        .bind(&tx.block_hash)
+        .bind(tx.position_in_block)
+        .bind(tx.time)
+        .bind(&tx.status)
+        .bind(decoded)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn set_transaction_fee(&mut self, txid: &str, fee_sats: Option<i64>) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE transactions SET fee_sats = ?2 WHERE txid = ?1")
+            .bind(txid)
+            .bind(fee_sats)
+            .execute(&mut *self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn insert_input(&mut self, input: &TxInputRecord) -> Result<(), sqlx::Error> {
+        let prevout = input.prevout.as_ref().filter(|_| self.schema.input_prevouts);
+        sqlx::query(
+            "INSERT INTO tx_inputs (txid, vin, prev_txid, prev_vout, sequence, prev_value_sats, prev_script_type, prev_address)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (txid, vin) DO UPDATE SET
+               prev_value_sats = COALESCE(tx_inputs.prev_value_sats, excluded.prev_value_sats),
+               prev_script_type = COALESCE(tx_inputs.prev_script_type, excluded.prev_script_type),
+               prev_address = COALESCE(tx_inputs.prev_address, excluded.prev_address)",
+        )
+        .bind(&input.txid)
+        .bind(input.vin)
+        .bind(&input.prev_txid)
+        .bind(input.prev_vout)
+        .bind(input.sequence)
+        .bind(prevout.map(|prevout| prevout.value_sats))
+        .bind(prevout.map(|prevout| prevout.script_type.clone()))
+        .bind(prevout.and_then(|prevout| prevout.address.clone()))
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_output(&mut self, output: &TxOutputRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO tx_outputs (txid, vout, value_sats, script_type, address, script_hex)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT (txid, vout) DO NOTHING",
+        )
+        .bind(&output.txid)
+        .bind(output.vout)
+        .bind(output.value_sats)
+        .bind(&output.script_type)
+        .bind(&output.address)
+        .bind(&output.script_hex)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn stored_output(&mut self, txid: &str, vout: i32) -> Result<Option<PrevoutRecord>, sqlx::Error> {
+        let row = sqlx::query("SELECT value_sats, script_type, address FROM tx_outputs WHERE txid = ?1 AND vout = ?2")
+            .bind(txid)
+            .bind(vout)
+            .fetch_optional(&mut *self.tx)
+            .await?;
+
+        Ok(row.map(|r| PrevoutRecord {
+            value_sats: r.get("value_sats"),
+            script_type: r.get("script_type"),
+            address: r.get("address"),
+        }))
+    }
+
+    async fn insert_unspent(&mut self, utxo: &UtxoCreateRecord) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO utxos_current \
+             (out_txid, out_vout, address, value_sats, created_in_txid, spent_in_txid, status) \
+             VALUES (?1, ?2, ?3, ?4, ?5, NULL, 'unspent') \
+             ON CONFLICT (out_txid, out_vout) DO NOTHING",
+        )
+        .bind(&utxo.out_txid)
+        .bind(utxo.out_vout)
+        .bind(&utxo.address)
+        .bind(utxo.value_sats)
+        .bind(&utxo.created_in_txid)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn mark_spent(&mut self, out_txid: &str, out_vout: i32, spent_in_txid: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE utxos_current \
+             SET spent_in_txid = ?3, status = 'spent' \
+             WHERE out_txid = ?1 AND out_vout = ?2 AND status = 'unspent'",
+        )
+        .bind(out_txid)
+        .bind(out_vout)
+        .bind(spent_in_txid)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn add_balance_delta(&mut self, address: &str, delta_sats: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO address_balance_current (address, balance_sats, updated_at) \
+             VALUES (?1, ?2, CURRENT_TIMESTAMP) \
+             ON CONFLICT (address) DO UPDATE SET \
+               balance_sats = address_balance_current.balance_sats + excluded.balance_sats, \
+               updated_at = CURRENT_TIMESTAMP",
+        )
+        .bind(address)
+        .bind(delta_sats)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn current_balance(&mut self, address: &str) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT balance_sats FROM address_balance_current WHERE address = ?1")
+            .bind(address)
+            .fetch_optional(&mut *self.tx)
+            .await
+    }
+
+    async fn upsert_balance_snapshot(
+        &mut self,
+        address: &str,
+        block_height: i32,
+        time: i64,
+        balance_sats: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO address_balance_history (address, block_height, time, balance_sats) \
+             VALUES (?1, ?2, ?3, ?4) \
+             ON CONFLICT (address, block_height) DO UPDATE SET \
+               time = excluded.time, \
+               balance_sats = excluded.balance_sats",
+        )
+        .bind(address)
+        .bind(block_height)
+        .bind(time)
+        .bind(balance_sats)
+        .execute(&mut *self.tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Jobs live in PostgreSQL only, so there is no checkpoint to move.
+    async fn save_checkpoint(&mut self, _job_id: &str, _height: i32, _hash: &str) -> Result<(), sqlx::Error> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::Row;
+
+    use super::SqliteStore;
+    use crate::modules::indexer::{IndexerPipeline, PersistBlockOutcome, RpcBlock};
+    use crate::modules::metrics::MetricsService;
+    use crate::modules::storage::SchemaFeatures;
+
+    const P2PKH_1: &str = "76a914111111111111111111111111111111111111111188ac";
+    const P2PKH_2: &str = "76a914222222222222222222222222222222222222222288ac";
+
+    fn block(json: serde_json::Value) -> RpcBlock {
+        serde_json::from_value(json).expect("block json")
+    }
+
+    /// Coinbase at height 0 and, at height 1 with hash `next_hash`, a transaction spending it.
+    fn spend_chain(next_hash: &str) -> (RpcBlock, RpcBlock) {
+        let genesis = block(serde_json::json!({
+            "hash": "hash0",
+            "height": 0,
+            "time": 1_700_000_000,
+            "tx": [{
+                "txid": "coinbase0",
+                "vin": [{"coinbase": "00", "sequence": 0}],
+                "vout": [{"n": 0, "value": 50.0, "scriptPubKey": {"type": "pubkeyhash", "hex": P2PKH_1}}]
+            }]
+        }));
+        let next = block(serde_json::json!({
+            "hash": next_hash,
+            "height": 1,
+            "previousblockhash": "hash0",
+            "time": 1_700_000_600,
+            "tx": [{
+                "txid": format!("spend-{next_hash}"),
+                "vin": [{"txid": "coinbase0", "vout": 0, "sequence": 0}],
+                "vout": [{"n": 0, "value": 49.9, "scriptPubKey": {"type": "pubkeyhash", "hex": P2PKH_2}}]
+            }]
+        }));
+        (genesis, next)
+    }
+
+    #[tokio::test]
+    async fn indexes_blocks_into_sqlite() {
+        let store = SqliteStore::connect("sqlite::memory:").await.expect("sqlite store");
+        let pipeline = IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(SqliteStore::schema_features());
+
+        let (genesis, next) = spend_chain("hash1");
+
+        assert_eq!(pipeline.persist_block(&genesis).await.expect("genesis"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&next).await.expect("block 1"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&next).await.expect("again"), PersistBlockOutcome::AlreadyIndexed);
+
+        let balances: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT o.script_hex, b.balance_sats \
+             FROM address_balance_current b JOIN tx_outputs o ON o.address = b.address \
+             ORDER BY o.script_hex",
+        )
+        .fetch_all(store.pool())
+        .await
+        .expect("balances");
+        assert_eq!(balances, vec![(P2PKH_1.to_string(), 0), (P2PKH_2.to_string(), 4_990_000_000)]);
+
+        let spend = sqlx::query("SELECT fee_sats FROM transactions WHERE txid = 'spend-hash1'")
+            .fetch_one(store.pool())
+            .await
+            .expect("spend");
+        assert_eq!(spend.get::<Option<i64>, _>("fee_sats"), Some(10_000_000));
+        let input = sqlx::query("SELECT prev_value_sats, prev_script_type FROM tx_inputs WHERE txid = 'spend-hash1'")
+            .fetch_one(store.pool())
+            .await
+            .expect("input");
+        assert_eq!(input.get::<Option<i64>, _>("prev_value_sats"), Some(5_000_000_000));
+        assert_eq!(input.get::<Option<String>, _>("prev_script_type").as_deref(), Some("pubkeyhash"));
+    }
+
+    #[tokio::test]
+    async fn rejects_a_competing_block_at_an_indexed_height() {
+        let store = SqliteStore::connect("sqlite::memory:").await.expect("sqlite store");
+        let pipeline = IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(SqliteStore::schema_features());
+        let (genesis, next) = spend_chain("hash1");
+        let (_, competing) = spend_chain("competing1");
+
+        assert_eq!(pipeline.persist_block(&genesis).await.expect("genesis"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&next).await.expect("block 1"), PersistBlockOutcome::Indexed);
+        assert_eq!(pipeline.persist_block(&genesis).await.expect("again"), PersistBlockOutcome::AlreadyIndexed);
+        let err = pipeline.persist_block(&competing).await.expect_err("height 1 is taken");
+        assert!(err.to_string().contains("height 1 is already occupied by canonical block hash1"));
+
+        let counts: (i64, i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM blocks), (SELECT COUNT(*) FROM transactions), \
+             (SELECT COUNT(*) FROM address_balance_history)",
+        )
+        .fetch_one(store.pool())
+        .await
+        .expect("counts");
+        assert_eq!(counts, (2, 2, 3));
+    }
+
+    #[tokio::test]
+    async fn rolls_back_the_block_when_a_stage_fails() {
+        let store = SqliteStore::connect("sqlite::memory:").await.expect("sqlite store");
+        let (genesis, next) = spend_chain("hash1");
+        IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(SqliteStore::schema_features())
+            .persist_block(&genesis)
+            .await
+            .expect("genesis");
+
+        // Double spend detection runs on PostgreSQL only, so it fails after the inputs are written.
+        let schema = SchemaFeatures {
+            double_spends: true,
+            ..SqliteStore::schema_features()
+        };
+        let err = IndexerPipeline::new(&store, MetricsService::new())
+            .with_schema_features(schema)
+            .persist_block(&next)
+            .await
+            .expect_err("double spends need PostgreSQL");
+        assert!(err.to_string().contains("PostgreSQL storage is needed for double spends"));
+
+        let rows: (i64, i64, i64) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM blocks WHERE hash = 'hash1'), (SELECT COUNT(*) FROM tx_inputs), \
+             (SELECT COUNT(*) FROM utxos_current WHERE spent_in_txid IS NOT NULL)",
+        )
+        .fetch_one(store.pool())
+        .await
+        .expect("rows");
+        assert_eq!(rows, (0, 0, 0));
+        let balance: i64 = sqlx::query_scalar("SELECT SUM(balance_sats) FROM address_balance_current")
+            .fetch_one(store.pool())
+            .await
+            .expect("balance");
+        assert_eq!(balance, 5_000_000_000);
+    }
+}
@@ -0,0 +1,313 @@
+use std::future::Future;
+
+use sqlx::{Executor, PgConnection, PgPool, Postgres, Row, Transaction};
+
+use crate::modules::storage::repo::{
+    AddressBalancesRepo, AddressLookupRepo, BlockRecord, BlocksRepo, JobCheckpointsRepo, PrevoutRecord,
+    TransactionRecord, TransactionsRepo, TxInputRecord, TxInputsRepo, TxOutputRecord, TxOutputsRepo,
+    UtxoCreateRecord, UtxosRepo,
+};
+use crate::modules::storage::SchemaFeatures;
+
+/// Advisory lock taken by every writer of chain state; single-key locks of block heights
+/// serialize the writers of one height.
+const CHAIN_STATE_LOCK_KEY: i64 = -1;
+
+/// Database the indexer pipeline writes blocks to. [`PgPool`] in production; the `sqlite`
+/// feature adds `SqliteStore` for regtest and development, and [`super::memory::MemoryStorage`]
+/// serves unit tests.
+///
+/// Other stores have the tables of the [`SchemaFeatures`] they report and nothing more:
+/// the pipeline stages behind the remaining features run on [`ChainStoreTx::postgres`] only.
+pub trait ChainStore: Clone + Send + Sync + 'static {
+    type Tx: ChainStoreTx;
+
+    /// Starts the transaction one block, or one chunk of it, is written in. Writes of
+    /// optional columns follow `schema`.
+    fn begin_write(&self, schema: SchemaFeatures) -> impl Future<Output = Result<Self::Tx, sqlx::Error>> + Send;
+
+    fn canonical_block_hash_at_height(
+        &self,
+        height: i32,
+    ) -> impl Future<Output = Result<Option<String>, sqlx::Error>> + Send;
+
+    /// Those of `outpoints` that have a stored output.
+    fn stored_outpoints(
+        &self,
+        outpoints: &[(String, i32)],
+    ) -> impl Future<Output = Result<Vec<(String, i32)>, sqlx::Error>> + Send;
+
+    /// Pool of a PostgreSQL store, for the queries of stages no other store has.
+    fn postgres(&self) -> Option<&PgPool> {
+        None
+    }
+}
+
+/// Writes of one transaction of a [`ChainStore`], the `storage::repo` calls of the pipeline.
+/// Dropping it without [`Self::commit`] rolls the writes back.
+pub trait ChainStoreTx: Send {
+    fn commit(self) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Held until the end of the transaction by every writer of chain state.
+    fn lock_chain_state(&mut self) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Held until the end of the transaction by writers of the block at `height`.
+    fn lock_height(&mut self, height: i32) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    fn canonical_block_hash_at_height(
+        &mut self,
+        height: i32,
+    ) -> impl Future<Output = Result<Option<String>, sqlx::Error>> + Send;
+
+    fn upsert_block(&mut self, block: &BlockRecord) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    fn set_block_total_fees(
+        &mut self,
+        hash: &str,
+        total_fees_sats: Option<i64>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// See [`TransactionsRepo::upsert`] and [`TransactionsRepo::with_decoded`].
+    fn upsert_transaction(
+        &mut self,
+        tx: &TransactionRecord,
+        store_decoded: bool,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    fn set_transaction_fee(
+        &mut self,
+        txid: &str,
+        fee_sats: Option<i64>,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// See [`TxInputsRepo::insert`].
+    fn insert_input(&mut self, input: &TxInputRecord) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// See [`TxOutputsRepo::insert`].
+    fn insert_output(&mut self, output: &TxOutputRecord) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Stored output `txid:vout`.
+    fn stored_output(
+        &mut self,
+        txid: &str,
+        vout: i32,
+    ) -> impl Future<Output = Result<Option<PrevoutRecord>, sqlx::Error>> + Send;
+
+    /// Adds an unspent UTXO; `false` when the output already has one.
+    fn insert_unspent(&mut self, utxo: &UtxoCreateRecord) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    /// Marks an unspent UTXO spent by `spent_in_txid`; `false` when there is none.
+    fn mark_spent(
+        &mut self,
+        out_txid: &str,
+        out_vout: i32,
+        spent_in_txid: &str,
+    ) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    fn add_balance_delta(
+        &mut self,
+        address: &str,
+        delta_sats: i64,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    fn current_balance(&mut self, address: &str) -> impl Future<Output = Result<Option<i64>, sqlx::Error>> + Send;
+
+    fn upsert_balance_snapshot(
+        &mut self,
+        address: &str,
+        block_height: i32,
+        time: i64,
+        balance_sats: i64,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// See [`JobCheckpointsRepo::save`].
+    fn save_checkpoint(
+        &mut self,
+        job_id: &str,
+        height: i32,
+        hash: &str,
+    ) -> impl Future<Output = Result<(), sqlx::Error>> + Send;
+
+    /// Connection of a PostgreSQL transaction, for the stages no other store has.
+    fn postgres(&mut self) -> Option<&mut PgConnection> {
+        None
+    }
+}
+
+/// Write transaction of a [`PgPool`], with the repos set up for its schema.
+pub struct PgChainTx {
+    pool: PgPool,
+    tx: Transaction<'static, Postgres>,
+    schema: SchemaFeatures,
+}
+
+impl ChainStore for PgPool {
+    type Tx = PgChainTx;
+
+    async fn begin_write(&self, schema: SchemaFeatures) -> Result<PgChainTx, sqlx::Error> {
+        Ok(PgChainTx {
+            pool: self.clone(),
+            tx: self.begin().await?,
+            schema,
+        })
+    }
+
+    async fn canonical_block_hash_at_height(&self, height: i32) -> Result<Option<String>, sqlx::Error> {
+        canonical_block_hash_at_height(self, height).await
+    }
+
+    async fn stored_outpoints(&self, outpoints: &[(String, i32)]) -> Result<Vec<(String, i32)>, sqlx::Error> {
+        let (txids, vouts): (Vec<String>, Vec<i32>) = outpoints.iter().cloned().unzip();
+        sqlx::query_as(
+            "SELECT o.txid, o.vout \
+             FROM tx_outputs o \
+             JOIN UNNEST($1::TEXT[], $2::INT[]) AS w(txid, vout) ON o.txid = w.txid AND o.vout = w.vout",
+        )
+        .bind(&txids)
+        .bind(&vouts)
+        .fetch_all(self)
+        .await
+    }
+
+    fn postgres(&self) -> Option<&PgPool> {
+        Some(self)
+    }
+}
+
+impl ChainStoreTx for PgChainTx {
+    async fn commit(self) -> Result<(), sqlx::Error> {
+        self.tx.commit().await
+    }
+
+    async fn lock_chain_state(&mut self) -> Result<(), sqlx::Error> {
+        acquire_chain_state_lock(&mut *self.tx).await
+    }
+
+    async fn lock_height(&mut self, height: i32) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(i64::from(height))
+            .execute(&mut *self.tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn canonical_block_hash_at_height(&mut self, height: i32) -> Result<Option<String>, sqlx::Error> {
+        canonical_block_hash_at_height(&mut *self.tx, height).await
+    }
+
+    async fn upsert_block(&mut self, block: &BlockRecord) -> Result<(), sqlx::Error> {
+        BlocksRepo::new(&self.pool)
+            .with_schema_features(self.schema)
+            .upsert(&mut *self.tx, block)
+            .await
+    }
+
+    async fn set_block_total_fees(&mut self, hash: &str, total_fees_sats: Option<i64>) -> Result<(), sqlx::Error> {
+        BlocksRepo::new(&self.pool)
+            .set_total_fees(&mut *self.tx, hash, total_fees_sats)
+            .await
+    }
+
+    async fn upsert_transaction(&mut self, tx: &TransactionRecord, store_decoded: bool) -> Result<(), sqlx::Error> {
+        TransactionsRepo::new(&self.pool)
+            .with_schema_features(self.schema)
+            .with_decoded(store_decoded)
+            .upsert(&mut self.tx, tx)
+            .await
+    }
+
+    async fn set_transaction_fee(&mut self, txid: &str, fee_sats: Option<i64>) -> Result<(), sqlx::Error> {
+        TransactionsRepo::new(&self.pool)
+            .set_fee(&mut *self.tx, txid, fee_sats)
+            .await
+    }
+
+    async fn insert_input(&mut self, input: &TxInputRecord) -> Result<(), sqlx::Error> {
+        TxInputsRepo::new(&self.pool)
+            .with_schema_features(self.schema)
+            .insert(&mut *self.tx, input)
+            .await
+    }
+
+    async fn insert_output(&mut self, output: &TxOutputRecord) -> Result<(), sqlx::Error> {
+        TxOutputsRepo::new(&self.pool)
+            .with_schema_features(self.schema)
+            .insert(&mut *self.tx, output)
+            .await
+    }
+
+    async fn stored_output(&mut self, txid: &str, vout: i32) -> Result<Option<PrevoutRecord>, sqlx::Error> {
+        AddressLookupRepo::new(&self.pool).output(&mut *self.tx, txid, vout).await
+    }
+
+    async fn insert_unspent(&mut self, utxo: &UtxoCreateRecord) -> Result<bool, sqlx::Error> {
+        UtxosRepo::new(&self.pool).insert_unspent_if_absent(&mut *self.tx, utxo).await
+    }
+
+    async fn mark_spent(&mut self, out_txid: &str, out_vout: i32, spent_in_txid: &str) -> Result<bool, sqlx::Error> {
+        UtxosRepo::new(&self.pool)
+            .mark_spent_if_unspent(&mut *self.tx, out_txid, out_vout, spent_in_txid)
+            .await
+    }
+
+    async fn add_balance_delta(&mut self, address: &str, delta_sats: i64) -> Result<(), sqlx::Error> {
+        AddressBalancesRepo::new(&self.pool).add_delta(&mut *self.tx, address, delta_sats).await
+    }
+
+    async fn current_balance(&mut self, address: &str) -> Result<Option<i64>, sqlx::Error> {
+        AddressBalancesRepo::new(&self.pool).current_balance(&mut *self.tx, address).await
+    }
+
+    async fn upsert_balance_snapshot(
+        &mut self,
+        address: &str,
+        block_height: i32,
+        time: i64,
+        balance_sats: i64,
+    ) -> Result<(), sqlx::Error> {
+        AddressBalancesRepo::new(&self.pool)
+            .upsert_history_snapshot(&mut *self.tx, address, block_height, time, balance_sats)
+            .await
+    }
+
+    async fn save_checkpoint(&mut self, job_id: &str, height: i32, hash: &str) -> Result<(), sqlx::Error> {
+        JobCheckpointsRepo::new(&self.pool).save(&mut *self.tx, job_id, height, hash).await
+    }
+
+    fn postgres(&mut self) -> Option<&mut PgConnection> {
+        Some(&mut *self.tx)
+    }
+}
+
+pub(crate) async fn acquire_chain_state_lock<'e, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(CHAIN_STATE_LOCK_KEY)
+        .execute(executor)
+        .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn canonical_block_hash_at_height<'e, E>(
+    executor: E,
+    height: i32,
+) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'e, Database = Postgres>,
+{
+    let row = sqlx::query(
+        "SELECT hash \
+         FROM blocks \
+         WHERE height = $1 AND status = 'canonical' \
+         LIMIT 1",
+    )
+    .bind(height)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(row.map(|row| row.get::<String, _>("hash")))
+}
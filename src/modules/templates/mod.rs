@@ -0,0 +1,229 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::{info, warn};
+
+use crate::modules::config::IndexerConfig;
+use crate::modules::reload::ReloadableConfig;
+use crate::modules::rpc::{RpcClient, RpcError};
+use crate::modules::storage::SchemaFeatures;
+
+#[derive(Debug, Error)]
+pub enum BlockTemplateError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Subset of the `getblocktemplate` response that is kept for later analysis.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RpcBlockTemplate {
+    pub height: i32,
+    #[serde(rename = "previousblockhash")]
+    pub previous_block_hash: String,
+    pub bits: String,
+    pub curtime: i64,
+    #[serde(rename = "coinbasevalue")]
+    pub coinbase_value: i64,
+    pub transactions: Vec<RpcTemplateTransaction>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RpcTemplateTransaction {
+    pub txid: String,
+    #[serde(default)]
+    pub fee: i64,
+    #[serde(default)]
+    pub weight: i64,
+    #[serde(default)]
+    pub depends: Vec<u32>,
+}
+
+/// Row stored in `block_templates`; raw transaction data is dropped, only the
+/// projected tx set (txid, fee, weight, depends) is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockTemplateSnapshot {
+    pub height: i32,
+    pub previous_block_hash: String,
+    pub bits: String,
+    pub curtime: i64,
+    pub coinbase_value_sats: i64,
+    pub total_fees_sats: i64,
+    pub tx_count: i32,
+    pub total_weight: i64,
+    pub transactions: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockTemplateRunnerConfig {
+    pub poll_interval: Duration,
+}
+
+#[derive(Clone)]
+pub struct BlockTemplateRunner {
+    rpc: RpcClient,
+    pool: PgPool,
+    schema: SchemaFeatures,
+    config: ReloadableConfig<BlockTemplateRunnerConfig>,
+}
+
+impl BlockTemplateRunnerConfig {
+    /// Returns `None` when template ingestion is disabled.
+    pub fn from_config(config: &IndexerConfig) -> Option<Self> {
+        config.poll.block_template_interval_ms.map(|interval_ms| Self {
+            poll_interval: Duration::from_millis(interval_ms),
+        })
+    }
+}
+
+impl BlockTemplateRunner {
+    pub fn new(rpc: RpcClient, pool: PgPool, config: BlockTemplateRunnerConfig) -> Self {
+        Self {
+            rpc,
+            pool,
+            schema: SchemaFeatures::latest(),
+            config: ReloadableConfig::new(config),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn update_config(&self, config: BlockTemplateRunnerConfig) {
+        self.config.set(config);
+    }
+
+    fn poll_interval(&self) -> Duration {
+        self.config.get().poll_interval
+    }
+
+    pub fn start(&self) {
+        if !self.schema.block_templates {
+            warn!(
+                component = "block_templates",
+                message = "block_templates table is missing, template ingestion disabled"
+            );
+            return;
+        }
+
+        let runner = self.clone();
+        info!(
+            component = "block_templates",
+            poll_interval_ms = runner.poll_interval().as_millis() as u64,
+            message = "block template ingestion started"
+        );
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = runner.capture_once().await {
+                    warn!(component = "block_templates", error = %err, message = "block template capture failed");
+                }
+
+                tokio::time::sleep(runner.poll_interval()).await;
+            }
+        });
+    }
+
+    /// Fetches the current template and stores it, returning the snapshot id.
+    pub async fn capture_once(&self) -> Result<Option<i64>, BlockTemplateError> {
+        if !self.schema.block_templates {
+            return Ok(None);
+        }
+
+        let template = self.rpc.get_block_template().await?;
+        let snapshot = BlockTemplateSnapshot::from_template(&template);
+        let id = self.store_snapshot(&snapshot).await?;
+
+        Ok(Some(id))
+    }
+
+    async fn store_snapshot(&self, snapshot: &BlockTemplateSnapshot) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "INSERT INTO block_templates (
+               height, previous_block_hash, bits, curtime, coinbase_value_sats,
+               total_fees_sats, tx_count, total_weight, transactions
+             )
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING id",
+        )
+        .bind(snapshot.height)
+        .bind(&snapshot.previous_block_hash)
+        .bind(&snapshot.bits)
+        .bind(snapshot.curtime)
+        .bind(snapshot.coinbase_value_sats)
+        .bind(snapshot.total_fees_sats)
+        .bind(snapshot.tx_count)
+        .bind(snapshot.total_weight)
+        .bind(&snapshot.transactions)
+        .fetch_one(&self.pool)
+        .await
+    }
+}
+
+impl BlockTemplateSnapshot {
+    pub fn from_template(template: &RpcBlockTemplate) -> Self {
+        let transactions = template
+            .transactions
+            .iter()
+            .map(|tx| {
+                serde_json::json!({
+                    "txid": tx.txid,
+                    "fee_sats": tx.fee,
+                    "weight": tx.weight,
+                    "depends": tx.depends,
+                })
+            })
+            .collect();
+
+        Self {
+            height: template.height,
+            previous_block_hash: template.previous_block_hash.clone(),
+            bits: template.bits.clone(),
+            curtime: template.curtime,
+            coinbase_value_sats: template.coinbase_value,
+            total_fees_sats: template.transactions.iter().map(|tx| tx.fee).sum(),
+            tx_count: template.transactions.len() as i32,
+            total_weight: template.transactions.iter().map(|tx| tx.weight).sum(),
+            transactions: Value::Array(transactions),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockTemplateSnapshot, RpcBlockTemplate};
+
+    #[test]
+    fn summarizes_template_fees_and_tx_set() {
+        let template: RpcBlockTemplate = serde_json::from_value(serde_json::json!({
+            "version": 536870912,
+            "height": 101,
+            "previousblockhash": "00ab",
+            "bits": "207fffff",
+            "curtime": 1700000000,
+            "coinbasevalue": 5000001500_i64,
+            "transactions": [
+                { "data": "0200", "txid": "tx-a", "hash": "wtx-a", "depends": [], "fee": 1000, "sigops": 4, "weight": 560 },
+                { "data": "0200", "txid": "tx-b", "hash": "wtx-b", "depends": [1], "fee": 500, "sigops": 4, "weight": 440 }
+            ]
+        }))
+        .expect("template");
+
+        let snapshot = BlockTemplateSnapshot::from_template(&template);
+
+        assert_eq!(snapshot.height, 101);
+        assert_eq!(snapshot.coinbase_value_sats, 5_000_001_500);
+        assert_eq!(snapshot.total_fees_sats, 1_500);
+        assert_eq!(snapshot.tx_count, 2);
+        assert_eq!(snapshot.total_weight, 1_000);
+        assert_eq!(snapshot.transactions[1]["txid"], "tx-b");
+        assert_eq!(snapshot.transactions[1]["depends"], serde_json::json!([1]));
+        assert!(snapshot.transactions[0].get("data").is_none());
+    }
+}
@@ -0,0 +1,374 @@
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use rustls::crypto::ring::sign::any_supported_type;
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::{InconsistentKeys, ServerConfig};
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+use serde::Serialize;
+use thiserror::Error;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+
+use crate::modules::config::{AppConfig, TlsConfig};
+
+/// Time a client gets to complete the TLS handshake before its connection is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Handshaken connections waiting for the server to accept them.
+const ACCEPT_BACKLOG: usize = 128;
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("'{path}' is not a valid PEM certificate: {reason}")]
+    Certificate { path: String, reason: String },
+    #[error("'{path}' is not a valid PEM private key: {reason}")]
+    PrivateKey { path: String, reason: String },
+    #[error("private key '{key_path}' does not match certificate '{cert_path}'")]
+    KeyMismatch { cert_path: String, key_path: String },
+    #[error("certificate '{path}' ({subject}) expired at {not_after}")]
+    Expired {
+        path: String,
+        subject: String,
+        not_after: DateTime<Utc>,
+    },
+    #[error("certificate '{path}' ({subject}) is not valid before {not_before}")]
+    NotYetValid {
+        path: String,
+        subject: String,
+        not_before: DateTime<Utc>,
+    },
+}
+
+/// Validity of a certificate that passed the preflight.
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct CertificateStatus {
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+    pub subject: String,
+    pub not_after: DateTime<Utc>,
+    /// Whole days left until `not_after`.
+    pub days_left: i64,
+    /// Expires within `server.tls.expiry_warning_days`.
+    pub expiring: bool,
+}
+
+/// Certificate the API serves over HTTPS. Clones share the pair, so [`Self::replace`] after
+/// a rotation reaches every listener; connections already established keep their session.
+#[derive(Debug, Clone)]
+pub struct ServerCertResolver {
+    key: Arc<RwLock<Arc<CertifiedKey>>>,
+}
+
+impl ServerCertResolver {
+    pub fn new(key: CertifiedKey) -> Self {
+        Self {
+            key: Arc::new(RwLock::new(Arc::new(key))),
+        }
+    }
+
+    /// Loads the `server.tls` pair; `None` when HTTPS is disabled.
+    pub fn from_config(config: &TlsConfig) -> Result<Option<Self>, TlsError> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        load_key_pair(&config.cert_path, &config.key_path).map(|key| Some(Self::new(key)))
+    }
+
+    /// Serves `key` to new TLS handshakes.
+    pub fn replace(&self, key: CertifiedKey) {
+        *self.key.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(key);
+    }
+
+    pub fn server_config(&self) -> Arc<ServerConfig> {
+        let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("ring supports the default protocol versions")
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(self.clone()));
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Arc::new(config)
+    }
+}
+
+impl ResolvesServerCert for ServerCertResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.key.read().unwrap_or_else(|err| err.into_inner()).clone())
+    }
+}
+
+/// TCP listener that completes the TLS handshake before handing a connection to the server.
+/// Handshakes run in their own tasks, so a slow client does not hold up the others.
+pub struct TlsListener {
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, certificates: &ServerCertResolver) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (connections_tx, connections) = mpsc::channel(ACCEPT_BACKLOG);
+        tokio::spawn(accept_connections(
+            listener,
+            TlsAcceptor::from(certificates.server_config()),
+            connections_tx,
+        ));
+
+        Ok(Self { connections, local_addr })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // The accept loop only stops once this listener is dropped.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+/// Accepts connections until the [`TlsListener`] is dropped, e.g. on shutdown.
+async fn accept_connections(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    connections: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(err) if is_connection_error(&err) => continue,
+                Err(err) => {
+                    // E.g. too many open files: back off instead of spinning.
+                    warn!(component = "api", error = %err, message = "failed to accept connection");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            _ = connections.closed() => return,
+        };
+
+        let acceptor = acceptor.clone();
+        let connections = connections.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = connections.send((stream, peer)).await;
+                }
+                Ok(Err(err)) => debug!(component = "api", peer = %peer, error = %err, message = "tls handshake failed"),
+                Err(_) => debug!(component = "api", peer = %peer, message = "tls handshake timed out"),
+            }
+        });
+    }
+}
+
+fn is_connection_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+    )
+}
+
+/// Parses the server TLS pair and, with RPC mTLS enabled, the client pair and the CA bundle.
+/// Fails when a key does not belong to its certificate or a certificate is outside its
+/// validity period, instead of at the first TLS handshake.
+pub fn preflight(config: &AppConfig, now: DateTime<Utc>) -> Result<Vec<CertificateStatus>, TlsError> {
+    let warning_days = i64::from(config.server.tls.expiry_warning_days);
+    let mut statuses = vec![check_key_pair(
+        &config.server.tls.cert_path,
+        &config.server.tls.key_path,
+        now,
+        warning_days,
+    )?];
+    let instance_rpcs = config.instances.iter().map(|instance| &instance.rpc);
+    for mtls in std::iter::once(&config.rpc)
+        .chain(instance_rpcs)
+        .filter_map(|rpc| rpc.mtls.as_ref())
+    {
+        statuses.push(check_key_pair(
+            &mtls.client_cert_path,
+            &mtls.client_key_path,
+            now,
+            warning_days,
+        )?);
+        for certificate in read_certificates(&mtls.ca_path)? {
+            statuses.push(certificate_status(&mtls.ca_path, &certificate, now, warning_days)?);
+        }
+    }
+
+    Ok(statuses)
+}
+
+/// Logs a `warn` for every certificate within `server.tls.expiry_warning_days` of expiry.
+pub fn warn_expiring(certificates: &[CertificateStatus]) {
+    for certificate in certificates.iter().filter(|certificate| certificate.expiring) {
+        warn!(
+            component = "config",
+            path = %certificate.path.display(),
+            subject = %certificate.subject,
+            not_after = %certificate.not_after,
+            days_left = certificate.days_left,
+            message = "certificate expires soon"
+        );
+    }
+}
+
+/// Checks that the key signs for the first certificate of `cert_path` and that it is valid
+/// at `now`. Key types whose public key cannot be derived are accepted, as rustls does.
+pub fn check_key_pair(
+    cert_path: &Path,
+    key_path: &Path,
+    now: DateTime<Utc>,
+    warning_days: i64,
+) -> Result<CertificateStatus, TlsError> {
+    let certified = load_key_pair(cert_path, key_path)?;
+    certificate_status(cert_path, &certified.cert[0], now, warning_days)
+}
+
+/// Reads a certificate chain and the key that signs for its first certificate.
+pub fn load_key_pair(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, TlsError> {
+    let chain = read_certificates(cert_path)?;
+    let key_error = |reason: String| TlsError::PrivateKey {
+        path: key_path.display().to_string(),
+        reason,
+    };
+    let key = PrivateKeyDer::from_pem_file(key_path).map_err(|err| key_error(err.to_string()))?;
+    let signing_key = any_supported_type(&key).map_err(|err| key_error(err.to_string()))?;
+
+    let certified = CertifiedKey::new(chain, signing_key);
+    match certified.keys_match() {
+        Ok(()) | Err(rustls::Error::InconsistentKeys(InconsistentKeys::Unknown)) => Ok(certified),
+        Err(_) => Err(TlsError::KeyMismatch {
+            cert_path: cert_path.display().to_string(),
+            key_path: key_path.display().to_string(),
+        }),
+    }
+}
+
+fn read_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, TlsError> {
+    let certificate_error = |reason: String| TlsError::Certificate {
+        path: path.display().to_string(),
+        reason,
+    };
+    let certificates = CertificateDer::pem_file_iter(path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(|err| certificate_error(err.to_string()))?;
+    if certificates.is_empty() {
+        return Err(certificate_error("no CERTIFICATE block found".to_string()));
+    }
+
+    Ok(certificates)
+}
+
+fn certificate_status(
+    path: &Path,
+    certificate: &CertificateDer<'_>,
+    now: DateTime<Utc>,
+    warning_days: i64,
+) -> Result<CertificateStatus, TlsError> {
+    let (_, parsed) = x509_parser::parse_x509_certificate(certificate.as_ref()).map_err(|err| TlsError::Certificate {
+        path: path.display().to_string(),
+        reason: err.to_string(),
+    })?;
+    let subject = parsed.subject().to_string();
+    let validity = parsed.validity();
+    let not_before = DateTime::from_timestamp(validity.not_before.timestamp(), 0).unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let not_after = DateTime::from_timestamp(validity.not_after.timestamp(), 0).unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+    if now < not_before {
+        return Err(TlsError::NotYetValid {
+            path: path.display().to_string(),
+            subject,
+            not_before,
+        });
+    }
+    if now > not_after {
+        return Err(TlsError::Expired {
+            path: path.display().to_string(),
+            subject,
+            not_after,
+        });
+    }
+
+    let days_left = (not_after - now).num_days();
+    Ok(CertificateStatus {
+        path: path.to_path_buf(),
+        subject,
+        not_after,
+        days_left,
+        expiring: days_left < warning_days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use chrono::{DateTime, Duration};
+    use rcgen::{date_time_ymd, CertificateParams, KeyPair};
+    use tempfile::tempdir;
+
+    use super::{check_key_pair, TlsError};
+
+    fn write_pair(dir: &Path, name: &str, params: CertificateParams, key: &KeyPair) {
+        let certificate = params.self_signed(key).expect("self-signed certificate");
+        fs::write(dir.join(format!("{name}.crt")), certificate.pem()).expect("write certificate");
+        fs::write(dir.join(format!("{name}.key")), key.serialize_pem()).expect("write key");
+    }
+
+    #[test]
+    fn checks_key_match_and_validity_period() {
+        let dir = tempdir().expect("tempdir");
+        let now = DateTime::parse_from_rfc3339("2026-02-20T00:00:00Z").expect("now").to_utc();
+        let key = KeyPair::generate().expect("key");
+        let params = || {
+            let mut params = CertificateParams::new(vec!["indexer.local".to_string()]).expect("params");
+            params.not_before = date_time_ymd(2026, 1, 1);
+            params.not_after = date_time_ymd(2026, 3, 1);
+            params
+        };
+        write_pair(dir.path(), "server", params(), &key);
+        write_pair(dir.path(), "other", params(), &KeyPair::generate().expect("key"));
+
+        let status = check_key_pair(&dir.path().join("server.crt"), &dir.path().join("server.key"), now, 30)
+            .expect("matching pair");
+        assert!(status.expiring);
+        assert_eq!(status.days_left, 9);
+        assert!(!check_key_pair(&dir.path().join("server.crt"), &dir.path().join("server.key"), now, 5)
+            .expect("matching pair")
+            .expiring);
+
+        let mismatch = check_key_pair(&dir.path().join("server.crt"), &dir.path().join("other.key"), now, 30);
+        assert!(matches!(mismatch, Err(TlsError::KeyMismatch { .. })));
+
+        let expired = check_key_pair(
+            &dir.path().join("server.crt"),
+            &dir.path().join("server.key"),
+            now + Duration::days(10),
+            30,
+        );
+        assert!(matches!(expired, Err(TlsError::Expired { .. })));
+
+        fs::write(dir.path().join("garbage.crt"), b"x").expect("write file");
+        let garbage = check_key_pair(&dir.path().join("garbage.crt"), &dir.path().join("server.key"), now, 30);
+        assert!(matches!(garbage, Err(TlsError::Certificate { .. })));
+    }
+}
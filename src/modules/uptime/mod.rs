@@ -0,0 +1,311 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+use crate::modules::storage::SchemaFeatures;
+
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const DEFAULT_EVENTS_LIMIT: i64 = 50;
+const MAX_EVENTS_LIMIT: i64 = 500;
+/// An instance that missed this many heartbeats without a stop event is reported as crashed.
+const STALE_HEARTBEATS: u32 = 3;
+
+#[derive(Debug, Error)]
+pub enum UptimeError {
+    #[error("validation error: {0}")]
+    Validation(String),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProcessEvent {
+    pub id: i64,
+    pub instance_id: String,
+    pub event: String,
+    pub occurred_at: DateTime<Utc>,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub details: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UptimeReport {
+    pub instance_id: String,
+    pub started_at: DateTime<Utc>,
+    pub uptime_seconds: i64,
+    pub starts_last_24h: i64,
+    pub crashes_last_24h: i64,
+    pub events: Vec<ProcessEvent>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UptimeService {
+    pool: PgPool,
+    instance_id: String,
+    started_at: DateTime<Utc>,
+    schema: SchemaFeatures,
+}
+
+#[derive(Debug, Clone)]
+pub struct UptimeRunnerConfig {
+    pub heartbeat_interval: Duration,
+}
+
+#[derive(Clone)]
+pub struct UptimeRunner {
+    uptime: UptimeService,
+    config: UptimeRunnerConfig,
+}
+
+impl UptimeService {
+    pub fn new(pool: PgPool) -> Self {
+        let started_at = Utc::now();
+        let host = std::env::var("HOSTNAME").unwrap_or_else(|_| "indexer".to_string());
+
+        Self {
+            pool,
+            instance_id: format!("{host}-{}-{:x}", std::process::id(), started_at.timestamp_micros()),
+            started_at,
+            schema: SchemaFeatures::latest(),
+        }
+    }
+
+    pub fn with_schema_features(mut self, schema: SchemaFeatures) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    /// Marks stale instances as crashed and records the start of this process.
+    pub async fn record_start(&self, heartbeat_interval: Duration) -> Result<(), UptimeError> {
+        if !self.schema.process_events {
+            return Ok(());
+        }
+
+        self.mark_crashed_instances(heartbeat_interval * STALE_HEARTBEATS).await?;
+
+        sqlx::query(
+            "INSERT INTO process_events (instance_id, event, occurred_at, last_seen_at, details)
+             VALUES ($1, 'started', $2, $2, $3)",
+        )
+        .bind(&self.instance_id)
+        .bind(self.started_at)
+        .bind(serde_json::json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "pid": std::process::id(),
+        }))
+        .execute(&self.pool)
+        .await?;
+
+        info!(component = "uptime", instance_id = %self.instance_id, message = "process start recorded");
+        Ok(())
+    }
+
+    pub async fn record_stop(&self, reason: &str) -> Result<(), UptimeError> {
+        if !self.schema.process_events {
+            return Ok(());
+        }
+
+        sqlx::query(
+            "INSERT INTO process_events (instance_id, event, occurred_at, details)
+             VALUES ($1, 'stopped', NOW(), $2)
+             ON CONFLICT (instance_id) WHERE event IN ('stopped', 'crashed') DO NOTHING",
+        )
+        .bind(&self.instance_id)
+        .bind(serde_json::json!({
+            "reason": reason,
+            "uptime_seconds": self.uptime_seconds(),
+        }))
+        .execute(&self.pool)
+        .await?;
+
+        info!(component = "uptime", instance_id = %self.instance_id, reason, message = "process stop recorded");
+        Ok(())
+    }
+
+    pub async fn heartbeat(&self) -> Result<(), UptimeError> {
+        if !self.schema.process_events {
+            return Ok(());
+        }
+
+        sqlx::query(
+            "UPDATE process_events
+             SET last_seen_at = NOW()
+             WHERE instance_id = $1 AND event = 'started'",
+        )
+        .bind(&self.instance_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Adds a `crashed` marker for every started instance whose heartbeat is
+    /// older than `stale_after` and which never recorded a stop.
+    pub async fn mark_crashed_instances(&self, stale_after: Duration) -> Result<u64, UptimeError> {
+        if !self.schema.process_events {
+            return Ok(0);
+        }
+
+        let marked = sqlx::query(
+            "INSERT INTO process_events (instance_id, event, occurred_at, details)
+             SELECT s.instance_id,
+                    'crashed',
+                    COALESCE(s.last_seen_at, s.occurred_at),
+                    jsonb_build_object('detected_by', $1::TEXT)
+             FROM process_events s
+             WHERE s.event = 'started'
+               AND s.instance_id <> $1
+               AND COALESCE(s.last_seen_at, s.occurred_at) < NOW() - make_interval(secs => $2)
+               AND NOT EXISTS (
+                 SELECT 1
+                 FROM process_events t
+                 WHERE t.instance_id = s.instance_id
+                   AND t.event IN ('stopped', 'crashed')
+               )
+             ON CONFLICT (instance_id) WHERE event IN ('stopped', 'crashed') DO NOTHING",
+        )
+        .bind(&self.instance_id)
+        .bind(stale_after.as_secs_f64())
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        if marked > 0 {
+            warn!(component = "uptime", crashed_instances = marked, message = "crashed instances detected");
+        }
+
+        Ok(marked)
+    }
+
+    pub async fn report(&self, limit: Option<i64>) -> Result<UptimeReport, UptimeError> {
+        let limit = validate_limit(limit)?;
+        let (events, starts_last_24h, crashes_last_24h) = if self.schema.process_events {
+            let rows: Vec<ProcessEventRow> = sqlx::query_as(
+                "SELECT id, instance_id, event, occurred_at, last_seen_at, details
+                 FROM process_events
+                 ORDER BY occurred_at DESC, id DESC
+                 LIMIT $1",
+            )
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+            let counts: CountsRow = sqlx::query_as(
+                "SELECT COUNT(*) FILTER (WHERE event = 'started') AS starts,
+                        COUNT(*) FILTER (WHERE event = 'crashed') AS crashes
+                 FROM process_events
+                 WHERE occurred_at >= NOW() - INTERVAL '24 hours'",
+            )
+            .fetch_one(&self.pool)
+            .await?;
+
+            (
+                rows.into_iter().map(ProcessEvent::from).collect(),
+                counts.starts,
+                counts.crashes,
+            )
+        } else {
+            (Vec::new(), 0, 0)
+        };
+
+        Ok(UptimeReport {
+            instance_id: self.instance_id.clone(),
+            started_at: self.started_at,
+            uptime_seconds: self.uptime_seconds(),
+            starts_last_24h,
+            crashes_last_24h,
+            events,
+        })
+    }
+
+    fn uptime_seconds(&self) -> i64 {
+        (Utc::now() - self.started_at).num_seconds().max(0)
+    }
+}
+
+impl UptimeRunner {
+    pub fn new(uptime: UptimeService, config: UptimeRunnerConfig) -> Self {
+        Self { uptime, config }
+    }
+
+    pub fn start(&self) {
+        let runner = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(runner.config.heartbeat_interval).await;
+
+                if let Err(err) = runner.uptime.heartbeat().await {
+                    warn!(component = "uptime", error = %err, message = "heartbeat failed");
+                }
+
+                let stale_after = runner.config.heartbeat_interval * STALE_HEARTBEATS;
+                if let Err(err) = runner.uptime.mark_crashed_instances(stale_after).await {
+                    warn!(component = "uptime", error = %err, message = "crash detection failed");
+                }
+            }
+        });
+    }
+}
+
+fn validate_limit(limit: Option<i64>) -> Result<i64, UptimeError> {
+    let limit = limit.unwrap_or(DEFAULT_EVENTS_LIMIT);
+    if !(1..=MAX_EVENTS_LIMIT).contains(&limit) {
+        return Err(UptimeError::Validation(format!(
+            "limit MUST be between 1 and {MAX_EVENTS_LIMIT}"
+        )));
+    }
+
+    Ok(limit)
+}
+
+impl From<ProcessEventRow> for ProcessEvent {
+    fn from(row: ProcessEventRow) -> Self {
+        Self {
+            id: row.id,
+            instance_id: row.instance_id,
+            event: row.event,
+            occurred_at: row.occurred_at,
+            last_seen_at: row.last_seen_at,
+            details: row.details,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct ProcessEventRow {
+    id: i64,
+    instance_id: String,
+    event: String,
+    occurred_at: DateTime<Utc>,
+    last_seen_at: Option<DateTime<Utc>>,
+    details: serde_json::Value,
+}
+
+#[derive(Debug, FromRow)]
+struct CountsRow {
+    starts: i64,
+    crashes: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_limit, DEFAULT_EVENTS_LIMIT};
+
+    #[test]
+    fn validates_events_limit() {
+        assert_eq!(validate_limit(None).unwrap(), DEFAULT_EVENTS_LIMIT);
+        assert_eq!(validate_limit(Some(500)).unwrap(), 500);
+        assert!(validate_limit(Some(0)).is_err());
+        assert!(validate_limit(Some(501)).is_err());
+    }
+}
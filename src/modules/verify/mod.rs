@@ -0,0 +1,301 @@
+//! Consistency checks of `verify` jobs: stored blocks against the node, transaction rows
+//! against their block, inputs against stored outputs and address balances against UTXOs.
+//! Discrepancies are kept in `job_discrepancies` (`0031_job_verify.sql`).
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::{FromRow, PgPool};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+use crate::modules::rpc::{RpcClient, RpcError};
+
+pub const KIND_MISSING_BLOCK: &str = "missing_block";
+pub const KIND_HASH_MISMATCH: &str = "hash_mismatch";
+pub const KIND_TX_COUNT_MISMATCH: &str = "tx_count_mismatch";
+pub const KIND_MISSING_PREVOUT: &str = "missing_prevout";
+pub const KIND_BALANCE_MISMATCH: &str = "balance_mismatch";
+
+pub const KINDS: [&str; 5] = [
+    KIND_MISSING_BLOCK,
+    KIND_HASH_MISMATCH,
+    KIND_TX_COUNT_MISMATCH,
+    KIND_MISSING_PREVOUT,
+    KIND_BALANCE_MISMATCH,
+];
+
+/// Inputs without a stored output reported per block, and balances per check.
+const MAX_REPORTED_ROWS: i64 = 100;
+
+#[derive(Debug, Error)]
+pub enum VerifyError {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    #[error(transparent)]
+    Storage(#[from] sqlx::Error),
+}
+
+/// Discrepancy found by one check, before it is recorded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub kind: &'static str,
+    pub height: Option<i32>,
+    pub block_hash: Option<String>,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+pub struct DiscrepancyItem {
+    pub id: i64,
+    pub job_id: String,
+    /// `missing_block`, `hash_mismatch`, `tx_count_mismatch`, `missing_prevout` or `balance_mismatch`.
+    pub kind: String,
+    /// `null` for `balance_mismatch`, which is checked once over all addresses.
+    pub height: Option<i32>,
+    pub block_hash: Option<String>,
+    pub detail: String,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct InputRow {
+    txid: String,
+    vin: i32,
+    prev_txid: String,
+    prev_vout: i32,
+    prev_tx_stored: bool,
+}
+
+#[derive(Debug, FromRow)]
+struct BalanceRow {
+    address: String,
+    balance_sats: i64,
+    utxo_sats: i64,
+}
+
+/// Whether the canonical chain is indexed from genesis, so every input must spend a stored
+/// output. Otherwise inputs spending transactions below the lowest indexed height are skipped.
+pub async fn history_from_genesis(pool: &PgPool) -> Result<bool, VerifyError> {
+    let indexed: Option<i32> = sqlx::query_scalar("SELECT height FROM blocks WHERE height = 0 AND status = 'canonical'")
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(indexed.is_some())
+}
+
+/// Checks the canonical block at `height`: its hash against the node, its transaction count
+/// against the node's block and its inputs against stored outputs.
+pub async fn verify_height(
+    pool: &PgPool,
+    rpc: &RpcClient,
+    height: i32,
+    history_from_genesis: bool,
+) -> Result<Vec<Discrepancy>, VerifyError> {
+    let stored: Option<String> = sqlx::query_scalar("SELECT hash FROM blocks WHERE height = $1 AND status = 'canonical'")
+        .bind(height)
+        .fetch_optional(pool)
+        .await?;
+    let Some(hash) = stored else {
+        return Ok(vec![Discrepancy {
+            kind: KIND_MISSING_BLOCK,
+            height: Some(height),
+            block_hash: None,
+            detail: "no canonical block is indexed at this height".to_string(),
+        }]);
+    };
+
+    let node_hash = rpc.get_block_hash(height as u32).await?;
+    if node_hash != hash {
+        return Ok(vec![Discrepancy {
+            kind: KIND_HASH_MISMATCH,
+            height: Some(height),
+            block_hash: Some(hash),
+            detail: format!("node has block {node_hash} at this height"),
+        }]);
+    }
+
+    let mut discrepancies = Vec::new();
+    let node_tx_count = block_tx_count(&rpc.get_block(&hash, 1).await?);
+    let stored_tx_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE block_hash = $1 AND status = 'confirmed'")
+            .bind(&hash)
+            .fetch_one(pool)
+            .await?;
+    if node_tx_count != Some(stored_tx_count) {
+        discrepancies.push(Discrepancy {
+            kind: KIND_TX_COUNT_MISMATCH,
+            height: Some(height),
+            block_hash: Some(hash.clone()),
+            detail: format!(
+                "{stored_tx_count} transactions stored, node block has {}",
+                node_tx_count.map_or_else(|| "no tx list".to_string(), |count| count.to_string())
+            ),
+        });
+    }
+
+    let inputs: Vec<InputRow> = sqlx::query_as(
+        "SELECT i.txid, i.vin, i.prev_txid, i.prev_vout, \
+           EXISTS (SELECT 1 FROM transactions p WHERE p.txid = i.prev_txid) AS prev_tx_stored \
+         FROM tx_inputs i \
+         JOIN transactions t ON t.txid = i.txid \
+         WHERE t.block_hash = $1 \
+           AND NOT EXISTS (SELECT 1 FROM tx_outputs o WHERE o.txid = i.prev_txid AND o.vout = i.prev_vout) \
+         ORDER BY i.txid, i.vin \
+         LIMIT $2",
+    )
+    .bind(&hash)
+    .bind(MAX_REPORTED_ROWS)
+    .fetch_all(pool)
+    .await?;
+    discrepancies.extend(
+        inputs
+            .into_iter()
+            .filter(|input| input.prev_tx_stored || history_from_genesis)
+            .map(|input| Discrepancy {
+                kind: KIND_MISSING_PREVOUT,
+                height: Some(height),
+                block_hash: Some(hash.clone()),
+                detail: if input.prev_tx_stored {
+                    format!(
+                        "input {}:{} spends output {} of {}, which is not stored",
+                        input.txid, input.vin, input.prev_vout, input.prev_txid
+                    )
+                } else {
+                    format!(
+                        "input {}:{} spends {}:{}, whose transaction is not stored",
+                        input.txid, input.vin, input.prev_txid, input.prev_vout
+                    )
+                },
+            }),
+    );
+
+    Ok(discrepancies)
+}
+
+/// Addresses whose `address_balance_current` differs from the sum of their unspent
+/// `utxos_current`. Both are updated in the transaction of a block, so one statement
+/// sees them consistent.
+pub async fn verify_balances(pool: &PgPool) -> Result<Vec<Discrepancy>, VerifyError> {
+    let rows: Vec<BalanceRow> = sqlx::query_as(
+        "SELECT COALESCE(b.address, u.address) AS address, \
+           COALESCE(b.balance_sats, 0) AS balance_sats, \
+           COALESCE(u.utxo_sats, 0) AS utxo_sats \
+         FROM address_balance_current b \
+         FULL OUTER JOIN ( \
+           SELECT address, SUM(value_sats)::BIGINT AS utxo_sats \
+           FROM utxos_current \
+           WHERE status = 'unspent' \
+           GROUP BY address \
+         ) u ON u.address = b.address \
+         WHERE COALESCE(b.balance_sats, 0) <> COALESCE(u.utxo_sats, 0) \
+         ORDER BY 1 \
+         LIMIT $1",
+    )
+    .bind(MAX_REPORTED_ROWS)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| Discrepancy {
+            kind: KIND_BALANCE_MISMATCH,
+            height: None,
+            block_hash: None,
+            detail: format!(
+                "address {} has balance {} sats, unspent outputs sum to {} sats",
+                row.address, row.balance_sats, row.utxo_sats
+            ),
+        })
+        .collect())
+}
+
+/// Replaces the discrepancies of `job_id` found earlier at `heights`, or by the balance
+/// check when `heights` is `None`, so a verified chunk that is verified again is not
+/// reported twice.
+pub async fn record(
+    pool: &PgPool,
+    job_id: &str,
+    heights: Option<(i32, i32)>,
+    discrepancies: &[Discrepancy],
+) -> Result<(), VerifyError> {
+    let mut tx = pool.begin().await?;
+    match heights {
+        Some((from_height, to_height)) => {
+            sqlx::query("DELETE FROM job_discrepancies WHERE job_id = $1 AND height BETWEEN $2 AND $3")
+                .bind(job_id)
+                .bind(from_height)
+                .bind(to_height)
+                .execute(&mut *tx)
+                .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM job_discrepancies WHERE job_id = $1 AND height IS NULL")
+                .bind(job_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    for discrepancy in discrepancies {
+        sqlx::query(
+            "INSERT INTO job_discrepancies (job_id, kind, height, block_hash, detail) \
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(job_id)
+        .bind(discrepancy.kind)
+        .bind(discrepancy.height)
+        .bind(&discrepancy.block_hash)
+        .bind(&discrepancy.detail)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Recorded discrepancies of `job_id` by height, those without a height last.
+pub async fn list(
+    pool: &PgPool,
+    job_id: &str,
+    kind: Option<&str>,
+    limit: i64,
+) -> Result<Vec<DiscrepancyItem>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT id, job_id, kind, height, block_hash, detail, detected_at \
+         FROM job_discrepancies \
+         WHERE job_id = $1 AND ($2::TEXT IS NULL OR kind = $2) \
+         ORDER BY height ASC NULLS LAST, id ASC \
+         LIMIT $3",
+    )
+    .bind(job_id)
+    .bind(kind)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Transactions of a `getblock` verbosity 1 response.
+fn block_tx_count(block: &Value) -> Option<i64> {
+    block
+        .get("nTx")
+        .and_then(Value::as_i64)
+        .or_else(|| block.get("tx").and_then(Value::as_array).map(|tx| tx.len() as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tx_count_of_verbose_block() {
+        let block = serde_json::json!({ "hash": "00ab", "nTx": 3, "tx": ["a", "b", "c"] });
+        assert_eq!(block_tx_count(&block), Some(3));
+
+        // Nodes without `nTx` still list the txids.
+        let block = serde_json::json!({ "hash": "00ab", "tx": ["a", "b"] });
+        assert_eq!(block_tx_count(&block), Some(2));
+        assert_eq!(block_tx_count(&serde_json::json!({ "hash": "00ab" })), None);
+    }
+}
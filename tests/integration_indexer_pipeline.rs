@@ -1,9 +1,16 @@
+use bitcoin_blockchain_indexer::modules::config::DatabaseConfig;
+use bitcoin_blockchain_indexer::modules::data::DataService;
+use bitcoin_blockchain_indexer::modules::filters;
 use bitcoin_blockchain_indexer::modules::indexer::{
-    IndexerPipeline, PersistBlockOutcome, RpcBlock, RpcScriptPubKey, RpcTransaction, RpcVin, RpcVout,
+    BlockProvenance, IndexerPipeline, PersistBlockOutcome, RpcBlock, RpcPrevout, RpcScriptPubKey, RpcTransaction,
+    RpcVin, RpcVout, PIPELINE_VERSION,
 };
 use bitcoin_blockchain_indexer::modules::mempool::list_mempool_txids_for_address;
 use bitcoin_blockchain_indexer::modules::metrics::MetricsService;
-use bitcoin_blockchain_indexer::modules::storage::Storage;
+use bitcoin_blockchain_indexer::modules::retention::{PruneReport, RetentionRunnerConfig, RetentionService};
+use bitcoin_blockchain_indexer::modules::stats::{StatsFilter, StatsReport, StatsRunner, StatsRunnerConfig, StatsService};
+use bitcoin_blockchain_indexer::modules::storage::repo::{TransactionRecord, TransactionsRepo};
+use bitcoin_blockchain_indexer::modules::storage::{SchemaFeatures, Storage};
 use sqlx::{PgPool, Row};
 use testcontainers::core::WaitFor;
 use testcontainers::{clients::Cli, GenericImage};
@@ -38,7 +45,7 @@ async fn setup_db() -> Option<PgPool> {
     std::env::set_var("DATABASE_URL", &database_url);
     std::env::set_var("MIGRATIONS_PATH", "migrations");
 
-    let storage = Storage::connect().await.expect("connect storage");
+    let storage = Storage::connect(&DatabaseConfig::default()).await.expect("connect storage");
     storage
         .apply_migrations()
         .await
@@ -59,10 +66,12 @@ fn block_zero() -> RpcBlock {
                 txid: None,
                 vout: None,
                 sequence: 0,
+                txinwitness: None,
+                prevout: None,
             }],
             vout: vec![RpcVout {
                 n: 0,
-                value: 50.0,
+                value_sats: 5_000_000_000,
                 script_pub_key: RpcScriptPubKey {
                     script_type: "pubkeyhash".to_string(),
                     hex: "0014coinbase0".to_string(),
@@ -70,6 +79,11 @@ fn block_zero() -> RpcBlock {
                     addresses: None,
                 },
             }],
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
         }],
     }
 }
@@ -86,11 +100,13 @@ fn block_one() -> RpcBlock {
                 txid: Some("coinbase0".to_string()),
                 vout: Some(0),
                 sequence: 1,
+                txinwitness: None,
+                prevout: None,
             }],
             vout: vec![
                 RpcVout {
                     n: 0,
-                    value: 20.0,
+                    value_sats: 2_000_000_000,
                     script_pub_key: RpcScriptPubKey {
                         script_type: "pubkeyhash".to_string(),
                         hex: "0014change1".to_string(),
@@ -100,7 +116,7 @@ fn block_one() -> RpcBlock {
                 },
                 RpcVout {
                     n: 1,
-                    value: 30.0,
+                    value_sats: 3_000_000_000,
                     script_pub_key: RpcScriptPubKey {
                         script_type: "pubkeyhash".to_string(),
                         hex: "0014pay1".to_string(),
@@ -109,6 +125,11 @@ fn block_one() -> RpcBlock {
                     },
                 },
             ],
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
         }],
     }
 }
@@ -199,6 +220,56 @@ async fn indexer_pipeline_persists_blocks_utxos_and_balances() {
     assert_eq!(history_rows[2].get::<i64, _>("balance_sats"), 3_000_000_000);
 }
 
+#[tokio::test]
+#[ignore]
+async fn blocks_written_below_indexed_ones_settle_utxos_and_balances() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block_from(&block_one(), 1).await.expect("persist block 1");
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+
+    let spent_status = sqlx::query(
+        "SELECT status, spent_in_txid
+         FROM utxos_current
+         WHERE out_txid = 'coinbase0' AND out_vout = 0",
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("load spent utxo");
+    assert_eq!(spent_status.get::<String, _>("status"), "spent");
+    assert_eq!(spent_status.get::<String, _>("spent_in_txid"), "spend1");
+
+    let current_balances: Vec<(String, i64)> =
+        sqlx::query_as("SELECT address, balance_sats FROM address_balance_current ORDER BY address")
+            .fetch_all(&pool)
+            .await
+            .expect("load current balances");
+    assert_eq!(
+        current_balances,
+        vec![("addr1".to_string(), 2_000_000_000), ("addr2".to_string(), 3_000_000_000)]
+    );
+
+    let history: Vec<(String, i32, i64)> = sqlx::query_as(
+        "SELECT address, block_height, balance_sats
+         FROM address_balance_history
+         ORDER BY block_height, address",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load balance history");
+    assert_eq!(
+        history,
+        vec![
+            ("addr1".to_string(), 0, 5_000_000_000),
+            ("addr1".to_string(), 1, 2_000_000_000),
+            ("addr2".to_string(), 1, 3_000_000_000),
+        ]
+    );
+}
+
 #[tokio::test]
 #[ignore]
 async fn indexer_pipeline_is_idempotent_and_waits_for_previous_height() {
@@ -281,3 +352,1089 @@ async fn mempool_lookup_returns_transactions_matching_address_in_inputs_and_outp
     assert_eq!(matches[1].txid, "mempool-out");
     assert_eq!(matches[1].addresses, vec!["addr1".to_string()]);
 }
+
+fn fee_vin(prev_txid: &str, value_sats: Option<i64>) -> RpcVin {
+    RpcVin {
+        txid: Some(prev_txid.to_string()),
+        vout: Some(0),
+        sequence: 1,
+        txinwitness: None,
+        prevout: value_sats.map(|value_sats| RpcPrevout {
+            value_sats,
+            script_pub_key: None,
+        }),
+    }
+}
+
+fn fee_vout(value_sats: i64) -> RpcVout {
+    RpcVout {
+        n: 0,
+        value_sats,
+        script_pub_key: RpcScriptPubKey {
+            script_type: "witness_v0_keyhash".to_string(),
+            hex: "0014fee".to_string(),
+            address: Some("addr3".to_string()),
+            addresses: None,
+        },
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn indexer_pipeline_records_transaction_and_block_fees() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+
+    let block = RpcBlock {
+        hash: "blockhash1".to_string(),
+        height: 1,
+        prev_hash: Some("blockhash0".to_string()),
+        time: 1_700_000_060,
+        tx: vec![
+            RpcTransaction {
+                txid: "from-prevout".to_string(),
+                vin: vec![fee_vin("coinbase0", Some(5_000_000_000))],
+                vout: vec![fee_vout(4_999_990_000)],
+                fee_sats: None,
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+            RpcTransaction {
+                txid: "from-indexed-output".to_string(),
+                vin: vec![fee_vin("from-prevout", None)],
+                vout: vec![fee_vout(4_999_970_000)],
+                fee_sats: None,
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+            RpcTransaction {
+                txid: "reported-by-node".to_string(),
+                vin: vec![fee_vin("not-indexed", None)],
+                vout: vec![fee_vout(100_000_000)],
+                fee_sats: Some(5_000),
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+        ],
+    };
+    pipeline.persist_block(&block).await.expect("persist block 1");
+
+    let unknown = RpcBlock {
+        hash: "blockhash2".to_string(),
+        height: 2,
+        prev_hash: Some("blockhash1".to_string()),
+        time: 1_700_000_120,
+        tx: vec![RpcTransaction {
+            txid: "unknown-prevout".to_string(),
+            vin: vec![fee_vin("not-indexed", None)],
+            vout: vec![fee_vout(100_000_000)],
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
+        }],
+    };
+    pipeline.persist_block(&unknown).await.expect("persist block 2");
+
+    let fees = sqlx::query("SELECT txid, fee_sats FROM transactions ORDER BY block_height, position_in_block")
+        .fetch_all(&pool)
+        .await
+        .expect("load transaction fees");
+    let fees: Vec<(String, Option<i64>)> = fees
+        .iter()
+        .map(|row| (row.get("txid"), row.get("fee_sats")))
+        .collect();
+    assert_eq!(
+        fees,
+        vec![
+            ("coinbase0".to_string(), None),
+            ("from-prevout".to_string(), Some(10_000)),
+            ("from-indexed-output".to_string(), Some(20_000)),
+            ("reported-by-node".to_string(), Some(5_000)),
+            ("unknown-prevout".to_string(), None),
+        ]
+    );
+
+    let totals: Vec<Option<i64>> = sqlx::query_scalar("SELECT total_fees_sats FROM blocks ORDER BY height")
+        .fetch_all(&pool)
+        .await
+        .expect("load block fees");
+    assert_eq!(totals, vec![Some(0), Some(35_000), None]);
+}
+
+#[tokio::test]
+#[ignore]
+async fn large_blocks_are_committed_in_chunks_and_stale_partial_blocks_discarded() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&block_zero())
+        .await
+        .expect("persist block 0");
+
+    // The last chunk of the stale block fails after the first one was committed, like a crash.
+    let stale = RpcBlock {
+        hash: "stale1".to_string(),
+        height: 1,
+        prev_hash: Some("blockhash0".to_string()),
+        time: 1_700_000_060,
+        tx: vec![
+            RpcTransaction {
+                txid: "stale-spend".to_string(),
+                vin: vec![fee_vin("coinbase0", Some(5_000_000_000))],
+                vout: vec![fee_vout(4_999_000_000)],
+                fee_sats: None,
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+            RpcTransaction {
+                txid: "stale-second".to_string(),
+                vin: vec![fee_vin("stale-spend", None)],
+                vout: vec![fee_vout(4_998_000_000)],
+                fee_sats: None,
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+        ],
+    };
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .with_tx_chunks(Some(1))
+        .with_block_filter(true)
+        .persist_block(&stale)
+        .await
+        .expect_err("block hash is not hex");
+    let marker: (String, i32) = sqlx::query_as("SELECT hash, next_tx FROM block_write_progress")
+        .fetch_one(&pool)
+        .await
+        .expect("load write progress");
+    assert_eq!(marker, ("stale1".to_string(), 1));
+    let status: String = sqlx::query_scalar("SELECT status FROM transactions WHERE txid = 'stale-spend'")
+        .fetch_one(&pool)
+        .await
+        .expect("load stale transaction");
+    assert_eq!(status, "incomplete");
+
+    let block = RpcBlock {
+        hash: "blockhash1".to_string(),
+        height: 1,
+        prev_hash: Some("blockhash0".to_string()),
+        time: 1_700_000_060,
+        tx: vec![
+            RpcTransaction {
+                txid: "from-prevout".to_string(),
+                vin: vec![fee_vin("coinbase0", Some(5_000_000_000))],
+                vout: vec![fee_vout(4_999_990_000)],
+                fee_sats: None,
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+            RpcTransaction {
+                txid: "from-indexed-output".to_string(),
+                vin: vec![fee_vin("from-prevout", None)],
+                vout: vec![fee_vout(4_999_970_000)],
+                fee_sats: None,
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+            RpcTransaction {
+                txid: "reported-by-node".to_string(),
+                vin: vec![fee_vin("not-indexed", None)],
+                vout: vec![fee_vout(100_000_000)],
+                fee_sats: Some(5_000),
+                vsize: None,
+                weight: None,
+                version: None,
+                locktime: None,
+            },
+        ],
+    };
+    let outcome = IndexerPipeline::new(&pool, MetricsService::new())
+        .with_tx_chunks(Some(2))
+        .persist_block(&block)
+        .await
+        .expect("persist block 1");
+    assert_eq!(outcome, PersistBlockOutcome::Indexed);
+
+    let statuses: Vec<(String, String)> = sqlx::query_as("SELECT txid, status FROM transactions ORDER BY txid")
+        .fetch_all(&pool)
+        .await
+        .expect("load transactions");
+    assert_eq!(
+        statuses,
+        vec![
+            ("coinbase0".to_string(), "confirmed".to_string()),
+            ("from-indexed-output".to_string(), "confirmed".to_string()),
+            ("from-prevout".to_string(), "confirmed".to_string()),
+            ("reported-by-node".to_string(), "confirmed".to_string()),
+            ("stale-spend".to_string(), "orphaned".to_string()),
+        ]
+    );
+    let total: Option<i64> = sqlx::query_scalar("SELECT total_fees_sats FROM blocks WHERE hash = 'blockhash1'")
+        .fetch_one(&pool)
+        .await
+        .expect("load block fees");
+    assert_eq!(total, Some(35_000));
+    let markers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM block_write_progress")
+        .fetch_one(&pool)
+        .await
+        .expect("count write progress");
+    assert_eq!(markers, 0);
+
+    let balances: Vec<(String, i64)> =
+        sqlx::query_as("SELECT address, balance_sats FROM address_balance_current ORDER BY address")
+            .fetch_all(&pool)
+            .await
+            .expect("load balances");
+    assert_eq!(
+        balances,
+        vec![("addr1".to_string(), 0), ("addr3".to_string(), 5_099_970_000)]
+    );
+    // Only the outputs of the stale block itself were reverted.
+    let utxos: Vec<(String, String)> =
+        sqlx::query_as("SELECT out_txid, status FROM utxos_current ORDER BY out_txid")
+            .fetch_all(&pool)
+            .await
+            .expect("load utxos");
+    assert_eq!(
+        utxos,
+        vec![
+            ("coinbase0".to_string(), "spent".to_string()),
+            ("from-indexed-output".to_string(), "unspent".to_string()),
+            ("from-prevout".to_string(), "spent".to_string()),
+            ("reported-by-node".to_string(), "unspent".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn indexer_pipeline_records_block_provenance_and_lists_ranges() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+    pipeline.persist_block(&block_one()).await.expect("persist block 1");
+
+    let meta: serde_json::Value = sqlx::query_scalar("SELECT meta FROM blocks WHERE height = 1")
+        .fetch_one(&pool)
+        .await
+        .expect("load block meta");
+    let provenance: BlockProvenance = serde_json::from_value(meta["provenance"].clone()).expect("provenance");
+    assert_eq!(provenance.pipeline_version, PIPELINE_VERSION);
+    assert_eq!(provenance.stages, vec!["chain", "address_index", "fees", "prevouts", "supply"]);
+
+    sqlx::query("UPDATE blocks SET meta = '{}'::jsonb WHERE height = 0")
+        .execute(&pool)
+        .await
+        .expect("drop provenance of block 0");
+
+    let data = DataService::new(pool.clone());
+    let ranges = data.list_provenance_ranges(None).await.expect("list ranges");
+    assert_eq!(ranges.len(), 2);
+    assert_eq!((ranges[0].from_height, ranges[0].to_height, ranges[0].pipeline_version), (0, 0, None));
+    assert!(ranges[0].stages.is_empty());
+    assert_eq!((ranges[1].from_height, ranges[1].to_height, ranges[1].blocks), (1, 1, 1));
+    assert_eq!(ranges[1].pipeline_version, Some(PIPELINE_VERSION));
+
+    let missing_fees = data.list_provenance_ranges(Some("fees")).await.expect("list missing fees");
+    assert_eq!(missing_fees.len(), 1);
+    assert_eq!((missing_fees[0].from_height, missing_fees[0].to_height), (0, 0));
+
+    assert!(data.list_provenance_ranges(Some("unknown")).await.is_err());
+}
+
+#[tokio::test]
+#[ignore]
+async fn top_balances_follow_indexed_blocks() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+    let data = DataService::new(pool.clone());
+    let top = data.top_balances(None).await.expect("top balances after block 0");
+    assert_eq!(top.block_height, Some(0));
+    assert_eq!(top.items.len(), 1);
+    assert_eq!((top.items[0].address.as_str(), top.items[0].balance_sats), ("addr1", 5_000_000_000));
+
+    pipeline.persist_block(&block_one()).await.expect("persist block 1");
+    sqlx::query("INSERT INTO address_balance_current (address, balance_sats, updated_at) VALUES ('emptied', 0, NOW())")
+        .execute(&pool)
+        .await
+        .expect("seed emptied address");
+
+    let top = data.top_balances(Some(100)).await.expect("top balances after block 1");
+    assert_eq!(top.limit, 100);
+    assert_eq!(top.block_height, Some(1));
+    let ranked: Vec<(i64, &str, i64)> = top
+        .items
+        .iter()
+        .map(|item| (item.rank, item.address.as_str(), item.balance_sats))
+        .collect();
+    assert_eq!(ranked, vec![(1, "addr2", 3_000_000_000), (2, "addr1", 2_000_000_000)]);
+
+    let first = data.top_balances(Some(1)).await.expect("top balance");
+    assert_eq!(first.items.len(), 1);
+    assert_eq!(first.items[0].address, "addr2");
+    assert!(data.top_balances(Some(0)).await.is_err());
+    assert!(data.top_balances(Some(1_001)).await.is_err());
+}
+
+#[tokio::test]
+#[ignore]
+async fn stats_roll_up_indexed_blocks_and_follow_reorgs() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+    pipeline.persist_block(&block_one()).await.expect("persist block 1");
+    let runner = StatsRunner::new(
+        pool.clone(),
+        StatsRunnerConfig {
+            interval: std::time::Duration::from_secs(60),
+        },
+    );
+    let stats = StatsService::new(pool.clone());
+
+    let report = runner.aggregate_once().await.expect("aggregate stats");
+    assert_eq!(
+        report,
+        StatsReport {
+            dropped_blocks: 0,
+            aggregated_blocks: 2,
+            rolled_up_days: 1,
+        }
+    );
+    assert_eq!(runner.aggregate_once().await.expect("aggregate again"), StatsReport::default());
+
+    let daily = stats.list(StatsFilter::default()).await.expect("daily stats");
+    assert_eq!((daily.granularity.as_str(), daily.total), ("day", 1));
+    let day = &daily.items[0];
+    assert_eq!(day.day.to_string(), "2023-11-14");
+    assert_eq!((day.height, day.blocks, day.tx_count), (None, 2, 2));
+    assert_eq!((day.output_sats, day.fees_sats, day.active_addresses), (10_000_000_000, Some(0), 2));
+    assert_eq!(day.avg_fee_rate_sat_vb, None);
+
+    let per_block = stats
+        .list(StatsFilter {
+            from: Some("2023-11-14".to_string()),
+            to: Some("2023-11-14".to_string()),
+            granularity: Some("block".to_string()),
+            ..StatsFilter::default()
+        })
+        .await
+        .expect("block stats");
+    let blocks: Vec<_> = per_block
+        .items
+        .iter()
+        .map(|item| (item.height, item.tx_count, item.output_sats, item.active_addresses))
+        .collect();
+    assert_eq!(blocks, vec![(Some(0), 1, 5_000_000_000, 1), (Some(1), 1, 5_000_000_000, 2)]);
+    let later = StatsFilter {
+        from: Some("2023-11-15".to_string()),
+        ..StatsFilter::default()
+    };
+    assert_eq!(stats.list(later).await.expect("later stats").total, 0);
+
+    sqlx::query("UPDATE blocks SET status = 'orphaned' WHERE height = 1")
+        .execute(&pool)
+        .await
+        .expect("orphan block 1");
+    sqlx::query("UPDATE transactions SET status = 'orphaned' WHERE block_height = 1")
+        .execute(&pool)
+        .await
+        .expect("orphan block 1 transactions");
+    let report = runner.aggregate_once().await.expect("aggregate after reorg");
+    assert_eq!((report.dropped_blocks, report.aggregated_blocks, report.rolled_up_days), (1, 0, 1));
+    let daily = stats.list(StatsFilter::default()).await.expect("daily stats after reorg");
+    assert_eq!((daily.items[0].blocks, daily.items[0].tx_count, daily.items[0].active_addresses), (1, 1, 1));
+
+    let mut replacement = block_zero();
+    replacement.hash = "blockhash1b".to_string();
+    replacement.height = 1;
+    replacement.prev_hash = Some("blockhash0".to_string());
+    replacement.time = 1_700_000_120;
+    replacement.tx[0].txid = "coinbase1b".to_string();
+    replacement.tx[0].vout[0].script_pub_key.address = Some("addr3".to_string());
+    pipeline.persist_block(&replacement).await.expect("persist replacement block 1");
+    let report = runner.aggregate_once().await.expect("aggregate replacement");
+    assert_eq!((report.dropped_blocks, report.aggregated_blocks, report.rolled_up_days), (0, 1, 1));
+    let daily = stats.list(StatsFilter::default()).await.expect("daily stats after replacement");
+    assert_eq!((daily.items[0].blocks, daily.items[0].tx_count, daily.items[0].active_addresses), (2, 2, 2));
+
+    let reversed = StatsFilter {
+        from: Some("2023-11-15".to_string()),
+        to: Some("2023-11-14".to_string()),
+        ..StatsFilter::default()
+    };
+    assert!(stats.list(reversed).await.is_err());
+    let hourly = StatsFilter {
+        granularity: Some("hour".to_string()),
+        ..StatsFilter::default()
+    };
+    assert!(stats.list(hourly).await.is_err());
+    let empty = StatsFilter {
+        limit: Some(0),
+        ..StatsFilter::default()
+    };
+    assert!(stats.list(empty).await.is_err());
+}
+
+#[tokio::test]
+#[ignore]
+async fn raw_blocks_are_stored_with_the_block() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let raw = b"serialized block zero".to_vec();
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .with_raw_block(Some(&raw))
+        .persist_block(&block_zero())
+        .await
+        .expect("persist block 0");
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&block_one())
+        .await
+        .expect("persist block 1");
+
+    let stored: Vec<(String, i32, i32)> = sqlx::query_as("SELECT hash, height, size FROM block_raw ORDER BY height")
+        .fetch_all(&pool)
+        .await
+        .expect("load raw blocks");
+    assert_eq!(stored, vec![("blockhash0".to_string(), 0, raw.len() as i32)]);
+}
+
+fn filter_tx(txid: &str, spends: Option<(&str, i32)>, script_hex: &str) -> RpcTransaction {
+    RpcTransaction {
+        txid: txid.to_string(),
+        vin: vec![RpcVin {
+            txid: spends.map(|(prev_txid, _)| prev_txid.to_string()),
+            vout: spends.map(|(_, prev_vout)| prev_vout),
+            sequence: 0,
+            txinwitness: None,
+            prevout: None,
+        }],
+        vout: vec![RpcVout {
+            n: 0,
+            value_sats: 5_000_000_000,
+            script_pub_key: RpcScriptPubKey {
+                script_type: "pubkey".to_string(),
+                hex: script_hex.to_string(),
+                address: None,
+                addresses: None,
+            },
+        }],
+        fee_sats: None,
+        vsize: None,
+        weight: None,
+        version: None,
+        locktime: None,
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn block_filters_are_built_from_outputs_and_spent_scripts() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    // BIP158 test vector: the testnet genesis block.
+    let genesis_hash = "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943";
+    let genesis_script = "4104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef\
+38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac";
+    let spend_script = "0014751e76e8199196d454941c45d1b3a323f1433bd6";
+    let blocks = [
+        RpcBlock {
+            hash: genesis_hash.to_string(),
+            height: 0,
+            prev_hash: None,
+            time: 1_700_000_000,
+            tx: vec![filter_tx("coinbase0", None, genesis_script)],
+        },
+        RpcBlock {
+            hash: "11".repeat(32),
+            height: 1,
+            prev_hash: Some(genesis_hash.to_string()),
+            time: 1_700_000_060,
+            tx: vec![filter_tx("spend1", Some(("coinbase0", 0)), spend_script)],
+        },
+        RpcBlock {
+            hash: "22".repeat(32),
+            height: 2,
+            prev_hash: Some("11".repeat(32)),
+            time: 1_700_000_120,
+            tx: vec![filter_tx("spend2", Some(("unindexed", 0)), spend_script)],
+        },
+    ];
+    for block in &blocks {
+        IndexerPipeline::new(&pool, MetricsService::new())
+            .with_block_filter(true)
+            .persist_block(block)
+            .await
+            .expect("persist block");
+    }
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&RpcBlock {
+            hash: "33".repeat(32),
+            height: 3,
+            prev_hash: Some("22".repeat(32)),
+            time: 1_700_000_180,
+            tx: vec![filter_tx("coinbase3", None, spend_script)],
+        })
+        .await
+        .expect("persist block 3");
+
+    let data = DataService::new(pool.clone());
+    let genesis = data.block_filter(genesis_hash).await.expect("genesis filter").expect("stored");
+    assert_eq!(genesis.filter_type, "basic");
+    assert_eq!(genesis.filter, "019dfca8");
+    assert_eq!(
+        genesis.header.as_deref(),
+        Some("21584579b7eb08997773e5aeff3a7f932700042d0ed2a6129012b7d7ae81b750")
+    );
+    assert!(genesis.complete);
+
+    let spend = data.block_filter(&"11".repeat(32)).await.expect("block 1 filter").expect("stored");
+    let spend_filter = hex::decode(&spend.filter).expect("filter hex");
+    let spent = hex::decode(genesis_script).expect("script hex");
+    assert!(filters::matches_any(&spend.hash, &spend_filter, [spent.as_slice()]));
+    assert_eq!(spend.header, filters::filter_header(&spend_filter, genesis.header.as_deref()));
+    assert!(spend.complete);
+
+    let incomplete = data.block_filter(&"22".repeat(32)).await.expect("block 2 filter").expect("stored");
+    assert!(!incomplete.complete);
+    assert!(incomplete.header.is_some());
+
+    assert!(data.block_filter(&"33".repeat(32)).await.expect("block 3 filter").is_none());
+    assert!(data.block_filter("blockhash0").await.is_err());
+}
+
+#[tokio::test]
+#[ignore]
+async fn decoded_json_is_reduced_when_not_stored() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let mut block = block_zero();
+    block.tx[0].vsize = Some(120);
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .with_decoded_tx(false)
+        .persist_block(&block)
+        .await
+        .expect("persist block 0");
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&block_one())
+        .await
+        .expect("persist block 1");
+
+    let decoded: Vec<(String, serde_json::Value)> =
+        sqlx::query_as("SELECT txid, decoded FROM transactions ORDER BY block_height")
+            .fetch_all(&pool)
+            .await
+            .expect("load transactions");
+    assert_eq!(decoded[0], ("coinbase0".to_string(), serde_json::json!({ "vsize": 120 })));
+    assert_eq!(decoded[1].0, "spend1");
+    assert!(decoded[1].1.get("vin").is_some());
+
+    let outputs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tx_outputs WHERE txid = 'coinbase0'")
+        .fetch_one(&pool)
+        .await
+        .expect("count outputs");
+    assert_eq!(outputs, 1);
+}
+
+/// Block with a single P2WPKH spend shaped like `getblock` verbosity 2 output.
+fn segwit_block(height: i32, prev_hash: &str, txid: &str, spends: &str, tx_fields: serde_json::Value) -> RpcBlock {
+    let mut tx = serde_json::json!({
+        "txid": txid,
+        "vin": [{
+            "txid": spends,
+            "vout": 0,
+            "sequence": 4_294_967_293_u32,
+            "txinwitness": [
+                "3044022047ac8e878352d3ebbde1c94ce3a10d057c24175747116f8288e5d794d12d482f0220217f36a485cae903c713331d877c1f64677e3622ad4010726870540656fe9dcb01",
+                "038262a6c6cec93c2d3ecd6c6072efea86d02ff8e3328bbd0242b20af3425990ac"
+            ]
+        }],
+        "vout": [{
+            "n": 0,
+            "value": 49.9999,
+            "scriptPubKey": { "type": "witness_v0_keyhash", "hex": "0014segwit", "address": "addr3" }
+        }]
+    });
+    tx.as_object_mut().expect("tx object").extend(tx_fields.as_object().expect("tx fields").clone());
+    serde_json::from_value(serde_json::json!({
+        "hash": format!("blockhash{height}"),
+        "height": height,
+        "previousblockhash": prev_hash,
+        "time": 1_700_000_000 + i64::from(height) * 60,
+        "tx": [tx]
+    }))
+    .expect("decode segwit block")
+}
+
+#[tokio::test]
+#[ignore]
+async fn segwit_transaction_details_and_witnesses_are_stored() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&block_zero())
+        .await
+        .expect("persist block 0");
+    let with_witness = segwit_block(
+        1,
+        "blockhash0",
+        "segwit-kept",
+        "coinbase0",
+        serde_json::json!({ "vsize": 110, "weight": 437, "version": 2, "locktime": 0 }),
+    );
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .with_witness(true)
+        .persist_block(&with_witness)
+        .await
+        .expect("persist block 1");
+    let without_witness = segwit_block(
+        2,
+        "blockhash1",
+        "segwit-dropped",
+        "segwit-kept",
+        serde_json::json!({ "vsize": 109, "weight": 436, "version": 1, "locktime": 839_999 }),
+    );
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&without_witness)
+        .await
+        .expect("persist block 2");
+
+    let details = sqlx::query("SELECT txid, vsize, weight, version, locktime FROM transactions ORDER BY block_height")
+        .fetch_all(&pool)
+        .await
+        .expect("load transaction details");
+    assert_eq!(
+        details
+            .iter()
+            .map(|row| (
+                row.get::<String, _>("txid"),
+                row.get::<Option<i32>, _>("vsize"),
+                row.get::<Option<i32>, _>("weight"),
+                row.get::<Option<i64>, _>("version"),
+                row.get::<Option<i64>, _>("locktime"),
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("coinbase0".to_string(), None, None, None, None),
+            ("segwit-kept".to_string(), Some(110), Some(437), Some(2), Some(0)),
+            ("segwit-dropped".to_string(), Some(109), Some(436), Some(1), Some(839_999)),
+        ]
+    );
+
+    let witnesses: Vec<(String, Option<Vec<String>>)> =
+        sqlx::query_as("SELECT txid, witness FROM tx_inputs WHERE txid LIKE 'segwit-%' ORDER BY txid")
+            .fetch_all(&pool)
+            .await
+            .expect("load input witnesses");
+    assert_eq!(
+        witnesses,
+        vec![
+            ("segwit-dropped".to_string(), None),
+            ("segwit-kept".to_string(), with_witness.tx[0].vin[0].txinwitness.clone()),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn retention_prunes_transaction_detail_below_kept_blocks() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+    pipeline.persist_block(&block_one()).await.expect("persist block 1");
+    let mut block_two = block_zero();
+    block_two.hash = "blockhash2".to_string();
+    block_two.height = 2;
+    block_two.prev_hash = Some("blockhash1".to_string());
+    block_two.tx[0].txid = "coinbase2".to_string();
+    pipeline.persist_block(&block_two).await.expect("persist block 2");
+
+    let retention = RetentionService::new(
+        pool.clone(),
+        RetentionRunnerConfig {
+            keep_blocks: 1,
+            batch_blocks: 1,
+            interval: std::time::Duration::from_secs(60),
+        },
+    );
+    let report = retention.prune().await.expect("prune");
+    assert_eq!(
+        report,
+        PruneReport {
+            pruned_height: Some(1),
+            heights: 2,
+            transactions: 2,
+            outputs: 3,
+        }
+    );
+
+    let detail: Vec<(String, bool)> = sqlx::query_as("SELECT txid, decoded ? 'vin' FROM transactions ORDER BY txid")
+        .fetch_all(&pool)
+        .await
+        .expect("load transactions");
+    let detail: Vec<(&str, bool)> = detail.iter().map(|(txid, vin)| (txid.as_str(), *vin)).collect();
+    assert_eq!(detail, vec![("coinbase0", false), ("coinbase2", true), ("spend1", false)]);
+    let scripts: Vec<(String, String)> =
+        sqlx::query_as("SELECT txid, script_hex FROM tx_outputs WHERE script_hex <> '' ORDER BY txid")
+            .fetch_all(&pool)
+            .await
+            .expect("load outputs");
+    assert_eq!(scripts, vec![("coinbase2".to_string(), "0014coinbase0".to_string())]);
+    let balance: i64 = sqlx::query_scalar("SELECT COALESCE(SUM(value_sats), 0)::BIGINT FROM tx_outputs")
+        .fetch_one(&pool)
+        .await
+        .expect("sum outputs");
+    assert_eq!(balance, 15_000_000_000);
+
+    let report = retention.prune().await.expect("prune again");
+    assert_eq!(
+        report,
+        PruneReport {
+            pruned_height: Some(1),
+            ..PruneReport::default()
+        }
+    );
+}
+
+async fn partition_of(pool: &PgPool, table: &str, key: &str, value: &str) -> String {
+    sqlx::query_scalar(&format!("SELECT tableoid::regclass::TEXT FROM {table} WHERE {key} = $1"))
+        .bind(value)
+        .fetch_one(pool)
+        .await
+        .expect("load partition")
+}
+
+#[tokio::test]
+#[ignore]
+async fn chain_tables_are_partitioned_by_height() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    sqlx::query(
+        "INSERT INTO transactions (txid, block_height, block_hash, time, status, decoded)
+         VALUES ('coinbase0', NULL, NULL, 1700000000, 'mempool', '{}'::jsonb)",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed mempool transaction");
+    sqlx::query(
+        "INSERT INTO tx_outputs (txid, vout, value_sats, script_type, address, script_hex)
+         VALUES ('coinbase0', 0, 5000000000, 'pubkeyhash', 'addr1', '0014coinbase0')",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed mempool output");
+    assert_eq!(partition_of(&pool, "transactions", "txid", "coinbase0").await, "transactions_unconfirmed");
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline.persist_block(&block_zero()).await.expect("persist block 0");
+    assert_eq!(partition_of(&pool, "blocks", "hash", "blockhash0").await, "blocks_p0");
+    assert_eq!(partition_of(&pool, "transactions", "txid", "coinbase0").await, "transactions_p0");
+    assert_eq!(partition_of(&pool, "tx_outputs", "txid", "coinbase0").await, "tx_outputs_p0");
+    let copies: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE txid = 'coinbase0'")
+        .fetch_one(&pool)
+        .await
+        .expect("count transactions");
+    assert_eq!(copies, 1);
+
+    let mut block = block_one();
+    block.height = 250_000;
+    block.hash = "blockhash250000".to_string();
+    block.tx[0].txid = "spend250000".to_string();
+    pipeline.persist_block_from(&block, 250_000).await.expect("persist block 250000");
+    let partitions: Vec<String> = sqlx::query_scalar(
+        "SELECT c.relname::TEXT FROM pg_inherits i JOIN pg_class c ON c.oid = i.inhrelid
+         WHERE i.inhparent = 'blocks'::regclass ORDER BY c.relname",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("list partitions");
+    assert_eq!(partitions, vec!["blocks_p0", "blocks_p100000", "blocks_p200000", "blocks_p300000"]);
+    let balance: i64 = sqlx::query_scalar("SELECT balance_sats FROM address_balance_current WHERE address = 'addr2'")
+        .fetch_one(&pool)
+        .await
+        .expect("load balance");
+    assert_eq!(balance, 3_000_000_000);
+}
+
+#[tokio::test]
+#[ignore]
+async fn partitioned_transaction_upserts_only_wait_for_the_same_txid() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+    let repo = TransactionsRepo::new(&pool).with_schema_features(SchemaFeatures::latest());
+    let record = |txid: &str| TransactionRecord {
+        txid: txid.to_string(),
+        block_height: None,
+        block_hash: None,
+        position_in_block: 0,
+        time: 1_700_000_000,
+        status: "mempool".to_string(),
+        decoded: serde_json::json!({}),
+        vsize: None,
+        weight: None,
+        version: None,
+        locktime: None,
+    };
+    let wait = std::time::Duration::from_millis(500);
+
+    let mut first = pool.begin().await.expect("begin first");
+    repo.upsert(&mut first, &record("locked")).await.expect("upsert in first");
+    let mut second = pool.begin().await.expect("begin second");
+    tokio::time::timeout(wait, repo.upsert(&mut second, &record("other")))
+        .await
+        .expect("another txid does not wait")
+        .expect("upsert in second");
+    assert!(
+        tokio::time::timeout(wait, repo.upsert(&mut second, &record("locked"))).await.is_err(),
+        "the same txid waits for the first writer"
+    );
+    drop(second);
+    first.commit().await.expect("commit first");
+
+    let copies: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM transactions WHERE txid IN ('locked', 'other')")
+        .fetch_one(&pool)
+        .await
+        .expect("count transactions");
+    assert_eq!(copies, 1);
+}
+
+#[tokio::test]
+#[ignore]
+async fn indexer_pipeline_decodes_output_scripts_for_configured_network() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let output = |n: i32, script_type: &str, hex: &str, addresses: Option<Vec<String>>| RpcVout {
+        n,
+        value_sats: 1_000,
+        script_pub_key: RpcScriptPubKey {
+            script_type: script_type.to_string(),
+            hex: hex.to_string(),
+            address: None,
+            addresses,
+        },
+    };
+    let mut block = block_zero();
+    block.tx[0].vout = vec![
+        output(0, "witness_v0_keyhash", "0014751e76e8199196d454941c45d1b3a323f1433bd6", None),
+        output(
+            1,
+            "multisig",
+            "51210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179851ae",
+            Some(vec!["mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r".to_string()]),
+        ),
+        output(2, "nulldata", "6a0568656c6c6f", None),
+    ];
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new()).with_network(bitcoin::Network::Regtest);
+    pipeline.persist_block(&block).await.expect("persist block 0");
+
+    let rows = sqlx::query("SELECT vout, script_type, address FROM tx_outputs ORDER BY vout")
+        .fetch_all(&pool)
+        .await
+        .expect("load outputs");
+    let outputs: Vec<(i32, String, Option<String>)> = rows
+        .iter()
+        .map(|row| (row.get("vout"), row.get("script_type"), row.get("address")))
+        .collect();
+    assert_eq!(
+        outputs,
+        vec![
+            (
+                0,
+                "witness_v0_keyhash".to_string(),
+                Some("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080".to_string())
+            ),
+            (1, "multisig".to_string(), None),
+            (2, "nulldata".to_string(), None),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn indexer_pipeline_queues_sink_events_in_outbox() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&block_zero())
+        .await
+        .expect("persist block 0");
+    let queued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM event_outbox")
+        .fetch_one(&pool)
+        .await
+        .expect("count outbox");
+    assert_eq!(queued, 0);
+
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .with_outbox(true)
+        .persist_block(&block_one())
+        .await
+        .expect("persist block 1");
+
+    let rows = sqlx::query("SELECT stream, event_key, payload FROM event_outbox WHERE published_at IS NULL ORDER BY id")
+        .fetch_all(&pool)
+        .await
+        .expect("load outbox");
+    let events: Vec<(String, String, serde_json::Value)> = rows
+        .iter()
+        .map(|row| (row.get("stream"), row.get("event_key"), row.get("payload")))
+        .collect();
+    let keys: Vec<(&str, &str)> = events
+        .iter()
+        .map(|(stream, key, _)| (stream.as_str(), key.as_str()))
+        .collect();
+    assert_eq!(
+        keys,
+        vec![
+            ("transactions", "spend1"),
+            ("address_activity", "addr1"),
+            ("address_activity", "addr2"),
+            ("blocks", "blockhash1"),
+        ]
+    );
+
+    let tx = &events[0].2;
+    assert_eq!(tx["type"], "transaction_confirmed");
+    assert_eq!(tx["fee_sats"], 0);
+    assert_eq!(tx["inputs"], serde_json::json!([{ "prev_txid": "coinbase0", "prev_vout": 0 }]));
+    assert_eq!(tx["outputs"][1]["address"], "addr2");
+    assert_eq!(events[1].2["delta_sats"], -3_000_000_000i64);
+    assert_eq!(events[1].2["balance_sats"], 2_000_000_000i64);
+    assert_eq!(events[2].2["delta_sats"], 3_000_000_000i64);
+    assert_eq!(events[3].2["type"], "block_connected");
+    assert_eq!(events[3].2["tx_count"], 1);
+    assert_eq!(events[3].2["total_fees_sats"], 0);
+}
+
+#[tokio::test]
+#[ignore]
+async fn storage_pool_follows_database_config() {
+    let Some(_pool) = setup_db().await else {
+        return;
+    };
+
+    let config = DatabaseConfig {
+        url_env: "INDEXER_TEST_DATABASE_URL".to_string(),
+        max_connections: 3,
+        min_connections: 1,
+        statement_timeout_ms: Some(1_500),
+        ..DatabaseConfig::default()
+    };
+    let err = Storage::connect(&config).await.err().expect("missing url should fail");
+    assert_eq!(err.to_string(), "INDEXER_TEST_DATABASE_URL is not set");
+
+    let config = DatabaseConfig {
+        url: Some(std::env::var("DATABASE_URL").expect("database url").into()),
+        ..config
+    };
+    let storage = Storage::connect(&config).await.expect("connect storage");
+    assert_eq!(storage.pool().options().get_max_connections(), 3);
+    assert_eq!(storage.pool().options().get_min_connections(), 1);
+    let timeout: String = sqlx::query_scalar("SHOW statement_timeout")
+        .fetch_one(storage.pool())
+        .await
+        .expect("show statement_timeout");
+    assert_eq!(timeout, "1500ms");
+
+    let instance = storage.with_schema("indexer_pool").await.expect("instance storage");
+    assert_eq!(instance.pool().options().get_max_connections(), 3);
+    let timeout: String = sqlx::query_scalar("SHOW statement_timeout")
+        .fetch_one(instance.pool())
+        .await
+        .expect("show statement_timeout");
+    assert_eq!(timeout, "1500ms");
+}
+
+#[tokio::test]
+#[ignore]
+async fn read_replica_serves_queries_read_only() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let storage = Storage::connect(&DatabaseConfig::default()).await.expect("connect storage");
+    assert!(storage.read_pool().connect_options().get_options().is_none());
+
+    std::env::set_var("INDEXER_TEST_REPLICA_URL", std::env::var("DATABASE_URL").expect("database url"));
+    let config = DatabaseConfig {
+        replica_url_env: Some("INDEXER_TEST_REPLICA_URL".to_string()),
+        ..DatabaseConfig::default()
+    };
+    let storage = Storage::connect(&config).await.expect("connect storage");
+    IndexerPipeline::new(storage.pool(), MetricsService::new())
+        .persist_block(&block_zero())
+        .await
+        .expect("persist block 0");
+
+    let hash: String = sqlx::query_scalar("SELECT hash FROM blocks WHERE height = 0")
+        .fetch_one(storage.read_pool())
+        .await
+        .expect("read block from replica pool");
+    assert_eq!(hash, "blockhash0");
+
+    let err = sqlx::query("DELETE FROM blocks")
+        .execute(storage.read_pool())
+        .await
+        .expect_err("replica pool should be read-only");
+    assert!(err.to_string().contains("read-only transaction"), "{err}");
+    let blocks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM blocks")
+        .fetch_one(&pool)
+        .await
+        .expect("count blocks");
+    assert_eq!(blocks, 1);
+
+    let instance = storage.with_schema("indexer_replica").await.expect("instance storage");
+    let err = sqlx::query("CREATE TABLE replica_probe (id INT)")
+        .execute(instance.read_pool())
+        .await
+        .expect_err("instance replica pool should be read-only");
+    assert!(err.to_string().contains("read-only transaction"), "{err}");
+}
@@ -1,19 +1,38 @@
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use axum::extract::State;
-use axum::http::StatusCode;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
 use axum::response::IntoResponse;
-use axum::{Json, Router, routing::post};
-use bitcoin_blockchain_indexer::modules::config::{BasicAuthResolved, RpcConfig, RpcTimeouts};
+use axum::{Json, Router, routing::{get, post}};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bitcoin_blockchain_indexer::modules::auth::{AuthChain, Credentials};
+use bitcoin_blockchain_indexer::modules::broadcast::{BroadcastError, BroadcastService};
+use bitcoin_blockchain_indexer::modules::config::{
+    AppConfig, BasicAuthResolved, DatabaseConfig, JobConfig, JobExportConfig, JobRetryPolicy, RpcConfig, RpcTimeouts,
+};
+use bitcoin_blockchain_indexer::modules::data::{DataError, DataService};
+use bitcoin_blockchain_indexer::modules::headers::{header_tip, HeaderSyncRunner, HeaderSyncRunnerConfig};
+use bitcoin_blockchain_indexer::modules::indexer::hooks::{
+    BlockHookContext, HookError, HookRegistry, HookVerdict, PipelineHook,
+};
 use bitcoin_blockchain_indexer::modules::indexer::{
     IndexerPipeline, IndexerService, RpcBlock, RpcScriptPubKey, RpcTransaction, RpcVin, RpcVout,
 };
+use bitcoin_blockchain_indexer::modules::jobs::{JobsRunner, JobsRunnerConfig, JobsService};
 use bitcoin_blockchain_indexer::modules::mempool::MempoolRunner;
 use bitcoin_blockchain_indexer::modules::metrics::MetricsService;
-use bitcoin_blockchain_indexer::modules::rpc::RpcClient;
+use bitcoin_blockchain_indexer::modules::node_info::NodeInfoService;
+use bitcoin_blockchain_indexer::modules::reload::{ConfigReloader, ReloadError};
+use bitcoin_blockchain_indexer::modules::rpc::{RpcClient, RpcError};
+use bitcoin_blockchain_indexer::modules::scan::{ScanError, ScanService};
+use bitcoin_blockchain_indexer::modules::stats::StatsService;
+use bitcoin_blockchain_indexer::modules::status::StatusService;
 use bitcoin_blockchain_indexer::modules::storage::Storage;
+use bitcoin_blockchain_indexer::modules::templates::{BlockTemplateRunner, BlockTemplateRunnerConfig};
 use sqlx::{PgPool, Row};
 use testcontainers::core::WaitFor;
 use testcontainers::{GenericImage, clients::Cli};
@@ -48,7 +67,7 @@ async fn setup_db() -> Option<PgPool> {
     std::env::set_var("DATABASE_URL", &database_url);
     std::env::set_var("MIGRATIONS_PATH", "migrations");
 
-    let storage = Storage::connect().await.expect("connect storage");
+    let storage = Storage::connect(&DatabaseConfig::default()).await.expect("connect storage");
     storage
         .apply_migrations()
         .await
@@ -63,6 +82,8 @@ struct MockRpcState {
     block_hashes: HashMap<u32, String>,
     mempool_sequences: VecDeque<Vec<String>>,
     transactions: HashMap<String, RpcTransaction>,
+    blocks: HashMap<String, RpcBlock>,
+    block_template: Option<serde_json::Value>,
 }
 
 #[derive(Clone)]
@@ -98,6 +119,15 @@ async fn mock_rpc_handler(
     State(state): State<Arc<Mutex<MockRpcState>>>,
     Json(body): Json<serde_json::Value>,
 ) -> impl IntoResponse {
+    let reply = match body.as_array() {
+        Some(batch) => serde_json::Value::Array(batch.iter().map(|request| mock_rpc_reply(&state, request)).collect()),
+        None => mock_rpc_reply(&state, &body),
+    };
+
+    (StatusCode::OK, Json(reply))
+}
+
+fn mock_rpc_reply(state: &Mutex<MockRpcState>, body: &serde_json::Value) -> serde_json::Value {
     let method = body
         .get("method")
         .and_then(|value| value.as_str())
@@ -129,6 +159,25 @@ async fn mock_rpc_handler(
                     .unwrap_or_default();
                 Some(serde_json::json!(response))
             }
+            "getblock" => {
+                let hash = params.first().and_then(|value| value.as_str()).unwrap_or_default();
+                guard
+                    .blocks
+                    .get(hash)
+                    .cloned()
+                    .map(|block| serde_json::to_value(block).expect("serialize block"))
+            }
+            "getblockheader" => {
+                let hash = params.first().and_then(|value| value.as_str()).unwrap_or_default();
+                guard.blocks.get(hash).map(|block| {
+                    serde_json::json!({
+                        "hash": block.hash,
+                        "height": block.height,
+                        "previousblockhash": block.prev_hash,
+                        "time": block.time,
+                    })
+                })
+            }
             "getrawtransaction" => {
                 let txid = params.first().and_then(|value| value.as_str()).unwrap_or_default();
                 guard
@@ -137,29 +186,96 @@ async fn mock_rpc_handler(
                     .cloned()
                     .map(|tx| serde_json::to_value(tx).expect("serialize transaction"))
             }
+            "getblocktemplate" => guard.block_template.clone(),
+            "getblockchaininfo" => Some(serde_json::json!({
+                "chain": "regtest",
+                "blocks": guard.block_count,
+                "headers": guard.block_count,
+                "bestblockhash": guard.block_hashes.get(&(guard.block_count as u32)),
+            })),
+            "getnetworkinfo" => Some(serde_json::json!({
+                "version": 270000,
+                "subversion": "/Satoshi:27.0.0/",
+                "protocolversion": 70016,
+                "connections": 1,
+                "relayfee": 0.00001,
+                "warnings": "",
+            })),
+            "getmempoolinfo" => {
+                let size = guard.mempool_sequences.back().map(Vec::len).unwrap_or_default();
+                Some(serde_json::json!({
+                    "size": size,
+                    "bytes": 0,
+                    "usage": 0,
+                    "maxmempool": 300000000,
+                    "mempoolminfee": 0.00001,
+                }))
+            }
+            // Relays version 2 transactions and refuses the rest.
+            "sendrawtransaction" => params
+                .first()
+                .and_then(|value| value.as_str())
+                .and_then(|raw| hex::decode(raw).ok())
+                .and_then(|bytes| bitcoin::consensus::deserialize::<bitcoin::Transaction>(&bytes).ok())
+                .filter(|tx| tx.version == bitcoin::transaction::Version::TWO)
+                .map(|tx| serde_json::json!(tx.compute_txid().to_string())),
+            // One 0.5 BTC output per `addr(...)` descriptor; anything else is an invalid descriptor.
+            "scantxoutset" => {
+                let descriptors: Vec<String> = params
+                    .get(1)
+                    .and_then(|value| value.as_array())
+                    .map(|objects| {
+                        objects
+                            .iter()
+                            .filter_map(|object| object.as_str().or_else(|| object.get("desc")?.as_str()))
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                descriptors
+                    .iter()
+                    .all(|descriptor| descriptor.starts_with("addr("))
+                    .then(|| {
+                        let unspents: Vec<_> = descriptors
+                            .iter()
+                            .enumerate()
+                            .map(|(vout, descriptor)| {
+                                serde_json::json!({
+                                    "txid": "11".repeat(32),
+                                    "vout": vout,
+                                    "scriptPubKey": "0014",
+                                    "desc": descriptor,
+                                    "amount": 0.5,
+                                    "coinbase": false,
+                                    "height": guard.block_count,
+                                })
+                            })
+                            .collect();
+                        serde_json::json!({
+                            "success": true,
+                            "txouts": 1000,
+                            "height": guard.block_count,
+                            "bestblock": "blockhash0",
+                            "unspents": unspents,
+                            "total_amount": 0.5 * descriptors.len() as f64,
+                        })
+                    })
+            }
             _ => None,
         }
     };
 
     match result {
-        Some(result) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "result": result,
-                "error": null,
-                "id": id
-            })),
-        )
-            .into_response(),
-        None => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "result": null,
-                "error": { "message": format!("unsupported method: {method}") },
-                "id": id
-            })),
-        )
-            .into_response(),
+        Some(result) => serde_json::json!({
+            "result": result,
+            "error": null,
+            "id": id
+        }),
+        None => serde_json::json!({
+            "result": null,
+            "error": { "message": format!("unsupported method: {method}") },
+            "id": id
+        }),
     }
 }
 
@@ -167,19 +283,52 @@ fn rpc_client(url: String) -> RpcClient {
     RpcClient::from_config(&RpcConfig {
         node_id: "mock-node".to_string(),
         url,
+        rest_url: None,
         auth: BasicAuthResolved {
             username: "rpcuser".to_string(),
-            password: "rpcpass".to_string(),
+            password: "rpcpass".to_string().into(),
+            password_file: None,
         },
         mtls: None,
+        insecure_skip_verify: false,
         timeouts: RpcTimeouts {
             connect_ms: 5_000,
             request_ms: 5_000,
+            startup_ms: 0,
         },
+        max_requests_per_second: None,
     })
     .expect("build rpc client")
 }
 
+/// bitcoind `-rest` stand-in serving `/rest/block/<hash>.json|.hex`; counts the requests.
+async fn start_mock_rest(blocks: HashMap<String, RpcBlock>, requests: Arc<Mutex<Vec<String>>>) -> String {
+    let router = Router::new().route(
+        "/rest/block/{file}",
+        get(move |Path(file): Path<String>| async move {
+            requests.lock().expect("mock rest mutex poisoned").push(file.clone());
+            let block = file
+                .rsplit_once('.')
+                .and_then(|(hash, _)| blocks.get(hash));
+            match (block, file.rsplit_once('.').map(|(_, format)| format)) {
+                (Some(block), Some("json")) => (StatusCode::OK, serde_json::to_string(block).expect("serialize block")),
+                (Some(_), Some("hex")) => (StatusCode::OK, "00ff\n".to_string()),
+                _ => (StatusCode::NOT_FOUND, format!("{file} not found\r\n")),
+            }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind mock rest");
+    let addr = listener.local_addr().expect("local addr");
+
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("serve mock rest");
+    });
+
+    format!("http://{}", addr)
+}
+
 fn canonical_block_zero() -> RpcBlock {
     RpcBlock {
         hash: "blockhash0".to_string(),
@@ -192,10 +341,12 @@ fn canonical_block_zero() -> RpcBlock {
                 txid: None,
                 vout: None,
                 sequence: 0,
+                txinwitness: None,
+                prevout: None,
             }],
             vout: vec![RpcVout {
                 n: 0,
-                value: 50.0,
+                value_sats: 5_000_000_000,
                 script_pub_key: RpcScriptPubKey {
                     script_type: "pubkeyhash".to_string(),
                     hex: "0014coinbase0".to_string(),
@@ -203,6 +354,11 @@ fn canonical_block_zero() -> RpcBlock {
                     addresses: None,
                 },
             }],
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
         }],
     }
 }
@@ -219,11 +375,13 @@ fn canonical_block_one(hash: &str) -> RpcBlock {
                 txid: Some("coinbase0".to_string()),
                 vout: Some(0),
                 sequence: 1,
+                txinwitness: None,
+                prevout: None,
             }],
             vout: vec![
                 RpcVout {
                     n: 0,
-                    value: 20.0,
+                    value_sats: 2_000_000_000,
                     script_pub_key: RpcScriptPubKey {
                         script_type: "pubkeyhash".to_string(),
                         hex: "0014addr1".to_string(),
@@ -233,7 +391,7 @@ fn canonical_block_one(hash: &str) -> RpcBlock {
                 },
                 RpcVout {
                     n: 1,
-                    value: 30.0,
+                    value_sats: 3_000_000_000,
                     script_pub_key: RpcScriptPubKey {
                         script_type: "pubkeyhash".to_string(),
                         hex: "0014addr2".to_string(),
@@ -242,6 +400,11 @@ fn canonical_block_one(hash: &str) -> RpcBlock {
                     },
                 },
             ],
+            fee_sats: None,
+            vsize: None,
+            weight: None,
+            version: None,
+            locktime: None,
         }],
     }
 }
@@ -253,10 +416,12 @@ fn mempool_transaction() -> RpcTransaction {
             txid: Some("confirmed-prev".to_string()),
             vout: Some(0),
             sequence: 1,
+            txinwitness: None,
+            prevout: None,
         }],
         vout: vec![RpcVout {
             n: 0,
-            value: 0.00003,
+            value_sats: 3_000,
             script_pub_key: RpcScriptPubKey {
                 script_type: "pubkeyhash".to_string(),
                 hex: "0014mempool".to_string(),
@@ -264,6 +429,11 @@ fn mempool_transaction() -> RpcTransaction {
                 addresses: None,
             },
         }],
+        fee_sats: None,
+        vsize: None,
+        weight: None,
+        version: None,
+        locktime: None,
     }
 }
 
@@ -295,6 +465,8 @@ async fn mempool_runner_syncs_new_and_dropped_transactions_via_rpc() {
         block_hashes: HashMap::new(),
         mempool_sequences: VecDeque::from(vec![vec!["mempooltx".to_string()], vec![]]),
         transactions: HashMap::from([(String::from("mempooltx"), mempool_transaction())]),
+        blocks: HashMap::new(),
+        block_template: None,
     })
     .start()
     .await;
@@ -343,6 +515,218 @@ async fn mempool_runner_syncs_new_and_dropped_transactions_via_rpc() {
     assert_eq!(dropped_row.get::<String, _>("status"), "dropped");
 }
 
+#[tokio::test]
+#[ignore]
+async fn double_spends_are_recorded_for_mempool_replacements_and_conflicting_blocks() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    sqlx::query(
+        "INSERT INTO transactions (txid, block_height, block_hash, position_in_block, time, status, decoded)
+         VALUES ('confirmed-prev', 10, 'blockhash10', 0, 1700001000, 'confirmed', '{}'::jsonb)",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed prev transaction");
+
+    let spend = |txid: &str| {
+        let mut tx = mempool_transaction();
+        tx.txid = txid.to_string();
+        tx
+    };
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 10,
+        block_hashes: HashMap::new(),
+        mempool_sequences: VecDeque::from(vec![vec!["mempooltx".to_string()], vec!["rbftx".to_string()]]),
+        transactions: HashMap::from([
+            ("mempooltx".to_string(), spend("mempooltx")),
+            ("rbftx".to_string(), spend("rbftx")),
+        ]),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let runner = MempoolRunner::new(
+        rpc_client(rpc_url),
+        pool.clone(),
+        bitcoin_blockchain_indexer::modules::mempool::MempoolRunnerConfig {
+            poll_interval: Duration::from_secs(1),
+        },
+    )
+    .with_outbox(true);
+    runner.sync_once().await.expect("first sync");
+    runner.sync_once().await.expect("replacement sync");
+
+    // The replacement is still in the mempool when a block confirms a third spend.
+    let block = RpcBlock {
+        hash: "blockhash11".to_string(),
+        height: 11,
+        prev_hash: Some("blockhash10".to_string()),
+        time: 1_700_001_600,
+        tx: vec![spend("blocktx")],
+    };
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .with_outbox(true)
+        .persist_block_from(&block, 11)
+        .await
+        .expect("persist conflicting block");
+
+    let rows = sqlx::query(
+        "SELECT prev_txid, prev_vout, txid, txid_status, conflicting_txid, conflicting_status, block_height
+         FROM double_spends
+         ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load double spends");
+    let pairs: Vec<(String, String, String, String, Option<i32>)> = rows
+        .iter()
+        .map(|row| {
+            assert_eq!(row.get::<String, _>("prev_txid"), "confirmed-prev");
+            assert_eq!(row.get::<i32, _>("prev_vout"), 0);
+            (
+                row.get("txid"),
+                row.get("txid_status"),
+                row.get("conflicting_txid"),
+                row.get("conflicting_status"),
+                row.get("block_height"),
+            )
+        })
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            (
+                "rbftx".to_string(),
+                "mempool".to_string(),
+                "mempooltx".to_string(),
+                "mempool".to_string(),
+                None
+            ),
+            (
+                "blocktx".to_string(),
+                "confirmed".to_string(),
+                "rbftx".to_string(),
+                "mempool".to_string(),
+                Some(11)
+            ),
+        ]
+    );
+
+    let events: Vec<(String, serde_json::Value)> = sqlx::query_as(
+        "SELECT event_key, payload
+         FROM event_outbox
+         WHERE stream = 'double_spends'
+         ORDER BY id",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load double spend events");
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].0, "confirmed-prev:0");
+    assert_eq!(events[0].1["type"], "double_spend");
+    assert_eq!(events[0].1["txid"], "rbftx");
+    assert_eq!(events[1].1["conflicting_txid"], "rbftx");
+}
+
+#[tokio::test]
+#[ignore]
+async fn mempool_runner_links_fee_bumps_into_a_replacement_chain() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    sqlx::query(
+        "INSERT INTO transactions (txid, block_height, block_hash, position_in_block, time, status, decoded)
+         VALUES ('confirmed-prev', 10, 'blockhash10', 0, 1700001000, 'confirmed', '{}'::jsonb)",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed prev transaction");
+    sqlx::query(
+        "INSERT INTO tx_outputs (txid, vout, value_sats, script_type, address, script_hex)
+         VALUES ('confirmed-prev', 0, 10000, 'pubkeyhash', 'addr1', '0014prev')",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed prev output");
+
+    let payment = |txid: &str, value_sats: i64| {
+        let mut tx = mempool_transaction();
+        tx.txid = txid.to_string();
+        tx.vout[0].value_sats = value_sats;
+        (txid.to_string(), tx)
+    };
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 10,
+        block_hashes: HashMap::new(),
+        mempool_sequences: VecDeque::from(vec![
+            vec!["pay".to_string()],
+            vec!["bump1".to_string()],
+            vec!["bump2".to_string()],
+            vec!["bump2".to_string(), "cheaper".to_string()],
+        ]),
+        transactions: HashMap::from([
+            payment("pay", 9_000),
+            payment("bump1", 8_000),
+            payment("bump2", 7_000),
+            payment("cheaper", 7_500),
+        ]),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let runner = MempoolRunner::new(
+        rpc_client(rpc_url),
+        pool.clone(),
+        bitcoin_blockchain_indexer::modules::mempool::MempoolRunnerConfig {
+            poll_interval: Duration::from_secs(1),
+        },
+    );
+    for _ in 0..4 {
+        runner.sync_once().await.expect("sync mempool");
+    }
+
+    let data = DataService::new(pool.clone());
+    for txid in ["pay", "bump1", "bump2"] {
+        let chain = data.list_tx_replacements(txid).await.expect("list replacements");
+        let links: Vec<_> = chain
+            .iter()
+            .map(|item| {
+                (
+                    item.replaced_txid.as_str(),
+                    item.replacement_txid.as_str(),
+                    item.replaced_fee_sats,
+                    item.replacement_fee_sats,
+                    item.replacement_status.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("pay", "bump1", Some(1_000), Some(2_000), Some("dropped")),
+                ("bump1", "bump2", Some(2_000), Some(3_000), Some("mempool")),
+            ],
+            "chain of {txid}"
+        );
+    }
+
+    // A lower-fee conflict is a double spend, not a replacement.
+    assert!(data.list_tx_replacements("cheaper").await.expect("list replacements").is_empty());
+    let cheaper_conflicts: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM double_spends WHERE txid = 'cheaper' AND conflicting_txid = 'bump2'")
+            .fetch_one(&pool)
+            .await
+            .expect("count conflicts");
+    assert_eq!(cheaper_conflicts, 1);
+}
+
 #[tokio::test]
 #[ignore]
 async fn indexer_service_reconcile_chain_marks_orphans_and_rebuilds_balances() {
@@ -365,6 +749,8 @@ async fn indexer_service_reconcile_chain_marks_orphans_and_rebuilds_balances() {
         block_hashes: HashMap::from([(0_u32, "blockhash0".to_string()), (1_u32, "newhash1".to_string())]),
         mempool_sequences: VecDeque::new(),
         transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: None,
     })
     .start()
     .await;
@@ -421,4 +807,1682 @@ async fn indexer_service_reconcile_chain_marks_orphans_and_rebuilds_balances() {
     assert_eq!(history_rows[0].get::<String, _>("address"), "addr1");
     assert_eq!(history_rows[0].get::<i32, _>("block_height"), 0);
     assert_eq!(history_rows[0].get::<i64, _>("balance_sats"), 5_000_000_000);
+
+    // The replacement confirms the same txid; the orphan keeps its own transaction list.
+    let mut replacement = canonical_block_one("newhash1");
+    replacement.tx[0].txid = "spend-oldhash1".to_string();
+    pipeline
+        .persist_block(&replacement)
+        .await
+        .expect("persist new block 1");
+
+    let forks = DataService::new(pool.clone())
+        .list_block_forks(1)
+        .await
+        .expect("list block forks");
+    assert_eq!(forks.len(), 2);
+    assert_eq!(forks[0].hash, "newhash1");
+    assert_eq!(forks[0].status, "canonical");
+    assert!(forks[0].orphaned_at.is_none());
+    assert_eq!(forks[0].txids, vec!["spend-oldhash1"]);
+    assert_eq!(forks[1].hash, "oldhash1");
+    assert_eq!(forks[1].status, "orphaned");
+    assert!(forks[1].orphaned_at.is_some());
+    assert_eq!(forks[1].txids, vec!["spend-oldhash1"]);
+}
+
+#[tokio::test]
+#[ignore]
+async fn block_forks_keep_orphan_metadata_across_repeated_reorgs() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline
+        .persist_block(&canonical_block_zero())
+        .await
+        .expect("persist block 0");
+    pipeline
+        .persist_block(&canonical_block_one("oldhash1"))
+        .await
+        .expect("persist old block 1");
+
+    for (node_hash, replacement_txid) in [("midhash1", "spend-oldhash1"), ("newhash1", "spend-newhash1")] {
+        let rpc_url = MockRpcServer::new(MockRpcState {
+            block_count: 1,
+            block_hashes: HashMap::from([(0_u32, "blockhash0".to_string()), (1_u32, node_hash.to_string())]),
+            mempool_sequences: VecDeque::new(),
+            transactions: HashMap::new(),
+            blocks: HashMap::new(),
+            block_template: None,
+        })
+        .start()
+        .await;
+
+        let divergence = IndexerService::new(rpc_client(rpc_url), pool.clone(), MetricsService::new())
+            .reconcile_chain(5)
+            .await
+            .expect("reconcile chain");
+        assert_eq!(divergence, Some(1));
+
+        let mut replacement = canonical_block_one(node_hash);
+        replacement.tx[0].txid = replacement_txid.to_string();
+        pipeline
+            .persist_block(&replacement)
+            .await
+            .expect("persist replacement block 1");
+    }
+
+    // The orphan keeps the txids it confirmed after `midhash1` re-confirmed the transaction.
+    let meta = sqlx::query(
+        "SELECT meta->'txids' AS txids, meta ? 'orphaned_at' AS has_orphaned_at
+         FROM blocks
+         WHERE hash = 'oldhash1'",
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("load orphan meta");
+    assert_eq!(meta.get::<serde_json::Value, _>("txids"), serde_json::json!(["spend-oldhash1"]));
+    assert!(meta.get::<bool, _>("has_orphaned_at"));
+    let confirmed_in: String = sqlx::query_scalar("SELECT block_hash FROM transactions WHERE txid = 'spend-oldhash1'")
+        .fetch_one(&pool)
+        .await
+        .expect("load tx block");
+    assert_eq!(confirmed_in, "midhash1");
+
+    let data = DataService::new(pool.clone());
+    let forks = data.list_block_forks(1).await.expect("list block forks");
+    let summary: Vec<(&str, &str, Vec<String>)> = forks
+        .iter()
+        .map(|fork| (fork.hash.as_str(), fork.status.as_str(), fork.txids.clone()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("newhash1", "canonical", vec!["spend-newhash1".to_string()]),
+            ("midhash1", "orphaned", vec!["spend-oldhash1".to_string()]),
+            ("oldhash1", "orphaned", vec!["spend-oldhash1".to_string()]),
+        ]
+    );
+    assert!(forks[0].orphaned_at.is_none());
+    let (Some(newer), Some(older)) = (forks[1].orphaned_at, forks[2].orphaned_at) else {
+        panic!("orphans MUST carry orphaned_at");
+    };
+    assert!(newer > older);
+
+    assert!(data.list_block_forks(2).await.expect("list empty height").is_empty());
+    assert!(matches!(data.list_block_forks(-1).await, Err(DataError::Validation(_))));
+}
+
+#[tokio::test]
+#[ignore]
+async fn indexer_service_reindex_block_replaces_indexed_block() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let pipeline = IndexerPipeline::new(&pool, MetricsService::new());
+    pipeline
+        .persist_block(&canonical_block_zero())
+        .await
+        .expect("persist block 0");
+    pipeline
+        .persist_block(&canonical_block_one("blockhash1"))
+        .await
+        .expect("persist block 1");
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 1,
+        block_hashes: HashMap::from([(0_u32, "blockhash0".to_string()), (1_u32, "blockhash1".to_string())]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash1".to_string(), canonical_block_one("blockhash1")),
+            ("stalehash1".to_string(), canonical_block_one("stalehash1")),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let indexer = IndexerService::new(rpc_client(rpc_url), pool.clone(), MetricsService::new());
+    let height = indexer.reindex_block("blockhash1").await.expect("reindex block");
+    assert_eq!(height, 1);
+
+    let block = sqlx::query("SELECT status FROM blocks WHERE hash = 'blockhash1'")
+        .fetch_one(&pool)
+        .await
+        .expect("load block");
+    assert_eq!(block.get::<String, _>("status"), "canonical");
+
+    let balances = sqlx::query(
+        "SELECT address, balance_sats
+         FROM address_balance_current
+         ORDER BY address",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load balances");
+    assert_eq!(balances.len(), 2);
+    assert_eq!(balances[0].get::<i64, _>("balance_sats"), 2_000_000_000);
+    assert_eq!(balances[1].get::<i64, _>("balance_sats"), 3_000_000_000);
+
+    let err = indexer
+        .reindex_block("stalehash1")
+        .await
+        .expect_err("stale block must be rejected");
+    assert!(err.to_string().contains("not in the node's active chain"));
+}
+
+#[tokio::test]
+#[ignore]
+async fn height_range_job_indexes_window_and_completes() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let block_one = canonical_block_one("blockhash1");
+    let mut block_two = canonical_block_one("blockhash2");
+    block_two.height = 2;
+    block_two.prev_hash = Some("blockhash1".to_string());
+    block_two.tx[0].txid = "spend-blockhash2".to_string();
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), block_one),
+            ("blockhash2".to_string(), block_two),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "window".to_string(),
+        mode: "height_range".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: Some(1),
+        to_height: Some(1),
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: None,
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+    jobs.start("window", None).await.expect("start job");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    JobsRunner::new(
+        jobs.clone(),
+        rpc.clone(),
+        IndexerService::new(rpc, pool.clone(), metrics.clone()),
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 10,
+            txs_per_batch: 5000,
+            reorg_depth: 0,
+            gap_scan_interval: None,
+        },
+    )
+    .start();
+
+    let mut status = String::new();
+    for _ in 0..50 {
+        status = jobs.get("window").await.expect("get job").status;
+        if status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status, "completed");
+
+    let heights: Vec<i32> = sqlx::query_scalar(
+        "SELECT height
+         FROM blocks
+         WHERE status = 'canonical'
+         ORDER BY height",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load canonical heights");
+    assert_eq!(heights, vec![1]);
+
+    let job = jobs.get("window").await.expect("get job");
+    assert_eq!(job.progress_height, 1);
+    assert_eq!(job.tip_height, Some(2));
+    assert_eq!(job.remaining_blocks, Some(0));
+    assert_eq!(job.eta_seconds, Some(0));
+}
+
+#[tokio::test]
+#[ignore]
+async fn supply_is_reconciled_after_a_range_is_indexed_below_indexed_blocks() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    const DAY: i64 = 86_400;
+    let mut block_one = canonical_block_one("blockhash1");
+    block_one.time = 1_700_000_000 + 2 * DAY;
+    let mut block_two = canonical_block_zero();
+    block_two.hash = "blockhash2".to_string();
+    block_two.height = 2;
+    block_two.prev_hash = Some("blockhash1".to_string());
+    block_two.time = 1_700_000_000 + 10 * DAY;
+    block_two.tx[0].txid = "coinbase2".to_string();
+    block_two.tx[0].vout[0].value_sats = 1_000_000_000;
+    let mut spend_two = block_one.tx[0].clone();
+    spend_two.txid = "spend-blockhash2".to_string();
+    spend_two.vin[0].txid = Some("spend-blockhash1".to_string());
+    spend_two.vin[0].vout = Some(1);
+    spend_two.vout.truncate(1);
+    spend_two.vout[0].value_sats = 2_500_000_000;
+    block_two.tx.push(spend_two);
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), block_one),
+            ("blockhash2".to_string(), block_two),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let range_job = |job_id: &str, from_height: i32, to_height: i32| JobConfig {
+        job_id: job_id.to_string(),
+        mode: "height_range".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: Some(from_height),
+        to_height: Some(to_height),
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: None,
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    };
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[range_job("upper", 1, 2), range_job("lower", 0, 0)])
+        .await
+        .expect("sync jobs");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    JobsRunner::new(
+        jobs.clone(),
+        rpc.clone(),
+        IndexerService::new(rpc, pool.clone(), metrics.clone()),
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 10,
+            txs_per_batch: 5000,
+            reorg_depth: 0,
+            gap_scan_interval: None,
+        },
+    )
+    .start();
+    let complete = |job_id: &'static str| {
+        let jobs = jobs.clone();
+        async move {
+            jobs.start(job_id, None).await.expect("start job");
+            for _ in 0..50 {
+                if jobs.get(job_id).await.expect("get job").status == "completed" {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            panic!("job {job_id} did not complete");
+        }
+    };
+    let stats = StatsService::new(pool.clone());
+
+    complete("upper").await;
+    let supply = stats.supply(None).await.expect("supply of the upper range");
+    let rows: Vec<_> = supply.blocks.iter().map(|block| (block.height, block.unknown_inputs)).collect();
+    assert_eq!(rows, vec![(2, 0), (1, 1)]);
+
+    complete("lower").await;
+    let supply = stats.supply(None).await.expect("supply after the lower range");
+    assert_eq!(supply.height, Some(2));
+    assert_eq!((supply.supply_sats, supply.utxo_count), (5_500_000_000, 3));
+    let rows: Vec<_> = supply
+        .blocks
+        .iter()
+        .map(|block| {
+            (
+                block.height,
+                block.created_sats,
+                block.spent_sats,
+                block.unknown_inputs,
+                block.coin_days_destroyed,
+                block.supply_sats,
+            )
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (2, 3_500_000_000, 3_000_000_000, 0, 240.0, 5_500_000_000),
+            (1, 5_000_000_000, 5_000_000_000, 0, 100.0, 5_000_000_000),
+            (0, 5_000_000_000, 0, 0, 0.0, 5_000_000_000),
+        ]
+    );
+    let buckets: Vec<_> = supply
+        .age_buckets
+        .iter()
+        .filter(|bucket| bucket.outputs > 0)
+        .map(|bucket| (bucket.age.as_str(), bucket.value_sats, bucket.outputs))
+        .collect();
+    assert_eq!(buckets, vec![("<1d", 3_500_000_000, 2), ("1w-1m", 2_000_000_000, 1)]);
+}
+
+#[tokio::test]
+#[ignore]
+async fn healed_gap_settles_balances_of_the_blocks_above() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let mut block_two = canonical_block_zero();
+    block_two.hash = "blockhash2".to_string();
+    block_two.height = 2;
+    block_two.prev_hash = Some("blockhash1".to_string());
+    block_two.time = 1_700_000_120;
+    block_two.tx[0].txid = "coinbase2".to_string();
+    block_two.tx[0].vout[0].value_sats = 1_000_000_000;
+    let mut spend_two = canonical_block_one("blockhash1").tx[0].clone();
+    spend_two.txid = "spend-blockhash2".to_string();
+    spend_two.vin[0].txid = Some("spend-blockhash1".to_string());
+    spend_two.vin[0].vout = Some(1);
+    spend_two.vout.truncate(1);
+    spend_two.vout[0].value_sats = 2_500_000_000;
+    block_two.tx.push(spend_two);
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), canonical_block_one("blockhash1")),
+            ("blockhash2".to_string(), block_two),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "full".to_string(),
+        mode: "all_addresses".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: None,
+        to_height: None,
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: None,
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    let indexer = IndexerService::new(rpc.clone(), pool.clone(), metrics.clone());
+    indexer.index_height_from(0, 0).await.expect("index height 0");
+    indexer.index_height_from(2, 2).await.expect("index height 2");
+    jobs.update_progress("full", 2).await.expect("update progress");
+
+    JobsRunner::new(
+        jobs.clone(),
+        rpc,
+        indexer,
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 10,
+            txs_per_batch: 5000,
+            reorg_depth: 0,
+            gap_scan_interval: Some(Duration::from_millis(50)),
+        },
+    )
+    .start();
+    jobs.start("full", None).await.expect("start job");
+    let mut healed = false;
+    for _ in 0..50 {
+        let height_one: Option<i32> =
+            sqlx::query_scalar("SELECT height FROM blocks WHERE height = 1 AND status = 'canonical'")
+                .fetch_optional(&pool)
+                .await
+                .expect("load height 1");
+        if height_one.is_some() {
+            healed = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(healed, "height 1 was not healed");
+
+    let spent_in: Option<String> = sqlx::query_scalar(
+        "SELECT spent_in_txid FROM utxos_current WHERE out_txid = 'spend-blockhash1' AND out_vout = 1",
+    )
+    .fetch_one(&pool)
+    .await
+    .expect("load utxo of block 1");
+    assert_eq!(spent_in.as_deref(), Some("spend-blockhash2"));
+    let balances: Vec<(String, i64)> =
+        sqlx::query_as("SELECT address, balance_sats FROM address_balance_current ORDER BY address")
+            .fetch_all(&pool)
+            .await
+            .expect("load balances");
+    assert_eq!(balances, vec![("addr1".to_string(), 5_500_000_000), ("addr2".to_string(), 0)]);
+    let history: Vec<(String, i32, i64)> = sqlx::query_as(
+        "SELECT address, block_height, balance_sats FROM address_balance_history ORDER BY address, block_height",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load balance history");
+    assert_eq!(
+        history,
+        vec![
+            ("addr1".to_string(), 0, 5_000_000_000),
+            ("addr1".to_string(), 1, 2_000_000_000),
+            ("addr1".to_string(), 2, 5_500_000_000),
+            ("addr2".to_string(), 1, 3_000_000_000),
+            ("addr2".to_string(), 2, 0),
+        ]
+    );
+}
+
+/// Panics on the first block at `height` it sees.
+struct PanicOnce {
+    height: i32,
+    armed: AtomicBool,
+}
+
+impl PipelineHook for PanicOnce {
+    fn name(&self) -> &str {
+        "panic-once"
+    }
+
+    fn on_block<'a>(&'a self, ctx: BlockHookContext<'a>) -> futures_util::future::BoxFuture<'a, Result<HookVerdict, HookError>> {
+        if ctx.block.height == self.height && self.armed.swap(false, Ordering::SeqCst) {
+            panic!("hook exploded at height {}", self.height);
+        }
+        Box::pin(std::future::ready(Ok(HookVerdict::Persist)))
+    }
+}
+
+#[tokio::test]
+#[ignore]
+async fn panicking_batch_fails_the_job_and_the_scheduler_keeps_running() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 1,
+        block_hashes: HashMap::from([(0_u32, "blockhash0".to_string()), (1_u32, "blockhash1".to_string())]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), canonical_block_one("blockhash1")),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "window".to_string(),
+        mode: "height_range".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: Some(0),
+        to_height: Some(1),
+        descriptor: None,
+        gap_limit: None,
+        retry: Some(JobRetryPolicy {
+            max_attempts: 1,
+            backoff_ms: 1,
+            max_backoff_ms: 1,
+        }),
+        export: None,
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+    jobs.start("window", None).await.expect("start job");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    let hooks = HookRegistry::new().register(PanicOnce {
+        height: 1,
+        armed: AtomicBool::new(true),
+    });
+    JobsRunner::new(
+        jobs.clone(),
+        rpc.clone(),
+        IndexerService::new(rpc, pool.clone(), metrics.clone()).with_hooks(hooks),
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 10,
+            txs_per_batch: 5000,
+            reorg_depth: 0,
+            gap_scan_interval: None,
+        },
+    )
+    .start();
+
+    let mut status = String::new();
+    for _ in 0..100 {
+        status = jobs.get("window").await.expect("get job").status;
+        if status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    // Completed by a later batch after the automatic retry, so the scheduler survived the panic.
+    assert_eq!(status, "completed");
+    assert_eq!(jobs.get("window").await.expect("get job").progress_height, 1);
+
+    let errors = jobs.errors("window", None).await.expect("job errors");
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].category, "panic");
+    assert_eq!(errors[0].block_height, None);
+    assert_eq!(errors[0].message, "batch panicked: hook exploded at height 1");
+    let heights: Vec<i32> = sqlx::query_scalar("SELECT height FROM blocks WHERE status = 'canonical' ORDER BY height")
+        .fetch_all(&pool)
+        .await
+        .expect("load canonical heights");
+    assert_eq!(heights, vec![0, 1]);
+}
+
+#[tokio::test]
+#[ignore]
+async fn throttled_job_is_paced_by_its_block_limit() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let mut block_two = canonical_block_one("blockhash2");
+    block_two.height = 2;
+    block_two.prev_hash = Some("blockhash1".to_string());
+    block_two.tx[0].txid = "spend-blockhash2".to_string();
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), canonical_block_one("blockhash1")),
+            ("blockhash2".to_string(), block_two),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "throttled".to_string(),
+        mode: "height_range".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: Some(0),
+        to_height: Some(2),
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: None,
+        priority: None,
+        max_rpc_per_second: Some(20),
+        max_blocks_per_minute: Some(120),
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+    jobs.start("throttled", None).await.expect("start job");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    let started = std::time::Instant::now();
+    JobsRunner::new(
+        jobs.clone(),
+        rpc.clone(),
+        IndexerService::new(rpc, pool.clone(), metrics.clone()),
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 10,
+            txs_per_batch: 5000,
+            reorg_depth: 0,
+            gap_scan_interval: None,
+        },
+    )
+    .start();
+
+    let mut status = String::new();
+    for _ in 0..50 {
+        status = jobs.get("throttled").await.expect("get job").status;
+        if status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status, "completed");
+    // Two blocks per second: the second and third block wait half a second each.
+    assert!(started.elapsed() >= Duration::from_millis(1_000));
+    assert_eq!(jobs.get("throttled").await.expect("get job").progress_height, 2);
+}
+
+#[tokio::test]
+#[ignore]
+async fn job_dry_run_fetches_next_blocks_without_writing() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    // Height 2 has a hash but no block, so fetching it fails.
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), canonical_block_one("blockhash1")),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let rpc = rpc_client(rpc_url);
+    let jobs = JobsService::new(pool.clone())
+        .with_indexer(IndexerService::new(rpc, pool.clone(), MetricsService::new()));
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "watch".to_string(),
+        mode: "address_list".to_string(),
+        enabled: false,
+        addresses: vec!["addr2".to_string()],
+        from_height: None,
+        to_height: None,
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: None,
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+
+    assert!(jobs.dry_run("watch", Some(0), Some(2)).await.is_err());
+    let dry_run = jobs.dry_run("watch", Some(5), Some(2)).await.expect("dry run");
+    assert_eq!(dry_run.watched_addresses, Some(1));
+    assert_eq!(dry_run.errors, 1);
+    let heights: Vec<i32> = dry_run.blocks.iter().map(|block| block.height).collect();
+    assert_eq!(heights, vec![0, 1, 2]);
+
+    let genesis = &dry_run.blocks[0];
+    assert_eq!(genesis.hash.as_deref(), Some("blockhash0"));
+    assert_eq!((genesis.txs, genesis.inputs, genesis.outputs), (1, 0, 1));
+    assert_eq!(genesis.matched_outputs, Some(0));
+    let spend = &dry_run.blocks[1];
+    assert_eq!((spend.txs, spend.inputs, spend.outputs), (1, 1, 2));
+    assert_eq!(spend.matched_outputs, Some(1));
+    assert!(dry_run.blocks[2].hash.is_none());
+    assert!(dry_run.blocks[2].error.is_some());
+
+    let first = jobs.dry_run("watch", Some(1), Some(2)).await.expect("dry run one block");
+    assert_eq!(first.blocks.len(), 1);
+
+    let stored_blocks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM blocks")
+        .fetch_one(&pool)
+        .await
+        .expect("count blocks");
+    assert_eq!(stored_blocks, 0);
+    let job = jobs.get("watch").await.expect("get job");
+    assert_eq!(job.status, "created");
+    assert_eq!(job.progress_height, 0);
+}
+
+#[tokio::test]
+#[ignore]
+async fn job_resumes_from_checkpoint_and_rewinds_reorg_during_downtime() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let chain_block = |height: i32, hash: &str, prev_hash: &str| {
+        let mut block = canonical_block_one(hash);
+        block.height = height;
+        block.prev_hash = Some(prev_hash.to_string());
+        block
+    };
+    let rpc_server = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash1".to_string(), chain_block(1, "blockhash1", "blockhash0")),
+            ("blockhash2".to_string(), chain_block(2, "blockhash2", "blockhash1")),
+            ("fork1".to_string(), chain_block(1, "fork1", "blockhash0")),
+            ("fork2".to_string(), chain_block(2, "fork2", "fork1")),
+            ("fork3".to_string(), chain_block(3, "fork3", "fork2")),
+        ]),
+        block_template: None,
+    });
+    let rpc_url = rpc_server.clone().start().await;
+
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "resume".to_string(),
+        mode: "height_range".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: Some(1),
+        to_height: Some(3),
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: None,
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+    jobs.start("resume", None).await.expect("start job");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    let indexer = IndexerService::new(rpc.clone(), pool.clone(), metrics.clone());
+    for height in 1..=2 {
+        indexer
+            .index_height_for_job(height, 1, Some("resume"))
+            .await
+            .expect("index height before crash");
+    }
+    assert_eq!(
+        jobs.checkpoint("resume").await.expect("checkpoint"),
+        Some((2, "blockhash2".to_string()))
+    );
+    assert_eq!(jobs.get("resume").await.expect("get job").progress_height, 2);
+
+    // Reorg of both indexed heights while the indexer was down, deeper than reorg_depth.
+    {
+        let mut state = rpc_server.state.lock().expect("mock rpc mutex poisoned");
+        state.block_count = 3;
+        state.block_hashes = HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "fork1".to_string()),
+            (2_u32, "fork2".to_string()),
+            (3_u32, "fork3".to_string()),
+        ]);
+    }
+
+    JobsRunner::new(
+        jobs.clone(),
+        rpc,
+        indexer,
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 10,
+            txs_per_batch: 5000,
+            reorg_depth: 1,
+            gap_scan_interval: None,
+        },
+    )
+    .start();
+
+    let mut status = String::new();
+    for _ in 0..50 {
+        status = jobs.get("resume").await.expect("get job").status;
+        if status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status, "completed");
+
+    let hashes: Vec<String> = sqlx::query_scalar(
+        "SELECT hash
+         FROM blocks
+         WHERE status = 'canonical'
+         ORDER BY height",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load canonical hashes");
+    assert_eq!(hashes, vec!["fork1", "fork2", "fork3"]);
+    assert_eq!(
+        jobs.checkpoint("resume").await.expect("checkpoint"),
+        Some((3, "fork3".to_string()))
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn header_sync_runs_ahead_of_bodies_and_follows_reorgs() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let chain_block = |height: i32, hash: &str, prev_hash: Option<&str>| {
+        let mut block = canonical_block_one(hash);
+        block.height = height;
+        block.prev_hash = prev_hash.map(str::to_string);
+        block
+    };
+    let rpc_server = MockRpcServer::new(MockRpcState {
+        block_count: 3,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+            (3_u32, "blockhash3".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), chain_block(1, "blockhash1", Some("blockhash0"))),
+            ("blockhash2".to_string(), chain_block(2, "blockhash2", Some("blockhash1"))),
+            ("blockhash3".to_string(), chain_block(3, "blockhash3", Some("blockhash2"))),
+            ("fork3".to_string(), chain_block(3, "fork3", Some("blockhash2"))),
+            ("fork4".to_string(), chain_block(4, "fork4", Some("fork3"))),
+        ]),
+        block_template: None,
+    });
+    let rpc_url = rpc_server.clone().start().await;
+    let rpc = rpc_client(rpc_url);
+    let runner = HeaderSyncRunner::new(
+        rpc.clone(),
+        pool.clone(),
+        MetricsService::new(),
+        HeaderSyncRunnerConfig {
+            poll_interval: Duration::from_millis(50),
+            batch_size: 2,
+        },
+    );
+
+    let report = runner.sync_once().await.expect("first header batch");
+    assert_eq!(report.synced, 2);
+    assert_eq!(report.header_height, Some(1));
+    let report = runner.sync_once().await.expect("second header batch");
+    assert_eq!(report.header_height, Some(3));
+    assert_eq!(report.reorg_height, None);
+
+    IndexerPipeline::new(&pool, MetricsService::new())
+        .persist_block(&canonical_block_zero())
+        .await
+        .expect("persist block body");
+    let status = StatusService::new(pool.clone())
+        .with_rpc(rpc_client("http://127.0.0.1:9".to_string()))
+        .report()
+        .await;
+    assert_eq!(status.header_height, Some(3));
+    assert_eq!(status.indexed_height, Some(0));
+    assert_eq!(status.sync_lag_blocks, Some(3));
+
+    {
+        let mut state = rpc_server.state.lock().expect("mock rpc mutex poisoned");
+        state.block_count = 4;
+        state.block_hashes.insert(3, "fork3".to_string());
+        state.block_hashes.insert(4, "fork4".to_string());
+    }
+    let report = runner.sync_once().await.expect("header reorg");
+    assert_eq!(report.reorg_height, Some(3));
+    assert_eq!(report.header_height, Some(4));
+
+    let headers: Vec<(i32, String, Option<String>)> =
+        sqlx::query_as("SELECT height, hash, prev_hash FROM block_headers ORDER BY height")
+            .fetch_all(&pool)
+            .await
+            .expect("load headers");
+    let hashes: Vec<&str> = headers.iter().map(|(_, hash, _)| hash.as_str()).collect();
+    assert_eq!(hashes, vec!["blockhash0", "blockhash1", "blockhash2", "fork3", "fork4"]);
+    assert_eq!(headers[4].2.as_deref(), Some("fork3"));
+    assert_eq!(header_tip(&pool).await.expect("header tip"), Some((4, "fork4".to_string())));
+}
+
+#[tokio::test]
+#[ignore]
+async fn export_job_writes_csv_chunks_and_completes() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let block_one = canonical_block_one("blockhash1");
+    let mut block_two = canonical_block_one("blockhash2");
+    block_two.height = 2;
+    block_two.prev_hash = Some("blockhash1".to_string());
+    block_two.tx[0].txid = "spend-blockhash2".to_string();
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 2,
+        block_hashes: HashMap::from([
+            (0_u32, "blockhash0".to_string()),
+            (1_u32, "blockhash1".to_string()),
+            (2_u32, "blockhash2".to_string()),
+        ]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::from([
+            ("blockhash0".to_string(), canonical_block_zero()),
+            ("blockhash1".to_string(), block_one),
+            ("blockhash2".to_string(), block_two),
+        ]),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let dir = tempfile::tempdir().expect("tempdir");
+    let jobs = JobsService::new(pool.clone());
+    jobs.sync_from_config(&[JobConfig {
+        job_id: "dump".to_string(),
+        mode: "export".to_string(),
+        enabled: true,
+        addresses: vec![],
+        from_height: Some(0),
+        to_height: Some(2),
+        descriptor: None,
+        gap_limit: None,
+        retry: None,
+        export: Some(JobExportConfig {
+            format: "csv".to_string(),
+            destination: dir.path().display().to_string(),
+            tables: vec!["blocks".to_string(), "transactions".to_string()],
+        }),
+        priority: None,
+        max_rpc_per_second: None,
+        max_blocks_per_minute: None,
+        protocols: Vec::new(),
+    }])
+    .await
+    .expect("sync jobs");
+    jobs.start("dump", None).await.expect("start job");
+
+    let rpc = rpc_client(rpc_url);
+    let metrics = MetricsService::new();
+    JobsRunner::new(
+        jobs.clone(),
+        rpc.clone(),
+        IndexerService::new(rpc, pool.clone(), metrics.clone()),
+        metrics,
+        JobsRunnerConfig {
+            max_jobs: 1,
+            poll_interval: Duration::from_millis(50),
+            blocks_per_batch: 2,
+            txs_per_batch: 5000,
+            reorg_depth: 0,
+            gap_scan_interval: None,
+        },
+    )
+    .start();
+
+    let mut status = String::new();
+    for _ in 0..50 {
+        status = jobs.get("dump").await.expect("get job").status;
+        if status == "completed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status, "completed");
+    assert_eq!(jobs.get("dump").await.expect("get job").progress_height, 2);
+
+    let blocks = std::fs::read_to_string(dir.path().join("dump/blocks/0000000000-0000000001.csv"))
+        .expect("first blocks chunk");
+    let lines: Vec<&str> = blocks.lines().collect();
+    assert_eq!(lines[0], "height,hash,prev_hash,time,tx_count,total_fees_sats");
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with("0,blockhash0,"));
+    assert!(dir.path().join("dump/transactions/0000000002-0000000002.csv").exists());
+
+    let chunks: Vec<(String, i32, i32, i64)> = sqlx::query_as(
+        "SELECT table_name, from_height, to_height, row_count
+         FROM job_exports
+         WHERE job_id = 'dump'
+         ORDER BY from_height, table_name",
+    )
+    .fetch_all(&pool)
+    .await
+    .expect("load exported chunks");
+    assert_eq!(
+        chunks,
+        vec![
+            ("blocks".to_string(), 0, 1, 2),
+            ("transactions".to_string(), 0, 1, 2),
+            ("blocks".to_string(), 2, 2, 1),
+            ("transactions".to_string(), 2, 2, 1),
+        ]
+    );
+}
+
+#[tokio::test]
+#[ignore]
+async fn block_template_runner_stores_projected_fees_and_tx_set() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 100,
+        block_hashes: HashMap::new(),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: Some(serde_json::json!({
+            "version": 536870912,
+            "height": 101,
+            "previousblockhash": "blockhash100",
+            "bits": "207fffff",
+            "curtime": 1700000600,
+            "coinbasevalue": 5000001500_i64,
+            "transactions": [
+                { "data": "0200", "txid": "tpl-a", "hash": "wtpl-a", "depends": [], "fee": 1000, "sigops": 4, "weight": 560 },
+                { "data": "0200", "txid": "tpl-b", "hash": "wtpl-b", "depends": [1], "fee": 500, "sigops": 4, "weight": 440 }
+            ]
+        })),
+    })
+    .start()
+    .await;
+
+    let runner = BlockTemplateRunner::new(
+        rpc_client(rpc_url),
+        pool.clone(),
+        BlockTemplateRunnerConfig {
+            poll_interval: Duration::from_secs(1),
+        },
+    );
+
+    let id = runner.capture_once().await.expect("capture template").expect("snapshot id");
+
+    let row = sqlx::query(
+        "SELECT height, previous_block_hash, total_fees_sats, tx_count, total_weight, transactions
+         FROM block_templates
+         WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_one(&pool)
+    .await
+    .expect("load template snapshot");
+    assert_eq!(row.get::<i32, _>("height"), 101);
+    assert_eq!(row.get::<String, _>("previous_block_hash"), "blockhash100");
+    assert_eq!(row.get::<i64, _>("total_fees_sats"), 1_500);
+    assert_eq!(row.get::<i32, _>("tx_count"), 2);
+    assert_eq!(row.get::<i64, _>("total_weight"), 1_000);
+    let transactions = row.get::<serde_json::Value, _>("transactions");
+    assert_eq!(transactions[0]["txid"], "tpl-a");
+    assert_eq!(transactions[0]["fee_sats"], 1000);
+}
+
+#[tokio::test]
+#[ignore]
+async fn migrations_are_tracked_and_verified_on_restart() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+    let files = std::fs::read_dir("migrations")
+        .expect("migrations dir")
+        .filter(|entry| {
+            entry
+                .as_ref()
+                .is_ok_and(|entry| entry.path().extension().is_some_and(|ext| ext == "sql"))
+        })
+        .count();
+    let applied = |pool: PgPool| async move {
+        sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM _sqlx_migrations WHERE success")
+            .fetch_one(&pool)
+            .await
+            .expect("count migrations")
+    };
+    assert_eq!(applied(pool.clone()).await, files as i64);
+
+    let storage = Storage::connect(&DatabaseConfig::default()).await.expect("connect storage");
+    storage.apply_migrations().await.expect("restart is a no-op");
+    assert_eq!(applied(pool.clone()).await, files as i64);
+
+    // A database migrated before version tracking: idempotent files are applied again and recorded.
+    sqlx::query("DELETE FROM _sqlx_migrations")
+        .execute(&pool)
+        .await
+        .expect("forget versions");
+    storage.apply_migrations().await.expect("adopt untracked schema");
+    assert_eq!(applied(pool.clone()).await, files as i64);
+
+    sqlx::query("UPDATE _sqlx_migrations SET checksum = '\\x00'::BYTEA WHERE version = 1")
+        .execute(&pool)
+        .await
+        .expect("tamper checksum");
+    let err = storage.apply_migrations().await.expect_err("modified migration");
+    assert!(err.to_string().contains("migration 1 was previously applied but has been modified"));
+}
+
+#[tokio::test]
+#[ignore]
+async fn status_service_reports_tip_lag_and_dependency_reachability() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    sqlx::query(
+        "INSERT INTO blocks (height, hash, prev_hash, time, status, meta)
+         VALUES
+           (7, 'blockhash7', 'blockhash6', 1700000000, 'canonical', '{}'::jsonb),
+           (8, 'blockhash8', 'blockhash7', 1700000600, 'orphaned', '{}'::jsonb)",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed blocks");
+    sqlx::query(
+        "INSERT INTO transactions (txid, block_height, block_hash, position_in_block, time, status, decoded)
+         VALUES ('pending', NULL, NULL, 0, 1700000700, 'mempool', '{}'::jsonb)",
+    )
+    .execute(&pool)
+    .await
+    .expect("seed mempool transaction");
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 10,
+        block_hashes: HashMap::from([(10, "blockhash10".to_string())]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let status = StatusService::new(pool.clone()).with_rpc(rpc_client(rpc_url)).report().await;
+    assert_eq!(status.status, "syncing");
+    assert_eq!(status.node_tip_height, Some(10));
+    assert_eq!(status.node_tip_hash.as_deref(), Some("blockhash10"));
+    assert_eq!(status.indexed_height, Some(7));
+    assert_eq!(status.indexed_hash.as_deref(), Some("blockhash7"));
+    assert_eq!(status.sync_lag_blocks, Some(3));
+    assert_eq!(status.mempool_tx_count, Some(1));
+    assert!(status.database_reachable && status.rpc_reachable);
+
+    let status = StatusService::new(pool.clone())
+        .with_rpc(rpc_client("http://127.0.0.1:9".to_string()))
+        .report()
+        .await;
+    assert_eq!(status.status, "degraded");
+    assert!(!status.rpc_reachable);
+    assert!(status.rpc_error.is_some());
+    assert_eq!(status.sync_lag_blocks, None);
+    assert_eq!(status.indexed_height, Some(7));
+}
+
+#[tokio::test]
+#[ignore]
+async fn readiness_requires_database_rpc_and_applied_migrations() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 1,
+        block_hashes: HashMap::from([(1, "blockhash1".to_string())]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+
+    let status = StatusService::new(pool.clone()).with_rpc(rpc_client(rpc_url.clone()));
+    let readiness = status.readiness().await;
+    assert_eq!(readiness.status, "ready");
+    assert!(readiness.migrations_applied && readiness.pending_migrations.is_empty());
+
+    let readiness = StatusService::new(pool.clone())
+        .with_rpc(rpc_client("http://127.0.0.1:9".to_string()))
+        .readiness()
+        .await;
+    assert_eq!(readiness.status, "not_ready");
+    assert!(readiness.database_reachable && !readiness.rpc_reachable);
+    assert!(readiness.rpc_error.is_some());
+
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 10")
+        .execute(&pool)
+        .await
+        .expect("forget latest migration");
+    let readiness = status.readiness().await;
+    assert_eq!(readiness.status, "not_ready");
+    assert!(!readiness.migrations_applied);
+    assert_eq!(readiness.pending_migrations, vec![10]);
+
+    // Compat mode defers migrations on purpose, so they do not block traffic.
+    let readiness = status.with_migrations_check(false).readiness().await;
+    assert_eq!(readiness.status, "ready");
+}
+
+#[tokio::test]
+#[ignore]
+async fn indexer_fetches_blocks_through_rest_interface() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    // The JSON-RPC node knows the chain but serves no blocks, so they can only come from REST.
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 1,
+        block_hashes: HashMap::from([(0_u32, "blockhash0".to_string()), (1_u32, "missing1".to_string())]),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let rest_url = start_mock_rest(
+        HashMap::from([("blockhash0".to_string(), canonical_block_zero())]),
+        requests.clone(),
+    )
+    .await;
+
+    let rpc = rpc_client(rpc_url).with_rest_url(Some(rest_url));
+    let indexer = IndexerService::new(rpc, pool.clone(), MetricsService::new()).with_raw_blocks(true);
+    let result = indexer.index_height(0).await.expect("index block 0");
+    assert_eq!(result.tx_count, 1);
+
+    let err = indexer.index_height(1).await.err().expect("block 1 is unknown");
+    assert!(err.to_string().contains("status=404"), "{err}");
+    assert_eq!(
+        *requests.lock().expect("mock rest mutex poisoned"),
+        vec!["blockhash0.json", "blockhash0.hex", "missing1.json"]
+    );
+
+    let block = sqlx::query("SELECT hash, status FROM blocks WHERE height = 0")
+        .fetch_one(&pool)
+        .await
+        .expect("load block");
+    assert_eq!(block.get::<String, _>("hash"), "blockhash0");
+    assert_eq!(block.get::<String, _>("status"), "canonical");
+    let raw_size: i32 = sqlx::query_scalar("SELECT size FROM block_raw WHERE hash = 'blockhash0'")
+        .fetch_one(&pool)
+        .await
+        .expect("load raw block");
+    assert_eq!(raw_size, 2);
+}
+
+#[tokio::test]
+#[ignore]
+async fn broadcast_relays_records_and_tracks_transactions() {
+    let Some(pool) = setup_db().await else {
+        return;
+    };
+
+    // Spends the genesis coinbase output; the mock node relays only version 2.
+    let accepted_hex = "02000000013ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a0000000000ffffffff\
+                        01e8030000000000000000000000";
+    let rejected_hex = accepted_hex.replacen("02", "01", 1);
+    let tx: bitcoin::Transaction =
+        bitcoin::consensus::deserialize(&hex::decode(accepted_hex).expect("hex")).expect("transaction");
+    let txid = tx.compute_txid().to_string();
+
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 0,
+        block_hashes: HashMap::new(),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::from([(
+            txid.clone(),
+            RpcTransaction {
+                txid: txid.clone(),
+                ..mempool_transaction()
+            },
+        )]),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+    let rpc = rpc_client(rpc_url);
+    let mempool = MempoolRunner::new(
+        rpc.clone(),
+        pool.clone(),
+        bitcoin_blockchain_indexer::modules::mempool::MempoolRunnerConfig {
+            poll_interval: Duration::from_secs(60),
+        },
+    );
+    let broadcast = BroadcastService::new(pool.clone()).with_rpc(rpc).with_mempool(mempool);
+
+    assert!(matches!(
+        broadcast.broadcast("not-hex", None).await,
+        Err(BroadcastError::Validation(_))
+    ));
+    let relayed = broadcast
+        .broadcast(accepted_hex, Some("basic:admin"))
+        .await
+        .expect("relay transaction");
+    assert_eq!(relayed.txid, txid);
+    assert_eq!(relayed.size, accepted_hex.len() / 2);
+    let rejected = broadcast.broadcast(&rejected_hex, None).await;
+    assert!(matches!(rejected, Err(BroadcastError::Rejected { .. })));
+
+    let status: String = sqlx::query_scalar("SELECT status FROM transactions WHERE txid = $1")
+        .bind(&txid)
+        .fetch_one(&pool)
+        .await
+        .expect("tracked transaction");
+    assert_eq!(status, "mempool");
+
+    let attempts: Vec<(String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT status, error, principal FROM broadcasts ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .expect("load broadcasts");
+    assert_eq!(attempts.len(), 2);
+    assert_eq!(attempts[0], ("accepted".to_string(), None, Some("basic:admin".to_string())));
+    assert_eq!(attempts[1].0, "rejected");
+    assert!(attempts[1].1.is_some());
+}
+
+#[tokio::test]
+#[ignore]
+async fn scan_queues_requests_and_returns_unspents_of_descriptors() {
+    let rpc_url = MockRpcServer::new(MockRpcState {
+        block_count: 120,
+        block_hashes: HashMap::new(),
+        mempool_sequences: VecDeque::new(),
+        transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: None,
+    })
+    .start()
+    .await;
+    let scan = ScanService::new().with_rpc(rpc_client(rpc_url));
+
+    let first = vec!["addr(bc1qfirst)".to_string()];
+    let second = vec!["addr(bc1qsecond)".to_string(), "addr(bc1qthird)".to_string()];
+    let (first, second) = tokio::join!(scan.scan(&first, None), scan.scan(&second, Some(10)));
+    let first = first.expect("first scan");
+    let second = second.expect("second scan");
+    assert_eq!(first.height, 120);
+    assert_eq!(first.items.len(), 1);
+    assert_eq!(first.items[0].value_sats, 50_000_000);
+    assert_eq!(first.items[0].descriptor, "addr(bc1qfirst)");
+    assert_eq!(second.items.len(), 2);
+    assert_eq!(second.total_sats, 100_000_000);
+
+    let rejected = scan.scan(&["wpkh(invalid)".to_string()], None).await;
+    assert!(matches!(rejected, Err(ScanError::Rejected(_))));
+    assert!(matches!(scan.scan(&[], None).await, Err(ScanError::Validation(_))));
+    assert!(matches!(
+        ScanService::new().scan(&["addr(bc1qfirst)".to_string()], None).await,
+        Err(ScanError::NodeUnavailable(_))
+    ));
+}
+
+#[tokio::test]
+#[ignore]
+async fn node_info_is_read_from_the_node_and_cached() {
+    let server = MockRpcServer::new(MockRpcState {
+        block_count: 120,
+        block_hashes: HashMap::from([(120, "blockhash120".to_string())]),
+        mempool_sequences: VecDeque::from([vec!["tx-a".to_string(), "tx-b".to_string()]]),
+        transactions: HashMap::new(),
+        blocks: HashMap::new(),
+        block_template: None,
+    });
+    let state = server.state.clone();
+    let node_info = NodeInfoService::new().with_rpc(rpc_client(server.start().await));
+
+    let info = node_info.info().await.expect("node info");
+    assert_eq!(info.blockchain.chain, "regtest");
+    assert_eq!(info.blockchain.blocks, 120);
+    assert_eq!(info.blockchain.best_block_hash, "blockhash120");
+    assert_eq!(info.network.subversion, "/Satoshi:27.0.0/");
+    assert_eq!(info.network.relay_fee_sat_vb, 1.0);
+    assert_eq!(info.mempool.tx_count, 2);
+
+    state.lock().expect("mock rpc mutex poisoned").block_count = 121;
+    let cached = node_info.info().await.expect("cached node info");
+    assert_eq!(cached.blockchain.blocks, 120);
+    assert_eq!(cached.fetched_at, info.fetched_at);
+
+    assert!(NodeInfoService::new().info().await.is_err());
+}
+
+#[tokio::test]
+#[ignore]
+async fn rpc_waits_while_the_node_is_warming_up() {
+    // Answers `-28` to the first three calls, as bitcoind does while loading its block index.
+    let calls = Arc::new(Mutex::new(0u32));
+    let router = Router::new()
+        .route(
+            "/",
+            post(|State(calls): State<Arc<Mutex<u32>>>, Json(body): Json<serde_json::Value>| async move {
+                let mut calls = calls.lock().expect("calls mutex poisoned");
+                *calls += 1;
+                if *calls <= 3 {
+                    let reply = serde_json::json!({
+                        "result": null,
+                        "error": { "code": -28, "message": "Loading block index…" },
+                        "id": body["id"]
+                    });
+                    return (StatusCode::INTERNAL_SERVER_ERROR, Json(reply));
+                }
+                let reply = serde_json::json!({
+                    "result": { "chain": "regtest", "blocks": 12, "verificationprogress": 1.0 },
+                    "error": null,
+                    "id": body["id"]
+                });
+                (StatusCode::OK, Json(reply))
+            }),
+        )
+        .with_state(calls.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind mock rpc");
+    let rpc = rpc_client(format!("http://{}", listener.local_addr().expect("local addr")));
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("serve mock rpc");
+    });
+
+    let err = rpc
+        .wait_until_ready(Duration::ZERO, Duration::from_millis(10))
+        .await
+        .expect_err("no wait without timeout");
+    assert!(matches!(err, RpcError::WarmingUp(_)));
+
+    let info = rpc
+        .wait_until_ready(Duration::from_secs(5), Duration::from_millis(10))
+        .await
+        .expect("node ready");
+    assert_eq!(info.blocks, 12);
+    assert_eq!(*calls.lock().expect("calls mutex poisoned"), 4);
+}
+
+#[tokio::test]
+#[ignore]
+async fn credentials_reload_rotates_rpc_and_api_passwords() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let key = rcgen::KeyPair::generate().expect("key");
+    let certificate = rcgen::CertificateParams::new(vec!["indexer.local".to_string()])
+        .expect("params")
+        .self_signed(&key)
+        .expect("self-signed certificate");
+    std::fs::write(dir.path().join("server.crt"), certificate.pem()).expect("write certificate");
+    std::fs::write(dir.path().join("server.key"), key.serialize_pem()).expect("write key");
+    std::fs::write(dir.path().join("api-password"), "api-old\n").expect("write api password");
+    std::fs::write(dir.path().join("rpc-password"), "rpc-old\n").expect("write rpc password");
+
+    // Accepts only the password the node was last configured with.
+    async fn block_count(
+        State(password): State<Arc<Mutex<String>>>,
+        headers: HeaderMap,
+        Json(body): Json<serde_json::Value>,
+    ) -> impl IntoResponse {
+        let password = password.lock().expect("password mutex poisoned").clone();
+        let expected = format!("Basic {}", STANDARD.encode(format!("rpcuser:{password}")));
+        if headers.get("authorization").and_then(|value| value.to_str().ok()) != Some(expected.as_str()) {
+            return (StatusCode::UNAUTHORIZED, Json(serde_json::Value::Null));
+        }
+        (StatusCode::OK, Json(serde_json::json!({ "result": 12, "error": null, "id": body["id"] })))
+    }
+    let node_password = Arc::new(Mutex::new("rpc-old".to_string()));
+    let router = Router::new()
+        .route("/", post(block_count))
+        .with_state(node_password.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind mock rpc");
+    let rpc_url = format!("http://{}", listener.local_addr().expect("local addr"));
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.expect("serve mock rpc");
+    });
+
+    let yaml = format!(
+        r#"server:
+  bind_host: "127.0.0.1"
+  bind_port: 8443
+  tls:
+    cert_path: "{dir}/server.crt"
+    key_path: "{dir}/server.key"
+  auth:
+    basic:
+      username: "admin"
+      password_file: "{dir}/api-password"
+rpc:
+  node_id: "mock-node"
+  url: "{rpc_url}"
+  auth:
+    basic:
+      username: "rpcuser"
+      password_file: "{dir}/rpc-password"
+  timeouts:
+    connect_ms: 5000
+    request_ms: 5000
+indexer:
+  chain: "bitcoin"
+  network: "regtest"
+  reorg_depth: 6
+  poll:
+    tip_interval_ms: 5000
+    mempool_interval_ms: 3000
+  concurrency:
+    max_jobs: 2
+    rpc_parallelism: 4
+    db_writer_parallelism: 2
+  batching:
+    blocks_per_batch: 50
+    txs_per_batch: 5000
+jobs: []
+"#,
+        dir = dir.path().display(),
+    );
+    let path = dir.path().join("indexer.yaml");
+    std::fs::write(&path, yaml).expect("write config");
+
+    let config = AppConfig::load_from_path(&path).expect("load config");
+    let rpc = RpcClient::from_config(&config.rpc).expect("rpc client");
+    let auth = AuthChain::from_config(&config.server.auth);
+    // Credentials reload does not touch jobs, so the pool is never connected.
+    let pool = PgPool::connect_lazy("postgres://unused@127.0.0.1:1/unused").expect("lazy pool");
+    let reload = ConfigReloader::new(path.clone(), config, JobsService::new(pool))
+        .with_auth(auth.clone())
+        .with_rpc_clients(vec![rpc.clone()]);
+    let api_login = |password: &str| Credentials {
+        authorization: Some(format!("Basic {}", STANDARD.encode(format!("admin:{password}")))),
+        ..Credentials::default()
+    };
+    assert_eq!(rpc.get_block_count().await.expect("block count"), 12);
+
+    std::fs::write(dir.path().join("rpc-password"), "rpc-new\n").expect("rotate rpc password");
+    std::fs::write(dir.path().join("api-password"), "api-new").expect("rotate api password");
+    *node_password.lock().expect("password mutex poisoned") = "rpc-new".to_string();
+    assert!(rpc.get_block_count().await.is_err());
+
+    let report = reload.reload_credentials().await.expect("reload credentials");
+    assert_eq!(report.changed, vec!["server.auth".to_string(), "rpc.auth".to_string()]);
+    assert_eq!(report.rpc_clients_rebuilt, 1);
+    assert_eq!(report.certificates.len(), 1);
+    assert_eq!(rpc.get_block_count().await.expect("block count after rotation"), 12);
+    assert!(auth.authenticate(&api_login("api-new")).is_some());
+    assert!(auth.authenticate(&api_login("api-old")).is_none());
+
+    std::fs::write(dir.path().join("server.crt"), "garbage").expect("break certificate");
+    std::fs::write(dir.path().join("rpc-password"), "rpc-newer").expect("rotate rpc password");
+    let err = reload.reload_credentials().await.expect_err("invalid certificate");
+    assert!(matches!(err, ReloadError::Tls(_)));
+    assert_eq!(rpc.get_block_count().await.expect("previous credentials kept"), 12);
 }